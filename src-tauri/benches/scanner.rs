@@ -0,0 +1,92 @@
+// Scanner benchmarks - scan_directory and the scan cache on synthetic trees
+//
+// Run with `cargo bench --bench scanner`. Criterion keeps the previous run's
+// results under target/criterion and reports regressions against them.
+
+mod support;
+
+use app_lib::scan_cache;
+use app_lib::scanner::{scan_directory, Exclusions, ScanStats, ScanStrategy, SymlinkPolicy};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use support::TreeSpec;
+
+const SHAPES: &[(&str, TreeSpec)] = &[
+    ("wide", TreeSpec { breadth: 40, depth: 1, files_per_dir: 50, file_size: 4096 }),
+    ("deep", TreeSpec { breadth: 2, depth: 8, files_per_dir: 5, file_size: 4096 }),
+    ("balanced", TreeSpec { breadth: 6, depth: 3, files_per_dir: 20, file_size: 1 << 20 }),
+];
+
+fn stats() -> Arc<ScanStats> {
+    Arc::new(ScanStats {
+        scanned_files: AtomicU64::new(0),
+        total_size: AtomicU64::new(0),
+        errors: AtomicU64::new(0),
+        on_child: None,
+    })
+}
+
+fn bench_scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan_directory");
+    group.sample_size(20);
+    for (name, spec) in SHAPES {
+        let tree = support::generate(*spec);
+        let path = tree.path().to_string_lossy().to_string();
+        let exclusions = Exclusions::default();
+        group.throughput(Throughput::Elements(spec.file_count()));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &path, |b, path| {
+            b.iter(|| scan_directory(path, Some(stats()), None, &exclusions, &ScanStrategy::default(), SymlinkPolicy::default(), None, &[]).unwrap());
+        });
+    }
+    group.finish();
+}
+
+fn bench_exclusions(c: &mut Criterion) {
+    let spec = SHAPES[2].1;
+    let tree = support::generate(spec);
+    let path = tree.path().to_string_lossy().to_string();
+    let exclusions = Exclusions::new(vec!["dir_0".to_string(), "*.tmp".to_string(), "node_modules".to_string()]);
+    c.bench_function("scan_directory/with_exclusions", |b| {
+        b.iter(|| scan_directory(&path, None, None, &exclusions, &ScanStrategy::default(), SymlinkPolicy::default(), None, &[]).unwrap());
+    });
+}
+
+/// Several scans of the same tree at once, as when the user opens tabs
+/// quickly; checks the parallel walker doesn't degrade under contention
+fn bench_concurrent_scans(c: &mut Criterion) {
+    let spec = SHAPES[2].1;
+    let tree = support::generate(spec);
+    let path = tree.path().to_string_lossy().to_string();
+    let mut group = c.benchmark_group("stress");
+    group.sample_size(10);
+    for threads in [2, 4, 8] {
+        group.bench_with_input(BenchmarkId::new("concurrent_scans", threads), &threads, |b, &threads| {
+            b.iter(|| {
+                std::thread::scope(|scope| {
+                    for _ in 0..threads {
+                        scope.spawn(|| scan_directory(&path, Some(stats()), None, &Exclusions::default(), &ScanStrategy::default(), SymlinkPolicy::default(), None, &[]).unwrap());
+                    }
+                });
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_cache(c: &mut Criterion) {
+    let tree = support::generate(SHAPES[0].1);
+    let path = tree.path().to_string_lossy().to_string();
+    let node = scan_directory(&path, None, None, &Exclusions::default(), &ScanStrategy::default(), SymlinkPolicy::default(), None, &[]).unwrap();
+
+    let mut group = c.benchmark_group("scan_cache");
+    group.bench_function("store", |b| b.iter(|| scan_cache::store(&path, &node)));
+    scan_cache::store(&path, &node);
+    group.bench_function("hit", |b| b.iter(|| scan_cache::get(&path).unwrap()));
+    group.bench_function("miss", |b| b.iter(|| scan_cache::get("/nonexistent/helium-bench")));
+    group.finish();
+    scan_cache::clear();
+}
+
+criterion_group!(benches, bench_scan, bench_exclusions, bench_concurrent_scans, bench_cache);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+// Synthetic directory trees for scanner benchmarks
+//
+// Trees are generated under a temporary directory that is removed when the
+// returned handle is dropped. Files are created sparse (set_len), so large
+// sizes cost metadata, not disk space.
+
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Shape of a generated tree
+#[derive(Debug, Clone, Copy)]
+pub struct TreeSpec {
+    /// Subdirectories per directory
+    pub breadth: usize,
+    /// Levels of subdirectories below the root
+    pub depth: usize,
+    /// Files in every directory
+    pub files_per_dir: usize,
+    /// Size of each file in bytes
+    pub file_size: u64,
+}
+
+impl TreeSpec {
+    /// Files the tree will contain
+    pub fn file_count(&self) -> u64 {
+        let dirs: usize = (0..=self.depth).map(|level| self.breadth.pow(level as u32)).sum();
+        (dirs * self.files_per_dir) as u64
+    }
+}
+
+pub fn generate(spec: TreeSpec) -> TempDir {
+    let root = tempfile::tempdir().expect("failed to create temp dir");
+    fill(root.path(), spec, spec.depth);
+    root
+}
+
+fn fill(dir: &Path, spec: TreeSpec, remaining_depth: usize) {
+    for i in 0..spec.files_per_dir {
+        let file = fs::File::create(dir.join(format!("file_{}.bin", i))).expect("failed to create file");
+        file.set_len(spec.file_size).expect("failed to size file");
+    }
+    if remaining_depth == 0 {
+        return;
+    }
+    for i in 0..spec.breadth {
+        let sub = dir.join(format!("dir_{}", i));
+        fs::create_dir(&sub).expect("failed to create dir");
+        fill(&sub, spec, remaining_depth - 1);
+    }
+}
@@ -0,0 +1,169 @@
+// Age Heatmap - Bytes by last-modified period per directory
+//
+// Big folders that nobody touched in years are the best cleanup and archive
+// candidates. For a folder and each of its subfolders, the bytes below it are
+// bucketed by the year or quarter files were last modified, so the treemap
+// can shade "cold" data. Buckets are keyed "2021" or "2021-Q3".
+
+use crate::scanner::Exclusions;
+use chrono::{DateTime, Datelike, Utc};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use walkdir::WalkDir;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgeGranularity {
+    #[default]
+    Year,
+    Quarter,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgeBucket {
+    /// "2021" or "2021-Q3"
+    pub period: String,
+    pub size: u64,
+    pub files: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryAge {
+    pub path: String,
+    pub size: u64,
+    /// Oldest period first
+    pub buckets: Vec<AgeBucket>,
+    /// Size-weighted mean last-modified time (Unix seconds), for shading
+    pub mean_modified: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgeHeatmap {
+    pub granularity: AgeGranularity,
+    /// The requested folder, including its own files
+    pub root: DirectoryAge,
+    /// Immediate subfolders, largest first
+    pub children: Vec<DirectoryAge>,
+}
+
+/// Bucket key of a modification time
+pub fn period(modified: u64, granularity: AgeGranularity) -> String {
+    let time = DateTime::<Utc>::from_timestamp(modified as i64, 0).unwrap_or_default();
+    match granularity {
+        AgeGranularity::Year => time.year().to_string(),
+        AgeGranularity::Quarter => format!("{}-Q{}", time.year(), time.month0() / 3 + 1),
+    }
+}
+
+#[derive(Default)]
+struct Histogram {
+    buckets: BTreeMap<String, (u64, u64)>,
+    size: u64,
+    /// Sum of size * mtime, for the weighted mean
+    weighted: u128,
+}
+
+impl Histogram {
+    fn add(&mut self, size: u64, modified: u64, granularity: AgeGranularity) {
+        let bucket = self.buckets.entry(period(modified, granularity)).or_default();
+        bucket.0 += size;
+        bucket.1 += 1;
+        self.size += size;
+        self.weighted += size as u128 * modified as u128;
+    }
+
+    fn merge(&mut self, other: &Histogram) {
+        for (period, (size, files)) in &other.buckets {
+            let bucket = self.buckets.entry(period.clone()).or_default();
+            bucket.0 += size;
+            bucket.1 += files;
+        }
+        self.size += other.size;
+        self.weighted += other.weighted;
+    }
+
+    fn into_age(self, path: &Path) -> DirectoryAge {
+        DirectoryAge {
+            path: path.to_string_lossy().to_string(),
+            size: self.size,
+            mean_modified: if self.size == 0 { 0 } else { (self.weighted / self.size as u128) as u64 },
+            buckets: self
+                .buckets
+                .into_iter()
+                .map(|(period, (size, files))| AgeBucket { period, size, files })
+                .collect(),
+        }
+    }
+}
+
+fn modified_secs(meta: &std::fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+fn histogram(path: &Path, granularity: AgeGranularity, exclusions: &Exclusions, cancel: &AtomicBool) -> Histogram {
+    let mut histogram = Histogram::default();
+    let walker = WalkDir::new(path).into_iter().filter_entry(|e| !exclusions.is_excluded(e.path()));
+    for entry in walker.filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        if let Ok(meta) = entry.metadata() {
+            histogram.add(meta.len(), modified_secs(&meta), granularity);
+        }
+    }
+    histogram
+}
+
+/// Age histogram of `path` and each of its immediate subfolders
+pub fn heatmap(
+    path: &str,
+    granularity: AgeGranularity,
+    exclusions: &Exclusions,
+    cancel: &AtomicBool,
+) -> Result<AgeHeatmap, String> {
+    let root = Path::new(path);
+    let entries: Vec<_> = std::fs::read_dir(root)
+        .map_err(|e| e.to_string())?
+        .filter_map(|e| e.ok())
+        .filter(|e| !exclusions.is_excluded(&e.path()))
+        .collect();
+
+    let (dirs, files): (Vec<_>, Vec<_>) = entries.into_iter().partition(|e| e.file_type().is_ok_and(|t| t.is_dir()));
+    let child_histograms: Vec<(std::path::PathBuf, Histogram)> = dirs
+        .par_iter()
+        .map(|dir| (dir.path(), histogram(&dir.path(), granularity, exclusions, cancel)))
+        .collect();
+    if cancel.load(Ordering::Relaxed) {
+        return Err("Cancelled".to_string());
+    }
+
+    let mut total = Histogram::default();
+    for file in files {
+        if let Ok(meta) = file.metadata() {
+            total.add(meta.len(), modified_secs(&meta), granularity);
+        }
+    }
+    let mut children: Vec<DirectoryAge> = child_histograms
+        .into_iter()
+        .map(|(path, histogram)| {
+            total.merge(&histogram);
+            histogram.into_age(&path)
+        })
+        .collect();
+    children.sort_by(|a, b| b.size.cmp(&a.size));
+
+    Ok(AgeHeatmap {
+        granularity,
+        root: total.into_age(root),
+        children,
+    })
+}
@@ -0,0 +1,311 @@
+// Accelerators - GPU inventory and inference device selection
+//
+// Lists the GPUs in the machine with their memory and current load, and which
+// compute backends exist on this build and machine (CUDA, Metal, DirectML).
+// Embedded models run on the device picked here, and the report includes the
+// memory budget a model has to fit in, so the UI can explain why a model is
+// or isn't offered. GPUs are read from `nvidia-smi`, from sysfs on Linux
+// (AMD and Intel cards), from `system_profiler` on macOS and from WMI on
+// Windows; any of these may be missing, in which case fewer details appear.
+
+use candle_core::{utils, Device};
+use serde::Serialize;
+use std::process::Command;
+use tracing::{debug, info};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuInfo {
+    pub name: String,
+    /// "nvidia", "amd", "intel", "apple" or "other"
+    pub vendor: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vram_total: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vram_used: Option<u64>,
+    /// Current load in percent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utilization: Option<f32>,
+    /// Shares system memory (Apple Silicon, most integrated GPUs)
+    pub unified_memory: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputeBackend {
+    /// "cuda", "metal" or "directml"
+    pub name: String,
+    /// This build of Helium can run models on it
+    pub supported: bool,
+    /// The machine has the hardware and drivers for it
+    pub present: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AcceleratorReport {
+    pub gpus: Vec<GpuInfo>,
+    pub backends: Vec<ComputeBackend>,
+    /// Device embedded models run on: "cuda", "metal" or "cpu"
+    pub inference_device: String,
+    /// Largest model that fits the inference device's free memory
+    pub model_budget_bytes: u64,
+    /// Why the budget is what it is, for display
+    pub budget_reason: String,
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn vendor_of(name: &str) -> &'static str {
+    let name = name.to_lowercase();
+    if name.contains("nvidia") || name.contains("geforce") || name.contains("quadro") {
+        "nvidia"
+    } else if name.contains("amd") || name.contains("radeon") {
+        "amd"
+    } else if name.contains("intel") {
+        "intel"
+    } else if name.contains("apple") {
+        "apple"
+    } else {
+        "other"
+    }
+}
+
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        debug!("{} exited with {}", program, output.status);
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// NVIDIA GPUs with live memory and load
+fn nvidia_gpus() -> Vec<GpuInfo> {
+    const MIB: u64 = 1024 * 1024;
+    let Some(output) = run(
+        "nvidia-smi",
+        &[
+            "--query-gpu=name,memory.total,memory.used,utilization.gpu",
+            "--format=csv,noheader,nounits",
+        ],
+    ) else {
+        return Vec::new();
+    };
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [name, total, used, load] = fields[..] else {
+                return None;
+            };
+            Some(GpuInfo {
+                name: name.to_string(),
+                vendor: "nvidia".to_string(),
+                vram_total: total.parse::<u64>().ok().map(|m| m * MIB),
+                vram_used: used.parse::<u64>().ok().map(|m| m * MIB),
+                utilization: load.parse().ok(),
+                unified_memory: false,
+            })
+        })
+        .collect()
+}
+
+/// AMD and Intel cards from sysfs (NVIDIA ones come from nvidia-smi)
+#[cfg(target_os = "linux")]
+fn platform_gpus() -> Vec<GpuInfo> {
+    let read = |path: std::path::PathBuf| std::fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+    let Ok(entries) = std::fs::read_dir("/sys/class/drm") else {
+        return Vec::new();
+    };
+    let mut gpus = Vec::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // card0, card1, ... but not connectors such as card0-HDMI-A-1
+        if !name.starts_with("card") || name.contains('-') {
+            continue;
+        }
+        let device = entry.path().join("device");
+        let vendor = match read(device.join("vendor")).as_deref() {
+            Some("0x1002") => "amd",
+            Some("0x8086") => "intel",
+            _ => continue,
+        };
+        let vram_total = read(device.join("mem_info_vram_total")).and_then(|v| v.parse().ok());
+        gpus.push(GpuInfo {
+            name: read(device.join("product_name")).unwrap_or_else(|| format!("{} GPU ({})", vendor.to_uppercase(), name)),
+            vendor: vendor.to_string(),
+            vram_used: read(device.join("mem_info_vram_used")).and_then(|v| v.parse().ok()),
+            utilization: read(device.join("gpu_busy_percent")).and_then(|v| v.parse().ok()),
+            unified_memory: vram_total.is_none(),
+            vram_total,
+        });
+    }
+    gpus
+}
+
+#[cfg(target_os = "macos")]
+fn platform_gpus() -> Vec<GpuInfo> {
+    let Some(output) = run("system_profiler", &["SPDisplaysDataType", "-json"]) else {
+        return Vec::new();
+    };
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(&output) else {
+        return Vec::new();
+    };
+    let displays = json["SPDisplaysDataType"].as_array().cloned().unwrap_or_default();
+    displays
+        .iter()
+        .filter_map(|gpu| {
+            let name = gpu["sppci_model"].as_str()?.to_string();
+            // "8 GB" / "1536 MB"; absent on Apple Silicon, which shares system memory
+            let vram_total = gpu["spdisplays_vram"]
+                .as_str()
+                .or_else(|| gpu["spdisplays_vram_shared"].as_str())
+                .and_then(|vram| {
+                    let (amount, unit) = vram.split_once(' ')?;
+                    let amount: u64 = amount.parse().ok()?;
+                    Some(match unit {
+                        "GB" => amount * 1024 * 1024 * 1024,
+                        _ => amount * 1024 * 1024,
+                    })
+                });
+            Some(GpuInfo {
+                vendor: vendor_of(&name).to_string(),
+                unified_memory: vram_total.is_none(),
+                name,
+                vram_total,
+                vram_used: None,
+                utilization: None,
+            })
+        })
+        .collect()
+}
+
+/// Adapters from WMI. AdapterRAM is a 32-bit field, so it caps at 4 GB.
+#[cfg(target_os = "windows")]
+fn platform_gpus() -> Vec<GpuInfo> {
+    let Some(output) = run(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "Get-CimInstance Win32_VideoController | Select-Object Name,AdapterRAM | ConvertTo-Json",
+        ],
+    ) else {
+        return Vec::new();
+    };
+    let json: serde_json::Value = serde_json::from_str(&output).unwrap_or_default();
+    // A single adapter comes back as an object rather than an array
+    let adapters = match json {
+        serde_json::Value::Array(items) => items,
+        serde_json::Value::Null => Vec::new(),
+        item => vec![item],
+    };
+    adapters
+        .iter()
+        .filter_map(|adapter| {
+            let name = adapter["Name"].as_str()?.to_string();
+            let vendor = vendor_of(&name);
+            Some(GpuInfo {
+                vram_total: adapter["AdapterRAM"].as_u64().filter(|&ram| ram > 0),
+                vendor: vendor.to_string(),
+                unified_memory: vendor == "intel",
+                name,
+                vram_used: None,
+                utilization: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn platform_gpus() -> Vec<GpuInfo> {
+    Vec::new()
+}
+
+fn direct_ml_present() -> bool {
+    cfg!(windows)
+        && std::env::var_os("SystemRoot")
+            .map(|root| std::path::Path::new(&root).join("System32").join("DirectML.dll").exists())
+            .unwrap_or(false)
+}
+
+/// Device embedded models run on: the first CUDA GPU, else Metal, else the CPU
+pub fn inference_device() -> Device {
+    if utils::cuda_is_available() {
+        match Device::new_cuda(0) {
+            Ok(device) => return device,
+            Err(e) => info!("CUDA device unavailable, trying others: {}", e),
+        }
+    }
+    if utils::metal_is_available() {
+        match Device::new_metal(0) {
+            Ok(device) => return device,
+            Err(e) => info!("Metal device unavailable, using the CPU: {}", e),
+        }
+    }
+    Device::Cpu
+}
+
+fn device_name(device: &Device) -> &'static str {
+    match device {
+        Device::Cuda(_) => "cuda",
+        Device::Metal(_) => "metal",
+        Device::Cpu => "cpu",
+    }
+}
+
+/// GPUs, backends, the inference device and the model memory budget
+pub fn report() -> AcceleratorReport {
+    let mut gpus = nvidia_gpus();
+    // nvidia-smi reports NVIDIA cards in more detail than the platform does
+    let has_nvidia = !gpus.is_empty();
+    gpus.extend(platform_gpus().into_iter().filter(|g| !(has_nvidia && g.vendor == "nvidia")));
+
+    let backends = vec![
+        ComputeBackend {
+            name: "cuda".to_string(),
+            supported: utils::cuda_is_available(),
+            present: gpus.iter().any(|g| g.vendor == "nvidia"),
+        },
+        ComputeBackend {
+            name: "metal".to_string(),
+            supported: utils::metal_is_available(),
+            present: cfg!(target_os = "macos"),
+        },
+        ComputeBackend {
+            name: "directml".to_string(),
+            // Candle has no DirectML backend; it is reported for context
+            supported: false,
+            present: direct_ml_present(),
+        },
+    ];
+
+    let device = device_name(&inference_device());
+    let ram_budget = crate::ai::model_pool::default_budget();
+    let (model_budget_bytes, budget_reason) = match device {
+        "cuda" => match gpus.iter().find(|g| g.vendor == "nvidia") {
+            Some(GpuInfo {
+                name,
+                vram_total: Some(total),
+                vram_used,
+                ..
+            }) => (
+                total.saturating_sub(vram_used.unwrap_or(0)),
+                format!("Free video memory on {}", name),
+            ),
+            _ => (ram_budget, "Half of system memory (GPU memory unknown)".to_string()),
+        },
+        // Unified memory: the GPU shares the same budget as the CPU
+        "metal" => (ram_budget, "Half of the unified memory shared by CPU and GPU".to_string()),
+        _ => (ram_budget, "Half of system memory; models run on the CPU".to_string()),
+    };
+
+    AcceleratorReport {
+        gpus,
+        backends,
+        inference_device: device.to_string(),
+        model_budget_bytes,
+        budget_reason,
+    }
+}
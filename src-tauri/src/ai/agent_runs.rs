@@ -0,0 +1,177 @@
+// Agent Runs - Checkpoints of the tool-calling loop for crash recovery
+//
+// The agent loop runs in the frontend: inference, tool calls, tool results,
+// inference again. It checkpoints its state here at every step (the messages
+// so far, the step index and the tool call in flight), and removes the
+// checkpoint when the run ends. A checkpoint still on disk at startup belongs
+// to a run the app never finished. The conversation can then show where it
+// stopped, including a tool call that may have been partly applied, and the
+// user chooses to resume the run or abandon it with a note in the conversation.
+
+use crate::ai::{ChatMessage, MessageRole};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::info;
+
+/// Tool call that was running when the checkpoint was taken
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: Value,
+    pub started_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentRun {
+    pub id: String,
+    pub conversation_id: String,
+    /// Loop iteration, starting at 1
+    pub step: u32,
+    /// Messages sent with the next inference, tool results included
+    pub messages: Vec<ChatMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_tool_call: Option<PendingToolCall>,
+    pub updated_at: i64,
+    /// Left over from an earlier app session
+    #[serde(default, skip_deserializing)]
+    pub interrupted: bool,
+}
+
+/// Tool result explaining a tool call cut off by the restart
+fn interrupted_tool_result(call: &PendingToolCall) -> String {
+    format!(
+        "<tool_result name=\"{}\" error=\"true\">\nHelium closed while this tool was running, so it may have been \
+partly applied. Check the current state of the affected files before trying it again.\n</tool_result>",
+        call.name
+    )
+}
+
+pub struct AgentRunStore {
+    dir: PathBuf,
+    runs: Mutex<HashMap<String, AgentRun>>,
+}
+
+impl AgentRunStore {
+    /// Load the checkpoints left by earlier sessions; all of them are interrupted
+    pub fn load(dir: PathBuf) -> Self {
+        let mut runs = HashMap::new();
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().is_some_and(|e| e == "json") {
+                    if let Ok(Some(mut run)) = storage::load_json::<AgentRun>(&path) {
+                        run.interrupted = true;
+                        runs.insert(run.id.clone(), run);
+                    }
+                }
+            }
+        }
+        if !runs.is_empty() {
+            info!("Found {} interrupted agent runs", runs.len());
+        }
+        Self {
+            dir,
+            runs: Mutex::new(runs),
+        }
+    }
+
+    fn path_for(&self, id: &str) -> Result<PathBuf, String> {
+        if id.is_empty() || id.contains(['/', '\\']) || id.contains("..") {
+            return Err(format!("Invalid run id: {}", id));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+
+    /// Record the current state of a running loop
+    pub fn checkpoint(&self, mut run: AgentRun) -> Result<(), String> {
+        let path = self.path_for(&run.id)?;
+        run.interrupted = false;
+        run.updated_at = chrono::Utc::now().timestamp_millis();
+        storage::save_json(&path, &run)?;
+        self.runs.lock().unwrap().insert(run.id.clone(), run);
+        Ok(())
+    }
+
+    /// Drop a run's checkpoint, returning it
+    pub fn remove(&self, id: &str) -> Result<Option<AgentRun>, String> {
+        let path = self.path_for(id)?;
+        let removed = self.runs.lock().unwrap().remove(id);
+        if removed.is_some() {
+            storage::remove_json(&path)?;
+        }
+        Ok(removed)
+    }
+
+    fn take_interrupted(&self, id: &str) -> Result<AgentRun, String> {
+        let interrupted = self.runs.lock().unwrap().get(id).is_some_and(|r| r.interrupted);
+        if !interrupted {
+            return Err(format!("No interrupted run: {}", id));
+        }
+        self.remove(id)?.ok_or_else(|| format!("No interrupted run: {}", id))
+    }
+
+    /// Interrupted runs, optionally of one conversation, most recent first
+    pub fn interrupted(&self, conversation_id: Option<&str>) -> Vec<AgentRun> {
+        let mut runs: Vec<AgentRun> = self
+            .runs
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|r| r.interrupted && !conversation_id.is_some_and(|id| r.conversation_id != id))
+            .cloned()
+            .collect();
+        runs.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        runs
+    }
+
+    /// Messages to continue an interrupted run with. A tool call that was in
+    /// flight gets an error result, so the model checks before retrying it.
+    pub fn resume(&self, id: &str) -> Result<Vec<ChatMessage>, String> {
+        let run = self.take_interrupted(id)?;
+        let mut messages = run.messages;
+        if let Some(call) = &run.pending_tool_call {
+            messages.push(note(MessageRole::User, interrupted_tool_result(call)));
+        }
+        Ok(messages)
+    }
+
+    /// Give up on an interrupted run. Returns the note to append to its
+    /// conversation, which says where it stopped.
+    pub fn abandon(&self, id: &str) -> Result<(String, ChatMessage), String> {
+        let run = self.take_interrupted(id)?;
+        let content = match &run.pending_tool_call {
+            Some(call) => format!(
+                "This run stopped at step {} while running the tool `{}` ({}). It may have been partly applied; \
+check the affected files before retrying.",
+                run.step, call.name, call.arguments
+            ),
+            None => format!("This run stopped at step {}, between tool calls, and was abandoned.", run.step),
+        };
+        let mut message = note(MessageRole::Assistant, content);
+        message.interrupted = Some(true);
+        Ok((run.conversation_id, message))
+    }
+}
+
+fn note(role: MessageRole, content: String) -> ChatMessage {
+    let now = chrono::Utc::now().timestamp_millis();
+    ChatMessage {
+        id: format!("msg-{}", uuid::Uuid::new_v4()),
+        role,
+        content,
+        timestamp: now,
+        context_paths: None,
+        is_streaming: None,
+        error: None,
+        tool_calls: None,
+        images: None,
+        interrupted: None,
+    }
+}
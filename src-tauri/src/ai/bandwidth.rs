@@ -0,0 +1,179 @@
+// Bandwidth - Download rate limiting and throughput reporting
+//
+// Model downloads pass every received chunk through a shared token bucket, so
+// a configurable cap (bytes per second) holds across all concurrent downloads
+// and pulling a multi-GB model doesn't saturate the connection. The same hook
+// measures throughput and reports it in download progress events.
+
+use crate::ai::providers::DownloadStatus;
+use crate::storage;
+use hf_hub::api::tokio::Progress;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::info;
+
+/// Minimum interval between progress events for one file
+const REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Persisted download settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BandwidthSettings {
+    /// Cap in bytes per second; None for unlimited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_bytes_per_sec: Option<u64>,
+}
+
+struct TokenBucket {
+    settings: BandwidthSettings,
+    path: Option<PathBuf>,
+    /// May go negative: a large chunk is admitted and the debt waited off
+    tokens: f64,
+    last_refill: Instant,
+}
+
+lazy_static! {
+    static ref BUCKET: Mutex<TokenBucket> = Mutex::new(TokenBucket {
+        settings: BandwidthSettings::default(),
+        path: None,
+        tokens: 0.0,
+        last_refill: Instant::now(),
+    });
+}
+
+/// Load the persisted settings (called once at startup)
+pub fn load(path: PathBuf) {
+    let settings: BandwidthSettings = storage::load_json(&path).ok().flatten().unwrap_or_default();
+    let mut bucket = BUCKET.lock().unwrap();
+    bucket.settings = settings;
+    bucket.path = Some(path);
+}
+
+pub fn settings() -> BandwidthSettings {
+    BUCKET.lock().unwrap().settings.clone()
+}
+
+/// Change the cap; applies to downloads already in progress
+pub fn set_limit(limit_bytes_per_sec: Option<u64>) -> Result<BandwidthSettings, String> {
+    let limit = limit_bytes_per_sec.filter(|&l| l > 0);
+    let mut bucket = BUCKET.lock().unwrap();
+    bucket.settings.limit_bytes_per_sec = limit;
+    bucket.tokens = 0.0;
+    bucket.last_refill = Instant::now();
+    if let Some(path) = &bucket.path {
+        storage::save_json(path, &bucket.settings)?;
+    }
+    info!("Download limit: {:?} bytes/s", limit);
+    Ok(bucket.settings.clone())
+}
+
+/// Take `bytes` from the bucket, waiting until the cap allows them
+async fn acquire(bytes: usize) {
+    let wait = {
+        let mut bucket = BUCKET.lock().unwrap();
+        let Some(rate) = bucket.settings.limit_bytes_per_sec else {
+            return;
+        };
+        let rate = rate as f64;
+        let now = Instant::now();
+        // Allow at most one second of burst
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.last_refill).as_secs_f64() * rate).min(rate);
+        bucket.last_refill = now;
+        bucket.tokens -= bytes as f64;
+        if bucket.tokens < 0.0 {
+            Duration::from_secs_f64(-bucket.tokens / rate)
+        } else {
+            Duration::ZERO
+        }
+    };
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+struct ProgressState {
+    total: usize,
+    downloaded: usize,
+    started: Instant,
+    last_report: Option<Instant>,
+}
+
+/// Download progress hook: throttles to the configured cap and reports
+/// progress (mapped into `range` of the overall download) and throughput
+#[derive(Clone)]
+pub struct DownloadProgress {
+    sender: Option<mpsc::Sender<DownloadStatus>>,
+    label: String,
+    range: (f32, f32),
+    state: Arc<Mutex<ProgressState>>,
+}
+
+impl DownloadProgress {
+    pub fn new(sender: Option<mpsc::Sender<DownloadStatus>>, label: &str, range: (f32, f32)) -> Self {
+        Self {
+            sender,
+            label: label.to_string(),
+            range,
+            state: Arc::new(Mutex::new(ProgressState {
+                total: 0,
+                downloaded: 0,
+                started: Instant::now(),
+                last_report: None,
+            })),
+        }
+    }
+
+    fn report(&self, force: bool) {
+        let Some(tx) = &self.sender else {
+            return;
+        };
+        let status = {
+            let mut state = self.state.lock().unwrap();
+            let now = Instant::now();
+            if !force && state.last_report.is_some_and(|t| now.duration_since(t) < REPORT_INTERVAL) {
+                return;
+            }
+            state.last_report = Some(now);
+
+            let fraction = if state.total > 0 {
+                (state.downloaded as f32 / state.total as f32).min(1.0)
+            } else {
+                0.0
+            };
+            let elapsed = now.duration_since(state.started).as_secs_f64();
+            DownloadStatus {
+                status: self.label.clone(),
+                progress: self.range.0 + (self.range.1 - self.range.0) * fraction,
+                bytes_per_second: (elapsed > 0.0).then(|| (state.downloaded as f64 / elapsed) as u64),
+            }
+        };
+        let _ = tx.try_send(status);
+    }
+}
+
+impl Progress for DownloadProgress {
+    async fn init(&mut self, size: usize, _filename: &str) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.total = size;
+            state.downloaded = 0;
+            state.started = Instant::now();
+        }
+        self.report(true);
+    }
+
+    async fn update(&mut self, size: usize) {
+        // Waiting here holds back the stream reader until the cap allows more
+        acquire(size).await;
+        self.state.lock().unwrap().downloaded += size;
+        self.report(false);
+    }
+
+    async fn finish(&mut self) {
+        self.report(true);
+    }
+}
@@ -0,0 +1,187 @@
+// Context - Files and folders attached to a conversation
+//
+// The user can attach folders (typically scanned subtrees) and files from the
+// file tree to a conversation; the attachments stay with it until removed.
+// Paths a message names in `contextPaths` are added for that request only.
+// Before inference the attachments are resolved into one system message: a
+// folder becomes a listing of its largest entries (from the scan cache when
+// it was scanned, else read from disk), a text file its content, any other
+// file a line with its type and size. Each file and the message as a whole
+// are capped, so attaching a big folder can't crowd out the conversation.
+// Callers pass only paths within the MCP allowed directories.
+
+use crate::ai::{ChatMessage, InferenceRequest, MessageRole};
+use crate::content_type;
+use crate::mcp::native_server::format_bytes;
+use crate::{paths, scan_cache};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+
+/// Characters of context injected per request, all attachments together
+const MAX_CONTEXT_CHARS: usize = 24_000;
+/// Characters of a single text file
+const MAX_FILE_CHARS: usize = 8_000;
+/// Entries listed per folder
+const MAX_LISTING_ENTRIES: usize = 40;
+
+/// File or folder attached to a conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextAttachment {
+    pub path: String,
+    pub is_dir: bool,
+    pub added_at: i64,
+}
+
+impl ContextAttachment {
+    pub fn new(path: &str) -> Result<Self, String> {
+        let meta = std::fs::metadata(paths::extended(Path::new(path))).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(Self {
+            path: path.to_string(),
+            is_dir: meta.is_dir(),
+            added_at: chrono::Utc::now().timestamp_millis(),
+        })
+    }
+}
+
+fn describe_folder(path: &str) -> String {
+    // A scanned folder has sizes for its subfolders too
+    if let Some(node) = scan_cache::get(path) {
+        let mut out = format!(
+            "### Folder {} ({}, {} files)\n",
+            path,
+            format_bytes(node.size),
+            node.file_count
+        );
+        let children = node.children.unwrap_or_default();
+        for child in children.iter().take(MAX_LISTING_ENTRIES) {
+            let suffix = if child.is_dir { "/" } else { "" };
+            out.push_str(&format!("- {}{} ({})\n", child.name, suffix, format_bytes(child.size)));
+        }
+        if children.len() > MAX_LISTING_ENTRIES {
+            out.push_str(&format!("- ... {} more entries\n", children.len() - MAX_LISTING_ENTRIES));
+        }
+        return out;
+    }
+
+    let mut entries: Vec<(String, bool, u64)> = std::fs::read_dir(paths::extended(Path::new(path)))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let meta = e.metadata().ok()?;
+                    Some((e.file_name().to_string_lossy().to_string(), meta.is_dir(), meta.len()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // Folders first, then files by size
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)));
+    let mut out = format!("### Folder {} (not scanned; folder sizes unknown)\n", path);
+    for (name, is_dir, size) in entries.iter().take(MAX_LISTING_ENTRIES) {
+        if *is_dir {
+            out.push_str(&format!("- {}/\n", name));
+        } else {
+            out.push_str(&format!("- {} ({})\n", name, format_bytes(*size)));
+        }
+    }
+    if entries.len() > MAX_LISTING_ENTRIES {
+        out.push_str(&format!("- ... {} more entries\n", entries.len() - MAX_LISTING_ENTRIES));
+    }
+    out
+}
+
+fn describe_file(path: &str) -> String {
+    let file_path = paths::extended(Path::new(path));
+    let size = std::fs::metadata(&file_path).map(|m| m.len()).unwrap_or(0);
+    let detected = match content_type::detect(Path::new(path)) {
+        Ok(detected) => detected,
+        Err(e) => return format!("### File {}\n(unreadable: {})\n", path, e),
+    };
+    if !detected.is_text() {
+        return format!("### File {} ({}, {})\n", path, detected.mime, format_bytes(size));
+    }
+
+    // Read a little more than fits, to cut at a character boundary
+    let mut bytes = Vec::new();
+    let _ = std::fs::File::open(&file_path).and_then(|f| f.take(MAX_FILE_CHARS as u64 * 4).read_to_end(&mut bytes));
+    let text = String::from_utf8_lossy(&bytes);
+    let content: String = text.chars().take(MAX_FILE_CHARS).collect();
+    let truncated = content.len() < size as usize;
+    format!(
+        "### File {} ({})\n```\n{}\n```\n{}",
+        path,
+        format_bytes(size),
+        content.trim_end(),
+        if truncated { "(truncated)\n" } else { "" }
+    )
+}
+
+/// Context block for the given paths, or None when there is nothing to add
+pub fn resolve(paths_to_attach: &[String]) -> Option<String> {
+    let mut out = String::from(
+        "The user attached these files and folders as context for the conversation. Refer to them when relevant.\n\n",
+    );
+    let header = out.len();
+    for (index, path) in paths_to_attach.iter().enumerate() {
+        let section = if paths::extended(Path::new(path)).is_dir() {
+            describe_folder(path)
+        } else {
+            describe_file(path)
+        };
+        if out.len() + section.len() > MAX_CONTEXT_CHARS {
+            let left = paths_to_attach.len() - index;
+            out.push_str(&format!("({} more attachments left out to stay within the context limit)\n", left));
+            break;
+        }
+        out.push_str(&section);
+        out.push('\n');
+    }
+    (out.len() > header).then_some(out)
+}
+
+/// The conversation's attachments and the paths named by the latest user message
+pub fn requested_paths(request: &InferenceRequest, attachments: &[ContextAttachment]) -> Vec<String> {
+    let mut all: Vec<String> = attachments.iter().map(|a| a.path.clone()).collect();
+    let message_paths = request
+        .messages
+        .iter()
+        .rev()
+        .find(|m| m.role == MessageRole::User)
+        .and_then(|m| m.context_paths.clone())
+        .unwrap_or_default();
+    for path in message_paths {
+        if !all.iter().any(|p| paths::same(Path::new(p), Path::new(&path))) {
+            all.push(path);
+        }
+    }
+    all
+}
+
+/// Add the context block for `paths` to the request, after any system prompt
+pub fn inject(request: &mut InferenceRequest, paths: &[String]) {
+    let Some(content) = resolve(paths) else {
+        return;
+    };
+    let position = request
+        .messages
+        .iter()
+        .take_while(|m| m.role == MessageRole::System)
+        .count();
+    request.messages.insert(
+        position,
+        ChatMessage {
+            id: "attached-context".to_string(),
+            role: MessageRole::System,
+            content,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            context_paths: None,
+            is_streaming: None,
+            error: None,
+            tool_calls: None,
+            images: None,
+            interrupted: None,
+        },
+    );
+}
@@ -0,0 +1,505 @@
+// Conversations - Persisted chat history with generated titles and summaries
+//
+// Each conversation is stored as its own JSON file in the app data directory.
+// After a few exchanges a background, low-priority inference generates a title
+// and a rolling summary so the saved-chats list is navigable. Updates are
+// emitted as `conversation-updated` events.
+
+use crate::ai::context::ContextAttachment;
+use crate::ai::providers::{infer_once, InferenceSink, ProviderRegistry};
+use crate::ai::scheduler::InferenceScheduler;
+use crate::ai::{AIError, AIMode, ChatMessage, InferenceRequest, MessageRole, ModelConfig, ModelProvider};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// User messages needed before a title is generated
+const TITLE_AFTER_USER_MESSAGES: usize = 2;
+/// New messages that trigger a summary refresh
+const SUMMARY_EVERY_MESSAGES: usize = 6;
+/// Characters of each message included in the background prompt
+const EXCERPT_CHARS: usize = 600;
+
+/// A saved conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Conversation {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub mode: AIMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_config: Option<ModelConfig>,
+    pub messages: Vec<ChatMessage>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    /// Number of messages covered by `summary`
+    #[serde(default)]
+    pub summarized_messages: usize,
+    /// Title was set by the user and must not be regenerated
+    #[serde(default)]
+    pub custom_title: bool,
+    /// Settings pinned to this conversation
+    #[serde(default)]
+    pub overrides: ConversationOverrides,
+    /// Files and folders attached as context to every request
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<ContextAttachment>,
+}
+
+/// Model and prompt settings pinned to a conversation, taking precedence over
+/// the global defaults sent with each request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<ModelProvider>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+}
+
+impl ConversationOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.provider.is_none()
+            && self.model_id.is_none()
+            && self.temperature.is_none()
+            && self.system_prompt.is_none()
+    }
+
+    /// Resolve the overrides over a model configuration
+    pub fn apply_to_config(&self, config: &mut ModelConfig) {
+        if let Some(provider) = &self.provider {
+            if &config.provider != provider {
+                // Endpoint and key belong to the global provider, not the pinned one
+                config.endpoint = None;
+                config.api_key = None;
+            }
+            config.provider = provider.clone();
+        }
+        if let Some(model_id) = &self.model_id {
+            config.model_id = model_id.clone();
+            config.id = model_id.clone();
+            config.name = model_id.clone();
+        }
+        if let Some(temperature) = self.temperature {
+            config.parameters.temperature = temperature;
+        }
+    }
+
+    /// Resolve the overrides over an inference request. A pinned system prompt
+    /// replaces any preset selected for the request.
+    pub fn apply(&self, request: &mut InferenceRequest) {
+        self.apply_to_config(&mut request.model_config);
+        if let Some(system_prompt) = &self.system_prompt {
+            request.preset_id = None;
+            request.messages.retain(|m| m.role != MessageRole::System);
+            request.messages.insert(
+                0,
+                ChatMessage {
+                    id: "conversation-system-prompt".to_string(),
+                    role: MessageRole::System,
+                    content: system_prompt.clone(),
+                    timestamp: chrono::Utc::now().timestamp_millis(),
+                    context_paths: None,
+                    is_streaming: None,
+                    error: None,
+                    tool_calls: None,
+                    images: None,
+                    interrupted: None,
+                },
+            );
+        }
+    }
+}
+
+/// Lightweight listing entry for the saved-chats list
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationMeta {
+    pub id: String,
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    pub mode: AIMode,
+    pub message_count: usize,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Conversation {
+    pub fn meta(&self) -> ConversationMeta {
+        ConversationMeta {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            summary: self.summary.clone(),
+            mode: self.mode.clone(),
+            message_count: self.messages.len(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+
+    fn needs_title(&self) -> bool {
+        let user_messages = self
+            .messages
+            .iter()
+            .filter(|m| m.role == MessageRole::User)
+            .count();
+        self.title.is_none() && !self.custom_title && user_messages >= TITLE_AFTER_USER_MESSAGES
+    }
+
+    fn needs_summary(&self) -> bool {
+        self.messages.len() >= self.summarized_messages + SUMMARY_EVERY_MESSAGES
+    }
+}
+
+/// Persisted conversations, kept in managed state
+pub struct ConversationStore {
+    dir: PathBuf,
+    conversations: Mutex<HashMap<String, Conversation>>,
+    /// Conversations with a background title/summary task running
+    in_flight: Mutex<HashSet<String>>,
+}
+
+impl ConversationStore {
+    /// Load every conversation file in `dir`
+    pub fn load(dir: PathBuf) -> Self {
+        let mut conversations = HashMap::new();
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().is_some_and(|e| e == "json") {
+                    if let Ok(Some(conversation)) = storage::load_json::<Conversation>(&path) {
+                        conversations.insert(conversation.id.clone(), conversation);
+                    }
+                }
+            }
+        }
+        info!("Loaded {} conversations", conversations.len());
+
+        Self {
+            dir,
+            conversations: Mutex::new(conversations),
+            in_flight: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn path_for(&self, id: &str) -> Result<PathBuf, String> {
+        if id.is_empty() || id.contains(['/', '\\']) || id.contains("..") {
+            return Err(format!("Invalid conversation id: {}", id));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+
+    fn persist(&self, conversation: &Conversation) -> Result<(), String> {
+        storage::save_json(&self.path_for(&conversation.id)?, conversation)
+    }
+
+    /// Saved conversations, most recently updated first
+    pub fn list(&self) -> Vec<ConversationMeta> {
+        let conversations = self.conversations.lock().unwrap();
+        let mut list: Vec<ConversationMeta> = conversations.values().map(|c| c.meta()).collect();
+        list.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        list
+    }
+
+    pub fn get(&self, id: &str) -> Option<Conversation> {
+        self.conversations.lock().unwrap().get(id).cloned()
+    }
+
+    /// Insert or replace a conversation, keeping generated metadata the caller
+    /// did not send back
+    pub fn save(&self, mut conversation: Conversation) -> Result<Conversation, String> {
+        let mut conversations = self.conversations.lock().unwrap();
+        if let Some(existing) = conversations.get(&conversation.id) {
+            if conversation.overrides.is_empty() {
+                conversation.overrides = existing.overrides.clone();
+            }
+            if conversation.attachments.is_empty() {
+                conversation.attachments = existing.attachments.clone();
+            }
+            if conversation.title.is_none() {
+                conversation.title = existing.title.clone();
+                conversation.custom_title = existing.custom_title;
+            }
+            if conversation.summary.is_none() {
+                conversation.summary = existing.summary.clone();
+                conversation.summarized_messages = existing.summarized_messages;
+            }
+        }
+        conversation.summarized_messages = conversation.summarized_messages.min(conversation.messages.len());
+        // API keys belong in the credential store, never in conversation files
+        if let Some(config) = conversation.model_config.as_mut() {
+            config.api_key = None;
+        }
+
+        self.persist(&conversation)?;
+        conversations.insert(conversation.id.clone(), conversation.clone());
+        Ok(conversation)
+    }
+
+    /// Replace the settings pinned to a conversation
+    pub fn set_overrides(&self, id: &str, overrides: ConversationOverrides) -> Result<Conversation, String> {
+        let mut conversations = self.conversations.lock().unwrap();
+        let conversation = conversations
+            .get_mut(id)
+            .ok_or_else(|| format!("Conversation not found: {}", id))?;
+        conversation.overrides = overrides;
+        self.persist(conversation)?;
+        Ok(conversation.clone())
+    }
+
+    /// Attach files or folders; paths already attached are skipped
+    pub fn attach(&self, id: &str, attachments: Vec<ContextAttachment>) -> Result<Conversation, String> {
+        let mut conversations = self.conversations.lock().unwrap();
+        let conversation = conversations
+            .get_mut(id)
+            .ok_or_else(|| format!("Conversation not found: {}", id))?;
+        for attachment in attachments {
+            if !conversation.attachments.iter().any(|a| a.path == attachment.path) {
+                conversation.attachments.push(attachment);
+            }
+        }
+        self.persist(conversation)?;
+        Ok(conversation.clone())
+    }
+
+    pub fn detach(&self, id: &str, path: &str) -> Result<Conversation, String> {
+        let mut conversations = self.conversations.lock().unwrap();
+        let conversation = conversations
+            .get_mut(id)
+            .ok_or_else(|| format!("Conversation not found: {}", id))?;
+        conversation.attachments.retain(|a| a.path != path);
+        self.persist(conversation)?;
+        Ok(conversation.clone())
+    }
+
+    pub fn rename(&self, id: &str, title: String) -> Result<ConversationMeta, String> {
+        let mut conversations = self.conversations.lock().unwrap();
+        let conversation = conversations
+            .get_mut(id)
+            .ok_or_else(|| format!("Conversation not found: {}", id))?;
+        conversation.title = Some(title);
+        conversation.custom_title = true;
+        self.persist(conversation)?;
+        Ok(conversation.meta())
+    }
+
+    /// Store the partial answer of an interrupted generation, replacing the
+    /// assistant message the frontend was still streaming into, if saved
+    pub fn append_interrupted(&self, id: &str, message: ChatMessage) -> Result<ConversationMeta, String> {
+        let mut conversations = self.conversations.lock().unwrap();
+        let conversation = conversations
+            .get_mut(id)
+            .ok_or_else(|| format!("Conversation not found: {}", id))?;
+        let streaming = conversation
+            .messages
+            .last()
+            .is_some_and(|m| m.role == MessageRole::Assistant && m.is_streaming == Some(true));
+        if streaming {
+            conversation.messages.pop();
+        }
+        conversation.messages.push(message);
+        conversation.updated_at = chrono::Utc::now().timestamp_millis();
+        self.persist(conversation)?;
+        Ok(conversation.meta())
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        let path = self.path_for(id)?;
+        let removed = self.conversations.lock().unwrap().remove(id);
+        if removed.is_none() {
+            return Err(format!("Conversation not found: {}", id));
+        }
+        storage::remove_json(&path)
+    }
+
+    /// Store generated metadata, unless the conversation changed underneath us
+    fn apply_generated(
+        &self,
+        id: &str,
+        title: Option<String>,
+        summary: Option<(String, usize)>,
+    ) -> Result<Option<ConversationMeta>, String> {
+        let mut conversations = self.conversations.lock().unwrap();
+        let Some(conversation) = conversations.get_mut(id) else {
+            return Ok(None);
+        };
+
+        if let Some(title) = title {
+            if !conversation.custom_title {
+                conversation.title = Some(title);
+            }
+        }
+        if let Some((summary, covered)) = summary {
+            if covered <= conversation.messages.len() {
+                conversation.summary = Some(summary);
+                conversation.summarized_messages = covered;
+            }
+        }
+
+        self.persist(conversation)?;
+        Ok(Some(conversation.meta()))
+    }
+}
+
+fn excerpt(messages: &[ChatMessage]) -> String {
+    messages
+        .iter()
+        .filter(|m| m.role != MessageRole::System && !m.content.trim().is_empty())
+        .map(|m| {
+            let role = if m.role == MessageRole::User { "User" } else { "Assistant" };
+            let content: String = m.content.chars().take(EXCERPT_CHARS).collect();
+            format!("{}: {}", role, content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn background_request(conversation: &Conversation, model_config: &ModelConfig, instruction: &str, max_tokens: u32) -> InferenceRequest {
+    let now = chrono::Utc::now().timestamp_millis();
+    let message = |role: MessageRole, content: String| ChatMessage {
+        id: uuid::Uuid::new_v4().to_string(),
+        role,
+        content,
+        timestamp: now,
+        context_paths: None,
+        is_streaming: None,
+        error: None,
+        tool_calls: None,
+        images: None,
+        interrupted: None,
+    };
+
+    let mut model_config = model_config.clone();
+    model_config.parameters.temperature = 0.2;
+    model_config.parameters.max_tokens = max_tokens;
+
+    InferenceRequest {
+        session_id: format!("background-{}", conversation.id),
+        model_config,
+        messages: vec![
+            message(MessageRole::System, instruction.to_string()),
+            message(MessageRole::User, excerpt(&conversation.messages)),
+        ],
+        fs_context: None,
+        mode: conversation.mode.clone(),
+        tools: None,
+        preset_id: None,
+        conversation_id: None,
+    }
+}
+
+fn clean_title(raw: &str) -> Option<String> {
+    let line = raw.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let title = line
+        .trim_start_matches("Title:")
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'' || c == '*')
+        .trim_end_matches('.')
+        .trim();
+    if title.is_empty() {
+        None
+    } else {
+        Some(title.chars().take(80).collect())
+    }
+}
+
+async fn generate_metadata(app: &AppHandle, conversation: Conversation) -> Result<(), AIError> {
+    let Some(mut model_config) = conversation.model_config.clone() else {
+        return Ok(());
+    };
+    conversation.overrides.apply_to_config(&mut model_config);
+    let registry = app.state::<ProviderRegistry>();
+    let scheduler = app.state::<InferenceScheduler>();
+    let Some(provider) = registry.get(&model_config.provider) else {
+        return Ok(());
+    };
+
+    // Low priority: never make an interactive request wait behind us. We will
+    // try again the next time the conversation is saved.
+    if scheduler.is_busy(provider.as_ref(), &model_config.model_id) {
+        info!("Model busy, deferring metadata for {}", conversation.id);
+        return Ok(());
+    }
+
+    let session_id = format!("background-{}", conversation.id);
+    let _permit = scheduler
+        .acquire(
+            provider.as_ref(),
+            &model_config.model_id,
+            &session_id,
+            &InferenceSink::silent(),
+            &CancellationToken::new(),
+        )
+        .await?;
+
+    let title = if conversation.needs_title() {
+        let request = background_request(
+            &conversation,
+            &model_config,
+            "Write a short title (at most 6 words) for the following conversation. Reply with the title only.",
+            24,
+        );
+        clean_title(&infer_once(provider.as_ref(), &request).await?)
+    } else {
+        None
+    };
+
+    let summary = if conversation.needs_summary() {
+        let mut instruction = String::from(
+            "Summarize the following conversation in 2-3 sentences, focusing on the files, folders and decisions discussed. Reply with the summary only.",
+        );
+        if let Some(previous) = &conversation.summary {
+            instruction.push_str(&format!("\n\nPrevious summary:\n{}", previous));
+        }
+        let request = background_request(&conversation, &model_config, &instruction, 160);
+        let text = infer_once(provider.as_ref(), &request).await?;
+        let text = text.trim();
+        (!text.is_empty()).then(|| (text.to_string(), conversation.messages.len()))
+    } else {
+        None
+    };
+
+    if title.is_none() && summary.is_none() {
+        return Ok(());
+    }
+
+    let store = app.state::<ConversationStore>();
+    if let Ok(Some(meta)) = store.apply_generated(&conversation.id, title, summary) {
+        let _ = app.emit("conversation-updated", meta);
+    }
+    Ok(())
+}
+
+/// Generate a title and/or summary in the background if the conversation is due
+pub fn schedule_metadata(app: &AppHandle, conversation: &Conversation) {
+    if !conversation.needs_title() && !conversation.needs_summary() {
+        return;
+    }
+
+    let store = app.state::<ConversationStore>();
+    if !store.in_flight.lock().unwrap().insert(conversation.id.clone()) {
+        return;
+    }
+
+    let app = app.clone();
+    let conversation = conversation.clone();
+    tauri::async_runtime::spawn(async move {
+        let id = conversation.id.clone();
+        if let Err(e) = generate_metadata(&app, conversation).await {
+            warn!("Failed to generate metadata for {}: {}", id, e.message);
+        }
+        app.state::<ConversationStore>().in_flight.lock().unwrap().remove(&id);
+    });
+}
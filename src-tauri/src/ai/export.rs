@@ -0,0 +1,146 @@
+// Export - Conversation transcripts as Markdown or JSON
+//
+// Renders a saved conversation as a readable Markdown transcript (tool calls
+// and tool results as fenced blocks) or as a versioned JSON document that can
+// be re-imported or processed by other tools.
+
+use crate::ai::conversations::Conversation;
+use crate::ai::{AIMode, ChatMessage, MessageRole};
+use serde::{Deserialize, Serialize};
+
+/// Version of the JSON export layout
+const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonExport<'a> {
+    format: &'static str,
+    version: u32,
+    exported_at: i64,
+    conversation: &'a Conversation,
+}
+
+fn format_time(millis: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// Fence that is longer than any backtick run in `content`
+fn fence(content: &str) -> String {
+    let longest = content
+        .split(|c| c != '`')
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn code_block(lang: &str, content: &str) -> String {
+    let fence = fence(content);
+    format!("{}{}\n{}\n{}\n", fence, lang, content.trim_end(), fence)
+}
+
+/// Split a `<tool_result name="...">...</tool_result>` message into name and body
+fn tool_result(content: &str) -> Option<(&str, &str)> {
+    let rest = content.trim().strip_prefix("<tool_result name=\"")?;
+    let (name, rest) = rest.split_once("\">")?;
+    let body = rest.strip_suffix("</tool_result>")?;
+    Some((name, body.trim()))
+}
+
+fn render_message(out: &mut String, message: &ChatMessage) {
+    if let Some((name, body)) = tool_result(&message.content) {
+        out.push_str(&format!("**Tool result:** `{}`\n\n", name));
+        out.push_str(&code_block("", body));
+        out.push('\n');
+        return;
+    }
+
+    let role = match message.role {
+        MessageRole::User => "User",
+        MessageRole::Assistant => "Assistant",
+        MessageRole::System => "System",
+    };
+    out.push_str(&format!("### {} · {}\n\n", role, format_time(message.timestamp)));
+
+    if !message.content.trim().is_empty() {
+        out.push_str(message.content.trim());
+        out.push_str("\n\n");
+    }
+    if let Some(paths) = message.context_paths.as_ref().filter(|p| !p.is_empty()) {
+        out.push_str("Context:\n");
+        for path in paths {
+            out.push_str(&format!("- `{}`\n", path));
+        }
+        out.push('\n');
+    }
+    if let Some(images) = message.images.as_ref().filter(|i| !i.is_empty()) {
+        out.push_str(&format!("_{} image(s) attached_\n\n", images.len()));
+    }
+    for call in message.tool_calls.iter().flatten() {
+        let arguments = serde_json::from_str::<serde_json::Value>(&call.function.arguments)
+            .and_then(|v| serde_json::to_string_pretty(&v))
+            .unwrap_or_else(|_| call.function.arguments.clone());
+        out.push_str(&format!("**Tool call:** `{}`\n\n", call.function.name));
+        out.push_str(&code_block("json", &arguments));
+        out.push('\n');
+    }
+    if let Some(error) = &message.error {
+        out.push_str(&format!("> **Error:** {}\n\n", error));
+    }
+}
+
+fn render_markdown(conversation: &Conversation) -> String {
+    let mut out = format!(
+        "# {}\n\n",
+        conversation.title.as_deref().unwrap_or("Untitled conversation")
+    );
+    out.push_str(&format!(
+        "- Mode: {}\n- Started: {}\n- Updated: {}\n",
+        if conversation.mode == AIMode::Agent { "Agent" } else { "Q&A" },
+        format_time(conversation.created_at),
+        format_time(conversation.updated_at)
+    ));
+    if let Some(config) = &conversation.model_config {
+        out.push_str(&format!("- Model: {} ({})\n", config.model_id, config.provider.id()));
+    }
+    out.push('\n');
+    if let Some(summary) = &conversation.summary {
+        out.push_str(&format!("> {}\n\n", summary.trim().replace('\n', "\n> ")));
+    }
+    out.push_str("---\n\n");
+
+    for message in &conversation.messages {
+        render_message(&mut out, message);
+    }
+    out
+}
+
+/// Render a conversation in the requested format
+pub fn render(conversation: &Conversation, format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Markdown => Ok(render_markdown(conversation)),
+        ExportFormat::Json => {
+            // Inline image data would bloat the export; keep only the paths
+            let mut conversation = conversation.clone();
+            for image in conversation.messages.iter_mut().flat_map(|m| m.images.iter_mut().flatten()) {
+                image.data = None;
+            }
+            serde_json::to_string_pretty(&JsonExport {
+                format: "helium-conversation",
+                version: EXPORT_VERSION,
+                exported_at: chrono::Utc::now().timestamp_millis(),
+                conversation: &conversation,
+            })
+            .map_err(|e| format!("Failed to serialize conversation: {}", e))
+        }
+    }
+}
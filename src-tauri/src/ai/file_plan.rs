@@ -0,0 +1,547 @@
+// File Plans - Natural-language file operations
+//
+// Turns an instruction such as "move all screenshots from Desktop older than a
+// month into Pictures/Screenshots" into a list of file operations. The agent
+// inspects the file system through the read-only MCP tools, then answers with
+// a JSON plan. Plans are previewed (sizes, conflicts) and kept in memory until
+// the user explicitly confirms them; nothing is touched before that. A
+// confirmed plan runs as one transaction, so it never ends up half-applied,
+// and the items it deletes are quarantined so they can still be restored.
+
+use crate::ai::providers::{AIProvider, InferenceSink};
+use crate::ai::{
+    AIError, AIErrorType, AIMode, ChatMessage, InferenceRequest, MessageRole, ModelConfig, Tool, ToolFunction,
+};
+use crate::mcp::{GuardrailDecision, MCPError, MCPResult, NativeMCPServer, ProposedAction};
+use crate::paths;
+use crate::quarantine::{QuarantineStore, QUARANTINE_EVENT};
+use crate::transactions::{self, TransactionStep};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// Tool-calling rounds before the agent must produce a plan
+const MAX_AGENT_ROUNDS: usize = 8;
+/// Longest tool result fed back to the model
+const MAX_TOOL_RESULT_CHARS: usize = 12_000;
+
+/// MCP tools the planner may call; it never modifies anything itself
+const PLANNER_TOOLS: &[&str] = &[
+    "list_directory",
+    "search_files",
+    "get_file_info",
+    "directory_tree",
+    "get_directory_size",
+    "analyze_directory",
+    "list_allowed_directories",
+];
+
+/// A single file operation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FileOperation {
+    CreateDirectory { path: String },
+    Move { from: String, to: String },
+    Delete { path: String },
+}
+
+impl FileOperation {
+    /// Path the operation reads from or removes
+    pub fn source(&self) -> &str {
+        match self {
+            FileOperation::CreateDirectory { path } | FileOperation::Delete { path } => path,
+            FileOperation::Move { from, .. } => from,
+        }
+    }
+}
+
+/// An operation in a plan, annotated for preview
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedOperation {
+    pub id: String,
+    #[serde(flatten)]
+    pub operation: FileOperation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+    /// Problems found while previewing (missing source, existing destination, ...)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// A previewable list of operations awaiting confirmation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileOperationPlan {
+    pub id: String,
+    pub instruction: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub operations: Vec<PlannedOperation>,
+    pub created_at: i64,
+    /// Guardrail evaluation of the whole plan, set by `preview_plan`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guardrail: Option<GuardrailDecision>,
+}
+
+impl FileOperationPlan {
+    /// Destructive actions of the selected operations (all of them if `selected` is None)
+    pub fn proposed_actions(&self, selected: Option<&[String]>) -> Vec<ProposedAction> {
+        self.operations
+            .iter()
+            .filter(|op| match selected {
+                Some(ids) => ids.contains(&op.id),
+                None => true,
+            })
+            .filter_map(|op| match &op.operation {
+                FileOperation::CreateDirectory { .. } => None,
+                FileOperation::Move { from, to } => Some(ProposedAction::Move {
+                    from: from.clone(),
+                    to: to.clone(),
+                }),
+                FileOperation::Delete { path } => Some(ProposedAction::Delete { path: path.clone() }),
+            })
+            .collect()
+    }
+}
+
+/// Outcome of one executed operation
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationResult {
+    pub id: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of executing a plan; it is applied as a whole or not at all
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanExecutionResult {
+    pub plan_id: String,
+    pub results: Vec<OperationResult>,
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+/// Plans awaiting confirmation, kept in managed state
+#[derive(Default)]
+pub struct PlanStore {
+    plans: Mutex<HashMap<String, FileOperationPlan>>,
+}
+
+impl PlanStore {
+    pub fn insert(&self, plan: FileOperationPlan) {
+        self.plans.lock().unwrap().insert(plan.id.clone(), plan);
+    }
+
+    pub fn get(&self, id: &str) -> Option<FileOperationPlan> {
+        self.plans.lock().unwrap().get(id).cloned()
+    }
+
+    pub fn remove(&self, id: &str) -> Option<FileOperationPlan> {
+        self.plans.lock().unwrap().remove(id)
+    }
+}
+
+/// JSON the model answers with
+#[derive(Debug, Deserialize)]
+pub(crate) struct PlanDraft {
+    #[serde(default)]
+    summary: Option<String>,
+    #[serde(default)]
+    pub(crate) operations: Vec<DraftOperation>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DraftOperation {
+    #[serde(flatten)]
+    pub(crate) operation: FileOperation,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+impl PlanDraft {
+    /// Assign ids to the drafted operations
+    pub(crate) fn into_plan(self, instruction: &str) -> FileOperationPlan {
+        FileOperationPlan {
+            id: uuid::Uuid::new_v4().to_string(),
+            instruction: instruction.to_string(),
+            summary: self.summary,
+            operations: self
+                .operations
+                .into_iter()
+                .map(|d| PlannedOperation {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    operation: d.operation,
+                    reason: d.reason,
+                    size_bytes: None,
+                    warnings: Vec::new(),
+                })
+                .collect(),
+            created_at: chrono::Utc::now().timestamp_millis(),
+            guardrail: None,
+        }
+    }
+}
+
+fn plan_error(message: String) -> AIError {
+    AIError {
+        error_type: AIErrorType::InferenceFailed,
+        message,
+        details: None,
+        suggested_actions: Some(vec![
+            "Rephrase the instruction more specifically".to_string(),
+            "Try a larger model".to_string(),
+        ]),
+    }
+}
+
+pub(crate) fn message(role: MessageRole, content: String) -> ChatMessage {
+    ChatMessage {
+        id: uuid::Uuid::new_v4().to_string(),
+        role,
+        content,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        context_paths: None,
+        is_streaming: None,
+        error: None,
+        tool_calls: None,
+        images: None,
+        interrupted: None,
+    }
+}
+
+fn truncate(mut text: String) -> String {
+    if text.len() > MAX_TOOL_RESULT_CHARS {
+        let mut end = MAX_TOOL_RESULT_CHARS;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("\n... (truncated)");
+    }
+    text
+}
+
+/// Same format the frontend tool loop uses for tool results
+fn format_tool_result(name: &str, result: &MCPResult<String>) -> String {
+    match result {
+        Ok(text) => format!("<tool_result name=\"{}\">\n{}\n</tool_result>", name, text),
+        Err(e) => format!("<tool_result name=\"{}\" error=\"true\">\n{}\n</tool_result>", name, e.message),
+    }
+}
+
+fn planner_tools() -> Vec<Tool> {
+    NativeMCPServer::get_tools()
+        .into_iter()
+        .filter(|t| PLANNER_TOOLS.contains(&t.name.as_str()))
+        .map(|t| Tool {
+            r#type: "function".to_string(),
+            function: ToolFunction {
+                name: t.name,
+                description: t.description,
+                parameters: t.input_schema,
+            },
+        })
+        .collect()
+}
+
+fn to_json<T: Serialize>(value: MCPResult<T>) -> MCPResult<String> {
+    value.and_then(|v| serde_json::to_string_pretty(&v).map_err(Into::into))
+}
+
+/// Run one of the read-only planner tools
+async fn call_planner_tool(server: &NativeMCPServer, name: &str, args: &Value) -> MCPResult<String> {
+    let arg = |key: &str| args.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    let result = match name {
+        "list_directory" => to_json(server.list_directory(arg("path")).await),
+        "search_files" => to_json(server.search_files(arg("directory"), arg("pattern")).await),
+        "get_file_info" => to_json(server.get_file_info(arg("path")).await),
+        "directory_tree" => {
+            let depth = args.get("max_depth").and_then(|v| v.as_u64()).map(|v| v as usize);
+            to_json(server.directory_tree(arg("path"), depth.or(Some(2))).await)
+        }
+        "get_directory_size" => to_json(server.get_directory_size(arg("path")).await),
+        "analyze_directory" => to_json(server.analyze_directory(arg("path")).await),
+        "list_allowed_directories" => to_json(server.list_allowed_directories().await),
+        _ => Err(MCPError {
+            code: -32601,
+            message: format!("Tool not available while planning: {}", name),
+            data: None,
+        }),
+    };
+    result.map(truncate)
+}
+
+fn system_prompt(allowed: &[String], with_tools: bool) -> String {
+    let inspect = if with_tools {
+        "Use the provided tools to inspect the file system before planning. Do not guess paths."
+    } else {
+        "Use the directory listing provided with the instruction. Do not guess paths."
+    };
+    format!(
+        "You plan file operations for Helium, a file manager. You never perform them yourself; \
+the user reviews and confirms the plan first.\n\
+Allowed directories: {}\n\
+Today's date: {}\n\
+{}\n\n\
+When you are done, reply with ONLY a JSON object of this shape:\n\
+{{\"summary\": \"one sentence\", \"operations\": [\n\
+  {{\"type\": \"create_directory\", \"path\": \"/abs/dir\", \"reason\": \"...\"}},\n\
+  {{\"type\": \"move\", \"from\": \"/abs/file\", \"to\": \"/abs/dir/file\", \"reason\": \"...\"}},\n\
+  {{\"type\": \"delete\", \"path\": \"/abs/file\", \"reason\": \"...\"}}\n\
+]}}\n\
+Use absolute paths inside the allowed directories. Move destinations are full target paths, \
+not directories. Only delete when the instruction explicitly asks for it. \
+If nothing matches, return an empty operations list.",
+        allowed.join(", "),
+        chrono::Local::now().format("%Y-%m-%d"),
+        inspect
+    )
+}
+
+/// Extract the JSON plan from a model reply (which may be wrapped in prose or a code fence)
+pub(crate) fn parse_plan(content: &str) -> Result<PlanDraft, AIError> {
+    let start = content.find('{');
+    let end = content.rfind('}');
+    let json = match (start, end) {
+        (Some(s), Some(e)) if e > s => &content[s..=e],
+        _ => return Err(plan_error("The model did not return a plan".to_string())),
+    };
+    serde_json::from_str(json).map_err(|e| plan_error(format!("The model returned an invalid plan: {}", e)))
+}
+
+/// Ask the agent for a plan, letting it inspect the file system through MCP
+pub async fn generate_plan(
+    provider: &dyn AIProvider,
+    model_config: &ModelConfig,
+    instruction: &str,
+    server: &NativeMCPServer,
+) -> Result<FileOperationPlan, AIError> {
+    let allowed = server.list_allowed_directories().await.unwrap_or_default();
+    let with_tools = provider.supports_tools();
+
+    let mut messages = vec![message(MessageRole::System, system_prompt(&allowed, with_tools))];
+    if with_tools {
+        messages.push(message(MessageRole::User, instruction.to_string()));
+    } else {
+        // Without native tools, give the model a shallow view of each root up front
+        let mut context = String::new();
+        for dir in &allowed {
+            let tree = call_planner_tool(server, "directory_tree", &serde_json::json!({ "path": dir, "max_depth": 2 })).await;
+            context.push_str(&format_tool_result("directory_tree", &tree));
+            context.push('\n');
+        }
+        messages.push(message(MessageRole::User, format!("{}\n\nInstruction: {}", context, instruction)));
+    }
+
+    let mut model_config = model_config.clone();
+    model_config.parameters.temperature = 0.1;
+
+    for round in 0..MAX_AGENT_ROUNDS {
+        let request = InferenceRequest {
+            session_id: format!("file-plan-{}", uuid::Uuid::new_v4()),
+            model_config: model_config.clone(),
+            messages: messages.clone(),
+            fs_context: None,
+            mode: AIMode::Agent,
+            tools: with_tools.then(planner_tools),
+            preset_id: None,
+            conversation_id: None,
+        };
+        let response = provider
+            .infer_stream(&request, InferenceSink::silent(), CancellationToken::new())
+            .await?;
+
+        let tool_calls = response.message.tool_calls.clone().unwrap_or_default();
+        if tool_calls.is_empty() {
+            let draft = parse_plan(&response.message.content)?;
+            info!("Plan with {} operations after {} rounds", draft.operations.len(), round + 1);
+            return Ok(draft.into_plan(instruction));
+        }
+
+        messages.push(response.message);
+        for call in tool_calls {
+            debug!("Tool call: {}({})", call.function.name, call.function.arguments);
+            let args: Value = serde_json::from_str(&call.function.arguments).unwrap_or(Value::Null);
+            let result = call_planner_tool(server, &call.function.name, &args).await;
+            messages.push(message(MessageRole::User, format_tool_result(&call.function.name, &result)));
+        }
+    }
+
+    Err(plan_error(format!("No plan after {} tool-calling rounds", MAX_AGENT_ROUNDS)))
+}
+
+/// Annotate each operation with its size and any problems it would hit
+pub async fn preview_plan(plan: &mut FileOperationPlan, server: &NativeMCPServer) {
+    for op in &mut plan.operations {
+        op.warnings.clear();
+        let source = Path::new(op.operation.source());
+
+        if !server.is_path_allowed(source).await {
+            op.warnings.push("Outside the allowed directories".to_string());
+        }
+
+        match &op.operation {
+            FileOperation::CreateDirectory { path } => {
+                if Path::new(path).exists() {
+                    op.warnings.push("Directory already exists".to_string());
+                }
+            }
+            FileOperation::Move { from, to } => {
+                if !Path::new(from).exists() {
+                    op.warnings.push("Source does not exist".to_string());
+                }
+                if Path::new(to).exists() {
+                    op.warnings.push("Destination already exists and will not be overwritten".to_string());
+                }
+                if !server.is_path_allowed(Path::new(to)).await {
+                    op.warnings.push("Destination is outside the allowed directories".to_string());
+                }
+            }
+            FileOperation::Delete { path } => {
+                if !Path::new(path).exists() {
+                    op.warnings.push("Path does not exist".to_string());
+                }
+            }
+        }
+
+        op.size_bytes = match &op.operation {
+            FileOperation::CreateDirectory { .. } => None,
+            _ if source.is_dir() => server
+                .get_directory_size(source.to_string_lossy().to_string())
+                .await
+                .ok()
+                .map(|s| s.total_bytes),
+            _ => std::fs::metadata(source).ok().map(|m| m.len()),
+        };
+    }
+
+    plan.guardrail = Some(server.evaluate_guardrails(&plan.proposed_actions(None)).await);
+}
+
+impl FileOperation {
+    /// Transaction step performing the operation; deletions go to quarantine
+    fn step(&self) -> TransactionStep {
+        match self.clone() {
+            FileOperation::CreateDirectory { path } => TransactionStep::CreateDirectory { path },
+            FileOperation::Move { from, to } => TransactionStep::Move { from, to },
+            FileOperation::Delete { path } => TransactionStep::Quarantine { path },
+        }
+    }
+}
+
+/// Why `op` may not run, checked against the MCP sandbox
+async fn access_error(server: &NativeMCPServer, op: &FileOperation) -> Option<String> {
+    let mut touched = vec![op.source()];
+    if let FileOperation::Move { to, .. } = op {
+        touched.push(to);
+    }
+    for path in touched {
+        if !server.is_path_allowed(Path::new(path)).await {
+            return Some(format!("Access denied: {} is not in allowed directories", path));
+        }
+    }
+    if let FileOperation::Delete { path } = op {
+        let roots = server.list_allowed_directories().await.unwrap_or_default();
+        if roots.iter().any(|root| paths::same(Path::new(root), Path::new(path))) {
+            return Some("Cannot delete an allowed root directory".to_string());
+        }
+    }
+    None
+}
+
+/// Execute the selected operations of a plan (all of them if `selected` is
+/// None) as one transaction journaled in `journal_dir`: either every
+/// operation is applied, or none is. Deleted items end up in quarantine.
+pub async fn execute_plan(
+    app: &AppHandle,
+    plan: &FileOperationPlan,
+    selected: Option<&[String]>,
+    server: &NativeMCPServer,
+    journal_dir: PathBuf,
+) -> PlanExecutionResult {
+    let ops: Vec<&PlannedOperation> = plan
+        .operations
+        .iter()
+        .filter(|op| match selected {
+            Some(ids) => ids.contains(&op.id),
+            None => true,
+        })
+        .collect();
+
+    // Index of the operation at fault (if known) and the error
+    let mut failure: Option<(Option<usize>, String)> = None;
+    for (index, op) in ops.iter().enumerate() {
+        if let Some(error) = access_error(server, &op.operation).await {
+            failure = Some((Some(index), error));
+            break;
+        }
+    }
+
+    if failure.is_none() {
+        let steps: Vec<TransactionStep> = ops.iter().map(|op| op.operation.step()).collect();
+        let reasons: HashMap<String, String> = ops
+            .iter()
+            .map(|op| (op.operation.source().to_string(), op.reason.clone().unwrap_or_else(|| plan.instruction.clone())))
+            .collect();
+        let app = app.clone();
+        let outcome = tokio::task::spawn_blocking(move || {
+            let result = transactions::run(steps, &journal_dir, &AtomicBool::new(false))?;
+            if result.held.is_empty() {
+                return Ok(result);
+            }
+            let store = app.state::<QuarantineStore>();
+            for item in &result.held {
+                match store.quarantine_moved(&item.original, Path::new(&item.parked), "ai", reasons.get(&item.original).cloned()) {
+                    Ok(_) => transactions::release(item),
+                    Err(e) => warn!("Could not quarantine {}, it stays in {}: {}", item.original, item.parked, e),
+                }
+            }
+            let _ = app.emit(QUARANTINE_EVENT, &store.summary());
+            Ok(result)
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+        failure = match outcome {
+            Ok(result) if result.committed => None,
+            Ok(result) => Some((result.failed_step, result.error.unwrap_or_default())),
+            Err(e) => Some((None, e)),
+        };
+    }
+    if let Some((_, error)) = &failure {
+        warn!("Plan {} not applied: {}", plan.id, error);
+    }
+
+    let results: Vec<OperationResult> = ops
+        .iter()
+        .enumerate()
+        .map(|(index, op)| OperationResult {
+            id: op.id.clone(),
+            success: failure.is_none(),
+            error: failure.as_ref().map(|(at, error)| match at {
+                Some(at) if *at != index => "Not applied: another operation failed".to_string(),
+                _ => error.clone(),
+            }),
+        })
+        .collect();
+
+    let succeeded = results.iter().filter(|r| r.success).count();
+    PlanExecutionResult {
+        plan_id: plan.id.clone(),
+        failed: results.len() - succeeded,
+        succeeded,
+        results,
+    }
+}
@@ -0,0 +1,294 @@
+// HTTP - Shared retry and error mapping for network calls
+//
+// Remote providers and model downloads go through these helpers so transient
+// failures (timeouts, rate limits, 5xx responses, dropped connections) are
+// retried with jittered exponential backoff, and every failure is reported as
+// an AIError with a specific type and consistent suggested actions.
+
+use crate::ai::{AIError, AIErrorType};
+use crate::ai::bandwidth::DownloadProgress;
+use hf_hub::api::tokio::{Api, ApiError};
+use hf_hub::{Cache, Repo};
+use reqwest::StatusCode;
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::warn;
+
+/// Longest Retry-After we are willing to honour
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// How many times and how quickly to retry a transient failure
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy for large downloads, which are worth waiting longer for
+    pub fn download() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+
+    /// Exponential backoff with jitter for the given (1-based) attempt
+    fn delay(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(1u32 << attempt.saturating_sub(1).min(16))
+            .min(self.max_delay);
+        exp.mul_f64(jitter())
+    }
+}
+
+/// Pseudo-random factor in [0.5, 1.0) so concurrent retries don't line up
+fn jitter() -> f64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    0.5 + (nanos % 1000) as f64 / 2000.0
+}
+
+fn error(error_type: AIErrorType, message: String, actions: &[&str]) -> AIError {
+    AIError {
+        error_type,
+        message,
+        details: None,
+        suggested_actions: if actions.is_empty() {
+            None
+        } else {
+            Some(actions.iter().map(|a| a.to_string()).collect())
+        },
+    }
+}
+
+/// Whether retrying the operation might succeed
+pub fn is_transient(e: &AIError) -> bool {
+    matches!(
+        e.error_type,
+        AIErrorType::NetworkError | AIErrorType::Timeout | AIErrorType::RateLimited | AIErrorType::ServerError
+    )
+}
+
+fn retry_after(e: &AIError) -> Option<Duration> {
+    let secs = e.details.as_ref()?.get("retryAfterSecs")?.as_u64()?;
+    Some(Duration::from_secs(secs).min(MAX_RETRY_AFTER))
+}
+
+/// Classify an error from its message (for errors that only expose text).
+///
+/// Only transport failures are recognised here; HTTP statuses are mapped from
+/// the status code itself by `map_status_error`.
+pub fn map_error_message(context: &str, message: &str) -> AIError {
+    let lower = message.to_lowercase();
+    let full = format!("{}: {}", context, message);
+
+    if lower.contains("dns error")
+        || lower.contains("failed to lookup address")
+        || lower.contains("name or service not known")
+        || lower.contains("nodename nor servname")
+        || lower.contains("no such host")
+    {
+        error(
+            AIErrorType::DnsFailure,
+            full,
+            &["Check the host name in the endpoint URL", "Check your internet connection"],
+        )
+    } else if lower.contains("timed out") || lower.contains("timeout") {
+        error(
+            AIErrorType::Timeout,
+            full,
+            &["Check your internet connection", "Try again; the server may be busy"],
+        )
+    } else {
+        error(
+            AIErrorType::NetworkError,
+            full,
+            &["Check your internet connection", "Verify the server is running"],
+        )
+    }
+}
+
+/// An error's message followed by those of its sources.
+///
+/// reqwest hides the interesting detail (e.g. the DNS failure) in the source chain.
+fn error_chain(e: &dyn std::error::Error) -> String {
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(s) = source {
+        message.push_str(": ");
+        message.push_str(&s.to_string());
+        source = s.source();
+    }
+    message
+}
+
+/// Map a request that failed before a response was received
+pub fn map_request_error(context: &str, e: &reqwest::Error) -> AIError {
+    let message = error_chain(e);
+
+    if let Some(status) = e.status() {
+        return map_status_error(context, status, None, &message);
+    }
+    if e.is_timeout() {
+        return error(
+            AIErrorType::Timeout,
+            format!("{}: {}", context, message),
+            &["Check your internet connection", "Try again; the server may be busy"],
+        );
+    }
+    map_error_message(context, &message)
+}
+
+/// Map an unsuccessful HTTP response
+pub fn map_status_error(context: &str, status: StatusCode, retry_after: Option<u64>, body: &str) -> AIError {
+    let message = format!("{}: {} - {}", context, status, body);
+    let mut e = match status {
+        StatusCode::TOO_MANY_REQUESTS => error(
+            AIErrorType::RateLimited,
+            message,
+            &["Wait a moment and try again", "Check the rate limits of your plan"],
+        ),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => error(
+            AIErrorType::AuthenticationFailed,
+            message,
+            &["Check the API key", "Verify the key has access to this model"],
+        ),
+        StatusCode::NOT_FOUND => error(
+            AIErrorType::ModelNotFound,
+            message,
+            &["Verify the model name", "Check the endpoint URL"],
+        ),
+        StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => error(
+            AIErrorType::Timeout,
+            message,
+            &["Try again; the server may be busy"],
+        ),
+        s if s.is_server_error() => error(
+            AIErrorType::ServerError,
+            message,
+            &["The server had a problem; try again later"],
+        ),
+        _ => error(
+            AIErrorType::InferenceFailed,
+            message,
+            &["Check the request parameters", "Verify model name"],
+        ),
+    };
+    if let Some(secs) = retry_after {
+        e.details = Some(serde_json::json!({ "retryAfterSecs": secs }));
+    }
+    e
+}
+
+/// Map an hf-hub error, using the HTTP status when the hub returned one
+fn map_hf_error(context: &str, e: &ApiError) -> AIError {
+    match e {
+        ApiError::TooManyRetries(inner) => map_hf_error(context, inner),
+        // hf-hub uses its own reqwest, so go through the numeric code
+        ApiError::RequestError(request) => match request
+            .status()
+            .and_then(|s| StatusCode::from_u16(s.as_u16()).ok())
+        {
+            Some(status) => map_status_error(context, status, None, &request.to_string()),
+            None if request.is_timeout() => error(
+                AIErrorType::Timeout,
+                format!("{}: {}", context, request),
+                &["Check your internet connection", "Try again; the server may be busy"],
+            ),
+            None => map_error_message(context, &error_chain(request)),
+        },
+        other => map_error_message(context, &error_chain(other)),
+    }
+}
+
+/// Run `op` until it succeeds, fails permanently, or runs out of attempts
+pub async fn with_retry<T, F, Fut>(label: &str, policy: &RetryPolicy, mut op: F) -> Result<T, AIError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, AIError>>,
+{
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < policy.max_attempts && is_transient(&e) => {
+                let delay = retry_after(&e).unwrap_or_else(|| policy.delay(attempt));
+                warn!(
+                    "{} failed (attempt {}/{}): {}; retrying in {:?}",
+                    label, attempt, policy.max_attempts, e.message, delay
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Send a request, retrying transient failures; non-2xx responses become errors.
+///
+/// `build` is called once per attempt since a RequestBuilder can't be reused.
+pub async fn send_with_retry<F>(label: &str, policy: &RetryPolicy, build: F) -> Result<reqwest::Response, AIError>
+where
+    F: Fn() -> reqwest::RequestBuilder,
+{
+    with_retry(label, policy, || {
+        let request = build();
+        async move {
+            let response = request.send().await.map_err(|e| map_request_error(label, &e))?;
+            let status = response.status();
+            if status.is_success() {
+                return Ok(response);
+            }
+
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok());
+            let body = response.text().await.unwrap_or_default();
+            Err(map_status_error(label, status, retry_after, &body))
+        }
+    })
+    .await
+}
+
+/// Fetch a file from a HuggingFace repo (cached by hf-hub), retrying transient
+/// failures. Downloads honour the bandwidth cap and report through `progress`.
+pub async fn fetch_hf_file(
+    api: &Api,
+    repo: &Repo,
+    file: &str,
+    progress: DownloadProgress,
+) -> Result<PathBuf, AIError> {
+    if let Some(path) = Cache::from_env().repo(repo.clone()).get(file) {
+        return Ok(path);
+    }
+
+    let label = format!("Download of {}", file);
+    let api_repo = api.repo(repo.clone());
+    with_retry(&label, &RetryPolicy::download(), || async {
+        api_repo
+            .download_with_progress(file, progress.clone())
+            .await
+            .map_err(|e| map_hf_error(&label, &e))
+    })
+    .await
+}
@@ -0,0 +1,96 @@
+// Image Attachments - Multimodal message support
+//
+// Resolves image attachments on chat messages (a file on disk or inline base64
+// data) into the base64 payload and MIME type that vision-capable providers
+// expect.
+
+use crate::ai::{AIError, AIErrorType};
+use crate::content_type;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Largest image we will send to a provider
+const MAX_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Image formats providers accept, identified by content
+const SUPPORTED_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp", "image/bmp"];
+
+/// Image attached to a chat message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageAttachment {
+    /// Path of an image on disk
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Inline base64 data (without a data: URL prefix)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// Image ready to send: MIME type and base64 payload
+pub struct ResolvedImage {
+    pub mime_type: String,
+    pub base64: String,
+}
+
+impl ResolvedImage {
+    /// `data:` URL form used by OpenAI-compatible APIs
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.mime_type, self.base64)
+    }
+}
+
+fn image_error(message: String) -> AIError {
+    AIError {
+        error_type: AIErrorType::InvalidConfiguration,
+        message,
+        details: None,
+        suggested_actions: Some(vec!["Attach a PNG, JPEG, GIF or WebP image".to_string()]),
+    }
+}
+
+impl ImageAttachment {
+    /// Load the attachment into memory as base64
+    pub fn resolve(&self) -> Result<ResolvedImage, AIError> {
+        if let Some(data) = &self.data {
+            return Ok(ResolvedImage {
+                mime_type: self.mime_type.clone().unwrap_or_else(|| "image/png".to_string()),
+                base64: data.clone(),
+            });
+        }
+
+        let path_str = self
+            .path
+            .as_ref()
+            .ok_or_else(|| image_error("Image attachment has neither a path nor data".to_string()))?;
+        let path = Path::new(path_str);
+
+        let mime_type = match &self.mime_type {
+            Some(mime) => mime.clone(),
+            None => content_type::detect(path)
+                .ok()
+                .filter(|t| SUPPORTED_MIME_TYPES.contains(&t.mime.as_str()))
+                .ok_or_else(|| image_error(format!("Not a supported image: {}", path_str)))?
+                .mime,
+        };
+
+        let size = std::fs::metadata(path)
+            .map_err(|e| image_error(format!("Cannot read {}: {}", path_str, e)))?
+            .len();
+        if size > MAX_IMAGE_BYTES {
+            return Err(image_error(format!(
+                "Image too large: {} bytes (max: {} bytes)",
+                size, MAX_IMAGE_BYTES
+            )));
+        }
+
+        let bytes = std::fs::read(path).map_err(|e| image_error(format!("Cannot read {}: {}", path_str, e)))?;
+        Ok(ResolvedImage {
+            mime_type,
+            base64: base64::engine::general_purpose::STANDARD.encode(bytes),
+        })
+    }
+}
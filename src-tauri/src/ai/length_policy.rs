@@ -0,0 +1,106 @@
+// Length Policy - How long a generation may run
+//
+// Caps max_tokens by the kind of request (short answers for quick questions,
+// long ones for code, plans and agent work) and bounds every generation by a
+// wall-clock limit, so a small local model can't ramble for minutes.
+
+use crate::ai::{AIMode, InferenceRequest, MessageRole};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+
+/// Words in a question that ask for long-form output
+const LONG_FORM_HINTS: &[&str] = &[
+    "code", "script", "function", "program", "plan", "step by step", "steps", "explain in detail",
+    "write", "implement", "generate", "list all", "```",
+];
+
+/// Configurable generation limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LengthPolicy {
+    /// Token cap for quick questions
+    pub short_max_tokens: u32,
+    /// Token cap for code, plans and agent mode
+    pub long_max_tokens: u32,
+    /// Wall-clock limit for one generation, in seconds
+    pub max_generation_secs: u64,
+}
+
+impl Default for LengthPolicy {
+    fn default() -> Self {
+        Self {
+            short_max_tokens: 512,
+            long_max_tokens: 2048,
+            max_generation_secs: 120,
+        }
+    }
+}
+
+impl LengthPolicy {
+    /// Whether the request calls for a long answer
+    fn is_long_form(request: &InferenceRequest) -> bool {
+        if request.mode == AIMode::Agent || request.tools.is_some() {
+            return true;
+        }
+        request
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == MessageRole::User)
+            .is_some_and(|m| {
+                let text = m.content.to_lowercase();
+                LONG_FORM_HINTS.iter().any(|hint| text.contains(hint))
+            })
+    }
+
+    /// Lower the request's max_tokens to the cap for its kind
+    pub fn apply(&self, request: &mut InferenceRequest) {
+        let cap = if Self::is_long_form(request) {
+            self.long_max_tokens
+        } else {
+            self.short_max_tokens
+        };
+        let max_tokens = &mut request.model_config.parameters.max_tokens;
+        if *max_tokens > cap {
+            info!("Capping max_tokens {} -> {} by length policy", max_tokens, cap);
+            *max_tokens = cap;
+        }
+    }
+
+    pub fn max_duration(&self) -> Duration {
+        Duration::from_secs(self.max_generation_secs)
+    }
+}
+
+/// Length policy persisted in the app data directory
+pub struct LengthPolicyStore {
+    path: PathBuf,
+    policy: Mutex<LengthPolicy>,
+}
+
+impl LengthPolicyStore {
+    pub fn load(path: PathBuf) -> Self {
+        let policy = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        Self {
+            path,
+            policy: Mutex::new(policy),
+        }
+    }
+
+    pub fn get(&self) -> LengthPolicy {
+        self.policy.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, policy: LengthPolicy) -> Result<LengthPolicy, String> {
+        if policy.short_max_tokens == 0 || policy.long_max_tokens == 0 || policy.max_generation_secs == 0 {
+            return Err("Generation limits must be greater than zero".to_string());
+        }
+        storage::save_json(&self.path, &policy)?;
+        *self.policy.lock().unwrap() = policy.clone();
+        Ok(policy)
+    }
+}
@@ -0,0 +1,156 @@
+// LoRA Adapters - Fine-tuned variants of embedded models
+//
+// Loads PEFT-format LoRA adapters (adapter_config.json + adapter_model.safetensors)
+// from a local directory or a HuggingFace repo and merges them into the base
+// model's weights at load time (W' = W + alpha/r * B·A), so generation runs at
+// the same speed as the base model.
+
+use crate::ai::bandwidth::DownloadProgress;
+use crate::ai::http::fetch_hf_file;
+use crate::ai::{AIError, AIErrorType};
+use candle_core::{DType, Device, Tensor};
+use crate::ai::secrets;
+use hf_hub::{Repo, RepoType};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+const ADAPTER_CONFIG_FILE: &str = "adapter_config.json";
+const ADAPTER_WEIGHTS_FILE: &str = "adapter_model.safetensors";
+
+/// Prefix PEFT adds in front of the base model's tensor names
+const PEFT_PREFIX: &str = "base_model.model.";
+
+/// Subset of PEFT's adapter_config.json needed for merging
+#[derive(Debug, Deserialize)]
+struct AdapterConfig {
+    r: f64,
+    lora_alpha: f64,
+    #[serde(default)]
+    base_model_name_or_path: Option<String>,
+}
+
+/// Files making up a LoRA adapter
+pub struct AdapterFiles {
+    pub config: PathBuf,
+    pub weights: PathBuf,
+}
+
+fn adapter_error(message: String) -> AIError {
+    AIError {
+        error_type: AIErrorType::InvalidConfiguration,
+        message,
+        details: None,
+        suggested_actions: Some(vec![
+            "Check that the adapter was trained for the selected base model".to_string(),
+        ]),
+    }
+}
+
+/// Locate an adapter given either a local directory or a HuggingFace repo id
+pub async fn resolve_adapter(adapter: &str) -> Result<AdapterFiles, AIError> {
+    let local = Path::new(adapter);
+    if local.is_dir() {
+        let files = AdapterFiles {
+            config: local.join(ADAPTER_CONFIG_FILE),
+            weights: local.join(ADAPTER_WEIGHTS_FILE),
+        };
+        if !files.config.exists() || !files.weights.exists() {
+            return Err(adapter_error(format!(
+                "{} must contain {} and {}",
+                adapter, ADAPTER_CONFIG_FILE, ADAPTER_WEIGHTS_FILE
+            )));
+        }
+        return Ok(files);
+    }
+
+    debug!("Fetching adapter from HuggingFace: {}", adapter);
+    let api = secrets::hf_api()?;
+    let repo = Repo::new(adapter.to_string(), RepoType::Model);
+
+    let fetch = |file: &'static str| {
+        let (api, repo) = (&api, &repo);
+        async move {
+            let progress = DownloadProgress::new(None, file, (0.0, 1.0));
+            fetch_hf_file(api, repo, file, progress).await.map_err(|mut e| {
+                if e.error_type == AIErrorType::ModelNotFound || e.error_type == AIErrorType::NetworkError {
+                    e.suggested_actions
+                        .get_or_insert_with(Vec::new)
+                        .push("Check the adapter repo id".to_string());
+                }
+                e
+            })
+        }
+    };
+
+    Ok(AdapterFiles {
+        config: fetch(ADAPTER_CONFIG_FILE).await?,
+        weights: fetch(ADAPTER_WEIGHTS_FILE).await?,
+    })
+}
+
+/// Load the base weights and merge the adapter's low-rank deltas into them
+pub fn merge_adapter(
+    base_paths: &[PathBuf],
+    adapter: &AdapterFiles,
+    device: &Device,
+) -> Result<HashMap<String, Tensor>, AIError> {
+    let config_str = std::fs::read_to_string(&adapter.config)
+        .map_err(|e| adapter_error(format!("Failed to read adapter config: {}", e)))?;
+    let config: AdapterConfig = serde_json::from_str(&config_str)
+        .map_err(|e| adapter_error(format!("Invalid adapter config: {}", e)))?;
+    if config.r <= 0.0 {
+        return Err(adapter_error("Adapter rank must be positive".to_string()));
+    }
+    let scale = config.lora_alpha / config.r;
+
+    if let Some(base) = &config.base_model_name_or_path {
+        info!("Adapter was trained on {}", base);
+    }
+
+    let mut weights: HashMap<String, Tensor> = HashMap::new();
+    for path in base_paths {
+        let tensors = candle_core::safetensors::load(path, device)
+            .map_err(|e| adapter_error(format!("Failed to load base weights: {}", e)))?;
+        weights.extend(tensors);
+    }
+
+    let lora = candle_core::safetensors::load(&adapter.weights, device)
+        .map_err(|e| adapter_error(format!("Failed to load adapter weights: {}", e)))?;
+
+    let mut merged = 0;
+    for (name, lora_a) in &lora {
+        let Some(module) = name.strip_suffix(".lora_A.weight") else {
+            continue;
+        };
+        let lora_b = lora
+            .get(&format!("{}.lora_B.weight", module))
+            .ok_or_else(|| adapter_error(format!("Missing lora_B for {}", module)))?;
+
+        let target = format!("{}.weight", module.strip_prefix(PEFT_PREFIX).unwrap_or(module));
+        let base = weights
+            .get(&target)
+            .ok_or_else(|| adapter_error(format!("Adapter targets unknown tensor {}", target)))?;
+
+        let merge = || -> candle_core::Result<Tensor> {
+            let delta = lora_b
+                .to_dtype(DType::F32)?
+                .matmul(&lora_a.to_dtype(DType::F32)?)?
+                .affine(scale, 0.0)?;
+            base.to_dtype(DType::F32)?.add(&delta)
+        };
+        let updated = merge()
+            .map_err(|e| adapter_error(format!("Failed to merge {}: {}", target, e)))?;
+
+        weights.insert(target, updated);
+        merged += 1;
+    }
+
+    if merged == 0 {
+        return Err(adapter_error("Adapter contains no LoRA weights".to_string()));
+    }
+
+    info!("Merged {} adapter tensors (scale {:.3})", merged, scale);
+    Ok(weights)
+}
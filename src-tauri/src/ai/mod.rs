@@ -18,6 +18,25 @@ pub enum ModelProvider {
     Candle,
 }
 
+impl ModelProvider {
+    /// Identifier used by the frontend (matches the serde representation)
+    pub fn id(&self) -> &'static str {
+        match self {
+            ModelProvider::TransformerJS => "transformerjs",
+            ModelProvider::Ollama => "ollama",
+            ModelProvider::OpenAICompatible => "openai-compatible",
+            ModelProvider::LlamaCpp => "llamacpp",
+            ModelProvider::MLX => "mlx",
+            ModelProvider::Candle => "candle",
+        }
+    }
+
+    /// Parse a frontend provider identifier
+    pub fn from_id(id: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(id.to_string())).ok()
+    }
+}
+
 /// AI operation modes
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -52,6 +71,9 @@ pub struct ModelConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub size_bytes: Option<u64>,
     pub recommended_for: Vec<AIMode>,
+    /// LoRA adapter (local directory or HF repo id) merged over an embedded model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adapter: Option<String>,
 }
 
 /// Model inference parameters
@@ -85,6 +107,12 @@ pub struct ChatMessage {
     /// Tool calls in OpenAI format (for native function calling)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<OpenAIToolCall>>,
+    /// Images attached to the message (for vision-capable models)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub images: Option<Vec<images::ImageAttachment>>,
+    /// Generation stopped (cancelled or app quit) before the response was complete
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interrupted: Option<bool>,
 }
 
 /// Inference request
@@ -100,6 +128,12 @@ pub struct InferenceRequest {
     /// Optional tools for native function calling (OpenAI format)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<Tool>>,
+    /// Prompt preset whose system prompt is prepended to the conversation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset_id: Option<String>,
+    /// Saved conversation whose pinned settings override the request defaults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
 }
 
 /// File system context
@@ -214,7 +248,7 @@ pub struct ProviderStatus {
 }
 
 /// AI error types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum AIErrorType {
     ModelNotFound,
@@ -224,6 +258,12 @@ pub enum AIErrorType {
     NetworkError,
     InvalidConfiguration,
     ContextTooLarge,
+    BudgetExceeded,
+    DnsFailure,
+    Timeout,
+    RateLimited,
+    ServerError,
+    AuthenticationFailed,
 }
 
 /// AI error
@@ -246,4 +286,25 @@ impl std::fmt::Display for AIError {
 
 impl std::error::Error for AIError {}
 
+pub mod accelerators;
+pub mod agent_runs;
+pub mod bandwidth;
+pub mod context;
+pub mod conversations;
+pub mod export;
+pub mod file_plan;
+pub mod http;
+pub mod images;
+pub mod length_policy;
+pub mod lora;
+pub mod model_pool;
+pub mod organizer;
+pub mod presets;
 pub mod providers;
+pub mod recommend;
+pub mod scheduler;
+pub mod secrets;
+pub mod summarize;
+pub mod tokenizer_cache;
+pub mod usage;
+pub mod whisper;
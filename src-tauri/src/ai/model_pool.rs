@@ -0,0 +1,165 @@
+// Model Pool - Embedded models kept resident in memory
+//
+// Holds several loaded Candle models at once so that a small model can serve
+// quick background tasks (titles, classification) while a larger one answers
+// chat, without reloading weights on every request. Total resident size is
+// bounded by a configurable RAM budget; the least recently used idle model is
+// evicted when a new one would not fit.
+
+use candle_core::Device;
+use candle_transformers::models::qwen2::Model as QwenModel;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokenizers::Tokenizer;
+use tracing::{info, warn};
+
+/// Fallback budget when total system memory cannot be determined
+const DEFAULT_BUDGET_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+/// A model resident in memory, ready for generation
+pub struct LoadedModel {
+    pub model: QwenModel,
+    pub tokenizer: Arc<Tokenizer>,
+    pub device: Device,
+    /// Tokens whose keys/values are currently held in the model's KV cache
+    pub cached_tokens: Vec<u32>,
+}
+
+struct PoolEntry {
+    model: Arc<tokio::sync::Mutex<LoadedModel>>,
+    size_bytes: u64,
+    loaded_at: chrono::DateTime<chrono::Utc>,
+    last_used: Instant,
+}
+
+/// Summary of a resident model, for the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedModelInfo {
+    pub model_id: String,
+    pub size_bytes: u64,
+    pub loaded_at: i64,
+    pub idle_secs: u64,
+    pub in_use: bool,
+}
+
+/// Pool of resident models with LRU eviction under a RAM budget
+pub struct ModelPool {
+    entries: HashMap<String, PoolEntry>,
+    budget_bytes: u64,
+}
+
+lazy_static! {
+    pub static ref MODEL_POOL: Mutex<ModelPool> = Mutex::new(ModelPool::new(default_budget()));
+}
+
+/// Half of physical memory, so the pool never competes with the rest of the system
+pub fn default_budget() -> u64 {
+    let mut sys = sysinfo::System::new();
+    sys.refresh_memory();
+    match sys.total_memory() {
+        0 => DEFAULT_BUDGET_BYTES,
+        total => total / 2,
+    }
+}
+
+impl ModelPool {
+    pub fn new(budget_bytes: u64) -> Self {
+        Self {
+            entries: HashMap::new(),
+            budget_bytes,
+        }
+    }
+
+    /// Fetch a resident model, marking it as most recently used
+    pub fn get(&mut self, model_id: &str) -> Option<Arc<tokio::sync::Mutex<LoadedModel>>> {
+        self.entries.get_mut(model_id).map(|entry| {
+            entry.last_used = Instant::now();
+            entry.model.clone()
+        })
+    }
+
+    /// Add a freshly loaded model, evicting idle models until it fits
+    pub fn insert(
+        &mut self,
+        model_id: &str,
+        model: LoadedModel,
+        size_bytes: u64,
+    ) -> Arc<tokio::sync::Mutex<LoadedModel>> {
+        self.make_room(size_bytes);
+
+        let model = Arc::new(tokio::sync::Mutex::new(model));
+        self.entries.insert(
+            model_id.to_string(),
+            PoolEntry {
+                model: model.clone(),
+                size_bytes,
+                loaded_at: chrono::Utc::now(),
+                last_used: Instant::now(),
+            },
+        );
+        model
+    }
+
+    /// Drop a model from the pool. Returns false if it was not resident.
+    pub fn unload(&mut self, model_id: &str) -> bool {
+        self.entries.remove(model_id).is_some()
+    }
+
+    /// Change the RAM budget, evicting idle models if now over it
+    pub fn set_budget(&mut self, budget_bytes: u64) {
+        self.budget_bytes = budget_bytes;
+        self.make_room(0);
+    }
+
+    pub fn budget(&self) -> u64 {
+        self.budget_bytes
+    }
+
+    pub fn resident_bytes(&self) -> u64 {
+        self.entries.values().map(|e| e.size_bytes).sum()
+    }
+
+    pub fn list(&self) -> Vec<LoadedModelInfo> {
+        let mut infos: Vec<LoadedModelInfo> = self
+            .entries
+            .iter()
+            .map(|(id, entry)| LoadedModelInfo {
+                model_id: id.clone(),
+                size_bytes: entry.size_bytes,
+                loaded_at: entry.loaded_at.timestamp_millis(),
+                idle_secs: entry.last_used.elapsed().as_secs(),
+                in_use: Arc::strong_count(&entry.model) > 1,
+            })
+            .collect();
+        infos.sort_by(|a, b| a.idle_secs.cmp(&b.idle_secs));
+        infos
+    }
+
+    /// Evict least recently used idle models until `incoming` more bytes fit.
+    /// Models currently running a generation are never evicted.
+    fn make_room(&mut self, incoming: u64) {
+        while self.resident_bytes() + incoming > self.budget_bytes {
+            let victim = self
+                .entries
+                .iter()
+                .filter(|(_, e)| Arc::strong_count(&e.model) == 1)
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(id, _)| id.clone());
+
+            match victim {
+                Some(id) => {
+                    info!("Evicting {} to stay within RAM budget", id);
+                    self.entries.remove(&id);
+                }
+                None => {
+                    warn!("Over RAM budget but all resident models are in use");
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+// Organizer - AI-assisted folder structure suggestions
+//
+// Samples a directory's contents (names, types, sizes, dates), asks the model
+// to propose a folder structure and the moves that get there, and returns the
+// result as a file plan the user can apply selectively with
+// `execute_file_plan`.
+
+use crate::ai::file_plan::{self, FileOperation, FileOperationPlan};
+use crate::ai::providers::{infer_once, AIProvider};
+use crate::ai::{AIError, AIErrorType, AIMode, InferenceRequest, MessageRole, ModelConfig};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use tracing::info;
+
+/// Entries sampled from the directory and shown to the model
+const MAX_SAMPLE_ENTRIES: usize = 300;
+
+/// One sampled entry
+struct SampledEntry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<String>,
+}
+
+fn sample_directory(path: &Path) -> Result<Vec<SampledEntry>, AIError> {
+    let entries = std::fs::read_dir(path).map_err(|e| AIError {
+        error_type: AIErrorType::InvalidConfiguration,
+        message: format!("Cannot read {}: {}", path.display(), e),
+        details: None,
+        suggested_actions: None,
+    })?;
+
+    let mut sampled: Vec<SampledEntry> = entries
+        .flatten()
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| {
+            let metadata = e.metadata().ok()?;
+            let modified = metadata
+                .modified()
+                .ok()
+                .map(|t| chrono::DateTime::<chrono::Local>::from(t).format("%Y-%m-%d").to_string());
+            Some(SampledEntry {
+                name: e.file_name().to_string_lossy().to_string(),
+                is_dir: metadata.is_dir(),
+                size: metadata.len(),
+                modified,
+            })
+        })
+        .collect();
+
+    // Keep the listing stable and representative: directories first, then by name
+    sampled.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok(sampled)
+}
+
+fn describe_sample(entries: &[SampledEntry]) -> String {
+    let mut by_type: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in entries.iter().filter(|e| !e.is_dir) {
+        let ext = Path::new(&entry.name)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_else(|| "(none)".to_string());
+        *by_type.entry(ext).or_insert(0) += 1;
+    }
+
+    let mut text = format!("{} entries. File types: ", entries.len());
+    text.push_str(
+        &by_type
+            .iter()
+            .map(|(ext, count)| format!("{} x{}", ext, count))
+            .collect::<Vec<_>>()
+            .join(", "),
+    );
+    text.push_str("\n\nname | kind | size bytes | modified\n");
+    for entry in entries.iter().take(MAX_SAMPLE_ENTRIES) {
+        text.push_str(&format!(
+            "{} | {} | {} | {}\n",
+            entry.name,
+            if entry.is_dir { "dir" } else { "file" },
+            entry.size,
+            entry.modified.as_deref().unwrap_or("?")
+        ));
+    }
+    if entries.len() > MAX_SAMPLE_ENTRIES {
+        text.push_str(&format!("... and {} more\n", entries.len() - MAX_SAMPLE_ENTRIES));
+    }
+    text
+}
+
+fn system_prompt(root: &str) -> String {
+    format!(
+        "You organize folders for Helium, a file manager. Given the contents of {root}, propose \
+a tidy folder structure (by project, type or date, whichever fits the files best) and the moves \
+that get there. Keep existing folders that already make sense. Never delete anything.\n\n\
+Reply with ONLY a JSON object of this shape:\n\
+{{\"summary\": \"one sentence describing the structure\", \"operations\": [\n\
+  {{\"type\": \"create_directory\", \"path\": \"{root}/Folder\", \"reason\": \"what goes here\"}},\n\
+  {{\"type\": \"move\", \"from\": \"{root}/file.ext\", \"to\": \"{root}/Folder/file.ext\", \"reason\": \"...\"}}\n\
+]}}\n\
+Only move entries from the listing, and use absolute paths under {root}.",
+        root = root
+    )
+}
+
+/// Ask the model for an organization plan for `path`
+pub async fn suggest_organization(
+    provider: &dyn AIProvider,
+    model_config: &ModelConfig,
+    path: &str,
+) -> Result<FileOperationPlan, AIError> {
+    let root = Path::new(path);
+    let entries = sample_directory(root)?;
+    let root_str = root.to_string_lossy().trim_end_matches(['/', '\\']).to_string();
+
+    let mut model_config = model_config.clone();
+    model_config.parameters.temperature = 0.2;
+
+    let request = InferenceRequest {
+        session_id: format!("organize-{}", uuid::Uuid::new_v4()),
+        model_config,
+        messages: vec![
+            file_plan::message(MessageRole::System, system_prompt(&root_str)),
+            file_plan::message(MessageRole::User, describe_sample(&entries)),
+        ],
+        fs_context: None,
+        mode: AIMode::Agent,
+        tools: None,
+        preset_id: None,
+        conversation_id: None,
+    };
+    let content = infer_once(provider, &request).await?;
+    let mut draft = file_plan::parse_plan(&content)?;
+
+    // Only keep operations that stay under the root, never delete, and only
+    // move entries that were actually in the listing
+    let known: HashSet<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+    let under_root = |p: &str| Path::new(p).starts_with(root) && !p.contains("..");
+    let before = draft.operations.len();
+    draft.operations.retain(|d| match &d.operation {
+        FileOperation::CreateDirectory { path } => under_root(path),
+        FileOperation::Move { from, to } => {
+            let from_path = Path::new(from);
+            from_path.parent() == Some(root)
+                && from_path
+                    .file_name()
+                    .is_some_and(|n| known.contains(n.to_string_lossy().as_ref()))
+                && under_root(to)
+        }
+        FileOperation::Delete { .. } => false,
+    });
+    if draft.operations.len() < before {
+        info!(
+            "Dropped {} operations outside {}",
+            before - draft.operations.len(),
+            root_str
+        );
+    }
+
+    Ok(draft.into_plan(&format!("Organize {}", root_str)))
+}
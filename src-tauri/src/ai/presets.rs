@@ -0,0 +1,211 @@
+// Prompt Presets - System prompt personas
+//
+// Built-in presets ship with the app; user-defined presets are persisted in the
+// app data directory. A conversation selects a preset by id and the backend
+// prepends its system prompt when building the request, so prompt text is not
+// hardcoded in the frontend.
+
+use crate::ai::{ChatMessage, MessageRole};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// A named system prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptPreset {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub system_prompt: String,
+    pub built_in: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+fn built_in_presets() -> Vec<PromptPreset> {
+    let preset = |id: &str, name: &str, description: &str, system_prompt: &str| PromptPreset {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        system_prompt: system_prompt.to_string(),
+        built_in: true,
+        created_at: 0,
+        updated_at: 0,
+    };
+
+    vec![
+        preset(
+            "disk-cleanup-expert",
+            "Disk cleanup expert",
+            "Finds what is using space and what is safe to remove",
+            "You are a disk cleanup expert inside Helium, a storage analyzer. Help the user \
+understand what is using their disk space and which files are safe to remove. Prefer \
+caches, temporary files, build outputs and duplicates. Never suggest deleting system files, \
+documents or photos without explicit confirmation, and always state the size that would be \
+reclaimed.",
+        ),
+        preset(
+            "file-organizer",
+            "File organizer",
+            "Proposes tidy folder structures and file moves",
+            "You are a file organization assistant inside Helium. Suggest clear folder \
+structures grouped by project, type or date, and describe moves as concrete source and \
+destination paths. Keep existing structure where it already works and never delete files.",
+        ),
+        preset(
+            "concise",
+            "Concise answers",
+            "Short, direct answers about files and folders",
+            "You are Helium's assistant. Answer questions about the user's files and folders \
+in as few words as possible, using sizes and paths from the provided context.",
+        ),
+    ]
+}
+
+/// Persisted store of user-defined presets (built-ins are always available)
+pub struct PresetStore {
+    path: PathBuf,
+    presets: Mutex<Vec<PromptPreset>>,
+}
+
+impl PresetStore {
+    /// Load user presets from `path` (a missing file means no user presets)
+    pub fn load(path: PathBuf) -> Self {
+        let presets = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        Self {
+            path,
+            presets: Mutex::new(presets),
+        }
+    }
+
+    fn persist(&self, presets: &[PromptPreset]) -> Result<(), String> {
+        storage::save_json(&self.path, &presets)
+    }
+
+    /// Built-in presets followed by user presets
+    pub fn list(&self) -> Vec<PromptPreset> {
+        let mut all = built_in_presets();
+        all.extend(self.presets.lock().unwrap().iter().cloned());
+        all
+    }
+
+    pub fn get(&self, id: &str) -> Option<PromptPreset> {
+        self.list().into_iter().find(|p| p.id == id)
+    }
+
+    pub fn create(&self, name: String, description: String, system_prompt: String) -> Result<PromptPreset, String> {
+        if name.trim().is_empty() {
+            return Err("Preset name cannot be empty".to_string());
+        }
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let preset = PromptPreset {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            description,
+            system_prompt,
+            built_in: false,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let mut presets = self.presets.lock().unwrap();
+        presets.push(preset.clone());
+        self.persist(&presets)?;
+        Ok(preset)
+    }
+
+    pub fn update(
+        &self,
+        id: &str,
+        name: Option<String>,
+        description: Option<String>,
+        system_prompt: Option<String>,
+    ) -> Result<PromptPreset, String> {
+        if built_in_presets().iter().any(|p| p.id == id) {
+            return Err("Built-in presets cannot be modified".to_string());
+        }
+
+        let mut presets = self.presets.lock().unwrap();
+        let preset = presets
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or_else(|| format!("Preset not found: {}", id))?;
+
+        if let Some(name) = name {
+            preset.name = name;
+        }
+        if let Some(description) = description {
+            preset.description = description;
+        }
+        if let Some(system_prompt) = system_prompt {
+            preset.system_prompt = system_prompt;
+        }
+        preset.updated_at = chrono::Utc::now().timestamp_millis();
+
+        let updated = preset.clone();
+        self.persist(&presets)?;
+        Ok(updated)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        if built_in_presets().iter().any(|p| p.id == id) {
+            return Err("Built-in presets cannot be deleted".to_string());
+        }
+
+        let mut presets = self.presets.lock().unwrap();
+        let before = presets.len();
+        presets.retain(|p| p.id != id);
+        if presets.len() == before {
+            return Err(format!("Preset not found: {}", id));
+        }
+        self.persist(&presets)
+    }
+
+    /// User presets only (what an export should carry)
+    pub fn user_presets(&self) -> Vec<PromptPreset> {
+        self.presets.lock().unwrap().clone()
+    }
+
+    /// Add or replace user presets by ID; built-in presets are skipped.
+    /// Returns how many were imported.
+    pub fn import(&self, imported: Vec<PromptPreset>) -> Result<usize, String> {
+        let built_in = built_in_presets();
+        let mut presets = self.presets.lock().unwrap();
+        let mut count = 0;
+        for mut preset in imported {
+            if preset.built_in || built_in.iter().any(|p| p.id == preset.id) {
+                continue;
+            }
+            preset.built_in = false;
+            match presets.iter_mut().find(|p| p.id == preset.id) {
+                Some(existing) => *existing = preset,
+                None => presets.push(preset),
+            }
+            count += 1;
+        }
+        self.persist(&presets)?;
+        Ok(count)
+    }
+}
+
+/// Prepend a preset's system prompt to the conversation
+pub fn apply_preset(preset: &PromptPreset, messages: &mut Vec<ChatMessage>) {
+    messages.insert(
+        0,
+        ChatMessage {
+            id: format!("preset-{}", preset.id),
+            role: MessageRole::System,
+            content: preset.system_prompt.clone(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            context_paths: None,
+            is_streaming: None,
+            error: None,
+            tool_calls: None,
+            images: None,
+            interrupted: None,
+        },
+    );
+}
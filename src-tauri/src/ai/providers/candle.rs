@@ -1,20 +1,32 @@
 // Candle Provider - Full Implementation
+use super::{AIProvider, InferenceSink};
 use crate::ai::{
     AIError, AIErrorType, ChatMessage, InferenceRequest, InferenceResponse, MessageRole,
     ModelConfig, ModelParameters, ModelProvider, ProviderStatus, TokenUsage, AIMode
 };
-use tauri::Emitter;
 use anyhow::Result;
-use candle_core::{DType, Device, Tensor};
+use async_trait::async_trait;
+use candle_core::{DType, Tensor};
 use candle_nn::VarBuilder;
 use candle_transformers::generation::LogitsProcessor;
 use candle_transformers::models::qwen2::{Config as QwenConfig, Model as QwenModel};
+use crate::ai::accelerators;
+use crate::ai::secrets;
 use hf_hub::{api::tokio::Api, Repo, RepoType};
+use crate::ai::bandwidth::DownloadProgress;
+use crate::ai::http::fetch_hf_file;
+use crate::ai::tokenizer_cache;
+use crate::ai::lora::{merge_adapter, resolve_adapter};
+use crate::ai::model_pool::{LoadedModel, MODEL_POOL};
 use std::path::PathBuf;
+use futures_util::future::{BoxFuture, FutureExt, Shared};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
-use tokenizers::Tokenizer;
 use tokio::sync::mpsc;
 use lazy_static::lazy_static;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
 
 // Model definition for configurable models
 #[derive(Clone)]
@@ -25,6 +37,8 @@ struct ModelDefinition {
     config_file: &'static str,
     eos_tokens: Vec<u32>,
     prompt_format: PromptFormat,
+    /// Approximate size of the weights
+    size_bytes: u64,
 }
 
 #[derive(Clone)]
@@ -45,6 +59,7 @@ fn get_model_registry() -> std::collections::HashMap<&'static str, ModelDefiniti
         config_file: "config.json",
         eos_tokens: vec![151645, 151643],
         prompt_format: PromptFormat::ChatML,
+        size_bytes: 500_000_000,
     });
     
     // Phi-2 - Best quality (~2.7GB)
@@ -55,6 +70,7 @@ fn get_model_registry() -> std::collections::HashMap<&'static str, ModelDefiniti
         config_file: "config.json",
         eos_tokens: vec![50256],
         prompt_format: PromptFormat::Instruct,
+        size_bytes: 2_700_000_000,
     });
     
     // StableLM-2-1.6B - Middle ground (~3.3GB)
@@ -65,21 +81,134 @@ fn get_model_registry() -> std::collections::HashMap<&'static str, ModelDefiniti
         config_file: "config.json",
         eos_tokens: vec![0, 2],
         prompt_format: PromptFormat::ChatML,
+        size_bytes: 3_300_000_000,
     });
     
     registry
 }
 
+/// Embedded model IDs with the approximate size of their weights, smallest first
+pub fn embedded_models() -> Vec<(&'static str, u64)> {
+    let mut models: Vec<(&'static str, u64)> = get_model_registry()
+        .into_iter()
+        .map(|(id, def)| (id, def.size_bytes))
+        .collect();
+    models.sort_by_key(|(_, size)| *size);
+    models
+}
+
 
 
 #[derive(Clone, serde::Serialize)]
 pub struct DownloadStatus {
     pub status: String,
     pub progress: f32, // 0.0 to 1.0
+    /// Current download throughput
+    #[serde(rename = "bytesPerSecond", skip_serializing_if = "Option::is_none")]
+    pub bytes_per_second: Option<u64>,
+}
+
+/// Weight files, config and tokenizer of a downloaded model
+type ModelFiles = (Vec<PathBuf>, PathBuf, PathBuf);
+
+/// A download in progress, shared by every caller that needs the same model
+struct SharedDownload {
+    task: Shared<BoxFuture<'static, Result<ModelFiles, AIError>>>,
+    subscribers: Arc<Mutex<Vec<mpsc::Sender<DownloadStatus>>>>,
+    /// Callers awaiting `task`; also tells this download from a later one
+    /// of the same model
+    waiters: Arc<AtomicUsize>,
+    cancel: CancellationToken,
+}
+
+lazy_static! {
+    static ref DOWNLOADS: Mutex<HashMap<String, SharedDownload>> = Mutex::new(HashMap::new());
+}
+
+/// Forget the download of `model_id` if it is still the one counting `waiters`
+fn remove_download(downloads: &mut HashMap<String, SharedDownload>, model_id: &str, waiters: &Arc<AtomicUsize>) {
+    if downloads.get(model_id).is_some_and(|d| Arc::ptr_eq(&d.waiters, waiters)) {
+        downloads.remove(model_id);
+    }
+}
+
+/// A caller awaiting a shared download. When the last one gives up before
+/// the download finishes (its operation was cancelled), the download is
+/// cancelled with it rather than left paused for the next caller to resume.
+struct Waiter {
+    model_id: String,
+    waiters: Arc<AtomicUsize>,
+    cancel: CancellationToken,
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        // Under the lock, so no caller joins between the count and the removal
+        let mut downloads = DOWNLOADS.lock().unwrap();
+        if self.waiters.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.cancel.cancel();
+            remove_download(&mut downloads, &self.model_id, &self.waiters);
+        }
+    }
+}
+
+/// Download the model if needed and return paths. Concurrent calls for the
+/// same model join one download; each caller's sender receives its progress.
+async fn ensure_model_files(model_id: &str, sender: Option<mpsc::Sender<DownloadStatus>>) -> Result<ModelFiles, AIError> {
+    let (task, _waiter) = {
+        let mut downloads = DOWNLOADS.lock().unwrap();
+        let download = downloads.entry(model_id.to_string()).or_insert_with(|| {
+            let subscribers: Arc<Mutex<Vec<mpsc::Sender<DownloadStatus>>>> = Arc::new(Mutex::new(Vec::new()));
+            let (tx, mut rx) = mpsc::channel::<DownloadStatus>(100);
+
+            // Fan progress out to everyone waiting on this download
+            let fan_out = subscribers.clone();
+            tokio::spawn(async move {
+                while let Some(status) = rx.recv().await {
+                    let mut subscribers = fan_out.lock().unwrap();
+                    subscribers.retain(|s| !s.is_closed());
+                    for subscriber in subscribers.iter() {
+                        let _ = subscriber.try_send(status.clone());
+                    }
+                }
+            });
+
+            let id = model_id.to_string();
+            let waiters = Arc::new(AtomicUsize::new(0));
+            let cancel = CancellationToken::new();
+            let (own_waiters, cancelled) = (waiters.clone(), cancel.clone());
+            let task = async move {
+                let result = tokio::select! {
+                    result = fetch_model_files(&id, Some(tx)) => result,
+                    _ = cancelled.cancelled() => Err(AIError {
+                        error_type: AIErrorType::NetworkError,
+                        message: format!("Download of {} was cancelled", id),
+                        details: None,
+                        suggested_actions: None,
+                    }),
+                };
+                remove_download(&mut DOWNLOADS.lock().unwrap(), &id, &own_waiters);
+                result
+            }
+            .boxed()
+            .shared();
+            SharedDownload { task, subscribers, waiters, cancel }
+        });
+        if let Some(sender) = sender {
+            download.subscribers.lock().unwrap().push(sender);
+        }
+        download.waiters.fetch_add(1, Ordering::SeqCst);
+        let waiter = Waiter {
+            model_id: model_id.to_string(),
+            waiters: download.waiters.clone(),
+            cancel: download.cancel.clone(),
+        };
+        (download.task.clone(), waiter)
+    };
+    task.await
 }
 
-/// Download the model if needed and return paths
-async fn ensure_model_files(model_id: &str, sender: Option<mpsc::Sender<DownloadStatus>>) -> Result<(Vec<PathBuf>, PathBuf, PathBuf), AIError> {
+async fn fetch_model_files(model_id: &str, sender: Option<mpsc::Sender<DownloadStatus>>) -> Result<ModelFiles, AIError> {
     let registry = get_model_registry();
     let model_def = registry.get(model_id).ok_or_else(|| AIError {
         error_type: AIErrorType::InvalidConfiguration,
@@ -87,49 +216,50 @@ async fn ensure_model_files(model_id: &str, sender: Option<mpsc::Sender<Download
         details: None,
         suggested_actions: Some(vec!["Use a supported model ID".to_string()]),
     })?;
-    let api = Api::new().map_err(|e| AIError {
-        error_type: AIErrorType::NetworkError,
-        message: format!("Failed to initialize HF API: {}", e),
-        details: None, suggested_actions: None
-    })?;
+    let api = secrets::hf_api()?;
     
-    println!("[Candle] Initializing HuggingFace API for model: {}", model_def.repo);
-    let repo = api.repo(Repo::new(model_def.repo.to_string(), RepoType::Model));
+    debug!("Initializing HuggingFace API for model: {}", model_def.repo);
+    let repo = Repo::new(model_def.repo.to_string(), RepoType::Model);
 
     let report = |msg: &str, prog: f32| {
         if let Some(tx) = &sender {
             let _ = tx.try_send(DownloadStatus {
                 status: msg.to_string(),
                 progress: prog,
+                bytes_per_second: None,
             });
         }
     };
 
     report("Checking/Downloading tokenizer...", 0.1);
-    println!("[Candle] Fetching tokenizer: {}", model_def.tokenizer_file);
-    let tokenizer_path = repo.get(model_def.tokenizer_file).await.map_err(|e| AIError {
-        error_type: AIErrorType::NetworkError,
-        message: format!("Failed to fetch tokenizer: {}", e),
-        details: None, suggested_actions: Some(vec!["Check internet connection".to_string()])
-    })?;
+    debug!("Fetching tokenizer: {}", model_def.tokenizer_file);
+    let tokenizer_path = fetch_hf_file(
+        &api,
+        &repo,
+        model_def.tokenizer_file,
+        DownloadProgress::new(sender.clone(), "Downloading tokenizer...", (0.1, 0.2)),
+    )
+    .await?;
     
     report("Checking/Downloading config...", 0.2);
-    println!("[Candle] Fetching config: {}", model_def.config_file);
-    let config_path = repo.get(model_def.config_file).await.map_err(|e| AIError {
-        error_type: AIErrorType::NetworkError,
-        message: format!("Failed to fetch config: {}", e),
-        details: None, suggested_actions: None
-    })?;
+    debug!("Fetching config: {}", model_def.config_file);
+    let config_path = fetch_hf_file(
+        &api,
+        &repo,
+        model_def.config_file,
+        DownloadProgress::new(sender.clone(), "Downloading config...", (0.2, 0.3)),
+    )
+    .await?;
     
     report("Downloading model weights...", 0.3);
     let mut model_paths = Vec::new();
+    let file_count = model_def.model_files.len();
     for (i, file) in model_def.model_files.iter().enumerate() {
-        println!("[Candle] Fetching model file {}/{}: {}", i+1, model_def.model_files.len(), file);
-        let path = repo.get(file).await.map_err(|e| AIError {
-            error_type: AIErrorType::NetworkError,
-            message: format!("Failed to fetch model file {}: {}", file, e),
-            details: None, suggested_actions: None
-        })?;
+        debug!("Fetching model file {}/{}: {}", i+1, file_count, file);
+        let share = 0.7 / file_count as f32;
+        let range = (0.3 + share * i as f32, 0.3 + share * (i + 1) as f32);
+        let progress = DownloadProgress::new(sender.clone(), "Downloading model weights...", range);
+        let path = fetch_hf_file(&api, &repo, file, progress).await?;
         model_paths.push(path);
     }
     
@@ -139,11 +269,46 @@ async fn ensure_model_files(model_id: &str, sender: Option<mpsc::Sender<Download
 
 pub async fn download_embedded_model(model_id: String, sender: mpsc::Sender<DownloadStatus>) -> Result<(), String> {
     match ensure_model_files(&model_id, Some(sender)).await {
-        Ok(_) => Ok(()),
+        Ok((model_paths, _, _)) => {
+            // Pull the fresh weights into the page cache so the first load reads from memory
+            spawn_page_warmer(model_paths);
+            Ok(())
+        }
         Err(e) => Err(e.message),
     }
 }
 
+/// Bytes read per step while pre-touching weight files
+const WARM_CHUNK_BYTES: usize = 8 * 1024 * 1024;
+
+/// Read files once in the background so their pages are resident in the OS
+/// page cache by the time the model is loaded
+fn spawn_page_warmer(paths: Vec<PathBuf>) {
+    tokio::task::spawn_blocking(move || {
+        use std::io::Read;
+        let start = std::time::Instant::now();
+        let mut buffer = vec![0u8; WARM_CHUNK_BYTES];
+        let mut total = 0u64;
+        for path in &paths {
+            let Ok(mut file) = std::fs::File::open(path) else {
+                continue;
+            };
+            while let Ok(n) = file.read(&mut buffer) {
+                if n == 0 {
+                    break;
+                }
+                total += n as u64;
+            }
+        }
+        info!("Warmed {} MB of weights in {}ms", total / (1024 * 1024), start.elapsed().as_millis());
+    });
+}
+
+/// Load a model into the resident pool ahead of the first request
+pub async fn prewarm_embedded_model(model_id: &str, adapter: Option<&str>) -> Result<(), AIError> {
+    get_or_load_model(model_id, adapter).await.map(|_| ())
+}
+
 pub async fn check_candle_availability() -> bool {
     // Just check if HF API is accessible
     Api::new().is_ok()
@@ -151,7 +316,105 @@ pub async fn check_candle_availability() -> bool {
 
 
 
-pub async fn run_candle_inference(window: tauri::Window, request: &InferenceRequest) -> Result<InferenceResponse, AIError> {
+/// Load a registry model from its files (downloading them first if needed),
+/// optionally merging a LoRA adapter into its weights
+async fn load_model(model_id: &str, adapter: Option<&str>) -> Result<(LoadedModel, u64), AIError> {
+    let start = std::time::Instant::now();
+    let (model_paths, config_path, tokenizer_path) = ensure_model_files(model_id, None).await?;
+    let adapter_files = match adapter {
+        Some(adapter) => Some(resolve_adapter(adapter).await?),
+        None => None,
+    };
+
+    let load_error = |what: &str, e: String| AIError {
+        error_type: AIErrorType::InvalidConfiguration,
+        message: format!("Failed to load {}: {}", what, e),
+        details: None,
+        suggested_actions: Some(vec!["Re-download the model".to_string()]),
+    };
+    let join_error = |e: tokio::task::JoinError| load_error("model", e.to_string());
+
+    // Parsing the tokenizer and building the weights are independent and both
+    // CPU-bound: run them side by side, off the async runtime
+    let tokenizer_task = {
+        let model_id = model_id.to_string();
+        tokio::task::spawn_blocking(move || {
+            let start = std::time::Instant::now();
+            let tokenizer = tokenizer_cache::load(&model_id, &tokenizer_path);
+            (tokenizer, start.elapsed().as_millis())
+        })
+    };
+    let device = accelerators::inference_device();
+    let model_device = device.clone();
+    let weights_task = tokio::task::spawn_blocking(move || -> Result<(QwenModel, u64, u128), AIError> {
+        let start = std::time::Instant::now();
+        let device = model_device;
+        let config_str = std::fs::read_to_string(config_path).map_err(|e| load_error("config", e.to_string()))?;
+        let config: QwenConfig = serde_json::from_str(&config_str).map_err(|e| load_error("config", e.to_string()))?;
+
+        let size_bytes = model_paths
+            .iter()
+            .filter_map(|p| std::fs::metadata(p).ok())
+            .map(|m| m.len())
+            .sum();
+
+        let vb = match adapter_files {
+            Some(files) => {
+                // Merged weights live in memory instead of being mmapped
+                let weights = merge_adapter(&model_paths, &files, &device)?;
+                VarBuilder::from_tensors(weights, DType::F32, &device)
+            }
+            None => {
+                let model_path_refs: Vec<&PathBuf> = model_paths.iter().collect();
+                unsafe { VarBuilder::from_mmaped_safetensors(&model_path_refs, DType::F32, &device) }
+                    .map_err(|e| load_error("weights", e.to_string()))?
+            }
+        };
+        let model = QwenModel::new(&config, vb).map_err(|e| load_error("model", e.to_string()))?;
+        Ok((model, size_bytes, start.elapsed().as_millis()))
+    });
+
+    let (tokenizer_result, weights_result) = tokio::join!(tokenizer_task, weights_task);
+    let (tokenizer, tokenizer_ms) = tokenizer_result.map_err(join_error)?;
+    let tokenizer = tokenizer.map_err(|e| AIError {
+        error_type: AIErrorType::InvalidConfiguration,
+        message: format!("Token error: {}", e),
+        details: None, suggested_actions: None
+    })?;
+    let (model, size_bytes, weights_ms) = weights_result.map_err(join_error)??;
+
+    info!(
+        "Loaded {} in {}ms (tokenizer {}ms, weights {}ms)",
+        model_id,
+        start.elapsed().as_millis(),
+        tokenizer_ms,
+        weights_ms
+    );
+    Ok((LoadedModel { model, tokenizer, device, cached_tokens: Vec::new() }, size_bytes))
+}
+
+/// Key identifying a base model plus optional adapter in the resident pool
+fn pool_key(model_id: &str, adapter: Option<&str>) -> String {
+    match adapter {
+        Some(adapter) => format!("{}+{}", model_id, adapter),
+        None => model_id.to_string(),
+    }
+}
+
+/// Fetch a model from the resident pool, loading it on first use
+async fn get_or_load_model(model_id: &str, adapter: Option<&str>) -> Result<Arc<tokio::sync::Mutex<LoadedModel>>, AIError> {
+    let key = pool_key(model_id, adapter);
+    let resident = MODEL_POOL.lock().unwrap().get(&key);
+    if let Some(model) = resident {
+        return Ok(model);
+    }
+
+    info!("Loading model {} into pool", key);
+    let (loaded, size_bytes) = load_model(model_id, adapter).await?;
+    Ok(MODEL_POOL.lock().unwrap().insert(&key, loaded, size_bytes))
+}
+
+pub async fn run_candle_inference(request: &InferenceRequest, sink: InferenceSink, cancel: CancellationToken) -> Result<InferenceResponse, AIError> {
     // Extract model ID from request
     let model_id = &request.model_config.model_id;
     
@@ -164,23 +427,10 @@ pub async fn run_candle_inference(window: tauri::Window, request: &InferenceRequ
         suggested_actions: Some(vec!["Select a supported embedded model".to_string()]),
     })?;
     
-    // Download/get model files
-    let (model_paths, config_path, tokenizer_path) = ensure_model_files(model_id, None).await?;
-    let device = Device::Cpu;
-
-    let tokenizer = Tokenizer::from_file(tokenizer_path).map_err(|e| AIError {
-        error_type: AIErrorType::InvalidConfiguration,
-        message: format!("Token error: {}", e),
-        details: None, suggested_actions: None
-    })?;
-
-    let config_str = std::fs::read_to_string(config_path).unwrap();
-    let config: QwenConfig = serde_json::from_str(&config_str).unwrap();
-
-    // Create fresh model instance to ensure empty KV cache
-    let model_path_refs: Vec<&PathBuf> = model_paths.iter().collect();
-    let vb = unsafe { VarBuilder::from_mmaped_safetensors(&model_path_refs, DType::F32, &device).unwrap() };
-    let mut model = QwenModel::new(&config, vb).unwrap();
+    // Reuse a resident model or load it into the pool
+    let loaded = get_or_load_model(model_id, request.model_config.adapter.as_deref()).await?;
+    let mut loaded = loaded.lock().await;
+    let LoadedModel { model, tokenizer, device, cached_tokens } = &mut *loaded;
 
     // Build prompt based on model's format
     let mut prompt = String::new();
@@ -215,6 +465,23 @@ pub async fn run_candle_inference(window: tauri::Window, request: &InferenceRequ
     })?;
 
     let mut input_ids = tokens.get_ids().to_vec();
+
+    // Reuse the KV cache when this prompt extends what the model last processed
+    // (the previous turn of the same conversation). Any divergence - another
+    // conversation or edited history - invalidates the whole cache. The cached
+    // token list is taken out while generating so a failed run leaves it empty.
+    let previous = std::mem::take(cached_tokens);
+    let reused = if !previous.is_empty()
+        && input_ids.len() > previous.len()
+        && input_ids.starts_with(&previous)
+    {
+        debug!("Reusing KV cache for {} of {} prompt tokens", previous.len(), input_ids.len());
+        previous.len()
+    } else {
+        model.clear_kv_cache();
+        0
+    };
+
     let mut generated_tokens = Vec::new();
     let mut logits_processor = LogitsProcessor::new(299792458, Some(request.model_config.parameters.temperature as f64), Some(request.model_config.parameters.top_p as f64));
     
@@ -222,31 +489,41 @@ pub async fn run_candle_inference(window: tauri::Window, request: &InferenceRequ
     let max_tokens = request.model_config.parameters.max_tokens as usize;
     let mut response_text = String::new();
     
-    let mut pos = 0;
+    // Number of tokens whose keys/values are already in the model's cache
+    let mut pos = reused;
 
     for _ in 0..max_tokens {
-        let (context_size, start_pos) = if pos == 0 {
-            (input_ids.len(), 0)
-        } else {
-            (1, pos)
-        };
+        if cancel.is_cancelled() {
+            info!("Inference cancelled by user");
+            break;
+        }
 
-        let ctxt = &input_ids[input_ids.len() - context_size..];
-        let input_tensor = Tensor::new(ctxt, &device).unwrap().unsqueeze(0).unwrap();
+        // Feed everything not yet in the cache: the uncached prompt suffix on
+        // the first step, then one sampled token per step
+        let ctxt = &input_ids[pos..];
+        let input_tensor = Tensor::new(ctxt, &*device).unwrap().unsqueeze(0).unwrap();
         
         // Forward pass with correct position
-        let logits = model.forward(&input_tensor, start_pos, None).unwrap();
+        let logits = model.forward(&input_tensor, pos, None).unwrap();
         let logits = logits.squeeze(0).unwrap();
         let logits = logits.get(logits.dim(0).unwrap() - 1).unwrap().to_dtype(DType::F32).unwrap();
 
         let next_token = logits_processor.sample(&logits).unwrap();
+        if generated_tokens.is_empty() {
+            info!(
+                "First token after {}ms ({} prompt tokens, {} from KV cache)",
+                start_time.elapsed().as_millis(),
+                input_ids.len(),
+                reused
+            );
+        }
+        pos = input_ids.len();
         generated_tokens.push(next_token);
         input_ids.push(next_token);
-        pos += context_size;
 
         if let Some(text) = tokenizer.decode(&[next_token], true).ok() {
              response_text.push_str(&text);
-             let _ = window.emit("ai-response-chunk", &text);
+             sink.chunk(&text);
         }
 
         // Check stop (EOS - use model's defined tokens)
@@ -255,6 +532,9 @@ pub async fn run_candle_inference(window: tauri::Window, request: &InferenceRequ
         }
     }
     
+    // Remember what the KV cache now holds for the next turn
+    *cached_tokens = input_ids[..pos].to_vec();
+
     // ... return response ...
     Ok(InferenceResponse {
         message: ChatMessage {
@@ -266,6 +546,8 @@ pub async fn run_candle_inference(window: tauri::Window, request: &InferenceRequ
             is_streaming: Some(false),
             error: None,
             tool_calls: None,
+            images: None,
+            interrupted: None,
         },
         is_complete: true,
         usage: Some(TokenUsage {
@@ -288,3 +570,34 @@ pub async fn get_candle_status() -> ProviderStatus {
         error: None,
     }
 }
+
+/// Candle (embedded) implementation of the provider interface
+pub struct CandleProvider;
+
+#[async_trait]
+impl AIProvider for CandleProvider {
+    fn kind(&self) -> ModelProvider {
+        ModelProvider::Candle
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+
+    async fn status(&self, _endpoint: Option<&str>) -> ProviderStatus {
+        get_candle_status().await
+    }
+
+    async fn list_models(&self, _endpoint: Option<&str>) -> Result<Vec<ModelConfig>, AIError> {
+        Ok(get_candle_status().await.available_models)
+    }
+
+    async fn infer_stream(
+        &self,
+        request: &InferenceRequest,
+        sink: InferenceSink,
+        cancel: CancellationToken,
+    ) -> Result<InferenceResponse, AIError> {
+        run_candle_inference(request, sink, cancel).await
+    }
+}
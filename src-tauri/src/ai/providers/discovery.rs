@@ -0,0 +1,63 @@
+// Local Server Discovery - Find OpenAI-compatible servers already running
+//
+// Probes the default ports of popular local inference servers (LM Studio,
+// llama.cpp's server, text-generation-webui, ...) so users who already run one
+// get its models listed without configuring an endpoint.
+
+use super::openai_compatible::list_unauthenticated_models;
+use crate::ai::ModelConfig;
+use serde::Serialize;
+use std::time::Duration;
+use tracing::info;
+
+/// How long to wait for a local server to answer
+const PROBE_TIMEOUT: Duration = Duration::from_millis(600);
+
+/// Well-known local servers and their default OpenAI-compatible endpoints
+const KNOWN_SERVERS: &[(&str, &str)] = &[
+    ("LM Studio", "http://127.0.0.1:1234/v1"),
+    ("llama.cpp server", "http://127.0.0.1:8080/v1"),
+    ("text-generation-webui", "http://127.0.0.1:5000/v1"),
+    ("Jan", "http://127.0.0.1:1337/v1"),
+];
+
+/// A local server that answered on its default port
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredServer {
+    pub name: String,
+    pub endpoint: String,
+    pub models: Vec<ModelConfig>,
+}
+
+async fn probe(name: &str, endpoint: &str) -> Option<DiscoveredServer> {
+    let mut models = tokio::time::timeout(PROBE_TIMEOUT, list_unauthenticated_models(endpoint))
+        .await
+        .ok()?
+        .ok()?;
+    for model in &mut models {
+        model.name = format!("{} ({})", model.name, name);
+    }
+    Some(DiscoveredServer {
+        name: name.to_string(),
+        endpoint: endpoint.to_string(),
+        models,
+    })
+}
+
+/// Probe all known local servers concurrently
+pub async fn discover_local_servers() -> Vec<DiscoveredServer> {
+    let probes = KNOWN_SERVERS.iter().map(|(name, endpoint)| probe(name, endpoint));
+    let servers: Vec<DiscoveredServer> = futures_util::future::join_all(probes)
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+    if !servers.is_empty() {
+        info!(
+            "Found local servers: {}",
+            servers.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(", ")
+        );
+    }
+    servers
+}
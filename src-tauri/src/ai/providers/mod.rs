@@ -1,11 +1,219 @@
 // AI Providers Module
+//
+// Every inference backend implements `AIProvider` and is registered in the
+// `ProviderRegistry` held in managed state, so the Tauri commands dispatch
+// through one interface instead of matching on each provider.
 
 pub mod ollama;
 pub mod openai_compatible;
-
-pub use ollama::*;
 pub mod candle;
+pub mod discovery;
 
 pub use ollama::*;
 pub use openai_compatible::*;
 pub use candle::*;
+
+use crate::event_batch::{self, EventBatcher};
+use crate::ai::{AIError, InferenceRequest, InferenceResponse, ModelConfig, ModelProvider, ProviderStatus};
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use tauri::Emitter;
+use tokio_util::sync::CancellationToken;
+
+/// Partial tool call, forwarded while the model is still generating its arguments
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCallDelta {
+    /// Position of the call among the tool calls of this response
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Newly received fragment of the JSON arguments
+    pub arguments_delta: String,
+    /// Arguments received so far (possibly incomplete JSON)
+    pub arguments: String,
+}
+
+/// Merge tool call deltas of the same call; another call's delta is sent separately
+fn merge_tool_call_delta(held: &mut ToolCallDelta, newer: ToolCallDelta) -> Option<ToolCallDelta> {
+    if held.index != newer.index {
+        return Some(newer);
+    }
+    held.arguments_delta.push_str(&newer.arguments_delta);
+    held.arguments = newer.arguments;
+    held.id = newer.id.or(held.id.take());
+    held.name = newer.name.or(held.name.take());
+    None
+}
+
+/// Destination for output streamed by a running inference
+#[derive(Clone, Default)]
+pub struct InferenceSink {
+    window: Option<tauri::Window>,
+    /// Text streamed so far, kept when recording
+    partial: Option<Arc<Mutex<String>>>,
+    /// Rate-limited `ai-response-chunk` and `ai-tool-call-delta` channels
+    chunks: Option<EventBatcher<String>>,
+    tool_call_deltas: Option<EventBatcher<ToolCallDelta>>,
+}
+
+impl InferenceSink {
+    /// Stream chunks to the given window as `ai-response-chunk` events
+    pub fn window(window: tauri::Window) -> Self {
+        Self {
+            chunks: Some(EventBatcher::new(window.clone(), "ai-response-chunk", event_batch::append)),
+            tool_call_deltas: Some(EventBatcher::new(window.clone(), "ai-tool-call-delta", merge_tool_call_delta)),
+            window: Some(window),
+            partial: None,
+        }
+    }
+
+    /// Discard streamed output (used by background tasks)
+    pub fn silent() -> Self {
+        Self::default()
+    }
+
+    /// Also keep the streamed text, so it survives an interrupted generation
+    pub fn recording(mut self) -> Self {
+        self.partial = Some(Arc::new(Mutex::new(String::new())));
+        self
+    }
+
+    /// Text streamed so far (empty unless recording)
+    pub fn partial_text(&self) -> String {
+        self.partial
+            .as_ref()
+            .map(|p| p.lock().unwrap().clone())
+            .unwrap_or_default()
+    }
+
+    /// Forward a chunk of generated text
+    pub fn chunk(&self, text: &str) {
+        if let Some(partial) = &self.partial {
+            partial.lock().unwrap().push_str(text);
+        }
+        if let Some(chunks) = &self.chunks {
+            chunks.send(text.to_string());
+        }
+    }
+
+    /// Forward a partial tool call as an `ai-tool-call-delta` event
+    pub fn tool_call_delta(&self, delta: ToolCallDelta) {
+        if let Some(deltas) = &self.tool_call_deltas {
+            deltas.send(delta);
+        }
+    }
+
+    /// Emit streamed output still held back by the rate limit; call before
+    /// returning the response, so no chunk arrives after it
+    pub fn flush(&self) {
+        if let Some(chunks) = &self.chunks {
+            chunks.flush();
+        }
+        if let Some(deltas) = &self.tool_call_deltas {
+            deltas.flush();
+        }
+    }
+
+    /// Emit an arbitrary inference-related event
+    pub fn event<S: serde::Serialize + Clone>(&self, name: &str, payload: S) {
+        if let Some(window) = &self.window {
+            let _ = window.emit(name, payload);
+        }
+    }
+}
+
+/// Common interface implemented by every inference backend
+#[async_trait]
+pub trait AIProvider: Send + Sync {
+    /// Which provider this implementation serves
+    fn kind(&self) -> ModelProvider;
+
+    /// Whether the provider accepts native (OpenAI-style) tool definitions
+    fn supports_tools(&self) -> bool {
+        false
+    }
+
+    /// Whether the provider can receive image attachments
+    fn supports_images(&self) -> bool {
+        false
+    }
+
+    /// Whether the provider runs in-process and shares model state between requests
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    /// Whether the provider cannot be probed without a user-supplied endpoint
+    fn requires_endpoint(&self) -> bool {
+        false
+    }
+
+    /// Report availability and the models the provider currently offers
+    async fn status(&self, endpoint: Option<&str>) -> ProviderStatus;
+
+    /// List the models the provider can run
+    async fn list_models(&self, endpoint: Option<&str>) -> Result<Vec<ModelConfig>, AIError>;
+
+    /// Run inference, streaming partial output into `sink`
+    async fn infer_stream(
+        &self,
+        request: &InferenceRequest,
+        sink: InferenceSink,
+        cancel: CancellationToken,
+    ) -> Result<InferenceResponse, AIError>;
+}
+
+/// Run a request to completion without streaming output (used by background tasks)
+pub async fn infer_once(provider: &dyn AIProvider, request: &InferenceRequest) -> Result<String, AIError> {
+    let response = provider
+        .infer_stream(request, InferenceSink::silent(), CancellationToken::new())
+        .await?;
+    Ok(response.message.content)
+}
+
+/// Registry of the providers available to the backend, kept in managed state
+pub struct ProviderRegistry {
+    providers: Vec<Arc<dyn AIProvider>>,
+}
+
+impl ProviderRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self { providers: Vec::new() }
+    }
+
+    /// Register a provider, replacing any existing one of the same kind
+    pub fn register(&mut self, provider: Arc<dyn AIProvider>) {
+        let kind = provider.kind();
+        self.providers.retain(|p| p.kind() != kind);
+        self.providers.push(provider);
+    }
+
+    /// Look up the provider serving `kind`
+    pub fn get(&self, kind: &ModelProvider) -> Option<Arc<dyn AIProvider>> {
+        self.providers.iter().find(|p| &p.kind() == kind).cloned()
+    }
+
+    /// All registered providers, in registration order
+    pub fn all(&self) -> &[Arc<dyn AIProvider>] {
+        &self.providers
+    }
+}
+
+impl Default for ProviderRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(Arc::new(OllamaProvider));
+        registry.register(Arc::new(CandleProvider));
+        registry.register(Arc::new(OpenAICompatibleProvider));
+        // Fixture builds replace every backend with the canned model
+        #[cfg(feature = "fixtures")]
+        for kind in [ModelProvider::Ollama, ModelProvider::Candle, ModelProvider::OpenAICompatible] {
+            registry.register(Arc::new(crate::fixtures::FixtureProvider { kind }));
+        }
+        registry
+    }
+}
@@ -2,16 +2,20 @@
 //
 // Integration with Ollama for local LLM inference via HTTP API.
 
+use super::{AIProvider, InferenceSink};
+use crate::ai::http::{send_with_retry, RetryPolicy};
 use crate::ai::{
     AIError, AIErrorType, AIMode, ChatMessage, InferenceRequest, InferenceResponse, MessageRole,
     ModelConfig, ModelParameters, ModelProvider, ProviderStatus, TokenUsage,
 };
+use async_trait::async_trait;
 use reqwest;
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
-use tauri::Emitter;
+use tokio_util::sync::CancellationToken;
 use std::io::BufRead; 
 use bytes::Buf;
+use tracing::{debug, info, warn};
 
 /// Default Ollama endpoint
 const DEFAULT_OLLAMA_ENDPOINT: &str = "http://127.0.0.1:11434";
@@ -29,6 +33,9 @@ struct OllamaChatRequest {
 struct OllamaMessage {
     role: String,
     content: String,
+    /// Base64-encoded images for multimodal models (llava, moondream, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    images: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -69,16 +76,16 @@ struct OllamaModel {
 /// Check if Ollama is available
 pub async fn check_ollama_availability(endpoint: Option<&str>) -> Result<bool, AIError> {
     let url = format!("{}/api/tags", endpoint.unwrap_or(DEFAULT_OLLAMA_ENDPOINT));
-    println!("Checking Ollama status at: {}", url);
+    debug!("Checking Ollama status at: {}", url);
 
     match reqwest::get(&url).await {
         Ok(response) => {
             let status = response.status();
-            println!("Ollama response status: {}", status);
+            debug!("Ollama response status: {}", status);
             Ok(status.is_success())
         },
         Err(e) => {
-            println!("Ollama connection failed: {}", e);
+            warn!("Ollama connection failed: {}", e);
             Ok(false)
         },
     }
@@ -87,7 +94,7 @@ pub async fn check_ollama_availability(endpoint: Option<&str>) -> Result<bool, A
 /// Get available Ollama models
 pub async fn get_ollama_models(endpoint: Option<&str>) -> Result<Vec<ModelConfig>, AIError> {
     let actual_endpoint = endpoint.unwrap_or(DEFAULT_OLLAMA_ENDPOINT);
-    println!("[get_ollama_models] Using endpoint: {}", actual_endpoint);
+    debug!("Using endpoint: {}", actual_endpoint);
     let url = format!("{}/api/tags", actual_endpoint);
 
     let response = reqwest::get(&url).await.map_err(|e| AIError {
@@ -142,19 +149,20 @@ pub async fn get_ollama_models(endpoint: Option<&str>) -> Result<Vec<ModelConfig
                 is_available: true,
                 size_bytes: Some(m.size),
                 recommended_for,
+                adapter: None,
             }
         })
         .collect();
 
-    println!("[get_ollama_models] Returning {} models, all with endpoint: {}", models.len(), actual_endpoint);
+    debug!("Returning {} models, all with endpoint: {}", models.len(), actual_endpoint);
     Ok(models)
 }
 
 /// Run inference with Ollama
 pub async fn run_ollama_inference(
-    window: tauri::Window,
     request: &InferenceRequest,
-    cancel_token: tokio_util::sync::CancellationToken,
+    sink: InferenceSink,
+    cancel_token: CancellationToken,
 ) -> Result<InferenceResponse, AIError> {
     let start_time = Instant::now();
 
@@ -165,9 +173,9 @@ pub async fn run_ollama_inference(
         .unwrap_or(DEFAULT_OLLAMA_ENDPOINT);
 
     let url = format!("{}/api/chat", endpoint);
-    println!("[Ollama] Using endpoint: {}", endpoint);
-    println!("[Ollama] Full URL: {}", url);
-    println!("[Ollama] Model: {}", request.model_config.model_id);
+    debug!("Using endpoint: {}", endpoint);
+    debug!("Full URL: {}", url);
+    debug!("Model: {}", request.model_config.model_id);
 
     // Convert messages to Ollama format and inject context
     // ... (context injection logic remains same, just copied for brevity or assumed helper)
@@ -231,6 +239,7 @@ pub async fn run_ollama_inference(
             ollama_messages.push(OllamaMessage {
                 role: "system".to_string(),
                 content: format!("Context Information:\n{}\nUse this context to answer the user's questions about their files.", context_str),
+                images: None,
             });
         }
     }
@@ -238,6 +247,16 @@ pub async fn run_ollama_inference(
 
     // 2. Append Conversation History
     for m in &request.messages {
+        let images = match &m.images {
+            Some(attachments) if !attachments.is_empty() => Some(
+                attachments
+                    .iter()
+                    .map(|a| a.resolve().map(|img| img.base64))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            _ => None,
+        };
+
         ollama_messages.push(OllamaMessage {
             role: match m.role {
                 MessageRole::User => "user".to_string(),
@@ -245,6 +264,7 @@ pub async fn run_ollama_inference(
                 MessageRole::System => "system".to_string(),
             },
             content: m.content.clone(),
+            images,
         });
     }
 
@@ -261,35 +281,23 @@ pub async fn run_ollama_inference(
     };
 
     let client = reqwest::Client::new();
-    println!("[Ollama] Sending request...");
-    let response = client
-        .post(&url)
-        .json(&ollama_request)
-        .send()
-        .await
-        .map_err(|e| AIError {
-            error_type: AIErrorType::NetworkError,
-            message: format!("Failed to send request to Ollama: {}", e),
-            details: None,
-            suggested_actions: Some(vec!["Check Ollama is running".to_string()]),
-        })?;
-
-    let status = response.status();
-    println!("[Ollama] Response status: {}", status);
+    debug!("Sending request...");
+    let response = send_with_retry("Ollama request", &RetryPolicy::default(), || {
+        client.post(&url).json(&ollama_request)
+    })
+    .await
+    .map_err(|mut e| {
+        warn!("{}", e.message);
+        let actions = e.suggested_actions.get_or_insert_with(Vec::new);
+        match e.error_type {
+            AIErrorType::ModelNotFound => actions.push("Try pulling the model with 'ollama pull'".to_string()),
+            AIErrorType::NetworkError => actions.push("Check Ollama is running".to_string()),
+            _ => {}
+        }
+        e
+    })?;
 
-    if !status.is_success() {
-        let error_body = response.text().await.unwrap_or_default();
-        println!("[Ollama] Error body: {}", error_body);
-        return Err(AIError {
-            error_type: AIErrorType::InferenceFailed,
-            message: format!("Ollama returned error: {} - {}", status, error_body),
-            details: None,
-            suggested_actions: Some(vec![
-                "Check if the model exists".to_string(),
-                "Try pulling the model with 'ollama pull'".to_string(),
-            ]),
-        });
-    }
+    debug!("Response status: {}", response.status());
 
     // Process streaming response
     let mut stream = response.bytes_stream();
@@ -306,7 +314,7 @@ pub async fn run_ollama_inference(
     while let Some(chunk_result) = stream.next().await {
         // Check if cancellation was requested
         if cancel_token.is_cancelled() {
-            println!("[Ollama] Inference cancelled by user");
+            info!("Inference cancelled by user");
             return Err(AIError {
                 error_type: AIErrorType::InferenceFailed,
                 message: "Inference cancelled by user".to_string(),
@@ -335,7 +343,7 @@ pub async fn run_ollama_inference(
                 let content = ollama_msg.message.content;
                 if !content.is_empty() {
                     full_content.push_str(&content);
-                    let _ = window.emit("ai-response-chunk", &content);
+                    sink.chunk(&content);
                 }
 
                 if ollama_msg.done {
@@ -349,7 +357,7 @@ pub async fn run_ollama_inference(
                     }
                 }
             } else {
-                eprintln!("Failed to parse JSON: {}", line);
+                warn!("Failed to parse JSON: {}", line);
             }
         }
     }
@@ -365,6 +373,8 @@ pub async fn run_ollama_inference(
         is_streaming: None,
         error: None,
         tool_calls: None,
+        images: None,
+        interrupted: None,
     };
 
     Ok(InferenceResponse {
@@ -399,3 +409,34 @@ pub async fn get_ollama_status(endpoint: Option<&str>) -> ProviderStatus {
         error,
     }
 }
+
+/// Ollama implementation of the provider interface
+pub struct OllamaProvider;
+
+#[async_trait]
+impl AIProvider for OllamaProvider {
+    fn kind(&self) -> ModelProvider {
+        ModelProvider::Ollama
+    }
+
+    fn supports_images(&self) -> bool {
+        true
+    }
+
+    async fn status(&self, endpoint: Option<&str>) -> ProviderStatus {
+        get_ollama_status(endpoint).await
+    }
+
+    async fn list_models(&self, endpoint: Option<&str>) -> Result<Vec<ModelConfig>, AIError> {
+        get_ollama_models(endpoint).await
+    }
+
+    async fn infer_stream(
+        &self,
+        request: &InferenceRequest,
+        sink: InferenceSink,
+        cancel: CancellationToken,
+    ) -> Result<InferenceResponse, AIError> {
+        run_ollama_inference(request, sink, cancel).await
+    }
+}
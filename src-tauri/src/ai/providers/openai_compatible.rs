@@ -2,13 +2,22 @@
 //
 // Generic client for OpenAI-compatible APIs (vLLM, LocalAI, LM Studio, etc.)
 
+use super::{AIProvider, InferenceSink, ToolCallDelta};
+use super::discovery::discover_local_servers;
+use crate::ai::http::{map_request_error, map_status_error, send_with_retry, RetryPolicy};
+use crate::ai::images::ImageAttachment;
+use crate::ai::secrets;
 use crate::ai::{
-    AIError, AIErrorType, ChatMessage, InferenceRequest, InferenceResponse, MessageRole,
-    ModelConfig, ModelProvider, ProviderStatus, TokenUsage,
+    AIError, AIErrorType, AIMode, ChatMessage, InferenceRequest, InferenceResponse, MessageRole,
+    ModelConfig, ModelParameters, ModelProvider, OpenAIToolCall, OpenAIToolCallFunction, ProviderStatus, TokenUsage,
 };
+use futures_util::StreamExt;
+use async_trait::async_trait;
 use reqwest;
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
 
 /// OpenAI chat request format
 #[derive(Debug, Serialize)]
@@ -30,12 +39,65 @@ struct OpenAIChatRequest {
 struct OpenAIMessage {
     role: String,
     /// Content can be null when tool_calls are present
-    content: Option<String>,
+    content: Option<OpenAIContent>,
     /// Tool calls in the response (OpenAI format)
     #[serde(skip_serializing_if = "Option::is_none")]
     tool_calls: Option<Vec<crate::ai::OpenAIToolCall>>,
 }
 
+/// Message content: plain text, or an array of parts for multimodal input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum OpenAIContent {
+    Text(String),
+    Parts(Vec<OpenAIContentPart>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OpenAIContentPart {
+    Text { text: String },
+    ImageUrl { image_url: OpenAIImageUrl },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIImageUrl {
+    url: String,
+}
+
+impl OpenAIContent {
+    /// Build content for a message, switching to parts when images are attached
+    fn with_images(text: String, images: Option<&Vec<ImageAttachment>>) -> Result<Self, AIError> {
+        let images = match images {
+            Some(images) if !images.is_empty() => images,
+            _ => return Ok(OpenAIContent::Text(text)),
+        };
+
+        let mut parts = vec![OpenAIContentPart::Text { text }];
+        for image in images {
+            parts.push(OpenAIContentPart::ImageUrl {
+                image_url: OpenAIImageUrl { url: image.resolve()?.data_url() },
+            });
+        }
+        Ok(OpenAIContent::Parts(parts))
+    }
+
+    /// Concatenated text of the content
+    fn into_text(self) -> String {
+        match self {
+            OpenAIContent::Text(text) => text,
+            OpenAIContent::Parts(parts) => parts
+                .into_iter()
+                .filter_map(|p| match p {
+                    OpenAIContentPart::Text { text } => Some(text),
+                    OpenAIContentPart::ImageUrl { .. } => None,
+                })
+                .collect::<Vec<_>>()
+                .join(""),
+        }
+    }
+}
+
 /// OpenAI chat response format
 #[derive(Debug, Deserialize)]
 struct OpenAIChatResponse {
@@ -57,9 +119,54 @@ struct OpenAIUsage {
     total_tokens: u32,
 }
 
+/// One server-sent event of a streaming chat response
+#[derive(Debug, Deserialize)]
+struct OpenAIStreamChunk {
+    #[serde(default)]
+    choices: Vec<OpenAIStreamChoice>,
+    #[serde(default)]
+    usage: Option<OpenAIUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIStreamChoice {
+    #[serde(default)]
+    delta: OpenAIStreamDelta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OpenAIStreamDelta {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Option<Vec<OpenAIToolCallChunk>>,
+}
+
+/// Fragment of a tool call; arguments arrive as pieces of a JSON string
+#[derive(Debug, Deserialize)]
+struct OpenAIToolCallChunk {
+    #[serde(default)]
+    index: usize,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    function: Option<OpenAIFunctionChunk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIFunctionChunk {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    arguments: Option<String>,
+}
+
 /// Run inference with OpenAI-compatible API
 pub async fn run_openai_compatible_inference(
     request: &InferenceRequest,
+    sink: &InferenceSink,
+    cancel_token: &CancellationToken,
 ) -> Result<InferenceResponse, AIError> {
     let start_time = Instant::now();
 
@@ -102,14 +209,14 @@ pub async fn run_openai_compatible_inference(
                 content.push_str(&m.content);
                 openai_messages.push(OpenAIMessage {
                     role: "user".to_string(),
-                    content: Some(content),
+                    content: Some(OpenAIContent::with_images(content, m.images.as_ref())?),
                     tool_calls: None,
                 });
             }
             MessageRole::Assistant => {
                 openai_messages.push(OpenAIMessage {
                     role: "assistant".to_string(),
-                    content: Some(m.content.clone()),
+                    content: Some(OpenAIContent::Text(m.content.clone())),
                     tool_calls: m.tool_calls.clone(),
                 });
             }
@@ -122,18 +229,18 @@ pub async fn run_openai_compatible_inference(
         temperature: request.model_config.parameters.temperature,
         top_p: request.model_config.parameters.top_p,
         max_tokens: request.model_config.parameters.max_tokens,
-        stream: false,
+        stream: request.model_config.parameters.stream,
         stop: request.model_config.parameters.stop_sequences.clone(),
         tools: request.tools.clone(),
     };
 
-    println!("[OpenAI-Compatible] Request URL: {}", url);
-    println!("[OpenAI-Compatible] Model: {}", request.model_config.model_id);
-    println!("[OpenAI-Compatible] Tools included: {}", request.tools.is_some());
+    debug!("Request URL: {}", url);
+    debug!("Model: {}", request.model_config.model_id);
+    debug!("Tools included: {}", request.tools.is_some());
     if let Some(tools) = &request.tools {
-        println!("[OpenAI-Compatible] Number of tools: {}", tools.len());
+        debug!("Number of tools: {}", tools.len());
         for tool in tools {
-            println!("[OpenAI-Compatible]   - {}: {}", tool.function.name, tool.function.description);
+            debug!("- {}: {}", tool.function.name, tool.function.description);
         }
     }
 
@@ -145,35 +252,23 @@ pub async fn run_openai_compatible_inference(
         suggested_actions: None,
     })?;
 
-    let mut request_builder = client.post(&url).json(&openai_request);
-
-    // Add API key if provided
-    if let Some(api_key) = &request.model_config.api_key {
-        request_builder = request_builder.header("Authorization", format!("Bearer {}", api_key));
-    }
+    let api_key = secrets::resolve_api_key(
+        &ModelProvider::OpenAICompatible,
+        request.model_config.api_key.as_deref(),
+    );
+    let response = send_with_retry("OpenAI-compatible request", &RetryPolicy::default(), || {
+        let mut request_builder = client.post(&url).json(&openai_request);
 
-    let response = request_builder.send().await.map_err(|e| AIError {
-        error_type: AIErrorType::NetworkError,
-        message: format!("Failed to send request: {}", e),
-        details: None,
-        suggested_actions: Some(vec![
-            "Check the endpoint URL".to_string(),
-            "Verify the server is running".to_string(),
-        ]),
-    })?;
+        // Add API key if provided or stored in the credential store
+        if let Some(api_key) = &api_key {
+            request_builder = request_builder.header("Authorization", format!("Bearer {}", api_key));
+        }
+        request_builder
+    })
+    .await?;
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(AIError {
-            error_type: AIErrorType::InferenceFailed,
-            message: format!("API returned error: {} - {}", status, error_text),
-            details: None,
-            suggested_actions: Some(vec![
-                "Check API key if required".to_string(),
-                "Verify model name".to_string(),
-            ]),
-        });
+    if openai_request.stream {
+        return read_openai_stream(response, sink, cancel_token, start_time).await;
     }
 
     let openai_response: OpenAIChatResponse = response.json().await.map_err(|e| AIError {
@@ -192,16 +287,21 @@ pub async fn run_openai_compatible_inference(
         suggested_actions: None,
     })?;
 
-    println!("[OpenAI-Compatible] Response received");
+    debug!("Response received");
 
-    let content = choice.message.content.clone().unwrap_or_default();
-    println!("[OpenAI-Compatible] Content length: {}", content.len());
-    println!("[OpenAI-Compatible] Has tool_calls: {}", choice.message.tool_calls.is_some());
+    let content = choice
+        .message
+        .content
+        .clone()
+        .map(OpenAIContent::into_text)
+        .unwrap_or_default();
+    debug!("Content length: {}", content.len());
+    debug!("Has tool_calls: {}", choice.message.tool_calls.is_some());
 
     if let Some(tool_calls) = &choice.message.tool_calls {
-        println!("[OpenAI-Compatible] Number of tool calls: {}", tool_calls.len());
+        debug!("Number of tool calls: {}", tool_calls.len());
         for tc in tool_calls {
-            println!("[OpenAI-Compatible]   - {} ({})", tc.function.name, tc.id);
+            debug!("- {} ({})", tc.function.name, tc.id);
         }
     }
 
@@ -214,6 +314,8 @@ pub async fn run_openai_compatible_inference(
         is_streaming: None,
         error: None,
         tool_calls: choice.message.tool_calls.clone(),
+        images: None,
+        interrupted: None,
     };
 
     let usage = openai_response.usage.map(|u| TokenUsage {
@@ -230,38 +332,243 @@ pub async fn run_openai_compatible_inference(
     })
 }
 
-/// Check if OpenAI-compatible endpoint is available
-pub async fn check_openai_compatible_availability(endpoint: &str) -> Result<bool, AIError> {
+/// Read a streamed (SSE) chat response, forwarding text chunks and tool-call
+/// argument deltas as they arrive
+async fn read_openai_stream(
+    response: reqwest::Response,
+    sink: &InferenceSink,
+    cancel_token: &CancellationToken,
+    start_time: Instant,
+) -> Result<InferenceResponse, AIError> {
+    let mut stream = response.bytes_stream();
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut content = String::new();
+    let mut tool_calls: Vec<OpenAIToolCall> = Vec::new();
+    let mut usage: Option<TokenUsage> = None;
+    let mut finished = false;
+
+    'stream: while let Some(chunk_result) = stream.next().await {
+        if cancel_token.is_cancelled() {
+            info!("Inference cancelled by user");
+            return Err(AIError {
+                error_type: AIErrorType::InferenceFailed,
+                message: "Inference cancelled by user".to_string(),
+                details: None,
+                suggested_actions: None,
+            });
+        }
+
+        let chunk = chunk_result.map_err(|e| AIError {
+            error_type: AIErrorType::NetworkError,
+            message: format!("Stream error: {}", e),
+            details: None,
+            suggested_actions: None,
+        })?;
+        buffer.extend_from_slice(&chunk);
+
+        while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+            let line_bytes = buffer.drain(..=pos).collect::<Vec<u8>>();
+            let line = String::from_utf8_lossy(&line_bytes);
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                finished = true;
+                break 'stream;
+            }
+
+            let parsed: OpenAIStreamChunk = match serde_json::from_str(data) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    warn!("Failed to parse stream event: {} ({})", data, e);
+                    continue;
+                }
+            };
+
+            if let Some(u) = parsed.usage {
+                usage = Some(TokenUsage {
+                    prompt_tokens: u.prompt_tokens,
+                    completion_tokens: u.completion_tokens,
+                    total_tokens: u.total_tokens,
+                });
+            }
+
+            for choice in parsed.choices {
+                if let Some(text) = choice.delta.content.filter(|t| !t.is_empty()) {
+                    content.push_str(&text);
+                    sink.chunk(&text);
+                }
+
+                for call in choice.delta.tool_calls.unwrap_or_default() {
+                    while tool_calls.len() <= call.index {
+                        tool_calls.push(OpenAIToolCall {
+                            id: String::new(),
+                            r#type: "function".to_string(),
+                            function: OpenAIToolCallFunction {
+                                name: String::new(),
+                                arguments: String::new(),
+                            },
+                        });
+                    }
+                    let entry = &mut tool_calls[call.index];
+                    if let Some(id) = &call.id {
+                        entry.id = id.clone();
+                    }
+                    let (name, arguments_delta) = match call.function {
+                        Some(f) => (f.name, f.arguments.unwrap_or_default()),
+                        None => (None, String::new()),
+                    };
+                    if let Some(name) = &name {
+                        entry.function.name.push_str(name);
+                    }
+                    entry.function.arguments.push_str(&arguments_delta);
+
+                    sink.tool_call_delta(ToolCallDelta {
+                        index: call.index,
+                        id: call.id,
+                        name,
+                        arguments_delta,
+                        arguments: entry.function.arguments.clone(),
+                    });
+                }
+
+                if choice.finish_reason.is_some() {
+                    finished = true;
+                }
+            }
+        }
+    }
+
+    info!(
+        "Stream finished: {} chars, {} tool calls",
+        content.len(),
+        tool_calls.len()
+    );
+
+    let response_message = ChatMessage {
+        id: format!("msg-{}", chrono::Utc::now().timestamp_millis()),
+        role: MessageRole::Assistant,
+        content,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        context_paths: None,
+        is_streaming: None,
+        error: None,
+        tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+        images: None,
+        interrupted: None,
+    };
+
+    Ok(InferenceResponse {
+        message: response_message,
+        is_complete: finished,
+        usage,
+        inference_time_ms: Some(start_time.elapsed().as_millis() as u64),
+    })
+}
+
+fn models_url(endpoint: &str) -> String {
     // Support both with and without /v1 prefix
-    let url = if endpoint.ends_with("/v1") || endpoint.contains("/v1/") {
+    if endpoint.ends_with("/v1") || endpoint.contains("/v1/") {
         format!("{}/models", endpoint.trim_end_matches('/'))
     } else {
         format!("{}/v1/models", endpoint.trim_end_matches('/'))
-    };
+    }
+}
 
-    match reqwest::get(&url).await {
+/// Check if OpenAI-compatible endpoint is available
+pub async fn check_openai_compatible_availability(endpoint: &str) -> Result<bool, AIError> {
+    match reqwest::get(&models_url(endpoint)).await {
         Ok(response) => Ok(response.status().is_success()),
         Err(_) => Ok(false),
     }
 }
 
+#[derive(Deserialize)]
+struct OpenAIModelList {
+    data: Vec<OpenAIModelEntry>,
+}
+
+#[derive(Deserialize)]
+struct OpenAIModelEntry {
+    id: String,
+}
+
+/// List the models served by an OpenAI-compatible endpoint (`GET /v1/models`),
+/// authenticating with `api_key` or the stored key
+pub async fn list_openai_compatible_models(
+    endpoint: &str,
+    api_key: Option<&str>,
+) -> Result<Vec<ModelConfig>, AIError> {
+    let key = secrets::resolve_api_key(&ModelProvider::OpenAICompatible, api_key);
+    list_models_with_key(endpoint, key.as_deref()).await
+}
+
+/// List the models of an endpoint the user never configured, such as a
+/// server found by discovery. No key is sent: the stored one belongs to the
+/// user's own endpoint, not to whatever answers on a local port.
+pub async fn list_unauthenticated_models(endpoint: &str) -> Result<Vec<ModelConfig>, AIError> {
+    list_models_with_key(endpoint, None).await
+}
+
+async fn list_models_with_key(
+    endpoint: &str,
+    api_key: Option<&str>,
+) -> Result<Vec<ModelConfig>, AIError> {
+    let mut request = reqwest::Client::new().get(models_url(endpoint));
+    if let Some(key) = api_key {
+        request = request.header("Authorization", format!("Bearer {}", key));
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| map_request_error("OpenAI-compatible model list", &e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(map_status_error("OpenAI-compatible model list", status, None, &body));
+    }
+    let list: OpenAIModelList = response
+        .json()
+        .await
+        .map_err(|e| map_request_error("OpenAI-compatible model list", &e))?;
+
+    Ok(list
+        .data
+        .into_iter()
+        .map(|m| ModelConfig {
+            id: format!("openai-compatible-{}", m.id),
+            name: m.id.clone(),
+            provider: ModelProvider::OpenAICompatible,
+            model_id: m.id,
+            parameters: ModelParameters {
+                temperature: 0.7,
+                top_p: 0.9,
+                max_tokens: 2048,
+                stream: true,
+                stop_sequences: None,
+                context_window: Some(4096),
+            },
+            endpoint: Some(endpoint.to_string()),
+            api_key: None,
+            is_available: true,
+            size_bytes: None,
+            recommended_for: vec![AIMode::QA, AIMode::Agent],
+            adapter: None,
+        })
+        .collect())
+}
+
 /// Get OpenAI-compatible provider status
 pub async fn get_openai_compatible_status(
     endpoint: &str,
     api_key: Option<&str>,
 ) -> ProviderStatus {
-    let is_available = check_openai_compatible_availability(endpoint)
-        .await
-        .unwrap_or(false);
-
-    // For OpenAI-compatible, we can't easily list models without more info
-    // User will need to manually configure models
-    let available_models = vec![];
-
-    let error = if !is_available {
-        Some(format!("Cannot connect to endpoint: {}", endpoint))
-    } else {
-        None
+    let (is_available, available_models, error) = match list_openai_compatible_models(endpoint, api_key).await {
+        Ok(models) => (true, models, None),
+        // Some servers answer but don't implement the model list
+        Err(_) if check_openai_compatible_availability(endpoint).await.unwrap_or(false) => (true, vec![], None),
+        Err(_) => (false, vec![], Some(format!("Cannot connect to endpoint: {}", endpoint))),
     };
 
     ProviderStatus {
@@ -272,3 +579,61 @@ pub async fn get_openai_compatible_status(
         error,
     }
 }
+
+/// Status from the local servers found on their default ports
+async fn get_discovered_status() -> ProviderStatus {
+    let servers = discover_local_servers().await;
+    ProviderStatus {
+        provider: ModelProvider::OpenAICompatible,
+        is_available: !servers.is_empty(),
+        version: None,
+        available_models: servers.into_iter().flat_map(|s| s.models).collect(),
+        error: None,
+    }
+}
+
+/// OpenAI-compatible implementation of the provider interface
+pub struct OpenAICompatibleProvider;
+
+#[async_trait]
+impl AIProvider for OpenAICompatibleProvider {
+    fn kind(&self) -> ModelProvider {
+        ModelProvider::OpenAICompatible
+    }
+
+    fn supports_tools(&self) -> bool {
+        true
+    }
+
+    fn supports_images(&self) -> bool {
+        true
+    }
+
+    async fn status(&self, endpoint: Option<&str>) -> ProviderStatus {
+        match endpoint {
+            Some(ep) => get_openai_compatible_status(ep, None).await,
+            // Without a configured endpoint, look for servers running locally
+            None => get_discovered_status().await,
+        }
+    }
+
+    async fn list_models(&self, endpoint: Option<&str>) -> Result<Vec<ModelConfig>, AIError> {
+        match endpoint {
+            Some(ep) => list_openai_compatible_models(ep, None).await,
+            None => Ok(discover_local_servers()
+                .await
+                .into_iter()
+                .flat_map(|s| s.models)
+                .collect()),
+        }
+    }
+
+    async fn infer_stream(
+        &self,
+        request: &InferenceRequest,
+        sink: InferenceSink,
+        cancel: CancellationToken,
+    ) -> Result<InferenceResponse, AIError> {
+        run_openai_compatible_inference(request, &sink, &cancel).await
+    }
+}
@@ -0,0 +1,149 @@
+// Recommend - Hardware-aware choice of the default embedded model
+//
+// New users shouldn't have to find out by trial and error which embedded
+// model their machine can run. The accelerator report gives the device models
+// run on and the memory they must fit in; every embedded model is checked
+// against that budget (weights plus room for the KV cache and activations)
+// and marked usable or not, with the reason. The default is the best model
+// that fits with headroom; on the CPU it is also kept small enough to answer
+// at a usable speed. Requests that name no model fall back to it.
+
+use crate::ai::accelerators::{self, AcceleratorReport};
+use crate::ai::providers::embedded_models;
+use crate::mcp::native_server::format_bytes;
+use serde::Serialize;
+
+/// Memory a loaded model needs relative to its weights
+const LOAD_OVERHEAD: f64 = 1.3;
+/// Largest model recommended for CPU inference on machines with few cores
+const CPU_COMFORT_BYTES: u64 = 1_000_000_000;
+/// Cores from which larger models still answer at a usable speed on the CPU
+const CPU_COMFORT_CORES: usize = 8;
+/// Embedded models from best to most modest answers
+const QUALITY_ORDER: [&str; 3] = ["phi-2", "stablelm-2-1.6b", "qwen1.5:0.5b"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelRecommendation {
+    pub model_id: String,
+    /// Approximate size of the weights
+    pub size_bytes: u64,
+    /// Memory the model needs once loaded
+    pub required_bytes: u64,
+    /// The model fits the inference device's memory budget
+    pub usable: bool,
+    pub recommended: bool,
+    /// Why the model is recommended, usable or not
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecommendedModels {
+    /// Best embedded model for this machine, None when none fits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_model_id: Option<String>,
+    pub models: Vec<ModelRecommendation>,
+    /// Device embedded models run on: "cuda", "metal" or "cpu"
+    pub inference_device: String,
+    pub budget_bytes: u64,
+    /// Summary of the decision, for display
+    pub rationale: String,
+}
+
+fn required_bytes(size_bytes: u64) -> u64 {
+    (size_bytes as f64 * LOAD_OVERHEAD) as u64
+}
+
+fn quality_rank(model_id: &str) -> usize {
+    QUALITY_ORDER
+        .iter()
+        .position(|id| *id == model_id)
+        .unwrap_or(QUALITY_ORDER.len())
+}
+
+/// Rank the embedded models against an accelerator report
+pub fn recommend_for(report: &AcceleratorReport, cores: usize) -> RecommendedModels {
+    let budget = report.model_budget_bytes;
+    let on_cpu = report.inference_device == "cpu";
+    let comfort_limit = if on_cpu && cores < CPU_COMFORT_CORES {
+        Some(CPU_COMFORT_BYTES)
+    } else {
+        None
+    };
+
+    let mut models: Vec<ModelRecommendation> = embedded_models()
+        .into_iter()
+        .map(|(model_id, size_bytes)| {
+            let required = required_bytes(size_bytes);
+            let usable = required <= budget;
+            let reason = if usable {
+                format!("Needs about {} of the {} available", format_bytes(required), format_bytes(budget))
+            } else {
+                format!(
+                    "Needs about {} but only {} is available; loading it would fail or swap heavily",
+                    format_bytes(required),
+                    format_bytes(budget)
+                )
+            };
+            ModelRecommendation {
+                model_id: model_id.to_string(),
+                size_bytes,
+                required_bytes: required,
+                usable,
+                recommended: false,
+                reason,
+            }
+        })
+        .collect();
+
+    let default_index = models
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| m.usable && !comfort_limit.is_some_and(|limit| m.size_bytes > limit))
+        .min_by_key(|(_, m)| quality_rank(&m.model_id))
+        .map(|(i, _)| i)
+        // On a slow CPU the smallest usable model is still better than none
+        .or_else(|| models.iter().position(|m| m.usable));
+
+    let rationale = match default_index {
+        Some(index) => {
+            let model = &mut models[index];
+            model.recommended = true;
+            let why = if comfort_limit.is_some() && model.size_bytes <= CPU_COMFORT_BYTES {
+                format!(
+                    "Models run on the CPU with {} cores, so the small {} keeps replies responsive",
+                    cores, model.model_id
+                )
+            } else {
+                format!(
+                    "{} is the best model that fits the {} budget on the {}",
+                    model.model_id,
+                    format_bytes(budget),
+                    report.inference_device.to_uppercase()
+                )
+            };
+            model.reason = format!("Recommended. {}", model.reason);
+            format!("{}. {}.", why, report.budget_reason)
+        }
+        None => format!(
+            "No embedded model fits the {} available ({}). Use Ollama or an OpenAI-compatible server instead.",
+            format_bytes(budget),
+            report.budget_reason
+        ),
+    };
+
+    RecommendedModels {
+        default_model_id: default_index.map(|i| models[i].model_id.clone()),
+        models,
+        inference_device: report.inference_device.clone(),
+        budget_bytes: budget,
+        rationale,
+    }
+}
+
+/// Rank the embedded models for this machine
+pub fn recommend() -> RecommendedModels {
+    let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    recommend_for(&accelerators::report(), cores)
+}
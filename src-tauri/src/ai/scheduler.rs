@@ -0,0 +1,173 @@
+// Inference Scheduler
+//
+// Queues inference requests so that a locally loaded model, whose KV cache is
+// shared between requests, only runs one generation at a time. Remote providers
+// are not queued and run in parallel. Queue changes are emitted as
+// `ai-queue-update` events so the UI can show each session's position.
+
+use crate::ai::providers::{AIProvider, InferenceSink};
+use crate::ai::{AIError, AIErrorType};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+use tokio_util::sync::CancellationToken;
+
+/// Snapshot of one model's queue, emitted whenever it changes
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueUpdate {
+    pub model_key: String,
+    pub running: Option<String>,
+    pub waiting: Vec<String>,
+}
+
+/// Queue for a single local model
+struct ModelQueue {
+    key: String,
+    lock: Arc<tokio::sync::Mutex<()>>,
+    state: Mutex<QueueState>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    running: Option<String>,
+    waiting: Vec<String>,
+}
+
+impl ModelQueue {
+    fn new(key: String) -> Self {
+        Self {
+            key,
+            lock: Arc::new(tokio::sync::Mutex::new(())),
+            state: Mutex::new(QueueState::default()),
+        }
+    }
+
+    fn update<F: FnOnce(&mut QueueState)>(&self, sink: &InferenceSink, f: F) {
+        let snapshot = {
+            let mut state = self.state.lock().unwrap();
+            f(&mut state);
+            QueueUpdate {
+                model_key: self.key.clone(),
+                running: state.running.clone(),
+                waiting: state.waiting.clone(),
+            }
+        };
+        sink.event("ai-queue-update", snapshot);
+    }
+}
+
+fn queue_key(provider: &dyn AIProvider, model_id: &str) -> String {
+    format!("{}:{}", provider.kind().id(), model_id)
+}
+
+/// Held while a request is allowed to run; releases the model slot on drop
+pub struct SchedulerPermit {
+    queue: Option<Arc<ModelQueue>>,
+    sink: InferenceSink,
+    _guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for SchedulerPermit {
+    fn drop(&mut self) {
+        if let Some(queue) = &self.queue {
+            queue.update(&self.sink, |state| state.running = None);
+        }
+    }
+}
+
+/// Scheduler shared by all inference commands, kept in managed state
+#[derive(Default)]
+pub struct InferenceScheduler {
+    queues: Mutex<HashMap<String, Arc<ModelQueue>>>,
+}
+
+impl InferenceScheduler {
+    /// Wait until `session_id` may run on `model_id`.
+    ///
+    /// Returns immediately for remote providers. For local providers the
+    /// request waits in FIFO order behind other sessions using the same model,
+    /// and gives up its place if `cancel` fires while waiting.
+    pub async fn acquire(
+        &self,
+        provider: &dyn AIProvider,
+        model_id: &str,
+        session_id: &str,
+        sink: &InferenceSink,
+        cancel: &CancellationToken,
+    ) -> Result<SchedulerPermit, AIError> {
+        if !provider.is_local() {
+            return Ok(SchedulerPermit {
+                queue: None,
+                sink: sink.clone(),
+                _guard: None,
+            });
+        }
+
+        let key = queue_key(provider, model_id);
+        let queue = {
+            let mut queues = self.queues.lock().unwrap();
+            queues
+                .entry(key.clone())
+                .or_insert_with(|| Arc::new(ModelQueue::new(key)))
+                .clone()
+        };
+
+        queue.update(sink, |state| state.waiting.push(session_id.to_string()));
+
+        let guard = tokio::select! {
+            guard = queue.lock.clone().lock_owned() => guard,
+            _ = cancel.cancelled() => {
+                queue.update(sink, |state| state.waiting.retain(|s| s != session_id));
+                return Err(AIError {
+                    error_type: AIErrorType::InferenceFailed,
+                    message: "Inference cancelled by user".to_string(),
+                    details: None,
+                    suggested_actions: None,
+                });
+            }
+        };
+
+        queue.update(sink, |state| {
+            state.waiting.retain(|s| s != session_id);
+            state.running = Some(session_id.to_string());
+        });
+
+        Ok(SchedulerPermit {
+            queue: Some(queue),
+            sink: sink.clone(),
+            _guard: Some(guard),
+        })
+    }
+
+    /// Whether a local model is running or has requests waiting.
+    ///
+    /// Background work checks this so it never delays an interactive request.
+    pub fn is_busy(&self, provider: &dyn AIProvider, model_id: &str) -> bool {
+        if !provider.is_local() {
+            return false;
+        }
+        let queues = self.queues.lock().unwrap();
+        queues.get(&queue_key(provider, model_id)).is_some_and(|q| {
+            let state = q.state.lock().unwrap();
+            state.running.is_some() || !state.waiting.is_empty()
+        })
+    }
+
+    /// Current state of every local model queue
+    pub fn snapshot(&self) -> Vec<QueueUpdate> {
+        let queues = self.queues.lock().unwrap();
+        queues
+            .values()
+            .map(|q| {
+                let state = q.state.lock().unwrap();
+                QueueUpdate {
+                    model_key: q.key.clone(),
+                    running: state.running.clone(),
+                    waiting: state.waiting.clone(),
+                }
+            })
+            .collect()
+    }
+}
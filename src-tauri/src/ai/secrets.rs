@@ -0,0 +1,107 @@
+// Secrets - API keys and tokens in the OS credential store
+//
+// Provider API keys, the HuggingFace token and object storage secret keys
+// live in the platform keychain
+// (macOS Keychain, Windows Credential Manager, Secret Service on Linux) and
+// are looked up when a request needs them. They are never written to the
+// app's JSON state.
+
+use crate::ai::{AIError, AIErrorType, ModelProvider};
+use hf_hub::api::tokio::{Api, ApiBuilder};
+use tracing::warn;
+
+/// Service name the credentials are stored under
+const SERVICE: &str = "com.helium.v3.core";
+
+/// Secret name of the HuggingFace access token
+pub const HF_TOKEN: &str = "huggingface";
+
+fn secret_error(message: String) -> AIError {
+    AIError {
+        error_type: AIErrorType::InvalidConfiguration,
+        message,
+        details: None,
+        suggested_actions: None,
+    }
+}
+
+/// Secret name of a provider's API key
+pub fn provider_key(provider: &ModelProvider) -> String {
+    format!("provider:{}", provider.id())
+}
+
+/// Only provider keys, the HuggingFace token and object storage keys
+/// (`s3:<account id>`) may be stored
+fn validate_name(name: &str) -> Result<(), AIError> {
+    let known = name == HF_TOKEN
+        || name.strip_prefix("s3:").is_some_and(|id| !id.is_empty())
+        || name
+            .strip_prefix("provider:")
+            .is_some_and(|id| ModelProvider::from_id(id).is_some());
+    if known {
+        Ok(())
+    } else {
+        Err(secret_error(format!("Unknown secret: {}", name)))
+    }
+}
+
+fn entry(name: &str) -> Result<keyring::Entry, AIError> {
+    validate_name(name)?;
+    keyring::Entry::new(SERVICE, name)
+        .map_err(|e| secret_error(format!("Credential store unavailable: {}", e)))
+}
+
+/// Read a secret; None if it was never set
+pub fn get(name: &str) -> Result<Option<String>, AIError> {
+    match entry(name)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(secret_error(format!("Failed to read {}: {}", name, e))),
+    }
+}
+
+/// Store a secret, replacing any previous value
+pub fn set(name: &str, value: &str) -> Result<(), AIError> {
+    if value.trim().is_empty() {
+        return Err(secret_error("Secret value cannot be empty".to_string()));
+    }
+    entry(name)?
+        .set_password(value.trim())
+        .map_err(|e| secret_error(format!("Failed to store {}: {}", name, e)))
+}
+
+/// Remove a secret; returns false if it was not set
+pub fn delete(name: &str) -> Result<bool, AIError> {
+    match entry(name)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(secret_error(format!("Failed to delete {}: {}", name, e))),
+    }
+}
+
+/// API key for a provider: the one in the request, else the stored one
+pub fn resolve_api_key(provider: &ModelProvider, explicit: Option<&str>) -> Option<String> {
+    if let Some(key) = explicit.filter(|k| !k.is_empty()) {
+        return Some(key.to_string());
+    }
+    get(&provider_key(provider)).unwrap_or_else(|e| {
+        warn!("{}", e.message);
+        None
+    })
+}
+
+/// HuggingFace API client, authenticated with the stored token if there is one
+pub fn hf_api() -> Result<Api, AIError> {
+    let mut builder = ApiBuilder::new();
+    match get(HF_TOKEN) {
+        Ok(Some(token)) => builder = builder.with_token(Some(token)),
+        Ok(None) => {}
+        Err(e) => warn!("{}", e.message),
+    }
+    builder.build().map_err(|e| AIError {
+        error_type: AIErrorType::NetworkError,
+        message: format!("Failed to initialize HF API: {}", e),
+        details: None,
+        suggested_actions: None,
+    })
+}
@@ -0,0 +1,266 @@
+// Summarize - Map-reduce summarization of large documents
+//
+// Splits a large text, markdown, log or PDF file into chunks sized for the
+// model's context window, summarizes each chunk (map), then combines the chunk
+// summaries (reduce). Each chunk summary keeps its source offsets so the agent
+// can point back into a 50MB log without ever loading it into context. Offsets
+// count characters of the decoded text (invalid UTF-8 replaced, PDFs
+// extracted), the same text for every kind of file, rather than file bytes.
+
+use crate::ai::providers::{infer_once, AIProvider, InferenceSink};
+use crate::ai::{AIError, AIErrorType, AIMode, ChatMessage, InferenceRequest, MessageRole, ModelConfig};
+use serde::Serialize;
+use std::path::Path;
+use tracing::info;
+
+/// Most chunks summarized in the map step; larger files are sampled evenly
+const MAX_MAP_CHUNKS: usize = 24;
+/// Chunk summaries combined per reduce call
+const REDUCE_FAN_IN: usize = 8;
+/// Largest file we will read
+const MAX_FILE_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Summary of one chunk with its location in the source
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkSummary {
+    /// Character offsets into the decoded text
+    pub start_offset: usize,
+    pub end_offset: usize,
+    /// 1-based line the chunk starts on
+    pub start_line: usize,
+    pub summary: String,
+}
+
+/// Result of summarizing a file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSummary {
+    pub path: String,
+    pub summary: String,
+    pub chunks: Vec<ChunkSummary>,
+    /// Characters of the decoded text
+    pub total_chars: usize,
+    /// Characters actually read by the model (less than total when sampled)
+    pub covered_chars: usize,
+    pub model_id: String,
+}
+
+/// Progress emitted as `file-summary-progress`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryProgress {
+    pub path: String,
+    pub completed: usize,
+    pub total: usize,
+    pub stage: String,
+}
+
+struct Chunk<'a> {
+    /// Character offset of `text`
+    start: usize,
+    start_line: usize,
+    text: &'a str,
+}
+
+fn summarize_error(message: String) -> AIError {
+    AIError {
+        error_type: AIErrorType::InvalidConfiguration,
+        message,
+        details: None,
+        suggested_actions: None,
+    }
+}
+
+/// Read a file as text, extracting it from PDFs
+fn load_text(path: &Path) -> Result<String, AIError> {
+    let size = std::fs::metadata(path)
+        .map_err(|e| summarize_error(format!("Cannot read {}: {}", path.display(), e)))?
+        .len();
+    if size > MAX_FILE_BYTES {
+        return Err(summarize_error(format!(
+            "File too large to summarize: {} bytes (max: {} bytes)",
+            size, MAX_FILE_BYTES
+        )));
+    }
+
+    let is_pdf = path
+        .extension()
+        .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case("pdf"));
+    if is_pdf {
+        return pdf_extract::extract_text(path)
+            .map_err(|e| summarize_error(format!("Failed to extract text from PDF: {}", e)));
+    }
+
+    let bytes = std::fs::read(path)
+        .map_err(|e| summarize_error(format!("Cannot read {}: {}", path.display(), e)))?;
+    if bytes.iter().take(8192).any(|&b| b == 0) {
+        return Err(summarize_error(format!("{} looks like a binary file", path.display())));
+    }
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Split text into chunks of about `chunk_chars`, breaking at line ends
+fn split_chunks(text: &str, chunk_chars: usize) -> Vec<Chunk<'_>> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut start_char = 0;
+    let mut line = 1;
+    while start < text.len() {
+        let mut end = (start + chunk_chars).min(text.len());
+        while !text.is_char_boundary(end) {
+            end += 1;
+        }
+        if end < text.len() {
+            if let Some(newline) = text[start..end].rfind('\n') {
+                if newline > chunk_chars / 2 {
+                    end = start + newline + 1;
+                }
+            }
+        }
+        let slice = &text[start..end];
+        chunks.push(Chunk {
+            start: start_char,
+            start_line: line,
+            text: slice,
+        });
+        line += slice.matches('\n').count();
+        start_char += slice.chars().count();
+        start = end;
+    }
+    chunks
+}
+
+/// Pick at most `max` chunks spread evenly over the document
+fn sample_chunks(chunks: Vec<Chunk<'_>>, max: usize) -> Vec<Chunk<'_>> {
+    if chunks.len() <= max {
+        return chunks;
+    }
+    let step = (chunks.len() - 1) as f64 / (max - 1) as f64;
+    let picks: Vec<usize> = (0..max).map(|i| (i as f64 * step).round() as usize).collect();
+    chunks
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| picks.contains(i))
+        .map(|(_, c)| c)
+        .collect()
+}
+
+fn request(model_config: &ModelConfig, instruction: &str, content: String) -> InferenceRequest {
+    let message = |role: MessageRole, content: String| ChatMessage {
+        id: uuid::Uuid::new_v4().to_string(),
+        role,
+        content,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        context_paths: None,
+        is_streaming: None,
+        error: None,
+        tool_calls: None,
+        images: None,
+        interrupted: None,
+    };
+    InferenceRequest {
+        session_id: format!("summarize-{}", uuid::Uuid::new_v4()),
+        model_config: model_config.clone(),
+        messages: vec![
+            message(MessageRole::System, instruction.to_string()),
+            message(MessageRole::User, content),
+        ],
+        fs_context: None,
+        mode: AIMode::QA,
+        tools: None,
+        preset_id: None,
+        conversation_id: None,
+    }
+}
+
+/// Summarize a file with map-reduce over its chunks
+pub async fn summarize_file(
+    provider: &dyn AIProvider,
+    model_config: &ModelConfig,
+    path: &str,
+    sink: &InferenceSink,
+) -> Result<FileSummary, AIError> {
+    let text = load_text(Path::new(path))?;
+    let name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+
+    // Leave room in the context for the prompt and the answer (~4 chars per token)
+    let context_tokens = model_config.parameters.context_window.unwrap_or(4096) as usize;
+    let chunk_chars = (context_tokens * 3).clamp(2_000, 48_000);
+
+    let mut model_config = model_config.clone();
+    model_config.parameters.temperature = 0.2;
+    model_config.parameters.max_tokens = model_config.parameters.max_tokens.min(400);
+
+    let chunks = sample_chunks(split_chunks(&text, chunk_chars), MAX_MAP_CHUNKS);
+    let total_chars = text.chars().count();
+    let covered_chars: usize = chunks.iter().map(|c| c.text.chars().count()).sum();
+    let total = chunks.len();
+    info!("Summarizing {} ({} characters) in {} chunks", path, total_chars, total);
+
+    let progress = |completed: usize, stage: &str| {
+        sink.event(
+            "file-summary-progress",
+            SummaryProgress {
+                path: path.to_string(),
+                completed,
+                total,
+                stage: stage.to_string(),
+            },
+        );
+    };
+
+    // Map
+    let map_instruction = format!(
+        "You summarize one section of the file {}. Write 2-4 sentences covering the key facts, \
+errors, names and numbers in this section. Reply with the summary only.",
+        name
+    );
+    let mut chunk_summaries = Vec::with_capacity(total);
+    for (i, chunk) in chunks.iter().enumerate() {
+        progress(i, "map");
+        let summary = infer_once(provider, &request(&model_config, &map_instruction, chunk.text.to_string())).await?;
+        chunk_summaries.push(ChunkSummary {
+            start_offset: chunk.start,
+            end_offset: chunk.start + chunk.text.chars().count(),
+            start_line: chunk.start_line,
+            summary: summary.trim().to_string(),
+        });
+    }
+
+    // Reduce, in groups, until a single summary is left
+    let reduce_instruction = format!(
+        "These are summaries of consecutive sections of the file {}{}. Combine them into one \
+coherent summary of at most two paragraphs. Reply with the summary only.",
+        name,
+        if covered_chars < total_chars { " (sampled evenly across the file)" } else { "" }
+    );
+    let mut level: Vec<String> = chunk_summaries.iter().map(|c| c.summary.clone()).collect();
+    while level.len() > 1 {
+        progress(total, "reduce");
+        let mut next = Vec::new();
+        for group in level.chunks(REDUCE_FAN_IN) {
+            let joined = group
+                .iter()
+                .enumerate()
+                .map(|(i, s)| format!("Section {}:\n{}", i + 1, s))
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            next.push(infer_once(provider, &request(&model_config, &reduce_instruction, joined)).await?);
+        }
+        level = next;
+    }
+    progress(total, "done");
+
+    Ok(FileSummary {
+        path: path.to_string(),
+        summary: level.pop().unwrap_or_default().trim().to_string(),
+        chunks: chunk_summaries,
+        total_chars,
+        covered_chars,
+        model_id: model_config.model_id.clone(),
+    })
+}
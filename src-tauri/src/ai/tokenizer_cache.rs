@@ -0,0 +1,82 @@
+// Tokenizer Cache - Parsed tokenizers shared between requests
+//
+// A tokenizer.json is 5-10MB of JSON; parsing it costs more than most short
+// generations. Parsed tokenizers are kept in a small LRU cache keyed by model
+// so model loads, token counting and generation share one instance. Entries
+// are evicted together with their model or when the cache is full.
+
+use lazy_static::lazy_static;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokenizers::Tokenizer;
+use tracing::debug;
+
+/// Tokenizers kept parsed at once
+const CAPACITY: usize = 4;
+
+/// LRU cache of parsed tokenizers, most recently used last
+pub struct TokenizerCache {
+    entries: Vec<(String, Arc<Tokenizer>)>,
+    capacity: usize,
+}
+
+lazy_static! {
+    pub static ref TOKENIZER_CACHE: Mutex<TokenizerCache> = Mutex::new(TokenizerCache::new(CAPACITY));
+}
+
+impl TokenizerCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Fetch a cached tokenizer, marking it as most recently used
+    pub fn get(&mut self, key: &str) -> Option<Arc<Tokenizer>> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(index);
+        let tokenizer = entry.1.clone();
+        self.entries.push(entry);
+        Some(tokenizer)
+    }
+
+    /// Cache a tokenizer, evicting the least recently used one if full
+    pub fn insert(&mut self, key: &str, tokenizer: Arc<Tokenizer>) {
+        self.entries.retain(|(k, _)| k != key);
+        if self.entries.len() >= self.capacity {
+            self.entries.remove(0);
+        }
+        self.entries.push((key.to_string(), tokenizer));
+    }
+
+    /// Drop a cached tokenizer. Returns false if it was not cached.
+    pub fn evict(&mut self, key: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|(k, _)| k != key);
+        self.entries.len() < before
+    }
+
+    /// Tokenizers currently cached
+    pub fn count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Drop all cached tokenizers, returning how many there were
+    pub fn clear(&mut self) -> usize {
+        std::mem::take(&mut self.entries).len()
+    }
+}
+
+/// Cached tokenizer for `key`, parsed from `path` on first use
+pub fn load(key: &str, path: &Path) -> Result<Arc<Tokenizer>, String> {
+    if let Some(tokenizer) = TOKENIZER_CACHE.lock().unwrap().get(key) {
+        return Ok(tokenizer);
+    }
+
+    // Parse outside the lock; a concurrent load of the same key just wins the race
+    let tokenizer = Arc::new(Tokenizer::from_file(path).map_err(|e| e.to_string())?);
+    debug!("Cached tokenizer for {}", key);
+    TOKENIZER_CACHE.lock().unwrap().insert(key, tokenizer.clone());
+    Ok(tokenizer)
+}
@@ -0,0 +1,311 @@
+// Usage Tracking - Token counts, costs and monthly budgets
+//
+// Records prompt/completion tokens per provider and model for each calendar
+// month, prices them with a configurable table, and enforces per-provider
+// monthly budgets so connecting a paid API can't produce a surprise bill.
+
+use crate::ai::{AIError, AIErrorType, ModelProvider, TokenUsage};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Fraction of a budget at which a warning is emitted
+const WARN_THRESHOLD: f64 = 0.8;
+
+/// Price per million tokens, in USD
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPrice {
+    pub prompt_per_million: f64,
+    pub completion_per_million: f64,
+}
+
+/// What happens once a provider's monthly budget is spent
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetAction {
+    Warn,
+    Block,
+}
+
+/// Monthly spending limit for a provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageBudget {
+    pub provider: ModelProvider,
+    pub monthly_limit_usd: f64,
+    pub action: BudgetAction,
+}
+
+/// Aggregated usage of one model in one month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageRecord {
+    month: String,
+    provider: ModelProvider,
+    model_id: String,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    requests: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageData {
+    #[serde(default)]
+    records: Vec<UsageRecord>,
+    #[serde(default)]
+    budgets: Vec<UsageBudget>,
+    /// User-defined prices, keyed by model id (or model id prefix)
+    #[serde(default)]
+    prices: HashMap<String, ModelPrice>,
+}
+
+/// Usage and cost of one model
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageEntry {
+    pub provider: ModelProvider,
+    pub model_id: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub requests: u64,
+    pub cost_usd: f64,
+}
+
+/// Spending against a provider budget
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetStatus {
+    pub provider: ModelProvider,
+    pub monthly_limit_usd: f64,
+    pub spent_usd: f64,
+    pub action: BudgetAction,
+    pub exceeded: bool,
+}
+
+/// Usage summary for a month
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSummary {
+    pub month: String,
+    pub entries: Vec<UsageEntry>,
+    pub total_cost_usd: f64,
+    pub budgets: Vec<BudgetStatus>,
+}
+
+/// Default prices for common hosted models (USD per million tokens)
+fn default_prices() -> Vec<(&'static str, ModelPrice)> {
+    let price = |prompt, completion| ModelPrice {
+        prompt_per_million: prompt,
+        completion_per_million: completion,
+    };
+    vec![
+        ("gpt-4o-mini", price(0.15, 0.60)),
+        ("gpt-4o", price(2.50, 10.00)),
+        ("gpt-4.1-mini", price(0.40, 1.60)),
+        ("gpt-4.1", price(2.00, 8.00)),
+        ("gpt-3.5-turbo", price(0.50, 1.50)),
+    ]
+}
+
+fn current_month() -> String {
+    chrono::Utc::now().format("%Y-%m").to_string()
+}
+
+/// Persisted usage tracker, kept in managed state
+pub struct UsageTracker {
+    path: PathBuf,
+    data: Mutex<UsageData>,
+}
+
+impl UsageTracker {
+    pub fn load(path: PathBuf) -> Self {
+        let data = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        Self {
+            path,
+            data: Mutex::new(data),
+        }
+    }
+
+    fn persist(&self, data: &UsageData) -> Result<(), String> {
+        storage::save_json(&self.path, data)
+    }
+
+    /// Price for a model: user prices first, then defaults; exact id, then the
+    /// longest matching prefix (so dated snapshots share their family's price)
+    fn price_for(data: &UsageData, model_id: &str) -> Option<ModelPrice> {
+        if let Some(price) = data.prices.get(model_id) {
+            return Some(price.clone());
+        }
+        let user = data.prices.iter().map(|(k, v)| (k.as_str(), v.clone()));
+        let defaults = default_prices().into_iter();
+        user.chain(defaults)
+            .filter(|(prefix, _)| model_id.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, price)| price)
+    }
+
+    fn cost(data: &UsageData, record: &UsageRecord) -> f64 {
+        if record.provider != ModelProvider::OpenAICompatible {
+            return 0.0;
+        }
+        Self::price_for(data, &record.model_id).map_or(0.0, |p| {
+            (record.prompt_tokens as f64 * p.prompt_per_million
+                + record.completion_tokens as f64 * p.completion_per_million)
+                / 1_000_000.0
+        })
+    }
+
+    fn budget_status(data: &UsageData, budget: &UsageBudget, month: &str) -> BudgetStatus {
+        let spent_usd: f64 = data
+            .records
+            .iter()
+            .filter(|r| r.month == month && r.provider == budget.provider)
+            .map(|r| Self::cost(data, r))
+            .sum();
+        BudgetStatus {
+            provider: budget.provider.clone(),
+            monthly_limit_usd: budget.monthly_limit_usd,
+            spent_usd,
+            action: budget.action.clone(),
+            exceeded: spent_usd >= budget.monthly_limit_usd,
+        }
+    }
+
+    /// Refuse to run if the provider's budget is spent and set to block
+    pub fn check_budget(&self, provider: &ModelProvider) -> Result<(), AIError> {
+        let data = self.data.lock().unwrap();
+        let month = current_month();
+        let Some(budget) = data.budgets.iter().find(|b| &b.provider == provider) else {
+            return Ok(());
+        };
+
+        let status = Self::budget_status(&data, budget, &month);
+        if status.exceeded && status.action == BudgetAction::Block {
+            return Err(AIError {
+                error_type: AIErrorType::BudgetExceeded,
+                message: format!(
+                    "Monthly budget of ${:.2} for {} is spent (${:.2} used)",
+                    status.monthly_limit_usd,
+                    provider.id(),
+                    status.spent_usd
+                ),
+                details: None,
+                suggested_actions: Some(vec![
+                    "Raise the monthly budget in settings".to_string(),
+                    "Switch to a local model".to_string(),
+                ]),
+            });
+        }
+        Ok(())
+    }
+
+    /// Record a completed request. Returns the budget status when spending
+    /// crosses the warning threshold.
+    pub fn record(&self, provider: &ModelProvider, model_id: &str, usage: &TokenUsage) -> Option<BudgetStatus> {
+        let mut data = self.data.lock().unwrap();
+        let month = current_month();
+
+        match data
+            .records
+            .iter_mut()
+            .find(|r| r.month == month && &r.provider == provider && r.model_id == model_id)
+        {
+            Some(record) => {
+                record.prompt_tokens += usage.prompt_tokens as u64;
+                record.completion_tokens += usage.completion_tokens as u64;
+                record.requests += 1;
+            }
+            None => data.records.push(UsageRecord {
+                month: month.clone(),
+                provider: provider.clone(),
+                model_id: model_id.to_string(),
+                prompt_tokens: usage.prompt_tokens as u64,
+                completion_tokens: usage.completion_tokens as u64,
+                requests: 1,
+            }),
+        }
+
+        if let Err(e) = self.persist(&data) {
+            warn!("Failed to save usage: {}", e);
+        }
+
+        let budget = data.budgets.iter().find(|b| &b.provider == provider)?;
+        let status = Self::budget_status(&data, budget, &month);
+        (status.spent_usd >= status.monthly_limit_usd * WARN_THRESHOLD).then_some(status)
+    }
+
+    /// Usage and spending for `month` (YYYY-MM), defaulting to the current month
+    pub fn summary(&self, month: Option<String>) -> UsageSummary {
+        let data = self.data.lock().unwrap();
+        let month = month.unwrap_or_else(current_month);
+
+        let entries: Vec<UsageEntry> = data
+            .records
+            .iter()
+            .filter(|r| r.month == month)
+            .map(|r| UsageEntry {
+                provider: r.provider.clone(),
+                model_id: r.model_id.clone(),
+                prompt_tokens: r.prompt_tokens,
+                completion_tokens: r.completion_tokens,
+                requests: r.requests,
+                cost_usd: Self::cost(&data, r),
+            })
+            .collect();
+        let total_cost_usd = entries.iter().map(|e| e.cost_usd).sum();
+        let budgets = data
+            .budgets
+            .iter()
+            .map(|b| Self::budget_status(&data, b, &month))
+            .collect();
+
+        UsageSummary {
+            month,
+            entries,
+            total_cost_usd,
+            budgets,
+        }
+    }
+
+    /// Set or clear (limit = None) a provider's monthly budget
+    pub fn set_budget(
+        &self,
+        provider: ModelProvider,
+        monthly_limit_usd: Option<f64>,
+        action: BudgetAction,
+    ) -> Result<(), String> {
+        let mut data = self.data.lock().unwrap();
+        data.budgets.retain(|b| b.provider != provider);
+        if let Some(limit) = monthly_limit_usd {
+            if limit < 0.0 {
+                return Err("Budget cannot be negative".to_string());
+            }
+            data.budgets.push(UsageBudget {
+                provider,
+                monthly_limit_usd: limit,
+                action,
+            });
+        }
+        self.persist(&data)
+    }
+
+    /// Set or clear (price = None) the price used for a model id or prefix
+    pub fn set_price(&self, model_id: String, price: Option<ModelPrice>) -> Result<(), String> {
+        let mut data = self.data.lock().unwrap();
+        match price {
+            Some(price) => data.prices.insert(model_id, price),
+            None => data.prices.remove(&model_id),
+        };
+        self.persist(&data)
+    }
+}
+
+/// Rough token estimate for providers that don't report usage
+pub fn estimate_tokens(text: &str) -> u32 {
+    (text.chars().count() as u32).div_ceil(4)
+}
@@ -0,0 +1,421 @@
+// Whisper Speech-to-Text - Offline voice input
+//
+// Transcribes recorded audio with candle's Whisper implementation so voice
+// prompts never leave the machine. Audio is accepted as WAV (PCM 16-bit or
+// 32-bit float, any channel count / sample rate) and converted to 16kHz mono
+// before computing the log-mel spectrogram.
+
+use crate::ai::accelerators;
+use crate::ai::bandwidth::DownloadProgress;
+use crate::ai::http::fetch_hf_file;
+use crate::ai::tokenizer_cache;
+use crate::ai::providers::DownloadStatus;
+use crate::ai::{AIError, AIErrorType};
+use candle_core::{DType, Device, IndexOp, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::whisper::{self as m, audio, model::Whisper, Config};
+use crate::ai::secrets;
+use hf_hub::{Repo, RepoType};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokenizers::Tokenizer;
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+/// Supported Whisper checkpoints: (model id, HuggingFace repo)
+const WHISPER_MODELS: &[(&str, &str)] = &[
+    ("whisper-tiny", "openai/whisper-tiny"),
+    ("whisper-base", "openai/whisper-base"),
+];
+
+const DEFAULT_WHISPER_MODEL: &str = "whisper-tiny";
+
+struct LoadedWhisper {
+    model_id: String,
+    model: Whisper,
+    tokenizer: Arc<Tokenizer>,
+    config: Config,
+    device: Device,
+}
+
+lazy_static! {
+    // The most recently used Whisper model stays loaded between transcriptions
+    static ref WHISPER: Mutex<Option<LoadedWhisper>> = Mutex::new(None);
+}
+
+/// Result of a transcription
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionResult {
+    pub text: String,
+    pub model_id: String,
+    pub audio_duration_secs: f32,
+    pub processing_ms: u64,
+}
+
+fn whisper_error(error_type: AIErrorType, message: String) -> AIError {
+    AIError {
+        error_type,
+        message,
+        details: None,
+        suggested_actions: None,
+    }
+}
+
+fn whisper_repo(model_id: &str) -> Result<&'static str, AIError> {
+    WHISPER_MODELS
+        .iter()
+        .find(|(id, _)| *id == model_id)
+        .map(|(_, repo)| *repo)
+        .ok_or_else(|| AIError {
+            error_type: AIErrorType::InvalidConfiguration,
+            message: format!("Unknown Whisper model: {}", model_id),
+            details: None,
+            suggested_actions: Some(vec!["Use whisper-tiny or whisper-base".to_string()]),
+        })
+}
+
+/// Download (or locate in the HF cache) the files for a Whisper model
+pub async fn ensure_whisper_files(
+    model_id: &str,
+    sender: Option<mpsc::Sender<DownloadStatus>>,
+) -> Result<(PathBuf, PathBuf, PathBuf), AIError> {
+    let repo_id = whisper_repo(model_id)?;
+    let api = secrets::hf_api()?;
+    let repo = Repo::new(repo_id.to_string(), RepoType::Model);
+
+    let files = ["config.json", "tokenizer.json", "model.safetensors"];
+    let mut paths = Vec::new();
+    for (i, file) in files.iter().enumerate() {
+        if let Some(tx) = &sender {
+            let _ = tx.try_send(DownloadStatus {
+                status: format!("Fetching {}...", file),
+                progress: i as f32 / files.len() as f32,
+                bytes_per_second: None,
+            });
+        }
+        debug!("Fetching {} from {}", file, repo_id);
+        let range = (i as f32 / files.len() as f32, (i + 1) as f32 / files.len() as f32);
+        let progress = DownloadProgress::new(sender.clone(), &format!("Downloading {}...", file), range);
+        let path = fetch_hf_file(&api, &repo, file, progress).await?;
+        paths.push(path);
+    }
+
+    if let Some(tx) = &sender {
+        let _ = tx.try_send(DownloadStatus {
+            status: "Ready".to_string(),
+            progress: 1.0,
+            bytes_per_second: None,
+        });
+    }
+
+    let weights = paths.pop().unwrap();
+    let tokenizer = paths.pop().unwrap();
+    let config = paths.pop().unwrap();
+    Ok((config, tokenizer, weights))
+}
+
+fn load_whisper(
+    model_id: &str,
+    config_path: PathBuf,
+    tokenizer_path: PathBuf,
+    weights_path: PathBuf,
+) -> Result<LoadedWhisper, AIError> {
+    let load_err = |e: String| whisper_error(AIErrorType::InvalidConfiguration, e);
+
+    let config: Config = serde_json::from_str(
+        &std::fs::read_to_string(config_path).map_err(|e| load_err(e.to_string()))?,
+    )
+    .map_err(|e| load_err(format!("Invalid Whisper config: {}", e)))?;
+    let tokenizer = tokenizer_cache::load(model_id, &tokenizer_path).map_err(load_err)?;
+
+    let device = accelerators::inference_device();
+    let vb = unsafe { VarBuilder::from_mmaped_safetensors(&[weights_path], DType::F32, &device) }
+        .map_err(|e| load_err(e.to_string()))?;
+    let model = Whisper::load(&vb, config.clone()).map_err(|e| load_err(e.to_string()))?;
+
+    Ok(LoadedWhisper {
+        model_id: model_id.to_string(),
+        model,
+        tokenizer,
+        config,
+        device,
+    })
+}
+
+/// Decode a WAV file into 16kHz mono samples
+fn decode_wav(bytes: &[u8]) -> Result<Vec<f32>, AIError> {
+    let bad = |msg: &str| {
+        whisper_error(AIErrorType::InvalidConfiguration, format!("Unsupported audio: {}", msg))
+    };
+
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(bad("expected a WAV (RIFF/WAVE) file"));
+    }
+
+    let mut format: Option<(u16, u16, u32, u16)> = None;
+    let mut data: Option<&[u8]> = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let len = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        let body_start = offset + 8;
+        let body_end = (body_start + len).min(bytes.len());
+        let body = &bytes[body_start..body_end];
+
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                let audio_format = u16::from_le_bytes([body[0], body[1]]);
+                let channels = u16::from_le_bytes([body[2], body[3]]);
+                let sample_rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+                let bits = u16::from_le_bytes([body[14], body[15]]);
+                format = Some((audio_format, channels, sample_rate, bits));
+            }
+            b"data" => data = Some(body),
+            _ => {}
+        }
+
+        // Chunks are padded to an even length
+        offset = body_start + len + (len % 2);
+    }
+
+    let (audio_format, channels, sample_rate, bits) = format.ok_or_else(|| bad("missing fmt chunk"))?;
+    let data = data.ok_or_else(|| bad("missing data chunk"))?;
+    if channels == 0 {
+        return Err(bad("zero channels"));
+    }
+
+    // 1 = integer PCM, 3 = IEEE float, 0xFFFE = extensible (assume PCM by bit depth)
+    let samples: Vec<f32> = match (audio_format, bits) {
+        (1, 16) | (0xFFFE, 16) => data
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+            .collect(),
+        (3, 32) | (0xFFFE, 32) => data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        _ => return Err(bad("only 16-bit PCM and 32-bit float WAV are supported")),
+    };
+
+    // Downmix to mono
+    let channels = channels as usize;
+    let mono: Vec<f32> = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+
+    Ok(resample(&mono, sample_rate, m::SAMPLE_RATE as u32))
+}
+
+/// Linear-interpolation resampler; adequate for speech recognition
+fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let ratio = from as f64 / to as f64;
+    let out_len = (samples.len() as f64 / ratio) as usize;
+    (0..out_len)
+        .map(|i| {
+            let pos = i as f64 * ratio;
+            let idx = pos as usize;
+            let frac = (pos - idx as f64) as f32;
+            let a = samples[idx.min(samples.len() - 1)];
+            let b = samples[(idx + 1).min(samples.len() - 1)];
+            a + (b - a) * frac
+        })
+        .collect()
+}
+
+fn hz_to_mel(hz: f64) -> f64 {
+    // Slaney mel scale (librosa default, used by Whisper)
+    let f_sp = 200.0 / 3.0;
+    let min_log_hz = 1000.0;
+    let min_log_mel = min_log_hz / f_sp;
+    let logstep = (6.4f64).ln() / 27.0;
+    if hz >= min_log_hz {
+        min_log_mel + (hz / min_log_hz).ln() / logstep
+    } else {
+        hz / f_sp
+    }
+}
+
+fn mel_to_hz(mel: f64) -> f64 {
+    let f_sp = 200.0 / 3.0;
+    let min_log_hz = 1000.0;
+    let min_log_mel = min_log_hz / f_sp;
+    let logstep = (6.4f64).ln() / 27.0;
+    if mel >= min_log_mel {
+        min_log_hz * (logstep * (mel - min_log_mel)).exp()
+    } else {
+        mel * f_sp
+    }
+}
+
+/// Slaney-normalized mel filterbank, row-major `[n_mels][n_fft / 2 + 1]`,
+/// equivalent to `librosa.filters.mel(sr=16000, n_fft=400, n_mels=n_mels)`
+fn mel_filters(n_mels: usize) -> Vec<f32> {
+    let sr = m::SAMPLE_RATE as f64;
+    let n_freqs = m::N_FFT / 2 + 1;
+    let fft_freqs: Vec<f64> = (0..n_freqs)
+        .map(|i| i as f64 * (sr / 2.0) / (n_freqs - 1) as f64)
+        .collect();
+
+    let mel_max = hz_to_mel(sr / 2.0);
+    let mel_points: Vec<f64> = (0..n_mels + 2)
+        .map(|i| mel_to_hz(mel_max * i as f64 / (n_mels + 1) as f64))
+        .collect();
+
+    let mut filters = vec![0f32; n_mels * n_freqs];
+    for i in 0..n_mels {
+        let (lower, center, upper) = (mel_points[i], mel_points[i + 1], mel_points[i + 2]);
+        let enorm = 2.0 / (upper - lower);
+        for (j, &f) in fft_freqs.iter().enumerate() {
+            let rising = (f - lower) / (center - lower);
+            let falling = (upper - f) / (upper - center);
+            let weight = rising.min(falling).max(0.0);
+            filters[i * n_freqs + j] = (weight * enorm) as f32;
+        }
+    }
+    filters
+}
+
+/// Greedy-decode one or more 30 second windows of audio
+fn transcribe_pcm(loaded: &mut LoadedWhisper, pcm: &[f32], language: &str) -> Result<String, AIError> {
+    let err = |e: candle_core::Error| whisper_error(AIErrorType::InferenceFailed, e.to_string());
+    let token = |t: &str| {
+        loaded.tokenizer.token_to_id(t).ok_or_else(|| {
+            whisper_error(AIErrorType::InvalidConfiguration, format!("Tokenizer is missing {}", t))
+        })
+    };
+
+    let sot = token(m::SOT_TOKEN)?;
+    let transcribe = token(m::TRANSCRIBE_TOKEN)?;
+    let no_timestamps = token(m::NO_TIMESTAMPS_TOKEN)?;
+    let eot = token(m::EOT_TOKEN)?;
+    // English-only checkpoints have no language tokens
+    let language_token = token(&format!("<|{}|>", language)).ok();
+
+    let device = loaded.device.clone();
+    let n_mels = loaded.config.num_mel_bins;
+    let filters = mel_filters(n_mels);
+    let mel = audio::pcm_to_mel(&loaded.config, pcm, &filters);
+    let mel_len = mel.len();
+    let mel = Tensor::from_vec(mel, (1, n_mels, mel_len / n_mels), &device).map_err(err)?;
+    let (_, _, content_frames) = mel.dims3().map_err(err)?;
+
+    let max_tokens = loaded.config.max_target_positions / 2;
+    let mut text = String::new();
+    let mut seek = 0;
+
+    while seek < content_frames {
+        let segment_size = usize::min(content_frames - seek, m::N_FRAMES);
+        let segment = mel.narrow(2, seek, segment_size).map_err(err)?;
+        let audio_features = loaded.model.encoder.forward(&segment, true).map_err(err)?;
+
+        let mut tokens = vec![sot];
+        if let Some(lang) = language_token {
+            tokens.push(lang);
+        }
+        tokens.push(transcribe);
+        tokens.push(no_timestamps);
+        let prefix_len = tokens.len();
+
+        for i in 0..max_tokens {
+            let tokens_t = Tensor::new(tokens.as_slice(), &device)
+                .and_then(|t| t.unsqueeze(0))
+                .map_err(err)?;
+            let ys = loaded
+                .model
+                .decoder
+                .forward(&tokens_t, &audio_features, i == 0)
+                .map_err(err)?;
+            let (_, seq_len, _) = ys.dims3().map_err(err)?;
+            let logits = loaded
+                .model
+                .decoder
+                .final_linear(&ys.i((..1, seq_len - 1..)).map_err(err)?)
+                .and_then(|l| l.i(0))
+                .and_then(|l| l.i(0))
+                .map_err(err)?;
+            let mut logits: Vec<f32> = logits.to_vec1().map_err(err)?;
+            for &t in &loaded.config.suppress_tokens {
+                if let Some(l) = logits.get_mut(t as usize) {
+                    *l = f32::NEG_INFINITY;
+                }
+            }
+
+            let next = logits
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .map(|(idx, _)| idx as u32)
+                .unwrap_or(eot);
+            if next == eot {
+                break;
+            }
+            tokens.push(next);
+        }
+
+        let segment_text = loaded
+            .tokenizer
+            .decode(&tokens[prefix_len..], true)
+            .map_err(|e| whisper_error(AIErrorType::InferenceFailed, e.to_string()))?;
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(segment_text.trim());
+        seek += segment_size;
+    }
+
+    Ok(text)
+}
+
+/// Transcribe WAV audio with the given (or default) Whisper model
+pub async fn transcribe(
+    audio_bytes: Vec<u8>,
+    model_id: Option<String>,
+    language: Option<String>,
+) -> Result<TranscriptionResult, AIError> {
+    let model_id = model_id.unwrap_or_else(|| DEFAULT_WHISPER_MODEL.to_string());
+    let language = language.unwrap_or_else(|| "en".to_string());
+    let start = std::time::Instant::now();
+
+    let needs_load = WHISPER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|w| w.model_id != model_id)
+        .unwrap_or(true);
+    let files = if needs_load {
+        Some(ensure_whisper_files(&model_id, None).await?)
+    } else {
+        None
+    };
+
+    tokio::task::spawn_blocking(move || {
+        let pcm = decode_wav(&audio_bytes)?;
+        let audio_duration_secs = pcm.len() as f32 / m::SAMPLE_RATE as f32;
+
+        let mut guard = WHISPER.lock().unwrap();
+        if let Some((config, tokenizer, weights)) = files {
+            info!("Loading {}", model_id);
+            *guard = Some(load_whisper(&model_id, config, tokenizer, weights)?);
+        }
+        let loaded = guard.as_mut().ok_or_else(|| {
+            whisper_error(AIErrorType::ModelNotFound, "Whisper model not loaded".to_string())
+        })?;
+
+        let text = transcribe_pcm(loaded, &pcm, &language)?;
+        Ok(TranscriptionResult {
+            text,
+            model_id,
+            audio_duration_secs,
+            processing_ms: start.elapsed().as_millis() as u64,
+        })
+    })
+    .await
+    .map_err(|e| whisper_error(AIErrorType::InferenceFailed, e.to_string()))?
+}
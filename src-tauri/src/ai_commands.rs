@@ -1,45 +1,155 @@
 // AI Commands - Tauri Commands for AI/LLM Operations
 
 use crate::ai::{
-    providers::{
-        get_ollama_models, get_ollama_status, get_openai_compatible_status,
-        run_ollama_inference, run_openai_compatible_inference,
-        get_candle_status, run_candle_inference, download_embedded_model, check_candle_availability
-    },
-    InferenceRequest, InferenceResponse, ModelConfig, ModelProvider, ProviderStatus,
+    accelerators::{self, AcceleratorReport},
+    agent_runs::{AgentRun, AgentRunStore},
+    bandwidth::{self, BandwidthSettings},
+    context::{self, ContextAttachment},
+    conversations::{self, Conversation, ConversationMeta, ConversationOverrides, ConversationStore},
+    export::{self, ExportFormat},
+    file_plan::{self, FileOperationPlan, PlanExecutionResult, PlanStore},
+    length_policy::{LengthPolicy, LengthPolicyStore},
+    model_pool::{LoadedModelInfo, MODEL_POOL},
+    tokenizer_cache::TOKENIZER_CACHE,
+    organizer,
+    presets::{self, PresetStore, PromptPreset},
+    providers::{download_embedded_model, prewarm_embedded_model, DownloadStatus, InferenceSink, ProviderRegistry},
+    recommend::{self, RecommendedModels},
+    scheduler::{InferenceScheduler, QueueUpdate},
+    secrets,
+    summarize::{self, FileSummary},
+    usage::{self, BudgetAction, ModelPrice, UsageSummary, UsageTracker},
+    whisper::{self, TranscriptionResult},
+    AIMode, ChatMessage, InferenceRequest, InferenceResponse, MessageRole, ModelConfig, ModelProvider, ProviderStatus,
+    TokenUsage,
 };
-use tauri::{command, Emitter, State};
+use crate::event_batch::EventBatcher;
+use crate::mcp::GuardrailVerdict;
+use crate::mcp_commands_native::NativeMCPState;
+use crate::profiles::ProfileStore;
+use crate::telemetry::Telemetry;
+use crate::operations::{OperationHandle, OperationKind, OperationManager};
+use crate::paths;
+use tauri::{command, Manager, State};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
 
 // Global state to track active inference sessions
 pub struct InferenceState {
     pub active_sessions: Arc<Mutex<HashMap<String, CancellationToken>>>,
+    /// Running generations that belong to a saved conversation
+    pub interruptible: Arc<Mutex<HashMap<String, InterruptibleSession>>>,
 }
 
 impl Default for InferenceState {
     fn default() -> Self {
         Self {
             active_sessions: Arc::new(Mutex::new(HashMap::new())),
+            interruptible: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+/// Where the partial output of a running generation goes if it is interrupted
+pub struct InterruptibleSession {
+    conversation_id: String,
+    sink: InferenceSink,
+}
+
+/// Save the partial answer of an interrupted generation into its conversation
+fn save_partial_response(store: &ConversationStore, session: InterruptibleSession) -> Option<ConversationMeta> {
+    let content = session.sink.partial_text();
+    if content.trim().is_empty() {
+        return None;
+    }
+    let now = chrono::Utc::now().timestamp_millis();
+    let message = ChatMessage {
+        id: format!("msg-{}", now),
+        role: MessageRole::Assistant,
+        content,
+        timestamp: now,
+        context_paths: None,
+        is_streaming: None,
+        error: None,
+        tool_calls: None,
+        images: None,
+        interrupted: Some(true),
+    };
+    match store.append_interrupted(&session.conversation_id, message) {
+        Ok(meta) => Some(meta),
+        Err(e) => {
+            warn!("Could not save interrupted response: {}", e);
+            None
+        }
+    }
+}
+
+/// Response built from the text streamed before a generation was stopped
+fn truncated_response(sink: &InferenceSink) -> InferenceResponse {
+    let now = chrono::Utc::now().timestamp_millis();
+    InferenceResponse {
+        message: ChatMessage {
+            id: format!("msg-{}", now),
+            role: MessageRole::Assistant,
+            content: sink.partial_text(),
+            timestamp: now,
+            context_paths: None,
+            is_streaming: None,
+            error: None,
+            tool_calls: None,
+            images: None,
+            interrupted: None,
+        },
+        is_complete: false,
+        usage: None,
+        inference_time_ms: None,
+    }
+}
+
+/// Cancel running generations and save their partial answers (called on app exit)
+pub fn persist_interrupted_sessions(app: &tauri::AppHandle) {
+    let state = app.state::<InferenceState>();
+    for token in state.active_sessions.lock().unwrap().values() {
+        token.cancel();
+    }
+    let sessions: Vec<InterruptibleSession> = state.interruptible.lock().unwrap().drain().map(|(_, s)| s).collect();
+    let store = app.state::<ConversationStore>();
+    for session in sessions {
+        if let Some(meta) = save_partial_response(&store, session) {
+            info!("Saved interrupted response in conversation {}", meta.id);
         }
     }
 }
 
 /// Get status of all AI providers
 #[command]
-pub async fn get_ai_providers_status(ollama_endpoint: Option<String>) -> Result<Vec<ProviderStatus>, String> {
+pub async fn get_ai_providers_status(
+    ollama_endpoint: Option<String>,
+    endpoints: Option<HashMap<String, String>>,
+    registry: State<'_, ProviderRegistry>,
+) -> Result<Vec<ProviderStatus>, String> {
+    let mut endpoints = endpoints.unwrap_or_default();
+    if let Some(ep) = ollama_endpoint {
+        endpoints.entry(ModelProvider::Ollama.id().to_string()).or_insert(ep);
+    }
+
     let mut statuses = Vec::new();
+    for provider in registry.all() {
+        let endpoint = endpoints.get(provider.kind().id()).map(|s| s.as_str());
 
-    // Check Ollama with provided endpoint
-    statuses.push(get_ollama_status(ollama_endpoint.as_deref()).await);
+        // Providers that need user configuration are skipped until configured
+        if provider.requires_endpoint() && endpoint.is_none() {
+            continue;
+        }
 
-    // Check Candle (Embedded)
-    statuses.push(get_candle_status().await);
+        statuses.push(provider.status(endpoint).await);
+    }
 
     // TransformerJS runs in browser, so we don't check it here
-    // OpenAI-compatible requires user configuration, so we skip it
-
     Ok(statuses)
 }
 
@@ -48,21 +158,16 @@ pub async fn get_ai_providers_status(ollama_endpoint: Option<String>) -> Result<
 pub async fn get_provider_models(
     provider: String,
     endpoint: Option<String>,
+    registry: State<'_, ProviderRegistry>,
 ) -> Result<Vec<ModelConfig>, String> {
-    match provider.as_str() {
-        "ollama" => get_ollama_models(endpoint.as_deref())
-            .await
-            .map_err(|e| e.message),
-        "candle" => {
-            let status = get_candle_status().await;
-            Ok(status.available_models)
-        }
-        "transformerjs" => {
-            // Return hardcoded list of Transformer.js models
-            // These are defined in the frontend
-            Ok(vec![])
-        }
-        _ => Err(format!("Unknown provider: {}", provider)),
+    let kind = ModelProvider::from_id(&provider)
+        .ok_or_else(|| format!("Unknown provider: {}", provider))?;
+
+    match registry.get(&kind) {
+        Some(p) => p.list_models(endpoint.as_deref()).await.map_err(|e| e.message),
+        // TransformerJS models are defined in the frontend
+        None if kind == ModelProvider::TransformerJS => Ok(vec![]),
+        None => Err(format!("Provider not yet implemented: {}", provider)),
     }
 }
 
@@ -84,13 +189,93 @@ pub async fn cancel_inference(
 
 /// Run AI inference
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub async fn run_ai_inference(
     window: tauri::Window,
-    request: InferenceRequest,
+    mut request: InferenceRequest,
     state: State<'_, InferenceState>,
+    registry: State<'_, ProviderRegistry>,
+    scheduler: State<'_, InferenceScheduler>,
+    preset_store: State<'_, PresetStore>,
+    conversation_store: State<'_, ConversationStore>,
+    usage_tracker: State<'_, UsageTracker>,
+    length_policy: State<'_, LengthPolicyStore>,
+    operations: State<'_, OperationManager>,
+    telemetry: State<'_, Telemetry>,
+    profiles: State<'_, ProfileStore>,
+    mcp_state: State<'_, NativeMCPState>,
 ) -> Result<InferenceResponse, String> {
-    // Create cancellation token for this session
-    let cancel_token = CancellationToken::new();
+    // Requests that don't name a model use the active profile's default
+    if request.model_config.model_id.is_empty() {
+        if let Some(model) = profiles.active().and_then(|p| p.default_model) {
+            request.model_config.provider = model.provider;
+            request.model_config.model_id = model.model_id;
+        } else if let Some(model_id) = tokio::task::spawn_blocking(recommend::recommend)
+            .await
+            .ok()
+            .and_then(|r| r.default_model_id)
+        {
+            // First run: nothing configured yet, use what this machine can run
+            request.model_config.provider = ModelProvider::Candle;
+            request.model_config.model_id = model_id;
+        }
+    }
+    let mut attachments = Vec::new();
+    if let Some(conversation) = request.conversation_id.as_deref().and_then(|id| conversation_store.get(id)) {
+        conversation.overrides.apply(&mut request);
+        attachments = conversation.attachments;
+    }
+
+    let provider = match registry.get(&request.model_config.provider) {
+        Some(p) => p,
+        None if request.model_config.provider == ModelProvider::TransformerJS => {
+            // TransformerJS runs in the browser, not in Rust
+            return Err("TransformerJS inference should run in the browser".to_string());
+        }
+        None => return Err("Provider not yet implemented".to_string()),
+    };
+
+    let has_images = request
+        .messages
+        .iter()
+        .any(|m| m.images.as_ref().is_some_and(|i| !i.is_empty()));
+    if has_images && !provider.supports_images() {
+        return Err(format!(
+            "The {} provider cannot answer questions about images; choose a vision-capable Ollama or OpenAI-compatible model",
+            provider.kind().id()
+        ));
+    }
+
+    if let Some(preset_id) = &request.preset_id {
+        let preset = preset_store
+            .get(preset_id)
+            .ok_or_else(|| format!("Prompt preset not found: {}", preset_id))?;
+        presets::apply_preset(&preset, &mut request.messages);
+    }
+    let context_paths = allowed_context_paths(&mcp_state, context::requested_paths(&request, &attachments)).await;
+    context::inject(&mut request, &context_paths);
+
+    if request.tools.is_some() && !provider.supports_tools() {
+        info!(
+            "Provider {} does not support native tools; ignoring tool definitions",
+            provider.kind().id()
+        );
+    }
+
+    usage_tracker
+        .check_budget(&request.model_config.provider)
+        .map_err(|e| e.message)?;
+
+    let policy = length_policy.get();
+    policy.apply(&mut request);
+    telemetry.feature_used(match request.mode {
+        AIMode::QA => "inference:qa",
+        AIMode::Agent => "inference:agent",
+    });
+
+    // The operation's token cancels this session (cancel_inference or cancel_operation)
+    let operation = operations.start(window.app_handle(), OperationKind::Inference, request.model_config.model_id.clone());
+    let cancel_token = operation.token();
     let session_id = request.session_id.clone();
 
     // Register the session
@@ -99,23 +284,87 @@ pub async fn run_ai_inference(
         sessions.insert(session_id.clone(), cancel_token.clone());
     }
 
-    // Run inference with cancellation support
-    let result = match request.model_config.provider {
-        ModelProvider::Ollama => run_ollama_inference(window, &request, cancel_token.clone())
-            .await
-            .map_err(|e| e.message),
-        ModelProvider::Candle => run_candle_inference(window, &request)
-            .await
-            .map_err(|e| e.message),
-        ModelProvider::OpenAICompatible => run_openai_compatible_inference(&request)
-            .await
-            .map_err(|e| e.message),
-        ModelProvider::TransformerJS => {
-            // TransformerJS runs in the browser, not in Rust
-            Err("TransformerJS inference should run in the browser".to_string())
+    // Wait for our turn on local models, then run with cancellation support.
+    // Output is recorded so it survives interruption and the time limit.
+    let sink = InferenceSink::window(window).recording();
+    if let Some(conversation_id) = request.conversation_id.clone() {
+        state.interruptible.lock().unwrap().insert(
+            session_id.clone(),
+            InterruptibleSession {
+                conversation_id,
+                sink: sink.clone(),
+            },
+        );
+    }
+    let result = match scheduler
+        .acquire(
+            provider.as_ref(),
+            &request.model_config.model_id,
+            &session_id,
+            &sink,
+            &cancel_token,
+        )
+        .await
+    {
+        Ok(_permit) => {
+            // Stop the generation once it exceeds the wall-clock limit
+            let timed_out = Arc::new(AtomicBool::new(false));
+            let watchdog = {
+                let (token, timed_out, limit) = (cancel_token.clone(), timed_out.clone(), policy.max_duration());
+                tokio::spawn(async move {
+                    tokio::time::sleep(limit).await;
+                    timed_out.store(true, Ordering::SeqCst);
+                    token.cancel();
+                })
+            };
+            let started = std::time::Instant::now();
+            let result = provider
+                .infer_stream(&request, sink.clone(), cancel_token.clone())
+                .await;
+            watchdog.abort();
+            if let Ok(response) = &result {
+                if let Some(usage) = &response.usage {
+                    telemetry.generation_completed(provider.kind().id(), usage.completion_tokens, started.elapsed());
+                }
+            }
+
+            if timed_out.load(Ordering::SeqCst) {
+                info!(
+                    "Generation stopped after the {}s time limit",
+                    policy.max_generation_secs
+                );
+                // Hand back what was generated instead of a cancellation error
+                let mut response = result.unwrap_or_else(|_| truncated_response(&sink));
+                response.is_complete = false;
+                // Not a user interruption: the frontend saves this answer itself
+                state.interruptible.lock().unwrap().remove(&session_id);
+                Ok(response)
+            } else {
+                result.map_err(|e| e.message)
+            }
         }
-        _ => Err("Provider not yet implemented".to_string()),
+        Err(e) => Err(e.message),
     };
+    sink.flush();
+
+    if let Ok(response) = &result {
+        let usage = response.usage.clone().unwrap_or_else(|| {
+            let prompt_tokens: u32 = request.messages.iter().map(|m| usage::estimate_tokens(&m.content)).sum();
+            let completion_tokens = usage::estimate_tokens(&response.message.content);
+            TokenUsage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+            }
+        });
+        if let Some(status) = usage_tracker.record(
+            &request.model_config.provider,
+            &request.model_config.model_id,
+            &usage,
+        ) {
+            sink.event("ai-budget-warning", status);
+        }
+    }
 
     // Cleanup: remove session from active sessions
     {
@@ -123,46 +372,616 @@ pub async fn run_ai_inference(
         sessions.remove(&session_id);
     }
 
+    // Whoever removes the entry saves the partial answer (here or on app exit)
+    let interruptible = state.interruptible.lock().unwrap().remove(&session_id);
+    if let Some(session) = interruptible.filter(|_| cancel_token.is_cancelled()) {
+        if let Some(meta) = save_partial_response(&conversation_store, session) {
+            sink.event("conversation-updated", meta);
+        }
+    }
+
+    operation.finish(&result);
     result
 }
 
+/// Get the current inference queues for local models
+#[command]
+pub fn get_inference_queue(scheduler: State<'_, InferenceScheduler>) -> Vec<QueueUpdate> {
+    scheduler.snapshot()
+}
+
+/// GPUs, compute backends, the device embedded models run on and the
+/// memory budget a model must fit in
+#[command]
+pub async fn get_accelerators() -> Result<AcceleratorReport, String> {
+    tokio::task::spawn_blocking(accelerators::report)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Embedded models checked against this machine's inference device and
+/// memory, with the recommended default and why
+#[command]
+pub async fn get_recommended_models() -> Result<RecommendedModels, String> {
+    tokio::task::spawn_blocking(recommend::recommend)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// List embedded models currently resident in memory
+#[command]
+pub fn get_loaded_models() -> Vec<LoadedModelInfo> {
+    MODEL_POOL.lock().unwrap().list()
+}
+
+/// Unload an embedded model from memory, along with its cached tokenizer
+#[command]
+pub fn unload_model(model_id: String) -> bool {
+    // Pool keys of adapter-merged models are "<base>+<adapter>"
+    let base = model_id.split('+').next().unwrap_or(&model_id);
+    TOKENIZER_CACHE.lock().unwrap().evict(base);
+    MODEL_POOL.lock().unwrap().unload(&model_id)
+}
+
+/// Drop all cached tokenizers (resident models keep their own reference).
+/// Returns how many were cached.
+#[command]
+pub fn clear_tokenizer_cache() -> usize {
+    TOKENIZER_CACHE.lock().unwrap().clear()
+}
+
+/// Load an embedded model into memory ahead of the first request (e.g. when
+/// the AI panel opens) so the first answer doesn't wait for the load
+#[command]
+pub async fn prewarm_model(model_id: String, adapter: Option<String>) -> Result<(), String> {
+    prewarm_embedded_model(&model_id, adapter.as_deref())
+        .await
+        .map_err(|e| e.message)
+}
+
+/// Set the RAM budget (in bytes) shared by all resident embedded models
+#[command]
+pub fn set_model_memory_budget(budget_bytes: u64) -> Result<(), String> {
+    if budget_bytes == 0 {
+        return Err("Memory budget must be greater than zero".to_string());
+    }
+    MODEL_POOL.lock().unwrap().set_budget(budget_bytes);
+    Ok(())
+}
+
 /// Check if a specific provider is available
 #[command]
 pub async fn check_provider_availability(
     provider: String,
     endpoint: Option<String>,
+    registry: State<'_, ProviderRegistry>,
 ) -> Result<bool, String> {
-    match provider.as_str() {
-        "ollama" => {
-            let status = get_ollama_status(endpoint.as_deref()).await;
-            Ok(status.is_available)
-        }
-        "candle" => {
-            Ok(check_candle_availability().await)
-        }
-        "openai-compatible" => {
-            if let Some(ep) = endpoint {
-                let status = get_openai_compatible_status(&ep, None).await;
-                Ok(status.is_available)
-            } else {
-                Ok(false)
-            }
-        }
-        _ => Ok(false),
+    let provider = match ModelProvider::from_id(&provider).and_then(|kind| registry.get(&kind)) {
+        Some(p) => p,
+        None => return Ok(false),
+    };
+
+    if provider.requires_endpoint() && endpoint.is_none() {
+        return Ok(false);
     }
+
+    Ok(provider.status(endpoint.as_deref()).await.is_available)
 }
 
 /// Download the embedded model (streaming progress)
 #[command]
-pub async fn download_model(window: tauri::Window, model_id: String) -> Result<(), String> {
-    let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+pub async fn download_model(
+    window: tauri::Window,
+    model_id: String,
+    operations: State<'_, OperationManager>,
+) -> Result<(), String> {
+    let operation = operations.start(window.app_handle(), OperationKind::Download, model_id.clone());
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<DownloadStatus>(100);
     
     // Spawn background task
+    let report = operation.clone();
     tokio::spawn(async move {
+        // The last update is flushed when the batcher drops with the channel
+        let events = EventBatcher::latest(window, "model-download-progress");
         while let Some(status) = rx.recv().await {
-            let _ = window.emit("model-download-progress", status);
+            report.progress(Some(status.progress), Some(status.status.clone()));
+            events.send(status);
         }
     });
 
-    download_embedded_model(model_id, tx).await
+    let result = run_cancellable(&operation, download_embedded_model(model_id, tx)).await;
+    operation.finish(&result);
+    result
+}
+
+/// Run `task` until it finishes or the operation is cancelled
+async fn run_cancellable<T>(
+    operation: &OperationHandle,
+    task: impl std::future::Future<Output = Result<T, String>>,
+) -> Result<T, String> {
+    let token = operation.token();
+    tokio::select! {
+        result = task => result,
+        _ = token.cancelled() => Err("Operation cancelled".to_string()),
+    }
+}
+
+/// Get the download bandwidth settings
+#[command]
+pub fn get_download_settings() -> BandwidthSettings {
+    bandwidth::settings()
+}
+
+/// Cap model download bandwidth in bytes per second (None or 0 for unlimited).
+/// Applies to downloads already in progress.
+#[command]
+pub fn set_download_bandwidth_limit(limit_bytes_per_sec: Option<u64>) -> Result<BandwidthSettings, String> {
+    bandwidth::set_limit(limit_bytes_per_sec)
+}
+
+/// Transcribe recorded audio (WAV bytes or a WAV file path) with Whisper
+#[command]
+pub async fn transcribe_audio(
+    audio: Option<Vec<u8>>,
+    path: Option<String>,
+    model_id: Option<String>,
+    language: Option<String>,
+) -> Result<TranscriptionResult, String> {
+    let bytes = match (audio, path) {
+        (Some(bytes), _) => bytes,
+        (None, Some(path)) => std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?,
+        (None, None) => return Err("Either audio bytes or a file path is required".to_string()),
+    };
+
+    whisper::transcribe(bytes, model_id, language)
+        .await
+        .map_err(|e| e.message)
+}
+
+/// Download a Whisper model (streaming progress)
+#[command]
+pub async fn download_whisper_model(
+    window: tauri::Window,
+    model_id: String,
+    operations: State<'_, OperationManager>,
+) -> Result<(), String> {
+    let operation = operations.start(window.app_handle(), OperationKind::Download, model_id.clone());
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<DownloadStatus>(100);
+
+    let report = operation.clone();
+    tokio::spawn(async move {
+        // The last update is flushed when the batcher drops with the channel
+        let events = EventBatcher::latest(window, "whisper-download-progress");
+        while let Some(status) = rx.recv().await {
+            report.progress(Some(status.progress), Some(status.status.clone()));
+            events.send(status);
+        }
+    });
+
+    let download = async {
+        whisper::ensure_whisper_files(&model_id, Some(tx))
+            .await
+            .map(|_| ())
+            .map_err(|e| e.message)
+    };
+    let result = run_cancellable(&operation, download).await;
+    operation.finish(&result);
+    result
+}
+
+/// List built-in and user-defined prompt presets
+#[command]
+pub fn list_prompt_presets(store: State<'_, PresetStore>) -> Vec<PromptPreset> {
+    store.list()
+}
+
+/// Create a user-defined prompt preset
+#[command]
+pub fn create_prompt_preset(
+    name: String,
+    description: Option<String>,
+    system_prompt: String,
+    store: State<'_, PresetStore>,
+) -> Result<PromptPreset, String> {
+    store.create(name, description.unwrap_or_default(), system_prompt)
+}
+
+/// Update a user-defined prompt preset
+#[command]
+pub fn update_prompt_preset(
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+    system_prompt: Option<String>,
+    store: State<'_, PresetStore>,
+) -> Result<PromptPreset, String> {
+    store.update(&id, name, description, system_prompt)
+}
+
+/// Delete a user-defined prompt preset
+#[command]
+pub fn delete_prompt_preset(id: String, store: State<'_, PresetStore>) -> Result<(), String> {
+    store.delete(&id)
+}
+
+/// List saved conversations, most recent first
+#[command]
+pub fn list_conversations(store: State<'_, ConversationStore>) -> Vec<ConversationMeta> {
+    store.list()
+}
+
+/// Load a saved conversation
+#[command]
+pub fn get_conversation(id: String, store: State<'_, ConversationStore>) -> Result<Conversation, String> {
+    store.get(&id).ok_or_else(|| format!("Conversation not found: {}", id))
+}
+
+/// Save a conversation and generate its title/summary in the background when due
+#[command]
+pub fn save_conversation(
+    app: tauri::AppHandle,
+    conversation: Conversation,
+    store: State<'_, ConversationStore>,
+) -> Result<ConversationMeta, String> {
+    let saved = store.save(conversation)?;
+    conversations::schedule_metadata(&app, &saved);
+    Ok(saved.meta())
+}
+
+/// Context paths the model may see: those the MCP read tools may read. The
+/// others are left out, as is everything while MCP is not initialized.
+async fn allowed_context_paths(mcp_state: &NativeMCPState, requested: Vec<String>) -> Vec<String> {
+    if requested.is_empty() {
+        return requested;
+    }
+    let server = match mcp_state.server().await {
+        Ok(server) => server,
+        Err(e) => {
+            warn!("Leaving out {} context paths: {}", requested.len(), e);
+            return Vec::new();
+        }
+    };
+    let mut allowed = Vec::new();
+    for path in requested {
+        if server.is_path_allowed(&paths::extended(Path::new(&path))).await {
+            allowed.push(path);
+        } else {
+            warn!("Leaving out context path {}: not in allowed directories", path);
+        }
+    }
+    allowed
+}
+
+/// Attach files or folders from the file tree to a conversation. Their
+/// listings and contents are added to every request of the conversation.
+#[command]
+pub async fn attach_context(
+    conversation_id: String,
+    paths: Vec<String>,
+    store: State<'_, ConversationStore>,
+    mcp_state: State<'_, NativeMCPState>,
+) -> Result<Conversation, String> {
+    let server = mcp_state.server().await?;
+    for path in &paths {
+        if !server.is_path_allowed(&crate::paths::extended(Path::new(path))).await {
+            return Err(format!("Access denied: {} is not in allowed directories", path));
+        }
+    }
+    let attachments = paths
+        .iter()
+        .map(|path| ContextAttachment::new(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    store.attach(&conversation_id, attachments)
+}
+
+#[command]
+pub fn detach_context(
+    conversation_id: String,
+    path: String,
+    store: State<'_, ConversationStore>,
+) -> Result<Conversation, String> {
+    store.detach(&conversation_id, &path)
+}
+
+/// Context block the attachments and paths would add to a request, as the
+/// model will see it
+#[command]
+pub async fn preview_context(
+    paths: Vec<String>,
+    mcp_state: State<'_, NativeMCPState>,
+) -> Result<Option<String>, String> {
+    Ok(context::resolve(&allowed_context_paths(&mcp_state, paths).await))
+}
+
+/// Set a user-chosen conversation title
+#[command]
+pub fn rename_conversation(
+    id: String,
+    title: String,
+    store: State<'_, ConversationStore>,
+) -> Result<ConversationMeta, String> {
+    store.rename(&id, title)
+}
+
+/// Delete a saved conversation
+#[command]
+pub fn delete_conversation(id: String, store: State<'_, ConversationStore>) -> Result<(), String> {
+    store.delete(&id)
+}
+
+/// Record the state of a running agent loop (called before each inference
+/// and each tool call) so a run cut off by a restart can be recovered
+#[command]
+pub fn checkpoint_agent_run(run: AgentRun, runs: State<'_, AgentRunStore>) -> Result<(), String> {
+    runs.checkpoint(run)
+}
+
+/// Drop the checkpoint of an agent run that ended
+#[command]
+pub fn finish_agent_run(id: String, runs: State<'_, AgentRunStore>) -> Result<(), String> {
+    runs.remove(&id).map(|_| ())
+}
+
+/// Agent runs left unfinished by an earlier session, optionally of one conversation
+#[command]
+pub fn list_interrupted_agent_runs(conversation_id: Option<String>, runs: State<'_, AgentRunStore>) -> Vec<AgentRun> {
+    runs.interrupted(conversation_id.as_deref())
+}
+
+/// Messages to continue an interrupted run with; a tool call that was in
+/// flight is reported to the model as possibly partly applied
+#[command]
+pub fn resume_agent_run(id: String, runs: State<'_, AgentRunStore>) -> Result<Vec<ChatMessage>, String> {
+    runs.resume(&id)
+}
+
+/// Give up on an interrupted run, noting in its conversation where it stopped
+#[command]
+pub fn abandon_agent_run(
+    id: String,
+    runs: State<'_, AgentRunStore>,
+    store: State<'_, ConversationStore>,
+) -> Result<ConversationMeta, String> {
+    let (conversation_id, note) = runs.abandon(&id)?;
+    store.append_interrupted(&conversation_id, note)
+}
+
+/// Export a conversation as a Markdown transcript or a JSON document.
+/// When `path` is given the export is also written there (within the allowed
+/// directories); the rendered content is returned either way.
+#[command]
+pub async fn export_conversation(
+    id: String,
+    format: ExportFormat,
+    path: Option<String>,
+    store: State<'_, ConversationStore>,
+    mcp_state: State<'_, NativeMCPState>,
+) -> Result<String, String> {
+    let conversation = store
+        .get(&id)
+        .ok_or_else(|| format!("Conversation not found: {}", id))?;
+    let content = export::render(&conversation, format)?;
+
+    if let Some(path) = path {
+        let server = mcp_state.server().await?;
+        server
+            .write_file(path.clone(), content.clone())
+            .await
+            .map_err(|e| e.message)?;
+        info!("Conversation {} written to {}", id, path);
+    }
+    Ok(content)
+}
+
+/// Pin provider, model, temperature or system prompt to a conversation
+#[command]
+pub fn set_conversation_overrides(
+    id: String,
+    overrides: ConversationOverrides,
+    store: State<'_, ConversationStore>,
+) -> Result<Conversation, String> {
+    store.set_overrides(&id, overrides)
+}
+
+/// Token usage and spending for a month (YYYY-MM, defaults to the current month)
+#[command]
+pub fn get_usage_summary(month: Option<String>, tracker: State<'_, UsageTracker>) -> UsageSummary {
+    tracker.summary(month)
+}
+
+/// Set or clear a provider's monthly budget
+#[command]
+pub fn set_usage_budget(
+    provider: ModelProvider,
+    monthly_limit_usd: Option<f64>,
+    action: Option<BudgetAction>,
+    tracker: State<'_, UsageTracker>,
+) -> Result<(), String> {
+    tracker.set_budget(provider, monthly_limit_usd, action.unwrap_or(BudgetAction::Warn))
+}
+
+/// Set or clear the price used for a model id (or model id prefix)
+#[command]
+pub fn set_model_price(
+    model_id: String,
+    price: Option<ModelPrice>,
+    tracker: State<'_, UsageTracker>,
+) -> Result<(), String> {
+    tracker.set_price(model_id, price)
+}
+
+/// Get the generation length policy
+#[command]
+pub fn get_length_policy(store: State<'_, LengthPolicyStore>) -> LengthPolicy {
+    store.get()
+}
+
+/// Replace the generation length policy
+#[command]
+pub fn set_length_policy(policy: LengthPolicy, store: State<'_, LengthPolicyStore>) -> Result<LengthPolicy, String> {
+    store.set(policy)
+}
+
+/// Store an API key or token in the OS credential store.
+/// `name` is `provider:<provider id>` or `huggingface`.
+#[command]
+pub fn set_secret(name: String, value: String) -> Result<(), String> {
+    secrets::set(&name, &value).map_err(|e| e.message)
+}
+
+/// Read a secret from the OS credential store
+#[command]
+pub fn get_secret(name: String) -> Result<Option<String>, String> {
+    secrets::get(&name).map_err(|e| e.message)
+}
+
+/// Remove a secret from the OS credential store
+#[command]
+pub fn delete_secret(name: String) -> Result<bool, String> {
+    secrets::delete(&name).map_err(|e| e.message)
+}
+
+/// Turn a natural-language instruction into a previewable plan of file operations.
+///
+/// Nothing is changed on disk; the plan must be confirmed with `execute_file_plan`.
+#[command]
+pub async fn plan_file_operations(
+    instruction: String,
+    model_config: ModelConfig,
+    registry: State<'_, ProviderRegistry>,
+    mcp_state: State<'_, NativeMCPState>,
+    plans: State<'_, PlanStore>,
+) -> Result<FileOperationPlan, String> {
+    let provider = registry
+        .get(&model_config.provider)
+        .ok_or_else(|| "Provider not yet implemented".to_string())?;
+    let server = mcp_state.server().await?;
+
+    let mut plan = file_plan::generate_plan(provider.as_ref(), &model_config, &instruction, &server)
+        .await
+        .map_err(|e| e.message)?;
+    file_plan::preview_plan(&mut plan, &server).await;
+
+    plans.insert(plan.clone());
+    Ok(plan)
+}
+
+/// Propose a folder structure for `path` as a plan of moves. Apply all or some
+/// of its operations with `execute_file_plan`.
+#[command]
+pub async fn suggest_file_organization(
+    path: String,
+    model_config: ModelConfig,
+    registry: State<'_, ProviderRegistry>,
+    mcp_state: State<'_, NativeMCPState>,
+    plans: State<'_, PlanStore>,
+) -> Result<FileOperationPlan, String> {
+    let provider = registry
+        .get(&model_config.provider)
+        .ok_or_else(|| "Provider not yet implemented".to_string())?;
+    let server = mcp_state.server().await?;
+    if !server.is_path_allowed(std::path::Path::new(&path)).await {
+        return Err(format!("Access denied: {} is not in allowed directories", path));
+    }
+
+    let mut plan = organizer::suggest_organization(provider.as_ref(), &model_config, &path)
+        .await
+        .map_err(|e| e.message)?;
+    file_plan::preview_plan(&mut plan, &server).await;
+
+    plans.insert(plan.clone());
+    Ok(plan)
+}
+
+/// Execute a previously generated plan as one transaction; deletions go to
+/// quarantine. `operation_ids` selects a subset of
+/// its operations; `confirmed` must be true and counts as the user's approval
+/// for operations the guardrails escalated. Blocked selections are refused.
+#[command]
+pub async fn execute_file_plan(
+    app: tauri::AppHandle,
+    plan_id: String,
+    operation_ids: Option<Vec<String>>,
+    confirmed: bool,
+    mcp_state: State<'_, NativeMCPState>,
+    plans: State<'_, PlanStore>,
+) -> Result<PlanExecutionResult, String> {
+    if !confirmed {
+        return Err("File operations require explicit confirmation".to_string());
+    }
+    crate::audit::ensure_writable()?;
+    let journal_dir = crate::storage::app_data_dir(&app)?.join("transactions");
+
+    let plan = plans
+        .get(&plan_id)
+        .ok_or_else(|| format!("Plan not found or already executed: {}", plan_id))?;
+    let server = mcp_state.server().await?;
+
+    // Keep a blocked plan around so a smaller selection can still be executed
+    let decision = server
+        .evaluate_guardrails(&plan.proposed_actions(operation_ids.as_deref()))
+        .await;
+    if decision.verdict == GuardrailVerdict::Block {
+        return Err(format!("Blocked by guardrails: {}", decision.reasons.join("; ")));
+    }
+    if plans.remove(&plan_id).is_none() {
+        return Err(format!("Plan not found or already executed: {}", plan_id));
+    }
+
+    let result = file_plan::execute_plan(&app, &plan, operation_ids.as_deref(), &server, journal_dir).await;
+    crate::commands::clear_cache();
+    Ok(result)
+}
+
+/// Discard a plan without executing it
+#[command]
+pub fn discard_file_plan(plan_id: String, plans: State<'_, PlanStore>) -> bool {
+    plans.remove(&plan_id).is_some()
+}
+
+/// Summarize a large text, markdown, log or PDF file with map-reduce over its
+/// chunks. Progress is emitted as `file-summary-progress` events.
+#[command]
+pub async fn summarize_file(
+    window: tauri::Window,
+    path: String,
+    model_config: ModelConfig,
+    registry: State<'_, ProviderRegistry>,
+    scheduler: State<'_, InferenceScheduler>,
+    operations: State<'_, OperationManager>,
+    mcp_state: State<'_, NativeMCPState>,
+) -> Result<FileSummary, String> {
+    // Files reach the model only from where the MCP read tools may read
+    let server = mcp_state.server().await?;
+    if !server.is_path_allowed(&paths::extended(Path::new(&path))).await {
+        return Err(format!("Access denied: {} is not in allowed directories", path));
+    }
+
+    let provider = registry
+        .get(&model_config.provider)
+        .ok_or_else(|| "Provider not yet implemented".to_string())?;
+
+    let operation = operations.start(window.app_handle(), OperationKind::Summarize, path.clone());
+    let sink = InferenceSink::window(window);
+    let session_id = format!("summarize-{}", operation.id());
+    let summary = async {
+        let _permit = scheduler
+            .acquire(
+                provider.as_ref(),
+                &model_config.model_id,
+                &session_id,
+                &sink,
+                &operation.token(),
+            )
+            .await
+            .map_err(|e| e.message)?;
+
+        summarize::summarize_file(provider.as_ref(), &model_config, &path, &sink)
+            .await
+            .map_err(|e| e.message)
+    };
+    let result = run_cancellable(&operation, summary).await;
+    sink.flush();
+    operation.finish(&result);
+    result
 }
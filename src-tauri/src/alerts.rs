@@ -0,0 +1,219 @@
+// Alerts - Low drive space monitoring
+//
+// A background task periodically compares the free space of each tracked
+// drive against a threshold. When a drive drops below it, Helium shows an OS
+// notification and emits an event, naming the folder on that drive that grew
+// the most according to the scan history. A drive is reported once per
+// crossing: it alerts again only after recovering above the threshold.
+
+use crate::i18n;
+use crate::mcp::native_server::format_bytes;
+use crate::scan_history::{FolderGrowth, ScanHistory};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use sysinfo::Disks;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tracing::{info, warn};
+
+/// Event emitted when a drive drops below the free space threshold
+pub const DRIVE_ALERT_EVENT: &str = "drive-space-alert";
+
+/// How far back growth is measured (one week)
+const GROWTH_WINDOW_SECS: i64 = 7 * 24 * 60 * 60;
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_threshold() -> f64 {
+    10.0
+}
+
+fn default_interval() -> u64 {
+    15
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Alert when free space drops below this percentage of the drive
+    #[serde(default = "default_threshold")]
+    pub threshold_percent: f64,
+    /// Mount points to watch; empty watches every drive
+    #[serde(default)]
+    pub tracked_drives: Vec<String>,
+    #[serde(default = "default_interval")]
+    pub interval_minutes: u64,
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            threshold_percent: default_threshold(),
+            tracked_drives: Vec::new(),
+            interval_minutes: default_interval(),
+        }
+    }
+}
+
+/// A drive below the free space threshold
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriveAlert {
+    pub mount_point: String,
+    pub name: String,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub free_percent: f64,
+    /// Folder on the drive that grew the most in the last week
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub growth: Option<FolderGrowth>,
+    /// Localized notification text
+    pub message: String,
+}
+
+/// Alert settings persisted in the app data directory
+pub struct AlertMonitor {
+    path: PathBuf,
+    settings: Mutex<AlertSettings>,
+    /// Drives already reported since they crossed the threshold
+    alerted: Mutex<HashSet<String>>,
+}
+
+/// Mount point of the drive holding `path` (the longest matching one)
+fn owning_mount<'a>(path: &str, mounts: &'a [String]) -> Option<&'a String> {
+    mounts
+        .iter()
+        .filter(|m| Path::new(path).starts_with(m.as_str()))
+        .max_by_key(|m| m.len())
+}
+
+fn message(alert_name: &str, threshold: f64, growth: Option<&FolderGrowth>) -> String {
+    let percent = format!("{}", threshold);
+    let low = i18n::t_with("alert.low_space", &[("drive", alert_name), ("percent", &percent)]);
+    match growth {
+        Some(growth) => {
+            let folder = Path::new(&growth.path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| growth.path.clone());
+            let size = format_bytes(growth.growth as u64);
+            i18n::t_with("alert.growth", &[("message", &low), ("folder", &folder), ("size", &size)])
+        }
+        None => low,
+    }
+}
+
+impl AlertMonitor {
+    pub fn load(path: PathBuf) -> Self {
+        let settings = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        Self {
+            path,
+            settings: Mutex::new(settings),
+            alerted: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn settings(&self) -> AlertSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn set_settings(&self, settings: AlertSettings) -> Result<AlertSettings, String> {
+        if !(0.0..100.0).contains(&settings.threshold_percent) {
+            return Err("Threshold must be between 0 and 100 percent".to_string());
+        }
+        if settings.interval_minutes == 0 {
+            return Err("Check interval must be at least one minute".to_string());
+        }
+        storage::save_json(&self.path, &settings)?;
+        *self.settings.lock().unwrap() = settings.clone();
+        // Re-evaluate every drive against the new threshold
+        self.alerted.lock().unwrap().clear();
+        Ok(settings)
+    }
+
+    /// Tracked drives currently below the threshold
+    pub fn check(&self, history: &ScanHistory) -> Vec<DriveAlert> {
+        let settings = self.settings();
+        let disks = Disks::new_with_refreshed_list();
+        let mounts: Vec<String> = disks
+            .iter()
+            .map(|d| d.mount_point().to_string_lossy().to_string())
+            .collect();
+        let since = chrono::Utc::now().timestamp() - GROWTH_WINDOW_SECS;
+
+        disks
+            .iter()
+            .zip(&mounts)
+            .filter(|(disk, mount)| {
+                disk.total_space() > 0 && (settings.tracked_drives.is_empty() || settings.tracked_drives.contains(mount))
+            })
+            .filter_map(|(disk, mount)| {
+                let free_percent = disk.available_space() as f64 / disk.total_space() as f64 * 100.0;
+                if free_percent >= settings.threshold_percent {
+                    return None;
+                }
+                let name = match disk.name().to_string_lossy().to_string() {
+                    name if name.is_empty() => mount.clone(),
+                    name => name,
+                };
+                let growth = history.largest_growth(|root| owning_mount(root, &mounts) == Some(mount), since);
+                Some(DriveAlert {
+                    message: message(&name, settings.threshold_percent, growth.as_ref()),
+                    mount_point: mount.clone(),
+                    name,
+                    total_bytes: disk.total_space(),
+                    available_bytes: disk.available_space(),
+                    free_percent,
+                    growth,
+                })
+            })
+            .collect()
+    }
+
+    /// Alerts for drives that crossed the threshold since the last call;
+    /// drives that recovered are forgotten so they can alert again
+    fn new_alerts(&self, alerts: Vec<DriveAlert>) -> Vec<DriveAlert> {
+        let mut alerted = self.alerted.lock().unwrap();
+        alerted.retain(|mount| alerts.iter().any(|a| &a.mount_point == mount));
+        alerts
+            .into_iter()
+            .filter(|a| alerted.insert(a.mount_point.clone()))
+            .collect()
+    }
+
+    /// Check drives periodically while the app runs
+    pub fn spawn(app: AppHandle) {
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let monitor = app.state::<AlertMonitor>();
+                let settings = monitor.settings();
+                if settings.enabled {
+                    let alerts = monitor.check(&app.state::<ScanHistory>());
+                    for alert in monitor.new_alerts(alerts) {
+                        info!("Low disk space on {}: {:.1}% free", alert.mount_point, alert.free_percent);
+                        if let Err(e) = app
+                            .notification()
+                            .builder()
+                            .title(i18n::t("alert.title"))
+                            .body(&alert.message)
+                            .show()
+                        {
+                            warn!("Failed to show notification: {}", e);
+                        }
+                        let _ = app.emit(DRIVE_ALERT_EVENT, alert);
+                    }
+                }
+                tokio::time::sleep(Duration::from_secs(settings.interval_minutes * 60)).await;
+            }
+        });
+    }
+}
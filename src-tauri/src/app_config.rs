@@ -0,0 +1,148 @@
+// App Config - Portable export and import of the user's setup
+//
+// Bundles profiles (scan roots, exclusions, MCP directories, default model),
+// user prompt presets, alert, download and generation settings and the MCP
+// guardrail policy into one JSON file that can be imported on another
+// machine. API keys live in the OS keychain and are never exported.
+// Importing merges by ID: existing entries with the same ID are replaced,
+// everything else is kept.
+
+use crate::ai::bandwidth::{self, BandwidthSettings};
+use crate::ai::length_policy::{LengthPolicy, LengthPolicyStore};
+use crate::ai::presets::{PresetStore, PromptPreset};
+use crate::alerts::{AlertMonitor, AlertSettings};
+use crate::mcp::GuardrailPolicy;
+use crate::mcp_commands_native::NativeMCPState;
+use crate::profiles::{Profile, ProfileStore};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+/// Version of the export format; newer files are refused
+pub const CONFIG_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppConfig {
+    pub format_version: u32,
+    pub app_version: String,
+    /// Unix timestamp of the export
+    pub exported_at: i64,
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile_id: Option<String>,
+    #[serde(default)]
+    pub prompt_presets: Vec<PromptPreset>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alert_settings: Option<AlertSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_settings: Option<BandwidthSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length_policy: Option<LengthPolicy>,
+    /// Only present if the MCP server was running at export time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guardrails: Option<GuardrailPolicy>,
+}
+
+/// What an import changed
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub profiles: usize,
+    pub prompt_presets: usize,
+    pub settings: Vec<String>,
+    /// Parts that could not be applied, with the reason
+    pub skipped: Vec<String>,
+}
+
+/// Collect the current configuration
+pub async fn collect(app: &AppHandle) -> AppConfig {
+    let profiles = app.state::<ProfileStore>();
+    let guardrails = match app.state::<NativeMCPState>().server().await {
+        Ok(server) => Some(server.guardrails().await),
+        Err(_) => None,
+    };
+    AppConfig {
+        format_version: CONFIG_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: chrono::Utc::now().timestamp(),
+        profiles: profiles.list(),
+        active_profile_id: profiles.active().map(|p| p.id),
+        prompt_presets: app.state::<PresetStore>().user_presets(),
+        alert_settings: Some(app.state::<AlertMonitor>().settings()),
+        download_settings: Some(bandwidth::settings()),
+        length_policy: Some(app.state::<LengthPolicyStore>().get()),
+        guardrails,
+    }
+}
+
+pub async fn export(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let config = collect(app).await;
+    let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    info!("Exported configuration to {}", path.display());
+    Ok(())
+}
+
+pub async fn import(app: &AppHandle, path: &Path) -> Result<ImportSummary, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let config: AppConfig = serde_json::from_str(&content).map_err(|e| format!("Not a Helium configuration file: {}", e))?;
+    if config.format_version > CONFIG_FORMAT_VERSION {
+        return Err(format!(
+            "Exported by a newer version of Helium ({}); update to import it",
+            config.app_version
+        ));
+    }
+
+    let mut summary = ImportSummary::default();
+    let mut note = |name: &str, result: Result<(), String>| match result {
+        Ok(()) => summary.settings.push(name.to_string()),
+        Err(e) => {
+            warn!("Skipped {} on import: {}", name, e);
+            summary.skipped.push(format!("{}: {}", name, e));
+        }
+    };
+
+    let profiles = app.state::<ProfileStore>();
+    let mut imported_profiles = 0;
+    for profile in config.profiles {
+        let name = profile.name.clone();
+        match profiles.save_profile(profile) {
+            Ok(_) => imported_profiles += 1,
+            Err(e) => note(&format!("profile \"{}\"", name), Err(e)),
+        }
+    }
+    if let Some(id) = &config.active_profile_id {
+        note("active profile", profiles.switch(Some(id)).map(|_| ()));
+    }
+    if let Some(settings) = config.alert_settings {
+        note("alerts", app.state::<AlertMonitor>().set_settings(settings).map(|_| ()));
+    }
+    if let Some(settings) = config.download_settings {
+        note("downloads", bandwidth::set_limit(settings.limit_bytes_per_sec).map(|_| ()));
+    }
+    if let Some(policy) = config.length_policy {
+        note("generation limits", app.state::<LengthPolicyStore>().set(policy).map(|_| ()));
+    }
+    if let Some(policy) = config.guardrails {
+        match app.state::<NativeMCPState>().server().await {
+            Ok(server) => {
+                server.set_guardrails(policy).await;
+                note("guardrails", Ok(()));
+            }
+            Err(e) => note("guardrails", Err(e)),
+        }
+    }
+
+    summary.profiles = imported_profiles;
+    summary.prompt_presets = app.state::<PresetStore>().import(config.prompt_presets)?;
+    info!(
+        "Imported configuration from {}: {} profiles, {} presets",
+        path.display(),
+        summary.profiles,
+        summary.prompt_presets
+    );
+    Ok(summary)
+}
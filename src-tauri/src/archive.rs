@@ -0,0 +1,425 @@
+// Archive - Offloading cold files instead of deleting them
+//
+// Copies a file to a folder, SSH host or rclone remote, verifies it and leaves
+// a `<name>.helium-archive` stub that `restore` uses to bring it back.
+
+use crate::audit;
+use crate::paths;
+use crate::remote::{self, RemoteStore};
+use crate::safeguard;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// Event emitted when files are archived or restored
+pub const ARCHIVE_EVENT: &str = "archive-changed";
+/// Extension appended to an archived file's path for its stub
+const STUB_EXTENSION: &str = ".helium-archive";
+/// Extension of a restore in progress, renamed once verified
+const PARTIAL_EXTENSION: &str = ".helium-restoring";
+
+/// Where archived files are copied to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase", tag = "kind")]
+pub enum ArchiveTarget {
+    /// A local folder, usually on another drive
+    Folder { path: String },
+    /// A folder on a host from the remote hosts list
+    Sftp { host_id: String, path: String },
+    /// An rclone remote and path, e.g. "nas:archive"
+    Rclone { remote: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveDestination {
+    pub id: String,
+    pub name: String,
+    pub target: ArchiveTarget,
+}
+
+/// A file moved to a destination; also the content of its stub
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedFile {
+    pub id: String,
+    pub original_path: String,
+    pub stub_path: String,
+    pub destination_name: String,
+    pub target: ArchiveTarget,
+    /// Path of the copy at the destination
+    pub location: String,
+    pub size: u64,
+    pub sha256: String,
+    /// Unix timestamp in seconds
+    pub archived_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveSummary {
+    pub destinations: Vec<ArchiveDestination>,
+    pub entries: Vec<ArchivedFile>,
+    pub total_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveResult {
+    pub archived: Vec<ArchivedFile>,
+    pub failed: Vec<ArchiveFailure>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveRestoreResult {
+    pub restored: Vec<String>,
+    pub failed: Vec<ArchiveFailure>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ArchiveState {
+    #[serde(default)]
+    destinations: Vec<ArchiveDestination>,
+    #[serde(default)]
+    entries: Vec<ArchivedFile>,
+}
+
+/// Run rclone, returning its output
+fn rclone(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("rclone")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to start rclone (is it installed?): {}", e))?;
+    if !output.status.success() {
+        return Err(format!("rclone: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// `base/rest` for a remote path; "remote:" takes no separator
+fn join_remote(base: &str, rest: &str) -> String {
+    if base.ends_with(':') {
+        format!("{}{}", base, rest)
+    } else {
+        format!("{}/{}", base.trim_end_matches('/'), rest)
+    }
+}
+
+impl ArchiveTarget {
+    fn validate(&self, remotes: &RemoteStore) -> Result<(), String> {
+        match self {
+            Self::Folder { path } if !Path::new(path).is_absolute() => {
+                Err("The archive folder must be an absolute path".to_string())
+            }
+            Self::Sftp { host_id, path } => {
+                remotes.get(host_id).ok_or_else(|| format!("Unknown remote host: {}", host_id))?;
+                if path.starts_with('/') {
+                    Ok(())
+                } else {
+                    Err("The remote folder must be an absolute path".to_string())
+                }
+            }
+            Self::Rclone { remote } if !remote.contains(':') => {
+                Err("An rclone destination is written \"remote:path\"".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Where the copy of file `name` archived as `id` goes
+    fn location(&self, id: &str, name: &str) -> String {
+        let rest = format!("{}/{}", id, name);
+        match self {
+            Self::Folder { path } => paths::display(&Path::new(path).join(id).join(name)),
+            Self::Sftp { path, .. } => join_remote(path, &rest),
+            Self::Rclone { remote } => join_remote(remote, &rest),
+        }
+    }
+
+    fn host(&self, remotes: &RemoteStore) -> Result<remote::RemoteHost, String> {
+        match self {
+            Self::Sftp { host_id, .. } => remotes.get(host_id).ok_or_else(|| format!("Unknown remote host: {}", host_id)),
+            _ => Err("Not a remote host destination".to_string()),
+        }
+    }
+
+    fn upload(&self, remotes: &RemoteStore, local: &Path, location: &str) -> Result<(), String> {
+        match self {
+            Self::Folder { .. } => {
+                let to = paths::extended(Path::new(location));
+                if let Some(parent) = to.parent() {
+                    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                std::fs::copy(local, &to).map(|_| ()).map_err(|e| e.to_string())
+            }
+            Self::Sftp { .. } => self.host(remotes)?.upload(local, location),
+            Self::Rclone { .. } => rclone(&["copyto", &local.to_string_lossy(), location]).map(|_| ()),
+        }
+    }
+
+    /// Size of the copy at `location`
+    fn size(&self, remotes: &RemoteStore, location: &str) -> Result<u64, String> {
+        match self {
+            Self::Folder { .. } => std::fs::metadata(paths::extended(Path::new(location)))
+                .map(|m| m.len())
+                .map_err(|e| e.to_string()),
+            Self::Sftp { .. } => {
+                let output = self.host(remotes)?.run(&format!("wc -c < {}", remote::shell_quote(location)))?;
+                output.trim().parse().map_err(|_| format!("Unexpected size of {}: {}", location, output.trim()))
+            }
+            Self::Rclone { .. } => {
+                let output = rclone(&["size", "--json", location])?;
+                let value: serde_json::Value = serde_json::from_str(&output).map_err(|e| e.to_string())?;
+                value["bytes"].as_u64().ok_or_else(|| format!("Unexpected rclone size output: {}", output.trim()))
+            }
+        }
+    }
+
+    fn download(&self, remotes: &RemoteStore, location: &str, local: &Path) -> Result<(), String> {
+        match self {
+            Self::Folder { .. } => std::fs::copy(paths::extended(Path::new(location)), local)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            Self::Sftp { .. } => self.host(remotes)?.download(location, local),
+            Self::Rclone { .. } => rclone(&["copyto", location, &local.to_string_lossy()]).map(|_| ()),
+        }
+    }
+
+    /// Delete the copy at `location` and its per-file folder
+    fn remove(&self, remotes: &RemoteStore, location: &str) -> Result<(), String> {
+        match self {
+            Self::Folder { .. } => {
+                let copy = paths::extended(Path::new(location));
+                match std::fs::remove_file(&copy) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.to_string()),
+                }
+                if let Some(parent) = copy.parent() {
+                    let _ = std::fs::remove_dir(parent);
+                }
+                Ok(())
+            }
+            Self::Sftp { .. } => {
+                let parent = location.rsplit_once('/').map_or("", |(parent, _)| parent);
+                let command = format!(
+                    "rm -f {} && (rmdir {} 2>/dev/null || true)",
+                    remote::shell_quote(location),
+                    remote::shell_quote(parent)
+                );
+                self.host(remotes)?.run(&command).map(|_| ())
+            }
+            // Object stores have no real folders to clean up
+            Self::Rclone { .. } => rclone(&["deletefile", location]).map(|_| ()),
+        }
+    }
+}
+
+fn stub_path(original: &str) -> String {
+    format!("{}{}", original, STUB_EXTENSION)
+}
+
+fn remove_if_present(path: &Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub struct ArchiveStore {
+    path: PathBuf,
+    state: Mutex<ArchiveState>,
+}
+
+impl ArchiveStore {
+    pub fn load(path: PathBuf) -> Self {
+        let state = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        Self {
+            path,
+            state: Mutex::new(state),
+        }
+    }
+
+    fn save(&self, state: &ArchiveState) -> Result<(), String> {
+        storage::save_json(&self.path, state)
+    }
+
+    pub fn summary(&self) -> ArchiveSummary {
+        let state = self.state.lock().unwrap();
+        ArchiveSummary {
+            destinations: state.destinations.clone(),
+            total_size: state.entries.iter().map(|e| e.size).sum(),
+            entries: state.entries.clone(),
+        }
+    }
+
+    pub fn save_destination(
+        &self,
+        mut destination: ArchiveDestination,
+        remotes: &RemoteStore,
+    ) -> Result<ArchiveDestination, String> {
+        if destination.name.trim().is_empty() {
+            return Err("Destination name cannot be empty".to_string());
+        }
+        destination.target.validate(remotes)?;
+        if destination.id.is_empty() {
+            destination.id = uuid::Uuid::new_v4().to_string();
+        }
+        let mut state = self.state.lock().unwrap();
+        match state.destinations.iter_mut().find(|d| d.id == destination.id) {
+            Some(existing) => *existing = destination.clone(),
+            None => state.destinations.push(destination.clone()),
+        }
+        self.save(&state)?;
+        Ok(destination)
+    }
+
+    /// Remove a destination from the list; files archived there stay
+    /// restorable
+    pub fn delete_destination(&self, id: &str) -> Result<bool, String> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.destinations.len();
+        state.destinations.retain(|d| d.id != id);
+        if state.destinations.len() == before {
+            return Ok(false);
+        }
+        self.save(&state)?;
+        Ok(true)
+    }
+
+    /// Copy one file to a destination, verify the copy and replace the
+    /// original with its stub
+    pub fn archive(&self, original: &str, destination_id: &str, remotes: &RemoteStore) -> Result<ArchivedFile, String> {
+        audit::ensure_writable()?;
+        let destination = self
+            .state
+            .lock()
+            .unwrap()
+            .destinations
+            .iter()
+            .find(|d| d.id == destination_id)
+            .cloned()
+            .ok_or_else(|| format!("Unknown archive destination: {}", destination_id))?;
+        let from = paths::extended(Path::new(original));
+        let meta = std::fs::symlink_metadata(&from).map_err(|e| format!("{}: {}", original, e))?;
+        if !meta.is_file() {
+            return Err(format!("{}: only files can be archived", original));
+        }
+        let stub = stub_path(original);
+        if Path::new(&stub).exists() {
+            return Err(format!("{} already exists", stub));
+        }
+        let sha256 = safeguard::sha256_file(&from).ok_or_else(|| format!("{}: cannot be read", original))?;
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let name = Path::new(original)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "file".to_string());
+        let target = destination.target;
+        let location = target.location(&id, &name);
+        let stored = target
+            .upload(remotes, &from, &location)
+            .and_then(|()| target.size(remotes, &location));
+        if stored != Ok(meta.len()) {
+            let _ = target.remove(remotes, &location);
+            return Err(match stored {
+                Err(e) => format!("{}: {}", original, e),
+                Ok(size) => format!("{}: copy at {} has {} of {} bytes", original, location, size, meta.len()),
+            });
+        }
+
+        let entry = ArchivedFile {
+            id,
+            original_path: original.to_string(),
+            stub_path: stub.clone(),
+            destination_name: destination.name,
+            target,
+            location,
+            size: meta.len(),
+            sha256,
+            archived_at: chrono::Utc::now().timestamp(),
+        };
+        let stub_written = serde_json::to_string_pretty(&entry)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(paths::extended(Path::new(&stub)), json).map_err(|e| e.to_string()));
+        if let Err(e) = stub_written.and_then(|()| std::fs::remove_file(&from).map_err(|e| e.to_string())) {
+            let _ = remove_if_present(&paths::extended(Path::new(&stub)));
+            let _ = entry.target.remove(remotes, &entry.location);
+            return Err(format!("{}: {}", original, e));
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.entries.push(entry.clone());
+        self.save(&state)?;
+        info!("Archived {} to {} ({} bytes)", original, entry.location, entry.size);
+        Ok(entry)
+    }
+
+    /// Copy an archived file back, verifying it against its checksum
+    fn restore_one(&self, entry: &ArchivedFile, remotes: &RemoteStore) -> Result<(), String> {
+        let original = paths::extended(Path::new(&entry.original_path));
+        if original.exists() {
+            return Err("Something else now exists at the original path".to_string());
+        }
+        if let Some(parent) = original.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let partial = paths::extended(Path::new(&format!("{}{}", entry.original_path, PARTIAL_EXTENSION)));
+        let verified = entry.target.download(remotes, &entry.location, &partial).and_then(|()| {
+            match safeguard::sha256_file(&partial) {
+                Some(hash) if hash == entry.sha256 => Ok(()),
+                _ => Err(format!("Copy at {} does not match the archived file", entry.location)),
+            }
+        });
+        if let Err(e) = verified.and_then(|()| std::fs::rename(&partial, &original).map_err(|e| e.to_string())) {
+            let _ = remove_if_present(&partial);
+            return Err(e);
+        }
+        remove_if_present(&paths::extended(Path::new(&entry.stub_path)))?;
+        // The file is back; a copy left at the destination only costs space there
+        if let Err(e) = entry.target.remove(remotes, &entry.location) {
+            warn!("Could not remove archived copy {}: {}", entry.location, e);
+        }
+        Ok(())
+    }
+
+    /// Bring one archived file back to its original path. It stays archived
+    /// if that path is taken again.
+    pub fn restore(&self, id: &str, remotes: &RemoteStore) -> Result<String, ArchiveFailure> {
+        let failure = |path: &str, error: String| ArchiveFailure {
+            path: path.to_string(),
+            error,
+        };
+        audit::ensure_writable().map_err(|e| failure(id, e))?;
+        let entry = self
+            .state
+            .lock()
+            .unwrap()
+            .entries
+            .iter()
+            .find(|e| e.id == id)
+            .cloned()
+            .ok_or_else(|| failure(id, "Unknown archived file".to_string()))?;
+        self.restore_one(&entry, remotes)
+            .map_err(|e| failure(&entry.original_path, e))?;
+        let mut state = self.state.lock().unwrap();
+        state.entries.retain(|e| e.id != id);
+        self.save(&state).map_err(|e| failure(&entry.original_path, e))?;
+        info!("Restored {} from {}", entry.original_path, entry.location);
+        Ok(entry.original_path)
+    }
+}
@@ -0,0 +1,208 @@
+// Audit Mode - Global read-only switch
+//
+// With audit mode on, Helium can scan and the AI can explore, but nothing on
+// disk is modified: item deletion, junk cleaning, snapshot reclaiming, WSL
+// compaction and every MCP write, move, edit or delete are refused with the
+// `AUDIT_MODE_ERROR` message (MCP code `AUDIT_MODE_CODE`). The switch is enforced
+// here in the backend, so no frontend or agent path can bypass it, and it
+// persists across restarts.
+//
+// The audit log is an append-only JSON-lines file of evidence records, such
+// as shredding reports. Each entry carries the SHA-256 of its content chained
+// with the previous entry's hash, so editing, removing or reordering entries
+// after the fact breaks the chain and `verify_log` reports where.
+
+use crate::i18n;
+use crate::mcp::MCPError;
+use crate::storage;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::info;
+
+/// Catalog key of the error returned by every refused modification
+pub const AUDIT_MODE_ERROR: &str = "error.audit_mode";
+
+/// MCP error code of a refused tool call
+pub const AUDIT_MODE_CODE: i32 = -32005;
+
+/// Event emitted when audit mode is switched
+pub const AUDIT_MODE_EVENT: &str = "audit-mode-changed";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditSettings {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+struct AuditState {
+    settings: AuditSettings,
+    path: Option<PathBuf>,
+    log_path: Option<PathBuf>,
+}
+
+/// Hash the first entry of the log chains to
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// One record of the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogEntry {
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+    /// What the record is evidence of, e.g. "shred"
+    pub kind: String,
+    pub record: serde_json::Value,
+    pub previous_hash: String,
+    /// SHA-256 over the previous hash and this entry's content
+    pub hash: String,
+}
+
+/// Result of checking the log's hash chain
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogCheck {
+    pub entries: usize,
+    pub intact: bool,
+    /// Zero-based line of the first entry that doesn't chain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broken_at: Option<usize>,
+}
+
+lazy_static! {
+    static ref AUDIT: Mutex<AuditState> = Mutex::new(AuditState {
+        settings: AuditSettings::default(),
+        path: None,
+        log_path: None,
+    });
+}
+
+/// Load the persisted switch (called once at startup)
+pub fn load(path: PathBuf) {
+    let settings: AuditSettings = storage::load_json(&path).ok().flatten().unwrap_or_default();
+    let mut state = AUDIT.lock().unwrap();
+    state.settings = settings;
+    state.path = Some(path);
+}
+
+/// Set where the audit log is kept (called once at startup)
+pub fn open_log(path: PathBuf) {
+    AUDIT.lock().unwrap().log_path = Some(path);
+}
+
+pub fn is_enabled() -> bool {
+    AUDIT.lock().unwrap().settings.enabled
+}
+
+pub fn set_enabled(enabled: bool) -> Result<AuditSettings, String> {
+    let mut state = AUDIT.lock().unwrap();
+    state.settings.enabled = enabled;
+    if let Some(path) = &state.path {
+        storage::save_json(path, &state.settings)?;
+    }
+    info!("Audit mode {}", if enabled { "on" } else { "off" });
+    Ok(state.settings.clone())
+}
+
+/// Refuse a modification while audit mode is on
+pub fn ensure_writable() -> Result<(), String> {
+    if is_enabled() {
+        Err(i18n::t(AUDIT_MODE_ERROR))
+    } else {
+        Ok(())
+    }
+}
+
+/// `ensure_writable` for MCP tools
+pub fn ensure_writable_mcp() -> Result<(), MCPError> {
+    ensure_writable().map_err(|message| MCPError {
+        code: AUDIT_MODE_CODE,
+        message,
+        data: None,
+    })
+}
+
+fn entry_hash(previous_hash: &str, timestamp: i64, kind: &str, record: &serde_json::Value) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(previous_hash.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(timestamp.to_string().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(kind.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(record.to_string().as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+fn read_entries(path: &std::path::Path) -> Result<Vec<AuditLogEntry>, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.to_string()),
+    };
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(|e| format!("Corrupt audit log entry: {}", e)))
+        .collect()
+}
+
+/// Append a record to the audit log, chained to the last entry
+pub fn append_log<T: Serialize>(kind: &str, record: &T) -> Result<AuditLogEntry, String> {
+    // The lock also keeps concurrent appends from chaining to the same entry
+    let state = AUDIT.lock().unwrap();
+    let path = state.log_path.as_ref().ok_or("Audit log is not open")?;
+    let previous_hash = read_entries(path)?
+        .last()
+        .map(|e| e.hash.clone())
+        .unwrap_or_else(|| GENESIS_HASH.to_string());
+    let record = serde_json::to_value(record).map_err(|e| e.to_string())?;
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let entry = AuditLogEntry {
+        hash: entry_hash(&previous_hash, timestamp, kind, &record),
+        timestamp,
+        kind: kind.to_string(),
+        record,
+        previous_hash,
+    };
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    let line = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+    writeln!(file, "{}", line).and_then(|_| file.sync_all()).map_err(|e| e.to_string())?;
+    info!("Audit log: {} entry {}", kind, &entry.hash[..12]);
+    Ok(entry)
+}
+
+/// All entries of the audit log, oldest first
+pub fn read_log() -> Result<Vec<AuditLogEntry>, String> {
+    let path = AUDIT.lock().unwrap().log_path.clone().ok_or("Audit log is not open")?;
+    read_entries(&path)
+}
+
+/// Recompute the hash chain of the audit log
+pub fn verify_log() -> Result<AuditLogCheck, String> {
+    let entries = read_log()?;
+    let mut previous = GENESIS_HASH.to_string();
+    let broken_at = entries.iter().position(|entry| {
+        let valid = entry.previous_hash == previous
+            && entry.hash == entry_hash(&entry.previous_hash, entry.timestamp, &entry.kind, &entry.record);
+        previous = entry.hash.clone();
+        !valid
+    });
+    Ok(AuditLogCheck {
+        entries: entries.len(),
+        intact: broken_at.is_none(),
+        broken_at,
+    })
+}
@@ -0,0 +1,282 @@
+// Backups - Backup targets and how fresh they are
+//
+// Deleting originals is only safe if a recent backup holds them. This finds
+// common backup targets on the mounted volumes and in the home folder: Time
+// Machine destinations (from `tmutil` on macOS, or a Backups.backupdb folder
+// anywhere), Windows File History folders, Borg and restic repositories and
+// rsnapshot-style rsync trees, each recognized by its structure, and reports
+// when each was last updated. Before a deletion the frontend asks for a
+// check, which warns when the newest backup is older than STALE_AFTER_DAYS or
+// there is none at all. Detection looks two levels below each root, so
+// backups buried deeper are not found.
+
+use crate::paths;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use sysinfo::Disks;
+use tracing::debug;
+
+/// A backup older than this is reported as stale
+pub const STALE_AFTER_DAYS: u64 = 30;
+/// Levels below a volume root or the home folder searched for backups
+const SEARCH_DEPTH: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackupKind {
+    TimeMachine,
+    FileHistory,
+    Borg,
+    Restic,
+    Rsnapshot,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupTarget {
+    pub path: String,
+    pub kind: BackupKind,
+    /// Unix timestamp in seconds of the newest backup; None if unknown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_updated: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age_days: Option<u64>,
+    pub stale: bool,
+}
+
+/// Result of checking backups before a deletion
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupCheck {
+    pub targets: Vec<BackupTarget>,
+    /// Most recently updated target
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub newest: Option<BackupTarget>,
+    /// No backup at all, or even the newest one is stale
+    pub stale: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn mtime(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    modified.duration_since(SystemTime::UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+/// Newest modification time of the entries directly in `dir`
+fn newest_child(dir: &Path, filter: impl Fn(&str) -> bool) -> Option<u64> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| filter(&e.file_name().to_string_lossy()))
+        .filter_map(|e| mtime(&e.path()))
+        .max()
+}
+
+/// Unix time of a "2024-01-15-123456" snapshot name (optionally ".backup")
+fn snapshot_time(name: &str) -> Option<u64> {
+    let stamp = name.strip_suffix(".backup").unwrap_or(name);
+    chrono::NaiveDateTime::parse_from_str(stamp, "%Y-%m-%d-%H%M%S")
+        .ok()
+        .map(|t| t.and_utc().timestamp().max(0) as u64)
+}
+
+/// Time Machine: a Backups.backupdb folder holds <machine>/<snapshot> folders
+fn time_machine(dir: &Path) -> Option<u64> {
+    let db = dir.join("Backups.backupdb");
+    if !db.is_dir() {
+        return None;
+    }
+    let newest = std::fs::read_dir(&db)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter_map(|machine| std::fs::read_dir(machine.path()).ok())
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| snapshot_time(&e.file_name().to_string_lossy()))
+        .max();
+    newest.or_else(|| mtime(&db))
+}
+
+/// File History: FileHistory/<user>/<machine>/{Configuration,Data}
+fn file_history(dir: &Path) -> Option<u64> {
+    if dir.file_name()?.to_string_lossy() != "FileHistory" {
+        return None;
+    }
+    let machines: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter_map(|user| std::fs::read_dir(user.path()).ok())
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.join("Data").is_dir())
+        .collect();
+    if machines.is_empty() {
+        return None;
+    }
+    // The configuration catalogs are rewritten after every backup cycle
+    machines
+        .iter()
+        .filter_map(|m| newest_child(&m.join("Configuration"), |_| true).or_else(|| mtime(&m.join("Data"))))
+        .max()
+}
+
+/// Borg: README naming it, a config file and a data folder; index.N and
+/// hints.N are rewritten on every commit
+fn borg(dir: &Path) -> Option<u64> {
+    let readme = std::fs::read_to_string(dir.join("README")).ok()?;
+    if !readme.contains("Borg Backup repository") || !dir.join("data").is_dir() {
+        return None;
+    }
+    newest_child(dir, |name| name.starts_with("index.") || name.starts_with("hints.")).or_else(|| mtime(dir))
+}
+
+/// restic: config, data, index, keys and snapshots; one file per snapshot
+fn restic(dir: &Path) -> Option<u64> {
+    let layout = ["data", "index", "keys", "snapshots"];
+    if !dir.join("config").is_file() || !layout.iter().all(|d| dir.join(d).is_dir()) {
+        return None;
+    }
+    newest_child(&dir.join("snapshots"), |_| true).or_else(|| mtime(dir))
+}
+
+/// rsnapshot and similar rsync rotations: hourly.0, daily.0, weekly.0, ...
+fn rsnapshot(dir: &Path) -> Option<u64> {
+    let rotations = ["hourly.0", "daily.0", "weekly.0", "monthly.0"];
+    rotations.iter().filter_map(|r| mtime(&dir.join(r))).max()
+}
+
+/// Newest snapshot time of one backup layout, if `dir` holds it
+type Detector = fn(&Path) -> Option<u64>;
+
+fn detect_at(dir: &Path) -> Option<(BackupKind, u64)> {
+    let detectors: [(BackupKind, Detector); 5] = [
+        (BackupKind::TimeMachine, time_machine),
+        (BackupKind::FileHistory, file_history),
+        (BackupKind::Borg, borg),
+        (BackupKind::Restic, restic),
+        (BackupKind::Rsnapshot, rsnapshot),
+    ];
+    detectors
+        .iter()
+        .find_map(|(kind, detect)| detect(dir).map(|time| (*kind, time)))
+}
+
+fn target(path: &Path, kind: BackupKind, last_updated: Option<u64>, now: u64) -> BackupTarget {
+    let age_days = last_updated.map(|t| now.saturating_sub(t) / 86_400);
+    BackupTarget {
+        path: paths::display(path),
+        kind,
+        last_updated,
+        age_days,
+        stale: !age_days.is_some_and(|days| days <= STALE_AFTER_DAYS),
+    }
+}
+
+/// Time Machine destinations as macOS knows them, even when their disk is
+/// not connected (then only the latest backup time is known)
+#[cfg(target_os = "macos")]
+fn time_machine_destinations(now: u64) -> Vec<BackupTarget> {
+    let output = std::process::Command::new("tmutil").arg("latestbackup").output();
+    let Ok(output) = output else {
+        return Vec::new();
+    };
+    let latest = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || latest.is_empty() {
+        debug!("tmutil latestbackup: {}", String::from_utf8_lossy(&output.stderr).trim());
+        return Vec::new();
+    }
+    let snapshot = Path::new(&latest);
+    let time = snapshot
+        .file_name()
+        .and_then(|n| snapshot_time(&n.to_string_lossy()))
+        .or_else(|| mtime(snapshot));
+    // Report the destination volume rather than the snapshot, as the volume
+    // scan would find it
+    let destination = snapshot
+        .ancestors()
+        .find(|p| p.file_name().is_some_and(|n| n == "Backups.backupdb"))
+        .and_then(Path::parent)
+        .unwrap_or(snapshot);
+    vec![target(destination, BackupKind::TimeMachine, time, now)]
+}
+
+#[cfg(not(target_os = "macos"))]
+fn time_machine_destinations(_now: u64) -> Vec<BackupTarget> {
+    Vec::new()
+}
+
+/// Backup targets on the mounted volumes and in the home folder, newest first
+pub fn detect() -> Vec<BackupTarget> {
+    let now = now_secs();
+    let mut roots: Vec<PathBuf> = Disks::new_with_refreshed_list()
+        .iter()
+        .map(|d| d.mount_point().to_path_buf())
+        .collect();
+    roots.extend(dirs::home_dir());
+
+    let mut targets = time_machine_destinations(now);
+    for root in roots {
+        let walker = walkdir::WalkDir::new(paths::extended(&root)).max_depth(SEARCH_DEPTH);
+        for entry in walker.into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_dir()) {
+            // Folders inside a backup found earlier (rsnapshot's daily.0, a
+            // volume seen again through the home folder) are the same backup
+            if targets.iter().any(|t| paths::is_within(entry.path(), Path::new(&t.path))) {
+                continue;
+            }
+            let Some((kind, time)) = detect_at(entry.path()) else {
+                continue;
+            };
+            debug!("Backup target {:?} at {}", kind, entry.path().display());
+            targets.push(target(entry.path(), kind, Some(time), now));
+        }
+    }
+    targets.sort_by(|a, b| b.last_updated.cmp(&a.last_updated));
+    targets
+}
+
+/// Whether a recent backup exists before `paths_to_delete` are deleted.
+/// Paths that are themselves inside a backup need no warning.
+pub fn check(paths_to_delete: &[String]) -> BackupCheck {
+    let targets = detect();
+    let inside_backup = |path: &String| targets.iter().any(|t| paths::is_within(Path::new(path), Path::new(&t.path)));
+    if !paths_to_delete.is_empty() && paths_to_delete.iter().all(inside_backup) {
+        return BackupCheck {
+            targets,
+            newest: None,
+            stale: false,
+            warning: None,
+        };
+    }
+
+    let newest = targets.first().cloned();
+    let (stale, warning) = match &newest {
+        None => (true, Some("No backup was found. Deleted files can't be restored from a backup.".to_string())),
+        Some(BackupTarget { last_updated: None, path, .. }) => (
+            true,
+            Some(format!("The backup at {} has no readable date; check that it is current.", path)),
+        ),
+        Some(BackupTarget { age_days: Some(days), path, stale: true, .. }) => (
+            true,
+            Some(format!(
+                "The newest backup ({}) was last updated {} days ago; files changed since then are not in it.",
+                path, days
+            )),
+        ),
+        Some(_) => (false, None),
+    };
+    BackupCheck {
+        targets,
+        newest,
+        stale,
+        warning,
+    }
+}
@@ -0,0 +1,313 @@
+// Basket - Staged deletions across scans and categories
+//
+// Instead of deleting item by item, users (and the AI agent, through the
+// `stage_for_deletion` MCP tool) stage candidate paths from any view: the
+// treemap, junk categories, duplicates, photo bursts. The basket persists
+// across restarts, reports the total it would reclaim and flags conflicts:
+// paths that vanished, paths already covered by a staged folder, paths that
+// changed since they were staged and paths that must never be deleted.
+// Everything is deleted in one commit, which only goes through for the
+// basket revision the user reviewed, so nothing staged afterwards (e.g. by
+// the agent) is deleted unseen. Items the agent or a heuristic picked are
+// quarantined by default rather than deleted, so they can still be restored
+// for a while.
+
+use crate::audit;
+use crate::paths;
+use crate::quarantine::{quarantines_by_default, QuarantineStore};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tracing::info;
+
+/// Event emitted when the basket's contents change
+pub const BASKET_EVENT: &str = "basket-changed";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BasketItem {
+    pub path: String,
+    /// Size when staged
+    pub size: u64,
+    pub is_dir: bool,
+    /// Where it was staged from: "treemap", "junk", "duplicates", "ai", ...
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub added_at: i64,
+    /// Modification time when staged, to detect later changes
+    pub modified: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictKind {
+    /// The path no longer exists; it is skipped on commit
+    Missing,
+    /// Inside another staged folder; counted and deleted once
+    Nested,
+    /// Modified since it was staged; re-stage or remove it to commit
+    Changed,
+    /// A root or home folder; remove it to commit
+    Protected,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BasketConflict {
+    pub path: String,
+    pub kind: ConflictKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub other_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BasketSummary {
+    /// Changes on every edit; pass it to commit
+    pub revision: u64,
+    pub items: Vec<BasketItem>,
+    /// Bytes a commit would free, counting nested items once
+    pub total_size: u64,
+    pub conflicts: Vec<BasketConflict>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BasketFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BasketCommitResult {
+    pub deleted: Vec<String>,
+    /// Moved into quarantine instead of deleted
+    pub quarantined: Vec<String>,
+    pub failed: Vec<BasketFailure>,
+    pub freed: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BasketState {
+    revision: u64,
+    items: Vec<BasketItem>,
+}
+
+fn modified_secs(meta: &std::fs::Metadata) -> u64 {
+    meta.modified()
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn is_protected(path: &Path) -> bool {
+    paths::is_root_or_home(path)
+}
+
+/// Staged ancestor of `path`, if any
+fn covering<'a>(path: &str, items: &'a [BasketItem]) -> Option<&'a BasketItem> {
+    items
+        .iter()
+        .find(|other| other.is_dir && other.path != path && Path::new(path).starts_with(&other.path))
+}
+
+pub struct BasketStore {
+    path: PathBuf,
+    state: Mutex<BasketState>,
+}
+
+impl BasketStore {
+    pub fn load(path: PathBuf) -> Self {
+        let state = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        Self {
+            path,
+            state: Mutex::new(state),
+        }
+    }
+
+    fn save(&self, state: &mut BasketState) -> Result<(), String> {
+        state.revision += 1;
+        storage::save_json(&self.path, &*state)
+    }
+
+    /// Stage paths, measuring their current size; staging a path again
+    /// refreshes it (which clears a "changed" conflict)
+    pub fn add(&self, paths: Vec<String>, source: &str, reason: Option<String>) -> Result<BasketSummary, String> {
+        let mut staged = Vec::new();
+        for path in paths {
+            let meta = std::fs::symlink_metadata(&path).map_err(|e| format!("Cannot stage {}: {}", path, e))?;
+            let size = if meta.is_dir() { fs_extra::dir::get_size(&path).unwrap_or(0) } else { meta.len() };
+            staged.push(BasketItem {
+                size,
+                is_dir: meta.is_dir(),
+                source: source.to_string(),
+                reason: reason.clone(),
+                added_at: chrono::Utc::now().timestamp(),
+                modified: modified_secs(&meta),
+                path,
+            });
+        }
+
+        let mut state = self.state.lock().unwrap();
+        for item in staged {
+            state.items.retain(|existing| existing.path != item.path);
+            state.items.push(item);
+        }
+        self.save(&mut state)?;
+        Ok(Self::summarize(&state))
+    }
+
+    pub fn remove(&self, paths: &[String]) -> Result<BasketSummary, String> {
+        let mut state = self.state.lock().unwrap();
+        state.items.retain(|item| !paths.contains(&item.path));
+        self.save(&mut state)?;
+        Ok(Self::summarize(&state))
+    }
+
+    pub fn clear(&self) -> Result<BasketSummary, String> {
+        let mut state = self.state.lock().unwrap();
+        state.items.clear();
+        self.save(&mut state)?;
+        Ok(Self::summarize(&state))
+    }
+
+    pub fn summary(&self) -> BasketSummary {
+        Self::summarize(&self.state.lock().unwrap())
+    }
+
+    fn summarize(state: &BasketState) -> BasketSummary {
+        let mut conflicts = Vec::new();
+        let mut total_size = 0;
+        for item in &state.items {
+            let path = Path::new(&item.path);
+            let conflict = |kind, other_path| BasketConflict {
+                path: item.path.clone(),
+                kind,
+                other_path,
+            };
+            if let Some(parent) = covering(&item.path, &state.items) {
+                conflicts.push(conflict(ConflictKind::Nested, Some(parent.path.clone())));
+                continue;
+            }
+            match std::fs::symlink_metadata(path) {
+                Err(_) => {
+                    conflicts.push(conflict(ConflictKind::Missing, None));
+                    continue;
+                }
+                Ok(meta) if modified_secs(&meta) != item.modified || (!item.is_dir && meta.len() != item.size) => {
+                    conflicts.push(conflict(ConflictKind::Changed, None));
+                }
+                Ok(_) => {}
+            }
+            if is_protected(path) {
+                conflicts.push(conflict(ConflictKind::Protected, None));
+            }
+            total_size += item.size;
+        }
+        BasketSummary {
+            revision: state.revision,
+            items: state.items.clone(),
+            total_size,
+            conflicts,
+        }
+    }
+
+    /// Delete everything staged. `revision` must be the one the user
+    /// reviewed; changed or protected items block the commit. Deleted and
+    /// missing items leave the basket, failed ones stay. `quarantine_all`
+    /// quarantines every item (true) or none (false); by default only the
+    /// low-confidence picks (the agent's, stale files) are quarantined.
+    pub fn commit(
+        &self,
+        revision: u64,
+        quarantine_all: Option<bool>,
+        quarantine: &QuarantineStore,
+        cancel: &AtomicBool,
+    ) -> Result<BasketCommitResult, String> {
+        audit::ensure_writable()?;
+        let mut state = self.state.lock().unwrap();
+        if state.revision != revision {
+            return Err("The basket changed since it was reviewed; review it again before deleting".to_string());
+        }
+        let summary = Self::summarize(&state);
+        let blocking: Vec<&BasketConflict> = summary
+            .conflicts
+            .iter()
+            .filter(|c| matches!(c.kind, ConflictKind::Changed | ConflictKind::Protected))
+            .collect();
+        if let Some(first) = blocking.first() {
+            return Err(format!(
+                "{} staged item(s) need attention first, e.g. {} ({:?})",
+                blocking.len(),
+                first.path,
+                first.kind
+            ));
+        }
+
+        let mut result = BasketCommitResult::default();
+        let mut done: Vec<String> = Vec::new();
+        for item in &state.items {
+            if cancel.load(Ordering::Relaxed) {
+                break;
+            }
+            let path = Path::new(&item.path);
+            if covering(&item.path, &state.items).is_some() {
+                continue;
+            }
+            if !path.exists() {
+                done.push(item.path.clone());
+                continue;
+            }
+            if quarantine_all.unwrap_or(quarantines_by_default(&item.source)) {
+                match quarantine.quarantine(&item.path, Some(item.size), &item.source, item.reason.clone()) {
+                    Ok(_) => {
+                        result.quarantined.push(item.path.clone());
+                        done.push(item.path.clone());
+                    }
+                    Err(error) => result.failed.push(BasketFailure {
+                        path: item.path.clone(),
+                        error,
+                    }),
+                }
+                continue;
+            }
+            let target = paths::extended(path);
+            let removed = if item.is_dir { std::fs::remove_dir_all(&target) } else { std::fs::remove_file(&target) };
+            match removed {
+                Ok(()) => {
+                    result.freed += item.size;
+                    result.deleted.push(item.path.clone());
+                    done.push(item.path.clone());
+                }
+                Err(e) => result.failed.push(BasketFailure {
+                    path: item.path.clone(),
+                    error: e.to_string(),
+                }),
+            }
+        }
+        // Nested items went with their folder
+        for item in &state.items {
+            if covering(&item.path, &state.items)
+                .is_some_and(|parent| result.deleted.contains(&parent.path) || result.quarantined.contains(&parent.path))
+            {
+                done.push(item.path.clone());
+            }
+        }
+        state.items.retain(|item| !done.contains(&item.path));
+        self.save(&mut state)?;
+        info!(
+            "Basket commit deleted {} items ({} bytes), quarantined {}, {} failed",
+            result.deleted.len(),
+            result.freed,
+            result.quarantined.len(),
+            result.failed.len()
+        );
+        Ok(result)
+    }
+}
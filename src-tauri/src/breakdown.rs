@@ -0,0 +1,236 @@
+// Breakdown - What a directory is made of
+//
+// One walk over a directory yields its bytes by file category and by
+// extension, its largest files and its bytes by last-modified year. The AI
+// gets this through the `analyze_directory` MCP tool as a single structured
+// overview to reason about before suggesting cleanup, instead of paging
+// through listings.
+//
+// The largest files under a folder are listed on their own as well, so the
+// UI doesn't have to flatten a scanned tree for them (which only holds the
+// files of its root anyway). Folders a recent scan measured as smaller than
+// the files found so far can't hold a larger one and are not walked.
+
+use crate::age_heatmap::{self, AgeBucket, AgeGranularity};
+use crate::content_type;
+use crate::paths;
+use crate::scanner::{Exclusions, FileNode};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+/// Extensions and largest files reported
+const TOP_EXTENSIONS: usize = 20;
+const TOP_FILES: usize = 15;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SizeGroup {
+    /// Category name or extension ("" for files without one)
+    pub name: String,
+    pub size: u64,
+    pub files: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LargeFile {
+    pub path: String,
+    pub size: u64,
+    pub last_modified: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryBreakdown {
+    pub path: String,
+    pub total_size: u64,
+    pub file_count: u64,
+    /// Largest first
+    pub categories: Vec<SizeGroup>,
+    /// Largest first, top entries only
+    pub extensions: Vec<SizeGroup>,
+    /// Largest first
+    pub largest_files: Vec<LargeFile>,
+    /// Bytes by last-modified year, oldest first
+    pub age: Vec<AgeBucket>,
+}
+
+fn groups(map: HashMap<String, (u64, u64)>, limit: usize) -> Vec<SizeGroup> {
+    let mut groups: Vec<SizeGroup> = map
+        .into_iter()
+        .map(|(name, (size, files))| SizeGroup { name, size, files })
+        .collect();
+    groups.sort_by(|a, b| b.size.cmp(&a.size));
+    groups.truncate(limit);
+    groups
+}
+
+/// Category, extension, largest-file and age breakdown of `path`
+pub fn analyze(path: &str, exclusions: &Exclusions, cancel: &AtomicBool) -> Result<DirectoryBreakdown, String> {
+    let root = paths::extended(Path::new(path));
+    if !root.is_dir() {
+        return Err("Directory does not exist".to_string());
+    }
+
+    let mut categories: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut extensions: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut years: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    let mut largest: Vec<LargeFile> = Vec::new();
+    let (mut total_size, mut file_count) = (0u64, 0u64);
+
+    let walker = WalkDir::new(&root).into_iter().filter_entry(|e| !exclusions.is_excluded(e.path()));
+    for entry in walker.filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".to_string());
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let size = meta.len();
+        let modified = meta
+            .modified()
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let extension = entry
+            .path()
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        for (map, key) in [(&mut categories, content_type::category(entry.path()).to_string()), (&mut extensions, extension)] {
+            let group = map.entry(key).or_default();
+            group.0 += size;
+            group.1 += 1;
+        }
+        let year = years.entry(age_heatmap::period(modified, AgeGranularity::Year)).or_default();
+        year.0 += size;
+        year.1 += 1;
+        total_size += size;
+        file_count += 1;
+
+        // Keep the running top list small
+        if largest.len() < TOP_FILES || largest.last().is_some_and(|f| size > f.size) {
+            largest.push(LargeFile {
+                path: paths::display(entry.path()),
+                size,
+                last_modified: modified,
+            });
+            largest.sort_by(|a, b| b.size.cmp(&a.size));
+            largest.truncate(TOP_FILES);
+        }
+    }
+
+    Ok(DirectoryBreakdown {
+        path: paths::display(&root),
+        total_size,
+        file_count,
+        categories: groups(categories, usize::MAX),
+        extensions: groups(extensions, TOP_EXTENSIONS),
+        largest_files: largest,
+        age: years
+            .into_iter()
+            .map(|(period, (size, files))| AgeBucket { period, size, files })
+            .collect(),
+    })
+}
+
+/// Smallest-first heap of the largest files found so far
+struct TopFiles {
+    limit: usize,
+    heap: BinaryHeap<Reverse<(u64, u64, String)>>,
+}
+
+impl TopFiles {
+    fn push(&mut self, size: u64, modified: u64, path: &Path) {
+        if self.heap.len() < self.limit {
+            self.heap.push(Reverse((size, modified, paths::display(path))));
+        } else if self.heap.peek().is_some_and(|Reverse((smallest, _, _))| size > *smallest) {
+            self.heap.pop();
+            self.heap.push(Reverse((size, modified, paths::display(path))));
+        }
+    }
+
+    /// Whether a folder of `size` bytes could hold a file that makes the list
+    fn could_hold(&self, size: u64) -> bool {
+        self.heap.len() < self.limit || self.heap.peek().is_some_and(|Reverse((smallest, _, _))| size > *smallest)
+    }
+}
+
+fn walk_largest(
+    dir: &Path,
+    scanned: Option<&FileNode>,
+    top: &mut TopFiles,
+    exclusions: &Exclusions,
+    cancel: &AtomicBool,
+) -> Result<(), String> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Ok(());
+    };
+    let mut subdirs = Vec::new();
+    for entry in read_dir.flatten() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".to_string());
+        }
+        let path = entry.path();
+        if exclusions.is_excluded(&path) {
+            continue;
+        }
+        // Links are not followed, and take no space worth listing
+        let Ok(meta) = std::fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if meta.is_dir() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let node = scanned
+                .and_then(|n| n.children.as_ref())
+                .and_then(|children| children.iter().find(|c| c.is_dir && c.name == name));
+            subdirs.push((path, node));
+        } else if meta.is_file() {
+            let modified = meta
+                .modified()
+                .unwrap_or(SystemTime::UNIX_EPOCH)
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            top.push(meta.len(), modified, &path);
+        }
+    }
+    // Largest measured folders first, so the rest are more likely skipped;
+    // folders the scan didn't measure are always walked
+    subdirs.sort_by_key(|(_, node)| Reverse(node.map_or(u64::MAX, |n| n.size)));
+    for (path, node) in subdirs {
+        if node.is_some_and(|n| !top.could_hold(n.size)) {
+            continue;
+        }
+        walk_largest(&path, node, top, exclusions, cancel)?;
+    }
+    Ok(())
+}
+
+/// The `limit` largest files under `path`, largest first. `scanned` is a
+/// recent scan of it, whose folder sizes let small folders be skipped.
+pub fn largest_files(
+    path: &str,
+    limit: usize,
+    scanned: Option<&FileNode>,
+    exclusions: &Exclusions,
+    cancel: &AtomicBool,
+) -> Result<Vec<LargeFile>, String> {
+    let root = paths::extended(Path::new(path));
+    if !root.is_dir() {
+        return Err("Directory does not exist".to_string());
+    }
+    let mut top = TopFiles { limit: limit.max(1), heap: BinaryHeap::new() };
+    walk_largest(&root, scanned, &mut top, exclusions, cancel)?;
+    Ok(top
+        .heap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((size, last_modified, path))| LargeFile { path, size, last_modified })
+        .collect())
+}
@@ -0,0 +1,504 @@
+// Browser Storage - What sites and extensions keep in browser profiles
+//
+// Browser caches are junk the cleaner already lists, but the data sites and
+// extensions store on purpose is often larger and is what users actually
+// want to prune: a web mail client's offline IndexedDB, a video site's
+// Service Worker cache, an extension's database. This reads the profiles of
+// Chromium-based browsers (Chrome, Edge, Brave, Chromium, Vivaldi) and of
+// Firefox and attributes their storage to sites and extensions.
+//
+// Only storage kept in a folder of its own per site or extension can be
+// cleared, and only while the browser is closed: Chromium's per-origin
+// IndexedDB and Cache Storage folders and extension settings, and Firefox's
+// per-origin storage folders. The browser recreates them empty. Storage that
+// several sites share a database in (Local Storage, partitioned WebStorage)
+// is reported but not offered for clearing, and extensions' own files are
+// removed by uninstalling the extension, not here.
+
+use crate::paths;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use sysinfo::System;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StorageKind {
+    IndexedDb,
+    /// Cache Storage of a site's service worker
+    ServiceWorker,
+    LocalStorage,
+    FileSystem,
+    /// Firefox keeps all of a site's storage in one folder
+    SiteData,
+    ExtensionData,
+    /// The installed extension itself
+    ExtensionCode,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageEntry {
+    pub kind: StorageKind,
+    pub path: String,
+    pub size: u64,
+    /// Can be deleted on its own; the browser recreates it empty
+    pub clearable: bool,
+}
+
+/// A site or extension and what it stores
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageOwner {
+    /// Origin of a site, or an extension's name
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extension_id: Option<String>,
+    pub size: u64,
+    pub entries: Vec<StorageEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowserProfile {
+    pub browser: String,
+    /// Profile name as the browser shows it
+    pub profile: String,
+    pub path: String,
+    /// Nothing can be cleared while the browser runs
+    pub running: bool,
+    pub size: u64,
+    /// Largest first
+    pub sites: Vec<StorageOwner>,
+    /// Largest first
+    pub extensions: Vec<StorageOwner>,
+    /// Storage shared by sites or not attributable to one
+    pub other: Vec<StorageEntry>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Family {
+    Chromium,
+    Firefox,
+}
+
+struct Browser {
+    name: &'static str,
+    family: Family,
+    /// User data folder (Chromium) or folder of profiles (Firefox)
+    dir: PathBuf,
+    /// Process names, lowercase and without .exe
+    processes: &'static [&'static str],
+}
+
+/// Name, user data folder on Windows (under local app data), on macOS
+/// (under Application Support) and on Linux (under ~/.config), and process
+/// names of a Chromium-based browser
+type ChromiumBrowser = (&'static str, &'static str, &'static str, &'static str, &'static [&'static str]);
+
+const CHROMIUM_BROWSERS: &[ChromiumBrowser] = &[
+    ("Chrome", r"Google\Chrome\User Data", "Google/Chrome", "google-chrome", &["chrome", "google chrome"]),
+    ("Edge", r"Microsoft\Edge\User Data", "Microsoft Edge", "microsoft-edge", &["msedge", "microsoft edge"]),
+    ("Brave", r"BraveSoftware\Brave-Browser\User Data", "BraveSoftware/Brave-Browser", "BraveSoftware/Brave-Browser", &["brave", "brave browser"]),
+    ("Chromium", r"Chromium\User Data", "Chromium", "chromium", &["chromium", "chromium-browser"]),
+    ("Vivaldi", r"Vivaldi\User Data", "Vivaldi", "vivaldi", &["vivaldi", "vivaldi-bin"]),
+];
+
+/// Browsers with a profile folder on this machine
+fn browsers() -> Vec<Browser> {
+    let base = if cfg!(target_os = "windows") {
+        dirs::data_local_dir()
+    } else if cfg!(target_os = "macos") {
+        dirs::data_dir()
+    } else {
+        dirs::config_dir()
+    };
+    let mut browsers = Vec::new();
+    if let Some(base) = base {
+        for &(name, windows, macos, linux, processes) in CHROMIUM_BROWSERS {
+            let relative = if cfg!(target_os = "windows") {
+                windows
+            } else if cfg!(target_os = "macos") {
+                macos
+            } else {
+                linux
+            };
+            browsers.push(Browser { name, family: Family::Chromium, dir: base.join(relative), processes });
+        }
+    }
+    let firefox = if cfg!(target_os = "windows") {
+        dirs::data_dir().map(|d| d.join("Mozilla").join("Firefox").join("Profiles"))
+    } else if cfg!(target_os = "macos") {
+        dirs::data_dir().map(|d| d.join("Firefox").join("Profiles"))
+    } else {
+        dirs::home_dir().map(|d| d.join(".mozilla").join("firefox"))
+    };
+    browsers.extend(firefox.map(|dir| Browser { name: "Firefox", family: Family::Firefox, dir, processes: &["firefox"] }));
+    browsers.retain(|b| b.dir.is_dir());
+    browsers
+}
+
+/// Lowercase names of the running processes, without .exe
+fn running_processes() -> HashSet<String> {
+    let mut system = System::new();
+    system.refresh_processes();
+    system
+        .processes()
+        .values()
+        .map(|p| {
+            let name = p.name().to_lowercase();
+            name.strip_suffix(".exe").map(String::from).unwrap_or(name)
+        })
+        .collect()
+}
+
+fn size_of(path: &Path) -> u64 {
+    let path = paths::extended(path);
+    match std::fs::metadata(&path) {
+        Ok(meta) if meta.is_dir() => fs_extra::dir::get_size(&path).unwrap_or(0),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+fn entries(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .map(|read| read.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+fn read_json(path: &Path) -> Option<serde_json::Value> {
+    serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+/// Storage of one profile, grouped as it is found
+#[derive(Default)]
+struct Collector {
+    sites: HashMap<String, Vec<StorageEntry>>,
+    extensions: HashMap<String, Vec<StorageEntry>>,
+    other: Vec<StorageEntry>,
+}
+
+impl Collector {
+    fn entry(path: &Path, kind: StorageKind, clearable: bool) -> Option<StorageEntry> {
+        let size = size_of(path);
+        (size > 0).then(|| StorageEntry { kind, path: paths::display(path), size, clearable })
+    }
+
+    fn site(&mut self, origin: String, path: &Path, kind: StorageKind) {
+        if let Some(entry) = Self::entry(path, kind, true) {
+            self.sites.entry(origin).or_default().push(entry);
+        }
+    }
+
+    fn extension(&mut self, id: String, path: &Path, kind: StorageKind) {
+        let clearable = kind != StorageKind::ExtensionCode;
+        if let Some(entry) = Self::entry(path, kind, clearable) {
+            self.extensions.entry(id).or_default().push(entry);
+        }
+    }
+
+    fn other(&mut self, path: &Path, kind: StorageKind) {
+        self.other.extend(Self::entry(path, kind, false));
+    }
+
+    fn finish(self, browser: &str, profile: String, path: &Path, running: bool, names: &HashMap<String, String>) -> BrowserProfile {
+        let owners = |groups: HashMap<String, Vec<StorageEntry>>, extensions: bool| {
+            let mut owners: Vec<StorageOwner> = groups
+                .into_iter()
+                .map(|(key, mut entries)| {
+                    entries.sort_by(|a, b| b.size.cmp(&a.size));
+                    StorageOwner {
+                        name: if extensions { names.get(&key).cloned().unwrap_or_else(|| key.clone()) } else { key.clone() },
+                        extension_id: extensions.then_some(key),
+                        size: entries.iter().map(|e| e.size).sum(),
+                        entries,
+                    }
+                })
+                .collect();
+            owners.sort_by(|a, b| b.size.cmp(&a.size));
+            owners
+        };
+        let sites = owners(self.sites, false);
+        let extensions = owners(self.extensions, true);
+        let mut other = self.other;
+        other.sort_by(|a, b| b.size.cmp(&a.size));
+        BrowserProfile {
+            browser: browser.to_string(),
+            profile,
+            path: paths::display(path),
+            running,
+            size: sites.iter().chain(&extensions).map(|o| o.size).sum::<u64>() + other.iter().map(|e| e.size).sum::<u64>(),
+            sites,
+            extensions,
+            other,
+        }
+    }
+}
+
+/// Origin of a Chromium storage folder name: "https_example.com_0" is
+/// https://example.com, the port being 0 for the scheme's default
+fn chromium_origin(name: &str) -> Option<(String, String)> {
+    let (scheme, rest) = name.split_once('_')?;
+    let (host, port) = rest.rsplit_once('_')?;
+    Some((scheme.to_string(), if port == "0" { host.to_string() } else { format!("{}:{}", host, port) }))
+}
+
+/// Origin recorded in a Cache Storage index; the index is a protobuf, the
+/// origin the only URL in it
+fn cache_storage_origin(dir: &Path) -> Option<String> {
+    let bytes = std::fs::read(dir.join("index.txt")).ok()?;
+    let text = String::from_utf8_lossy(&bytes);
+    let start = text.find("https://").or_else(|| text.find("http://"))?;
+    let url: String = text[start..].chars().take_while(|c| c.is_ascii_graphic()).collect();
+    let host_end = url.find("://").map(|i| i + 3)?;
+    let origin_end = url[host_end..].find('/').map_or(url.len(), |i| host_end + i);
+    Some(url[..origin_end].to_string())
+}
+
+/// Name of a Chromium extension from its manifest, resolving a localized
+/// `__MSG_name__` from its default locale
+fn chromium_extension_name(dir: &Path) -> Option<String> {
+    let version = entries(dir).into_iter().find(|v| v.join("manifest.json").is_file())?;
+    let manifest = read_json(&version.join("manifest.json"))?;
+    let name = manifest.get("name")?.as_str()?.to_string();
+    let Some(key) = name.strip_prefix("__MSG_").and_then(|k| k.strip_suffix("__")) else {
+        return Some(name);
+    };
+    let locale = manifest.get("default_locale").and_then(|l| l.as_str()).unwrap_or("en");
+    let messages = read_json(&version.join("_locales").join(locale).join("messages.json"))?;
+    let messages = messages.as_object()?;
+    messages
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .and_then(|(_, m)| m.get("message")?.as_str().map(String::from))
+}
+
+/// Profiles of a Chromium user data folder, with the names Local State gives them
+fn chromium_profiles(user_data: &Path) -> Vec<(PathBuf, String)> {
+    let local_state = read_json(&user_data.join("Local State"));
+    entries(user_data)
+        .into_iter()
+        .filter(|dir| dir.join("Preferences").is_file())
+        .map(|dir| {
+            let folder = file_name(&dir);
+            let name = local_state
+                .as_ref()
+                .and_then(|s| s.pointer(&format!("/profile/info_cache/{}/name", folder.replace('~', "~0").replace('/', "~1")))?.as_str().map(String::from))
+                .unwrap_or(folder);
+            (dir, name)
+        })
+        .collect()
+}
+
+fn chromium_profile(browser: &str, dir: &Path, name: String, running: bool) -> BrowserProfile {
+    let mut collector = Collector::default();
+
+    for path in entries(&dir.join("IndexedDB")) {
+        let folder = file_name(&path);
+        let Some(origin) = folder.strip_suffix(".indexeddb.leveldb").or_else(|| folder.strip_suffix(".indexeddb.blob")) else {
+            collector.other(&path, StorageKind::IndexedDb);
+            continue;
+        };
+        match chromium_origin(origin) {
+            Some((scheme, id)) if scheme == "chrome-extension" => collector.extension(id, &path, StorageKind::IndexedDb),
+            Some((scheme, host)) => collector.site(format!("{}://{}", scheme, host), &path, StorageKind::IndexedDb),
+            None => collector.other(&path, StorageKind::IndexedDb),
+        }
+    }
+
+    let service_worker = dir.join("Service Worker");
+    for path in entries(&service_worker.join("CacheStorage")) {
+        match cache_storage_origin(&path) {
+            Some(origin) => collector.site(origin, &path, StorageKind::ServiceWorker),
+            None => collector.other(&path, StorageKind::ServiceWorker),
+        }
+    }
+    for shared in ["ScriptCache", "Database"] {
+        collector.other(&service_worker.join(shared), StorageKind::ServiceWorker);
+    }
+    collector.other(&dir.join("Local Storage"), StorageKind::LocalStorage);
+    collector.other(&dir.join("File System"), StorageKind::FileSystem);
+    // Partitioned storage; which origin owns a bucket is only recorded in
+    // the quota manager's database
+    collector.other(&dir.join("WebStorage"), StorageKind::Other);
+
+    let mut names = HashMap::new();
+    for path in entries(&dir.join("Extensions")) {
+        let id = file_name(&path);
+        names.extend(chromium_extension_name(&path).map(|name| (id.clone(), name)));
+        collector.extension(id, &path, StorageKind::ExtensionCode);
+    }
+    for settings in ["Local Extension Settings", "Sync Extension Settings"] {
+        for path in entries(&dir.join(settings)) {
+            collector.extension(file_name(&path), &path, StorageKind::ExtensionData);
+        }
+    }
+    collector.finish(browser, name, dir, running, &names)
+}
+
+/// Origin of a Firefox storage folder name: "https+++example.com+8080" is
+/// https://example.com:8080; origin attributes after `^` are kept
+fn firefox_origin(name: &str) -> String {
+    let (origin, attributes) = match name.split_once('^') {
+        Some((origin, attributes)) => (origin, Some(attributes)),
+        None => (name, None),
+    };
+    let mut origin = origin.replacen("+++", "://", 1);
+    if let Some((host, port)) = origin.rsplit_once('+') {
+        if !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()) {
+            origin = format!("{}:{}", host, port);
+        }
+    }
+    match attributes {
+        Some(attributes) => format!("{} ({})", origin, attributes),
+        None => origin,
+    }
+}
+
+/// Extension ids by the internal UUID Firefox names their storage after
+fn firefox_extension_uuids(profile: &Path) -> HashMap<String, String> {
+    let prefs = std::fs::read_to_string(profile.join("prefs.js")).unwrap_or_default();
+    prefs
+        .lines()
+        .find(|l| l.contains("\"extensions.webextensions.uuids\""))
+        .and_then(|line| {
+            // user_pref("extensions.webextensions.uuids", "{\"id\":\"uuid\"}");
+            let value = line.split_once(", \"")?.1.rsplit_once("\");")?.0;
+            serde_json::from_str::<HashMap<String, String>>(&value.replace("\\\"", "\"").replace("\\\\", "\\")).ok()
+        })
+        .map(|ids| ids.into_iter().map(|(id, uuid)| (uuid, id)).collect())
+        .unwrap_or_default()
+}
+
+/// Extension names by id, from the add-on database
+fn firefox_extension_names(profile: &Path) -> HashMap<String, String> {
+    let Some(addons) = read_json(&profile.join("extensions.json")) else {
+        return HashMap::new();
+    };
+    addons
+        .get("addons")
+        .and_then(|a| a.as_array())
+        .map(|addons| {
+            addons
+                .iter()
+                .filter_map(|a| {
+                    let id = a.get("id")?.as_str()?;
+                    let name = a.pointer("/defaultLocale/name")?.as_str()?;
+                    Some((id.to_string(), name.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn firefox_profile(dir: &Path, running: bool) -> BrowserProfile {
+    let mut collector = Collector::default();
+    let uuids = firefox_extension_uuids(dir);
+    let storage = dir.join("storage");
+
+    for path in entries(&storage.join("default")) {
+        let folder = file_name(&path);
+        match folder.strip_prefix("moz-extension+++") {
+            Some(uuid) => {
+                let uuid = uuid.split('^').next().unwrap_or(uuid);
+                let id = uuids.get(uuid).cloned().unwrap_or_else(|| uuid.to_string());
+                collector.extension(id, &path, StorageKind::ExtensionData);
+            }
+            None => collector.site(firefox_origin(&folder), &path, StorageKind::SiteData),
+        }
+    }
+    for shared in ["permanent", "temporary"] {
+        collector.other(&storage.join(shared), StorageKind::Other);
+    }
+    for path in entries(&dir.join("extensions")) {
+        let id = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        collector.extension(id, &path, StorageKind::ExtensionCode);
+    }
+
+    // Profile folders are named "<salt>.<name>"
+    let folder = file_name(dir);
+    let name = folder.split_once('.').map_or(folder.clone(), |(_, name)| name.to_string());
+    collector.finish("Firefox", name, dir, running, &firefox_extension_names(dir))
+}
+
+/// Site and extension storage of every browser profile found, largest first
+pub fn analyze() -> Vec<BrowserProfile> {
+    let running = running_processes();
+    let mut profiles = Vec::new();
+    for browser in browsers() {
+        let is_running = browser.processes.iter().any(|p| running.contains(*p));
+        match browser.family {
+            Family::Chromium => {
+                for (dir, name) in chromium_profiles(&browser.dir) {
+                    profiles.push(chromium_profile(browser.name, &dir, name, is_running));
+                }
+            }
+            Family::Firefox => {
+                for dir in entries(&browser.dir).into_iter().filter(|d| d.join("prefs.js").is_file()) {
+                    profiles.push(firefox_profile(&dir, is_running));
+                }
+            }
+        }
+    }
+    profiles.sort_by(|a, b| b.size.cmp(&a.size));
+    info!("Analyzed storage of {} browser profiles", profiles.len());
+    profiles
+}
+
+/// Delete the given storage entries. Each must be one `analyze` reports as
+/// clearable, in a profile whose browser is closed. Returns the bytes freed.
+pub fn clear(requested: &[String]) -> Result<u64, String> {
+    let profiles = analyze();
+    let mut freed = 0;
+    let mut errors = Vec::new();
+    for path in requested {
+        let found = profiles.iter().find_map(|profile| {
+            profile
+                .sites
+                .iter()
+                .chain(&profile.extensions)
+                .flat_map(|owner| &owner.entries)
+                .chain(&profile.other)
+                .find(|e| paths::same(Path::new(&e.path), Path::new(path)))
+                .map(|entry| (profile, entry))
+        });
+        let (profile, entry) = match found {
+            Some(found) => found,
+            None => {
+                errors.push(format!("Not browser storage: {}", path));
+                continue;
+            }
+        };
+        if !entry.clearable {
+            errors.push(format!("Cannot be cleared on its own: {}", path));
+            continue;
+        }
+        if profile.running {
+            errors.push(format!("Close {} before clearing its storage", profile.browser));
+            continue;
+        }
+        let target = paths::extended(Path::new(&entry.path));
+        let result = if target.is_dir() { std::fs::remove_dir_all(&target) } else { std::fs::remove_file(&target) };
+        match result {
+            Ok(()) => freed += entry.size,
+            Err(e) => {
+                warn!("Failed to clear {}: {}", entry.path, e);
+                errors.push(format!("{}: {}", entry.path, e));
+            }
+        }
+    }
+    info!("Cleared {} bytes of browser storage", freed);
+    errors.dedup();
+    if errors.is_empty() {
+        Ok(freed)
+    } else {
+        Err(errors.join("\n"))
+    }
+}
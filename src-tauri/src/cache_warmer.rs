@@ -0,0 +1,158 @@
+// Cache Warmer - Rescans folders after heavy churn, before they are visited
+//
+// A build, an install or an extraction rewrites thousands of files, which
+// leaves the cached scan of that folder wrong and makes the next visit wait
+// for a full rescan. The size index's watcher and the scan watcher report
+// every change here; changes are counted per directory, and once a directory
+// saw at least CHURN_THRESHOLD of them and then stayed quiet for SETTLE_TIME
+// (the build finished), the subtree is rescanned in the background and the
+// scan cache updated. The rescanned subtree is the deepest folder a few
+// levels up that is already in the scan cache, the one the user is likely to
+// come back to; otherwise the churned folder itself. Warming uses a single
+// walker thread and waits while a user-started scan runs, so it never
+// competes with foreground work. Only changes below indexed roots and
+// recently scanned paths are seen, since those are what the watchers follow.
+
+use crate::operations::{OperationKind, OperationManager};
+use crate::profiles::ProfileStore;
+use crate::scan_cache;
+use crate::scan_strategy::{ScanStrategy, ScanStrategyStore};
+use crate::scanner::scan_directory;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, info, warn};
+
+/// Event emitted with the path of a folder whose cached scan was refreshed
+pub const CACHE_WARMED_EVENT: &str = "cache-warmed";
+/// Changes in one directory that count as heavy churn
+const CHURN_THRESHOLD: u32 = 200;
+/// Quiet time after the last change before a churned folder is rescanned
+const SETTLE_TIME: Duration = Duration::from_secs(10);
+/// Churn counts older than this without reaching the threshold are dropped
+const WINDOW: Duration = Duration::from_secs(5 * 60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Levels above a churned directory a cached folder may be to be rescanned
+/// in its place; a cached drive root is never worth rescanning for one build
+const MAX_LEVELS_UP: usize = 3;
+
+struct Churn {
+    changes: u32,
+    first: Instant,
+    last: Instant,
+}
+
+lazy_static! {
+    static ref CHURN: Mutex<HashMap<PathBuf, Churn>> = Mutex::new(HashMap::new());
+}
+
+/// Count a change to `path`, as reported by a filesystem watcher
+pub fn observe(path: &Path) {
+    let dir = if path.is_dir() { path } else { path.parent().unwrap_or(path) };
+    let now = Instant::now();
+    let mut churn = CHURN.lock().unwrap();
+    let entry = churn.entry(dir.to_path_buf()).or_insert(Churn {
+        changes: 0,
+        first: now,
+        last: now,
+    });
+    entry.changes += 1;
+    entry.last = now;
+}
+
+/// Churned directories that settled, removing them from the counts. Counts
+/// that never reached the threshold expire after WINDOW.
+fn take_settled() -> Vec<PathBuf> {
+    let mut churn = CHURN.lock().unwrap();
+    let mut settled = Vec::new();
+    churn.retain(|dir, c| {
+        if c.last.elapsed() < SETTLE_TIME {
+            return true;
+        }
+        if c.changes >= CHURN_THRESHOLD {
+            settled.push(dir.clone());
+            return false;
+        }
+        c.first.elapsed() < WINDOW
+    });
+    settled
+}
+
+/// Subtree to rescan for a churned directory: its deepest ancestor with a
+/// cached scan within MAX_LEVELS_UP, or the directory itself
+fn warm_target(dir: &Path) -> Option<PathBuf> {
+    let cached = dir
+        .ancestors()
+        .take(MAX_LEVELS_UP + 1)
+        .find(|a| scan_cache::is_cached(&crate::paths::display(a)))
+        .map(Path::to_path_buf);
+    // A folder removed along the way (a clean build) is rescanned through
+    // its nearest existing ancestor
+    cached.or_else(|| dir.ancestors().take(MAX_LEVELS_UP + 1).find(|a| a.is_dir()).map(Path::to_path_buf))
+}
+
+/// Folders to rescan, without those inside another one
+fn targets(settled: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut targets: Vec<PathBuf> = settled.iter().filter_map(|d| warm_target(d)).collect();
+    targets.sort();
+    targets.dedup();
+    let outer = targets.clone();
+    targets.retain(|t| !outer.iter().any(|o| o != t && crate::paths::is_within(t, o)));
+    targets
+}
+
+fn warm(app: &AppHandle, target: &Path) {
+    let path = crate::paths::display(target);
+    let exclusions = app.state::<ProfileStore>().exclusions();
+    let symlinks = app.state::<ProfileStore>().symlink_policy();
+    // One walker thread: warming must not slow down anything in front
+    let strategy = ScanStrategy {
+        threads: 1,
+        ..app.state::<ScanStrategyStore>().resolve(target).strategy
+    };
+    let started = Instant::now();
+    match scan_directory(&path, None, None, &exclusions, &strategy, symlinks, None, &[]) {
+        Ok(node) => {
+            scan_cache::store(&path, &node);
+            debug!("Warmed scan cache for {} in {:?}", path, started.elapsed());
+            let _ = app.emit(CACHE_WARMED_EVENT, &path);
+            // An agent that looked at this folder is working from old sizes
+            crate::mcp::notifications::resource_updated(&path);
+        }
+        Err(e) => warn!("Cache warming of {} failed: {}", path, e),
+    }
+}
+
+/// Rescan churned folders periodically while the app runs
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            // A scan the user started has the disk to itself; churn keeps
+            // counting until it ends
+            let scanning = app
+                .state::<OperationManager>()
+                .list()
+                .iter()
+                .any(|op| op.kind == OperationKind::Scan);
+            if scanning {
+                continue;
+            }
+            let targets = targets(take_settled());
+            if targets.is_empty() {
+                continue;
+            }
+            info!("Warming scan cache for {} churned folders", targets.len());
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                for target in targets {
+                    warm(&handle, &target);
+                }
+            })
+            .await;
+        }
+    });
+}
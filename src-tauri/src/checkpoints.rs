@@ -0,0 +1,240 @@
+// Checkpoints - Long-running operations that survive a restart
+//
+// Scanning a full drive can take hours, and a crash, an update or a reboot
+// used to throw all of that work away. While a scan runs it checkpoints the
+// folders it has finished measuring, and the checkpoint is removed once the
+// scan completes or the user cancels it. A checkpoint still on disk at
+// startup belongs to an operation the app never finished: it is listed as
+// resumable, and scanning the same path again reuses every finished folder
+// whose modification time hasn't changed instead of walking it again.
+//
+// Scans are the only operation long enough to need this today. Checkpoints
+// are keyed by operation kind and target, one JSON file each, so other
+// operations can checkpoint the same way.
+
+use crate::operations::OperationKind;
+use crate::scanner::{FileNode, Reuse};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, info, warn};
+
+/// Minimum time between two writes of a running operation's checkpoint
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+/// Checkpoints older than this describe a tree that has likely changed too
+/// much to be worth resuming
+const MAX_AGE_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Checkpoint {
+    id: String,
+    kind: OperationKind,
+    /// What the operation works on: the scanned path
+    target: String,
+    /// Unix timestamps in milliseconds
+    started_at: i64,
+    updated_at: i64,
+    /// Folders measured so far, by path
+    #[serde(default)]
+    completed: HashMap<String, FileNode>,
+}
+
+/// An operation that can continue from its checkpoint
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumableOperation {
+    pub id: String,
+    pub kind: OperationKind,
+    pub target: String,
+    pub started_at: i64,
+    pub updated_at: i64,
+    /// Folders that won't be walked again
+    pub completed_folders: usize,
+    pub completed_files: u64,
+    pub completed_bytes: u64,
+}
+
+impl Checkpoint {
+    fn summary(&self) -> ResumableOperation {
+        // A finished top-level folder also holds its subfolders; count each
+        // file once
+        let tops: Vec<&FileNode> = self
+            .completed
+            .values()
+            .filter(|node| !self.completed.keys().any(|other| other != &node.path && is_parent(other, &node.path)))
+            .collect();
+        ResumableOperation {
+            id: self.id.clone(),
+            kind: self.kind,
+            target: self.target.clone(),
+            started_at: self.started_at,
+            updated_at: self.updated_at,
+            completed_folders: self.completed.len(),
+            completed_files: tops.iter().map(|n| n.file_count).sum(),
+            completed_bytes: tops.iter().map(|n| n.size).sum(),
+        }
+    }
+}
+
+fn is_parent(parent: &str, child: &str) -> bool {
+    Path::new(child).parent() == Some(Path::new(parent))
+}
+
+fn modified_secs(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    modified.duration_since(SystemTime::UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+/// File name of the checkpoint of `kind` on `target`
+fn file_name(kind: OperationKind, target: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let key = format!("{:?}:{}", kind, crate::paths::key(target));
+    format!("{}.json", &hex::encode(Sha256::digest(key.as_bytes()))[..16])
+}
+
+/// Checkpoint of an operation while it runs. Clones share it.
+#[derive(Clone)]
+pub struct ActiveCheckpoint {
+    inner: Arc<ActiveInner>,
+}
+
+struct ActiveInner {
+    path: PathBuf,
+    checkpoint: Mutex<Checkpoint>,
+    last_saved: Mutex<Instant>,
+}
+
+impl Reuse for ActiveCheckpoint {
+    /// A folder finished in an earlier session, if it hasn't been modified
+    /// since
+    fn reuse(&self, path: &Path) -> Option<FileNode> {
+        let key = crate::paths::display(path);
+        let node = self.inner.checkpoint.lock().unwrap().completed.get(&key).cloned()?;
+        (modified_secs(path) == Some(node.last_modified)).then_some(node)
+    }
+
+    /// Record a finished folder; the checkpoint is written at most every
+    /// SAVE_INTERVAL
+    fn finished(&self, node: &FileNode) {
+        let mut checkpoint = self.inner.checkpoint.lock().unwrap();
+        // Its subfolders are part of it now
+        checkpoint.completed.retain(|path, _| !is_parent(&node.path, path));
+        checkpoint.completed.insert(node.path.clone(), node.clone());
+        let mut last_saved = self.inner.last_saved.lock().unwrap();
+        if last_saved.elapsed() >= SAVE_INTERVAL {
+            checkpoint.updated_at = chrono::Utc::now().timestamp_millis();
+            if let Err(e) = storage::save_json(&self.inner.path, &*checkpoint) {
+                warn!("Failed to write checkpoint {}: {}", self.inner.path.display(), e);
+            }
+            *last_saved = Instant::now();
+        }
+    }
+}
+
+pub struct CheckpointStore {
+    dir: PathBuf,
+    /// Checkpoints left by earlier sessions
+    interrupted: Mutex<HashMap<String, Checkpoint>>,
+}
+
+impl CheckpointStore {
+    /// Load the checkpoints left by earlier sessions, dropping stale ones
+    pub fn load(dir: PathBuf) -> Self {
+        let cutoff = chrono::Utc::now().timestamp_millis() - MAX_AGE_DAYS * 86_400_000;
+        let mut interrupted = HashMap::new();
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if !path.extension().is_some_and(|e| e == "json") {
+                    continue;
+                }
+                match storage::load_json::<Checkpoint>(&path) {
+                    Ok(Some(checkpoint)) if checkpoint.updated_at >= cutoff => {
+                        interrupted.insert(checkpoint.id.clone(), checkpoint);
+                    }
+                    // Left for the newer version that wrote it
+                    Err(_) => {}
+                    _ => {
+                        debug!("Dropping stale checkpoint {}", path.display());
+                        let _ = storage::remove_json(&path);
+                    }
+                }
+            }
+        }
+        if !interrupted.is_empty() {
+            info!("Found {} interrupted operations", interrupted.len());
+        }
+        Self {
+            dir,
+            interrupted: Mutex::new(interrupted),
+        }
+    }
+
+    /// Operations interrupted by a restart, most recent first
+    pub fn resumable(&self) -> Vec<ResumableOperation> {
+        let mut list: Vec<_> = self.interrupted.lock().unwrap().values().map(Checkpoint::summary).collect();
+        list.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        list
+    }
+
+    /// Start checkpointing an operation, continuing from an interrupted
+    /// checkpoint of the same kind and target unless `fresh`
+    pub fn begin(&self, kind: OperationKind, target: &str, fresh: bool) -> ActiveCheckpoint {
+        let name = file_name(kind, target);
+        let mut interrupted = self.interrupted.lock().unwrap();
+        let earlier = interrupted
+            .iter()
+            .find(|(_, c)| file_name(c.kind, &c.target) == name)
+            .map(|(id, _)| id.clone())
+            .and_then(|id| interrupted.remove(&id));
+        let now = chrono::Utc::now().timestamp_millis();
+        let checkpoint = match earlier {
+            Some(checkpoint) if !fresh => {
+                info!("Resuming {:?} of {} with {} finished folders", kind, target, checkpoint.completed.len());
+                checkpoint
+            }
+            _ => Checkpoint {
+                id: uuid::Uuid::new_v4().to_string(),
+                kind,
+                target: target.to_string(),
+                started_at: now,
+                updated_at: now,
+                completed: HashMap::new(),
+            },
+        };
+        ActiveCheckpoint {
+            inner: Arc::new(ActiveInner {
+                path: self.dir.join(name),
+                checkpoint: Mutex::new(checkpoint),
+                last_saved: Mutex::new(Instant::now()),
+            }),
+        }
+    }
+
+    /// Remove the checkpoint of an operation that finished or was cancelled
+    pub fn end(&self, active: &ActiveCheckpoint) {
+        if let Err(e) = storage::remove_json(&active.inner.path) {
+            warn!("Failed to remove checkpoint {}: {}", active.inner.path.display(), e);
+        }
+    }
+
+    /// Give up on an interrupted operation
+    pub fn discard(&self, id: &str) -> Result<(), String> {
+        let checkpoint = self
+            .interrupted
+            .lock()
+            .unwrap()
+            .remove(id)
+            .ok_or_else(|| format!("No interrupted operation: {}", id))?;
+        storage::remove_json(&self.dir.join(file_name(checkpoint.kind, &checkpoint.target)))
+    }
+
+    /// Kind and target of an interrupted operation, to start it again
+    pub fn target_of(&self, id: &str) -> Option<(OperationKind, String)> {
+        self.interrupted.lock().unwrap().get(id).map(|c| (c.kind, c.target.clone()))
+    }
+}
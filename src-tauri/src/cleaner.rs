@@ -2,6 +2,12 @@ use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::fs;
 use std::time::SystemTime;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::i18n;
+use crate::junk_age::JunkAgeSettings;
+use crate::open_handles::InUseItem;
+use crate::paths;
+use crate::safeguard::PreCleanRecord;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct JunkItem {
@@ -19,129 +25,146 @@ pub struct JunkCategory {
     pub items: Vec<JunkItem>,
     pub total_size: u64,
     pub icon: String, // Helper for frontend icon mapping
+    /// Occasionally holds something the user still needs; cleaning it can
+    /// be preceded by a recovery record (see safeguard)
+    #[serde(default)]
+    pub risky: bool,
+    /// Name of the plugin server that contributed it; None for built-in
+    /// categories (see junk_plugins)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Items left out for having changed more recently than the category's
+    /// minimum age (see junk_age)
+    #[serde(default)]
+    pub too_recent: u64,
 }
 
+/// Categories whose cleanup is hard to undo or affects the system
+const RISKY_CATEGORIES: &[&str] = &["trash", "logs", "system_logs", "windows_temp", "prefetch"];
+
 #[cfg(target_os = "macos")]
-fn get_potential_junk_paths() -> Vec<(&'static str, &'static str, &'static str)> {
+fn get_potential_junk_paths() -> Vec<(&'static str, &'static str)> {
     vec![
-        // (Category ID, Path (Environment variable expanded manually), Description)
-        ("system_cache", "~/Library/Caches", "Application Caches"),
-        ("system_logs", "~/Library/Logs", "Application Logs"),
-        ("trash", "~/.Trashes", "Trash Bin"), // Note: Trashes handling might be tricky with permissions
-        ("temp", "/tmp", "Temporary Files"), 
+        // (Category ID, Path (Environment variable expanded manually)); names come from the `junk.<id>` messages
+        ("system_cache", "~/Library/Caches"),
+        ("system_logs", "~/Library/Logs"),
+        ("trash", "~/.Trash"), // Needs Full Disk Access; see permissions::scan_issue
+        ("temp", "/tmp"), 
         // More safe paths
     ]
 }
 
 #[cfg(target_os = "linux")]
-fn get_potential_junk_paths() -> Vec<(&'static str, &'static str, &'static str)> {
+fn get_potential_junk_paths() -> Vec<(&'static str, &'static str)> {
     vec![
-        ("system_cache", "~/.cache", "Application Caches"),
-        ("temp", "/tmp", "Temporary Files"),
-        ("logs", "/var/log", "System Logs"), // Often restricted, need to handle gracefully
+        ("system_cache", "~/.cache"),
+        ("temp", "/tmp"),
+        ("logs", "/var/log"), // Often restricted, need to handle gracefully
     ]
 }
 
 #[cfg(target_os = "windows")]
-fn get_potential_junk_paths() -> Vec<(&'static str, &'static str, &'static str)> {
+fn get_potential_junk_paths() -> Vec<(&'static str, &'static str)> {
     vec![
-        ("temp", "%TEMP%", "Temporary Files"),
-        ("windows_temp", "C:\\Windows\\Temp", "Windows Temporary Files"),
-        ("prefetch", "C:\\Windows\\Prefetch", "Prefetch Files"),
+        ("temp", "%TEMP%"),
+        ("windows_temp", "C:\\Windows\\Temp"),
+        ("prefetch", "C:\\Windows\\Prefetch"),
     ]
 }
 
+/// A junk location with `~` and variables expanded, if it exists here
 fn expand_path(path: &str) -> Option<PathBuf> {
-    if path.starts_with('~') {
-        if let Some(home_dir) = dirs::home_dir() {
-            if path == "~" {
-                return Some(home_dir);
-            }
-            return Some(home_dir.join(&path[2..]));
-        }
+    let path = paths::expand(path);
+    path.exists().then_some(path)
+}
+
+/// IDs of the built-in categories on this platform
+pub fn category_ids() -> Vec<&'static str> {
+    let mut ids: Vec<&'static str> = get_potential_junk_paths().into_iter().map(|(id, _)| id).collect();
+    ids.dedup();
+    ids.push(crate::game_libraries::UNPLAYED_CATEGORY);
+    ids
+}
+
+/// Size of a file or folder, and when anything in it last changed
+fn size_and_newest(path: &Path, meta: &fs::Metadata) -> (u64, SystemTime) {
+    let mut newest = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    if !meta.is_dir() {
+        return (meta.len(), newest);
     }
-    
-    #[cfg(target_os = "windows")]
-    {
-        use std::env;
-        // Simple simplistic env var expansion for %TEMP%
-        if path.contains('%') {
-            // This is a naive expansion, real world usage might need regex or specific crate
-            // For now handling specific known ones
-            if path.contains("%TEMP%") {
-                let val = env::var("TEMP").or_else(|_| env::var("TMP")).unwrap_or_default();
-                return Some(PathBuf::from(path.replace("%TEMP%", &val)));
-            }
-            if path.contains("%LOCALAPPDATA%") {
-                let val = env::var("LOCALAPPDATA").unwrap_or_default();
-                return Some(PathBuf::from(path.replace("%LOCALAPPDATA%", &val)));
-            }
+    let mut size = 0;
+    for meta in walkdir::WalkDir::new(path).into_iter().filter_map(|e| e.ok()).filter_map(|e| e.metadata().ok()) {
+        if meta.is_file() {
+            size += meta.len();
+        }
+        if let Ok(modified) = meta.modified() {
+            newest = newest.max(modified);
         }
     }
-    
-    let p = PathBuf::from(path);
-    if p.exists() {
-        Some(p)
-    } else {
-        None
-    }
+    (size, newest)
 }
 
-pub fn scan_junk_items() -> Vec<JunkCategory> {
+/// Junk in the built-in categories, leaving out items younger than the
+/// minimum age of their category
+pub fn scan_junk_items(ages: &JunkAgeSettings) -> Vec<JunkCategory> {
     let mut categories: Vec<JunkCategory> = Vec::new();
     let paths = get_potential_junk_paths();
+    let now = SystemTime::now();
 
     // Grouping by ID
-    for (id, path_str, desc) in paths {
+    for (id, path_str) in paths {
         if let Some(path) = expand_path(path_str) {
+            let category_name = i18n::t(&format!("junk.{}", id));
+            let min_age = ages.min_age(id);
             let mut items = Vec::new();
             let mut total_size = 0;
+            let mut too_recent = 0;
             
             // Shallow scan for caching folders? Or File level? 
             // For Caches, often deleting the whole subfolder is what's wanted, 
             // but we might want to list top-level folders inside Cache.
             
-            if let Ok(read_dir) = fs::read_dir(&path) {
+            if let Ok(read_dir) = fs::read_dir(paths::extended(&path)) {
                 for entry in read_dir.flatten() {
                     if let Ok(meta) = entry.metadata() {
-                        let size = if meta.is_dir() {
-                            // Deep size calc is expensive, maybe just use 0 or do a quick walk?
-                            // For UI responsiveness we might skip deep size here or do it async.
-                            // Let's implement a quick depth-1 size estimation or just 0 for now
-                            // To be accurate, we should probably do a walk. 
-                            match fs_extra::dir::get_size(entry.path()) {
-                                Ok(s) => s,
-                                Err(_) => 0,
-                            }
-                        } else {
-                            meta.len()
-                        };
+                        // Deep size calc is expensive, but the walk also
+                        // finds the newest file, which the age filter needs
+                        let (size, newest) = size_and_newest(&entry.path(), &meta);
+                        let age = now.duration_since(newest).unwrap_or_default();
+                        if min_age.is_some_and(|min_age| age < min_age) {
+                            too_recent += 1;
+                            continue;
+                        }
 
                         total_size += size;
                         
                         items.push(JunkItem {
-                            path: entry.path().to_string_lossy().to_string(),
+                            path: paths::display(&entry.path()),
                             name: entry.file_name().to_string_lossy().to_string(),
                             size,
-                            description: format!("Item in {}", desc),
+                            description: i18n::t_with("junk.item_in", &[("category", &category_name)]),
                         });
                     }
                 }
             }
 
-            if !items.is_empty() {
+            if !items.is_empty() || too_recent > 0 {
                 // Check if category already exists (e.g. multiple temp paths)
                 if let Some(cat) = categories.iter_mut().find(|c| c.id == id) {
                     cat.items.extend(items);
                     cat.total_size += total_size;
+                    cat.too_recent += too_recent;
                 } else {
                     categories.push(JunkCategory {
                         id: id.to_string(),
-                        name: desc.to_string(),
-                        description: format!("Files located in {}", path.to_string_lossy()),
+                        name: category_name,
+                        description: i18n::t_with("junk.located_in", &[("path", &path.to_string_lossy())]),
                         items,
                         total_size,
                         icon: id.to_string(), // Frontend can map this
+                        risky: RISKY_CATEGORIES.contains(&id),
+                        provider: None,
+                        too_recent,
                     });
                 }
             }
@@ -150,18 +173,46 @@ pub fn scan_junk_items() -> Vec<JunkCategory> {
     categories
 }
 
-pub fn delete_junk_items(paths: Vec<String>) -> Result<(), String> {
+/// Whether any of `paths` lies in a risky category
+pub fn touches_risky(paths: &[String]) -> bool {
+    let roots: Vec<PathBuf> = get_potential_junk_paths()
+        .into_iter()
+        .filter(|(id, _)| RISKY_CATEGORIES.contains(id))
+        .filter_map(|(_, path)| expand_path(path))
+        .collect();
+    paths
+        .iter()
+        .any(|p| roots.iter().any(|root| paths::is_within(Path::new(p), root)))
+}
+
+/// What clean_junk did
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanResult {
+    /// Recovery point, when `safeguard` recorded one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record: Option<PreCleanRecord>,
+    /// Items left alone because a running program had them open; cleaning
+    /// them again after closing it can succeed
+    pub skipped_in_use: Vec<InUseItem>,
+}
+
+/// Delete the given junk items, stopping early once `cancel` is set
+pub fn delete_junk_items(paths: Vec<String>, cancel: &AtomicBool) -> Result<(), String> {
     let mut errors = Vec::new();
     for path in paths {
-        let p = Path::new(&path);
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let p = &paths::extended(Path::new(&path));
         if p.exists() {
             if p.is_file() {
                 if let Err(e) = fs::remove_file(p) {
-                    errors.push(format!("Failed to delete file {}: {}", path, e));
+                    errors.push(i18n::t_with("error.delete_file", &[("path", &path), ("error", &e.to_string())]));
                 }
             } else if p.is_dir() {
                 if let Err(e) = fs::remove_dir_all(p) {
-                    errors.push(format!("Failed to delete folder {}: {}", path, e));
+                    errors.push(i18n::t_with("error.delete_folder", &[("path", &path), ("error", &e.to_string())]));
                 }
             }
         }
@@ -0,0 +1,139 @@
+// Command Scope - Which windows may invoke which commands
+//
+// Every webview can call every registered command, so a compromised page in
+// a secondary window (a future tray popover, say) could delete files as
+// easily as the main window. Every invocation passes through `guard` first:
+//
+// - Read-only commands (scanning, listing, reading settings) may be called
+//   from any window. Commands returning file contents are not among them.
+// - Everything else changes state and is limited to the main window.
+// - Destructive commands (deleting, cleaning, shredding, importing) further
+//   need the main window visible and focused, i.e. in front of a user who
+//   could have asked for them, not running hidden or in the background.
+//
+// MCP tool calls are classified by the tool's annotations: read-only tools
+// that don't return file contents count as read-only commands, destructive ones (writing, moving, editing
+// files) as destructive commands, and tools without either hint as
+// main-window commands. Agent runs are no exception: a run's writes happen
+// only while the main window is in front of the user.
+// Commands not listed here are main-window only, so a new command is never
+// exposed to restricted windows by accident.
+
+use crate::mcp_commands_native;
+use tauri::ipc::{Invoke, InvokeBody, InvokeMessage};
+use tauri::Runtime;
+use tracing::warn;
+
+/// Label of the main application window
+pub const MAIN_WINDOW: &str = "main";
+
+/// Commands any window may invoke
+const READ_ONLY: &[&str] = &[
+    // Scanning and analysis
+    "scan_dir", "refresh_scan", "scan_multiple", "scan_dir_shallow", "get_deep_stats", "cancel_scan",
+    "get_type_breakdown", "get_largest_files", "get_treemap", "get_drives", "check_drive_space",
+    "get_scan_history", "get_indexed_tree", "list_index_roots", "get_scan_strategy",
+    "list_scan_overrides", "scan_junk", "get_junk_age_settings", "find_git_repos",
+    "inspect_disk_image", "find_mail_archives", "find_games", "analyze_browser_storage",
+    "find_photo_bursts", "get_age_heatmap", "find_stale_files", "forecast_reclaim",
+    "compare_directories", "detect_backups", "check_backups",
+    "list_wsl_distros", "get_wsl_usage", "get_snapshot_report", "get_disk_writers",
+    "get_open_files", "get_basket", "get_quarantine", "get_archive",
+    // App state
+    "get_recent_logs", "get_available_locales", "list_operations", "list_resumable_operations",
+    "get_environment_report", "get_memory_report", "get_memory_settings", "get_permission_state",
+    "get_telemetry_state", "list_profiles",
+    "get_active_profile", "get_alert_settings", "get_audit_mode", "get_audit_log",
+    "verify_audit_log", "list_remote_hosts", "list_junk_plugins", "list_storage_accounts",
+    // AI and MCP
+    "get_ai_providers_status", "get_provider_models", "get_inference_queue", "get_accelerators",
+    "get_recommended_models", "get_loaded_models", "check_provider_availability",
+    "get_download_settings", "list_prompt_presets", "list_conversations", "get_conversation",
+    "get_usage_summary", "get_length_policy", "list_interrupted_agent_runs",
+    "get_mcp_tools", "is_mcp_initialized", "get_guardrail_policy", "get_tool_cache_settings",
+    "get_mcp_resource_limits",
+];
+
+/// Commands that delete, overwrite or run something, for the focused main
+/// window only
+const DESTRUCTIVE: &[&str] = &[
+    "delete_item", "open_file", "clean_junk", "clear_browser_storage", "gc_git_repo",
+    "commit_basket", "quarantine_items", "restore_quarantined", "purge_quarantined",
+    "archive_files", "restore_archived",
+    "run_file_transaction", "shred_items", "compact_wsl_distro", "reclaim_snapshot_space",
+    "execute_file_plan", "import_config", "install_update", "delete_profile",
+];
+
+/// Read-only MCP tools that return file contents, for the main window only
+const CONTENT_TOOLS: &[&str] = &["read_file", "read_multiple_files"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    ReadOnly,
+    MainWindow,
+    Destructive,
+}
+
+/// Name of the tool an execute_mcp_tool call runs
+fn mcp_tool(payload: &InvokeBody) -> Option<&str> {
+    match payload {
+        InvokeBody::Json(args) => args.get("request")?.get("tool_name")?.as_str(),
+        _ => None,
+    }
+}
+
+fn scope_of(command: &str, payload: &InvokeBody) -> Scope {
+    if command == "execute_mcp_tool" {
+        let tool = mcp_tool(payload);
+        let annotations = tool.and_then(mcp_commands_native::tool_annotations);
+        return match annotations {
+            _ if tool.is_some_and(|t| CONTENT_TOOLS.contains(&t)) => Scope::MainWindow,
+            Some(a) if a.read_only_hint == Some(true) => Scope::ReadOnly,
+            Some(a) if a.destructive_hint == Some(true) => Scope::Destructive,
+            _ => Scope::MainWindow,
+        };
+    }
+    if READ_ONLY.contains(&command) {
+        Scope::ReadOnly
+    } else if DESTRUCTIVE.contains(&command) {
+        Scope::Destructive
+    } else {
+        Scope::MainWindow
+    }
+}
+
+/// Why the window that sent `message` may not run its command, if it may not
+fn refusal<R: Runtime>(message: &InvokeMessage<R>) -> Option<String> {
+    let command = message.command();
+    let scope = scope_of(command, message.payload());
+    if scope == Scope::ReadOnly {
+        return None;
+    }
+    let webview = message.webview();
+    if webview.label() != MAIN_WINDOW {
+        return Some(format!("{} is not available in window \"{}\"", command, webview.label()));
+    }
+    if scope == Scope::Destructive {
+        let window = webview.window();
+        let in_front = window.is_visible().unwrap_or(false) && window.is_focused().unwrap_or(false);
+        if !in_front {
+            return Some(format!("{} needs the main window in the foreground", command));
+        }
+    }
+    None
+}
+
+/// Wrap the app's command handler so every invocation is checked first;
+/// refused ones are rejected without running
+pub fn guard<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| match refusal(&invoke.message) {
+        None => handler(invoke),
+        Some(reason) => {
+            warn!("Refused command: {}", reason);
+            invoke.resolver.reject(reason);
+            true
+        }
+    }
+}
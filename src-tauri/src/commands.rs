@@ -1,105 +1,216 @@
-use tauri::{command, AppHandle, Emitter};
-use crate::scanner::{scan_directory, FileNode, ScanStats};
-use crate::cleaner::{self, JunkCategory};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex, RwLock};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime, State, Webview};
+use crate::scanner::{self, scan_directory, FileNode, NodeCallback, Reuse, ScanStats, TypeBreakdown};
+use crate::scan_watch::ScanWatcher;
+use crate::scan_sessions::{ScanSessions, ScanStarted, SCAN_STARTED_EVENT};
+use crate::scan_cache;
+use crate::breakdown::{self, LargeFile};
+use crate::treemap::{self, Treemap, TreemapLimits};
+use crate::scan_history::{ScanHistory, ScanRecord};
+use crate::size_index::{IndexedRoot, SizeIndex};
+use crate::scan_strategy::{ScanStrategyStore, VolumeOverride, VolumeStrategy};
+use crate::volume_space::SpaceReader;
+use crate::remote::{self, RemoteHost, RemoteStore};
+use crate::disk_images::{self, ImageContents};
+use crate::git_repos::{self, GcResult, GitRepo};
+use crate::browser_storage::{self, BrowserProfile};
+use crate::game_libraries::{self, Game};
+use crate::mail_archives::{self, MailArchive};
+use crate::open_handles;
+use crate::paths;
+use crate::safeguard;
+use crate::transactions::{self, TransactionResult, TransactionStep};
+use crate::reclaim::{self, ReclaimForecast};
+use crate::shred::{self, ShredReport};
+use crate::dir_compare::{self, CompareMode, DirectoryComparison};
+use crate::backups::{self, BackupCheck, BackupTarget};
+use crate::content_type::{self, FilePreview};
+use crate::basket::{BasketCommitResult, BasketStore, BasketSummary, BASKET_EVENT};
+use crate::archive::{ArchiveDestination, ArchiveFailure, ArchiveRestoreResult, ArchiveResult, ArchiveStore, ArchiveSummary, ARCHIVE_EVENT};
+use crate::quarantine::{PurgeResult, QuarantineStore, QuarantineSummary, RestoreResult, QUARANTINE_EVENT};
+use crate::age_heatmap::{self, AgeGranularity, AgeHeatmap};
+use crate::stale_files::{self, StaleOptions, StaleReport};
+use crate::photo_bursts::{self, BurstOptions, PhotoBurst};
+use crate::object_storage::{self, BucketReport, ObjectStorageAccount, ObjectStorageStore};
+use crate::alerts::{AlertMonitor, AlertSettings, DriveAlert};
+use crate::wsl::{self, CompactResult, WslDistro, WslUsage};
+use crate::snapshots::{self, ReclaimAction, SnapshotCategory};
+use crate::disk_activity::{self, DiskActivityMonitor, ProcessDiskActivity};
+use crate::app_config::{self, ImportSummary};
+use crate::profiles::{Profile, ProfileStore, PROFILE_EVENT};
+use crate::mcp_commands_native::NativeMCPState;
+use crate::mcp::notifications;
+use crate::cleaner::{self, CleanResult, JunkCategory};
+use crate::junk_age::{JunkAgeSettings, JunkAgeStore};
+use crate::junk_plugins::{self, JunkPluginServer, JunkPluginStore};
+use crate::environment::{self, EnvironmentReport};
+use crate::memory::{self, MemoryReport, MemorySettings, MemoryStore};
+use crate::i18n;
+use crate::audit::{self, AuditLogCheck, AuditLogEntry, AuditSettings, AUDIT_MODE_EVENT};
+use crate::logging::{self, LogEntry, LogFilter};
+use crate::permissions::{self, PermissionReport, SettingsPane, PERMISSION_EVENT};
+use crate::updater::{self, UpdateInfo, UpdateState};
+use crate::telemetry::{Telemetry, TelemetryState};
+use crate::operations::{OperationHandle, OperationInfo, OperationKind, OperationManager};
+use crate::checkpoints::{CheckpointStore, ResumableOperation};
+use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::{SystemTime, Duration};
-use lazy_static::lazy_static;
 use std::path::Path;
 use sysinfo::Disks;
 
-struct CacheEntry {
-    node: FileNode,
-    timestamp: SystemTime,
-}
-
-// Global state to manage cancellation
-struct ScanState {
-    cancel_token: Arc<AtomicBool>,
-}
-
-lazy_static! {
-    static ref SCAN_CACHE: Mutex<HashMap<String, CacheEntry>> = Mutex::new(HashMap::new());
-    static ref SCAN_STATE: RwLock<ScanState> = RwLock::new(ScanState { 
-        cancel_token: Arc::new(AtomicBool::new(false)) 
-    });
-}
-
-const CACHE_TTL: u64 = 60 * 60; 
-
-fn normalize_path(path: &str) -> String {
-    let mut s = path.to_string();
-    if s.len() > 1 && (s.ends_with('/') || s.ends_with('\\')) {
-         let is_root = s.len() == 3 && s.chars().nth(1) == Some(':');
-         if !is_root && s != "/" {
-             s.pop();
-         }
-    }
-    s
-}
-
 #[derive(Clone, serde::Serialize)]
 struct ScanProgress {
+    session_id: String,
     path: String, // Just the root path being scanned
     count: u64,
     size: u64,
     errors: u64,
 }
 
+/// Scan `path` as a new session, announced to the calling window with a
+/// `scan-started` event carrying the session ID for cancel_scan.
+/// `same_device` overrides the active profile's choice of whether to skip
+/// folders on other file systems.
 #[command]
-pub async fn scan_dir(app: AppHandle, path: String) -> Result<FileNode, String> {
-    scan_dir_internal(app, path, false).await
+pub async fn scan_dir(
+    app: AppHandle,
+    webview: Webview,
+    path: String,
+    same_device: Option<bool>,
+    operations: State<'_, OperationManager>,
+) -> Result<FileNode, String> {
+    scan_dir_internal(app, webview.label(), path, false, same_device, &operations).await
 }
 
 #[command]
-pub async fn refresh_scan(app: AppHandle, path: String) -> Result<FileNode, String> {
-    scan_dir_internal(app, path, true).await
+pub async fn refresh_scan(
+    app: AppHandle,
+    webview: Webview,
+    path: String,
+    same_device: Option<bool>,
+    operations: State<'_, OperationManager>,
+) -> Result<FileNode, String> {
+    scan_dir_internal(app, webview.label(), path, true, same_device, &operations).await
 }
 
+/// List `path` only `depth` levels deep, leaving the folders below that
+/// unmeasured (`deferred`) for get_deep_stats. A fully scanned tree from the
+/// cache is returned as it is.
 #[command]
-pub fn cancel_scan() {
-    if let Ok(state) = SCAN_STATE.read() {
-        state.cancel_token.store(true, Ordering::Relaxed);
+pub async fn scan_dir_shallow<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    depth: usize,
+    operations: State<'_, OperationManager>,
+) -> Result<FileNode, String> {
+    if let Some(node) = scan_cache::get(&path) {
+        return Ok(node);
     }
+    let operation = operations.start(&app, OperationKind::Scan, format!("list: {}", path));
+    #[cfg(feature = "fixtures")]
+    let result = crate::fixtures::scan_shallow(&path, depth);
+    #[cfg(not(feature = "fixtures"))]
+    let result = {
+        let cancel = operation.cancel_flag();
+        let exclusions = app.state::<ProfileStore>().exclusions();
+        let symlinks = app.state::<ProfileStore>().symlink_policy();
+        tauri::async_runtime::spawn_blocking(move || {
+            scanner::scan_shallow(&path, depth, &cancel, &exclusions, symlinks)
+        }).await.map_err(|e| e.to_string()).and_then(|r| r)
+    };
+    operation.finish(&result);
+    result
 }
 
-async fn scan_dir_internal(app: AppHandle, path: String, force_refresh: bool) -> Result<FileNode, String> {
-    let key = normalize_path(&path);
-
-    // Check cache
-    if !force_refresh {
-        let cache = SCAN_CACHE.lock().map_err(|e| e.to_string())?;
-        if let Some(entry) = cache.get(&key) {
-            if let Ok(elapsed) = entry.timestamp.elapsed() {
-                if elapsed.as_secs() < CACHE_TTL {
-                    return Ok(entry.node.clone());
-                }
-            }
-        }
+/// Size and file count of everything below `path`, without its children:
+/// from the last scan when it has the folder, otherwise by walking it
+#[command]
+pub async fn get_deep_stats<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    operations: State<'_, OperationManager>,
+) -> Result<FileNode, String> {
+    if let Some(node) = scan_cache::get(&path) {
+        return Ok(FileNode { children: None, ..node });
     }
+    let operation = operations.start(&app, OperationKind::Scan, format!("measure: {}", path));
+    #[cfg(feature = "fixtures")]
+    let result = crate::fixtures::deep_stats(&path);
+    #[cfg(not(feature = "fixtures"))]
+    let result = {
+        let cancel = operation.cancel_flag();
+        let exclusions = app.state::<ProfileStore>().exclusions();
+        let symlinks = app.state::<ProfileStore>().symlink_policy();
+        tauri::async_runtime::spawn_blocking(move || {
+            scanner::deep_stats(&path, Some(cancel), &exclusions, symlinks)
+        }).await.map_err(|e| e.to_string()).and_then(|r| r)
+    };
+    operation.finish(&result);
+    result
+}
 
-    // Reset cancellation
-    let cancel_token = Arc::new(AtomicBool::new(false));
-    if let Ok(mut state) = SCAN_STATE.write() {
-        state.cancel_token = cancel_token.clone();
+/// Stop the scan session `session_id`, or without one every scan the
+/// calling window started. Returns whether any scan was running.
+#[command]
+pub fn cancel_scan(webview: Webview, session_id: Option<String>, sessions: State<'_, ScanSessions>) -> bool {
+    match session_id {
+        Some(id) => sessions.cancel(&id),
+        None => sessions.cancel_window(webview.label()) > 0,
     }
+}
+
+/// A folder directly below a scan's root, sent as soon as it is measured
+#[derive(Clone, serde::Serialize)]
+struct ScanNodeEvent {
+    session_id: String,
+    parent: String,
+    node: FileNode,
+}
 
-    // Stats for progress
-    let stats = Arc::new(ScanStats {
+fn new_scan_stats(on_child: Option<NodeCallback>) -> Arc<ScanStats> {
+    Arc::new(ScanStats {
         scanned_files: AtomicU64::new(0),
         total_size: AtomicU64::new(0),
         errors: AtomicU64::new(0),
-    });
+        on_child,
+    })
+}
 
-    let is_done = Arc::new(AtomicBool::new(false));
+/// Send each folder below `path` to `window` as a `scan-node` event once it
+/// is measured, so the tree of a large drive fills in while it is scanned
+fn stream_scan_nodes(app: &AppHandle, window: &str, session_id: &str, path: &str) -> NodeCallback {
+    let (app, window) = (app.clone(), window.to_string());
+    let (session_id, parent) = (session_id.to_string(), path.to_string());
+    Box::new(move |node: &FileNode| {
+        let event = ScanNodeEvent {
+            session_id: session_id.clone(),
+            parent: parent.clone(),
+            node: node.clone(),
+        };
+        let _ = app.emit_to(window.as_str(), "scan-node", event);
+    })
+}
 
-    // Spawn progress emitter
+/// Send `stats` to `window` as `scan-progress` events every 100ms until the
+/// scan is cancelled or the returned flag is set once it is done
+fn spawn_scan_progress(
+    app: &AppHandle,
+    window: &str,
+    session_id: &str,
+    path: &str,
+    stats: &Arc<ScanStats>,
+    cancel: &Arc<AtomicBool>,
+    operation: &OperationHandle,
+) -> Arc<AtomicBool> {
+    let is_done = Arc::new(AtomicBool::new(false));
     let stats_clone = stats.clone();
     let app_handle = app.clone();
-    let path_report = path.clone();
-    let cancel_clone = cancel_token.clone();
+    let path_report = path.to_string();
+    let cancel_clone = cancel.clone();
     let is_done_clone = is_done.clone();
+    let operation_report = operation.clone();
+    let window_report = window.to_string();
+    let session_report = session_id.to_string();
     
     tauri::async_runtime::spawn(async move {
         // Emit every 100ms
@@ -114,55 +225,329 @@ async fn scan_dir_internal(app: AppHandle, path: String, force_refresh: bool) ->
             let errors = stats_clone.errors.load(Ordering::Relaxed);
 
             let payload = ScanProgress {
+                 session_id: session_report.clone(),
                  path: path_report.clone(),
                  count,
                  size,
                  errors
             };
-            let _ = app_handle.emit("scan-progress", payload);
+            let _ = app_handle.emit_to(window_report.as_str(), "scan-progress", payload);
+            operation_report.progress(
+                None,
+                Some(i18n::t_with("operation.files_scanned", &[("count", &count.to_string())])),
+            );
 
             tokio::time::sleep(Duration::from_millis(100)).await;
         }
     });
+    is_done
+}
+
+async fn scan_dir_internal(
+    app: AppHandle,
+    window: &str,
+    path: String,
+    force_refresh: bool,
+    same_device: Option<bool>,
+    operations: &OperationManager,
+) -> Result<FileNode, String> {
+    // Check cache, unless the scan asks for other mounts than cached scans
+    if !force_refresh && same_device.is_none() {
+        if let Some(node) = scan_cache::get(&path) {
+            return Ok(node);
+        }
+    }
+
+    // ssh://<host>/<path> is scanned on the remote host
+    let remote = app.state::<RemoteStore>().resolve(&path).transpose()?;
+
+    // Without Full Disk Access parts of the tree read as empty; tell the UI why
+    if let Some(issue) = permissions::scan_issue(&path) {
+        let _ = app.emit(PERMISSION_EVENT, issue);
+    }
+
+    // Register the scan as a session of the calling window; cancel_scan and
+    // cancel_operation both stop it
+    let operation = operations.start(&app, OperationKind::Scan, path.clone());
+    let cancel_token = operation.cancel_flag();
+    let session_id = operation.id().to_string();
+    app.state::<ScanSessions>().begin(&session_id, window, cancel_token.clone());
+    let _ = app.emit_to(window, SCAN_STARTED_EVENT, ScanStarted { session_id: session_id.clone(), path: path.clone() });
+
+    // Stats for progress, and folders for the tree as they are measured
+    let stats = new_scan_stats(Some(stream_scan_nodes(&app, window, &session_id, &path)));
+
+    let is_done = spawn_scan_progress(&app, window, &session_id, &path, &stats, &cancel_token, &operation);
 
     let path_clone = path.clone();
-    let result = tauri::async_runtime::spawn_blocking(move || {
-        scan_directory(&path_clone, Some(stats), Some(cancel_token))
-    }).await.map_err(|e| e.to_string())??;
+    let scan_stats = stats.clone();
+    let exclusions = app.state::<ProfileStore>().exclusions();
+    let symlinks = app.state::<ProfileStore>().symlink_policy();
+    let strategy = app.state::<ScanStrategyStore>().resolve(Path::new(&path)).strategy;
+    let started = std::time::Instant::now();
+    // A scan of the same path interrupted by a restart continues where it
+    // stopped; a refresh starts over
+    let checkpoint = app.state::<CheckpointStore>().begin(OperationKind::Scan, &path, force_refresh);
+    let scan_checkpoint = checkpoint.clone();
+    // A path scanned before and watched since only has its changed folders
+    // walked again
+    let watched = remote.is_none();
+    let unchanged = if watched { app.state::<ScanWatcher>().begin(&path) } else { None };
+    #[cfg(feature = "fixtures")]
+    let result = {
+        let _ = (stats, cancel_token, exclusions, symlinks, same_device, remote, strategy, scan_checkpoint, unchanged);
+        crate::fixtures::scan(&path_clone)
+    };
+    #[cfg(not(feature = "fixtures"))]
+    let result = tauri::async_runtime::spawn_blocking(move || match remote {
+        Some((host, remote_path)) => remote::scan(&host, &remote_path, Some(stats), Some(cancel_token)),
+        None => {
+            let mut reuse: Vec<&dyn Reuse> = vec![&scan_checkpoint];
+            if let Some(unchanged) = &unchanged {
+                reuse.insert(0, unchanged);
+            }
+            scan_directory(&path_clone, Some(stats), Some(cancel_token), &exclusions, &strategy, symlinks, same_device, &reuse)
+        }
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
 
     is_done.store(true, Ordering::Relaxed);
+    app.state::<ScanSessions>().end(&session_id);
+    app.state::<CheckpointStore>().end(&checkpoint);
+    if watched {
+        app.state::<ScanWatcher>().finish(&path, result.as_ref().ok());
+    }
+    operation.finish(&result);
+    let result = result?;
+
+    let telemetry = app.state::<Telemetry>();
+    telemetry.feature_used("scan");
+    telemetry.scan_completed(scan_stats.scanned_files.load(Ordering::Relaxed), started.elapsed());
     
-    // Update cache
-    let mut cache = SCAN_CACHE.lock().map_err(|e| e.to_string())?;
-    let now = SystemTime::now();
-    
-    cache.insert(key.clone(), CacheEntry {
-        node: result.clone(),
-        timestamp: now,
-    });
-    
-    if let Some(children) = &result.children {
-        for child in children {
-            let child_key = normalize_path(&child.path);
-            cache.insert(child_key, CacheEntry {
-                node: child.clone(),
-                timestamp: now,
-            });
+    scan_cache::store(&path, &result);
+    app.state::<ScanHistory>().record(&result);
+
+    Ok(result)
+}
+
+/// One root of scan_multiple, on the remote host for ssh:// paths
+#[cfg(not(feature = "fixtures"))]
+fn scan_one_root(
+    path: &str,
+    remote: Option<(RemoteHost, String)>,
+    stats: Arc<ScanStats>,
+    cancel: Arc<AtomicBool>,
+    exclusions: &scanner::Exclusions,
+    strategy: &scanner::ScanStrategy,
+    symlinks: scanner::SymlinkPolicy,
+) -> Result<FileNode, String> {
+    match remote {
+        Some((host, remote_path)) => remote::scan(&host, &remote_path, Some(stats), Some(cancel)),
+        None => scan_directory(path, Some(stats), Some(cancel), exclusions, strategy, symlinks, None, &[]),
+    }
+}
+
+/// Fixture builds answer from the in-memory filesystem
+#[cfg(feature = "fixtures")]
+fn scan_one_root(
+    path: &str,
+    _remote: Option<(RemoteHost, String)>,
+    _stats: Arc<ScanStats>,
+    _cancel: Arc<AtomicBool>,
+    _exclusions: &scanner::Exclusions,
+    _strategy: &scanner::ScanStrategy,
+    _symlinks: scanner::SymlinkPolicy,
+) -> Result<FileNode, String> {
+    crate::fixtures::scan(path)
+}
+
+/// Scan several folders or drives together. The roots are scanned
+/// concurrently as one session with combined progress (its `path` is the
+/// roots joined by ", "), and returned as the children of a synthetic root,
+/// largest first. Roots already cached are not scanned again; a root inside
+/// another one in the list is left out so nothing is counted twice.
+#[command]
+pub async fn scan_multiple(
+    app: AppHandle,
+    webview: Webview,
+    paths: Vec<String>,
+    operations: State<'_, OperationManager>,
+) -> Result<FileNode, String> {
+    let mut roots: Vec<String> = Vec::new();
+    for path in paths {
+        let nested = |a: &str, b: &str| paths::is_within(Path::new(a), Path::new(b));
+        if roots.iter().any(|root| nested(&path, root)) {
+            continue;
         }
+        roots.retain(|root| !nested(root, &path));
+        roots.push(path);
+    }
+    if roots.is_empty() {
+        return Err("No paths to scan".to_string());
     }
 
-    Ok(result)
+    // Resolve everything that can fail before the session starts
+    let mut targets = Vec::new();
+    for path in &roots {
+        let remote = app.state::<RemoteStore>().resolve(path).transpose()?;
+        let strategy = app.state::<ScanStrategyStore>().resolve(Path::new(path)).strategy;
+        if let Some(issue) = permissions::scan_issue(path) {
+            let _ = app.emit(PERMISSION_EVENT, issue);
+        }
+        targets.push((path.clone(), remote, strategy));
+    }
+
+    let label = roots.join(", ");
+    let window = webview.label();
+    let operation = operations.start(&app, OperationKind::Scan, label.clone());
+    let cancel = operation.cancel_flag();
+    let session_id = operation.id().to_string();
+    app.state::<ScanSessions>().begin(&session_id, window, cancel.clone());
+    let _ = app.emit_to(window, SCAN_STARTED_EVENT, ScanStarted { session_id: session_id.clone(), path: label.clone() });
+
+    let stats = new_scan_stats(None);
+    let is_done = spawn_scan_progress(&app, window, &session_id, &label, &stats, &cancel, &operation);
+
+    let exclusions = app.state::<ProfileStore>().exclusions();
+    let symlinks = app.state::<ProfileStore>().symlink_policy();
+    let started = std::time::Instant::now();
+    let handles: Vec<_> = targets
+        .into_iter()
+        .map(|(path, remote, strategy)| {
+            let (stats, cancel, exclusions) = (stats.clone(), cancel.clone(), exclusions.clone());
+            tauri::async_runtime::spawn_blocking(move || {
+                if let Some(node) = scan_cache::get(&path) {
+                    stats.scanned_files.fetch_add(node.file_count, Ordering::Relaxed);
+                    stats.total_size.fetch_add(node.size, Ordering::Relaxed);
+                    return Ok(node);
+                }
+                scan_one_root(&path, remote, stats, cancel, &exclusions, &strategy, symlinks)
+            })
+        })
+        .collect();
+
+    // One failed root fails the whole scan; the others are stopped
+    let mut scanned = Vec::new();
+    let mut failure = None;
+    for handle in handles {
+        match handle.await.map_err(|e| e.to_string()).and_then(|r| r) {
+            Ok(node) => scanned.push(node),
+            Err(e) => {
+                cancel.store(true, Ordering::Relaxed);
+                failure.get_or_insert(e);
+            }
+        }
+    }
+
+    is_done.store(true, Ordering::Relaxed);
+    app.state::<ScanSessions>().end(&session_id);
+    let result = match failure {
+        Some(e) => Err(e),
+        None => Ok(()),
+    };
+    operation.finish(&result);
+    result?;
+
+    let telemetry = app.state::<Telemetry>();
+    telemetry.feature_used("scan-multiple");
+    telemetry.scan_completed(stats.scanned_files.load(Ordering::Relaxed), started.elapsed());
+    for node in &scanned {
+        scan_cache::store(&node.path, node);
+        app.state::<ScanHistory>().record(node);
+    }
+
+    let name = i18n::t_with("operation.scan_roots", &[("count", &scanned.len().to_string())]);
+    Ok(scanner::merge_roots(name, scanned))
 }
 
+/// Files listed by get_largest_files unless asked otherwise, and at most
+const DEFAULT_LARGEST_FILES: usize = 100;
+const MAX_LARGEST_FILES: usize = 10_000;
+
+/// The largest files under `path` with their sizes and modification times,
+/// largest first. A cached scan of `path` spares walking folders too small
+/// to hold any of them.
 #[command]
-pub fn clear_cache() {
-    if let Ok(mut cache) = SCAN_CACHE.lock() {
-        cache.clear();
+pub async fn get_largest_files<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    limit: Option<usize>,
+    operations: State<'_, OperationManager>,
+) -> Result<Vec<LargeFile>, String> {
+    let limit = limit.unwrap_or(DEFAULT_LARGEST_FILES).clamp(1, MAX_LARGEST_FILES);
+    let operation = operations.start(&app, OperationKind::Scan, format!("largest files: {}", path));
+    #[cfg(feature = "fixtures")]
+    let result = crate::fixtures::largest_files(&path, limit);
+    #[cfg(not(feature = "fixtures"))]
+    let result = {
+        let cancel = operation.cancel_flag();
+        let exclusions = app.state::<ProfileStore>().exclusions();
+        tauri::async_runtime::spawn_blocking(move || {
+            let scanned = scan_cache::get(&path);
+            breakdown::largest_files(&path, limit, scanned.as_ref(), &exclusions, &cancel)
+        }).await.map_err(|e| e.to_string()).and_then(|r| r)
+    };
+    operation.finish(&result);
+    result
+}
+
+/// Bytes by file category and extension below `path`: from the last scan
+/// when it recorded them, otherwise by walking the folder
+#[command]
+pub async fn get_type_breakdown<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    operations: State<'_, OperationManager>,
+) -> Result<TypeBreakdown, String> {
+    if let Some(breakdown) = scan_cache::get(&path).as_ref().and_then(TypeBreakdown::of_node) {
+        return Ok(breakdown);
     }
+    let operation = operations.start(&app, OperationKind::Scan, format!("types: {}", path));
+    #[cfg(feature = "fixtures")]
+    let result = crate::fixtures::type_breakdown(&path);
+    #[cfg(not(feature = "fixtures"))]
+    let result = {
+        let cancel = operation.cancel_flag();
+        let exclusions = app.state::<ProfileStore>().exclusions();
+        let symlinks = app.state::<ProfileStore>().symlink_policy();
+        tauri::async_runtime::spawn_blocking(move || {
+            scanner::type_breakdown(&path, Some(cancel), &exclusions, symlinks)
+        }).await.map_err(|e| e.to_string()).and_then(|r| r)
+    };
+    operation.finish(&result);
+    result
+}
+
+/// The cached scan of `path` as flat rows for the treemap, capped at
+/// `max_rows` and `max_depth` levels and leaving out nodes below `min_size`
+#[command]
+pub async fn get_treemap(
+    path: String,
+    max_rows: Option<usize>,
+    max_depth: Option<u32>,
+    min_size: Option<u64>,
+) -> Result<Treemap, String> {
+    let limits = TreemapLimits {
+        max_rows: max_rows.unwrap_or(treemap::DEFAULT_ROWS),
+        max_depth: max_depth.unwrap_or(u32::MAX),
+        min_size,
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let node = scan_cache::get(&path).ok_or_else(|| format!("{} has not been scanned", path))?;
+        Ok(treemap::flatten(&node, limits))
+    }).await.map_err(|e| e.to_string()).and_then(|r| r)
+}
+
+#[command]
+pub fn clear_cache() {
+    scan_cache::clear();
 }
 
 #[command]
 pub fn reveal_in_explorer(path: String) {
+    // Fixture paths exist in memory only
+    if cfg!(feature = "fixtures") {
+        return;
+    }
     #[cfg(target_os = "windows")]
     {
         use std::process::Command;
@@ -199,6 +584,9 @@ pub fn reveal_in_explorer(path: String) {
 
 #[command]
 pub fn open_file(path: String) {
+    if cfg!(feature = "fixtures") {
+        return;
+    }
     #[cfg(target_os = "windows")]
     {
         use std::process::Command;
@@ -225,37 +613,92 @@ pub fn open_file(path: String) {
     }
 }
 
+/// Delete one item. `source` names the view it was picked in, as for the
+/// basket; low-confidence picks (the agent's, stale files) are quarantined
+/// instead.
 #[command]
-pub fn delete_item(path: String) -> Result<(), String> {
-    let p = Path::new(&path);
-    if !p.exists() {
-        return Err("Path does not exist".to_string());
+pub fn delete_item<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    source: Option<String>,
+    operations: State<'_, OperationManager>,
+) -> Result<(), String> {
+    audit::ensure_writable()?;
+    #[cfg(not(feature = "fixtures"))]
+    if !paths::extended(Path::new(&path)).exists() {
+        return Err(i18n::t("error.path_not_found"));
     }
+    let operation = operations.start_uncancellable(&app, OperationKind::Deletion, path.clone());
 
-    if p.is_dir() {
-        std::fs::remove_dir_all(p).map_err(|e| e.to_string())?;
-    } else {
-        std::fs::remove_file(p).map_err(|e| e.to_string())?;
-    }
+    #[cfg(feature = "fixtures")]
+    let result = {
+        let _ = source;
+        crate::fixtures::delete(&[path]).map(|_| false)
+    };
+    #[cfg(not(feature = "fixtures"))]
+    let result = remove_item(&app, &path, source);
+    operation.finish(&result);
+    let quarantined = result?;
     
     // Invalidate cache for parent or just clear all for safety?
     // Let's clear for now to be safe as size calc up the tree changes.
     clear_cache();
+    if quarantined {
+        let _ = app.emit(QUARANTINE_EVENT, &app.state::<QuarantineStore>().summary());
+    }
     
     Ok(())
 }
 
+/// Quarantine `path` if `source` is a low-confidence one, otherwise delete
+/// it. Returns whether it was quarantined.
+#[cfg(not(feature = "fixtures"))]
+fn remove_item<R: Runtime>(app: &AppHandle<R>, path: &str, source: Option<String>) -> Result<bool, String> {
+    if let Some(source) = source.filter(|s| crate::quarantine::quarantines_by_default(s)) {
+        app.state::<QuarantineStore>().quarantine(path, None, &source, None)?;
+        return Ok(true);
+    }
+    let p = &paths::extended(Path::new(path));
+    let result = if p.is_dir() {
+        std::fs::remove_dir_all(p)
+    } else {
+        std::fs::remove_file(p)
+    };
+    result.map(|_| false).map_err(|e| e.to_string())
+}
+
+/// Local volumes, then configured SSH hosts
 #[command]
-pub fn get_drives() -> Vec<FileNode> {
+pub async fn get_drives(remotes: State<'_, RemoteStore>) -> Result<Vec<FileNode>, String> {
+    #[cfg(feature = "fixtures")]
+    return { let _ = remotes; Ok(crate::fixtures::drives()) };
+
+    // Reading APFS space details runs diskutil, so keep it off the main thread
+    #[cfg(not(feature = "fixtures"))]
+    {
+        let mut drives = tauri::async_runtime::spawn_blocking(local_drives).await.map_err(|e| e.to_string())?;
+        drives.extend(remotes.drives());
+        Ok(drives)
+    }
+}
+
+#[cfg(not(feature = "fixtures"))]
+fn local_drives() -> Vec<FileNode> {
     let mut drives = Vec::new();
     let disks = Disks::new_with_refreshed_list();
+    let space_reader = SpaceReader::load();
 
     for disk in &disks {
         let name = disk.name().to_string_lossy().to_string();
         let mount_point = disk.mount_point().to_string_lossy().to_string();
-        let total = disk.total_space();
-        let available = disk.available_space();
-        let used = total.saturating_sub(available);
+        // Used as Finder/Explorer count it: purgeable space is available
+        let volume = space_reader.read(
+            &mount_point,
+            &disk.file_system().to_string_lossy(),
+            disk.total_space(),
+            disk.available_space(),
+        );
+        let used = volume.used();
 
         let height_name = if name.is_empty() {
              if mount_point == "/" { 
@@ -288,30 +731,1057 @@ pub fn get_drives() -> Vec<FileNode> {
             children: None,
             last_modified,
             file_count: 0,
+            volume: Some(volume),
+            link: None,
+            skipped_links: None,
+            allocated_size: None,
+            extensions: None,
+            deferred: false,
         });
     }
     drives
 }
 
 #[command]
-pub async fn scan_junk() -> Result<Vec<JunkCategory>, String> {
+pub async fn scan_junk<R: Runtime>(app: AppHandle<R>, operations: State<'_, OperationManager>) -> Result<Vec<JunkCategory>, String> {
+    let operation = operations.start_uncancellable(&app, OperationKind::JunkScan, "junk");
+    #[cfg(target_os = "macos")]
+    if let Some(issue) = dirs::home_dir().and_then(|home| permissions::scan_issue(&home.join(".Trash").to_string_lossy())) {
+        let _ = app.emit(PERMISSION_EVENT, issue);
+    }
     // This could also be spawned blocking if it takes time
+    #[cfg(feature = "fixtures")]
+    let result: Result<_, String> = Ok(crate::fixtures::junk());
+    #[cfg(not(feature = "fixtures"))]
+    let ages = app.state::<JunkAgeStore>().settings();
+    #[cfg(not(feature = "fixtures"))]
     let result = tauri::async_runtime::spawn_blocking(move || {
-        cleaner::scan_junk_items()
-    }).await.map_err(|e| e.to_string())?;
-    
-    Ok(result)
+        let mut categories = cleaner::scan_junk_items(&ages);
+        categories.extend(game_libraries::unplayed_category(&game_libraries::find_games()));
+        categories
+    }).await.map_err(|e| e.to_string());
+    #[cfg(not(feature = "fixtures"))]
+    let result = match result {
+        Ok(mut categories) => {
+            let plugins = app.state::<JunkPluginStore>().list();
+            categories.extend(junk_plugins::discover(plugins).await);
+            Ok(categories)
+        }
+        Err(e) => Err(e),
+    };
+    operation.finish(&result);
+    app.state::<Telemetry>().feature_used("junk-scan");
+
+    result
+}
+
+/// Minimum age of junk items per category
+#[command]
+pub fn get_junk_age_settings(ages: State<'_, JunkAgeStore>) -> JunkAgeSettings {
+    ages.settings()
+}
+
+#[command]
+pub fn set_junk_age_settings(settings: JunkAgeSettings, ages: State<'_, JunkAgeStore>) -> Result<JunkAgeSettings, String> {
+    ages.set_settings(settings)
 }
 
+/// Delete junk items. Items a running program has open are skipped and
+/// listed in the result rather than failing the cleanup. With `safeguard`,
+/// cleaning a risky category first creates a restore point (Windows) and a
+/// manifest of the deleted files, which is returned.
 #[command]
-pub async fn clean_junk(paths: Vec<String>) -> Result<(), String> {
+pub async fn clean_junk<R: Runtime>(
+    app: AppHandle<R>,
+    paths: Vec<String>,
+    safeguard: Option<bool>,
+    operations: State<'_, OperationManager>,
+) -> Result<CleanResult, String> {
+    audit::ensure_writable()?;
+    #[cfg(not(feature = "fixtures"))]
+    let manifest_dir = crate::storage::app_data_dir(&app)?.join("cleanup_manifests");
+    let operation = operations.start(&app, OperationKind::Cleanup, format!("{} items", paths.len()));
+    #[cfg(feature = "fixtures")]
+    let result = {
+        let _ = safeguard;
+        crate::fixtures::delete(&paths).map(|_| CleanResult { record: None, skipped_in_use: Vec::new() })
+    };
+    #[cfg(not(feature = "fixtures"))]
+    let cancel = operation.cancel_flag();
+    #[cfg(not(feature = "fixtures"))]
     let result = tauri::async_runtime::spawn_blocking(move || {
-        cleaner::delete_junk_items(paths)
-    }).await.map_err(|e| e.to_string())??;
+        let skipped_in_use = open_handles::in_use(&paths);
+        let paths: Vec<String> = paths
+            .into_iter()
+            .filter(|p| !skipped_in_use.iter().any(|s| &s.path == p))
+            .collect();
+        let record = if safeguard == Some(true) && cleaner::touches_risky(&paths) {
+            Some(safeguard::before_clean(&paths, &manifest_dir)?)
+        } else {
+            None
+        };
+        game_libraries::forget(&paths);
+        cleaner::delete_junk_items(paths, &cancel).map(|_| CleanResult { record, skipped_in_use })
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
+    operation.finish(&result);
+    let result = result?;
+    app.state::<Telemetry>().feature_used("junk-clean");
     
     // Invalidate main scan cache just in case we deleted something overlapping
     clear_cache();
     
+    Ok(result)
+}
+
+/// Recent backend log entries for the diagnostics panel, newest first
+#[command]
+pub fn get_recent_logs(filter: Option<LogFilter>) -> Result<Vec<LogEntry>, String> {
+    logging::recent_logs(&filter.unwrap_or_default())
+}
+
+/// Set the language for backend-generated text (a BCP 47 tag such as "de-AT").
+/// Returns the catalog locale that was selected.
+#[command]
+pub fn set_locale(locale: String) -> Result<String, String> {
+    i18n::set_locale(&locale).map(String::from)
+}
+
+/// Locales with a message catalog
+#[command]
+pub fn get_available_locales() -> Vec<String> {
+    i18n::available_locales().into_iter().map(String::from).collect()
+}
+
+/// Long-running operations currently in progress, oldest first
+#[command]
+pub fn list_operations(operations: State<'_, OperationManager>) -> Vec<OperationInfo> {
+    operations.list()
+}
+
+/// Cancel a running operation. Returns false if it already finished or
+/// cannot be cancelled.
+#[command]
+pub fn cancel_operation(id: String, operations: State<'_, OperationManager>) -> bool {
+    operations.cancel(&id)
+}
+
+/// Operations interrupted by an app or system restart that can continue from
+/// their checkpoint, most recent first
+#[command]
+pub fn list_resumable_operations(checkpoints: State<'_, CheckpointStore>) -> Vec<ResumableOperation> {
+    checkpoints.resumable()
+}
+
+/// Continue an interrupted operation from its checkpoint
+#[command]
+pub async fn resume_operation(
+    app: AppHandle,
+    webview: Webview,
+    id: String,
+    operations: State<'_, OperationManager>,
+    checkpoints: State<'_, CheckpointStore>,
+) -> Result<FileNode, String> {
+    match checkpoints.target_of(&id) {
+        Some((OperationKind::Scan, path)) => scan_dir_internal(app, webview.label(), path, false, None, &operations).await,
+        Some((kind, _)) => Err(format!("{:?} operations can't be resumed", kind)),
+        None => Err(format!("No interrupted operation: {}", id)),
+    }
+}
+
+/// Drop the checkpoint of an interrupted operation instead of resuming it
+#[command]
+pub fn discard_checkpoint(id: String, checkpoints: State<'_, CheckpointStore>) -> Result<(), String> {
+    checkpoints.discard(&id)
+}
+
+/// Memory held by the scan cache, resident models and the size index, and
+/// by the whole process
+#[command]
+pub fn get_memory_report(index: State<'_, SizeIndex>) -> MemoryReport {
+    memory::report(index.memory_usage())
+}
+
+#[command]
+pub fn get_memory_settings(memory: State<'_, MemoryStore>) -> MemorySettings {
+    memory.settings()
+}
+
+/// Set the memory ceilings; caches over them are trimmed right away
+#[command]
+pub fn set_memory_settings(settings: MemorySettings, memory: State<'_, MemoryStore>) -> Result<MemorySettings, String> {
+    memory.set_settings(settings)
+}
+
+/// Check Helium's dependencies (Node/npx, HuggingFace, acceleration, disk
+/// space, folder access) so the UI can guide setup
+#[command]
+pub async fn get_environment_report(app: AppHandle) -> Result<EnvironmentReport, String> {
+    let data_dir = crate::storage::app_data_dir(&app)?;
+    Ok(environment::report(&data_dir, app.package_info().version.to_string()).await)
+}
+
+/// Whether Helium can read folders protected by macOS privacy settings
+#[command]
+pub fn get_permission_state() -> PermissionReport {
+    permissions::report()
+}
+
+/// Open the System Settings pane where access is granted (macOS)
+#[command]
+pub fn open_permission_settings(pane: SettingsPane) -> Result<(), String> {
+    permissions::open_settings(pane)
+}
+
+/// Check the release feed for a newer version of Helium
+#[command]
+pub async fn check_for_updates(app: AppHandle, state: State<'_, UpdateState>) -> Result<Option<UpdateInfo>, String> {
+    updater::check(&app, &state).await
+}
+
+/// Download, verify and install the update found by `check_for_updates`.
+/// It takes effect on the next launch, or right away with `restart`.
+#[command]
+pub async fn install_update(
+    app: AppHandle,
+    restart: bool,
+    state: State<'_, UpdateState>,
+    operations: State<'_, OperationManager>,
+) -> Result<(), String> {
+    updater::install(&app, &state, &operations).await?;
+    if restart {
+        app.restart();
+    }
     Ok(())
 }
 
+/// Everything telemetry has queued, exactly as it would be uploaded
+#[command]
+pub fn get_telemetry_state(telemetry: State<'_, Telemetry>) -> TelemetryState {
+    telemetry.state()
+}
+
+/// Opt in to or out of anonymous telemetry. Opting out deletes queued data.
+#[command]
+pub fn set_telemetry_enabled(enabled: bool, telemetry: State<'_, Telemetry>) -> TelemetryState {
+    telemetry.set_enabled(enabled);
+    telemetry.state()
+}
+
+/// Upload queued telemetry now. Returns false if there was nothing to send.
+#[command]
+pub async fn upload_telemetry(telemetry: State<'_, Telemetry>) -> Result<bool, String> {
+    telemetry.upload().await
+}
+
+#[command]
+pub fn list_profiles(profiles: State<'_, ProfileStore>) -> Vec<Profile> {
+    profiles.list()
+}
+
+#[command]
+pub fn get_active_profile(profiles: State<'_, ProfileStore>) -> Option<Profile> {
+    profiles.active()
+}
+
+/// Create a profile (empty ID) or update an existing one
+#[command]
+pub fn save_profile(profile: Profile, profiles: State<'_, ProfileStore>) -> Result<Profile, String> {
+    profiles.save_profile(profile)
+}
+
+#[command]
+pub fn delete_profile(id: String, profiles: State<'_, ProfileStore>) -> Result<bool, String> {
+    profiles.delete(&id)
+}
+
+/// Activate a profile (or none). Scans, the MCP server and inference pick up
+/// its settings right away.
+#[command]
+pub async fn switch_profile(
+    app: AppHandle,
+    id: Option<String>,
+    profiles: State<'_, ProfileStore>,
+    mcp_state: State<'_, NativeMCPState>,
+) -> Result<Option<Profile>, String> {
+    let profile = profiles.switch(id.as_deref())?;
+
+    // Cached trees were built with the previous profile's exclusions
+    clear_cache();
+    app.state::<SizeIndex>().set_exclusions(profiles.exclusions());
+    mcp_state.apply_profile(profile.as_ref()).await;
+
+    let _ = app.emit(PROFILE_EVENT, &profile);
+    Ok(profile)
+}
+
+#[command]
+pub fn get_alert_settings(alerts: State<'_, AlertMonitor>) -> AlertSettings {
+    alerts.settings()
+}
+
+#[command]
+pub fn set_alert_settings(settings: AlertSettings, alerts: State<'_, AlertMonitor>) -> Result<AlertSettings, String> {
+    alerts.set_settings(settings)
+}
+
+/// Tracked drives currently below the free space threshold
+#[command]
+pub fn check_drive_space(alerts: State<'_, AlertMonitor>, history: State<'_, ScanHistory>) -> Vec<DriveAlert> {
+    alerts.check(&history)
+}
+
+/// Sizes recorded by past scans of `path`, oldest first
+#[command]
+pub fn get_scan_history(path: String, history: State<'_, ScanHistory>) -> Vec<ScanRecord> {
+    history.records(&path)
+}
+
+#[command]
+pub async fn list_wsl_distros() -> Result<Vec<WslDistro>, String> {
+    wsl::list_distros().await
+}
+
+/// Per-folder usage inside a WSL distro (starts the distro if it is stopped)
+#[command]
+pub async fn get_wsl_usage(
+    app: AppHandle,
+    distro: String,
+    operations: State<'_, OperationManager>,
+) -> Result<WslUsage, String> {
+    let operation = operations.start(&app, OperationKind::Scan, format!("WSL: {}", distro));
+    let token = operation.token();
+    let result = tokio::select! {
+        result = wsl::usage(&distro) => result,
+        _ = token.cancelled() => Err("Operation cancelled".to_string()),
+    };
+    operation.finish(&result);
+    result
+}
+
+/// Shut WSL down and compact a distro's virtual disk to return freed space
+/// to Windows
+#[command]
+pub async fn compact_wsl_distro(
+    app: AppHandle,
+    distro: String,
+    operations: State<'_, OperationManager>,
+) -> Result<CompactResult, String> {
+    audit::ensure_writable()?;
+    let operation = operations.start_uncancellable(&app, OperationKind::Cleanup, format!("WSL: {}", distro));
+    let result = wsl::compact(&distro).await;
+    operation.finish(&result);
+    if result.is_ok() {
+        app.state::<Telemetry>().feature_used("wsl-compact");
+    }
+    result
+}
+
+/// Time Machine local snapshots (macOS) or System Restore / File History
+/// usage (Windows)
+#[command]
+pub async fn get_snapshot_report() -> Result<Vec<SnapshotCategory>, String> {
+    snapshots::report().await
+}
+
+#[command]
+pub async fn reclaim_snapshot_space(
+    app: AppHandle,
+    action: ReclaimAction,
+    operations: State<'_, OperationManager>,
+) -> Result<(), String> {
+    audit::ensure_writable()?;
+    let operation = operations.start_uncancellable(&app, OperationKind::Cleanup, "snapshots");
+    let result = snapshots::reclaim(&action).await;
+    operation.finish(&result);
+    if result.is_ok() {
+        app.state::<Telemetry>().feature_used("snapshot-reclaim");
+    }
+    result
+}
+
+/// Processes ranked by bytes written during a short sampling window
+#[command]
+pub async fn get_disk_writers(
+    window_ms: Option<u64>,
+    limit: Option<usize>,
+    monitor: State<'_, DiskActivityMonitor>,
+) -> Result<Vec<ProcessDiskActivity>, String> {
+    let window = window_ms.map(Duration::from_millis).unwrap_or(disk_activity::DEFAULT_WINDOW);
+    if window > Duration::from_secs(30) {
+        return Err("Sampling window cannot exceed 30 seconds".to_string());
+    }
+    Ok(monitor.sample(window, limit.unwrap_or(20)).await)
+}
+
+#[command]
+pub fn get_open_files(pid: u32) -> Result<Vec<String>, String> {
+    disk_activity::open_files(pid)
+}
+
+/// Write profiles, presets and settings (no secrets) to a portable file
+#[command]
+pub async fn export_config(app: AppHandle, path: String) -> Result<(), String> {
+    app_config::export(&app, Path::new(&path)).await
+}
+
+/// Merge a file written by `export_config` into the current setup
+#[command]
+pub async fn import_config(app: AppHandle, path: String) -> Result<ImportSummary, String> {
+    let summary = app_config::import(&app, Path::new(&path)).await?;
+    // The active profile may have changed, and with it the exclusions
+    clear_cache();
+    let profiles = app.state::<ProfileStore>();
+    app.state::<SizeIndex>().set_exclusions(profiles.exclusions());
+    let _ = app.emit(PROFILE_EVENT, profiles.active());
+    Ok(summary)
+}
+
+#[command]
+pub fn get_audit_mode() -> AuditSettings {
+    AuditSettings { enabled: audit::is_enabled() }
+}
+
+/// Switch the global read-only mode
+#[command]
+pub fn set_audit_mode(app: AppHandle, enabled: bool) -> Result<AuditSettings, String> {
+    let settings = audit::set_enabled(enabled)?;
+    let _ = app.emit(AUDIT_MODE_EVENT, &settings);
+    // A running agent learns that its modifying tools stopped (or started)
+    // working
+    notifications::publish(
+        notifications::NATIVE_SERVER,
+        notifications::TOOLS_LIST_CHANGED,
+        Some(serde_json::json!({ "auditMode": settings.enabled })),
+    );
+    Ok(settings)
+}
+
+#[command]
+pub fn list_index_roots(index: State<'_, SizeIndex>) -> Vec<IndexedRoot> {
+    index.roots()
+}
+
+/// Keep a directory's sizes continuously up to date
+#[command]
+pub fn add_index_root(path: String, index: State<'_, SizeIndex>) -> Result<IndexedRoot, String> {
+    index.add_root(&path)
+}
+
+#[command]
+pub fn remove_index_root(path: String, index: State<'_, SizeIndex>) -> Result<bool, String> {
+    index.remove_root(&path)
+}
+
+/// Directory sizes from the index, without scanning. None if `path` is not
+/// below an indexed root or its first walk is still running.
+#[command]
+pub fn get_indexed_tree(path: String, index: State<'_, SizeIndex>) -> Option<FileNode> {
+    index.tree(&path)
+}
+
+#[command]
+pub fn list_remote_hosts(remotes: State<'_, RemoteStore>) -> Vec<RemoteHost> {
+    remotes.list()
+}
+
+/// Add a host (empty ID) or update an existing one. Its drive entry scans
+/// over SSH using the user's keys.
+#[command]
+pub fn save_remote_host(host: RemoteHost, remotes: State<'_, RemoteStore>) -> Result<RemoteHost, String> {
+    remotes.save_host(host)
+}
+
+#[command]
+pub fn delete_remote_host(id: String, remotes: State<'_, RemoteStore>) -> Result<bool, String> {
+    remotes.delete(&id)
+}
+
+#[command]
+pub fn list_junk_plugins(plugins: State<'_, JunkPluginStore>) -> Vec<JunkPluginServer> {
+    plugins.list()
+}
+
+/// Register an MCP server (empty ID) or update one. Enabled servers are
+/// asked for junk candidates on every junk scan.
+#[command]
+pub fn save_junk_plugin(server: JunkPluginServer, plugins: State<'_, JunkPluginStore>) -> Result<JunkPluginServer, String> {
+    plugins.save_server(server)
+}
+
+#[command]
+pub fn delete_junk_plugin(id: String, plugins: State<'_, JunkPluginStore>) -> Result<bool, String> {
+    plugins.delete(&id)
+}
+
+#[command]
+pub fn list_storage_accounts(accounts: State<'_, ObjectStorageStore>) -> Vec<ObjectStorageAccount> {
+    accounts.list()
+}
+
+/// Add an account (empty ID) or update one. `secret_access_key` goes to the
+/// OS keychain; leave it out to keep the stored one.
+#[command]
+pub fn save_storage_account(
+    account: ObjectStorageAccount,
+    secret_access_key: Option<String>,
+    accounts: State<'_, ObjectStorageStore>,
+) -> Result<ObjectStorageAccount, String> {
+    accounts.save_account(account, secret_access_key)
+}
+
+#[command]
+pub fn delete_storage_account(id: String, accounts: State<'_, ObjectStorageStore>) -> Result<bool, String> {
+    accounts.delete(&id)
+}
+
+/// Size tree and storage class breakdown of a bucket, or of one prefix of it
+#[command]
+pub async fn analyze_bucket(
+    account_id: String,
+    bucket: Option<String>,
+    prefix: Option<String>,
+    accounts: State<'_, ObjectStorageStore>,
+) -> Result<BucketReport, String> {
+    let account = accounts.get(&account_id)?;
+    let bucket = bucket
+        .or_else(|| account.default_bucket.clone())
+        .ok_or("No bucket given and the account has no default bucket")?;
+    object_storage::analyze(&account, &bucket, prefix.as_deref().unwrap_or("")).await
+}
+
+/// Git repositories under `path` with their .git, untracked and ignored sizes
+#[command]
+pub async fn find_git_repos(
+    app: AppHandle,
+    path: String,
+    operations: State<'_, OperationManager>,
+) -> Result<Vec<GitRepo>, String> {
+    let operation = operations.start(&app, OperationKind::Scan, format!("git: {}", path));
+    let cancel = operation.cancel_flag();
+    let exclusions = app.state::<ProfileStore>().exclusions();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        git_repos::find_repos(&path, &exclusions, &cancel)
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
+    operation.finish(&result);
+    result
+}
+
+/// Storage of the sites and extensions in every browser profile found
+#[command]
+pub async fn analyze_browser_storage(
+    app: AppHandle,
+    operations: State<'_, OperationManager>,
+) -> Result<Vec<BrowserProfile>, String> {
+    let operation = operations.start_uncancellable(&app, OperationKind::Scan, "browser storage");
+    let result = tauri::async_runtime::spawn_blocking(browser_storage::analyze)
+        .await
+        .map_err(|e| e.to_string());
+    operation.finish(&result);
+    result
+}
+
+/// Delete site and extension storage entries the analysis reported as
+/// clearable, while their browser is closed. Returns the bytes freed.
+#[command]
+pub async fn clear_browser_storage(
+    app: AppHandle,
+    paths: Vec<String>,
+    operations: State<'_, OperationManager>,
+) -> Result<u64, String> {
+    audit::ensure_writable()?;
+    let operation = operations.start_uncancellable(&app, OperationKind::Cleanup, format!("{} browser storage items", paths.len()));
+    let result = tauri::async_runtime::spawn_blocking(move || browser_storage::clear(&paths))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+    operation.finish(&result);
+    clear_cache();
+    result
+}
+
+/// Installed Steam and Epic Games titles with their sizes, largest first
+#[command]
+pub async fn find_games(app: AppHandle, operations: State<'_, OperationManager>) -> Result<Vec<Game>, String> {
+    let operation = operations.start_uncancellable(&app, OperationKind::Scan, "games");
+    let result = tauri::async_runtime::spawn_blocking(game_libraries::find_games)
+        .await
+        .map_err(|e| e.to_string());
+    operation.finish(&result);
+    result
+}
+
+/// Outlook data files and mailboxes under `path`, or in the mail clients'
+/// usual locations when no path is given
+#[command]
+pub async fn find_mail_archives(
+    app: AppHandle,
+    path: Option<String>,
+    operations: State<'_, OperationManager>,
+) -> Result<Vec<MailArchive>, String> {
+    let label = format!("mail: {}", path.as_deref().unwrap_or("default locations"));
+    let operation = operations.start(&app, OperationKind::Scan, label);
+    let cancel = operation.cancel_flag();
+    let exclusions = app.state::<ProfileStore>().exclusions();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        mail_archives::find_archives(path.as_deref(), &exclusions, &cancel)
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
+    operation.finish(&result);
+    result
+}
+
+/// What a disk image holds, attached read-only while it is looked at
+#[command]
+pub async fn inspect_disk_image(
+    app: AppHandle,
+    path: String,
+    operations: State<'_, OperationManager>,
+) -> Result<ImageContents, String> {
+    let operation = operations.start(&app, OperationKind::Scan, format!("image: {}", path));
+    let cancel = operation.cancel_flag();
+    let result = tauri::async_runtime::spawn_blocking(move || disk_images::inspect(&path, &cancel))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+    operation.finish(&result);
+    result
+}
+
+/// Run `git gc` on a repository the user confirmed
+#[command]
+pub async fn gc_git_repo(
+    app: AppHandle,
+    path: String,
+    prune_now: bool,
+    operations: State<'_, OperationManager>,
+) -> Result<GcResult, String> {
+    audit::ensure_writable()?;
+    let operation = operations.start_uncancellable(&app, OperationKind::Cleanup, format!("git gc: {}", path));
+    let result = tauri::async_runtime::spawn_blocking(move || git_repos::gc(&path, prune_now))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+    operation.finish(&result);
+    if result.is_ok() {
+        app.state::<Telemetry>().feature_used("git-gc");
+        clear_cache();
+    }
+    result
+}
+
+/// Bursts of near-identical screenshots/photos under `path`. The proposed
+/// duplicates are removed with `clean_junk` once the user confirms.
+#[command]
+pub async fn find_photo_bursts(
+    app: AppHandle,
+    path: String,
+    options: Option<BurstOptions>,
+    operations: State<'_, OperationManager>,
+) -> Result<Vec<PhotoBurst>, String> {
+    let operation = operations.start(&app, OperationKind::JunkScan, format!("photos: {}", path));
+    let cancel = operation.cancel_flag();
+    let exclusions = app.state::<ProfileStore>().exclusions();
+    let options = options.unwrap_or_default();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        photo_bursts::find_bursts(&path, &options, &exclusions, &cancel)
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
+    operation.finish(&result);
+    if result.is_ok() {
+        app.state::<Telemetry>().feature_used("photo-bursts");
+    }
+    result
+}
+
+/// Bytes of `path` and its subfolders bucketed by last-modified year or
+/// quarter, for the cold-data overlay
+#[command]
+pub async fn get_age_heatmap(
+    app: AppHandle,
+    path: String,
+    granularity: Option<AgeGranularity>,
+    operations: State<'_, OperationManager>,
+) -> Result<AgeHeatmap, String> {
+    let operation = operations.start(&app, OperationKind::Scan, format!("age: {}", path));
+    let cancel = operation.cancel_flag();
+    let exclusions = app.state::<ProfileStore>().exclusions();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        age_heatmap::heatmap(&path, granularity.unwrap_or_default(), &exclusions, &cancel)
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
+    operation.finish(&result);
+    result
+}
+
+/// Files under `path` neither modified nor accessed for a number of days,
+/// grouped by folder
+#[command]
+pub async fn find_stale_files(
+    app: AppHandle,
+    path: String,
+    options: Option<StaleOptions>,
+    operations: State<'_, OperationManager>,
+) -> Result<StaleReport, String> {
+    let operation = operations.start(&app, OperationKind::Scan, format!("stale: {}", path));
+    let cancel = operation.cancel_flag();
+    let exclusions = app.state::<ProfileStore>().exclusions();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        stale_files::find_stale(&path, options.unwrap_or_default(), &exclusions, &cancel)
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
+    operation.finish(&result);
+    result
+}
+
+#[command]
+pub fn get_basket(basket: State<'_, BasketStore>) -> BasketSummary {
+    basket.summary()
+}
+
+/// Stage paths for deletion. `source` names the view they came from.
+#[command]
+pub async fn add_to_basket(
+    app: AppHandle,
+    paths: Vec<String>,
+    source: String,
+    reason: Option<String>,
+) -> Result<BasketSummary, String> {
+    let handle = app.clone();
+    // Staged folders are measured, which can take a while
+    let summary = tauri::async_runtime::spawn_blocking(move || {
+        handle.state::<BasketStore>().add(paths, &source, reason)
+    }).await.map_err(|e| e.to_string())??;
+    let _ = app.emit(BASKET_EVENT, &summary);
+    Ok(summary)
+}
+
+#[command]
+pub fn remove_from_basket(app: AppHandle, paths: Vec<String>, basket: State<'_, BasketStore>) -> Result<BasketSummary, String> {
+    let summary = basket.remove(&paths)?;
+    let _ = app.emit(BASKET_EVENT, &summary);
+    Ok(summary)
+}
+
+#[command]
+pub fn clear_basket(app: AppHandle, basket: State<'_, BasketStore>) -> Result<BasketSummary, String> {
+    let summary = basket.clear()?;
+    let _ = app.emit(BASKET_EVENT, &summary);
+    Ok(summary)
+}
+
+/// Delete everything in the basket as reviewed at `revision`. `quarantine`
+/// quarantines all items (true) or none (false) instead of only the agent's.
+#[command]
+pub async fn commit_basket(
+    app: AppHandle,
+    revision: u64,
+    quarantine: Option<bool>,
+    operations: State<'_, OperationManager>,
+) -> Result<BasketCommitResult, String> {
+    audit::ensure_writable()?;
+    let operation = operations.start(&app, OperationKind::Deletion, "basket");
+    let cancel = operation.cancel_flag();
+    let handle = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        handle
+            .state::<BasketStore>()
+            .commit(revision, quarantine, &handle.state::<QuarantineStore>(), &cancel)
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
+    operation.finish(&result);
+    let result = result?;
+    app.state::<Telemetry>().feature_used("basket-commit");
+    clear_cache();
+    let _ = app.emit(BASKET_EVENT, &app.state::<BasketStore>().summary());
+    if !result.quarantined.is_empty() {
+        let _ = app.emit(QUARANTINE_EVENT, &app.state::<QuarantineStore>().summary());
+    }
+    Ok(result)
+}
+
+#[command]
+pub fn get_quarantine(quarantine: State<'_, QuarantineStore>) -> QuarantineSummary {
+    quarantine.summary()
+}
+
+/// Move items into quarantine instead of deleting them
+#[command]
+pub async fn quarantine_items(
+    app: AppHandle,
+    paths: Vec<String>,
+    source: String,
+    reason: Option<String>,
+    operations: State<'_, OperationManager>,
+) -> Result<QuarantineSummary, String> {
+    audit::ensure_writable()?;
+    let operation = operations.start_uncancellable(&app, OperationKind::Deletion, format!("quarantine: {} items", paths.len()));
+    let handle = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let store = handle.state::<QuarantineStore>();
+        for path in &paths {
+            store.quarantine(path, None, &source, reason.clone())?;
+        }
+        Ok(store.summary())
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
+    operation.finish(&result);
+    clear_cache();
+    let _ = app.emit(QUARANTINE_EVENT, &app.state::<QuarantineStore>().summary());
+    result
+}
+
+/// Move quarantined items back to their original paths
+#[command]
+pub async fn restore_quarantined(app: AppHandle, ids: Vec<String>) -> Result<RestoreResult, String> {
+    let handle = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        handle.state::<QuarantineStore>().restore(&ids)
+    }).await.map_err(|e| e.to_string())??;
+    clear_cache();
+    let _ = app.emit(QUARANTINE_EVENT, &app.state::<QuarantineStore>().summary());
+    Ok(result)
+}
+
+/// Delete quarantined items now, before their retention ends
+#[command]
+pub async fn purge_quarantined(app: AppHandle, ids: Vec<String>) -> Result<PurgeResult, String> {
+    let handle = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        handle.state::<QuarantineStore>().purge(&ids)
+    }).await.map_err(|e| e.to_string())??;
+    let _ = app.emit(QUARANTINE_EVENT, &app.state::<QuarantineStore>().summary());
+    Ok(result)
+}
+
+/// Days quarantined items are kept; applies to items quarantined from now on
+#[command]
+pub fn set_quarantine_retention(days: u32, quarantine: State<'_, QuarantineStore>) -> Result<QuarantineSummary, String> {
+    quarantine.set_retention(days)
+}
+
+#[command]
+pub fn get_archive(archive: State<'_, ArchiveStore>) -> ArchiveSummary {
+    archive.summary()
+}
+
+#[command]
+pub fn save_archive_destination(
+    destination: ArchiveDestination,
+    archive: State<'_, ArchiveStore>,
+    remotes: State<'_, RemoteStore>,
+) -> Result<ArchiveDestination, String> {
+    archive.save_destination(destination, &remotes)
+}
+
+#[command]
+pub fn delete_archive_destination(id: String, archive: State<'_, ArchiveStore>) -> Result<bool, String> {
+    archive.delete_destination(&id)
+}
+
+/// Move big, rarely used files to an archive destination, leaving a stub
+/// in place of each. Files that fail stay where they are.
+#[command]
+pub async fn archive_files(
+    app: AppHandle,
+    paths: Vec<String>,
+    destination_id: String,
+    operations: State<'_, OperationManager>,
+) -> Result<ArchiveResult, String> {
+    audit::ensure_writable()?;
+    let operation = operations.start(&app, OperationKind::Archive, format!("archive: {} files", paths.len()));
+    let handle = app.clone();
+    let op = operation.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let store = handle.state::<ArchiveStore>();
+        let remotes = handle.state::<RemoteStore>();
+        let mut result = ArchiveResult::default();
+        for (i, path) in paths.iter().enumerate() {
+            if op.is_cancelled() {
+                return Err("Cancelled".to_string());
+            }
+            op.progress(Some(i as f32 / paths.len() as f32), Some(path.clone()));
+            match store.archive(path, &destination_id, &remotes) {
+                Ok(entry) => result.archived.push(entry),
+                Err(error) => result.failed.push(ArchiveFailure { path: path.clone(), error }),
+            }
+        }
+        Ok(result)
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
+    operation.finish(&result);
+    clear_cache();
+    let _ = app.emit(ARCHIVE_EVENT, &app.state::<ArchiveStore>().summary());
+    result
+}
+
+/// Copy archived files back to their original paths and remove their stubs
+#[command]
+pub async fn restore_archived(
+    app: AppHandle,
+    ids: Vec<String>,
+    operations: State<'_, OperationManager>,
+) -> Result<ArchiveRestoreResult, String> {
+    audit::ensure_writable()?;
+    let operation = operations.start(&app, OperationKind::Archive, format!("restore: {} files", ids.len()));
+    let handle = app.clone();
+    let op = operation.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let store = handle.state::<ArchiveStore>();
+        let remotes = handle.state::<RemoteStore>();
+        let mut result = ArchiveRestoreResult::default();
+        for (i, id) in ids.iter().enumerate() {
+            if op.is_cancelled() {
+                return Err("Cancelled".to_string());
+            }
+            op.progress(Some(i as f32 / ids.len() as f32), None);
+            match store.restore(id, &remotes) {
+                Ok(path) => result.restored.push(path),
+                Err(failure) => result.failed.push(failure),
+            }
+        }
+        Ok(result)
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
+    operation.finish(&result);
+    clear_cache();
+    let _ = app.emit(ARCHIVE_EVENT, &app.state::<ArchiveStore>().summary());
+    result
+}
+
+/// Apply a batch of moves, renames, new folders and deletions (to the trash)
+/// as one transaction: if any step fails or the operation is cancelled, the
+/// steps already applied are undone. Invalid batches are refused up front.
+#[command]
+pub async fn run_file_transaction(
+    app: AppHandle,
+    steps: Vec<TransactionStep>,
+    operations: State<'_, OperationManager>,
+) -> Result<TransactionResult, String> {
+    audit::ensure_writable()?;
+    let journal_dir = crate::storage::app_data_dir(&app)?.join("transactions");
+    let operation = operations.start(&app, OperationKind::Transaction, format!("{} steps", steps.len()));
+    let cancel = operation.cancel_flag();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        transactions::run(steps, &journal_dir, &cancel)
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
+    operation.finish(&result);
+    let result = result?;
+    if result.committed {
+        app.state::<Telemetry>().feature_used("file-transaction");
+        clear_cache();
+    }
+    Ok(result)
+}
+
+/// Forecast how much space deleting `paths` would actually free, accounting
+/// for hard links, cloud placeholders, overlapping selections and the trash.
+#[command]
+pub async fn forecast_reclaim(
+    app: AppHandle,
+    paths: Vec<String>,
+    operations: State<'_, OperationManager>,
+) -> Result<ReclaimForecast, String> {
+    let operation = operations.start(&app, OperationKind::Scan, format!("forecast: {} items", paths.len()));
+    let cancel = operation.cancel_flag();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        reclaim::forecast(&paths, &cancel)
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
+    operation.finish(&result);
+    result
+}
+
+/// Entries only in `a`, only in `b`, and in both but different, e.g. to
+/// check that a backup is complete
+#[command]
+pub async fn compare_directories(
+    app: AppHandle,
+    a: String,
+    b: String,
+    max_depth: Option<usize>,
+    mode: Option<CompareMode>,
+    operations: State<'_, OperationManager>,
+) -> Result<DirectoryComparison, String> {
+    let operation = operations.start(&app, OperationKind::Scan, format!("compare: {} / {}", a, b));
+    let cancel = operation.cancel_flag();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        dir_compare::compare(&a, &b, max_depth, mode.unwrap_or_default(), &cancel)
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
+    operation.finish(&result);
+    result
+}
+
+/// Backup targets (Time Machine, File History, Borg, restic, rsnapshot)
+/// with when each was last updated
+#[command]
+pub async fn detect_backups() -> Result<Vec<BackupTarget>, String> {
+    tauri::async_runtime::spawn_blocking(backups::detect)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Warn before deleting `paths` when no recent backup exists
+#[command]
+pub async fn check_backups(paths: Vec<String>) -> Result<BackupCheck, String> {
+    tauri::async_runtime::spawn_blocking(move || backups::check(&paths))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Securely delete files and folders: overwrite, verify, remove, and append
+/// the report to the audit log
+#[command]
+pub async fn shred_items(
+    app: AppHandle,
+    paths: Vec<String>,
+    passes: Option<u32>,
+    operations: State<'_, OperationManager>,
+) -> Result<ShredReport, String> {
+    audit::ensure_writable()?;
+    let operation = operations.start(&app, OperationKind::Deletion, format!("shred: {} items", paths.len()));
+    let cancel = operation.cancel_flag();
+    let progress = operation.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        shred::shred(&paths, passes.unwrap_or(shred::DEFAULT_PASSES), &cancel, |fraction, file| {
+            progress.progress(Some(fraction), Some(file.to_string()));
+        })
+    }).await.map_err(|e| e.to_string()).and_then(|r| r);
+    operation.finish(&result);
+    clear_cache();
+    result
+}
+
+/// Entries of the audit log, oldest first
+#[command]
+pub fn get_audit_log() -> Result<Vec<AuditLogEntry>, String> {
+    audit::read_log()
+}
+
+/// Check that no audit log entry was altered, removed or reordered
+#[command]
+pub fn verify_audit_log() -> Result<AuditLogCheck, String> {
+    audit::verify_log()
+}
+
+/// Storage type of the volume holding `path` and the scan strategy used for it
+#[command]
+pub async fn get_scan_strategy(path: String, strategies: State<'_, ScanStrategyStore>) -> Result<VolumeStrategy, String> {
+    Ok(strategies.resolve(Path::new(&path)))
+}
+
+#[command]
+pub fn list_scan_overrides(strategies: State<'_, ScanStrategyStore>) -> Vec<VolumeOverride> {
+    strategies.overrides()
+}
+
+/// Override the detected storage type or strategy of a mount point
+#[command]
+pub fn save_scan_override(entry: VolumeOverride, strategies: State<'_, ScanStrategyStore>) -> Result<(), String> {
+    strategies.save_override(entry)
+}
+
+#[command]
+pub fn delete_scan_override(mount_point: String, strategies: State<'_, ScanStrategyStore>) -> Result<bool, String> {
+    strategies.delete_override(&mount_point)
+}
+
+/// Detected type of a file, with the start of it for text and the image
+/// itself for small images
+#[command]
+pub async fn preview_file(path: String) -> Result<FilePreview, String> {
+    tauri::async_runtime::spawn_blocking(move || content_type::preview(Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r)
+}
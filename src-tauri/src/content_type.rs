@@ -0,0 +1,274 @@
+// Content Type - What a file actually is
+//
+// Extensions lie: a ".dat" may be a SQLite database, a download without an
+// extension may be a ZIP, a ".txt" may hold binary garbage. The first bytes of
+// a file identify most formats (via `infer`), and the absence of NUL bytes and
+// invalid UTF-8 identifies text. The scanner's categories, the MCP read tools
+// (text or binary) and the preview command all go through here, so they agree
+// on what a file is. The extension is the fallback when the bytes say
+// nothing, and is used on its own where opening every file would cost too
+// much.
+
+use base64::Engine;
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+
+/// Bytes read to identify a file
+const SNIFF_BYTES: usize = 8192;
+
+/// Text shown by a preview, and the largest image inlined in one
+const PREVIEW_TEXT_BYTES: u64 = 64 * 1024;
+const PREVIEW_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    Text,
+    Image,
+    Video,
+    Audio,
+    Document,
+    Archive,
+    Executable,
+    Binary,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentType {
+    pub mime: String,
+    pub kind: ContentKind,
+    /// Usual extension of the detected format, when it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extension: Option<String>,
+    /// "magic" (file signature), "text" (decodes as text) or "extension"
+    pub detected_by: &'static str,
+}
+
+impl ContentType {
+    pub fn is_text(&self) -> bool {
+        self.kind == ContentKind::Text
+    }
+}
+
+/// What the preview pane shows for a file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilePreview {
+    pub path: String,
+    pub size: u64,
+    pub content_type: ContentType,
+    /// Start of a text file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// The text stops before the end of the file
+    pub truncated: bool,
+    /// Data URL of an image small enough to inline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_data: Option<String>,
+}
+
+pub fn lowercase_extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Whether a file head looks like text: no NUL bytes, and valid UTF-8 apart
+/// from a character cut off at the end of the sample
+pub fn looks_like_text(head: &[u8]) -> bool {
+    if head.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none() && head.len() - e.valid_up_to() < 4,
+    }
+}
+
+fn kind_of(matcher: infer::MatcherType) -> ContentKind {
+    use infer::MatcherType;
+    match matcher {
+        MatcherType::Image => ContentKind::Image,
+        MatcherType::Video => ContentKind::Video,
+        MatcherType::Audio => ContentKind::Audio,
+        MatcherType::Doc | MatcherType::Book | MatcherType::Font => ContentKind::Document,
+        MatcherType::Archive => ContentKind::Archive,
+        MatcherType::App => ContentKind::Executable,
+        MatcherType::Text => ContentKind::Text,
+        MatcherType::Custom => ContentKind::Binary,
+    }
+}
+
+/// Type from the extension alone
+pub fn from_extension(path: &Path) -> ContentType {
+    let extension = lowercase_extension(path);
+    let (mime, kind) = match extension.as_str() {
+        "png" => ("image/png", ContentKind::Image),
+        "jpg" | "jpeg" => ("image/jpeg", ContentKind::Image),
+        "gif" => ("image/gif", ContentKind::Image),
+        "webp" => ("image/webp", ContentKind::Image),
+        "bmp" => ("image/bmp", ContentKind::Image),
+        "svg" => ("image/svg+xml", ContentKind::Image),
+        "heic" | "tiff" | "raw" | "cr2" | "nef" => ("image/x-unknown", ContentKind::Image),
+        "mp4" | "m4v" => ("video/mp4", ContentKind::Video),
+        "mov" => ("video/quicktime", ContentKind::Video),
+        "mkv" | "avi" | "webm" | "wmv" => ("video/x-unknown", ContentKind::Video),
+        "mp3" => ("audio/mpeg", ContentKind::Audio),
+        "wav" | "flac" | "aac" | "m4a" | "ogg" => ("audio/x-unknown", ContentKind::Audio),
+        "pdf" => ("application/pdf", ContentKind::Document),
+        "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "rtf" | "odt" | "pages" | "key" | "numbers" => {
+            ("application/octet-stream", ContentKind::Document)
+        }
+        "zip" => ("application/zip", ContentKind::Archive),
+        "tar" | "gz" | "tgz" | "bz2" | "xz" | "7z" | "rar" | "zst" => ("application/octet-stream", ContentKind::Archive),
+        "exe" | "msi" | "dmg" | "pkg" | "deb" | "rpm" | "appimage" | "app" => {
+            ("application/octet-stream", ContentKind::Executable)
+        }
+        "txt" | "md" | "log" | "csv" | "rs" | "js" | "ts" | "tsx" | "jsx" | "py" | "go" | "java" | "c" | "cpp" | "h"
+        | "swift" | "kt" | "rb" | "toml" | "yaml" | "yml" | "css" | "sh" | "ini" | "cfg" | "conf" => {
+            ("text/plain", ContentKind::Text)
+        }
+        "json" => ("application/json", ContentKind::Text),
+        "html" | "htm" => ("text/html", ContentKind::Text),
+        "xml" => ("application/xml", ContentKind::Text),
+        _ => ("application/octet-stream", ContentKind::Binary),
+    };
+    ContentType {
+        mime: mime.to_string(),
+        kind,
+        extension: (!extension.is_empty()).then_some(extension),
+        detected_by: "extension",
+    }
+}
+
+/// Type of a file from its first bytes, falling back to the extension
+pub fn detect_bytes(head: &[u8], path: &Path) -> ContentType {
+    if let Some(found) = infer::get(head) {
+        return ContentType {
+            mime: found.mime_type().to_string(),
+            kind: kind_of(found.matcher_type()),
+            extension: Some(found.extension().to_string()),
+            detected_by: "magic",
+        };
+    }
+    let by_extension = from_extension(path);
+    if head.is_empty() {
+        return by_extension;
+    }
+    if looks_like_text(head) {
+        // Keep a more specific text type (JSON, HTML) from the extension
+        let mime = if by_extension.is_text() { by_extension.mime } else { "text/plain".to_string() };
+        return ContentType {
+            mime,
+            kind: ContentKind::Text,
+            extension: by_extension.extension,
+            detected_by: "text",
+        };
+    }
+    // Binary content under a text extension is binary
+    if by_extension.is_text() {
+        return ContentType {
+            mime: "application/octet-stream".to_string(),
+            kind: ContentKind::Binary,
+            extension: by_extension.extension,
+            detected_by: "magic",
+        };
+    }
+    by_extension
+}
+
+/// First bytes of a file
+pub fn read_head(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    std::fs::File::open(crate::paths::extended(path))?
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut head)?;
+    Ok(head)
+}
+
+/// Type of a file, reading its first bytes
+pub fn detect(path: &Path) -> std::io::Result<ContentType> {
+    Ok(detect_bytes(&read_head(path)?, path))
+}
+
+/// Scanner category of a lowercase extension
+pub fn category_of_extension(extension: &str) -> &'static str {
+    match extension {
+        "jpg" | "jpeg" | "png" | "gif" | "heic" | "webp" | "tiff" | "bmp" | "raw" | "cr2" | "nef" | "svg" => "images",
+        "mp4" | "mov" | "mkv" | "avi" | "webm" | "m4v" | "wmv" => "video",
+        "mp3" | "wav" | "flac" | "aac" | "m4a" | "ogg" => "audio",
+        "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "txt" | "md" | "rtf" | "odt" | "pages" | "key" | "numbers" => "documents",
+        "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "7z" | "rar" | "zst" => "archives",
+        "dmg" | "iso" | "img" | "vhd" | "vhdx" | "vmdk" | "qcow2" | "pkg" | "msi" | "exe" | "app" | "deb" | "rpm" | "appimage" => "installers",
+        "rs" | "js" | "ts" | "tsx" | "jsx" | "py" | "go" | "java" | "c" | "cpp" | "h" | "swift" | "kt" | "rb" | "json" | "toml" | "yaml" | "yml" | "html" | "css" => "code",
+        "log" | "tmp" | "cache" | "bak" => "temporary",
+        "safetensors" | "gguf" | "bin" | "pt" | "onnx" | "ckpt" => "models",
+        _ => "other",
+    }
+}
+
+/// Scanner category of a detected type
+fn category_of_kind(kind: ContentKind) -> &'static str {
+    match kind {
+        ContentKind::Image => "images",
+        ContentKind::Video => "video",
+        ContentKind::Audio => "audio",
+        ContentKind::Document | ContentKind::Text => "documents",
+        ContentKind::Archive => "archives",
+        ContentKind::Executable => "installers",
+        ContentKind::Binary => "other",
+    }
+}
+
+/// Scanner category of a file ("images", "archives", ...). Known extensions
+/// decide on their own, as walks visit far too many files to open each one;
+/// files with an unknown or missing extension are identified by content.
+pub fn category(path: &Path) -> &'static str {
+    match category_of_extension(&lowercase_extension(path)) {
+        "other" => read_head(path)
+            .map(|head| category_of_kind(detect_bytes(&head, path).kind))
+            .unwrap_or("other"),
+        category => category,
+    }
+}
+
+/// Preview of a file: its detected type, plus the start of it if it's text
+/// or the image itself if it's a small enough image
+pub fn preview(path: &Path) -> Result<FilePreview, String> {
+    let file_path = crate::paths::extended(path);
+    let meta = std::fs::metadata(&file_path).map_err(|e| e.to_string())?;
+    if meta.is_dir() {
+        return Err(format!("{} is a directory", crate::paths::display(path)));
+    }
+    let content_type = detect(path).map_err(|e| e.to_string())?;
+    let mut preview = FilePreview {
+        path: crate::paths::display(path),
+        size: meta.len(),
+        content_type,
+        text: None,
+        truncated: false,
+        image_data: None,
+    };
+    match preview.content_type.kind {
+        ContentKind::Text => {
+            let mut bytes = Vec::new();
+            std::fs::File::open(&file_path)
+                .and_then(|f| f.take(PREVIEW_TEXT_BYTES).read_to_end(&mut bytes))
+                .map_err(|e| e.to_string())?;
+            preview.truncated = meta.len() > PREVIEW_TEXT_BYTES;
+            preview.text = Some(String::from_utf8_lossy(&bytes).into_owned());
+        }
+        ContentKind::Image if meta.len() <= PREVIEW_IMAGE_BYTES && preview.content_type.detected_by == "magic" => {
+            let bytes = std::fs::read(&file_path).map_err(|e| e.to_string())?;
+            preview.image_data = Some(format!(
+                "data:{};base64,{}",
+                preview.content_type.mime,
+                base64::engine::general_purpose::STANDARD.encode(bytes)
+            ));
+        }
+        _ => {}
+    }
+    Ok(preview)
+}
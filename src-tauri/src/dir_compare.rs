@@ -0,0 +1,218 @@
+// Directory Compare - What differs between two trees
+//
+// Compares two directories entry by entry, by relative path: what exists only
+// in A, only in B, and what exists in both but differs. "Differs" depends on
+// the mode: `name` only checks presence, `size` also compares file sizes and
+// `hash` also compares the content of files of equal size. Answers "is my
+// backup complete?" for the user (`compare_directories` command) and the AI
+// (MCP tool of the same name). A folder missing on one side is reported once
+// with its total size rather than file by file, and the lists are capped so a
+// badly diverged pair doesn't produce an unreadable result.
+
+use crate::paths;
+use crate::safeguard;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use walkdir::WalkDir;
+
+/// Entries listed per list at most
+const MAX_LISTED: usize = 500;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompareMode {
+    /// Presence only
+    Name,
+    /// Presence and file size
+    #[default]
+    Size,
+    /// Presence, size, and SHA-256 of files with equal sizes
+    Hash,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparedEntry {
+    /// Relative to the compared directories, with `/` separators
+    pub path: String,
+    pub is_dir: bool,
+    /// Total size of a folder's files
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryDifference {
+    pub path: String,
+    /// "size", "content" or "type" (a file on one side, a folder on the other)
+    pub reason: String,
+    pub size_a: u64,
+    pub size_b: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryComparison {
+    pub a: String,
+    pub b: String,
+    pub mode: CompareMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+    pub only_in_a: Vec<ComparedEntry>,
+    pub only_in_b: Vec<ComparedEntry>,
+    pub different: Vec<EntryDifference>,
+    /// Files present and equal on both sides
+    pub identical_files: u64,
+    pub only_in_a_bytes: u64,
+    pub only_in_b_bytes: u64,
+    /// Some list was cut at its limit; the byte totals still cover everything
+    pub truncated: bool,
+    /// B holds everything A has, unchanged
+    pub b_contains_a: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Item {
+    is_dir: bool,
+    size: u64,
+}
+
+fn relative_key(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn list(root: &Path, max_depth: Option<usize>, cancel: &AtomicBool) -> Result<BTreeMap<String, Item>, String> {
+    let mut walker = WalkDir::new(root).min_depth(1);
+    if let Some(depth) = max_depth {
+        walker = walker.max_depth(depth);
+    }
+    let mut items = BTreeMap::new();
+    for entry in walker.into_iter().filter_map(|e| e.ok()) {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".to_string());
+        }
+        let Some(key) = relative_key(root, entry.path()) else {
+            continue;
+        };
+        let is_dir = entry.file_type().is_dir();
+        let size = if is_dir { 0 } else { entry.metadata().map(|m| m.len()).unwrap_or(0) };
+        items.insert(key, Item { is_dir, size });
+    }
+    Ok(items)
+}
+
+/// Entries of `side` missing from `other`, with a missing folder's content
+/// folded into the folder
+fn missing(side: &BTreeMap<String, Item>, other: &BTreeMap<String, Item>) -> (Vec<ComparedEntry>, u64) {
+    let mut entries: Vec<ComparedEntry> = Vec::new();
+    // Missing folders by key, with their index in `entries`
+    let mut folders: HashMap<&str, usize> = HashMap::new();
+    let mut bytes = 0;
+    // Keys are sorted, so a folder comes before its content
+    for (key, item) in side {
+        let folder = key
+            .match_indices('/')
+            .find_map(|(i, _)| folders.get(&key[..i]).copied());
+        if let Some(index) = folder {
+            bytes += item.size;
+            entries[index].size += item.size;
+            continue;
+        }
+        if other.contains_key(key) {
+            continue;
+        }
+        if item.is_dir {
+            folders.insert(key, entries.len());
+        }
+        bytes += item.size;
+        entries.push(ComparedEntry {
+            path: key.clone(),
+            is_dir: item.is_dir,
+            size: item.size,
+        });
+    }
+    (entries, bytes)
+}
+
+/// Compare directory `a` with directory `b`
+pub fn compare(
+    a: &str,
+    b: &str,
+    max_depth: Option<usize>,
+    mode: CompareMode,
+    cancel: &AtomicBool,
+) -> Result<DirectoryComparison, String> {
+    let (root_a, root_b) = (paths::extended(Path::new(a)), paths::extended(Path::new(b)));
+    for (path, root) in [(a, &root_a), (b, &root_b)] {
+        if !root.is_dir() {
+            return Err(format!("{}: directory does not exist", path));
+        }
+    }
+    let items_a = list(&root_a, max_depth, cancel)?;
+    let items_b = list(&root_b, max_depth, cancel)?;
+
+    let (mut only_in_a, only_in_a_bytes) = missing(&items_a, &items_b);
+    let (mut only_in_b, only_in_b_bytes) = missing(&items_b, &items_a);
+    let mut different = Vec::new();
+    let mut identical_files = 0;
+
+    for (key, item_a) in &items_a {
+        let Some(item_b) = items_b.get(key) else {
+            continue;
+        };
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".to_string());
+        }
+        let reason = if item_a.is_dir != item_b.is_dir {
+            Some("type")
+        } else if item_a.is_dir {
+            None
+        } else if mode != CompareMode::Name && item_a.size != item_b.size {
+            Some("size")
+        } else if mode == CompareMode::Hash
+            && safeguard::sha256_file(&root_a.join(key)) != safeguard::sha256_file(&root_b.join(key))
+        {
+            Some("content")
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => different.push(EntryDifference {
+                path: key.clone(),
+                reason: reason.to_string(),
+                size_a: item_a.size,
+                size_b: item_b.size,
+            }),
+            None if !item_a.is_dir => identical_files += 1,
+            None => {}
+        }
+    }
+
+    let b_contains_a = only_in_a.is_empty() && different.is_empty();
+    let truncated = only_in_a.len() > MAX_LISTED || only_in_b.len() > MAX_LISTED || different.len() > MAX_LISTED;
+    only_in_a.truncate(MAX_LISTED);
+    only_in_b.truncate(MAX_LISTED);
+    different.truncate(MAX_LISTED);
+
+    Ok(DirectoryComparison {
+        a: a.to_string(),
+        b: b.to_string(),
+        mode,
+        max_depth,
+        only_in_a,
+        only_in_b,
+        different,
+        identical_files,
+        only_in_a_bytes,
+        only_in_b_bytes,
+        truncated,
+        b_contains_a,
+    })
+}
@@ -0,0 +1,402 @@
+// Disk Images - What's inside a .dmg, .iso or .wim before deleting it
+//
+// Installers and backups leave multi-gigabyte disk images behind, and their
+// names rarely say whether anything in them still matters. An image is
+// attached read-only with the tools the OS ships (hdiutil on macOS,
+// Mount-DiskImage on Windows, udisksctl on Linux), its volumes are walked,
+// and it is detached again however the walk ends. Formats the OS can't
+// attach (.wim everywhere, most images on Linux without udisks) are listed
+// with 7-Zip instead when it is installed. Nothing in the image is opened
+// for writing either way.
+
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+/// Extensions of the disk images that can be inspected
+pub const IMAGE_EXTENSIONS: &[&str] = &["dmg", "sparseimage", "iso", "img", "wim", "esd", "vhd", "vhdx"];
+
+/// Largest files reported per image
+const TOP_FILES: usize = 20;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InspectMethod {
+    /// Attached read-only and walked
+    Mounted,
+    /// Listed by 7-Zip without attaching
+    Listed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageEntry {
+    /// Path inside the image, separated by `/`
+    pub path: String,
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageContents {
+    pub path: String,
+    /// Lowercase extension, e.g. "iso"
+    pub format: String,
+    /// Size of the image file
+    pub image_size: u64,
+    pub method: InspectMethod,
+    pub file_count: u64,
+    pub folder_count: u64,
+    /// Bytes of the files inside, before any compression of the image
+    pub content_size: u64,
+    /// Top-level entries, folders with the size of everything below them
+    pub top_level: Vec<ImageEntry>,
+    /// Largest files, biggest first
+    pub largest_files: Vec<ImageEntry>,
+}
+
+pub fn is_disk_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Totals gathered from either a walk or a listing
+#[derive(Default)]
+struct Tally {
+    file_count: u64,
+    folder_count: u64,
+    content_size: u64,
+    top_level: HashMap<String, ImageEntry>,
+    largest: BinaryHeap<Reverse<(u64, String)>>,
+}
+
+impl Tally {
+    /// Count an entry at `path` (inside the image, `/`-separated)
+    fn add(&mut self, path: &str, size: u64, is_dir: bool) {
+        let path = path.trim_matches('/');
+        if path.is_empty() {
+            return;
+        }
+        let (top, nested) = match path.split_once('/') {
+            Some((top, _)) => (top, true),
+            None => (path, false),
+        };
+        let entry = self.top_level.entry(top.to_string()).or_insert_with(|| ImageEntry {
+            path: top.to_string(),
+            size: 0,
+            is_dir: nested || is_dir,
+        });
+        entry.is_dir |= nested;
+        if is_dir {
+            self.folder_count += 1;
+            return;
+        }
+        entry.size += size;
+        self.file_count += 1;
+        self.content_size += size;
+        self.largest.push(Reverse((size, path.to_string())));
+        if self.largest.len() > TOP_FILES {
+            self.largest.pop();
+        }
+    }
+
+    fn finish(self, path: &Path, method: InspectMethod) -> ImageContents {
+        let mut top_level: Vec<ImageEntry> = self.top_level.into_values().collect();
+        top_level.sort_by(|a, b| b.size.cmp(&a.size));
+        let mut largest: Vec<(u64, String)> = self.largest.into_iter().map(|Reverse(e)| e).collect();
+        largest.sort_by(|a, b| b.0.cmp(&a.0));
+        ImageContents {
+            path: crate::paths::display(path),
+            format: path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default(),
+            image_size: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+            method,
+            file_count: self.file_count,
+            folder_count: self.folder_count,
+            content_size: self.content_size,
+            top_level,
+            largest_files: largest
+                .into_iter()
+                .map(|(size, path)| ImageEntry { path, size, is_dir: false })
+                .collect(),
+        }
+    }
+}
+
+/// Look inside the disk image at `path`, attaching it read-only if the OS
+/// can, listing it with 7-Zip otherwise
+pub fn inspect(path: &str, cancel: &AtomicBool) -> Result<ImageContents, String> {
+    let image = crate::paths::extended(Path::new(path));
+    if !image.is_file() {
+        return Err(format!("Not a file: {}", path));
+    }
+    if !is_disk_image(&image) {
+        return Err(format!("Not a disk image: {}", path));
+    }
+
+    let mount_error = match Mount::attach(&image) {
+        Ok(mount) => {
+            info!("Inspecting {} attached at {:?}", path, mount.points);
+            let mut tally = Tally::default();
+            for point in &mount.points {
+                // Several volumes are told apart by their mount point's name
+                let prefix = match mount.points.len() {
+                    1 => String::new(),
+                    _ => match point.file_name() {
+                        Some(name) => format!("{}/", name.to_string_lossy()),
+                        None => format!("{}/", point.to_string_lossy().trim_end_matches([':', '\\', '/'])),
+                    },
+                };
+                walk(point, &prefix, &mut tally, cancel)?;
+            }
+            return Ok(tally.finish(&image, InspectMethod::Mounted));
+        }
+        Err(e) => e,
+    };
+
+    debug!("Cannot attach {} ({}); listing it instead", path, mount_error);
+    list_with_7zip(&image, cancel)
+        .map(|tally| tally.finish(&image, InspectMethod::Listed))
+        .map_err(|list_error| format!("Cannot inspect {}: {}; {}", path, mount_error, list_error))
+}
+
+fn walk(root: &Path, prefix: &str, tally: &mut Tally, cancel: &AtomicBool) -> Result<(), String> {
+    for (idx, entry) in WalkDir::new(root).min_depth(1).into_iter().enumerate() {
+        if idx % 256 == 0 && cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".to_string());
+        }
+        let Ok(entry) = entry else { continue };
+        let Ok(relative) = entry.path().strip_prefix(root) else { continue };
+        let relative = format!("{}{}", prefix, relative.to_string_lossy().replace('\\', "/"));
+        let is_dir = entry.file_type().is_dir();
+        let size = if is_dir { 0 } else { entry.metadata().map(|m| m.len()).unwrap_or(0) };
+        tally.add(&relative, size, is_dir);
+    }
+    Ok(())
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let detail = if stderr.trim().is_empty() { stdout } else { stderr };
+        return Err(format!("{} failed: {}", program, detail.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// `7z l -slt` prints one block of `Key = Value` lines per entry
+fn list_with_7zip(image: &Path, cancel: &AtomicBool) -> Result<Tally, String> {
+    let image = image.to_string_lossy();
+    let mut programs = vec!["7z", "7zz", "7za"];
+    if cfg!(target_os = "windows") {
+        programs.push(r"C:\Program Files\7-Zip\7z.exe");
+    }
+    let mut last_error = "7-Zip is not installed".to_string();
+    for program in programs {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".to_string());
+        }
+        match run(program, &["l", "-slt", "-ba", &image]) {
+            Ok(listing) => return Ok(parse_7zip_listing(&listing)),
+            Err(e) if e.starts_with("Failed to run") => continue,
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+fn parse_7zip_listing(listing: &str) -> Tally {
+    let mut tally = Tally::default();
+    for block in listing.replace("\r\n", "\n").split("\n\n") {
+        let mut path = None;
+        let mut size = 0;
+        let mut is_dir = false;
+        for line in block.lines() {
+            match line.split_once(" = ") {
+                Some(("Path", value)) => path = Some(value.replace('\\', "/")),
+                Some(("Size", value)) => size = value.trim().parse().unwrap_or(0),
+                Some(("Folder", value)) => is_dir = value.trim() == "+",
+                Some(("Attributes", value)) => is_dir |= value.trim_start().starts_with('D'),
+                _ => {}
+            }
+        }
+        if let Some(path) = path {
+            tally.add(&path, size, is_dir);
+        }
+    }
+    tally
+}
+
+/// An image attached read-only; detached again when dropped
+struct Mount {
+    /// Where its volumes are mounted
+    points: Vec<PathBuf>,
+    /// What detaching needs: the device on Linux, the image elsewhere
+    handle: String,
+}
+
+impl Drop for Mount {
+    fn drop(&mut self) {
+        if let Err(e) = self.detach() {
+            warn!("Failed to detach disk image {}: {}", self.handle, e);
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl Mount {
+    fn attach(image: &Path) -> Result<Self, String> {
+        let image = image.to_string_lossy();
+        let mount_root = std::env::temp_dir();
+        let plist = run(
+            "hdiutil",
+            &["attach", "-readonly", "-nobrowse", "-noautoopen", "-noverify", "-plist", "-mountrandom", &mount_root.to_string_lossy(), &image],
+        )?;
+        // <key>mount-point</key> followed by <string>/private/var/.../dmg.XYZ</string>
+        let values = |key: &str| -> Vec<String> {
+            plist
+                .split(&format!("<key>{}</key>", key))
+                .skip(1)
+                .filter_map(|rest| Some(rest.split_once("<string>")?.1.split_once("</string>")?.0.trim().to_string()))
+                .collect()
+        };
+        // The first device is the whole image; detaching it detaches all
+        let Some(device) = values("dev-entry").into_iter().next() else {
+            return Err(format!("Unexpected hdiutil output for {}", image));
+        };
+        let mount = Self {
+            points: values("mount-point").into_iter().map(PathBuf::from).collect(),
+            handle: device,
+        };
+        if mount.points.is_empty() {
+            return Err("The image has no volume macOS can mount".to_string());
+        }
+        Ok(mount)
+    }
+
+    fn detach(&self) -> Result<(), String> {
+        run("hdiutil", &["detach", "-force", &self.handle]).map(|_| ())
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Mount {
+    fn attach(image: &Path) -> Result<Self, String> {
+        let format = image.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        if !matches!(format.as_str(), "iso" | "img" | "vhd" | "vhdx") {
+            return Err(format!("Windows cannot attach .{} images", format));
+        }
+        let image = image.to_string_lossy().trim_start_matches(r"\\?\").to_string();
+        let quoted = image.replace('\'', "''");
+        let script = format!(
+            "(Mount-DiskImage -ImagePath '{}' -Access ReadOnly -PassThru | Get-Volume).DriveLetter",
+            quoted
+        );
+        let mut mount = Self { points: Vec::new(), handle: image };
+        // Detached again by the drop if listing its volumes fails
+        let letters = run("powershell", &["-NoProfile", "-NonInteractive", "-Command", &script])?;
+        mount.points = letters
+            .lines()
+            .map(str::trim)
+            .filter(|l| l.len() == 1)
+            .map(|letter| PathBuf::from(format!("{}:\\", letter)))
+            .collect();
+        if mount.points.is_empty() {
+            return Err("The image has no volume with a drive letter".to_string());
+        }
+        Ok(mount)
+    }
+
+    fn detach(&self) -> Result<(), String> {
+        let script = format!("Dismount-DiskImage -ImagePath '{}'", self.handle.replace('\'', "''"));
+        run("powershell", &["-NoProfile", "-NonInteractive", "-Command", &script]).map(|_| ())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Mount {
+    fn attach(image: &Path) -> Result<Self, String> {
+        // Mapped file /home/me/disk.iso as /dev/loop12.
+        let output = run(
+            "udisksctl",
+            &["loop-setup", "--read-only", "--no-user-interaction", "-f", &image.to_string_lossy()],
+        )?;
+        let device = output
+            .split(" as ")
+            .nth(1)
+            .map(|d| d.trim().trim_end_matches('.').to_string())
+            .ok_or_else(|| format!("Unexpected udisksctl output: {}", output.trim()))?;
+        let mut mount = Self { points: Vec::new(), handle: device.clone() };
+
+        // The whole device holds a file system, or its partitions do
+        let candidates = std::iter::once(device.clone()).chain((1..=8).map(|n| format!("{}p{}", device, n)));
+        for candidate in candidates.filter(|c| Path::new(c).exists()) {
+            let mounted = run(
+                "udisksctl",
+                &["mount", "--no-user-interaction", "-o", "ro", "-b", &candidate],
+            )
+            // Auto-mounted by the desktop the moment the loop device appeared
+            .or_else(|e| if e.contains("AlreadyMounted") { Ok(e) } else { Err(e) });
+            // Mounted /dev/loop12 at /media/me/Ubuntu 24.04 LTS amd64
+            // ...is already mounted at `/media/me/Ubuntu 24.04 LTS amd64'
+            if let Ok(text) = mounted {
+                let point = text
+                    .split_once(" at ")
+                    .map(|(_, p)| p.trim().trim_matches(|c| c == '`' || c == '\'' || c == '.').to_string());
+                if let Some(point) = point {
+                    mount.points.push(PathBuf::from(point));
+                }
+            }
+        }
+        if mount.points.is_empty() {
+            return Err("The image has no file system Linux can mount".to_string());
+        }
+        Ok(mount)
+    }
+
+    fn detach(&self) -> Result<(), String> {
+        for point in &self.points {
+            let _ = run("udisksctl", &["unmount", "--no-user-interaction", "-f", "-b", &device_of(point, &self.handle)]);
+        }
+        run("udisksctl", &["loop-delete", "--no-user-interaction", "-b", &self.handle]).map(|_| ())
+    }
+}
+
+/// Device mounted at `point`: the loop device or one of its partitions
+#[cfg(target_os = "linux")]
+fn device_of(point: &Path, loop_device: &str) -> String {
+    std::fs::read_to_string("/proc/mounts")
+        .ok()
+        .and_then(|mounts| {
+            mounts.lines().find_map(|line| {
+                let mut fields = line.split_whitespace();
+                let device = fields.next()?;
+                // Spaces in mount points are escaped as \040
+                let mounted_at = fields.next()?.replace("\\040", " ");
+                (Path::new(&mounted_at) == point && device.starts_with(loop_device)).then(|| device.to_string())
+            })
+        })
+        .unwrap_or_else(|| loop_device.to_string())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+impl Mount {
+    fn attach(_image: &Path) -> Result<Self, String> {
+        Err("Attaching disk images is not supported on this platform".to_string())
+    }
+
+    fn detach(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
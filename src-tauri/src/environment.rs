@@ -0,0 +1,234 @@
+// Environment - Startup health check
+//
+// Probes what Helium depends on (Node/npx for the subprocess MCP backend,
+// HuggingFace for model downloads, CPU/GPU acceleration, free space for
+// models and state, read access to the usual scan locations) and reports
+// each as a structured check, so the UI can guide setup up front instead of
+// letting a feature fail later.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use sysinfo::Disks;
+use tokio::process::Command;
+
+/// HuggingFace endpoint probed for connectivity
+const HF_URL: &str = "https://huggingface.co/api/models?limit=1";
+
+/// Free space below which downloading a model is likely to fail
+const LOW_DISK_BYTES: u64 = 5 * 1024 * 1024 * 1024;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// Result of one probe
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentCheck {
+    /// Stable identifier ("node", "npx", "huggingface", "acceleration", "disk", "scan:<path>")
+    pub id: String,
+    pub status: CheckStatus,
+    pub summary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl EnvironmentCheck {
+    fn new(id: &str, status: CheckStatus, summary: String) -> Self {
+        Self {
+            id: id.to_string(),
+            status,
+            summary,
+            suggestion: None,
+        }
+    }
+
+    fn suggest(mut self, suggestion: &str) -> Self {
+        self.suggestion = Some(suggestion.to_string());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentReport {
+    pub os: String,
+    pub arch: String,
+    pub app_version: String,
+    pub checks: Vec<EnvironmentCheck>,
+}
+
+/// `<program> --version`, resolving `.cmd` shims through cmd on Windows
+async fn tool_version(program: &str) -> Option<String> {
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        let mut c = Command::new("cmd");
+        c.args(["/c", program]);
+        c
+    };
+    #[cfg(not(target_os = "windows"))]
+    let mut cmd = Command::new(program);
+
+    let output = tokio::time::timeout(PROBE_TIMEOUT, cmd.arg("--version").output())
+        .await
+        .ok()?
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+async fn check_tool(program: &str) -> EnvironmentCheck {
+    match tool_version(program).await {
+        Some(version) => EnvironmentCheck::new(program, CheckStatus::Ok, format!("{} {}", program, version)),
+        None => EnvironmentCheck::new(program, CheckStatus::Warning, format!("{} was not found on PATH", program))
+            .suggest("Install Node.js (which provides npx) to use the subprocess MCP backend; the built-in backend works without it"),
+    }
+}
+
+async fn check_huggingface() -> EnvironmentCheck {
+    let client = match reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => return EnvironmentCheck::new("huggingface", CheckStatus::Error, e.to_string()),
+    };
+    match client.get(HF_URL).send().await {
+        Ok(response) if response.status().is_success() => {
+            EnvironmentCheck::new("huggingface", CheckStatus::Ok, "HuggingFace is reachable".to_string())
+        }
+        Ok(response) => EnvironmentCheck::new(
+            "huggingface",
+            CheckStatus::Warning,
+            format!("HuggingFace responded with {}", response.status()),
+        )
+        .suggest("Model downloads may fail; try again later"),
+        Err(e) => EnvironmentCheck::new(
+            "huggingface",
+            CheckStatus::Error,
+            format!("HuggingFace is unreachable: {}", e),
+        )
+        .suggest("Check your network or proxy settings; already downloaded models keep working offline"),
+    }
+}
+
+fn check_acceleration() -> EnvironmentCheck {
+    use candle_core::utils;
+
+    let mut features = Vec::new();
+    if utils::cuda_is_available() {
+        features.push("CUDA");
+    }
+    if utils::metal_is_available() {
+        features.push("Metal");
+    }
+    if utils::has_accelerate() {
+        features.push("Accelerate");
+    }
+    if utils::has_mkl() {
+        features.push("MKL");
+    }
+    if utils::with_avx() {
+        features.push("AVX");
+    }
+    if utils::with_neon() {
+        features.push("NEON");
+    }
+    if utils::with_f16c() {
+        features.push("F16C");
+    }
+
+    let threads = utils::get_num_threads();
+    let gpu = utils::cuda_is_available() || utils::metal_is_available();
+    let summary = if features.is_empty() {
+        format!("CPU inference on {} threads without SIMD acceleration", threads)
+    } else {
+        format!("{} threads; {}", threads, features.join(", "))
+    };
+    let check = EnvironmentCheck::new(
+        "acceleration",
+        if gpu || !features.is_empty() { CheckStatus::Ok } else { CheckStatus::Warning },
+        summary,
+    );
+    if gpu {
+        check
+    } else {
+        check.suggest("Embedded models run on the CPU; prefer small models or use Ollama for GPU inference")
+    }
+}
+
+fn check_disk(data_dir: &Path) -> EnvironmentCheck {
+    let disks = Disks::new_with_refreshed_list();
+    // The most specific mount point containing the data directory
+    let disk = disks
+        .iter()
+        .filter(|d| data_dir.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len());
+
+    let Some(disk) = disk else {
+        return EnvironmentCheck::new(
+            "disk",
+            CheckStatus::Warning,
+            format!("Could not determine free space for {}", data_dir.display()),
+        );
+    };
+    let available = disk.available_space();
+    let gb = available as f64 / (1024.0 * 1024.0 * 1024.0);
+    if available < LOW_DISK_BYTES {
+        EnvironmentCheck::new("disk", CheckStatus::Warning, format!("Only {:.1} GB free for app data", gb))
+            .suggest("Free up space before downloading models; a small model needs 1-5 GB")
+    } else {
+        EnvironmentCheck::new("disk", CheckStatus::Ok, format!("{:.1} GB free for app data", gb))
+    }
+}
+
+/// Locations users commonly scan
+fn scan_locations() -> Vec<PathBuf> {
+    let library = if cfg!(target_os = "macos") {
+        dirs::home_dir().map(|home| home.join("Library"))
+    } else {
+        None
+    };
+    [dirs::home_dir(), dirs::document_dir(), dirs::download_dir(), dirs::desktop_dir(), library]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+fn check_scan_access(path: &Path) -> EnvironmentCheck {
+    let id = format!("scan:{}", path.display());
+    match std::fs::read_dir(path) {
+        Ok(_) => EnvironmentCheck::new(&id, CheckStatus::Ok, format!("{} is readable", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            EnvironmentCheck::new(&id, CheckStatus::Ok, format!("{} does not exist", path.display()))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => EnvironmentCheck::new(
+            &id,
+            CheckStatus::Warning,
+            format!("No permission to read {}", path.display()),
+        )
+        .suggest("Grant Helium access to this folder in your system's privacy settings"),
+        Err(e) => EnvironmentCheck::new(&id, CheckStatus::Warning, format!("Cannot read {}: {}", path.display(), e)),
+    }
+}
+
+/// Run all checks; the slow network and process probes run concurrently
+pub async fn report(data_dir: &Path, app_version: String) -> EnvironmentReport {
+    let (node, npx, huggingface) = tokio::join!(check_tool("node"), check_tool("npx"), check_huggingface());
+
+    let mut checks = vec![node, npx, huggingface, check_acceleration(), check_disk(data_dir)];
+    checks.extend(scan_locations().iter().map(|p| check_scan_access(p)));
+
+    EnvironmentReport {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        app_version,
+        checks,
+    }
+}
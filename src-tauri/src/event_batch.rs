@@ -0,0 +1,169 @@
+// Event Batch - Rate-limited event channels to the webview
+//
+// Scans, downloads and token streaming can produce thousands of updates a
+// second; sending each as a Tauri event floods the webview and makes the UI
+// stutter. A batcher coalesces the updates of one event channel and emits at
+// most MAX_EVENTS_PER_SEC of them: progress keeps only the newest payload,
+// streamed text is concatenated. An update arriving during the quiet period
+// is held and emitted when it ends, so the last state is never lost, and
+// whatever is still held goes out on `flush` or when the last clone of the
+// batcher is dropped.
+
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Runtime};
+
+/// Events per second a channel emits at most
+pub const MAX_EVENTS_PER_SEC: u64 = 30;
+const MIN_INTERVAL: Duration = Duration::from_millis(1000 / MAX_EVENTS_PER_SEC);
+
+/// Merge a newer update into the held one. Returns the newer update when it
+/// can't be merged, in which case the held one is emitted first.
+pub type Merge<T> = fn(&mut T, T) -> Option<T>;
+
+/// Keep only the newest payload (progress, status)
+pub fn replace<T>(held: &mut T, newer: T) -> Option<T> {
+    *held = newer;
+    None
+}
+
+/// Concatenate streamed text
+pub fn append(held: &mut String, newer: String) -> Option<String> {
+    held.push_str(&newer);
+    None
+}
+
+type Emit<T> = Box<dyn Fn(&str, T) + Send + Sync>;
+
+struct Channel<T> {
+    held: Option<T>,
+    last_emit: Option<Instant>,
+    flush_scheduled: bool,
+    closed: bool,
+}
+
+struct Inner<T> {
+    event: String,
+    emit: Emit<T>,
+    merge: Merge<T>,
+    channel: Mutex<Channel<T>>,
+}
+
+impl<T> Inner<T> {
+    fn emit_held(&self, channel: &mut Channel<T>) {
+        if let Some(payload) = channel.held.take() {
+            channel.last_emit = Some(Instant::now());
+            (self.emit)(&self.event, payload);
+        }
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        let channel = self.channel.get_mut().unwrap();
+        if !channel.closed {
+            if let Some(payload) = channel.held.take() {
+                (self.emit)(&self.event, payload);
+            }
+        }
+    }
+}
+
+/// Rate-limited sender for one event channel. Clones share the channel.
+pub struct EventBatcher<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Clone for EventBatcher<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: Serialize + Clone + Send + 'static> EventBatcher<T> {
+    pub fn new<R: Runtime>(
+        emitter: impl Emitter<R> + Send + Sync + 'static,
+        event: impl Into<String>,
+        merge: Merge<T>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                event: event.into(),
+                emit: Box::new(move |event, payload| {
+                    let _ = emitter.emit(event, payload);
+                }),
+                merge,
+                channel: Mutex::new(Channel {
+                    held: None,
+                    last_emit: None,
+                    flush_scheduled: false,
+                    closed: false,
+                }),
+            }),
+        }
+    }
+
+    /// Channel that only delivers the newest payload
+    pub fn latest<R: Runtime>(emitter: impl Emitter<R> + Send + Sync + 'static, event: impl Into<String>) -> Self {
+        Self::new(emitter, event, replace)
+    }
+
+    /// Queue an update; it is emitted now if the channel has been quiet long
+    /// enough, else merged into the held update and emitted shortly
+    pub fn send(&self, payload: T) {
+        let mut channel = self.inner.channel.lock().unwrap();
+        if channel.closed {
+            return;
+        }
+        let unmerged = match channel.held.as_mut() {
+            Some(held) => (self.inner.merge)(held, payload),
+            None => {
+                channel.held = Some(payload);
+                None
+            }
+        };
+        if let Some(newer) = unmerged {
+            // Keep the order: what was held goes out before the newer update
+            self.inner.emit_held(&mut channel);
+            channel.held = Some(newer);
+        }
+
+        let since_last = channel.last_emit.map(|t| t.elapsed());
+        match since_last {
+            Some(elapsed) if elapsed < MIN_INTERVAL => {
+                if !channel.flush_scheduled {
+                    channel.flush_scheduled = true;
+                    let batcher = self.clone();
+                    tauri::async_runtime::spawn(async move {
+                        tokio::time::sleep(MIN_INTERVAL - elapsed).await;
+                        let mut channel = batcher.inner.channel.lock().unwrap();
+                        channel.flush_scheduled = false;
+                        if !channel.closed {
+                            batcher.inner.emit_held(&mut channel);
+                        }
+                    });
+                }
+            }
+            _ => self.inner.emit_held(&mut channel),
+        }
+    }
+
+    /// Emit the held update now, e.g. before a command returns its result
+    pub fn flush(&self) {
+        let mut channel = self.inner.channel.lock().unwrap();
+        if !channel.closed {
+            self.inner.emit_held(&mut channel);
+        }
+    }
+
+    /// Drop the held update and stop emitting, when a final event sent
+    /// elsewhere supersedes it
+    pub fn discard(&self) {
+        let mut channel = self.inner.channel.lock().unwrap();
+        channel.held = None;
+        channel.closed = true;
+    }
+}
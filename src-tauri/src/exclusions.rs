@@ -0,0 +1,233 @@
+// Exclusions - Entries skipped by scans, in .gitignore syntax
+//
+// Patterns follow .gitignore rules (globs, `**`, trailing `/`, `!` to
+// re-include, last match wins); network mounts can be skipped as a whole.
+
+use crate::paths;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Anchor {
+    /// Matched against the file or folder name
+    Name,
+    /// Matched against the trailing components of the path
+    Suffix,
+    /// Matched against the absolute path and its ancestors
+    Absolute,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    glob: String,
+    anchor: Anchor,
+    negated: bool,
+    dir_only: bool,
+    /// `*.ext`: compared case-insensitively, as extensions always were
+    extension: bool,
+}
+
+impl Rule {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.trim().is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            // `\#` and `\!` start a pattern with a literal `#` or `!`
+            None if line.starts_with("\\#") || line.starts_with("\\!") => (false, &line[1..]),
+            None => (false, line),
+        };
+        let mut glob = pattern.replace('\\', "/");
+        if glob.starts_with("~/") {
+            glob = paths::display(&paths::expand_home(&glob)).replace('\\', "/");
+        }
+        let dir_only = glob.len() > 1 && glob.ends_with('/');
+        if dir_only {
+            glob.pop();
+        }
+        let absolute = glob.starts_with('/') || Path::new(&glob).is_absolute() || glob.get(1..3) == Some(":/");
+        let anchor = if absolute {
+            Anchor::Absolute
+        } else if glob.contains('/') {
+            Anchor::Suffix
+        } else {
+            Anchor::Name
+        };
+        let extension = anchor == Anchor::Name
+            && glob.strip_prefix("*.").is_some_and(|ext| !ext.contains(['*', '?', '[']));
+        Some(Self {
+            glob,
+            anchor,
+            negated,
+            dir_only,
+            extension,
+        })
+    }
+
+    fn matches(&self, components: &[&str], is_dir: &mut impl FnMut() -> bool) -> bool {
+        let hit = match self.anchor {
+            Anchor::Name => {
+                let name = components.last().copied().unwrap_or_default();
+                if self.extension {
+                    glob_match(&self.glob.to_lowercase(), &name.to_lowercase())
+                } else {
+                    glob_match(&self.glob, name)
+                }
+            }
+            Anchor::Suffix => {
+                let glob = self.glob.trim_start_matches('/');
+                (0..components.len()).any(|start| glob_match(glob, &components[start..].join("/")))
+            }
+            // A path below an excluded folder is excluded too
+            Anchor::Absolute => (1..=components.len()).any(|end| glob_match(&self.glob, &components[..end].join("/"))),
+        };
+        hit && (!self.dir_only || is_dir())
+    }
+}
+
+/// Whether `text` matches the glob `pattern`: `*` and `?` stay within one
+/// path component, `**` spans any number of them, `[...]` is a character
+/// class (`[!...]` or `[^...]` negated)
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    match_from(&pattern, &text)
+}
+
+fn match_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            // `**/` also matches no folder at all
+            let rest = &pattern[2..];
+            if let Some(after_slash) = rest.strip_prefix(&['/']) {
+                if match_from(after_slash, text) {
+                    return true;
+                }
+            }
+            (0..=text.len()).any(|skip| match_from(rest, &text[skip..]))
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for skip in 0..=text.len() {
+                if match_from(rest, &text[skip..]) {
+                    return true;
+                }
+                if text.get(skip) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => text.first().is_some_and(|&c| c != '/') && match_from(&pattern[1..], &text[1..]),
+        Some('[') => match (class_match(&pattern[1..], text.first().copied()), text.first()) {
+            (Some((true, consumed)), Some(_)) => match_from(&pattern[1 + consumed..], &text[1..]),
+            (Some(_), _) => false,
+            // No closing bracket: a literal `[`
+            (None, Some('[')) => match_from(&pattern[1..], &text[1..]),
+            (None, _) => false,
+        },
+        Some(&c) => text.first() == Some(&c) && match_from(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Match `c` against the class starting after `[`. Returns whether it
+/// matched and how many pattern characters the class used (including `]`),
+/// or None if the class is never closed.
+fn class_match(class: &[char], c: Option<char>) -> Option<(bool, usize)> {
+    let negated = matches!(class.first(), Some('!') | Some('^'));
+    let mut i = usize::from(negated);
+    let mut matched = false;
+    let mut first = true;
+    while i < class.len() {
+        let start = class[i];
+        if start == ']' && !first {
+            let hit = c.is_some_and(|c| c != '/') && matched != negated;
+            return Some((hit, i + 1));
+        }
+        first = false;
+        if class.get(i + 1) == Some(&'-') && class.get(i + 2).is_some_and(|&end| end != ']') {
+            let end = class[i + 2];
+            matched |= c.is_some_and(|c| (start..=end).contains(&c));
+            i += 3;
+        } else {
+            matched |= c == Some(start);
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Entries skipped by a scan: .gitignore-style patterns, and optionally
+/// every network mount or every folder on another file system than the
+/// scanned one
+#[derive(Debug, Default, Clone)]
+pub struct Exclusions {
+    rules: Vec<Rule>,
+    mounts: Vec<PathBuf>,
+    same_device: bool,
+}
+
+impl Exclusions {
+    pub fn new(patterns: Vec<String>) -> Self {
+        Self {
+            rules: patterns.iter().flat_map(|p| p.lines()).filter_map(Rule::parse).collect(),
+            mounts: Vec::new(),
+            same_device: false,
+        }
+    }
+
+    /// Also skip the network shares mounted right now
+    pub fn skipping_network_mounts(mut self) -> Self {
+        self.mounts = crate::scan_strategy::network_mounts();
+        self
+    }
+
+    /// Also skip mount points: folders on another device than the scan root
+    /// (checked by the scanner, which has their metadata at hand)
+    pub fn staying_on_device(mut self) -> Self {
+        self.same_device = true;
+        self
+    }
+
+    pub fn same_device(&self) -> bool {
+        self.same_device
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.mounts.is_empty() && !self.same_device
+    }
+
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        if self.mounts.iter().any(|mount| paths::same(path, mount)) {
+            return true;
+        }
+        let display = paths::display(path).replace('\\', "/");
+        let components: Vec<&str> = display.split('/').filter(|c| !c.is_empty()).collect();
+        // Absolute patterns keep their leading slash; so does the path when
+        // matched against them
+        let mut rooted = components.clone();
+        if display.starts_with('/') {
+            rooted.insert(0, "");
+        }
+
+        let mut is_dir_cache = None;
+        let mut is_dir = || *is_dir_cache.get_or_insert_with(|| path.is_dir());
+        let mut excluded = false;
+        for rule in &self.rules {
+            // Only a later negation can change an exclusion, and the reverse
+            if rule.negated != excluded {
+                continue;
+            }
+            let parts = if rule.anchor == Anchor::Absolute { &rooted } else { &components };
+            if rule.matches(parts, &mut is_dir) {
+                excluded = !rule.negated;
+            }
+        }
+        excluded
+    }
+}
@@ -0,0 +1,778 @@
+// Fixtures - Deterministic stand-ins for disk, MCP and AI (feature "fixtures")
+//
+// Built with `--features fixtures`, scans and listings, size and type
+// breakdowns, drive listing, junk detection and cleanup, deletion, MCP tool
+// calls and every AI provider are served from an in-memory fake filesystem
+// and canned model replies. Nothing touches the real disk, the network or
+// model weights, so commands can be integration-tested and the frontend
+// developed anywhere with identical results on every run.
+
+use crate::ai::providers::{AIProvider, InferenceSink};
+use crate::ai::{
+    usage, AIError, AIErrorType, AIMode, ChatMessage, InferenceRequest, InferenceResponse, MessageRole,
+    ModelConfig, ModelParameters, ModelProvider, OpenAIToolCall, OpenAIToolCallFunction, ProviderStatus,
+    TokenUsage,
+};
+use crate::breakdown::LargeFile;
+use crate::cleaner::{JunkCategory, JunkItem};
+use crate::i18n;
+use crate::mcp::report::{self, ReportSection};
+use crate::mcp::{MCPError, MCPResult};
+use crate::scanner::{ExtensionTotals, FileNode, TypeBreakdown, MAX_SHALLOW_DEPTH};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// Root of the fake filesystem
+pub const FIXTURE_ROOT: &str = "/fixture";
+
+/// Modification time of every fixture entry
+const FIXTURE_MTIME: u64 = 1_700_000_000;
+
+const MB: u64 = 1024 * 1024;
+
+/// (path relative to the root, size, text content)
+const FIXTURE_FILES: &[(&str, u64, Option<&str>)] = &[
+    ("Documents/report.docx", 240 * 1024, None),
+    ("Documents/notes.txt", 0, Some("Quarterly planning notes\n- Ship profiles\n- Benchmark the scanner\n")),
+    ("Documents/Taxes/2023.pdf", MB, None),
+    ("Downloads/installer.dmg", 500 * MB, None),
+    ("Downloads/archive.zip", 70 * MB, None),
+    ("Downloads/photo.jpg", 3 * MB, None),
+    ("Pictures/Vacation/img_0001.jpg", 4 * MB, None),
+    ("Pictures/Vacation/img_0002.jpg", 4 * MB, None),
+    ("Pictures/Vacation/img_0003.jpg", 4 * MB, None),
+    ("Projects/helium/README.md", 0, Some("# Helium\n\nDisk usage explorer with a local AI assistant.\n")),
+    ("Projects/helium/node_modules/left-pad/index.js", 0, Some("module.exports = (s, n) => s.padStart(n);\n")),
+    ("Projects/helium/target/debug/app", 150 * MB, None),
+    (".cache/thumbnails/cache.db", 20 * MB, None),
+];
+
+/// Canned replies, chosen by a word in the last user message
+const SCRIPTED_REPLIES: &[(&str, &str)] = &[
+    ("hello", "Hello! I'm the fixture model. Ask me about the files in /fixture."),
+    ("largest", "The largest folder is Downloads (573 MB), mostly installer.dmg (500 MB)."),
+    ("clean", "You could remove Projects/helium/target (150 MB of build output) and .cache (20 MB)."),
+];
+
+#[derive(Debug, Clone)]
+struct Entry {
+    is_dir: bool,
+    size: u64,
+    content: Option<String>,
+}
+
+lazy_static! {
+    static ref FAKE_FS: Mutex<BTreeMap<String, Entry>> = Mutex::new(initial_fs());
+}
+
+fn initial_fs() -> BTreeMap<String, Entry> {
+    let mut fs = BTreeMap::new();
+    fs.insert(FIXTURE_ROOT.to_string(), Entry { is_dir: true, size: 0, content: None });
+    for (relative, size, content) in FIXTURE_FILES {
+        let path = format!("{}/{}", FIXTURE_ROOT, relative);
+        add_parents(&mut fs, &path);
+        fs.insert(
+            path,
+            Entry {
+                is_dir: false,
+                size: content.map_or(*size, |c| c.len() as u64),
+                content: content.map(String::from),
+            },
+        );
+    }
+    fs
+}
+
+/// Restore the fake filesystem to its initial state (between tests)
+pub fn reset() {
+    *FAKE_FS.lock().unwrap() = initial_fs();
+}
+
+fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() { "/".to_string() } else { trimmed.to_string() }
+}
+
+fn parent(path: &str) -> Option<&str> {
+    path.rsplit_once('/').map(|(p, _)| p).filter(|p| !p.is_empty())
+}
+
+fn name(path: &str) -> String {
+    path.rsplit('/').next().unwrap_or(path).to_string()
+}
+
+fn add_parents(fs: &mut BTreeMap<String, Entry>, path: &str) {
+    let mut current = parent(path);
+    while let Some(dir) = current {
+        fs.entry(dir.to_string()).or_insert(Entry { is_dir: true, size: 0, content: None });
+        current = parent(dir);
+    }
+}
+
+fn children<'a>(fs: &'a BTreeMap<String, Entry>, dir: &str) -> Vec<(&'a String, &'a Entry)> {
+    let prefix = format!("{}/", dir);
+    fs.range(prefix.clone()..)
+        .take_while(|(p, _)| p.starts_with(&prefix))
+        .filter(|(p, _)| !p[prefix.len()..].contains('/'))
+        .collect()
+}
+
+/// Total size and file count below `path`
+fn subtree(fs: &BTreeMap<String, Entry>, path: &str) -> (u64, u64) {
+    let prefix = format!("{}/", path);
+    fs.range(prefix.clone()..)
+        .take_while(|(p, _)| p.starts_with(&prefix))
+        .filter(|(_, e)| !e.is_dir)
+        .fold((0, 0), |(size, count), (_, e)| (size + e.size, count + 1))
+}
+
+fn not_found(path: &str) -> String {
+    i18n::t_with("error.fixture_not_found", &[("path", path)])
+}
+
+/// Build a node with `depth` levels of children, like the real scanner
+/// (root plus one level of look-ahead)
+fn node(fs: &BTreeMap<String, Entry>, path: &str, depth: usize) -> FileNode {
+    let entry = &fs[path];
+    if !entry.is_dir {
+        return FileNode {
+            name: name(path),
+            path: path.to_string(),
+            size: entry.size,
+            is_dir: false,
+            children: None,
+            last_modified: FIXTURE_MTIME,
+            file_count: 1,
+            volume: None,
+            link: None,
+            skipped_links: None,
+            allocated_size: None,
+            extensions: None,
+            deferred: false,
+        };
+    }
+    let (size, file_count) = subtree(fs, path);
+    let children = (depth > 0).then(|| {
+        let mut nodes: Vec<FileNode> = children(fs, path).into_iter().map(|(p, _)| node(fs, p, depth - 1)).collect();
+        nodes.sort_by(|a, b| b.size.cmp(&a.size));
+        nodes
+    });
+    FileNode {
+        name: name(path),
+        path: path.to_string(),
+        size,
+        is_dir: true,
+        children,
+        last_modified: FIXTURE_MTIME,
+        file_count,
+        volume: None,
+        link: None,
+        skipped_links: None,
+        allocated_size: None,
+        extensions: None,
+        deferred: false,
+    }
+}
+
+/// Node of the directory `path` with `depth` levels of children
+fn directory(path: &str, depth: usize) -> Result<FileNode, String> {
+    let fs = FAKE_FS.lock().unwrap();
+    let path = normalize(path);
+    match fs.get(&path) {
+        Some(entry) if entry.is_dir => Ok(node(&fs, &path, depth)),
+        _ => Err(i18n::t("error.directory_not_found")),
+    }
+}
+
+/// Scan a directory of the fake filesystem
+pub fn scan(path: &str) -> Result<FileNode, String> {
+    directory(path, 2)
+}
+
+/// List a directory `depth` levels deep; fixture sizes are known, so
+/// nothing is deferred
+pub fn scan_shallow(path: &str, depth: usize) -> Result<FileNode, String> {
+    directory(path, depth.clamp(1, MAX_SHALLOW_DEPTH))
+}
+
+/// Size and file count of a directory, without its children
+pub fn deep_stats(path: &str) -> Result<FileNode, String> {
+    directory(path, 0)
+}
+
+/// Files below `path`, largest first
+fn files_below<'a>(fs: &'a BTreeMap<String, Entry>, path: &str) -> Vec<(&'a String, &'a Entry)> {
+    let prefix = format!("{}/", path);
+    let mut files: Vec<_> = fs
+        .range(prefix.clone()..)
+        .take_while(|(p, _)| p.starts_with(&prefix))
+        .filter(|(_, e)| !e.is_dir)
+        .collect();
+    files.sort_by(|a, b| b.1.size.cmp(&a.1.size).then_with(|| a.0.cmp(b.0)));
+    files
+}
+
+/// The `limit` largest files below `path`
+pub fn largest_files(path: &str, limit: usize) -> Result<Vec<LargeFile>, String> {
+    let fs = FAKE_FS.lock().unwrap();
+    let path = normalize(path);
+    if !fs.get(&path).is_some_and(|e| e.is_dir) {
+        return Err(i18n::t("error.directory_not_found"));
+    }
+    Ok(files_below(&fs, &path)
+        .into_iter()
+        .take(limit)
+        .map(|(p, e)| LargeFile {
+            path: p.clone(),
+            size: e.size,
+            last_modified: FIXTURE_MTIME,
+        })
+        .collect())
+}
+
+/// Bytes by category and extension below `path`
+pub fn type_breakdown(path: &str) -> Result<TypeBreakdown, String> {
+    let fs = FAKE_FS.lock().unwrap();
+    let path = normalize(path);
+    if !fs.get(&path).is_some_and(|e| e.is_dir) {
+        return Err(i18n::t("error.directory_not_found"));
+    }
+    let mut extensions = ExtensionTotals::new();
+    for (file, entry) in files_below(&fs, &path) {
+        let extension = name(file).rsplit_once('.').map(|(_, e)| e.to_lowercase()).unwrap_or_default();
+        let totals = extensions.entry(extension).or_default();
+        totals.0 += entry.size;
+        totals.1 += 1;
+    }
+    Ok(TypeBreakdown::new(path, &extensions))
+}
+
+/// Remove entries (and everything below folders) from the fake filesystem.
+/// Nothing is removed if any of them is missing.
+pub fn delete(paths: &[String]) -> Result<(), String> {
+    let mut fs = FAKE_FS.lock().unwrap();
+    let paths: Vec<String> = paths.iter().map(|p| normalize(p)).collect();
+    if let Some(missing) = paths.iter().find(|p| !fs.contains_key(*p)) {
+        return Err(not_found(missing));
+    }
+    for path in paths {
+        let prefix = format!("{}/", path);
+        fs.retain(|p, _| *p != path && !p.starts_with(&prefix));
+    }
+    Ok(())
+}
+
+/// The single fake drive
+pub fn drives() -> Vec<FileNode> {
+    let fs = FAKE_FS.lock().unwrap();
+    let mut drive = node(&fs, FIXTURE_ROOT, 0);
+    drive.name = "Fixture Disk".to_string();
+    vec![drive]
+}
+
+/// Junk found in the fake filesystem
+pub fn junk() -> Vec<JunkCategory> {
+    let fs = FAKE_FS.lock().unwrap();
+    let cache_dir = format!("{}/.cache", FIXTURE_ROOT);
+    let items: Vec<JunkItem> = children(&fs, &cache_dir)
+        .into_iter()
+        .map(|(path, entry)| JunkItem {
+            path: path.clone(),
+            name: name(path),
+            size: if entry.is_dir { subtree(&fs, path).0 } else { entry.size },
+            description: i18n::t_with("junk.item_in", &[("category", &i18n::t("junk.system_cache"))]),
+        })
+        .collect();
+    let total_size = items.iter().map(|i| i.size).sum();
+    vec![JunkCategory {
+        id: "system_cache".to_string(),
+        name: i18n::t("junk.system_cache"),
+        description: i18n::t_with("junk.located_in", &[("path", &cache_dir)]),
+        items,
+        total_size,
+        icon: "system_cache".to_string(),
+        risky: false,
+        provider: None,
+        too_recent: 0,
+    }]
+}
+
+fn mcp_error(message: String) -> MCPError {
+    MCPError {
+        code: -32000,
+        message,
+        data: None,
+    }
+}
+
+fn arg<'a>(args: &'a Value, key: &str) -> MCPResult<&'a str> {
+    args.get(key)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| mcp_error(format!("Missing '{}' argument", key)))
+}
+
+fn file_info(path: &str, entry: &Entry) -> Value {
+    json!({
+        "name": name(path),
+        "path": path,
+        "size": entry.size,
+        "is_dir": entry.is_dir,
+        "is_file": !entry.is_dir,
+        "modified": FIXTURE_MTIME,
+    })
+}
+
+fn tree(fs: &BTreeMap<String, Entry>, path: &str) -> Value {
+    let entry = &fs[path];
+    let mut value = json!({ "name": name(path), "path": path, "type": if entry.is_dir { "directory" } else { "file" }, "size": entry.size });
+    if entry.is_dir {
+        value["children"] = children(fs, path).into_iter().map(|(p, _)| tree(fs, p)).collect();
+    }
+    value
+}
+
+fn pretty(value: Value) -> MCPResult<String> {
+    serde_json::to_string_pretty(&value).map_err(|e| mcp_error(e.to_string()))
+}
+
+/// Scripted MCP server: answers tool calls from the fake filesystem.
+/// Writes, moves and edits change the in-memory tree only.
+pub fn mcp_tool(tool: &str, args: &Value) -> MCPResult<String> {
+    if matches!(tool, "write_file" | "move_file" | "create_directory" | "save_report")
+        || (tool == "edit_file" && args.get("dry_run").and_then(|v| v.as_bool()) != Some(true))
+    {
+        crate::audit::ensure_writable_mcp()?;
+    }
+    let mut fs = FAKE_FS.lock().unwrap();
+    let path = || arg(args, "path").map(normalize);
+
+    match tool {
+        "read_file" => {
+            let path = path()?;
+            match fs.get(&path) {
+                Some(Entry { content: Some(content), .. }) => Ok(content.clone()),
+                Some(Entry { is_dir: false, size, .. }) => Ok(format!("<binary fixture file, {} bytes>", size)),
+                _ => Err(mcp_error(not_found(&path))),
+            }
+        }
+        "read_multiple_files" => {
+            let paths = args.get("paths").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+            let results: Vec<Value> = paths
+                .iter()
+                .filter_map(|p| p.as_str())
+                .map(|p| match fs.get(&normalize(p)).and_then(|e| e.content.clone()) {
+                    Some(content) => json!({ "path": p, "content": content }),
+                    None => json!({ "path": p, "error": not_found(p) }),
+                })
+                .collect();
+            pretty(json!(results))
+        }
+        "write_file" => {
+            let path = path()?;
+            let content = arg(args, "content")?.to_string();
+            add_parents(&mut fs, &path);
+            fs.insert(path, Entry { is_dir: false, size: content.len() as u64, content: Some(content) });
+            Ok("File written successfully".to_string())
+        }
+        "save_report" => {
+            let path = path()?;
+            let title = arg(args, "title")?;
+            let sections: Vec<ReportSection> = args
+                .get("sections")
+                .cloned()
+                .map(serde_json::from_value)
+                .transpose()
+                .map_err(|e| mcp_error(e.to_string()))?
+                .unwrap_or_default();
+            let (content, format) = if path.ends_with(".json") {
+                (report::render_json(title, "fixture", &sections), "json")
+            } else {
+                (report::render_markdown(title, "fixture", &sections), "markdown")
+            };
+            let bytes = content.len();
+            add_parents(&mut fs, &path);
+            fs.insert(path.clone(), Entry { is_dir: false, size: bytes as u64, content: Some(content) });
+            Ok(format!("Report saved to {} ({} bytes, {})", path, bytes, format))
+        }
+        "edit_file" => {
+            let path = path()?;
+            let (old_text, new_text) = (arg(args, "old_text")?, arg(args, "new_text")?);
+            let dry_run = args.get("dry_run").and_then(|v| v.as_bool()) == Some(true);
+            let entry = fs.get_mut(&path).ok_or_else(|| mcp_error(not_found(&path)))?;
+            let content = entry.content.clone().unwrap_or_default();
+            if !content.contains(old_text) {
+                return Err(mcp_error(format!("Text not found in {}", path)));
+            }
+            let edited = content.replacen(old_text, new_text, 1);
+            if !dry_run {
+                entry.size = edited.len() as u64;
+                entry.content = Some(edited);
+            }
+            pretty(json!({ "path": path, "applied": !dry_run }))
+        }
+        "list_directory" => {
+            let path = path()?;
+            let entries: Vec<Value> = children(&fs, &path).into_iter().map(|(p, e)| file_info(p, e)).collect();
+            if entries.is_empty() && !fs.contains_key(&path) {
+                return Err(mcp_error(not_found(&path)));
+            }
+            pretty(json!(entries))
+        }
+        "get_file_info" => {
+            let path = path()?;
+            let entry = fs.get(&path).ok_or_else(|| mcp_error(not_found(&path)))?;
+            pretty(file_info(&path, entry))
+        }
+        "search_files" => {
+            let path = normalize(arg(args, "directory")?);
+            let pattern = arg(args, "pattern")?.to_lowercase();
+            let prefix = format!("{}/", path);
+            let matches: Vec<&String> = fs
+                .keys()
+                .filter(|p| p.starts_with(&prefix) && name(p).to_lowercase().contains(&pattern))
+                .collect();
+            pretty(json!(matches))
+        }
+        "get_directory_size" => {
+            let path = path()?;
+            if !fs.contains_key(&path) {
+                return Err(mcp_error(not_found(&path)));
+            }
+            let (size, files) = subtree(&fs, &path);
+            pretty(json!({ "path": path, "total_size": size, "file_count": files }))
+        }
+        "directory_tree" => {
+            let path = path()?;
+            if !fs.contains_key(&path) {
+                return Err(mcp_error(not_found(&path)));
+            }
+            pretty(tree(&fs, &path))
+        }
+        "compare_directories" => {
+            let (a, b) = (normalize(arg(args, "a")?), normalize(arg(args, "b")?));
+            for root in [&a, &b] {
+                if !fs.get(root).is_some_and(|e| e.is_dir) {
+                    return Err(mcp_error(not_found(root)));
+                }
+            }
+            let relative = |root: &str| -> BTreeMap<String, (bool, u64)> {
+                let prefix = format!("{}/", root);
+                fs.iter()
+                    .filter_map(|(p, e)| Some((p.strip_prefix(&prefix)?.to_string(), (e.is_dir, e.size))))
+                    .collect()
+            };
+            let (items_a, items_b) = (relative(&a), relative(&b));
+            let only = |side: &BTreeMap<String, (bool, u64)>, other: &BTreeMap<String, (bool, u64)>| -> Vec<Value> {
+                side.iter()
+                    .filter(|(p, _)| !other.contains_key(*p))
+                    .map(|(p, (is_dir, size))| json!({ "path": p, "isDir": is_dir, "size": size }))
+                    .collect()
+            };
+            let different: Vec<Value> = items_a
+                .iter()
+                .filter_map(|(p, (dir_a, size_a))| {
+                    let (dir_b, size_b) = items_b.get(p)?;
+                    let reason = if dir_a != dir_b { "type" } else if !dir_a && size_a != size_b { "size" } else { return None };
+                    Some(json!({ "path": p, "reason": reason, "sizeA": size_a, "sizeB": size_b }))
+                })
+                .collect();
+            let (only_in_a, only_in_b) = (only(&items_a, &items_b), only(&items_b, &items_a));
+            pretty(json!({
+                "a": a,
+                "b": b,
+                "mode": "size",
+                "bContainsA": only_in_a.is_empty() && different.is_empty(),
+                "onlyInA": only_in_a,
+                "onlyInB": only_in_b,
+                "different": different,
+            }))
+        }
+        "create_directory" => {
+            let path = path()?;
+            add_parents(&mut fs, &format!("{}/_", path));
+            Ok(format!("Created directory {}", path))
+        }
+        "move_file" => {
+            let (source, destination) = (normalize(arg(args, "from")?), normalize(arg(args, "to")?));
+            if !fs.contains_key(&source) {
+                return Err(mcp_error(not_found(&source)));
+            }
+            let prefix = format!("{}/", source);
+            let moved: Vec<String> = fs.keys().filter(|p| **p == source || p.starts_with(&prefix)).cloned().collect();
+            for old in moved {
+                let entry = fs.remove(&old).unwrap();
+                fs.insert(format!("{}{}", destination, &old[source.len()..]), entry);
+            }
+            add_parents(&mut fs, &destination);
+            Ok(format!("Moved {} to {}", source, destination))
+        }
+        "inspect_disk_image" => {
+            // Fixture images are plain entries; report them as empty
+            let path = path()?;
+            let entry = fs.get(&path).ok_or_else(|| mcp_error(not_found(&path)))?;
+            pretty(json!({
+                "path": path,
+                "format": path.rsplit('.').next().unwrap_or_default().to_lowercase(),
+                "imageSize": entry.size,
+                "method": "listed",
+                "fileCount": 0,
+                "folderCount": 0,
+                "contentSize": 0,
+                "topLevel": [],
+                "largestFiles": [],
+            }))
+        }
+        "ui_action" => {
+            // Nothing to drive without a window; only check the paths exist
+            let mut paths: Vec<&str> = args.get("paths").and_then(|v| v.as_array()).into_iter().flatten().filter_map(|p| p.as_str()).collect();
+            paths.extend(args.get("path").and_then(|v| v.as_str()));
+            if let Some(missing) = paths.into_iter().map(normalize).find(|p| !fs.contains_key(p)) {
+                return Err(mcp_error(not_found(&missing)));
+            }
+            arg(args, "action").map(|_| "Shown in the app.".to_string())
+        }
+        "list_allowed_directories" => pretty(json!([FIXTURE_ROOT])),
+        _ => Err(mcp_error(format!("Unknown tool: {}", tool))),
+    }
+}
+
+/// Canned model standing in for a real provider
+pub struct FixtureProvider {
+    pub kind: ModelProvider,
+}
+
+impl FixtureProvider {
+    fn model(&self) -> ModelConfig {
+        ModelConfig {
+            id: format!("fixture-{}", self.kind.id()),
+            name: "Fixture Model".to_string(),
+            provider: self.kind.clone(),
+            model_id: "fixture-model".to_string(),
+            parameters: ModelParameters {
+                temperature: 0.0,
+                top_p: 1.0,
+                max_tokens: 512,
+                stream: true,
+                stop_sequences: None,
+                context_window: Some(4096),
+            },
+            endpoint: None,
+            api_key: None,
+            is_available: true,
+            size_bytes: None,
+            recommended_for: vec![AIMode::QA, AIMode::Agent],
+            adapter: None,
+        }
+    }
+}
+
+fn reply_to(request: &InferenceRequest) -> String {
+    let question = request
+        .messages
+        .iter()
+        .rev()
+        .find(|m| m.role == MessageRole::User)
+        .map(|m| m.content.clone())
+        .unwrap_or_default();
+    let lower = question.to_lowercase();
+    SCRIPTED_REPLIES
+        .iter()
+        .find(|(word, _)| lower.contains(word))
+        .map(|(_, reply)| reply.to_string())
+        .unwrap_or_else(|| format!("Fixture reply to: {}", question))
+}
+
+#[async_trait]
+impl AIProvider for FixtureProvider {
+    fn kind(&self) -> ModelProvider {
+        self.kind.clone()
+    }
+
+    fn supports_tools(&self) -> bool {
+        true
+    }
+
+    fn supports_images(&self) -> bool {
+        true
+    }
+
+    async fn status(&self, _endpoint: Option<&str>) -> ProviderStatus {
+        ProviderStatus {
+            provider: self.kind.clone(),
+            is_available: true,
+            version: Some("fixture".to_string()),
+            available_models: vec![self.model()],
+            error: None,
+        }
+    }
+
+    async fn list_models(&self, _endpoint: Option<&str>) -> Result<Vec<ModelConfig>, AIError> {
+        Ok(vec![self.model()])
+    }
+
+    async fn infer_stream(
+        &self,
+        request: &InferenceRequest,
+        sink: InferenceSink,
+        cancel: CancellationToken,
+    ) -> Result<InferenceResponse, AIError> {
+        // With tools offered, the first turn asks to list the fixture root
+        let wants_tool = request.tools.is_some() && request.messages.last().is_some_and(|m| m.role == MessageRole::User);
+        let (content, tool_calls) = if wants_tool {
+            let call = OpenAIToolCall {
+                id: "call_fixture_1".to_string(),
+                r#type: "function".to_string(),
+                function: OpenAIToolCallFunction {
+                    name: "list_directory".to_string(),
+                    arguments: json!({ "path": FIXTURE_ROOT }).to_string(),
+                },
+            };
+            (String::new(), Some(vec![call]))
+        } else {
+            let reply = reply_to(request);
+            for word in reply.split_inclusive(' ') {
+                if cancel.is_cancelled() {
+                    return Err(AIError {
+                        error_type: AIErrorType::InferenceFailed,
+                        message: "Inference cancelled".to_string(),
+                        details: None,
+                        suggested_actions: None,
+                    });
+                }
+                sink.chunk(word);
+            }
+            (reply, None)
+        };
+
+        let prompt_tokens = request.messages.iter().map(|m| usage::estimate_tokens(&m.content)).sum();
+        let completion_tokens = usage::estimate_tokens(&content);
+        Ok(InferenceResponse {
+            message: ChatMessage {
+                id: "msg-fixture".to_string(),
+                role: MessageRole::Assistant,
+                content,
+                timestamp: FIXTURE_MTIME as i64 * 1000,
+                context_paths: None,
+                is_streaming: None,
+                error: None,
+                tool_calls,
+                images: None,
+                interrupted: None,
+            },
+            is_complete: true,
+            usage: Some(TokenUsage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+            }),
+            inference_time_ms: Some(0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands;
+    use crate::operations::OperationManager;
+    use crate::remote::RemoteStore;
+    use crate::telemetry::Telemetry;
+    use tauri::async_runtime::block_on;
+    use tauri::test::MockRuntime;
+    use tauri::{App, Manager};
+
+    /// Tests share the fake filesystem
+    static FAKE_FS_USERS: Mutex<()> = Mutex::new(());
+
+    fn mock_app(dir: &std::path::Path) -> App<MockRuntime> {
+        let app = tauri::test::mock_app();
+        app.manage(OperationManager::default());
+        app.manage(Telemetry::load(dir.join("telemetry.json")));
+        app.manage(RemoteStore::load(dir.join("remotes.json")));
+        app
+    }
+
+    #[test]
+    fn scan_sizes_match_fixture_files() {
+        let _fs = FAKE_FS_USERS.lock().unwrap();
+        let root = scan(FIXTURE_ROOT).unwrap();
+        let downloads = root.children.unwrap().into_iter().find(|c| c.name == "Downloads").unwrap();
+        assert_eq!(downloads.size, 573 * MB);
+        assert_eq!(downloads.file_count, 3);
+        assert!(downloads.children.is_some());
+    }
+
+    #[test]
+    fn scripted_mcp_writes_stay_in_memory() {
+        let _fs = FAKE_FS_USERS.lock().unwrap();
+        reset();
+        let path = format!("{}/Documents/new.txt", FIXTURE_ROOT);
+        mcp_tool("write_file", &json!({ "path": path, "content": "hi" })).unwrap();
+        assert_eq!(mcp_tool("read_file", &json!({ "path": path })).unwrap(), "hi");
+        assert!(!std::path::Path::new(&path).exists());
+        reset();
+        assert!(mcp_tool("read_file", &json!({ "path": path })).is_err());
+    }
+
+    #[test]
+    fn listing_commands_answer_from_the_fake_filesystem() {
+        let _fs = FAKE_FS_USERS.lock().unwrap();
+        reset();
+        let dir = tempfile::tempdir().unwrap();
+        let app = mock_app(dir.path());
+        let operations = || app.state::<OperationManager>();
+        let downloads = format!("{}/Downloads", FIXTURE_ROOT);
+
+        let drives = block_on(commands::get_drives(app.state::<RemoteStore>())).unwrap();
+        assert_eq!(drives[0].path, FIXTURE_ROOT);
+
+        let listing = block_on(commands::scan_dir_shallow(app.handle().clone(), FIXTURE_ROOT.to_string(), 1, operations())).unwrap();
+        let children = listing.children.unwrap();
+        assert_eq!(children[0].name, "Downloads");
+        assert!(children.iter().all(|c| c.children.is_none()));
+
+        let stats = block_on(commands::get_deep_stats(app.handle().clone(), downloads.clone(), operations())).unwrap();
+        assert_eq!((stats.size, stats.file_count), (573 * MB, 3));
+
+        let largest = block_on(commands::get_largest_files(app.handle().clone(), FIXTURE_ROOT.to_string(), Some(2), operations())).unwrap();
+        let names: Vec<String> = largest.iter().map(|f| name(&f.path)).collect();
+        assert_eq!(names, ["installer.dmg", "app"]);
+
+        let types = block_on(commands::get_type_breakdown(app.handle().clone(), downloads, operations())).unwrap();
+        assert_eq!(types.extensions[0].name, "dmg");
+        assert_eq!(types.total_size, 573 * MB);
+
+        // Every operation finished and left the registry
+        assert!(operations().list().is_empty());
+    }
+
+    #[test]
+    fn deletions_change_only_the_fake_filesystem() {
+        let _fs = FAKE_FS_USERS.lock().unwrap();
+        reset();
+        let dir = tempfile::tempdir().unwrap();
+        let app = mock_app(dir.path());
+        let operations = || app.state::<OperationManager>();
+        let photo = format!("{}/Downloads/photo.jpg", FIXTURE_ROOT);
+
+        commands::delete_item(app.handle().clone(), photo.clone(), None, operations()).unwrap();
+        let stats = block_on(commands::get_deep_stats(app.handle().clone(), format!("{}/Downloads", FIXTURE_ROOT), operations())).unwrap();
+        assert_eq!(stats.size, 570 * MB);
+        assert!(commands::delete_item(app.handle().clone(), photo, None, operations()).is_err());
+
+        let junk = block_on(commands::scan_junk(app.handle().clone(), operations())).unwrap();
+        let paths: Vec<String> = junk[0].items.iter().map(|i| i.path.clone()).collect();
+        assert_eq!(paths.len(), 1);
+        let cleaned = block_on(commands::clean_junk(app.handle().clone(), paths, Some(true), operations())).unwrap();
+        assert!(cleaned.record.is_none());
+        assert!(block_on(commands::scan_junk(app.handle().clone(), operations())).unwrap()[0].items.is_empty());
+
+        assert!(operations().list().is_empty());
+        reset();
+    }
+}
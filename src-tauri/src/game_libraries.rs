@@ -0,0 +1,314 @@
+// Game Libraries - Installed Steam and Epic Games titles
+//
+// Games are among the largest installs on a home machine and are easy to
+// forget about: a title finished two years ago can still take 100 GB. Steam
+// lists its library folders in steamapps/libraryfolders.vdf and describes
+// each installed game in an appmanifest_<id>.acf next to it, with its size
+// and when it was last played. The Epic Games Launcher keeps one JSON .item
+// manifest per game instead, without play times; there the install folder's
+// last change stands in.
+//
+// Games nobody has played for a year form their own junk category. Cleaning
+// one also removes its launcher manifest, so the launcher lists the game as
+// not installed (it can be downloaded again) rather than as broken.
+
+use crate::cleaner::{JunkCategory, JunkItem};
+use crate::i18n;
+use crate::paths;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+/// Junk category of games not played for UNPLAYED_DAYS
+pub const UNPLAYED_CATEGORY: &str = "unplayed_games";
+const UNPLAYED_DAYS: u64 = 365;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameStore {
+    Steam,
+    Epic,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Game {
+    pub store: GameStore,
+    /// Steam app id or Epic app name
+    pub id: String,
+    pub name: String,
+    pub install_dir: String,
+    pub size: u64,
+    /// Unix timestamp in seconds; None if the launcher doesn't record it or
+    /// the game was never started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_played: Option<u64>,
+    /// Launcher file describing the install
+    pub manifest: String,
+}
+
+impl Game {
+    /// When the game was last played, or else last changed on disk
+    fn last_used(&self) -> u64 {
+        self.last_played.unwrap_or_else(|| {
+            std::fs::metadata(paths::extended(Path::new(&self.install_dir)))
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs())
+        })
+    }
+}
+
+/// Key/value pairs of a Valve KeyValues (.vdf/.acf) file with the depth each
+/// was found at, in file order. Sections are not kept; the keys needed here
+/// are unique enough without them.
+fn vdf_pairs(text: &str) -> Vec<(usize, String, String)> {
+    let mut pairs = Vec::new();
+    let mut depth: usize = 0;
+    let mut key: Option<String> = None;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                depth += 1;
+                key = None;
+            }
+            '}' => {
+                depth = depth.saturating_sub(1);
+                key = None;
+            }
+            '"' => {
+                let mut token = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => token.extend(chars.next()),
+                        c => token.push(c),
+                    }
+                }
+                match key.take() {
+                    Some(key) => pairs.push((depth, key, token)),
+                    None => key = Some(token),
+                }
+            }
+            _ => {}
+        }
+    }
+    pairs
+}
+
+/// Value of a top-level key of a single-section file like an .acf
+fn vdf_value<'a>(pairs: &'a [(usize, String, String)], key: &str) -> Option<&'a str> {
+    pairs
+        .iter()
+        .find(|(depth, k, _)| *depth == 1 && k.eq_ignore_ascii_case(key))
+        .map(|(_, _, v)| v.as_str())
+}
+
+/// Where Steam may be installed on this platform
+fn steam_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    if cfg!(target_os = "windows") {
+        if let Ok(output) = std::process::Command::new("reg")
+            .args(["query", r"HKCU\Software\Valve\Steam", "/v", "SteamPath"])
+            .output()
+        {
+            let text = String::from_utf8_lossy(&output.stdout);
+            if let Some(path) = text.lines().find_map(|l| l.split_once("REG_SZ").map(|(_, v)| v.trim().to_string())) {
+                roots.push(PathBuf::from(path));
+            }
+        }
+        roots.push(PathBuf::from(r"C:\Program Files (x86)\Steam"));
+    } else if cfg!(target_os = "macos") {
+        roots.extend(dirs::data_dir().map(|d| d.join("Steam")));
+    } else {
+        roots.extend(dirs::home_dir().map(|d| d.join(".steam").join("steam")));
+        roots.extend(dirs::data_dir().map(|d| d.join("Steam")));
+        roots.extend(dirs::home_dir().map(|d| d.join(".var/app/com.valvesoftware.Steam/.local/share/Steam")));
+    }
+    let mut found: Vec<PathBuf> = Vec::new();
+    for root in roots {
+        if root.join("steamapps").is_dir() && !found.iter().any(|f| paths::same(f, &root)) {
+            found.push(root);
+        }
+    }
+    found
+}
+
+/// Steam library folders: each root's own, plus those in libraryfolders.vdf
+fn steam_libraries() -> Vec<PathBuf> {
+    let mut libraries: Vec<PathBuf> = Vec::new();
+    for root in steam_roots() {
+        let listed = std::fs::read_to_string(root.join("steamapps").join("libraryfolders.vdf"))
+            .map(|text| {
+                vdf_pairs(&text)
+                    .into_iter()
+                    .filter(|(_, key, _)| key.eq_ignore_ascii_case("path"))
+                    .map(|(_, _, path)| PathBuf::from(path))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        for library in std::iter::once(root).chain(listed) {
+            if library.join("steamapps").is_dir() && !libraries.iter().any(|l| paths::same(l, &library)) {
+                libraries.push(library);
+            }
+        }
+    }
+    libraries
+}
+
+fn steam_games() -> Vec<Game> {
+    let mut games = Vec::new();
+    for library in steam_libraries() {
+        let steamapps = library.join("steamapps");
+        let Ok(entries) = std::fs::read_dir(&steamapps) else { continue };
+        for entry in entries.flatten() {
+            let manifest = entry.path();
+            let is_manifest = manifest
+                .file_name()
+                .map(|n| n.to_string_lossy().to_lowercase())
+                .is_some_and(|n| n.starts_with("appmanifest_") && n.ends_with(".acf"));
+            if !is_manifest {
+                continue;
+            }
+            let Ok(text) = std::fs::read_to_string(&manifest) else { continue };
+            let pairs = vdf_pairs(&text);
+            let (Some(id), Some(dir)) = (vdf_value(&pairs, "appid"), vdf_value(&pairs, "installdir")) else {
+                warn!("Unreadable Steam manifest {}", manifest.display());
+                continue;
+            };
+            let install_dir = steamapps.join("common").join(dir);
+            let size = vdf_value(&pairs, "SizeOnDisk")
+                .and_then(|s| s.parse().ok())
+                .filter(|&s: &u64| s > 0)
+                .unwrap_or_else(|| fs_extra::dir::get_size(&install_dir).unwrap_or(0));
+            games.push(Game {
+                store: GameStore::Steam,
+                id: id.to_string(),
+                name: vdf_value(&pairs, "name").unwrap_or(dir).to_string(),
+                install_dir: paths::display(&install_dir),
+                size,
+                last_played: vdf_value(&pairs, "LastPlayed").and_then(|s| s.parse().ok()).filter(|&t: &u64| t > 0),
+                manifest: paths::display(&manifest),
+            });
+        }
+    }
+    games
+}
+
+/// Folder holding the Epic Games Launcher's install manifests
+fn epic_manifests() -> Option<PathBuf> {
+    let dir = if cfg!(target_os = "windows") {
+        PathBuf::from(std::env::var("PROGRAMDATA").unwrap_or_else(|_| r"C:\ProgramData".to_string()))
+            .join("Epic")
+            .join("EpicGamesLauncher")
+    } else if cfg!(target_os = "macos") {
+        dirs::data_dir()?.join("Epic").join("EpicGamesLauncher")
+    } else {
+        return None;
+    };
+    Some(dir.join("Data").join("Manifests")).filter(|d| d.is_dir())
+}
+
+fn epic_games() -> Vec<Game> {
+    let Some(dir) = epic_manifests() else { return Vec::new() };
+    let Ok(entries) = std::fs::read_dir(&dir) else { return Vec::new() };
+    let mut games = Vec::new();
+    for entry in entries.flatten() {
+        let manifest = entry.path();
+        if !manifest.extension().is_some_and(|e| e.eq_ignore_ascii_case("item")) {
+            continue;
+        }
+        let Some(item) = std::fs::read_to_string(&manifest)
+            .ok()
+            .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        else {
+            warn!("Unreadable Epic manifest {}", manifest.display());
+            continue;
+        };
+        let text = |key: &str| item.get(key).and_then(|v| v.as_str()).map(String::from);
+        let Some(install_dir) = text("InstallLocation") else { continue };
+        let size = item
+            .get("InstallSize")
+            .and_then(|v| v.as_u64())
+            .filter(|&s| s > 0)
+            .unwrap_or_else(|| fs_extra::dir::get_size(&install_dir).unwrap_or(0));
+        games.push(Game {
+            store: GameStore::Epic,
+            id: text("AppName").unwrap_or_default(),
+            name: text("DisplayName").or_else(|| text("AppName")).unwrap_or_default(),
+            install_dir: paths::display(Path::new(&install_dir)),
+            size,
+            last_played: None,
+            manifest: paths::display(&manifest),
+        });
+    }
+    games
+}
+
+/// Installed games of every launcher found, largest first
+pub fn find_games() -> Vec<Game> {
+    let mut games = steam_games();
+    games.extend(epic_games());
+    games.retain(|g| Path::new(&g.install_dir).is_dir());
+    games.sort_by(|a, b| b.size.cmp(&a.size));
+    info!("Found {} installed games", games.len());
+    games
+}
+
+/// Junk category of games not played for a year, if there are any
+pub fn unplayed_category(games: &[Game]) -> Option<JunkCategory> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let cutoff = now.saturating_sub(UNPLAYED_DAYS * 86_400);
+    let name = i18n::t(&format!("junk.{}", UNPLAYED_CATEGORY));
+    let items: Vec<JunkItem> = games
+        .iter()
+        .filter(|g| g.last_used() < cutoff)
+        .map(|g| {
+            let description = match g.last_played {
+                Some(played) => {
+                    let days = ((now.saturating_sub(played)) / 86_400).to_string();
+                    i18n::t_with("junk.game_last_played", &[("days", &days)])
+                }
+                None => i18n::t("junk.game_untouched"),
+            };
+            JunkItem {
+                path: g.install_dir.clone(),
+                name: g.name.clone(),
+                size: g.size,
+                description,
+            }
+        })
+        .collect();
+    if items.is_empty() {
+        return None;
+    }
+    Some(JunkCategory {
+        id: UNPLAYED_CATEGORY.to_string(),
+        name,
+        description: i18n::t("junk.unplayed_games_description"),
+        total_size: items.iter().map(|i| i.size).sum(),
+        items,
+        icon: UNPLAYED_CATEGORY.to_string(),
+        risky: false,
+        provider: None,
+        too_recent: 0,
+    })
+}
+
+/// Remove the launcher manifests of the games installed in `install_dirs`,
+/// so the launchers stop listing them once their folders are deleted
+pub fn forget(install_dirs: &[String]) {
+    let games = steam_games().into_iter().chain(epic_games());
+    for game in games.filter(|g| install_dirs.iter().any(|d| paths::same(Path::new(d), Path::new(&g.install_dir)))) {
+        match std::fs::remove_file(paths::extended(Path::new(&game.manifest))) {
+            Ok(()) => info!("Removed {} manifest of {}", game.manifest, game.name),
+            Err(e) => warn!("Could not remove manifest {}: {}", game.manifest, e),
+        }
+    }
+}
+
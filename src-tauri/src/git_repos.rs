@@ -0,0 +1,192 @@
+// Git Repos - Repository awareness for scans
+//
+// Developer folders are mostly git checkouts, and a checkout's size is split
+// between the working tree, the object store in .git and ignored bulk such
+// as build outputs and dependency folders. This finds the repositories under
+// a scanned folder, breaks each one down that way using `git status`, and
+// can run `git gc` on an oversized .git directory. Git must be installed for
+// the untracked/ignored breakdown and for gc.
+
+use crate::scanner::Exclusions;
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+/// .git directories above this size are candidates for gc
+const GC_SIZE_THRESHOLD: u64 = 256 * 1024 * 1024;
+
+/// Largest untracked / ignored entries reported per repository
+const TOP_ENTRIES: usize = 10;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepoEntry {
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRepo {
+    pub path: String,
+    pub name: String,
+    /// Size of the .git directory (0 for worktrees and submodules, whose
+    /// objects live elsewhere)
+    pub git_dir_size: u64,
+    /// Everything outside .git, including untracked and ignored files
+    pub working_tree_size: u64,
+    pub untracked_size: u64,
+    pub ignored_size: u64,
+    /// Largest first
+    pub largest_untracked: Vec<RepoEntry>,
+    /// Largest first; typically build outputs and dependency folders
+    pub largest_ignored: Vec<RepoEntry>,
+    /// .git is large and outweighs the tracked files; `git gc` may help
+    pub gc_suggested: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcResult {
+    pub path: String,
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+fn dir_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Untracked ("??") and ignored ("!!") entries, collapsed to directories
+fn status_entries(repo: &Path) -> Result<(Vec<String>, Vec<String>), String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["status", "--porcelain=v1", "-z", "--ignored=matching", "--untracked-files=normal"])
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let mut untracked = Vec::new();
+    let mut ignored = Vec::new();
+    for record in String::from_utf8_lossy(&output.stdout).split('\0') {
+        if let Some(path) = record.strip_prefix("?? ") {
+            untracked.push(path.to_string());
+        } else if let Some(path) = record.strip_prefix("!! ") {
+            ignored.push(path.to_string());
+        }
+    }
+    Ok((untracked, ignored))
+}
+
+/// Sizes of status entries, largest first, and their total
+fn sized(repo: &Path, entries: Vec<String>) -> (u64, Vec<RepoEntry>) {
+    let mut sized: Vec<RepoEntry> = entries
+        .into_iter()
+        .map(|relative| {
+            let path = repo.join(relative.trim_end_matches('/'));
+            RepoEntry {
+                size: dir_size(&path),
+                path: path.to_string_lossy().to_string(),
+            }
+        })
+        .collect();
+    sized.sort_by(|a, b| b.size.cmp(&a.size));
+    let total = sized.iter().map(|e| e.size).sum();
+    sized.truncate(TOP_ENTRIES);
+    (total, sized)
+}
+
+fn analyze(repo: &Path) -> GitRepo {
+    let git_dir = repo.join(".git");
+    let git_dir_size = if git_dir.is_dir() { dir_size(&git_dir) } else { 0 };
+    let working_tree_size: u64 = WalkDir::new(repo)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum();
+
+    let (untracked, ignored) = status_entries(repo).unwrap_or_else(|e| {
+        warn!("git status failed in {}: {}", repo.display(), e);
+        (Vec::new(), Vec::new())
+    });
+    let (untracked_size, largest_untracked) = sized(repo, untracked);
+    let (ignored_size, largest_ignored) = sized(repo, ignored);
+    let tracked_size = working_tree_size.saturating_sub(untracked_size + ignored_size);
+
+    GitRepo {
+        path: repo.to_string_lossy().to_string(),
+        name: repo.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        git_dir_size,
+        working_tree_size,
+        untracked_size,
+        ignored_size,
+        largest_untracked,
+        largest_ignored,
+        gc_suggested: git_dir_size > GC_SIZE_THRESHOLD && git_dir_size > tracked_size,
+    }
+}
+
+/// Git repositories under `root` (including `root` itself), largest first.
+/// Nested repositories such as submodules are reported separately.
+pub fn find_repos(root: &str, exclusions: &Exclusions, cancel: &AtomicBool) -> Result<Vec<GitRepo>, String> {
+    let root = Path::new(root);
+    if !root.is_dir() {
+        return Err("Directory does not exist".to_string());
+    }
+
+    let mut repos = Vec::new();
+    let walker = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git" && !exclusions.is_excluded(e.path()));
+    for entry in walker.filter_map(|e| e.ok()).filter(|e| e.file_type().is_dir()) {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".to_string());
+        }
+        if entry.path().join(".git").exists() {
+            repos.push(analyze(entry.path()));
+        }
+    }
+    repos.sort_by(|a, b| (b.git_dir_size + b.working_tree_size).cmp(&(a.git_dir_size + a.working_tree_size)));
+    info!("Found {} git repositories under {}", repos.len(), root.display());
+    Ok(repos)
+}
+
+/// Run `git gc` in a repository; `prune_now` also drops unreachable objects
+/// immediately instead of after the default two weeks
+pub fn gc(repo: &str, prune_now: bool) -> Result<GcResult, String> {
+    let git_dir = Path::new(repo).join(".git");
+    if !git_dir.is_dir() {
+        return Err(format!("{} is not a git repository", repo));
+    }
+    let size_before = dir_size(&git_dir);
+    let mut cmd = Command::new("git");
+    cmd.arg("-C").arg(repo).args(["gc", "--quiet"]);
+    if prune_now {
+        cmd.arg("--prune=now");
+    }
+    let output = cmd.output().map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("git gc failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let size_after = dir_size(&git_dir);
+    info!("git gc in {}: {} -> {} bytes", repo, size_before, size_after);
+    Ok(GcResult {
+        path: repo.to_string(),
+        size_before,
+        size_after,
+    })
+}
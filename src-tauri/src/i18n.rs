@@ -0,0 +1,87 @@
+// I18n - Message catalogs for backend-generated text
+//
+// User-facing strings built in Rust (junk category names, file errors, tool
+// descriptions) are looked up by key in a per-locale catalog under
+// `locales/`. The frontend sets the locale; anything a catalog lacks falls
+// back to English. Placeholders are written `{name}`.
+
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Locale used when none is set and for missing translations
+pub const DEFAULT_LOCALE: &str = "en";
+
+type Catalog = HashMap<String, String>;
+
+lazy_static! {
+    static ref CATALOGS: HashMap<&'static str, Catalog> = [
+        ("en", include_str!("../locales/en.json")),
+        ("es", include_str!("../locales/es.json")),
+        ("de", include_str!("../locales/de.json")),
+    ]
+    .into_iter()
+    .map(|(locale, source)| (locale, serde_json::from_str(source).expect("invalid message catalog")))
+    .collect();
+    static ref LOCALE: RwLock<&'static str> = RwLock::new(DEFAULT_LOCALE);
+}
+
+/// Locales with a catalog
+pub fn available_locales() -> Vec<&'static str> {
+    let mut locales: Vec<_> = CATALOGS.keys().copied().collect();
+    locales.sort();
+    locales
+}
+
+/// Catalog locale for a BCP 47 tag ("de-AT" -> "de")
+fn resolve(tag: &str) -> Option<&'static str> {
+    let language = tag.split(['-', '_']).next()?.to_ascii_lowercase();
+    CATALOGS.keys().copied().find(|l| *l == language)
+}
+
+/// Switch the active locale
+pub fn set_locale(tag: &str) -> Result<&'static str, String> {
+    let locale = resolve(tag).ok_or_else(|| t_with("error.unsupported_locale", &[("locale", tag)]))?;
+    *LOCALE.write().unwrap() = locale;
+    Ok(locale)
+}
+
+pub fn locale() -> &'static str {
+    *LOCALE.read().unwrap()
+}
+
+/// Translation in the active locale only, without the English fallback
+pub fn lookup(key: &str) -> Option<String> {
+    CATALOGS.get(locale())?.get(key).cloned()
+}
+
+/// Message for `key` in the active locale; the key itself if no catalog has it
+pub fn t(key: &str) -> String {
+    lookup(key)
+        .or_else(|| CATALOGS.get(DEFAULT_LOCALE)?.get(key).cloned())
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// Message for `key` with `{name}` placeholders filled in
+pub fn t_with(key: &str, args: &[(&str, &str)]) -> String {
+    args.iter()
+        .fold(t(key), |message, (name, value)| message.replace(&format!("{{{}}}", name), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_every_locale_has_the_english_keys() {
+        let keys = |locale: &str| CATALOGS[locale].keys().cloned().collect::<BTreeSet<_>>();
+        let english = keys(DEFAULT_LOCALE);
+        for locale in available_locales() {
+            let translated = keys(locale);
+            let missing: Vec<_> = english.difference(&translated).collect();
+            let extra: Vec<_> = translated.difference(&english).collect();
+            assert!(missing.is_empty() && extra.is_empty(), "{}: missing {:?}, not in en.json {:?}", locale, missing, extra);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// Junk Age - Minimum age of junk items, per category
+//
+// A temp file written a minute ago most likely belongs to a program that is
+// still running, and a log file that changed today is still being appended
+// to. Each built-in junk category can have a minimum age: an item is only
+// listed once nothing in it has changed for that long (for a folder, its
+// most recently modified file counts). Items held back are counted per
+// category so the cleaner can say why it found less than expected.
+
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JunkAgeSettings {
+    /// Minimum age in hours by category ID; categories not listed have none
+    #[serde(default)]
+    pub min_age_hours: BTreeMap<String, u64>,
+}
+
+impl Default for JunkAgeSettings {
+    fn default() -> Self {
+        let defaults = [
+            ("temp", 48),
+            ("windows_temp", 48),
+            ("system_cache", 24),
+            ("logs", 30 * 24),
+            ("system_logs", 30 * 24),
+        ];
+        Self {
+            min_age_hours: defaults.into_iter().map(|(id, hours)| (id.to_string(), hours)).collect(),
+        }
+    }
+}
+
+impl JunkAgeSettings {
+    /// Minimum age of items in `category`, if it has one
+    pub fn min_age(&self, category: &str) -> Option<Duration> {
+        self.min_age_hours
+            .get(category)
+            .filter(|&&hours| hours > 0)
+            .map(|hours| Duration::from_secs(hours * 3600))
+    }
+}
+
+/// Age settings persisted in the app data directory
+pub struct JunkAgeStore {
+    path: PathBuf,
+    settings: Mutex<JunkAgeSettings>,
+}
+
+impl JunkAgeStore {
+    pub fn load(path: PathBuf) -> Self {
+        let settings = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        Self {
+            path,
+            settings: Mutex::new(settings),
+        }
+    }
+
+    pub fn settings(&self) -> JunkAgeSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn set_settings(&self, settings: JunkAgeSettings) -> Result<JunkAgeSettings, String> {
+        storage::save_json(&self.path, &settings)?;
+        *self.settings.lock().unwrap() = settings.clone();
+        Ok(settings)
+    }
+}
@@ -0,0 +1,186 @@
+// Junk Plugins - Junk categories contributed by external MCP servers
+//
+// The built-in cleaner only knows the caches Helium ships rules for. Users can
+// register external MCP servers (e.g. one that knows JetBrains caches) that
+// expose a `helium_junk_candidates` tool. On every junk scan each enabled
+// server is started, the tool is called without arguments, and the JSON it
+// returns, a list of `{ "path", "size", "reason", "category"? }` objects, is
+// merged into the scan results as categories labelled with the server's name.
+// Candidates that don't exist or are a root or home folder are dropped; a
+// server that fails to start or answer is logged and skipped.
+
+use crate::cleaner::{JunkCategory, JunkItem};
+use crate::mcp::client::MCPClient;
+use crate::mcp::server::MCPServer;
+use crate::mcp::types::ToolContent;
+use crate::paths;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// Tool a plugin server exposes to report junk
+pub const DISCOVERY_TOOL: &str = "helium_junk_candidates";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JunkPluginServer {
+    pub id: String,
+    /// Shown as the provenance of its categories
+    pub name: String,
+    /// Program that starts the server over stdio
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+/// One entry of the discovery tool's reply
+#[derive(Debug, Deserialize)]
+struct Candidate {
+    path: String,
+    size: u64,
+    reason: String,
+    #[serde(default)]
+    category: Option<String>,
+}
+
+fn is_protected(path: &Path) -> bool {
+    paths::is_root_or_home(path)
+}
+
+/// Ask one server for candidates
+async fn discover_server(server: &JunkPluginServer) -> Result<Vec<Candidate>, String> {
+    let client = MCPClient::new(MCPServer::with_command(server.command.clone(), server.args.clone()));
+    client.initialize().await.map_err(|e| e.message)?;
+    let tools = client.list_tools().await.map_err(|e| e.message)?;
+    if !tools.iter().any(|t| t.name == DISCOVERY_TOOL) {
+        let _ = client.shutdown().await;
+        return Err(format!("Server has no {} tool", DISCOVERY_TOOL));
+    }
+    let result = client.execute_tool(DISCOVERY_TOOL, HashMap::new()).await;
+    let _ = client.shutdown().await;
+    let result = result.map_err(|e| e.message)?;
+
+    let text: String = result
+        .content
+        .iter()
+        .filter_map(|c| match c {
+            ToolContent::Text { text } => Some(text.as_str()),
+            ToolContent::Resource { .. } => None,
+        })
+        .collect();
+    if result.is_error.unwrap_or(false) {
+        return Err(text);
+    }
+    serde_json::from_str(&text).map_err(|e| format!("Invalid candidate list: {}", e))
+}
+
+/// Group a server's candidates into categories labelled with its name
+fn categories(server: &JunkPluginServer, candidates: Vec<Candidate>) -> Vec<JunkCategory> {
+    let mut grouped: BTreeMap<String, Vec<JunkItem>> = BTreeMap::new();
+    for candidate in candidates {
+        let path = Path::new(&candidate.path);
+        if !path.is_absolute() || !path.exists() || is_protected(path) {
+            warn!("Ignoring junk candidate {} from {}", candidate.path, server.name);
+            continue;
+        }
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| candidate.path.clone());
+        grouped
+            .entry(candidate.category.unwrap_or_else(|| server.name.clone()))
+            .or_default()
+            .push(JunkItem {
+                name,
+                path: candidate.path,
+                size: candidate.size,
+                description: candidate.reason,
+            });
+    }
+    grouped
+        .into_iter()
+        .map(|(category, items)| JunkCategory {
+            id: format!("plugin:{}:{}", server.id, category),
+            description: format!("Reported by {}", server.name),
+            total_size: items.iter().map(|i| i.size).sum(),
+            items,
+            name: category,
+            icon: "plugin".to_string(),
+            risky: false,
+            provider: Some(server.name.clone()),
+            too_recent: 0,
+        })
+        .collect()
+}
+
+/// Categories from every enabled server
+pub async fn discover(servers: Vec<JunkPluginServer>) -> Vec<JunkCategory> {
+    let mut all = Vec::new();
+    for server in servers.iter().filter(|s| s.enabled) {
+        match discover_server(server).await {
+            Ok(candidates) => {
+                let found = categories(server, candidates);
+                info!("Junk plugin {} contributed {} categories", server.name, found.len());
+                all.extend(found);
+            }
+            Err(e) => warn!("Junk plugin {} failed: {}", server.name, e),
+        }
+    }
+    all
+}
+
+pub struct JunkPluginStore {
+    path: PathBuf,
+    servers: Mutex<Vec<JunkPluginServer>>,
+}
+
+impl JunkPluginStore {
+    pub fn load(path: PathBuf) -> Self {
+        let servers = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        Self {
+            path,
+            servers: Mutex::new(servers),
+        }
+    }
+
+    pub fn list(&self) -> Vec<JunkPluginServer> {
+        self.servers.lock().unwrap().clone()
+    }
+
+    /// Add a server, or replace the one with the same ID
+    pub fn save_server(&self, mut server: JunkPluginServer) -> Result<JunkPluginServer, String> {
+        if server.command.trim().is_empty() {
+            return Err("Command cannot be empty".to_string());
+        }
+        if server.id.is_empty() {
+            server.id = uuid::Uuid::new_v4().to_string();
+        }
+        let mut servers = self.servers.lock().unwrap();
+        match servers.iter_mut().find(|s| s.id == server.id) {
+            Some(existing) => *existing = server.clone(),
+            None => servers.push(server.clone()),
+        }
+        storage::save_json(&self.path, &*servers)?;
+        Ok(server)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<bool, String> {
+        let mut servers = self.servers.lock().unwrap();
+        let before = servers.len();
+        servers.retain(|s| s.id != id);
+        if servers.len() == before {
+            return Ok(false);
+        }
+        storage::save_json(&self.path, &*servers)?;
+        Ok(true)
+    }
+}
@@ -1,50 +1,314 @@
-mod scanner;
+// Fixture builds swap the disk-facing code for fakes, leaving much of it unused
+#![cfg_attr(feature = "fixtures", allow(dead_code, unused_imports))]
+
+pub mod scanner;
+pub mod scan_cache;
+mod scan_strategy;
+mod exclusions;
+mod volume_space;
+mod scan_history;
+mod cache_warmer;
+mod scan_watch;
+mod scan_sessions;
+mod size_index;
+#[cfg(windows)]
+mod ntfs_mft;
+mod remote;
+mod object_storage;
+mod git_repos;
+mod photo_bursts;
+mod age_heatmap;
+mod stale_files;
+mod basket;
+mod archive;
+mod quarantine;
+mod paths;
+mod breakdown;
+mod treemap;
+mod dir_compare;
+mod content_type;
+mod disk_images;
+mod mail_archives;
+mod game_libraries;
+mod browser_storage;
+mod safeguard;
+mod junk_plugins;
+mod junk_age;
+mod open_handles;
+mod transactions;
+mod reclaim;
+mod backups;
+mod shred;
 mod commands;
 mod ai;
 mod ai_commands;
 mod cleaner;
+mod storage;
+mod logging;
+mod i18n;
+mod operations;
+mod checkpoints;
+mod event_batch;
+mod environment;
+mod memory;
+mod permissions;
+mod updater;
+mod telemetry;
+mod profiles;
+mod alerts;
+mod wsl;
+mod snapshots;
+mod disk_activity;
+mod app_config;
+mod audit;
+mod command_scope;
+mod ui_actions;
+#[cfg(feature = "fixtures")]
+mod fixtures;
 mod mcp;
 mod mcp_commands_native; // Native Rust MCP implementation (replaces subprocess)
 
+use tauri::Manager;
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-  tauri::Builder::default()
+  let context = tauri::generate_context!();
+  // Without a key to verify them, updates can't be installed at all
+  let update_key = updater::public_key(context.config());
+  let mut builder = tauri::Builder::default();
+  if let Some(key) = &update_key {
+    builder = builder.plugin(tauri_plugin_updater::Builder::new().pubkey(key).build());
+  }
+  builder
+    .plugin(tauri_plugin_notification::init())
     .setup(|app| {
-      if cfg!(debug_assertions) {
-        app.handle().plugin(
-          tauri_plugin_log::Builder::default()
-            .level(log::LevelFilter::Info)
-            .build(),
-        )?;
-      }
+      let data_dir = storage::app_data_dir(app.handle())?;
+      mcp::notifications::init(app.handle().clone());
+      app.manage(logging::init(data_dir.join("logs")));
+      app.manage(ai::presets::PresetStore::load(data_dir.join("prompt_presets.json")));
+      app.manage(ai::conversations::ConversationStore::load(data_dir.join("conversations")));
+      app.manage(ai::agent_runs::AgentRunStore::load(data_dir.join("agent_runs")));
+      app.manage(checkpoints::CheckpointStore::load(data_dir.join("checkpoints")));
+      app.manage(ai::usage::UsageTracker::load(data_dir.join("usage.json")));
+      app.manage(ai::length_policy::LengthPolicyStore::load(data_dir.join("length_policy.json")));
+      ai::bandwidth::load(data_dir.join("download_settings.json"));
+      audit::load(data_dir.join("audit.json"));
+      audit::open_log(data_dir.join("audit_log.jsonl"));
+      transactions::recover(&data_dir.join("transactions"));
+      let telemetry = telemetry::Telemetry::load(data_dir.join("telemetry.json"));
+      telemetry.spawn_uploader();
+      app.manage(telemetry);
+      let profiles = profiles::ProfileStore::load(data_dir.join("profiles.json"));
+      let index = size_index::SizeIndex::load(data_dir.join("index"));
+      index.set_exclusions(profiles.exclusions());
+      index.spawn();
+      app.manage(index);
+      app.manage(profiles);
+      app.manage(remote::RemoteStore::load(data_dir.join("remote_hosts.json")));
+      app.manage(basket::BasketStore::load(data_dir.join("basket.json")));
+      app.manage(quarantine::QuarantineStore::load(data_dir.join("quarantine.db"), data_dir.join("quarantine")));
+      app.manage(archive::ArchiveStore::load(data_dir.join("archive.json")));
+      app.manage(junk_plugins::JunkPluginStore::load(data_dir.join("junk_plugins.json")));
+      app.manage(junk_age::JunkAgeStore::load(data_dir.join("junk_age.json")));
+      app.manage(memory::MemoryStore::load(data_dir.join("memory.json")));
+      app.manage(scan_strategy::ScanStrategyStore::load(data_dir.join("scan_strategies.json")));
+      app.manage(object_storage::ObjectStorageStore::load(data_dir.join("object_storage.json")));
+      app.manage(scan_history::ScanHistory::load(data_dir.join("scan_history.json")));
+      app.manage(alerts::AlertMonitor::load(data_dir.join("alerts.json")));
+      alerts::AlertMonitor::spawn(app.handle().clone());
+      quarantine::QuarantineStore::spawn(app.handle().clone());
+      cache_warmer::spawn(app.handle().clone());
       Ok(())
     })
+    .manage(operations::OperationManager::default())
+    .manage(scan_watch::ScanWatcher::new())
+    .manage(scan_sessions::ScanSessions::default())
+    .manage(updater::UpdateState::new(update_key.is_some()))
+    .manage(disk_activity::DiskActivityMonitor::default())
     .manage(ai_commands::InferenceState::default())
+    .manage(ai::providers::ProviderRegistry::default())
+    .manage(ai::scheduler::InferenceScheduler::default())
+    .manage(ai::file_plan::PlanStore::default())
     .manage(mcp_commands_native::NativeMCPState::new()) // Use native MCP state
-    .invoke_handler(tauri::generate_handler![
+    .invoke_handler(command_scope::guard(tauri::generate_handler![
         commands::scan_dir,
         commands::refresh_scan,
         commands::clear_cache,
+        commands::get_type_breakdown,
+        commands::get_largest_files,
+        commands::get_treemap,
         commands::reveal_in_explorer,
         commands::open_file,
         commands::delete_item,
         commands::get_drives,
+        commands::scan_multiple,
+        commands::scan_dir_shallow,
+        commands::get_deep_stats,
         commands::cancel_scan,
+        commands::get_recent_logs,
+        commands::set_locale,
+        commands::get_available_locales,
+        commands::list_operations,
+        commands::cancel_operation,
+        commands::list_resumable_operations,
+        commands::resume_operation,
+        commands::discard_checkpoint,
+        commands::get_environment_report,
+        commands::get_memory_report,
+        commands::get_memory_settings,
+        commands::set_memory_settings,
+        commands::get_permission_state,
+        commands::open_permission_settings,
+        commands::check_for_updates,
+        commands::install_update,
+        commands::get_telemetry_state,
+        commands::set_telemetry_enabled,
+        commands::upload_telemetry,
+        commands::list_profiles,
+        commands::get_active_profile,
+        commands::save_profile,
+        commands::delete_profile,
+        commands::switch_profile,
+        commands::get_alert_settings,
+        commands::set_alert_settings,
+        commands::check_drive_space,
+        commands::get_scan_history,
+        commands::list_wsl_distros,
+        commands::get_wsl_usage,
+        commands::compact_wsl_distro,
+        commands::get_snapshot_report,
+        commands::reclaim_snapshot_space,
+        commands::get_disk_writers,
+        commands::get_open_files,
+        commands::export_config,
+        commands::import_config,
+        commands::get_audit_mode,
+        commands::set_audit_mode,
+        commands::list_index_roots,
+        commands::add_index_root,
+        commands::remove_index_root,
+        commands::get_indexed_tree,
+        commands::list_remote_hosts,
+        commands::save_remote_host,
+        commands::delete_remote_host,
+        commands::list_junk_plugins,
+        commands::save_junk_plugin,
+        commands::delete_junk_plugin,
+        commands::list_storage_accounts,
+        commands::save_storage_account,
+        commands::delete_storage_account,
+        commands::analyze_bucket,
+        commands::find_git_repos,
+        commands::inspect_disk_image,
+        commands::find_mail_archives,
+        commands::find_games,
+        commands::analyze_browser_storage,
+        commands::clear_browser_storage,
+        commands::gc_git_repo,
+        commands::find_photo_bursts,
+        commands::get_age_heatmap,
+        commands::find_stale_files,
+        commands::get_basket,
+        commands::add_to_basket,
+        commands::remove_from_basket,
+        commands::clear_basket,
+        commands::commit_basket,
+        commands::get_quarantine,
+        commands::quarantine_items,
+        commands::restore_quarantined,
+        commands::purge_quarantined,
+        commands::set_quarantine_retention,
+        commands::get_archive,
+        commands::save_archive_destination,
+        commands::delete_archive_destination,
+        commands::archive_files,
+        commands::restore_archived,
+        commands::run_file_transaction,
+        commands::forecast_reclaim,
+        commands::compare_directories,
+        commands::detect_backups,
+        commands::check_backups,
+        commands::shred_items,
+        commands::get_audit_log,
+        commands::verify_audit_log,
+        commands::get_scan_strategy,
+        commands::list_scan_overrides,
+        commands::save_scan_override,
+        commands::delete_scan_override,
+        commands::preview_file,
         ai_commands::get_ai_providers_status,
         ai_commands::get_provider_models,
         ai_commands::run_ai_inference,
         ai_commands::cancel_inference,
+        ai_commands::get_inference_queue,
+        ai_commands::get_accelerators,
+        ai_commands::get_recommended_models,
+        ai_commands::get_loaded_models,
+        ai_commands::unload_model,
+        ai_commands::clear_tokenizer_cache,
+        ai_commands::prewarm_model,
+        ai_commands::set_model_memory_budget,
         ai_commands::check_provider_availability,
         ai_commands::download_model,
+        ai_commands::transcribe_audio,
+        ai_commands::download_whisper_model,
+        ai_commands::get_download_settings,
+        ai_commands::set_download_bandwidth_limit,
+        ai_commands::list_prompt_presets,
+        ai_commands::create_prompt_preset,
+        ai_commands::update_prompt_preset,
+        ai_commands::delete_prompt_preset,
+        ai_commands::list_conversations,
+        ai_commands::get_conversation,
+        ai_commands::save_conversation,
+        ai_commands::rename_conversation,
+        ai_commands::attach_context,
+        ai_commands::detach_context,
+        ai_commands::preview_context,
+        ai_commands::set_conversation_overrides,
+        ai_commands::get_usage_summary,
+        ai_commands::set_usage_budget,
+        ai_commands::set_model_price,
+        ai_commands::get_length_policy,
+        ai_commands::set_length_policy,
+        ai_commands::set_secret,
+        ai_commands::get_secret,
+        ai_commands::delete_secret,
+        ai_commands::plan_file_operations,
+        ai_commands::suggest_file_organization,
+        ai_commands::execute_file_plan,
+        ai_commands::discard_file_plan,
+        ai_commands::summarize_file,
+        ai_commands::delete_conversation,
+        ai_commands::checkpoint_agent_run,
+        ai_commands::finish_agent_run,
+        ai_commands::list_interrupted_agent_runs,
+        ai_commands::resume_agent_run,
+        ai_commands::abandon_agent_run,
+        ai_commands::export_conversation,
         commands::scan_junk,
         commands::clean_junk,
+        commands::get_junk_age_settings,
+        commands::set_junk_age_settings,
         mcp_commands_native::initialize_mcp,
         mcp_commands_native::get_mcp_tools,
         mcp_commands_native::execute_mcp_tool,
         mcp_commands_native::shutdown_mcp,
-        mcp_commands_native::is_mcp_initialized
-    ])
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+        mcp_commands_native::is_mcp_initialized,
+        mcp_commands_native::get_guardrail_policy,
+        mcp_commands_native::set_guardrail_policy,
+        mcp_commands_native::get_tool_cache_settings,
+        mcp_commands_native::set_tool_cache_settings,
+        mcp_commands_native::clear_tool_cache,
+        mcp_commands_native::get_mcp_resource_limits,
+        mcp_commands_native::set_mcp_resource_limits
+    ]))
+    .build(context)
+    .expect("error while building tauri application")
+    .run(|app, event| {
+        if let tauri::RunEvent::Exit = event {
+            ai_commands::persist_interrupted_sessions(app);
+        }
+    });
 }
 
@@ -0,0 +1,201 @@
+// Logging - Structured diagnostics for the backend
+//
+// Every `tracing` event (and `log` records from dependencies) goes to stdout
+// and, as one JSON object per line, to daily-rotated files under
+// `<app data>/logs`. The diagnostics panel reads those files back through
+// `recent_logs`, so users can attach what happened to a bug report.
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// Log files are named `helium.<date>.log`
+const FILE_PREFIX: &str = "helium";
+const FILE_SUFFIX: &str = "log";
+
+/// Daily files kept before the oldest is deleted
+const MAX_LOG_FILES: usize = 7;
+
+/// Entries returned when the filter sets no limit
+const DEFAULT_LIMIT: usize = 200;
+
+lazy_static! {
+    static ref LOG_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+/// Keeps the background log writer alive; dropping it flushes pending lines.
+/// Empty when logs only go to stdout.
+pub struct LogGuard(#[allow(dead_code)] Option<WorkerGuard>);
+
+fn file_appender(log_dir: &Path) -> Result<RollingFileAppender, String> {
+    std::fs::create_dir_all(log_dir).map_err(|e| e.to_string())?;
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(FILE_PREFIX)
+        .filename_suffix(FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(log_dir)
+        .map_err(|e| e.to_string())
+}
+
+/// Install the global subscriber. `RUST_LOG` overrides the default `info` level.
+/// Logging never keeps the app from starting: without a usable log folder,
+/// or when a subscriber is already installed, it goes on with what it has
+/// and warns.
+pub fn init(log_dir: PathBuf) -> LogGuard {
+    let (file_layer, guard, file_error) = match file_appender(&log_dir) {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (Some(fmt::layer().json().with_writer(writer).with_ansi(false)), Some(guard), None)
+        }
+        Err(e) => (None, None, Some(e)),
+    };
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    if let Err(e) = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(file_layer)
+        .try_init()
+    {
+        warn!("Could not install the log subscriber: {}", e);
+        return LogGuard(None);
+    }
+    if let Some(e) = file_error {
+        warn!("Logging to stdout only, cannot write to {}: {}", log_dir.display(), e);
+        return LogGuard(None);
+    }
+
+    *LOG_DIR.lock().unwrap() = Some(log_dir);
+    LogGuard(guard)
+}
+
+/// Which entries to return from `recent_logs`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFilter {
+    /// Minimum level ("error", "warn", "info", "debug", "trace")
+    pub level: Option<String>,
+    /// Only entries whose target starts with this (e.g. "app_lib::mcp")
+    pub target: Option<String>,
+    /// Case-insensitive text the message must contain
+    pub contains: Option<String>,
+    /// Maximum number of entries, newest first
+    pub limit: Option<usize>,
+}
+
+/// One parsed log line
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    /// Structured fields other than the message
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub fields: Map<String, Value>,
+}
+
+/// Lower is more severe
+fn severity(level: &str) -> u8 {
+    match level.to_ascii_lowercase().as_str() {
+        "error" => 0,
+        "warn" => 1,
+        "info" => 2,
+        "debug" => 3,
+        _ => 4,
+    }
+}
+
+fn parse_line(line: &str) -> Option<LogEntry> {
+    let mut value: Map<String, Value> = serde_json::from_str(line).ok()?;
+    let mut fields = match value.remove("fields") {
+        Some(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    };
+    let message = match fields.remove("message") {
+        Some(Value::String(message)) => message,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    let text = |value: &mut Map<String, Value>, key: &str| {
+        value.remove(key).and_then(|v| v.as_str().map(String::from)).unwrap_or_default()
+    };
+    Some(LogEntry {
+        timestamp: text(&mut value, "timestamp"),
+        level: text(&mut value, "level"),
+        target: text(&mut value, "target"),
+        message,
+        fields,
+    })
+}
+
+fn matches(entry: &LogEntry, filter: &LogFilter) -> bool {
+    if let Some(level) = &filter.level {
+        if severity(&entry.level) > severity(level) {
+            return false;
+        }
+    }
+    if let Some(target) = &filter.target {
+        if !entry.target.starts_with(target.as_str()) {
+            return false;
+        }
+    }
+    if let Some(text) = &filter.contains {
+        if !entry.message.to_lowercase().contains(&text.to_lowercase()) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Log files in the directory, newest first
+fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.starts_with(FILE_PREFIX) && n.ends_with(FILE_SUFFIX))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // Dated names sort chronologically
+    files.sort();
+    files.reverse();
+    files
+}
+
+/// Most recent entries matching the filter, newest first
+pub fn recent_logs(filter: &LogFilter) -> Result<Vec<LogEntry>, String> {
+    let Some(dir) = LOG_DIR.lock().unwrap().clone() else {
+        return Err("Logging is not initialized".to_string());
+    };
+    let limit = filter.limit.unwrap_or(DEFAULT_LIMIT);
+
+    let mut entries = Vec::new();
+    for file in log_files(&dir) {
+        let content = std::fs::read_to_string(&file).map_err(|e| e.to_string())?;
+        for entry in content.lines().rev().filter_map(parse_line) {
+            if entries.len() >= limit {
+                return Ok(entries);
+            }
+            if matches(&entry, filter) {
+                entries.push(entry);
+            }
+        }
+    }
+    Ok(entries)
+}
@@ -0,0 +1,243 @@
+// Mail Archives - Outlook data files and mailboxes
+//
+// On work machines a handful of mail files are often the largest files on the
+// disk: Outlook .pst archives, .ost caches of Exchange and IMAP accounts, and
+// the mbox files Thunderbird and other clients keep one mailbox per file in.
+// This finds them under a folder (or in the clients' usual locations) and
+// reports each mailbox with its size and when it last changed.
+//
+// An .ost file is only a cache of a mailbox kept on the server, and Outlook
+// leaves it behind when the account is removed. One is flagged as orphaned
+// when no Outlook profile refers to it and it hasn't synced for a while, or,
+// away from Windows, always: no other Outlook reads .ost files. Deleting an
+// orphaned cache loses nothing. .pst files are never flagged; they can hold
+// the only copy of old mail.
+
+use crate::scanner::Exclusions;
+use serde::Serialize;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
+use tracing::info;
+use walkdir::WalkDir;
+
+/// An .ost file Outlook still uses syncs at least this often
+const ORPHAN_AFTER_DAYS: u64 = 30;
+
+/// Extensionless files smaller than this aren't checked for mbox content
+const MIN_MBOX_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveKind {
+    /// Outlook personal folders: archives and POP mailboxes
+    Pst,
+    /// Outlook offline cache of a server mailbox
+    Ost,
+    /// One mailbox per file (Thunderbird, Evolution, mutt), or an Apple
+    /// Mail .mbox folder
+    Mbox,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MailArchive {
+    pub path: String,
+    /// Mailbox name: the file name without extension
+    pub name: String,
+    pub kind: ArchiveKind,
+    pub size: u64,
+    /// Unix timestamp in seconds
+    pub last_modified: u64,
+    /// Account the file belongs to, when its name or location tells
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    /// A cache no configured account uses; safe to delete
+    pub orphaned: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orphan_reason: Option<String>,
+}
+
+/// Where mail clients keep their files on this platform
+fn known_locations() -> Vec<PathBuf> {
+    let mut locations = Vec::new();
+    if cfg!(target_os = "windows") {
+        locations.extend(dirs::data_local_dir().map(|d| d.join("Microsoft").join("Outlook")));
+        locations.extend(dirs::document_dir().map(|d| d.join("Outlook Files")));
+        locations.extend(dirs::data_dir().map(|d| d.join("Thunderbird").join("Profiles")));
+    } else if cfg!(target_os = "macos") {
+        locations.extend(dirs::home_dir().map(|d| d.join("Library").join("Mail")));
+        locations.extend(dirs::home_dir().map(|d| d.join("Library").join("Thunderbird").join("Profiles")));
+    } else {
+        locations.extend(dirs::home_dir().map(|d| d.join(".thunderbird")));
+        locations.extend(dirs::home_dir().map(|d| d.join("Mail")));
+        locations.extend(dirs::home_dir().map(|d| d.join(".local").join("share").join("evolution").join("mail")));
+        locations.push(PathBuf::from("/var/mail"));
+    }
+    locations.into_iter().filter(|l| l.is_dir()).collect()
+}
+
+fn modified_secs(meta: &std::fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension().map(|e| e.to_string_lossy().to_lowercase())
+}
+
+/// Whether an extensionless file is a mailbox: Thunderbird keeps an index
+/// next to each one, other clients only leave the mbox "From " line
+fn is_mbox_file(path: &Path, size: u64) -> bool {
+    if path.with_extension("msf").is_file() {
+        return true;
+    }
+    let mut head = [0u8; 5];
+    size >= MIN_MBOX_SIZE
+        && std::fs::File::open(path).and_then(|mut f| f.read_exact(&mut head)).is_ok()
+        && head == *b"From "
+}
+
+/// Outlook names data files after the account: "me@example.com.ost" or
+/// "me@example.com - Work.pst"
+fn outlook_account(name: &str) -> Option<String> {
+    let account = name.split(" - ").next()?.trim();
+    account.contains('@').then(|| account.to_string())
+}
+
+/// Thunderbird keeps each account's mailboxes in Mail/<server> or
+/// ImapMail/<server>
+fn thunderbird_account(path: &Path) -> Option<String> {
+    for dir in path.ancestors().skip(1) {
+        if let Some(parent) = dir.parent().and_then(|p| p.file_name()) {
+            if parent == "Mail" || parent == "ImapMail" {
+                return dir.file_name().map(|n| n.to_string_lossy().to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Text of every Outlook profile in the registry, if it can be read
+#[cfg(target_os = "windows")]
+fn outlook_profiles() -> Option<String> {
+    let mut text = String::new();
+    for version in ["16.0", "15.0"] {
+        let key = format!(r"HKCU\Software\Microsoft\Office\{}\Outlook\Profiles", version);
+        if let Ok(output) = std::process::Command::new("reg").args(["query", &key, "/s"]).output() {
+            if output.status.success() {
+                text.push_str(&String::from_utf8_lossy(&output.stdout));
+            }
+        }
+    }
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn outlook_profiles() -> Option<String> {
+    None
+}
+
+/// Why an .ost file is a leftover, if it is one
+fn orphan_reason(path: &Path, last_modified: u64, profiles: Option<&str>) -> Option<String> {
+    if !cfg!(target_os = "windows") {
+        return Some("Outlook for Windows cache; no mail client on this system reads it".to_string());
+    }
+    let idle_days = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|now| now.as_secs().saturating_sub(last_modified) / 86_400)
+        .unwrap_or(0);
+    if idle_days < ORPHAN_AFTER_DAYS {
+        return None;
+    }
+    // Profiles store the data file path as UTF-16LE bytes, printed as hex
+    let profiles = profiles?;
+    let name = path.file_name()?.to_string_lossy().to_string();
+    let hex: String = name.encode_utf16().flat_map(|unit| unit.to_le_bytes()).map(|b| format!("{:02X}", b)).collect();
+    let referenced = profiles.to_uppercase().contains(&hex) || profiles.to_lowercase().contains(&name.to_lowercase());
+    (!referenced).then(|| format!("No Outlook profile uses it and it hasn't synced for {} days", idle_days))
+}
+
+/// Mail archives under `root`, or in the mail clients' usual locations when
+/// `root` is None, largest first
+pub fn find_archives(root: Option<&str>, exclusions: &Exclusions, cancel: &AtomicBool) -> Result<Vec<MailArchive>, String> {
+    let roots = match root {
+        Some(root) if !Path::new(root).is_dir() => return Err("Directory does not exist".to_string()),
+        Some(root) => vec![PathBuf::from(root)],
+        None => known_locations(),
+    };
+    let profiles = outlook_profiles();
+
+    let mut archives = Vec::new();
+    for root in &roots {
+        let mut walker = WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|e| !exclusions.is_excluded(e.path()));
+        while let Some(entry) = walker.next() {
+            if cancel.load(Ordering::Relaxed) {
+                return Err("Cancelled".to_string());
+            }
+            let Ok(entry) = entry else { continue };
+            let path = entry.path();
+            let Ok(meta) = entry.metadata() else { continue };
+            let ext = extension(path);
+
+            let kind = if entry.file_type().is_dir() {
+                // An Apple Mail mailbox is a folder of messages; it is
+                // measured as a whole
+                if ext.as_deref() != Some("mbox") {
+                    continue;
+                }
+                walker.skip_current_dir();
+                ArchiveKind::Mbox
+            } else {
+                match ext.as_deref() {
+                    Some("pst") => ArchiveKind::Pst,
+                    Some("ost") => ArchiveKind::Ost,
+                    Some("mbox") | Some("mbx") => ArchiveKind::Mbox,
+                    None if is_mbox_file(path, meta.len()) => ArchiveKind::Mbox,
+                    _ => continue,
+                }
+            };
+
+            let size = if meta.is_dir() {
+                WalkDir::new(path)
+                    .into_iter()
+                    .filter_map(|e| e.ok())
+                    .filter_map(|e| e.metadata().ok())
+                    .filter(|m| m.is_file())
+                    .map(|m| m.len())
+                    .sum()
+            } else {
+                meta.len()
+            };
+            let name = path.file_stem().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let last_modified = modified_secs(&meta);
+            let account = match kind {
+                ArchiveKind::Pst | ArchiveKind::Ost => outlook_account(&name),
+                ArchiveKind::Mbox => thunderbird_account(path),
+            };
+            let orphan_reason = match kind {
+                ArchiveKind::Ost => orphan_reason(path, last_modified, profiles.as_deref()),
+                _ => None,
+            };
+            archives.push(MailArchive {
+                path: crate::paths::display(path),
+                name,
+                kind,
+                size,
+                last_modified,
+                account,
+                orphaned: orphan_reason.is_some(),
+                orphan_reason,
+            });
+        }
+    }
+    archives.sort_by(|a, b| b.size.cmp(&a.size));
+    info!("Found {} mail archives in {} locations", archives.len(), roots.len());
+    Ok(archives)
+}
@@ -4,10 +4,11 @@
  * Handles initialization, tool discovery, and tool execution via stdio.
  */
 
+use super::notifications;
 use super::server::MCPServer;
 use super::types::*;
 use super::{MCPError, MCPResult};
-use log::{debug, error, info, warn};
+use tracing::{debug, error, info, warn};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Write};
@@ -21,6 +22,7 @@ pub struct MCPClient {
     request_id: Arc<AtomicU64>,
     tools: Arc<Mutex<Vec<MCPToolDefinition>>>,
     initialized: Arc<Mutex<bool>>,
+    server_name: Arc<Mutex<Option<String>>>,
 }
 
 impl MCPClient {
@@ -31,6 +33,7 @@ impl MCPClient {
             request_id: Arc::new(AtomicU64::new(1)),
             tools: Arc::new(Mutex::new(Vec::new())),
             initialized: Arc::new(Mutex::new(false)),
+            server_name: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -84,6 +87,8 @@ impl MCPClient {
             init_response.server_info.name, init_response.server_info.version
         );
 
+        *self.server_name.lock().await = Some(init_response.server_info.name.clone());
+
         // Send initialized notification
         self.send_notification("notifications/initialized", None)
             .await?;
@@ -208,7 +213,8 @@ impl MCPClient {
             })?;
         }
 
-        // Read response
+        // Read response; notifications the server sends in between are
+        // forwarded to the agent loop
         let mut response_line = String::new();
         {
             let mut stdout_guard = stdout_arc.lock().await;
@@ -219,11 +225,25 @@ impl MCPClient {
             })?;
 
             let mut reader = BufReader::new(stdout);
-            reader.read_line(&mut response_line).map_err(|e| MCPError {
-                code: -32000,
-                message: format!("Failed to read response: {}", e),
-                data: None,
-            })?;
+            loop {
+                response_line.clear();
+                let read = reader.read_line(&mut response_line).map_err(|e| MCPError {
+                    code: -32000,
+                    message: format!("Failed to read response: {}", e),
+                    data: None,
+                })?;
+                if read == 0 {
+                    return Err(MCPError {
+                        code: -32000,
+                        message: "MCP server closed its output".to_string(),
+                        data: None,
+                    });
+                }
+                match serde_json::from_str::<Value>(&response_line) {
+                    Ok(message) if message.get("method").is_some() => self.handle_server_message(message).await,
+                    _ => break,
+                }
+            }
         }
 
         debug!("Received response: {}", response_line.trim());
@@ -246,6 +266,26 @@ impl MCPClient {
         })
     }
 
+    /// A message the server sent on its own: notifications are forwarded,
+    /// requests (sampling, roots) aren't supported and are ignored
+    async fn handle_server_message(&self, message: Value) {
+        let method = message["method"].as_str().unwrap_or_default().to_string();
+        if message.get("id").is_some() {
+            debug!("Ignoring server request: {}", method);
+            return;
+        }
+        if method == notifications::TOOLS_LIST_CHANGED {
+            // The cached list is stale; the next get_mcp_tools asks again
+            self.tools.lock().await.clear();
+        }
+        notifications::publish(&self.server_name().await, &method, message.get("params").cloned());
+    }
+
+    /// Name the server gave in its initialize response
+    async fn server_name(&self) -> String {
+        self.server_name.lock().await.clone().unwrap_or_else(|| "mcp".to_string())
+    }
+
     /// Send a JSON-RPC notification (no response expected)
     async fn send_notification(&self, method: &str, params: Option<Value>) -> MCPResult<()> {
         let notification = JsonRpcRequest {
@@ -326,6 +366,7 @@ mod tests {
             allowed_directories: vec!["/tmp".to_string()],
             confirm_destructive: true,
             max_file_size: Some(1024 * 1024),
+            ..Default::default()
         };
 
         let server = MCPServer::new(config);
@@ -0,0 +1,267 @@
+//! Guardrails for AI-initiated destructive actions
+//!
+//! Proposed deletes, overwrites and moves are checked against protected paths,
+//! allowed roots and size/count limits, whatever the model decided.
+
+use crate::i18n;
+use crate::paths;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Configurable guardrail rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardrailPolicy {
+    /// Most files a single action (or plan) may touch
+    pub max_files_per_action: usize,
+    /// Actions touching more bytes than this need human approval
+    pub approval_size_threshold: u64,
+    /// Deletions always need human approval
+    pub approve_all_deletes: bool,
+    /// Paths (and everything below them) the agent may never modify.
+    /// A leading `~` expands to the home directory.
+    pub protected_paths: Vec<String>,
+}
+
+impl Default for GuardrailPolicy {
+    fn default() -> Self {
+        Self {
+            max_files_per_action: 500,
+            approval_size_threshold: 1024 * 1024 * 1024, // 1GB
+            approve_all_deletes: true,
+            protected_paths: vec![
+                "~/.ssh".to_string(),
+                "~/.gnupg".to_string(),
+                "~/.aws".to_string(),
+                "~/.config".to_string(),
+                "/System".to_string(),
+                "/Library".to_string(),
+                "/bin".to_string(),
+                "/boot".to_string(),
+                "/etc".to_string(),
+                "/usr".to_string(),
+                "/var".to_string(),
+                "C:\\Windows".to_string(),
+                "C:\\Program Files".to_string(),
+                "C:\\Program Files (x86)".to_string(),
+            ],
+        }
+    }
+}
+
+/// An operation the agent wants to perform
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProposedAction {
+    Delete { path: String },
+    Overwrite { path: String },
+    Move { from: String, to: String },
+}
+
+/// Outcome of evaluating proposed actions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardrailVerdict {
+    Allow,
+    RequireApproval,
+    Block,
+}
+
+/// Verdict with the reasons and the size of what would be touched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardrailDecision {
+    pub verdict: GuardrailVerdict,
+    pub reasons: Vec<String>,
+    pub file_count: usize,
+    pub total_bytes: u64,
+}
+
+/// Files and bytes under a path (a single file counts as one)
+fn measure(path: &Path) -> (usize, u64) {
+    if !path.is_dir() {
+        return (1, std::fs::metadata(path).map(|m| m.len()).unwrap_or(0));
+    }
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .fold((0, 0), |(count, bytes), e| {
+            (count + 1, bytes + e.metadata().map(|m| m.len()).unwrap_or(0))
+        })
+}
+
+impl GuardrailPolicy {
+    fn is_protected(&self, path: &Path) -> bool {
+        self.protected_paths.iter().any(|p| {
+            paths::is_within(path, &paths::expand(p))
+        })
+    }
+
+    /// The file system root, the home directory and allowed roots themselves
+    /// may never be removed or moved wholesale
+    fn is_root_like(path: &Path, allowed_roots: &[PathBuf]) -> bool {
+        paths::is_root_or_home(path) || allowed_roots.iter().any(|r| paths::same(path, r))
+    }
+
+    /// Evaluate a batch of actions as a whole
+    pub fn evaluate(&self, actions: &[ProposedAction], allowed_directories: &[String]) -> GuardrailDecision {
+        let roots: Vec<PathBuf> = allowed_directories
+            .iter()
+            .map(|d| paths::canonical(&paths::expand(d)).unwrap_or_else(|| PathBuf::from(d)))
+            .collect();
+        let resolve = |p: &str| paths::canonical(Path::new(p)).unwrap_or_else(|| PathBuf::from(p));
+        let inside_roots = |p: &Path| roots.iter().any(|r| paths::is_within(p, r));
+
+        let mut blocked = Vec::new();
+        let mut escalated = Vec::new();
+        let mut file_count = 0;
+        let mut total_bytes = 0;
+
+        for action in actions {
+            let (target, destination) = match action {
+                ProposedAction::Delete { path } | ProposedAction::Overwrite { path } => (resolve(path), None),
+                ProposedAction::Move { from, to } => (resolve(from), Some(resolve(to))),
+            };
+
+            for path in std::iter::once(&target).chain(destination.as_ref()) {
+                if self.is_protected(path) {
+                    blocked.push(i18n::t_with("guardrail.protected", &[("path", &path.display().to_string())]));
+                }
+                if !inside_roots(path) {
+                    blocked.push(i18n::t_with("guardrail.outside_allowed", &[("path", &path.display().to_string())]));
+                }
+            }
+
+            if !matches!(action, ProposedAction::Overwrite { .. }) && Self::is_root_like(&target, &roots) {
+                blocked.push(i18n::t_with("guardrail.root", &[("path", &target.display().to_string())]));
+            }
+
+            if let Some(dest) = &destination {
+                if dest.exists() {
+                    escalated.push(i18n::t_with("guardrail.replaces", &[("path", &dest.display().to_string())]));
+                }
+            }
+            if matches!(action, ProposedAction::Delete { .. }) && self.approve_all_deletes {
+                escalated.push(i18n::t_with("guardrail.delete", &[("path", &target.display().to_string())]));
+            }
+
+            let (files, bytes) = measure(&target);
+            file_count += files;
+            total_bytes += bytes;
+        }
+
+        if file_count > self.max_files_per_action {
+            blocked.push(i18n::t_with(
+                "guardrail.too_many_files",
+                &[("count", &file_count.to_string()), ("limit", &self.max_files_per_action.to_string())],
+            ));
+        }
+        if total_bytes > self.approval_size_threshold {
+            escalated.push(i18n::t_with(
+                "guardrail.size_threshold",
+                &[("bytes", &total_bytes.to_string()), ("threshold", &self.approval_size_threshold.to_string())],
+            ));
+        }
+
+        let (verdict, reasons) = if !blocked.is_empty() {
+            (GuardrailVerdict::Block, blocked)
+        } else if !escalated.is_empty() {
+            (GuardrailVerdict::RequireApproval, escalated)
+        } else {
+            (GuardrailVerdict::Allow, Vec::new())
+        };
+
+        GuardrailDecision {
+            verdict,
+            reasons,
+            file_count,
+            total_bytes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("helium-guardrails-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn test_move_outside_roots_is_blocked() {
+        let root = temp_root("outside");
+        let file = root.join("a.txt");
+        std::fs::write(&file, "a").unwrap();
+
+        let policy = GuardrailPolicy::default();
+        let decision = policy.evaluate(
+            &[ProposedAction::Move {
+                from: file.to_string_lossy().to_string(),
+                to: "/definitely/not/allowed/a.txt".to_string(),
+            }],
+            &[root.to_string_lossy().to_string()],
+        );
+        assert_eq!(decision.verdict, GuardrailVerdict::Block);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_protected_and_root_paths_are_blocked() {
+        let root = temp_root("protected");
+        let mut policy = GuardrailPolicy::default();
+        policy.protected_paths.push(root.join("keep").to_string_lossy().to_string());
+        let allowed = [root.to_string_lossy().to_string()];
+
+        let decision = policy.evaluate(
+            &[ProposedAction::Overwrite {
+                path: root.join("keep/notes.txt").to_string_lossy().to_string(),
+            }],
+            &allowed,
+        );
+        assert_eq!(decision.verdict, GuardrailVerdict::Block);
+
+        let decision = policy.evaluate(
+            &[ProposedAction::Delete {
+                path: root.to_string_lossy().to_string(),
+            }],
+            &allowed,
+        );
+        assert_eq!(decision.verdict, GuardrailVerdict::Block);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_size_threshold_and_file_limit() {
+        let root = temp_root("limits");
+        for i in 0..3 {
+            std::fs::write(root.join(format!("{}.bin", i)), vec![0u8; 100]).unwrap();
+        }
+        let allowed = [root.to_string_lossy().to_string()];
+        let overwrite = |i: usize| ProposedAction::Overwrite {
+            path: root.join(format!("{}.bin", i)).to_string_lossy().to_string(),
+        };
+
+        let mut policy = GuardrailPolicy {
+            approval_size_threshold: 250,
+            ..Default::default()
+        };
+        assert_eq!(policy.evaluate(&[overwrite(0)], &allowed).verdict, GuardrailVerdict::Allow);
+
+        let all = [overwrite(0), overwrite(1), overwrite(2)];
+        let decision = policy.evaluate(&all, &allowed);
+        assert_eq!(decision.verdict, GuardrailVerdict::RequireApproval);
+        assert_eq!(decision.total_bytes, 300);
+
+        policy.max_files_per_action = 2;
+        assert_eq!(policy.evaluate(&all, &allowed).verdict, GuardrailVerdict::Block);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,156 @@
+//! Resource limits for the native MCP server's filesystem tools
+//!
+//! Bounds concurrent walks and open files, and optionally the read rate, of
+//! agent tool calls. The scanner is never throttled.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceLimits {
+    /// Recursive walks (sizes, trees, searches, analyses) running at once
+    pub max_concurrent_walks: usize,
+    /// Files read or edited at once
+    pub max_open_files: usize,
+    /// Global cap on bytes read per second; None for no cap
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_read_bytes_per_sec: Option<u64>,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent_walks: 2,
+            max_open_files: 16,
+            max_read_bytes_per_sec: None,
+        }
+    }
+}
+
+/// Read budget refilled at the configured rate, holding at most one second
+/// of reads
+struct Bucket {
+    available: f64,
+    refilled: Instant,
+}
+
+pub struct Limiter {
+    settings: RwLock<ResourceLimits>,
+    walks: RwLock<Arc<Semaphore>>,
+    files: RwLock<Arc<Semaphore>>,
+    bucket: Mutex<Bucket>,
+}
+
+impl Limiter {
+    pub fn new(settings: ResourceLimits) -> Self {
+        let settings = Self::sanitize(settings);
+        Self {
+            walks: RwLock::new(Arc::new(Semaphore::new(settings.max_concurrent_walks))),
+            files: RwLock::new(Arc::new(Semaphore::new(settings.max_open_files))),
+            bucket: Mutex::new(Bucket {
+                available: settings.max_read_bytes_per_sec.unwrap_or(0) as f64,
+                refilled: Instant::now(),
+            }),
+            settings: RwLock::new(settings),
+        }
+    }
+
+    /// A limit of zero would block every call forever
+    fn sanitize(mut settings: ResourceLimits) -> ResourceLimits {
+        settings.max_concurrent_walks = settings.max_concurrent_walks.max(1);
+        settings.max_open_files = settings.max_open_files.max(1);
+        settings.max_read_bytes_per_sec = settings.max_read_bytes_per_sec.filter(|&rate| rate > 0);
+        settings
+    }
+
+    pub fn settings(&self) -> ResourceLimits {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Apply new limits. Calls holding a permit finish under the old ones.
+    pub fn set_settings(&self, settings: ResourceLimits) {
+        let settings = Self::sanitize(settings);
+        *self.walks.write().unwrap() = Arc::new(Semaphore::new(settings.max_concurrent_walks));
+        *self.files.write().unwrap() = Arc::new(Semaphore::new(settings.max_open_files));
+        *self.settings.write().unwrap() = settings;
+    }
+
+    async fn acquire(semaphore: &RwLock<Arc<Semaphore>>) -> OwnedSemaphorePermit {
+        let semaphore = semaphore.read().unwrap().clone();
+        semaphore.acquire_owned().await.expect("limiter semaphores are never closed")
+    }
+
+    /// Wait for a walk slot; the walk may run while the permit is held
+    pub async fn walk(&self) -> OwnedSemaphorePermit {
+        Self::acquire(&self.walks).await
+    }
+
+    /// Wait for a file slot; the file may stay open while the permit is held
+    pub async fn open_file(&self) -> OwnedSemaphorePermit {
+        Self::acquire(&self.files).await
+    }
+
+    /// Wait until `bytes` more may be read under the global rate cap
+    pub async fn throttle(&self, bytes: u64) {
+        let Some(rate) = self.settings.read().unwrap().max_read_bytes_per_sec else {
+            return;
+        };
+        let rate = rate as f64;
+        // Readers queue on the bucket, so the cap holds across all of them
+        let mut bucket = self.bucket.lock().await;
+        let now = Instant::now();
+        bucket.available = (bucket.available + now.duration_since(bucket.refilled).as_secs_f64() * rate).min(rate);
+        bucket.refilled = now;
+        bucket.available -= bytes as f64;
+        if bucket.available < 0.0 {
+            // The debt is paid off by the refill while sleeping
+            tokio::time::sleep(Duration::from_secs_f64(-bucket.available / rate)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn walks_wait_for_a_free_slot() {
+        let limiter = Limiter::new(ResourceLimits {
+            max_concurrent_walks: 1,
+            ..Default::default()
+        });
+        let first = limiter.walk().await;
+        assert!(tokio::time::timeout(Duration::from_millis(50), limiter.walk()).await.is_err());
+        drop(first);
+        assert!(tokio::time::timeout(Duration::from_millis(50), limiter.walk()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn zero_limits_are_raised_to_one() {
+        let limiter = Limiter::new(ResourceLimits {
+            max_concurrent_walks: 0,
+            max_open_files: 0,
+            max_read_bytes_per_sec: Some(0),
+        });
+        let settings = limiter.settings();
+        assert_eq!(settings.max_concurrent_walks, 1);
+        assert_eq!(settings.max_open_files, 1);
+        assert_eq!(settings.max_read_bytes_per_sec, None);
+    }
+
+    #[tokio::test]
+    async fn reads_beyond_the_rate_wait() {
+        let limiter = Limiter::new(ResourceLimits {
+            max_read_bytes_per_sec: Some(1000),
+            ..Default::default()
+        });
+        let started = Instant::now();
+        // One second of budget is available up front, the rest is waited for
+        limiter.throttle(1000).await;
+        limiter.throttle(200).await;
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+}
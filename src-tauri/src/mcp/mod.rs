@@ -10,10 +10,19 @@ pub mod server;
 pub mod types;
 pub mod client;
 pub mod native_server;
+pub mod guardrails;
+pub mod tool_cache;
+pub mod report;
+pub mod notifications;
+pub mod limits;
 
 pub use server::MCPServer;
 pub use types::*;
 pub use client::MCPClient;
+pub use guardrails::{GuardrailDecision, GuardrailPolicy, GuardrailVerdict, ProposedAction};
+pub use tool_cache::{ToolCache, ToolCacheSettings};
+pub use limits::{Limiter, ResourceLimits};
+pub use report::ReportSection;
 pub use native_server::{
     NativeMCPServer, ServerInfo, FileInfo, DirectorySizeInfo, DirectoryTreeNode,
     MultiFileResult, EditFileResult, ToolDefinition
@@ -31,6 +40,15 @@ pub struct MCPConfig {
     pub confirm_destructive: bool,
     /// Maximum file size for read operations (in bytes)
     pub max_file_size: Option<u64>,
+    /// Rules applied to destructive operations proposed by the agent
+    #[serde(default)]
+    pub guardrails: GuardrailPolicy,
+    /// Opt-in caching of read-only tool results
+    #[serde(default)]
+    pub result_cache: ToolCacheSettings,
+    /// Concurrency and read-rate limits of the filesystem tools
+    #[serde(default)]
+    pub resource_limits: ResourceLimits,
 }
 
 impl Default for MCPConfig {
@@ -39,6 +57,9 @@ impl Default for MCPConfig {
             allowed_directories: vec![],
             confirm_destructive: true,
             max_file_size: Some(10 * 1024 * 1024), // 10MB default
+            guardrails: GuardrailPolicy::default(),
+            result_cache: ToolCacheSettings::default(),
+            resource_limits: ResourceLimits::default(),
         }
     }
 }
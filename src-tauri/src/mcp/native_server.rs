@@ -5,25 +5,42 @@
  * This replaces the subprocess-based Node.js implementation.
  */
 
-use super::{MCPConfig, MCPError, MCPResult};
-use log::{debug, error, info, warn};
+use crate::audit;
+use crate::breakdown::{self, DirectoryBreakdown};
+use crate::content_type;
+use crate::dir_compare::{self, CompareMode, DirectoryComparison};
+use crate::disk_images::{self, ImageContents};
+use crate::i18n;
+use crate::paths;
+use crate::scanner::Exclusions;
+use super::report::{self, ReportSection, SavedReport};
+use super::{GuardrailDecision, GuardrailPolicy, Limiter, MCPConfig, MCPError, MCPResult, ProposedAction, ToolCache};
+use tracing::{debug, error, info, warn};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 /// Native MCP Server with filesystem tools
+///
+/// Cloning is cheap and shares configuration and state with the original.
+#[derive(Clone)]
 pub struct NativeMCPServer {
     config: Arc<RwLock<MCPConfig>>,
     initialized: Arc<RwLock<bool>>,
+    cache: Arc<ToolCache>,
+    limiter: Arc<Limiter>,
 }
 
 impl NativeMCPServer {
     /// Create a new native MCP server
     pub fn new(config: MCPConfig) -> Self {
         Self {
+            cache: Arc::new(ToolCache::new(config.result_cache.clone())),
+            limiter: Arc::new(Limiter::new(config.resource_limits.clone())),
             config: Arc::new(RwLock::new(config)),
             initialized: Arc::new(RwLock::new(false)),
         }
@@ -53,25 +70,28 @@ impl NativeMCPServer {
     }
 
     /// Check if path is allowed
-    async fn is_path_allowed(&self, path: &Path) -> bool {
+    ///
+    /// Paths that don't exist yet (a new file or directory) are checked via
+    /// their nearest existing ancestor.
+    pub(crate) async fn is_path_allowed(&self, path: &Path) -> bool {
         let config = self.config.read().await;
-        let abs_path = match path.canonicalize() {
-            Ok(p) => p,
-            Err(_) => return false,
+        let abs_path = match paths::canonical(path) {
+            Some(p) => p,
+            None => return false,
         };
 
         config.allowed_directories.iter().any(|allowed| {
-            let allowed_path = match PathBuf::from(allowed).canonicalize() {
+            let allowed_path = match paths::expand(allowed).canonicalize() {
                 Ok(p) => p,
                 Err(_) => return false,
             };
-            abs_path.starts_with(&allowed_path)
+            paths::is_within(&abs_path, &allowed_path)
         })
     }
 
     /// Read file contents
     pub async fn read_file(&self, path: String) -> MCPResult<String> {
-        let path = PathBuf::from(&path);
+        let path = paths::extended(Path::new(&path));
 
         if !self.is_path_allowed(&path).await {
             return Err(MCPError {
@@ -83,9 +103,9 @@ impl NativeMCPServer {
 
         // Check file size limit
         let metadata = fs::metadata(&path)?;
-        let config = self.config.read().await;
+        let max_file_size = self.config.read().await.max_file_size;
 
-        if let Some(max_size) = config.max_file_size {
+        if let Some(max_size) = max_file_size {
             if metadata.len() > max_size {
                 return Err(MCPError {
                     code: -32002,
@@ -100,13 +120,15 @@ impl NativeMCPServer {
         }
 
         debug!("Reading file: {}", path.display());
-        let content = fs::read_to_string(&path)?;
-        Ok(content)
+        let _file = self.limiter.open_file().await;
+        self.limiter.throttle(metadata.len()).await;
+        read_text(&path)
     }
 
     /// Write file contents
     pub async fn write_file(&self, path: String, content: String) -> MCPResult<()> {
-        let path = PathBuf::from(&path);
+        audit::ensure_writable_mcp()?;
+        let path = paths::extended(Path::new(&path));
 
         if !self.is_path_allowed(&path).await {
             return Err(MCPError {
@@ -121,9 +143,49 @@ impl NativeMCPServer {
         Ok(())
     }
 
+    /// Render a structured report into a file: JSON for ".json", Markdown otherwise
+    pub async fn save_report(&self, path: String, title: String, sections: Vec<ReportSection>) -> MCPResult<SavedReport> {
+        audit::ensure_writable_mcp()?;
+        let path = paths::extended(Path::new(&path));
+
+        if !self.is_path_allowed(&path).await {
+            return Err(MCPError {
+                code: -32001,
+                message: format!("Access denied: {} is not in allowed directories", path.display()),
+                data: None,
+            });
+        }
+        if title.trim().is_empty() || sections.is_empty() {
+            return Err(MCPError {
+                code: -32602,
+                message: "A report needs a title and at least one section".to_string(),
+                data: None,
+            });
+        }
+
+        let generated_at = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+        let is_json = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("json"));
+        let (content, format) = if is_json {
+            (report::render_json(&title, &generated_at, &sections), "json")
+        } else {
+            (report::render_markdown(&title, &generated_at, &sections), "markdown")
+        };
+
+        debug!("Saving report: {}", path.display());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, &content)?;
+        Ok(SavedReport {
+            path: paths::display(&path),
+            bytes: content.len() as u64,
+            format: format.to_string(),
+        })
+    }
+
     /// List directory contents
     pub async fn list_directory(&self, path: String) -> MCPResult<Vec<FileInfo>> {
-        let path = PathBuf::from(&path);
+        let path = paths::extended(Path::new(&path));
 
         if !self.is_path_allowed(&path).await {
             return Err(MCPError {
@@ -144,7 +206,7 @@ impl NativeMCPServer {
 
             files.push(FileInfo {
                 name: entry.file_name().to_string_lossy().to_string(),
-                path: path.to_string_lossy().to_string(),
+                path: paths::display(&path),
                 is_dir: metadata.is_dir(),
                 size: metadata.len(),
                 modified: metadata
@@ -169,7 +231,7 @@ impl NativeMCPServer {
 
     /// Search for files matching a pattern
     pub async fn search_files(&self, directory: String, pattern: String) -> MCPResult<Vec<String>> {
-        let dir_path = PathBuf::from(&directory);
+        let dir_path = paths::extended(Path::new(&directory));
 
         if !self.is_path_allowed(&dir_path).await {
             return Err(MCPError {
@@ -180,6 +242,7 @@ impl NativeMCPServer {
         }
 
         debug!("Searching for '{}' in {}", pattern, dir_path.display());
+        let _walk = self.limiter.walk().await;
 
         let mut results = Vec::new();
         let pattern_lower = pattern.to_lowercase();
@@ -201,7 +264,7 @@ impl NativeMCPServer {
                 let name = entry.file_name().to_string_lossy().to_lowercase();
 
                 if name.contains(pattern) {
-                    results.push(path.to_string_lossy().to_string());
+                    results.push(paths::display(&path));
                 }
 
                 if path.is_dir() && current_depth < max_depth {
@@ -217,7 +280,7 @@ impl NativeMCPServer {
 
     /// Get file metadata
     pub async fn get_file_info(&self, path: String) -> MCPResult<FileInfo> {
-        let path = PathBuf::from(&path);
+        let path = paths::extended(Path::new(&path));
 
         if !self.is_path_allowed(&path).await {
             return Err(MCPError {
@@ -234,7 +297,7 @@ impl NativeMCPServer {
                 .file_name()
                 .map(|n| n.to_string_lossy().to_string())
                 .unwrap_or_default(),
-            path: path.to_string_lossy().to_string(),
+            path: paths::display(&path),
             is_dir: metadata.is_dir(),
             size: metadata.len(),
             modified: metadata
@@ -247,8 +310,9 @@ impl NativeMCPServer {
 
     /// Move/rename a file or directory
     pub async fn move_file(&self, from: String, to: String) -> MCPResult<()> {
-        let from_path = PathBuf::from(&from);
-        let to_path = PathBuf::from(&to);
+        audit::ensure_writable_mcp()?;
+        let from_path = paths::extended(Path::new(&from));
+        let to_path = paths::extended(Path::new(&to));
 
         if !self.is_path_allowed(&from_path).await || !self.is_path_allowed(&to_path).await {
             return Err(MCPError {
@@ -265,7 +329,8 @@ impl NativeMCPServer {
 
     /// Create a directory
     pub async fn create_directory(&self, path: String) -> MCPResult<()> {
-        let path = PathBuf::from(&path);
+        audit::ensure_writable_mcp()?;
+        let path = paths::extended(Path::new(&path));
 
         if !self.is_path_allowed(&path).await {
             return Err(MCPError {
@@ -280,9 +345,98 @@ impl NativeMCPServer {
         Ok(())
     }
 
+    /// Category, extension, largest-file and age breakdown of a directory
+    pub async fn analyze_directory(&self, path: String) -> MCPResult<DirectoryBreakdown> {
+        let path = paths::extended(Path::new(&path));
+
+        if !self.is_path_allowed(&path).await {
+            return Err(MCPError {
+                code: -32001,
+                message: format!("Access denied: {} is not in allowed directories", path.display()),
+                data: None,
+            });
+        }
+
+        debug!("Analyzing directory: {}", path.display());
+        let _walk = self.limiter.walk().await;
+        let path = paths::display(&path);
+        tokio::task::spawn_blocking(move || {
+            breakdown::analyze(&path, &Exclusions::default(), &AtomicBool::new(false))
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r)
+        .map_err(|message| MCPError {
+            code: -32603,
+            message,
+            data: None,
+        })
+    }
+
+    /// What a disk image holds, attached read-only while it is walked
+    pub async fn inspect_disk_image(&self, path: String) -> MCPResult<ImageContents> {
+        let path = paths::extended(Path::new(&path));
+
+        if !self.is_path_allowed(&path).await {
+            return Err(MCPError {
+                code: -32001,
+                message: format!("Access denied: {} is not in allowed directories", path.display()),
+                data: None,
+            });
+        }
+
+        debug!("Inspecting disk image: {}", path.display());
+        let _walk = self.limiter.walk().await;
+        let path = paths::display(&path);
+        tokio::task::spawn_blocking(move || disk_images::inspect(&path, &AtomicBool::new(false)))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r)
+            .map_err(|message| MCPError {
+                code: -32603,
+                message,
+                data: None,
+            })
+    }
+
+    /// Entries only in `a`, only in `b`, and in both but different
+    pub async fn compare_directories(
+        &self,
+        a: String,
+        b: String,
+        max_depth: Option<usize>,
+        mode: CompareMode,
+    ) -> MCPResult<DirectoryComparison> {
+        let (path_a, path_b) = (paths::extended(Path::new(&a)), paths::extended(Path::new(&b)));
+        for path in [&path_a, &path_b] {
+            if !self.is_path_allowed(path).await {
+                return Err(MCPError {
+                    code: -32001,
+                    message: format!("Access denied: {} is not in allowed directories", path.display()),
+                    data: None,
+                });
+            }
+        }
+
+        debug!("Comparing {} with {}", path_a.display(), path_b.display());
+        let _walk = self.limiter.walk().await;
+        let (a, b) = (paths::display(&path_a), paths::display(&path_b));
+        tokio::task::spawn_blocking(move || {
+            dir_compare::compare(&a, &b, max_depth, mode, &AtomicBool::new(false))
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r)
+        .map_err(|message| MCPError {
+            code: -32603,
+            message,
+            data: None,
+        })
+    }
+
     /// Get recursive size of a directory
     pub async fn get_directory_size(&self, path: String) -> MCPResult<DirectorySizeInfo> {
-        let path = PathBuf::from(&path);
+        let path = paths::extended(Path::new(&path));
 
         if !self.is_path_allowed(&path).await {
             return Err(MCPError {
@@ -324,10 +478,11 @@ impl NativeMCPServer {
             Ok((total_size, file_count, dir_count))
         }
 
+        let _walk = self.limiter.walk().await;
         let (total_bytes, file_count, dir_count) = calculate_size(&path)?;
 
         Ok(DirectorySizeInfo {
-            path: path.to_string_lossy().to_string(),
+            path: paths::display(&path),
             total_bytes,
             file_count,
             dir_count,
@@ -337,7 +492,7 @@ impl NativeMCPServer {
 
     /// Get recursive directory tree structure
     pub async fn directory_tree(&self, path: String, max_depth: Option<usize>) -> MCPResult<DirectoryTreeNode> {
-        let path = PathBuf::from(&path);
+        let path = paths::extended(Path::new(&path));
 
         if !self.is_path_allowed(&path).await {
             return Err(MCPError {
@@ -388,7 +543,7 @@ impl NativeMCPServer {
 
             Ok(DirectoryTreeNode {
                 name,
-                path: path.to_string_lossy().to_string(),
+                path: paths::display(path),
                 is_dir,
                 size,
                 children,
@@ -396,6 +551,7 @@ impl NativeMCPServer {
         }
 
         let max_depth = max_depth.unwrap_or(5); // Default to 5 levels deep
+        let _walk = self.limiter.walk().await;
         let tree = build_tree(&path, 0, max_depth)?;
         Ok(tree)
     }
@@ -407,7 +563,7 @@ impl NativeMCPServer {
         let mut results = Vec::new();
 
         for path_str in paths {
-            let path = PathBuf::from(&path_str);
+            let path = paths::extended(Path::new(&path_str));
 
             if !self.is_path_allowed(&path).await {
                 results.push(MultiFileResult {
@@ -419,10 +575,10 @@ impl NativeMCPServer {
             }
 
             // Check file size limit
-            match fs::metadata(&path) {
+            let size = match fs::metadata(&path) {
                 Ok(metadata) => {
-                    let config = self.config.read().await;
-                    if let Some(max_size) = config.max_file_size {
+                    let max_file_size = self.config.read().await.max_file_size;
+                    if let Some(max_size) = max_file_size {
                         if metadata.len() > max_size {
                             results.push(MultiFileResult {
                                 path: path_str.clone(),
@@ -432,6 +588,7 @@ impl NativeMCPServer {
                             continue;
                         }
                     }
+                    metadata.len()
                 }
                 Err(e) => {
                     results.push(MultiFileResult {
@@ -441,10 +598,12 @@ impl NativeMCPServer {
                     });
                     continue;
                 }
-            }
+            };
 
             // Try to read the file
-            match fs::read_to_string(&path) {
+            let _file = self.limiter.open_file().await;
+            self.limiter.throttle(size).await;
+            match read_text(&path) {
                 Ok(content) => {
                     results.push(MultiFileResult {
                         path: path_str.clone(),
@@ -456,7 +615,7 @@ impl NativeMCPServer {
                     results.push(MultiFileResult {
                         path: path_str.clone(),
                         content: None,
-                        error: Some(format!("Failed to read file: {}", e)),
+                        error: Some(format!("Failed to read file: {}", e.message)),
                     });
                 }
             }
@@ -473,7 +632,10 @@ impl NativeMCPServer {
         new_text: String,
         dry_run: Option<bool>,
     ) -> MCPResult<EditFileResult> {
-        let path = PathBuf::from(&path);
+        if dry_run != Some(true) {
+            audit::ensure_writable_mcp()?;
+        }
+        let path = paths::extended(Path::new(&path));
 
         if !self.is_path_allowed(&path).await {
             return Err(MCPError {
@@ -486,6 +648,8 @@ impl NativeMCPServer {
         debug!("Editing file: {}", path.display());
 
         // Read current content
+        let _file = self.limiter.open_file().await;
+        self.limiter.throttle(fs::metadata(&path)?.len()).await;
         let content = fs::read_to_string(&path)?;
 
         // Perform replacement
@@ -536,9 +700,46 @@ impl NativeMCPServer {
         Ok(config.allowed_directories.clone())
     }
 
-    /// Get list of available tools
+    /// Classify proposed destructive actions against the guardrail policy
+    pub async fn evaluate_guardrails(&self, actions: &[ProposedAction]) -> GuardrailDecision {
+        let config = self.config.read().await;
+        let decision = config.guardrails.evaluate(actions, &config.allowed_directories);
+        if !decision.reasons.is_empty() {
+            info!("Guardrails: {:?} - {}", decision.verdict, decision.reasons.join("; "));
+        }
+        decision
+    }
+
+    /// Current guardrail policy
+    pub async fn guardrails(&self) -> GuardrailPolicy {
+        self.config.read().await.guardrails.clone()
+    }
+
+    /// Replace the guardrail policy
+    pub async fn set_guardrails(&self, policy: GuardrailPolicy) {
+        self.config.write().await.guardrails = policy;
+    }
+
+    /// Replace the allowed directories (e.g. when the active profile changes)
+    pub async fn set_allowed_directories(&self, directories: Vec<String>) {
+        self.config.write().await.allowed_directories = directories;
+        // Cached results were access-checked against the old directories
+        self.cache.clear();
+    }
+
+    /// Concurrency and read-rate limits of the filesystem tools
+    pub fn limiter(&self) -> &Limiter {
+        &self.limiter
+    }
+
+    /// Result cache of read-only tools
+    pub fn tool_cache(&self) -> &ToolCache {
+        &self.cache
+    }
+
+    /// Get list of available tools, with descriptions in the active locale
     pub fn get_tools() -> Vec<ToolDefinition> {
-        vec![
+        let tools = vec![
             ToolDefinition {
                 name: "read_file".to_string(),
                 description: "Read the complete contents of a file from the file system. Use this when you need to examine file contents.".to_string(),
@@ -735,7 +936,159 @@ impl NativeMCPServer {
                     "required": []
                 }),
             },
-        ]
+            ToolDefinition {
+                name: "analyze_directory".to_string(),
+                description: "Get a one-call overview of a directory: bytes by file category and by extension, the largest files, and bytes by last-modified year. Use this before suggesting what to clean up.".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Absolute path of the directory to analyze"
+                        }
+                    },
+                    "required": ["path"]
+                }),
+            },
+            ToolDefinition {
+                name: "compare_directories".to_string(),
+                description: "Compare two directories, e.g. a folder and its backup: lists what exists only in A, only in B, and in both but with a different size or content. Use this when asked whether a backup or copy is complete.".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "a": {
+                            "type": "string",
+                            "description": "Absolute path of the first directory (e.g. the original)"
+                        },
+                        "b": {
+                            "type": "string",
+                            "description": "Absolute path of the second directory (e.g. the backup)"
+                        },
+                        "mode": {
+                            "type": "string",
+                            "enum": ["name", "size", "hash"],
+                            "description": "name: presence only; size (default): also file sizes; hash: also file content (slow on large trees)"
+                        },
+                        "max_depth": {
+                            "type": "number",
+                            "description": "Levels below the directories to compare (default: all)"
+                        }
+                    },
+                    "required": ["a", "b"]
+                }),
+            },
+            ToolDefinition {
+                name: "inspect_disk_image".to_string(),
+                description: "List what a disk image (.dmg, .iso, .wim, .img, .vhd) holds without extracting it: file and folder counts, size of the contents, top-level entries and the largest files. The image is attached read-only. Use this before suggesting to delete a large image.".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Absolute path of the disk image"
+                        }
+                    },
+                    "required": ["path"]
+                }),
+            },
+            ToolDefinition {
+                name: "save_report".to_string(),
+                description: "Save a structured report (paragraphs, bullet lists, tables, JSON data) as a file the user can keep, such as cleanup-plan.md. Files ending in .json get JSON, anything else Markdown. Prefer this over long chat answers for plans and inventories.".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Absolute path of the report file (.md or .json)"
+                        },
+                        "title": {
+                            "type": "string",
+                            "description": "Title of the report"
+                        },
+                        "sections": {
+                            "type": "array",
+                            "description": "Sections in order; each has a heading and any of text, items, table and data",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "heading": { "type": "string" },
+                                    "text": { "type": "string", "description": "Paragraphs (Markdown)" },
+                                    "items": { "type": "array", "items": { "type": "string" }, "description": "Bullet list" },
+                                    "table": {
+                                        "type": "object",
+                                        "properties": {
+                                            "columns": { "type": "array", "items": { "type": "string" } },
+                                            "rows": { "type": "array", "items": { "type": "array" } }
+                                        },
+                                        "required": ["columns", "rows"]
+                                    },
+                                    "data": { "description": "Any JSON value, shown as a code block" }
+                                },
+                                "required": ["heading"]
+                            }
+                        }
+                    },
+                    "required": ["path", "title", "sections"]
+                }),
+            },
+            ToolDefinition {
+                name: "ui_action".to_string(),
+                description: "Show something in the app instead of only describing it: open a folder in the explorer (navigate_to), select files or folders of one folder (highlight_items), or open the cleaner on a junk category (open_clean_preview). Nothing is changed on disk.".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "action": {
+                            "type": "string",
+                            "enum": ["navigate_to", "highlight_items", "open_clean_preview"],
+                            "description": "What to show"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "navigate_to: absolute path of the folder to open"
+                        },
+                        "paths": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "highlight_items: absolute paths of the items to select, all in the same folder"
+                        },
+                        "category": {
+                            "type": "string",
+                            "description": "open_clean_preview: junk category ID, e.g. temp or system_cache"
+                        }
+                    },
+                    "required": ["action"]
+                }),
+            },
+            ToolDefinition {
+                name: "stage_for_deletion".to_string(),
+                description: "Stage files or folders in the deletion basket as cleanup candidates. Nothing is deleted; the user reviews the basket and confirms the deletion.".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "paths": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Absolute paths of the files or folders to stage"
+                        },
+                        "reason": {
+                            "type": "string",
+                            "description": "Why these are safe to delete, shown to the user"
+                        }
+                    },
+                    "required": ["paths"]
+                }),
+            },
+        ];
+
+        tools
+            .into_iter()
+            .map(|mut tool| {
+                if let Some(description) = i18n::lookup(&format!("tool.{}", tool.name)) {
+                    tool.description = description;
+                }
+                tool
+            })
+            .collect()
     }
 }
 
@@ -757,6 +1110,25 @@ pub struct FileInfo {
     pub modified: Option<u64>,
 }
 
+/// Read a file as text, refusing files whose content is binary whatever
+/// their extension
+fn read_text(path: &Path) -> MCPResult<String> {
+    let bytes = fs::read(path)?;
+    let detected = content_type::detect_bytes(&bytes[..bytes.len().min(8192)], path);
+    if !detected.is_text() {
+        return Err(MCPError {
+            code: -32003,
+            message: format!(
+                "{} is a binary file ({}) and cannot be read as text; use get_file_info for its details",
+                paths::display(path),
+                detected.mime
+            ),
+            data: None,
+        });
+    }
+    Ok(String::from_utf8(bytes).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned()))
+}
+
 /// Directory size information
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DirectorySizeInfo {
@@ -795,7 +1167,7 @@ pub struct EditFileResult {
 }
 
 /// Format bytes into human-readable string
-fn format_bytes(bytes: u64) -> String {
+pub fn format_bytes(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
     let mut size = bytes as f64;
     let mut unit_index = 0;
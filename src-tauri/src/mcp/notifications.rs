@@ -0,0 +1,60 @@
+//! MCP Notifications - Server-initiated messages forwarded to the agent loop
+//!
+//! Native and external server notifications are published as one
+//! `mcp-notification` event for the agent loop to pass to the model.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter};
+use tracing::debug;
+
+/// Event carrying each forwarded notification
+pub const MCP_NOTIFICATION_EVENT: &str = "mcp-notification";
+
+pub const TOOLS_LIST_CHANGED: &str = "notifications/tools/list_changed";
+pub const RESOURCE_UPDATED: &str = "notifications/resources/updated";
+
+/// Name the native server's notifications are published under
+pub const NATIVE_SERVER: &str = "helium-native";
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MCPNotification {
+    /// JSON-RPC method, e.g. "notifications/tools/list_changed"
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+    /// Server that sent it
+    pub server: String,
+    /// Unix timestamp in milliseconds
+    pub received_at: i64,
+}
+
+/// Start forwarding; notifications published before this are dropped
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+/// Forward a notification to the agent loop
+pub fn publish(server: &str, method: &str, params: Option<Value>) {
+    let Some(app) = APP.get() else {
+        return;
+    };
+    debug!("MCP notification from {}: {}", server, method);
+    let notification = MCPNotification {
+        method: method.to_string(),
+        params,
+        server: server.to_string(),
+        received_at: chrono::Utc::now().timestamp_millis(),
+    };
+    let _ = app.emit(MCP_NOTIFICATION_EVENT, notification);
+}
+
+/// A resource (a file or folder, as a file:// URI) changed
+pub fn resource_updated(path: &str) {
+    let uri = format!("file://{}", path.replace('\\', "/"));
+    publish(NATIVE_SERVER, RESOURCE_UPDATED, Some(serde_json::json!({ "uri": uri })));
+}
@@ -0,0 +1,139 @@
+//! Structured reports written by the agent
+//!
+//! Renders `save_report` sections as Markdown, or as JSON for ".json" paths.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Table inside a report section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportTable {
+    pub columns: Vec<String>,
+    #[serde(default)]
+    pub rows: Vec<Vec<Value>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportSection {
+    pub heading: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub items: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table: Option<ReportTable>,
+    /// Arbitrary data, shown as a JSON block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+/// Result of `save_report`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedReport {
+    pub path: String,
+    pub bytes: u64,
+    pub format: String,
+}
+
+/// Text of a table cell: strings as they are, other values as JSON, on one
+/// line and without breaking the table
+fn cell(value: &Value) -> String {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    };
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// Render a report as Markdown
+pub fn render_markdown(title: &str, generated_at: &str, sections: &[ReportSection]) -> String {
+    let mut out = format!("# {}\n\n_Generated by Helium on {}_\n", title.trim(), generated_at);
+    for section in sections {
+        out.push_str(&format!("\n## {}\n", section.heading.trim()));
+        if let Some(text) = &section.text {
+            out.push_str(&format!("\n{}\n", text.trim_end()));
+        }
+        if let Some(items) = section.items.as_ref().filter(|items| !items.is_empty()) {
+            out.push('\n');
+            for item in items {
+                out.push_str(&format!("- {}\n", item.replace('\n', " ")));
+            }
+        }
+        if let Some(table) = section.table.as_ref().filter(|t| !t.columns.is_empty()) {
+            let header: Vec<String> = table.columns.iter().map(|c| cell(&Value::String(c.clone()))).collect();
+            out.push_str(&format!("\n| {} |\n|{}\n", header.join(" | "), " --- |".repeat(header.len())));
+            for row in &table.rows {
+                // Short rows are padded, long rows cut to the header
+                let cells: Vec<String> = (0..header.len())
+                    .map(|i| row.get(i).map(cell).unwrap_or_default())
+                    .collect();
+                out.push_str(&format!("| {} |\n", cells.join(" | ")));
+            }
+        }
+        if let Some(data) = &section.data {
+            let json = serde_json::to_string_pretty(data).unwrap_or_default();
+            out.push_str(&format!("\n```json\n{}\n```\n", json));
+        }
+    }
+    out
+}
+
+/// Render a report as JSON
+pub fn render_json(title: &str, generated_at: &str, sections: &[ReportSection]) -> String {
+    let report = serde_json::json!({
+        "title": title,
+        "generatedAt": generated_at,
+        "sections": sections,
+    });
+    let mut out = serde_json::to_string_pretty(&report).unwrap_or_default();
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn section(value: Value) -> ReportSection {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_markdown_table_escapes_and_pads_cells() {
+        let sections = vec![section(json!({
+            "heading": "Largest folders",
+            "table": {
+                "columns": ["Folder", "Size"],
+                "rows": [["a|b", 1024], ["only one"]]
+            }
+        }))];
+        let md = render_markdown("Cleanup plan", "2026-01-01", &sections);
+        assert!(md.starts_with("# Cleanup plan\n"));
+        assert!(md.contains("| Folder | Size |\n| --- | --- |\n"));
+        assert!(md.contains("| a\\|b | 1024 |\n"));
+        assert!(md.contains("| only one |  |\n"));
+    }
+
+    #[test]
+    fn test_markdown_items_text_and_data() {
+        let sections = vec![section(json!({
+            "heading": "Summary",
+            "text": "Two caches can go.",
+            "items": ["npm cache", "pip cache"],
+            "data": { "total": 42 }
+        }))];
+        let md = render_markdown("Report", "2026-01-01", &sections);
+        assert!(md.contains("\n## Summary\n\nTwo caches can go.\n\n- npm cache\n- pip cache\n"));
+        assert!(md.contains("```json\n{\n  \"total\": 42\n}\n```"));
+    }
+
+    #[test]
+    fn test_json_report_keeps_sections() {
+        let sections = vec![section(json!({ "heading": "Summary", "text": "Done" }))];
+        let out: Value = serde_json::from_str(&render_json("Report", "2026-01-01", &sections)).unwrap();
+        assert_eq!(out["title"], "Report");
+        assert_eq!(out["sections"][0]["heading"], "Summary");
+    }
+}
@@ -9,7 +9,7 @@ use super::{MCPConfig, MCPError, MCPResult};
 use std::process::{Child, ChildStdin, ChildStdout, ChildStderr, Command, Stdio};
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use log::{debug, error, info, warn};
+use tracing::{debug, error, info, warn};
 
 /// MCP Server process manager with separate stdio handles
 pub struct MCPServer {
@@ -18,6 +18,9 @@ pub struct MCPServer {
     stdout: Arc<Mutex<Option<ChildStdout>>>,
     stderr: Arc<Mutex<Option<ChildStderr>>>,
     config: MCPConfig,
+    /// Program and arguments of a third-party server; the filesystem
+    /// server is started when unset
+    command: Option<(String, Vec<String>)>,
 }
 
 impl MCPServer {
@@ -29,9 +32,17 @@ impl MCPServer {
             stdout: Arc::new(Mutex::new(None)),
             stderr: Arc::new(Mutex::new(None)),
             config,
+            command: None,
         }
     }
 
+    /// A third-party MCP server started with `program args...`
+    pub fn with_command(program: String, args: Vec<String>) -> Self {
+        let mut server = Self::new(MCPConfig::default());
+        server.command = Some((program, args));
+        server
+    }
+
     /// Start the MCP filesystem server process
     pub async fn start(&self) -> MCPResult<()> {
         let mut process_guard = self.process.lock().await;
@@ -43,6 +54,12 @@ impl MCPServer {
 
         info!("Starting MCP filesystem server...");
 
+        if let Some((program, args)) = &self.command {
+            let mut cmd = Command::new(program);
+            cmd.args(args);
+            return self.spawn(cmd, &mut process_guard).await;
+        }
+
         // Validate configuration
         if self.config.allowed_directories.is_empty() {
             return Err(MCPError {
@@ -75,6 +92,11 @@ impl MCPServer {
             cmd.arg(dir);
         }
 
+        self.spawn(cmd, &mut process_guard).await
+    }
+
+    /// Spawn the server process and keep its stdio handles
+    async fn spawn(&self, mut cmd: Command, process_guard: &mut Option<Child>) -> MCPResult<()> {
         // Configure stdio for JSON-RPC communication
         cmd.stdin(Stdio::piped())
             .stdout(Stdio::piped())
@@ -201,6 +223,7 @@ mod tests {
             allowed_directories: vec!["/tmp".to_string()],
             confirm_destructive: true,
             max_file_size: Some(1024 * 1024),
+            ..Default::default()
         };
 
         let server = MCPServer::new(config);
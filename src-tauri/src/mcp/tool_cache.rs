@@ -0,0 +1,210 @@
+//! Result cache for idempotent MCP tools
+//!
+//! Opt-in TTL cache for read-only tool results, invalidated by the path's
+//! modification time and cleared by any modifying tool.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Tools whose results may be cached
+const CACHEABLE_TOOLS: &[&str] = &[
+    "get_file_info",
+    "list_directory",
+    "get_directory_size",
+    "directory_tree",
+    "search_files",
+    "analyze_directory",
+    "compare_directories",
+    "inspect_disk_image",
+];
+
+/// Tools that change the file system and so invalidate everything
+const MODIFYING_TOOLS: &[&str] = &["write_file", "edit_file", "move_file", "create_directory", "save_report"];
+
+/// Entries kept at most; the oldest go first
+const MAX_ENTRIES: usize = 512;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCacheSettings {
+    pub enabled: bool,
+    /// Seconds a result stays valid
+    pub ttl_secs: u64,
+}
+
+impl Default for ToolCacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: 30,
+        }
+    }
+}
+
+struct CacheEntry {
+    result: String,
+    stored_at: Instant,
+    /// Path the result was computed for and its modification time then
+    stamp: Option<(String, Option<SystemTime>)>,
+}
+
+pub struct ToolCache {
+    settings: RwLock<ToolCacheSettings>,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+fn cache_key(tool: &str, arguments: &HashMap<String, Value>) -> String {
+    // Sorted, so the same arguments always give the same key
+    let sorted: BTreeMap<&String, &Value> = arguments.iter().collect();
+    format!("{}:{}", tool, serde_json::to_string(&sorted).unwrap_or_default())
+}
+
+fn target_path(arguments: &HashMap<String, Value>) -> Option<String> {
+    ["path", "directory"]
+        .iter()
+        .find_map(|key| arguments.get(*key).and_then(|v| v.as_str()))
+        .map(String::from)
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::symlink_metadata(crate::paths::extended(Path::new(path)))
+        .and_then(|m| m.modified())
+        .ok()
+}
+
+impl ToolCache {
+    pub fn new(settings: ToolCacheSettings) -> Self {
+        Self {
+            settings: RwLock::new(settings),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn settings(&self) -> ToolCacheSettings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Replace the settings; cached results are dropped
+    pub fn set_settings(&self, settings: ToolCacheSettings) {
+        *self.settings.write().unwrap() = settings;
+        self.clear();
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Cached result of a tool call that is still valid
+    pub fn get(&self, tool: &str, arguments: &HashMap<String, Value>) -> Option<String> {
+        let settings = self.settings();
+        if !settings.enabled || !CACHEABLE_TOOLS.contains(&tool) {
+            return None;
+        }
+        let key = cache_key(tool, arguments);
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get(&key)?;
+        let fresh = entry.stored_at.elapsed() < Duration::from_secs(settings.ttl_secs)
+            && !entry.stamp.as_ref().is_some_and(|(path, mtime)| modified(path) != *mtime);
+        if !fresh {
+            entries.remove(&key);
+            return None;
+        }
+        Some(entry.result.clone())
+    }
+
+    /// Record the successful result of a tool call. Results of modifying
+    /// tools aren't stored; they clear the cache instead.
+    pub fn record(&self, tool: &str, arguments: &HashMap<String, Value>, result: &str) {
+        if MODIFYING_TOOLS.contains(&tool) {
+            self.clear();
+            return;
+        }
+        if !self.settings().enabled || !CACHEABLE_TOOLS.contains(&tool) {
+            return;
+        }
+        let stamp = target_path(arguments).map(|path| {
+            let mtime = modified(&path);
+            (path, mtime)
+        });
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_ENTRIES {
+            if let Some(oldest) = entries.iter().min_by_key(|(_, e)| e.stored_at).map(|(k, _)| k.clone()) {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            cache_key(tool, arguments),
+            CacheEntry {
+                result: result.to_string(),
+                stored_at: Instant::now(),
+                stamp,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(path: &Path) -> HashMap<String, Value> {
+        HashMap::from([("path".to_string(), Value::String(path.to_string_lossy().to_string()))])
+    }
+
+    fn enabled() -> ToolCache {
+        ToolCache::new(ToolCacheSettings {
+            enabled: true,
+            ttl_secs: 60,
+        })
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("helium-tool-cache-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_disabled_cache_stores_nothing() {
+        let dir = temp_dir("disabled");
+        let cache = ToolCache::new(ToolCacheSettings::default());
+        cache.record("list_directory", &args(&dir), "[]");
+        assert!(cache.get("list_directory", &args(&dir)).is_none());
+    }
+
+    #[test]
+    fn test_hit_until_modified() {
+        let dir = temp_dir("modified");
+        let file = dir.join("a.txt");
+        std::fs::write(&file, "one").unwrap();
+        let cache = enabled();
+        cache.record("get_file_info", &args(&file), "info");
+        assert_eq!(cache.get("get_file_info", &args(&file)).as_deref(), Some("info"));
+
+        let later = SystemTime::now() + Duration::from_secs(5);
+        std::fs::File::options().write(true).open(&file).unwrap().set_modified(later).unwrap();
+        assert!(cache.get("get_file_info", &args(&file)).is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_modifying_tool_clears_cache() {
+        let dir = temp_dir("clear");
+        let cache = enabled();
+        cache.record("list_directory", &args(&dir), "[]");
+        cache.record("create_directory", &args(&dir.join("new")), "Directory created successfully");
+        assert!(cache.get("list_directory", &args(&dir)).is_none());
+    }
+
+    #[test]
+    fn test_other_tools_are_not_cached() {
+        let dir = temp_dir("uncached");
+        let cache = enabled();
+        cache.record("read_file", &args(&dir), "contents");
+        assert!(cache.get("read_file", &args(&dir)).is_none());
+    }
+}
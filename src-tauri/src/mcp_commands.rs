@@ -6,7 +6,7 @@
  */
 
 use crate::mcp::{MCPClient, MCPConfig, MCPError, MCPServer, MCPToolDefinition};
-use log::{debug, error, info};
+use tracing::{debug, error, info};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
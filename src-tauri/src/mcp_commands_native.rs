@@ -7,27 +7,61 @@
 
 use crate::mcp::{
     MCPConfig, MCPError, NativeMCPServer, ServerInfo, FileInfo, DirectorySizeInfo,
-    DirectoryTreeNode, MultiFileResult, EditFileResult, ToolDefinition
+    DirectoryTreeNode, MultiFileResult, EditFileResult, ToolDefinition,
+    GuardrailDecision, GuardrailPolicy, GuardrailVerdict, ProposedAction, ReportSection, ResourceLimits, ToolCacheSettings
 };
-use log::{debug, error, info};
+use crate::audit;
+use crate::dir_compare::CompareMode;
+use crate::basket::{BasketStore, BASKET_EVENT};
+use crate::junk_plugins::JunkPluginStore;
+use crate::profiles::{Profile, ProfileStore};
+use crate::ui_actions::{UIAction, UI_ACTION_EVENT};
+use tracing::{debug, error, info};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::sync::Mutex;
 
 /// Global MCP server state
 pub struct NativeMCPState {
     server: Arc<Mutex<Option<NativeMCPServer>>>,
+    /// Directories initialize_mcp was given, used while no profile with its
+    /// own directories is active
+    configured_directories: Mutex<Vec<String>>,
 }
 
 impl NativeMCPState {
     pub fn new() -> Self {
         Self {
             server: Arc::new(Mutex::new(None)),
+            configured_directories: Mutex::new(Vec::new()),
         }
     }
+
+    /// Limit the running server to `profile`'s directories, or to the
+    /// configured ones when it has none (or no profile is active), so a
+    /// previous profile's directories never outlive it
+    pub async fn apply_profile(&self, profile: Option<&Profile>) {
+        let Ok(server) = self.server().await else { return };
+        let directories = match profile {
+            Some(profile) if !profile.mcp_allowed_directories.is_empty() => profile.mcp_allowed_directories.clone(),
+            _ => self.configured_directories.lock().await.clone(),
+        };
+        info!("MCP allowed directories for the active profile: {:?}", directories);
+        server.set_allowed_directories(directories).await;
+    }
+
+    /// Handle to the running server; clones share state, so long-running
+    /// callers don't have to hold the lock
+    pub async fn server(&self) -> Result<NativeMCPServer, String> {
+        self.server
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(|| "MCP not initialized. Call initialize_mcp first.".to_string())
+    }
 }
 
 /// Response for MCP initialization
@@ -42,12 +76,23 @@ pub struct InitializeMCPResponse {
 
 /// Initialize the native MCP server
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn initialize_mcp(
     allowed_directories: Vec<String>,
     confirm_destructive: Option<bool>,
     max_file_size: Option<u64>,
+    guardrails: Option<GuardrailPolicy>,
+    result_cache: Option<ToolCacheSettings>,
+    resource_limits: Option<ResourceLimits>,
     state: State<'_, NativeMCPState>,
+    profiles: State<'_, ProfileStore>,
 ) -> Result<InitializeMCPResponse, String> {
+    *state.configured_directories.lock().await = allowed_directories.clone();
+    // Fall back to the active profile's directories
+    let allowed_directories = match profiles.active() {
+        Some(profile) if allowed_directories.is_empty() => profile.mcp_allowed_directories,
+        _ => allowed_directories,
+    };
     info!("Initializing native MCP server with directories: {:?}", allowed_directories);
 
     let mut server_guard = state.server.lock().await;
@@ -68,6 +113,9 @@ pub async fn initialize_mcp(
         allowed_directories,
         confirm_destructive: confirm_destructive.unwrap_or(true),
         max_file_size,
+        guardrails: guardrails.unwrap_or_default(),
+        result_cache: result_cache.unwrap_or_default(),
+        resource_limits: resource_limits.unwrap_or_default(),
     };
 
     // Create native server
@@ -124,6 +172,27 @@ pub struct ToolAnnotations {
     pub destructive_hint: Option<bool>,
 }
 
+/// Behavior hints of a native tool; None for tools without any
+pub(crate) fn tool_annotations(name: &str) -> Option<ToolAnnotations> {
+    match name {
+        "read_file" | "list_directory" | "get_file_info" | "search_files" |
+        "get_directory_size" | "directory_tree" | "read_multiple_files" | "list_allowed_directories" |
+        "analyze_directory" | "compare_directories" | "inspect_disk_image" | "ui_action" => {
+            Some(ToolAnnotations {
+                read_only_hint: Some(true),
+                idempotent_hint: Some(true),
+                destructive_hint: Some(false),
+            })
+        }
+        "write_file" | "move_file" | "create_directory" | "edit_file" | "save_report" | "stage_for_deletion" => Some(ToolAnnotations {
+            read_only_hint: Some(false),
+            idempotent_hint: Some(false),
+            destructive_hint: Some(true),
+        }),
+        _ => None,
+    }
+}
+
 /// Get list of available MCP tools
 #[tauri::command]
 pub async fn get_mcp_tools(state: State<'_, NativeMCPState>) -> Result<Vec<MCPToolDefinition>, String> {
@@ -142,28 +211,17 @@ pub async fn get_mcp_tools(state: State<'_, NativeMCPState>) -> Result<Vec<MCPTo
     let frontend_tools: Vec<MCPToolDefinition> = tools
         .into_iter()
         .map(|tool| {
-            let annotations = match tool.name.as_str() {
-                "read_file" | "list_directory" | "get_file_info" | "search_files" |
-                "get_directory_size" | "directory_tree" | "read_multiple_files" | "list_allowed_directories" => {
-                    Some(ToolAnnotations {
-                        read_only_hint: Some(true),
-                        idempotent_hint: Some(true),
-                        destructive_hint: Some(false),
-                    })
-                }
-                "write_file" | "move_file" | "create_directory" | "edit_file" => Some(ToolAnnotations {
-                    read_only_hint: Some(false),
-                    idempotent_hint: Some(false),
-                    destructive_hint: Some(true),
-                }),
-                _ => None,
-            };
+            let annotations = tool_annotations(&tool.name);
+
+            // Modifying tools are unavailable while audit mode is on
+            let is_available = !(audit::is_enabled()
+                && annotations.as_ref().and_then(|a| a.read_only_hint) == Some(false));
 
             MCPToolDefinition {
                 name: tool.name,
                 description: tool.description,
                 input_schema: tool.input_schema,
-                is_available: true,
+                is_available,
                 annotations,
             }
         })
@@ -178,6 +236,9 @@ pub async fn get_mcp_tools(state: State<'_, NativeMCPState>) -> Result<Vec<MCPTo
 pub struct ExecuteToolRequest {
     pub tool_name: String,
     pub arguments: HashMap<String, Value>,
+    /// Set once the user approved an action the guardrails escalated
+    #[serde(default)]
+    pub approved: bool,
 }
 
 /// Response from tool execution
@@ -188,6 +249,28 @@ pub struct ExecuteToolResponse {
     pub is_error: bool,
     pub execution_time_ms: Option<u64>,
     pub error: Option<String>,
+    /// Guardrail decision when the action was blocked or needs approval
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guardrail: Option<GuardrailDecision>,
+}
+
+/// Destructive action a tool call would perform, for the guardrails
+fn proposed_action(tool_name: &str, arguments: &HashMap<String, Value>) -> Option<ProposedAction> {
+    let arg = |key: &str| arguments.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+    match tool_name {
+        "write_file" | "edit_file" | "save_report" => {
+            let path = arg("path")?;
+            // Writing a new file is not destructive
+            std::path::Path::new(&path)
+                .exists()
+                .then_some(ProposedAction::Overwrite { path })
+        }
+        "move_file" => Some(ProposedAction::Move {
+            from: arg("from")?,
+            to: arg("to")?,
+        }),
+        _ => None,
+    }
 }
 
 /// Tool content in response
@@ -201,6 +284,7 @@ pub enum ToolContentResponse {
 /// Execute an MCP tool
 #[tauri::command]
 pub async fn execute_mcp_tool(
+    app: AppHandle,
     request: ExecuteToolRequest,
     state: State<'_, NativeMCPState>,
 ) -> Result<ExecuteToolResponse, String> {
@@ -214,7 +298,48 @@ pub async fn execute_mcp_tool(
 
     match server_guard.as_ref() {
         Some(server) => {
+            let dry_run = request.arguments.get("dry_run").and_then(|v| v.as_bool()) == Some(true);
+            if let Some(action) = proposed_action(&request.tool_name, &request.arguments).filter(|_| !dry_run) {
+                let decision = server.evaluate_guardrails(&[action]).await;
+                let refusal = match decision.verdict {
+                    GuardrailVerdict::Allow => None,
+                    GuardrailVerdict::RequireApproval if request.approved => None,
+                    GuardrailVerdict::RequireApproval => Some("Requires user approval"),
+                    GuardrailVerdict::Block => Some("Blocked by guardrails"),
+                };
+                if let Some(refusal) = refusal {
+                    let message = format!("{}: {}", refusal, decision.reasons.join("; "));
+                    info!("Tool {} not executed. {}", request.tool_name, message);
+                    return Ok(ExecuteToolResponse {
+                        success: false,
+                        content: vec![ToolContentResponse::Text { text: message.clone() }],
+                        is_error: true,
+                        execution_time_ms: Some(start_time.elapsed().as_millis() as u64),
+                        error: Some(message),
+                        guardrail: Some(decision),
+                    });
+                }
+            }
+
+            // Fixture builds answer from the in-memory filesystem
+            #[cfg(feature = "fixtures")]
+            let result = { let _ = &app; crate::fixtures::mcp_tool(&request.tool_name, &serde_json::json!(request.arguments)) };
+
+            #[cfg(not(feature = "fixtures"))]
+            if let Some(text) = server.tool_cache().get(&request.tool_name, &request.arguments) {
+                debug!("Tool {} answered from cache", request.tool_name);
+                return Ok(ExecuteToolResponse {
+                    success: true,
+                    content: vec![ToolContentResponse::Text { text }],
+                    is_error: false,
+                    execution_time_ms: Some(start_time.elapsed().as_millis() as u64),
+                    error: None,
+                    guardrail: None,
+                });
+            }
+
             // Execute the tool based on name
+            #[cfg(not(feature = "fixtures"))]
             let result = match request.tool_name.as_str() {
                 "read_file" => {
                     let path = request
@@ -242,6 +367,31 @@ pub async fn execute_mcp_tool(
                         .await
                         .map(|_| "File written successfully".to_string())
                 }
+                "save_report" => {
+                    let path = request
+                        .arguments
+                        .get("path")
+                        .and_then(|v| v.as_str())
+                        .ok_or("Missing 'path' argument")?;
+                    let title = request
+                        .arguments
+                        .get("title")
+                        .and_then(|v| v.as_str())
+                        .ok_or("Missing 'title' argument")?;
+                    let sections: Vec<ReportSection> = request
+                        .arguments
+                        .get("sections")
+                        .cloned()
+                        .map(serde_json::from_value)
+                        .transpose()
+                        .map_err(|e| format!("Invalid 'sections' argument: {}", e))?
+                        .ok_or("Missing 'sections' argument")?;
+
+                    server
+                        .save_report(path.to_string(), title.to_string(), sections)
+                        .await
+                        .map(|saved| format!("Report saved to {} ({} bytes, {})", saved.path, saved.bytes, saved.format))
+                }
                 "list_directory" => {
                     let path = request
                         .arguments
@@ -348,6 +498,78 @@ pub async fn execute_mcp_tool(
                             })
                         })
                 }
+                "analyze_directory" => {
+                    let path = request
+                        .arguments
+                        .get("path")
+                        .and_then(|v| v.as_str())
+                        .ok_or("Missing 'path' argument")?;
+
+                    server
+                        .analyze_directory(path.to_string())
+                        .await
+                        .and_then(|breakdown| {
+                            serde_json::to_string_pretty(&breakdown).map_err(|e| MCPError {
+                                code: -32700,
+                                message: format!("Failed to serialize directory breakdown: {}", e),
+                                data: None,
+                            })
+                        })
+                }
+                "inspect_disk_image" => {
+                    let path = request
+                        .arguments
+                        .get("path")
+                        .and_then(|v| v.as_str())
+                        .ok_or("Missing 'path' argument")?;
+
+                    server
+                        .inspect_disk_image(path.to_string())
+                        .await
+                        .and_then(|contents| {
+                            serde_json::to_string_pretty(&contents).map_err(|e| MCPError {
+                                code: -32700,
+                                message: format!("Failed to serialize disk image contents: {}", e),
+                                data: None,
+                            })
+                        })
+                }
+                "compare_directories" => {
+                    let a = request
+                        .arguments
+                        .get("a")
+                        .and_then(|v| v.as_str())
+                        .ok_or("Missing 'a' argument")?;
+                    let b = request
+                        .arguments
+                        .get("b")
+                        .and_then(|v| v.as_str())
+                        .ok_or("Missing 'b' argument")?;
+                    let mode = request
+                        .arguments
+                        .get("mode")
+                        .cloned()
+                        .map(serde_json::from_value::<CompareMode>)
+                        .transpose()
+                        .map_err(|e| format!("Invalid 'mode' argument: {}", e))?
+                        .unwrap_or_default();
+                    let max_depth = request
+                        .arguments
+                        .get("max_depth")
+                        .and_then(|v| v.as_u64())
+                        .map(|v| v as usize);
+
+                    server
+                        .compare_directories(a.to_string(), b.to_string(), max_depth, mode)
+                        .await
+                        .and_then(|comparison| {
+                            serde_json::to_string_pretty(&comparison).map_err(|e| MCPError {
+                                code: -32700,
+                                message: format!("Failed to serialize directory comparison: {}", e),
+                                data: None,
+                            })
+                        })
+                }
                 "directory_tree" => {
                     let path = request
                         .arguments
@@ -424,6 +646,85 @@ pub async fn execute_mcp_tool(
                             })
                         })
                 }
+                "stage_for_deletion" => {
+                    let paths: Vec<String> = request
+                        .arguments
+                        .get("paths")
+                        .and_then(|v| v.as_array())
+                        .ok_or("Missing 'paths' argument")?
+                        .iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect();
+                    let reason = request.arguments.get("reason").and_then(|v| v.as_str()).map(String::from);
+
+                    // Staging leads to deletion, so it is off in audit mode too
+                    let mut refused = audit::ensure_writable_mcp().err();
+                    for path in &paths {
+                        if refused.is_some() {
+                            break;
+                        }
+                        if !server.is_path_allowed(std::path::Path::new(path)).await {
+                            refused = Some(MCPError {
+                                code: -32001,
+                                message: format!("Access denied: {} is not in allowed directories", path),
+                                data: None,
+                            });
+                        }
+                    }
+                    match refused {
+                        Some(e) => Err(e),
+                        None => app
+                            .state::<BasketStore>()
+                            .add(paths, "ai", reason)
+                            .map(|summary| {
+                                let _ = app.emit(BASKET_EVENT, &summary);
+                                format!(
+                                    "Staged. The basket now holds {} items ({} bytes) awaiting the user's review.",
+                                    summary.items.len(),
+                                    summary.total_size
+                                )
+                            })
+                            .map_err(|message| MCPError {
+                                code: -32603,
+                                message,
+                                data: None,
+                            }),
+                    }
+                }
+                "ui_action" => {
+                    let mut action = serde_json::Map::from_iter(request.arguments.clone());
+                    if let Some(kind) = action.remove("action") {
+                        action.insert("type".to_string(), kind);
+                    }
+                    let action: UIAction = serde_json::from_value(Value::Object(action))
+                        .map_err(|e| format!("Invalid 'action' argument: {}", e))?;
+
+                    let mut denied = None;
+                    for path in action.paths() {
+                        if !server.is_path_allowed(std::path::Path::new(path)).await {
+                            denied = Some(path.to_string());
+                            break;
+                        }
+                    }
+                    match denied {
+                        Some(path) => Err(MCPError {
+                            code: -32001,
+                            message: format!("Access denied: {} is not in allowed directories", path),
+                            data: None,
+                        }),
+                        None => action
+                            .validate(&app.state::<JunkPluginStore>().list())
+                            .map(|action| {
+                                let _ = app.emit(UI_ACTION_EVENT, &action);
+                                "Shown in the app.".to_string()
+                            })
+                            .map_err(|message| MCPError {
+                                code: -32602,
+                                message,
+                                data: None,
+                            }),
+                    }
+                }
                 "list_allowed_directories" => {
                     server
                         .list_allowed_directories()
@@ -443,6 +744,7 @@ pub async fn execute_mcp_tool(
                         is_error: true,
                         execution_time_ms: Some(start_time.elapsed().as_millis() as u64),
                         error: Some(format!("Unknown tool: {}", request.tool_name)),
+                        guardrail: None,
                     });
                 }
             };
@@ -455,6 +757,7 @@ pub async fn execute_mcp_tool(
                         "Tool {} executed successfully in {}ms",
                         request.tool_name, execution_time
                     );
+                    server.tool_cache().record(&request.tool_name, &request.arguments, &content);
 
                     Ok(ExecuteToolResponse {
                         success: true,
@@ -462,6 +765,7 @@ pub async fn execute_mcp_tool(
                         is_error: false,
                         execution_time_ms: Some(execution_time),
                         error: None,
+                        guardrail: None,
                     })
                 }
                 Err(e) => {
@@ -475,6 +779,7 @@ pub async fn execute_mcp_tool(
                         is_error: true,
                         execution_time_ms: Some(execution_time),
                         error: Some(e.message),
+                        guardrail: None,
                     })
                 }
             }
@@ -505,3 +810,61 @@ pub async fn is_mcp_initialized(state: State<'_, NativeMCPState>) -> Result<bool
     let server_guard = state.server.lock().await;
     Ok(server_guard.is_some())
 }
+
+/// Get the guardrail policy applied to agent-initiated actions
+#[tauri::command]
+pub async fn get_guardrail_policy(state: State<'_, NativeMCPState>) -> Result<GuardrailPolicy, String> {
+    Ok(state.server().await?.guardrails().await)
+}
+
+/// Current result cache settings of the read-only tools
+#[tauri::command]
+pub async fn get_tool_cache_settings(state: State<'_, NativeMCPState>) -> Result<ToolCacheSettings, String> {
+    Ok(state.server().await?.tool_cache().settings())
+}
+
+/// Enable, disable or retune the result cache; cached results are dropped
+#[tauri::command]
+pub async fn set_tool_cache_settings(
+    settings: ToolCacheSettings,
+    state: State<'_, NativeMCPState>,
+) -> Result<(), String> {
+    info!("Updating tool result cache: {:?}", settings);
+    state.server().await?.tool_cache().set_settings(settings);
+    Ok(())
+}
+
+/// Current concurrency and read-rate limits of the filesystem tools
+#[tauri::command]
+pub async fn get_mcp_resource_limits(state: State<'_, NativeMCPState>) -> Result<ResourceLimits, String> {
+    Ok(state.server().await?.limiter().settings())
+}
+
+/// Change the limits; tool calls already running finish under the old ones
+#[tauri::command]
+pub async fn set_mcp_resource_limits(
+    limits: ResourceLimits,
+    state: State<'_, NativeMCPState>,
+) -> Result<(), String> {
+    info!("Updating MCP resource limits: {:?}", limits);
+    state.server().await?.limiter().set_settings(limits);
+    Ok(())
+}
+
+/// Drop all cached tool results
+#[tauri::command]
+pub async fn clear_tool_cache(state: State<'_, NativeMCPState>) -> Result<(), String> {
+    state.server().await?.tool_cache().clear();
+    Ok(())
+}
+
+/// Replace the guardrail policy applied to agent-initiated actions
+#[tauri::command]
+pub async fn set_guardrail_policy(
+    policy: GuardrailPolicy,
+    state: State<'_, NativeMCPState>,
+) -> Result<(), String> {
+    info!("Updating guardrail policy");
+    state.server().await?.set_guardrails(policy).await;
+    Ok(())
+}
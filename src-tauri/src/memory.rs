@@ -0,0 +1,138 @@
+// Memory - Helium's own memory footprint and cache ceilings
+//
+// A disk analyzer should not be what fills a small machine's RAM. The large
+// holders of memory are the scan cache (whole trees of scanned drives), the
+// embedded models resident in the model pool, and the persistent size
+// index. The report shows each next to the process's resident size, and
+// the settings put ceilings on the first two: past them, least recently
+// used cache entries and idle models are dropped. The size index only holds
+// the roots the user chose to index, so it is reported but not capped.
+
+use crate::ai::model_pool::MODEL_POOL;
+use crate::ai::tokenizer_cache::TOKENIZER_CACHE;
+use crate::scan_cache::{self, CacheUsage};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemorySettings {
+    /// Ceiling on the scan cache's estimated size
+    #[serde(default = "default_scan_cache_bytes")]
+    pub scan_cache_max_bytes: u64,
+    /// RAM budget of resident models; None keeps the default of half the
+    /// physical memory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_budget_bytes: Option<u64>,
+}
+
+fn default_scan_cache_bytes() -> u64 {
+    scan_cache::DEFAULT_MAX_BYTES
+}
+
+impl Default for MemorySettings {
+    fn default() -> Self {
+        Self {
+            scan_cache_max_bytes: default_scan_cache_bytes(),
+            model_budget_bytes: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelUsage {
+    pub loaded: usize,
+    pub bytes: u64,
+    pub budget_bytes: u64,
+    /// Parsed tokenizers kept alongside
+    pub tokenizers: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexUsage {
+    pub directories: usize,
+    pub bytes: u64,
+}
+
+/// Memory held by Helium, by holder. Sizes other than the process's are
+/// estimates.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryReport {
+    /// Resident memory of the whole process; None if it couldn't be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub process_bytes: Option<u64>,
+    pub scan_cache: CacheUsage,
+    pub models: ModelUsage,
+    pub size_index: IndexUsage,
+}
+
+fn process_bytes() -> Option<u64> {
+    let pid = sysinfo::Pid::from_u32(std::process::id());
+    let mut system = sysinfo::System::new();
+    system.refresh_process(pid);
+    system.process(pid).map(|p| p.memory())
+}
+
+/// Current footprint; `index` is the size index's (directories, bytes)
+pub fn report(index: (usize, u64)) -> MemoryReport {
+    let pool = MODEL_POOL.lock().unwrap();
+    let models = ModelUsage {
+        loaded: pool.list().len(),
+        bytes: pool.resident_bytes(),
+        budget_bytes: pool.budget(),
+        tokenizers: TOKENIZER_CACHE.lock().unwrap().count(),
+    };
+    drop(pool);
+    MemoryReport {
+        process_bytes: process_bytes(),
+        scan_cache: scan_cache::usage(),
+        models,
+        size_index: IndexUsage {
+            directories: index.0,
+            bytes: index.1,
+        },
+    }
+}
+
+fn apply(settings: &MemorySettings) {
+    scan_cache::set_max_bytes(settings.scan_cache_max_bytes);
+    if let Some(budget) = settings.model_budget_bytes {
+        MODEL_POOL.lock().unwrap().set_budget(budget);
+    }
+}
+
+/// Memory settings persisted in the app data directory, applied on load
+pub struct MemoryStore {
+    path: PathBuf,
+    settings: Mutex<MemorySettings>,
+}
+
+impl MemoryStore {
+    pub fn load(path: PathBuf) -> Self {
+        let settings = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        apply(&settings);
+        Self {
+            path,
+            settings: Mutex::new(settings),
+        }
+    }
+
+    pub fn settings(&self) -> MemorySettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn set_settings(&self, settings: MemorySettings) -> Result<MemorySettings, String> {
+        if settings.scan_cache_max_bytes == 0 || settings.model_budget_bytes == Some(0) {
+            return Err("Memory ceilings must be greater than zero".to_string());
+        }
+        storage::save_json(&self.path, &settings)?;
+        apply(&settings);
+        *self.settings.lock().unwrap() = settings.clone();
+        Ok(settings)
+    }
+}
@@ -0,0 +1,571 @@
+// NTFS MFT - Whole-drive scans read straight from the master file table
+//
+// Used for NTFS drive roots when running elevated; counts files the way the
+// directory walker does, which every other scan still goes through.
+
+use crate::paths;
+use crate::scanner::{Exclusions, FileNode, LinkHandling, LinkInfo, ScanStats, SymlinkPolicy};
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use sysinfo::Disks;
+use tracing::{info, warn};
+
+/// Record number of the volume's root folder
+const ROOT_RECORD: usize = 5;
+/// Records below this are NTFS metafiles
+const FIRST_USER_RECORD: usize = 16;
+/// Bytes read from the table at a time
+const CHUNK_SIZE: usize = 1 << 20;
+/// Update sequence stride of MFT records, whatever the sector size
+const FIXUP_STRIDE: usize = 512;
+const NO_RECORD: u32 = u32::MAX;
+/// Low 48 bits of a file reference are the record number
+const RECORD_MASK: u64 = 0xFFFF_FFFF_FFFF;
+/// Seconds from 1601, where FILETIMEs start, to 1970
+const FILETIME_UNIX_OFFSET: u64 = 11_644_473_600;
+
+const ATTR_STANDARD_INFORMATION: u32 = 0x10;
+const ATTR_ATTRIBUTE_LIST: u32 = 0x20;
+const ATTR_FILE_NAME: u32 = 0x30;
+const ATTR_DATA: u32 = 0x80;
+const ATTR_REPARSE_POINT: u32 = 0xC0;
+const ATTR_END: u32 = 0xFFFF_FFFF;
+
+const RECORD_IN_USE: u16 = 0x01;
+const RECORD_DIRECTORY: u16 = 0x02;
+/// Compressed or sparse data attributes, which record the clusters in use
+const DATA_COMPRESSED_OR_SPARSE: u16 = 0x8001;
+/// File name namespace of 8.3 short names
+const NAMESPACE_DOS: u8 = 2;
+/// Reparse tags of links (symbolic links, junctions) have this bit
+const TAG_NAME_SURROGATE: u32 = 0x2000_0000;
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    bytes.get(at..at + 2)?.try_into().ok().map(u16::from_le_bytes)
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes.get(at..at + 4)?.try_into().ok().map(u32::from_le_bytes)
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> Option<u64> {
+    bytes.get(at..at + 8)?.try_into().ok().map(u64::from_le_bytes)
+}
+
+/// Layout of the volume, from its boot sector
+struct Geometry {
+    cluster_size: u64,
+    record_size: usize,
+    /// Byte offset of the table's first record
+    mft_offset: u64,
+}
+
+fn geometry(boot: &[u8]) -> Option<Geometry> {
+    if boot.get(3..11)? != b"NTFS    " {
+        return None;
+    }
+    let sector_size = u64::from(u16_at(boot, 0x0B)?);
+    let sectors_per_cluster = match *boot.get(0x0D)? {
+        // Values above 0x80 are negative powers of two
+        v if v > 0x80 => 1u64 << (256 - u32::from(v)),
+        v => u64::from(v),
+    };
+    let cluster_size = sector_size * sectors_per_cluster;
+    // Negative: 2^-v bytes, positive: that many clusters
+    let record_size = match *boot.get(0x40)? as i8 {
+        v if v < 0 => 1usize << -i32::from(v),
+        v => v as usize * cluster_size as usize,
+    };
+    let mft_offset = u64_at(boot, 0x30)? * cluster_size;
+    let usable = cluster_size > 0 && record_size >= FIXUP_STRIDE && CHUNK_SIZE % record_size == 0;
+    usable.then_some(Geometry {
+        cluster_size,
+        record_size,
+        mft_offset,
+    })
+}
+
+/// Undo the update sequence protecting the end of each of a record's
+/// sectors; false if the record was torn by an interrupted write
+fn apply_fixups(record: &mut [u8]) -> bool {
+    let (Some(offset), Some(count)) = (u16_at(record, 0x04), u16_at(record, 0x06)) else {
+        return false;
+    };
+    let (offset, count) = (usize::from(offset), usize::from(count));
+    let Some(sequence) = u16_at(record, offset) else { return false };
+    for i in 1..count {
+        let end = i * FIXUP_STRIDE - 2;
+        let Some(original) = u16_at(record, offset + 2 * i) else { return false };
+        if u16_at(record, end) != Some(sequence) {
+            return false;
+        }
+        record[end..end + 2].copy_from_slice(&original.to_le_bytes());
+    }
+    true
+}
+
+struct Attribute<'a> {
+    kind: u32,
+    non_resident: bool,
+    named: bool,
+    flags: u16,
+    body: &'a [u8],
+}
+
+impl<'a> Attribute<'a> {
+    /// Value of a resident attribute
+    fn value(&self) -> Option<&'a [u8]> {
+        let length = u32_at(self.body, 0x10)? as usize;
+        let offset = usize::from(u16_at(self.body, 0x14)?);
+        self.body.get(offset..offset + length)
+    }
+}
+
+fn attributes(record: &[u8]) -> impl Iterator<Item = Attribute<'_>> {
+    let mut at = u16_at(record, 0x14).map_or(record.len(), usize::from);
+    std::iter::from_fn(move || {
+        let kind = u32_at(record, at)?;
+        let length = u32_at(record, at + 4)? as usize;
+        if kind == ATTR_END || length < 0x18 {
+            return None;
+        }
+        let body = record.get(at..at + length)?;
+        at += length;
+        Some(Attribute {
+            kind,
+            non_resident: body[8] != 0,
+            named: body[9] != 0,
+            flags: u16_at(body, 0x0C)?,
+            body,
+        })
+    })
+}
+
+/// Size and space on disk of an unnamed data attribute; None for the
+/// extents after the first, which don't repeat them
+fn data_sizes(attribute: &Attribute) -> Option<(u64, u64)> {
+    if !attribute.non_resident {
+        // Stored inside the record; GetCompressedFileSize reports its size
+        let size = attribute.value()?.len() as u64;
+        return Some((size, size));
+    }
+    if u64_at(attribute.body, 0x10)? != 0 {
+        return None;
+    }
+    let size = u64_at(attribute.body, 0x30)?;
+    let allocated = if attribute.flags & DATA_COMPRESSED_OR_SPARSE != 0 {
+        u64_at(attribute.body, 0x40)?
+    } else {
+        u64_at(attribute.body, 0x28)?
+    };
+    Some((size, allocated))
+}
+
+fn le_uint(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |value, b| (value << 8) | u64::from(*b))
+}
+
+fn le_int(bytes: &[u8]) -> i64 {
+    let value = le_uint(bytes) as i64;
+    let bits = bytes.len() * 8;
+    if bits > 0 && bits < 64 && (value >> (bits - 1)) & 1 == 1 {
+        value - (1i64 << bits)
+    } else {
+        value
+    }
+}
+
+/// Extents of a non-resident attribute as (byte offset, byte length) on
+/// the volume; None where a run is sparse or malformed
+fn data_runs(attribute: &Attribute, cluster_size: u64) -> Option<Vec<(u64, u64)>> {
+    let mut at = usize::from(u16_at(attribute.body, 0x20)?);
+    let mut cluster: i64 = 0;
+    let mut runs = Vec::new();
+    loop {
+        let header = *attribute.body.get(at)?;
+        if header == 0 {
+            return Some(runs);
+        }
+        let (length_bytes, offset_bytes) = (usize::from(header & 0x0F), usize::from(header >> 4));
+        if length_bytes > 8 || offset_bytes == 0 || offset_bytes > 8 {
+            return None;
+        }
+        let length = le_uint(attribute.body.get(at + 1..at + 1 + length_bytes)?);
+        let start = at + 1 + length_bytes;
+        cluster += le_int(attribute.body.get(start..start + offset_bytes)?);
+        at = start + offset_bytes;
+        runs.push((cluster as u64 * cluster_size, length * cluster_size));
+    }
+}
+
+/// What the table says about one file or folder
+#[derive(Debug, Clone, Default)]
+struct Entry {
+    in_use: bool,
+    is_dir: bool,
+    /// A symbolic link or junction
+    link: bool,
+    name: Box<str>,
+    /// Whether `name` is an 8.3 short name, replaced by a long one if the
+    /// record has it
+    short_name: bool,
+    parent: u32,
+    size: u64,
+    allocated: u64,
+    /// Unix timestamp in seconds
+    modified: u64,
+}
+
+/// Parent record, namespace and name of a $FILE_NAME value
+fn file_name(value: &[u8]) -> Option<(u64, u8, String)> {
+    let parent = u64_at(value, 0)? & RECORD_MASK;
+    let length = usize::from(*value.get(0x40)?);
+    let namespace = *value.get(0x41)?;
+    let units: Vec<u16> = value
+        .get(0x42..0x42 + 2 * length)?
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    Some((parent, namespace, String::from_utf16_lossy(&units)))
+}
+
+/// Read record `number` into `entries`. Extension records only add
+/// attributes of their base record.
+fn parse_record(record: &mut [u8], number: usize, entries: &mut [Entry]) {
+    if !record.starts_with(b"FILE") || !apply_fixups(record) {
+        return;
+    }
+    let flags = u16_at(record, 0x16).unwrap_or(0);
+    if flags & RECORD_IN_USE == 0 {
+        return;
+    }
+    let base = u64_at(record, 0x20).unwrap_or(0) & RECORD_MASK;
+    let target = if base == 0 { number } else { base as usize };
+    let Some(entry) = entries.get_mut(target) else { return };
+    if base == 0 {
+        entry.in_use = true;
+        entry.is_dir = flags & RECORD_DIRECTORY != 0;
+    }
+    for attribute in attributes(record) {
+        match attribute.kind {
+            ATTR_STANDARD_INFORMATION => {
+                if let Some(time) = attribute.value().and_then(|v| u64_at(v, 0x08)) {
+                    entry.modified = (time / 10_000_000).saturating_sub(FILETIME_UNIX_OFFSET);
+                }
+            }
+            ATTR_FILE_NAME => {
+                let Some((parent, namespace, name)) = attribute.value().and_then(file_name) else { continue };
+                // Hard links have a name per folder; the first one counts
+                if entry.name.is_empty() || (entry.short_name && namespace != NAMESPACE_DOS) {
+                    entry.name = name.into();
+                    entry.short_name = namespace == NAMESPACE_DOS;
+                    entry.parent = parent as u32;
+                }
+            }
+            ATTR_DATA if !attribute.named => {
+                if let Some((size, allocated)) = data_sizes(&attribute) {
+                    entry.size = size;
+                    entry.allocated = allocated;
+                }
+            }
+            ATTR_REPARSE_POINT => {
+                let tag = attribute.value().and_then(|v| u32_at(v, 0)).unwrap_or(0);
+                entry.link = tag & TAG_NAME_SURROGATE != 0;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn read_at(volume: &mut File, offset: u64, buffer: &mut [u8]) -> std::io::Result<()> {
+    volume.seek(SeekFrom::Start(offset))?;
+    volume.read_exact(buffer)
+}
+
+fn cancelled(cancel: Option<&AtomicBool>) -> bool {
+    cancel.is_some_and(|c| c.load(Ordering::Relaxed))
+}
+
+/// Every record of the table; None if its layout isn't handled
+fn read_entries(volume: &mut File, geometry: &Geometry, cancel: Option<&AtomicBool>) -> Result<Option<Vec<Entry>>, String> {
+    // Reads of a raw volume must cover whole sectors
+    let mut first = vec![0u8; geometry.record_size.max(geometry.cluster_size as usize)];
+    read_at(volume, geometry.mft_offset, &mut first).map_err(|e| e.to_string())?;
+    let record = &mut first[..geometry.record_size];
+    if !record.starts_with(b"FILE") || !apply_fixups(record) {
+        return Err("The MFT's own record is damaged".to_string());
+    }
+    let mut table = None;
+    for attribute in attributes(record) {
+        match attribute.kind {
+            // A table so fragmented that its extents continue in other
+            // records; rare enough to leave to the walker
+            ATTR_ATTRIBUTE_LIST => return Ok(None),
+            ATTR_DATA if !attribute.named && attribute.non_resident => {
+                let size = u64_at(attribute.body, 0x30).unwrap_or(0);
+                table = data_runs(&attribute, geometry.cluster_size).map(|runs| (size, runs));
+            }
+            _ => {}
+        }
+    }
+    let Some((size, runs)) = table else { return Ok(None) };
+
+    let total = (size / geometry.record_size as u64) as usize;
+    let mut entries = vec![Entry::default(); total];
+    let mut number = 0;
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    'runs: for (offset, length) in runs {
+        let mut done = 0;
+        while done < length {
+            if cancelled(cancel) {
+                return Err("Cancelled".to_string());
+            }
+            let n = (length - done).min(CHUNK_SIZE as u64) as usize;
+            read_at(volume, offset + done, &mut chunk[..n]).map_err(|e| e.to_string())?;
+            for record in chunk[..n].chunks_exact_mut(geometry.record_size) {
+                if number == total {
+                    break 'runs;
+                }
+                parse_record(record, number, &mut entries);
+                number += 1;
+            }
+            done += n as u64;
+        }
+    }
+    Ok(Some(entries))
+}
+
+/// What was counted below a record
+#[derive(Debug, Clone, Copy, Default)]
+struct Tally {
+    size: u64,
+    allocated: u64,
+    count: u64,
+    skipped_links: u64,
+}
+
+impl Tally {
+    fn add(&mut self, other: Tally) {
+        self.size += other.size;
+        self.allocated += other.allocated;
+        self.count += other.count;
+        self.skipped_links += other.skipped_links;
+    }
+}
+
+/// The table's records with each folder's children as linked lists
+struct Table {
+    entries: Vec<Entry>,
+    first_child: Vec<u32>,
+    next_sibling: Vec<u32>,
+}
+
+impl Table {
+    fn new(entries: Vec<Entry>) -> Self {
+        let mut first_child = vec![NO_RECORD; entries.len()];
+        let mut next_sibling = vec![NO_RECORD; entries.len()];
+        for (n, entry) in entries.iter().enumerate().skip(FIRST_USER_RECORD) {
+            let parent = entry.parent as usize;
+            if entry.in_use && !entry.name.is_empty() && parent < entries.len() && parent != n {
+                next_sibling[n] = first_child[parent];
+                first_child[parent] = n as u32;
+            }
+        }
+        Self {
+            entries,
+            first_child,
+            next_sibling,
+        }
+    }
+
+    fn children(&self, n: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut at = self.first_child[n];
+        std::iter::from_fn(move || {
+            (at != NO_RECORD).then(|| {
+                let child = at as usize;
+                at = self.next_sibling[child];
+                child
+            })
+        })
+    }
+
+    /// A folder the scan descends into, rather than a file or link
+    fn is_folder(&self, n: usize) -> bool {
+        self.entries[n].is_dir && !self.entries[n].link
+    }
+
+    /// Totals of every record below `root`, and which records were counted
+    fn tally(&self, root: usize, root_path: &Path, exclusions: &Exclusions, symlinks: SymlinkPolicy) -> (Vec<Tally>, Vec<bool>) {
+        let mut tallies = vec![Tally::default(); self.entries.len()];
+        let mut counted = vec![false; self.entries.len()];
+        let mut order = Vec::new();
+        // Paths are only built where exclusions need them
+        let with_paths = !exclusions.is_empty();
+        let mut stack = vec![(root, with_paths.then(|| root_path.to_path_buf()))];
+        while let Some((n, path)) = stack.pop() {
+            counted[n] = true;
+            order.push(n);
+            for child in self.children(n) {
+                let entry = &self.entries[child];
+                let child_path = path.as_ref().map(|p| p.join(&*entry.name));
+                if child_path.as_deref().is_some_and(|p| exclusions.is_excluded(p)) {
+                    continue;
+                }
+                if entry.link && symlinks == SymlinkPolicy::Skip {
+                    tallies[n].skipped_links += 1;
+                } else if self.is_folder(child) {
+                    stack.push((child, child_path));
+                } else {
+                    let file = Tally {
+                        size: entry.size,
+                        allocated: entry.allocated,
+                        count: 1,
+                        skipped_links: 0,
+                    };
+                    counted[child] = true;
+                    tallies[child] = file;
+                    tallies[n].add(file);
+                }
+            }
+        }
+        // Children come after their parents in `order`
+        for &n in order.iter().rev().filter(|&&n| n != root) {
+            let child = tallies[n];
+            tallies[self.entries[n].parent as usize].add(child);
+        }
+        (tallies, counted)
+    }
+
+    fn node(&self, n: usize, path: &Path, tallies: &[Tally], children: Option<Vec<FileNode>>) -> FileNode {
+        let entry = &self.entries[n];
+        let tally = tallies[n];
+        FileNode {
+            name: entry.name.to_string(),
+            path: paths::display(path),
+            size: tally.size,
+            is_dir: self.is_folder(n),
+            children,
+            last_modified: entry.modified,
+            file_count: tally.count,
+            volume: None,
+            link: entry.link.then(|| LinkInfo {
+                target: std::fs::read_link(path).map(|t| paths::display(&t)).unwrap_or_default(),
+                handling: LinkHandling::Counted,
+            }),
+            skipped_links: (tally.skipped_links > 0).then_some(tally.skipped_links),
+            allocated_size: Some(tally.allocated),
+            extensions: None,
+            deferred: false,
+        }
+    }
+
+    /// The tree below the volume root, shaped as the walker's: folders
+    /// directly below the root with their own folders, files of the root
+    fn build(&self, path: &str, exclusions: &Exclusions, symlinks: SymlinkPolicy, stats: Option<&Arc<ScanStats>>) -> FileNode {
+        let root_path = Path::new(path);
+        let (tallies, counted) = self.tally(ROOT_RECORD, root_path, exclusions, symlinks);
+        let by_size = |nodes: &mut Vec<FileNode>| nodes.sort_by_key(|n| Reverse(n.size));
+        let mut children: Vec<FileNode> = self
+            .children(ROOT_RECORD)
+            .filter(|&n| counted[n])
+            .map(|n| {
+                let path: PathBuf = root_path.join(&*self.entries[n].name);
+                if !self.is_folder(n) {
+                    return self.node(n, &path, &tallies, None);
+                }
+                let mut folders: Vec<FileNode> = self
+                    .children(n)
+                    .filter(|&c| counted[c] && self.is_folder(c))
+                    .map(|c| self.node(c, &path.join(&*self.entries[c].name), &tallies, None))
+                    .collect();
+                by_size(&mut folders);
+                let node = self.node(n, &path, &tallies, Some(folders));
+                if let Some(on_child) = stats.and_then(|s| s.on_child.as_ref()) {
+                    on_child(&node);
+                }
+                node
+            })
+            .collect();
+        by_size(&mut children);
+
+        let tally = tallies[ROOT_RECORD];
+        if let Some(s) = stats {
+            s.scanned_files.fetch_add(tally.count, Ordering::Relaxed);
+            s.total_size.fetch_add(tally.size, Ordering::Relaxed);
+        }
+        FileNode {
+            name: root_path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            path: path.to_string(),
+            size: tally.size,
+            is_dir: true,
+            children: Some(children),
+            last_modified: 0,
+            file_count: tally.count,
+            volume: None,
+            link: None,
+            skipped_links: (tally.skipped_links > 0).then_some(tally.skipped_links),
+            allocated_size: Some(tally.allocated),
+            extensions: None,
+            deferred: false,
+        }
+    }
+}
+
+/// Raw device of the NTFS drive whose root is `path`, e.g. `\\.\C:`
+fn ntfs_device(path: &str) -> Option<String> {
+    let disks = Disks::new_with_refreshed_list();
+    let disk = disks.iter().find(|d| paths::same(Path::new(path), d.mount_point()))?;
+    if !disk.file_system().eq_ignore_ascii_case("NTFS") {
+        return None;
+    }
+    let mount = disk.mount_point().to_string_lossy();
+    let letter = mount.trim_end_matches('\\');
+    (letter.len() == 2 && letter.ends_with(':')).then(|| format!(r"\\.\{}", letter))
+}
+
+/// Scan `path` from the MFT if it is the root of an NTFS drive this process
+/// may read raw; None to have the directory walker scan it instead
+pub fn scan(
+    path: &str,
+    stats: Option<&Arc<ScanStats>>,
+    cancel: Option<&AtomicBool>,
+    exclusions: &Exclusions,
+    symlinks: SymlinkPolicy,
+) -> Option<Result<FileNode, String>> {
+    if symlinks == SymlinkPolicy::Follow {
+        return None;
+    }
+    let device = ntfs_device(path)?;
+    let started = Instant::now();
+    let mut volume = match File::open(&device) {
+        Ok(volume) => volume,
+        Err(e) => {
+            info!("Cannot read the MFT of {} ({}); walking it instead", path, e);
+            return None;
+        }
+    };
+    let mut boot = vec![0u8; 4096];
+    read_at(&mut volume, 0, &mut boot).ok()?;
+    let geometry = geometry(&boot)?;
+    let entries = match read_entries(&mut volume, &geometry, cancel) {
+        Ok(Some(entries)) => entries,
+        Ok(None) => {
+            info!("MFT layout of {} not supported; walking it instead", path);
+            return None;
+        }
+        Err(e) if cancelled(cancel) => return Some(Err(e)),
+        Err(e) => {
+            warn!("Reading the MFT of {} failed ({}); walking it instead", path, e);
+            return None;
+        }
+    };
+    let records = entries.len();
+    let node = Table::new(entries).build(path, exclusions, symlinks, stats);
+    info!("Scanned {} from {} MFT records in {:?}", path, records, started.elapsed());
+    Some(Ok(node))
+}
@@ -0,0 +1,425 @@
+// Object Storage - Size analysis of S3-compatible buckets
+//
+// Lists a bucket (AWS S3, MinIO, Cloudflare R2, Backblaze B2, ...) with
+// ListObjectsV2 and folds the keys into the same FileNode tree a disk scan
+// produces, using "/" in keys as the folder separator, so the treemap can
+// show what drives a storage bill. Sizes are also broken down by storage
+// class. Accounts are kept in the app data directory; the secret access key
+// lives in the OS keychain under `s3:<account id>`. Requests are signed with
+// AWS Signature Version 4.
+
+use crate::ai::http::{send_with_retry, RetryPolicy};
+use crate::ai::secrets;
+use crate::scanner::FileNode;
+use crate::storage;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::info;
+
+/// Scheme prefix of object paths in the tree
+pub const SCHEME: &str = "s3://";
+
+/// Levels of children returned; deeper prefixes are analyzed on drill-down
+const TREE_DEPTH: usize = 3;
+
+/// Storage class S3 reports when the listing omits one
+const DEFAULT_STORAGE_CLASS: &str = "STANDARD";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectStorageAccount {
+    pub id: String,
+    pub name: String,
+    /// e.g. "https://<account>.r2.cloudflarestorage.com"; AWS when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// Signing region ("auto" for R2)
+    pub region: String,
+    pub access_key_id: String,
+    /// Bucket analyzed when none is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_bucket: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageClassUsage {
+    pub storage_class: String,
+    pub size: u64,
+    pub objects: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketReport {
+    pub bucket: String,
+    pub prefix: String,
+    pub tree: FileNode,
+    pub total_size: u64,
+    pub object_count: u64,
+    /// Largest class first
+    pub storage_classes: Vec<StorageClassUsage>,
+}
+
+/// One page of a ListObjectsV2 response
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListBucketResult {
+    #[serde(default)]
+    contents: Vec<S3Object>,
+    #[serde(default)]
+    is_truncated: bool,
+    next_continuation_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct S3Object {
+    key: String,
+    size: u64,
+    last_modified: Option<String>,
+    storage_class: Option<String>,
+}
+
+/// Secret name of an account's secret access key
+pub fn secret_name(account_id: &str) -> String {
+    format!("s3:{}", account_id)
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// RFC 3986 encoding as SigV4 expects; `/` is kept in paths
+fn uri_encode(value: &str, keep_slash: bool) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            b'/' if keep_slash => "/".to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Signs and sends ListObjectsV2 requests for one bucket
+struct BucketClient {
+    account: ObjectStorageAccount,
+    secret_key: String,
+    /// "https://host[:port]"
+    origin: String,
+    host: String,
+    /// Canonical (encoded) path of the bucket
+    path: String,
+    client: reqwest::Client,
+}
+
+impl BucketClient {
+    /// Virtual-hosted bucket URL on AWS, path-style on custom endpoints
+    fn new(account: &ObjectStorageAccount, secret_key: String, bucket: &str) -> Result<Self, String> {
+        let base = match &account.endpoint {
+            Some(endpoint) => format!("{}/{}", endpoint.trim_end_matches('/'), bucket),
+            None => format!("https://{}.s3.{}.amazonaws.com", bucket, account.region),
+        };
+        let url = reqwest::Url::parse(&base).map_err(|e| format!("Invalid endpoint: {}", e))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err("Endpoint has no host".to_string()),
+        };
+        let path = match url.path() {
+            "" => "/".to_string(),
+            path => uri_encode(path, true),
+        };
+        Ok(Self {
+            account: account.clone(),
+            secret_key,
+            origin: format!("{}://{}", url.scheme(), host),
+            host,
+            path,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    fn signed_get(&self, query: &[(&str, &str)]) -> reqwest::RequestBuilder {
+        let (host, path) = (&self.host, &self.path);
+        let mut params: Vec<(String, String)> = query
+            .iter()
+            .map(|(k, v)| (uri_encode(k, false), uri_encode(v, false)))
+            .collect();
+        params.sort();
+        let canonical_query = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join("&");
+
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = sha256_hex(b"");
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "GET\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            path, canonical_query, host, payload_hash, amz_date, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.account.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            sha256_hex(canonical_request.as_bytes())
+        );
+        let mut key = hmac_sha256(format!("AWS4{}", self.secret_key).as_bytes(), &date);
+        for part in [self.account.region.as_str(), "s3", "aws4_request"] {
+            key = hmac_sha256(&key, part);
+        }
+        let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.account.access_key_id, scope, signed_headers, signature
+        );
+
+        self.client
+            .get(format!("{}{}?{}", self.origin, path, canonical_query))
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+    }
+
+    /// Every object under `prefix`, following continuation tokens
+    async fn list_objects(&self, prefix: &str) -> Result<Vec<S3Object>, String> {
+        let mut objects = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("max-keys", "1000")];
+            if !prefix.is_empty() {
+                query.push(("prefix", prefix));
+            }
+            if let Some(token) = &token {
+                query.push(("continuation-token", token.as_str()));
+            }
+            // Signed per attempt so the timestamp stays fresh across retries
+            let response = send_with_retry("List bucket", &RetryPolicy::default(), || self.signed_get(&query))
+            .await
+            .map_err(|e| e.message)?;
+            let body = response.text().await.map_err(|e| e.to_string())?;
+            let page: ListBucketResult =
+                quick_xml::de::from_str(&body).map_err(|e| format!("Unexpected ListObjectsV2 response: {}", e))?;
+            objects.extend(page.contents);
+            match (page.is_truncated, page.next_continuation_token) {
+                (true, Some(next)) => token = Some(next),
+                _ => break,
+            }
+        }
+        Ok(objects)
+    }
+}
+
+/// Prefix folder while aggregating
+#[derive(Default)]
+struct PrefixDir {
+    size: u64,
+    objects: u64,
+    modified: u64,
+    dirs: BTreeMap<String, PrefixDir>,
+    files: Vec<(String, u64, u64)>,
+}
+
+impl PrefixDir {
+    fn insert(&mut self, relative: &str, size: u64, modified: u64) {
+        self.size += size;
+        self.modified = self.modified.max(modified);
+        match relative.split_once('/') {
+            Some((dir, rest)) => {
+                let child = self.dirs.entry(dir.to_string()).or_default();
+                if rest.is_empty() {
+                    // "folder/" marker objects only create the folder
+                    child.size += size;
+                } else {
+                    child.insert(rest, size, modified);
+                }
+                self.objects += 1;
+            }
+            None => {
+                self.objects += 1;
+                self.files.push((relative.to_string(), size, modified));
+            }
+        }
+    }
+
+    fn into_node(self, name: String, path: String, depth: usize) -> FileNode {
+        let children = (depth > 0).then(|| {
+            let mut children: Vec<FileNode> = self
+                .dirs
+                .into_iter()
+                .map(|(dir, sub)| {
+                    let child_path = format!("{}{}/", path, dir);
+                    sub.into_node(dir, child_path, depth - 1)
+                })
+                .chain(self.files.into_iter().map(|(file, size, modified)| FileNode {
+                    path: format!("{}{}", path, file),
+                    name: file,
+                    size,
+                    is_dir: false,
+                    children: None,
+                    last_modified: modified,
+                    file_count: 1,
+                    volume: None,
+                    link: None,
+                    skipped_links: None,
+                    allocated_size: None,
+                    extensions: None,
+                    deferred: false,
+                }))
+                .collect();
+            children.sort_by(|a, b| b.size.cmp(&a.size));
+            children
+        });
+        FileNode {
+            name,
+            path,
+            size: self.size,
+            is_dir: true,
+            children,
+            last_modified: self.modified,
+            file_count: self.objects,
+            volume: None,
+            link: None,
+            skipped_links: None,
+            allocated_size: None,
+            extensions: None,
+            deferred: false,
+        }
+    }
+}
+
+/// Analyze `bucket` (or the part under `prefix`) of an account
+pub async fn analyze(account: &ObjectStorageAccount, bucket: &str, prefix: &str) -> Result<BucketReport, String> {
+    let secret_key = secrets::get(&secret_name(&account.id))
+        .map_err(|e| e.message)?
+        .ok_or_else(|| format!("No secret access key stored for {}", account.name))?;
+    let client = BucketClient::new(account, secret_key, bucket)?;
+
+    // Only whole prefix folders are analyzed
+    let prefix = match prefix.trim_start_matches('/') {
+        "" => String::new(),
+        p if p.ends_with('/') => p.to_string(),
+        p => format!("{}/", p),
+    };
+    let objects = client.list_objects(&prefix).await?;
+
+    let mut root = PrefixDir::default();
+    let mut classes: HashMap<String, (u64, u64)> = HashMap::new();
+    for object in objects {
+        let modified = object
+            .last_modified
+            .as_deref()
+            .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+            .map_or(0, |t| t.timestamp().max(0) as u64);
+        let class = classes
+            .entry(object.storage_class.unwrap_or_else(|| DEFAULT_STORAGE_CLASS.to_string()))
+            .or_default();
+        class.0 += object.size;
+        class.1 += 1;
+        if let Some(relative) = object.key.strip_prefix(&prefix).filter(|r| !r.is_empty()) {
+            root.insert(relative, object.size, modified);
+        }
+    }
+
+    let mut storage_classes: Vec<StorageClassUsage> = classes
+        .into_iter()
+        .map(|(storage_class, (size, objects))| StorageClassUsage { storage_class, size, objects })
+        .collect();
+    storage_classes.sort_by(|a, b| b.size.cmp(&a.size));
+
+    let (total_size, object_count) = (root.size, root.objects);
+    let name = prefix.trim_end_matches('/').rsplit('/').next().filter(|n| !n.is_empty()).unwrap_or(bucket).to_string();
+    let tree = root.into_node(name, format!("{}{}/{}", SCHEME, bucket, prefix), TREE_DEPTH);
+    info!(
+        "Analyzed s3://{}/{}: {} objects, {} bytes",
+        bucket, prefix, object_count, total_size
+    );
+    Ok(BucketReport {
+        bucket: bucket.to_string(),
+        prefix,
+        tree,
+        total_size,
+        object_count,
+        storage_classes,
+    })
+}
+
+/// Configured object storage accounts, persisted in the app data directory
+pub struct ObjectStorageStore {
+    path: PathBuf,
+    accounts: Mutex<Vec<ObjectStorageAccount>>,
+}
+
+impl ObjectStorageStore {
+    pub fn load(path: PathBuf) -> Self {
+        let accounts = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        Self {
+            path,
+            accounts: Mutex::new(accounts),
+        }
+    }
+
+    pub fn list(&self) -> Vec<ObjectStorageAccount> {
+        self.accounts.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, id: &str) -> Result<ObjectStorageAccount, String> {
+        self.list()
+            .into_iter()
+            .find(|a| a.id == id)
+            .ok_or_else(|| format!("Unknown storage account: {}", id))
+    }
+
+    /// Add an account, or replace the one with the same ID. The secret key is
+    /// stored in the keychain when given.
+    pub fn save_account(
+        &self,
+        mut account: ObjectStorageAccount,
+        secret_access_key: Option<String>,
+    ) -> Result<ObjectStorageAccount, String> {
+        if account.access_key_id.trim().is_empty() {
+            return Err("Access key ID cannot be empty".to_string());
+        }
+        if account.region.trim().is_empty() {
+            account.region = "us-east-1".to_string();
+        }
+        if account.id.is_empty() {
+            account.id = uuid::Uuid::new_v4().to_string();
+        }
+        if let Some(secret) = secret_access_key {
+            secrets::set(&secret_name(&account.id), &secret).map_err(|e| e.message)?;
+        }
+        let mut accounts = self.accounts.lock().unwrap();
+        match accounts.iter_mut().find(|a| a.id == account.id) {
+            Some(existing) => *existing = account.clone(),
+            None => accounts.push(account.clone()),
+        }
+        storage::save_json(&self.path, &*accounts)?;
+        Ok(account)
+    }
+
+    /// Remove an account and its stored secret key
+    pub fn delete(&self, id: &str) -> Result<bool, String> {
+        let mut accounts = self.accounts.lock().unwrap();
+        let before = accounts.len();
+        accounts.retain(|a| a.id != id);
+        if accounts.len() == before {
+            return Ok(false);
+        }
+        storage::save_json(&self.path, &*accounts)?;
+        secrets::delete(&secret_name(id)).map_err(|e| e.message)?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,144 @@
+// Open Handles - Whether running programs use files about to be deleted
+//
+// Deleting a cache or temp file a running program still has open either
+// fails halfway through a folder (Windows) or pulls the file out from under
+// the program (Unix), which may then crash or rewrite it. Before cleaning,
+// each item is checked for open files at or below it: on Linux from the
+// file descriptors in /proc, on macOS with lsof, and on Windows by trying to
+// open each file without sharing, which fails while another program holds
+// it. Items in use are skipped with the programs holding them, where known,
+// so the user can close those and try again.
+
+use crate::i18n;
+use crate::paths;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+use tracing::info;
+
+/// An item left alone because a program has it open
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InUseItem {
+    pub path: String,
+    /// Programs holding it, as "name (pid)"; empty where the platform only
+    /// tells that it is locked, not by whom
+    pub processes: Vec<String>,
+    pub reason: String,
+}
+
+/// Open files of every other process this user may inspect, as
+/// (path, process); the app's own log and cache files don't count
+#[cfg(target_os = "linux")]
+fn open_files() -> Vec<(std::path::PathBuf, String)> {
+    let own = std::process::id().to_string();
+    let mut open = Vec::new();
+    let Ok(procs) = std::fs::read_dir("/proc") else { return open };
+    for proc_dir in procs.flatten() {
+        let pid = proc_dir.file_name().to_string_lossy().to_string();
+        if pid == own || !pid.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        let Ok(fds) = std::fs::read_dir(proc_dir.path().join("fd")) else { continue };
+        let name = std::fs::read_to_string(proc_dir.path().join("comm")).unwrap_or_default();
+        let process = format!("{} ({})", name.trim(), pid);
+        for fd in fds.flatten() {
+            if let Ok(target) = std::fs::read_link(fd.path()) {
+                if target.is_absolute() {
+                    open.push((target, process.clone()));
+                }
+            }
+        }
+    }
+    open
+}
+
+#[cfg(target_os = "macos")]
+fn open_files() -> Vec<(std::path::PathBuf, String)> {
+    // -F pcn prints "p<pid>", "c<command>" and one "n<path>" per open file
+    let Ok(output) = std::process::Command::new("lsof").args(["-n", "-P", "-F", "pcn"]).output() else {
+        return Vec::new();
+    };
+    let own = std::process::id().to_string();
+    let mut open = Vec::new();
+    let (mut pid, mut command) = (String::new(), String::new());
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(value) = line.strip_prefix('p') {
+            pid = value.to_string();
+        } else if let Some(value) = line.strip_prefix('c') {
+            command = value.to_string();
+        } else if let Some(value) = line.strip_prefix('n').filter(|v| v.starts_with('/') && pid != own) {
+            open.push((std::path::PathBuf::from(value), format!("{} ({})", command, pid)));
+        }
+    }
+    open
+}
+
+/// Programs with files open at or below `path`
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn holders(path: &Path, open: &[(std::path::PathBuf, String)]) -> Vec<String> {
+    let holders: BTreeSet<&String> = open
+        .iter()
+        .filter(|(file, _)| paths::is_within(file, path))
+        .map(|(_, process)| process)
+        .collect();
+    holders.into_iter().cloned().collect()
+}
+
+/// Whether a file below `path` can't be opened without sharing; Windows
+/// refuses that while another program has it open
+#[cfg(target_os = "windows")]
+fn is_locked(path: &Path) -> bool {
+    use std::os::windows::fs::OpenOptionsExt;
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+    walkdir::WalkDir::new(paths::extended(path))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .any(|e| {
+            let opened = std::fs::OpenOptions::new().read(true).share_mode(0).open(e.path());
+            matches!(
+                opened.map_err(|err| err.raw_os_error()),
+                Err(Some(ERROR_SHARING_VIOLATION)) | Err(Some(ERROR_LOCK_VIOLATION))
+            )
+        })
+}
+
+/// The items of `items` that running programs have files open in
+pub fn in_use(items: &[String]) -> Vec<InUseItem> {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    let found: Vec<InUseItem> = {
+        let open = open_files();
+        items
+            .iter()
+            .filter_map(|item| {
+                let processes = holders(Path::new(item), &open);
+                (!processes.is_empty()).then(|| InUseItem {
+                    path: item.clone(),
+                    reason: i18n::t_with("junk.in_use", &[("processes", &processes.join(", "))]),
+                    processes,
+                })
+            })
+            .collect()
+    };
+    #[cfg(target_os = "windows")]
+    let found: Vec<InUseItem> = items
+        .iter()
+        .filter(|item| is_locked(Path::new(item)))
+        .map(|item| InUseItem {
+            path: item.clone(),
+            processes: Vec::new(),
+            reason: i18n::t("junk.locked"),
+        })
+        .collect();
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    let found: Vec<InUseItem> = {
+        let _ = items;
+        Vec::new()
+    };
+    if !found.is_empty() {
+        info!("{} of {} items are in use and will be skipped", found.len(), items.len());
+    }
+    found
+}
@@ -0,0 +1,253 @@
+// Operations - One registry for every long-running backend task
+//
+// Scans, junk scans, cleanups, deletions, downloads and inferences register
+// here while they run. Each gets an ID, a cancellation signal usable from
+// both async code (a CancellationToken) and blocking code (an AtomicBool),
+// and reports progress through the same `operation-updated` event, so the
+// frontend can show one activity center and cancel anything from it.
+
+use crate::event_batch::EventBatcher;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Runtime};
+use tokio_util::sync::CancellationToken;
+
+/// Event emitted whenever an operation starts, progresses or finishes
+pub const OPERATION_EVENT: &str = "operation-updated";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OperationKind {
+    Scan,
+    JunkScan,
+    Cleanup,
+    Deletion,
+    Download,
+    Inference,
+    Summarize,
+    Transaction,
+    Archive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Snapshot of an operation as shown to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationInfo {
+    pub id: String,
+    pub kind: OperationKind,
+    /// What the operation works on (a path, a model ID)
+    pub label: String,
+    pub status: OperationStatus,
+    /// 0.0 to 1.0; None while the total is unknown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub cancellable: bool,
+    /// Unix timestamp in milliseconds
+    pub started_at: i64,
+}
+
+struct Tracked {
+    info: OperationInfo,
+    token: CancellationToken,
+    flag: Arc<AtomicBool>,
+}
+
+/// Running operations, kept in managed state
+#[derive(Default, Clone)]
+pub struct OperationManager {
+    operations: Arc<Mutex<HashMap<String, Tracked>>>,
+}
+
+impl OperationManager {
+    /// Register an operation that can be cancelled
+    pub fn start<R: Runtime>(&self, app: &AppHandle<R>, kind: OperationKind, label: impl Into<String>) -> OperationHandle {
+        self.register(app, kind, label.into(), true)
+    }
+
+    /// Register an operation that runs to completion once started
+    pub fn start_uncancellable<R: Runtime>(&self, app: &AppHandle<R>, kind: OperationKind, label: impl Into<String>) -> OperationHandle {
+        self.register(app, kind, label.into(), false)
+    }
+
+    fn register<R: Runtime>(&self, app: &AppHandle<R>, kind: OperationKind, label: String, cancellable: bool) -> OperationHandle {
+        let info = OperationInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            label,
+            status: OperationStatus::Running,
+            progress: None,
+            message: None,
+            cancellable,
+            started_at: chrono::Utc::now().timestamp_millis(),
+        };
+        let token = CancellationToken::new();
+        let flag = Arc::new(AtomicBool::new(false));
+        let _ = app.emit(OPERATION_EVENT, &info);
+
+        let id = info.id.clone();
+        self.operations.lock().unwrap().insert(
+            id.clone(),
+            Tracked {
+                info,
+                token: token.clone(),
+                flag: flag.clone(),
+            },
+        );
+        OperationHandle {
+            inner: Arc::new(HandleInner {
+                id,
+                manager: self.clone(),
+                emit: {
+                    let app = app.clone();
+                    Box::new(move |info| {
+                        let _ = app.emit(OPERATION_EVENT, info);
+                    })
+                },
+                token,
+                flag,
+                progress_events: EventBatcher::latest(app.clone(), OPERATION_EVENT),
+            }),
+        }
+    }
+
+    /// Running operations, oldest first
+    pub fn list(&self) -> Vec<OperationInfo> {
+        let mut list: Vec<_> = self.operations.lock().unwrap().values().map(|t| t.info.clone()).collect();
+        list.sort_by_key(|info| info.started_at);
+        list
+    }
+
+    /// Signal an operation to stop. Returns false if it is not running or
+    /// cannot be cancelled.
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.operations.lock().unwrap().get(id) {
+            Some(tracked) if tracked.info.cancellable => {
+                tracked.flag.store(true, Ordering::Relaxed);
+                tracked.token.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Update a running operation, returning its new state
+    fn update(&self, id: &str, change: impl FnOnce(&mut OperationInfo)) -> Option<OperationInfo> {
+        let mut operations = self.operations.lock().unwrap();
+        let tracked = operations.get_mut(id)?;
+        change(&mut tracked.info);
+        Some(tracked.info.clone())
+    }
+
+    /// Remove a finished operation, returning its final state
+    fn complete(&self, id: &str, status: OperationStatus, message: Option<String>) -> Option<OperationInfo> {
+        let mut tracked = self.operations.lock().unwrap().remove(id)?;
+        tracked.info.status = status;
+        if status == OperationStatus::Completed {
+            tracked.info.progress = Some(1.0);
+        }
+        if message.is_some() {
+            tracked.info.message = message;
+        }
+        Some(tracked.info)
+    }
+}
+
+struct HandleInner {
+    id: String,
+    manager: OperationManager,
+    /// Emits the final state
+    emit: Box<dyn Fn(&OperationInfo) + Send + Sync>,
+    token: CancellationToken,
+    flag: Arc<AtomicBool>,
+    /// Progress updates, rate-limited; the final state is emitted directly
+    progress_events: EventBatcher<OperationInfo>,
+}
+
+impl HandleInner {
+    fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled() || self.flag.load(Ordering::Relaxed)
+    }
+
+    fn complete(&self, status: OperationStatus, message: Option<String>) {
+        // A progress update still held back must not arrive after the final state
+        self.progress_events.discard();
+        if let Some(info) = self.manager.complete(&self.id, status, message) {
+            (self.emit)(&info);
+        }
+    }
+}
+
+impl Drop for HandleInner {
+    // An operation whose task ends without `finish` (early return, panic)
+    // still leaves the registry
+    fn drop(&mut self) {
+        let status = if self.is_cancelled() {
+            OperationStatus::Cancelled
+        } else {
+            OperationStatus::Completed
+        };
+        self.complete(status, None);
+    }
+}
+
+/// A registered operation. Clones share it; it is finished when `finish` is
+/// called or the last clone is dropped.
+#[derive(Clone)]
+pub struct OperationHandle {
+    inner: Arc<HandleInner>,
+}
+
+impl OperationHandle {
+    pub fn id(&self) -> &str {
+        &self.inner.id
+    }
+
+    /// Cancellation signal for async code
+    pub fn token(&self) -> CancellationToken {
+        self.inner.token.clone()
+    }
+
+    /// Cancellation signal for blocking code
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        self.inner.flag.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled()
+    }
+
+    /// Report progress (0.0 to 1.0, None if unknown) and a status line
+    pub fn progress(&self, progress: Option<f32>, message: Option<String>) {
+        let info = self.inner.manager.update(&self.inner.id, |info| {
+            info.progress = progress.map(|p| p.clamp(0.0, 1.0));
+            info.message = message;
+        });
+        if let Some(info) = info {
+            self.inner.progress_events.send(info);
+        }
+    }
+
+    /// Record the outcome; a cancelled operation is reported as cancelled
+    /// whatever its result
+    pub fn finish<T, E: std::fmt::Display>(&self, result: &Result<T, E>) {
+        let (status, message) = match result {
+            _ if self.is_cancelled() => (OperationStatus::Cancelled, None),
+            Ok(_) => (OperationStatus::Completed, None),
+            Err(e) => (OperationStatus::Failed, Some(e.to_string())),
+        };
+        self.inner.complete(status, message);
+    }
+}
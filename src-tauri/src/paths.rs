@@ -0,0 +1,294 @@
+// Paths - Platform path handling shared by scanner, cleaner and MCP
+//
+// `extended` and `display` convert to and from Windows extended-length form,
+// `key` and `is_within` compare paths spelled differently (NFC/NFD, case),
+// and `expand` and `canonical` resolve user-typed and sandboxed paths.
+
+use std::path::{Path, PathBuf};
+#[cfg(target_os = "macos")]
+use unicode_normalization::UnicodeNormalization;
+
+#[cfg(windows)]
+const VERBATIM: &str = r"\\?\";
+#[cfg(windows)]
+const VERBATIM_UNC: &str = r"\\?\UNC\";
+
+/// Extended-length form of an absolute path for filesystem calls
+#[cfg(windows)]
+pub fn extended(path: &Path) -> PathBuf {
+    use std::path::Component;
+
+    let raw = path.to_string_lossy();
+    // Already verbatim, or a device path (\\.\) that must stay as it is
+    if raw.starts_with(r"\\?\") || raw.starts_with(r"\\.\") || !path.is_absolute() {
+        return path.to_path_buf();
+    }
+    // Verbatim paths are not normalized by Windows: resolve "." and "..",
+    // and use backslashes only
+    let mut parts: Vec<String> = Vec::new();
+    let mut prefix = String::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(p) => {
+                let p = p.as_os_str().to_string_lossy().replace('/', "\\");
+                prefix = match p.strip_prefix(r"\\") {
+                    Some(unc) => format!("{}{}", VERBATIM_UNC, unc),
+                    None => format!("{}{}", VERBATIM, p),
+                };
+            }
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => {
+                parts.pop();
+            }
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+        }
+    }
+    PathBuf::from(format!("{}\\{}", prefix.trim_end_matches('\\'), parts.join("\\")))
+}
+
+#[cfg(not(windows))]
+pub fn extended(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// User-facing form of a path, without any extended-length prefix
+#[cfg(windows)]
+pub fn display(path: &Path) -> String {
+    let raw = path.to_string_lossy();
+    if let Some(unc) = raw.strip_prefix(VERBATIM_UNC) {
+        format!(r"\\{}", unc)
+    } else if let Some(local) = raw.strip_prefix(VERBATIM) {
+        local.to_string()
+    } else {
+        raw.to_string()
+    }
+}
+
+#[cfg(not(windows))]
+pub fn display(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// Display form of a path without trailing separators; the roots `/` and
+/// `C:\` keep theirs
+pub fn normalize(path: &str) -> String {
+    let normal = display(Path::new(path));
+    let trimmed = normal.trim_end_matches(['/', '\\']);
+    if trimmed.len() == normal.len() {
+        return normal;
+    }
+    if trimmed.is_empty() || (trimmed.len() == 2 && trimmed.ends_with(':')) {
+        return normal[..trimmed.len() + 1].to_string();
+    }
+    trimmed.to_string()
+}
+
+/// Comparison key of a path: `normalize`d, NFC on macOS, case-folded with
+/// backslashes on Windows
+pub fn key(path: &str) -> String {
+    #[allow(unused_mut)]
+    let mut key = normalize(path);
+    #[cfg(target_os = "macos")]
+    {
+        key = key.nfc().collect();
+    }
+    #[cfg(windows)]
+    {
+        key = key.replace('/', "\\").to_lowercase();
+    }
+    key
+}
+
+/// Whether `path` is `base` or lies below it, compared by `key`
+pub fn is_within(path: &Path, base: &Path) -> bool {
+    let path = key(&path.to_string_lossy());
+    let base = key(&base.to_string_lossy());
+    match path.strip_prefix(&base) {
+        Some("") => true,
+        Some(rest) => {
+            base.ends_with(std::path::MAIN_SEPARATOR) || rest.starts_with(std::path::MAIN_SEPARATOR)
+        }
+        None => false,
+    }
+}
+
+/// Whether two paths name the same location, compared by `key`
+pub fn same(a: &Path, b: &Path) -> bool {
+    key(&a.to_string_lossy()) == key(&b.to_string_lossy())
+}
+
+/// Whether `path` is a file system root or the home directory, which bulk
+/// deletions and moves must never take as a whole
+pub fn is_root_or_home(path: &Path) -> bool {
+    path.parent().is_none() || dirs::home_dir().is_some_and(|home| same(path, &home))
+}
+
+/// `path` with a leading `~` replaced by the home directory; `~user` is
+/// left as written
+pub fn expand_home(path: &str) -> PathBuf {
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => rest,
+        _ => return PathBuf::from(path),
+    };
+    match dirs::home_dir() {
+        Some(home) if rest.is_empty() => home,
+        Some(home) => home.join(rest.trim_start_matches(['/', '\\'])),
+        None => PathBuf::from(path),
+    }
+}
+
+fn var(name: &str) -> Option<String> {
+    if name.is_empty() {
+        return None;
+    }
+    std::env::var(name).ok()
+}
+
+/// Environment variables written `%NAME%` replaced by their values; unset
+/// ones are left as written
+#[cfg(windows)]
+fn expand_vars(path: &str) -> String {
+    let mut expanded = String::new();
+    let mut rest = path;
+    while let Some(start) = rest.find('%') {
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('%') else { break };
+        match var(&after[..end]) {
+            Some(value) => {
+                expanded.push_str(&rest[..start]);
+                expanded.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                expanded.push_str(&rest[..=start]);
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Environment variables written `$NAME` or `${NAME}` replaced by their
+/// values; unset ones are left as written
+#[cfg(not(windows))]
+fn expand_vars(path: &str) -> String {
+    let mut expanded = String::new();
+    let mut rest = path;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, tail) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => ("", after),
+            },
+            None => {
+                let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+                after.split_at(end)
+            }
+        };
+        match var(name) {
+            Some(value) => {
+                expanded.push_str(&value);
+                rest = tail;
+            }
+            None => {
+                expanded.push('$');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// A path as the user or a setting wrote it, with environment variables
+/// and a leading `~` expanded
+pub fn expand(path: &str) -> PathBuf {
+    expand_home(&expand_vars(path))
+}
+
+/// Canonical form of `path`, with links, `.` and `..` resolved. A path that
+/// doesn't exist yet (a file about to be written) resolves through its
+/// nearest existing ancestor. None if no part of it exists, or if `..`
+/// follows a missing part, which could otherwise climb out of a checked
+/// folder.
+pub fn canonical(path: &Path) -> Option<PathBuf> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            let mut result = canonical;
+            for component in missing.iter().rev() {
+                result.push(component);
+            }
+            return Some(result);
+        }
+        let name = existing.file_name()?;
+        missing.push(name.to_os_string());
+        existing = existing.parent()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_trims_trailing_separators_but_not_roots() {
+        assert_eq!(normalize("/home/user/"), "/home/user");
+        assert_eq!(normalize("/home/user//"), "/home/user");
+        assert_eq!(normalize("/"), "/");
+        assert_eq!(normalize("C:\\"), "C:\\");
+        assert_eq!(normalize("C:\\Users\\"), "C:\\Users");
+        assert_eq!(normalize("\\\\server\\share\\"), "\\\\server\\share");
+    }
+
+    #[test]
+    fn test_within_compares_whole_components() {
+        assert!(is_within(Path::new("/data/photos/2020"), Path::new("/data/photos")));
+        assert!(is_within(Path::new("/data/photos/"), Path::new("/data/photos")));
+        assert!(is_within(Path::new("/data"), Path::new("/")));
+        assert!(!is_within(Path::new("/data/photos-old"), Path::new("/data/photos")));
+        assert!(same(Path::new("/data/photos/"), Path::new("/data/photos")));
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home("~"), home);
+        assert_eq!(expand_home("~/.cache"), home.join(".cache"));
+        assert_eq!(expand_home("~other/x"), PathBuf::from("~other/x"));
+        assert_eq!(expand_home("/tmp/~"), PathBuf::from("/tmp/~"));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_expand_vars() {
+        std::env::set_var("HELIUM_PATHS_TEST", "/srv/data");
+        assert_eq!(expand("$HELIUM_PATHS_TEST/logs"), PathBuf::from("/srv/data/logs"));
+        assert_eq!(expand("${HELIUM_PATHS_TEST}-old"), PathBuf::from("/srv/data-old"));
+        assert_eq!(expand("/a/$HELIUM_PATHS_UNSET/b"), PathBuf::from("/a/$HELIUM_PATHS_UNSET/b"));
+        assert_eq!(expand("/cost/$5"), PathBuf::from("/cost/$5"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_expand_vars() {
+        std::env::set_var("HELIUM_PATHS_TEST", "D:\\data");
+        assert_eq!(expand("%HELIUM_PATHS_TEST%\\logs"), PathBuf::from("D:\\data\\logs"));
+        assert_eq!(expand("100%\\%HELIUM_PATHS_UNSET%"), PathBuf::from("100%\\%HELIUM_PATHS_UNSET%"));
+    }
+
+    #[test]
+    fn test_canonical_resolves_missing_tail() {
+        let dir = std::env::temp_dir().join(format!("helium-paths-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let real = dir.canonicalize().unwrap();
+        assert_eq!(canonical(&dir.join("new/file.txt")), Some(real.join("new").join("file.txt")));
+        assert_eq!(canonical(&dir.join("sub/../x")), None);
+        assert_eq!(canonical(&dir.join("..")), real.parent().map(Path::to_path_buf));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,155 @@
+// Permissions - Full Disk Access detection (macOS)
+//
+// macOS privacy protection (TCC) hides ~/Library/Mail, Safari data, the
+// Trash and similar folders from apps without Full Disk Access. Reads fail
+// with "Operation not permitted", which a scan otherwise turns into a silent
+// zero-size folder. This module probes those locations, reports the
+// permission state and opens the right System Settings pane. On other
+// platforms it reports that no permission is needed.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Event emitted when a scan touches a location it is not allowed to read
+pub const PERMISSION_EVENT: &str = "permission-required";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AccessState {
+    Granted,
+    Denied,
+    /// Nothing to probe (e.g. no protected folder exists)
+    Unknown,
+    NotApplicable,
+}
+
+/// A protected folder and whether Helium can read it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtectedLocation {
+    pub path: String,
+    pub accessible: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionReport {
+    pub full_disk_access: AccessState,
+    pub locations: Vec<ProtectedLocation>,
+}
+
+/// Payload of the permission-required event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionIssue {
+    /// The path whose scan is affected
+    pub path: String,
+    /// The protected folder that could not be read
+    pub blocked: String,
+    /// Settings pane that grants access (for `open_permission_settings`)
+    pub pane: SettingsPane,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SettingsPane {
+    FullDiskAccess,
+    FilesAndFolders,
+}
+
+/// Folders under the home directory that require Full Disk Access
+#[cfg(target_os = "macos")]
+fn protected_locations() -> Vec<PathBuf> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    [
+        "Library/Mail",
+        "Library/Messages",
+        "Library/Safari",
+        "Library/Application Support/com.apple.TCC",
+        ".Trash",
+    ]
+    .iter()
+    .map(|p| home.join(p))
+    .collect()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn protected_locations() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+/// Whether reading `path` is refused by the OS (as opposed to missing)
+fn is_denied(path: &Path) -> bool {
+    matches!(
+        std::fs::read_dir(path),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied
+    )
+}
+
+/// Current permission state with the result for each protected folder
+pub fn report() -> PermissionReport {
+    if !cfg!(target_os = "macos") {
+        return PermissionReport {
+            full_disk_access: AccessState::NotApplicable,
+            locations: Vec::new(),
+        };
+    }
+
+    let locations: Vec<ProtectedLocation> = protected_locations()
+        .into_iter()
+        .filter(|p| p.exists())
+        .map(|p| ProtectedLocation {
+            accessible: !is_denied(&p),
+            path: p.to_string_lossy().to_string(),
+        })
+        .collect();
+    let full_disk_access = if locations.is_empty() {
+        AccessState::Unknown
+    } else if locations.iter().all(|l| l.accessible) {
+        AccessState::Granted
+    } else {
+        AccessState::Denied
+    };
+    PermissionReport {
+        full_disk_access,
+        locations,
+    }
+}
+
+/// The protected folder a scan of `path` would be unable to read, if any:
+/// either `path` itself lies inside one, or it contains one.
+pub fn scan_issue(path: &str) -> Option<PermissionIssue> {
+    let root = Path::new(path);
+    protected_locations()
+        .into_iter()
+        .filter(|p| root.starts_with(p) || p.starts_with(root))
+        .find(|p| is_denied(p))
+        .map(|blocked| PermissionIssue {
+            path: path.to_string(),
+            blocked: blocked.to_string_lossy().to_string(),
+            pane: SettingsPane::FullDiskAccess,
+        })
+}
+
+/// Open the System Settings pane where the user grants access
+pub fn open_settings(pane: SettingsPane) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        let anchor = match pane {
+            SettingsPane::FullDiskAccess => "Privacy_AllFiles",
+            SettingsPane::FilesAndFolders => "Privacy_FilesAndFolders",
+        };
+        std::process::Command::new("open")
+            .arg(format!("x-apple.systempreferences:com.apple.preference.security?{}", anchor))
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = pane;
+        Err("Permission settings are only needed on macOS".to_string())
+    }
+}
@@ -0,0 +1,188 @@
+// Photo Bursts - Near-identical screenshots and photos
+//
+// Screenshot folders and camera imports fill up with runs of captures taken
+// seconds apart that differ by a cursor position or a blink. Images are
+// grouped by pixel dimensions, split into runs of close timestamps, and a run
+// is kept together while each image's perceptual hash (dHash) stays within a
+// few bits of the previous one. Each burst proposes keeping its largest file
+// (usually the most detail) and removing the rest through the regular junk
+// cleanup. Only PNG and JPEG are decoded.
+
+use crate::scanner::Exclusions;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BurstOptions {
+    /// Largest gap between consecutive captures of one burst, in seconds
+    pub max_gap_seconds: u64,
+    /// Largest dHash Hamming distance (of 64 bits) between neighbours
+    pub max_distance: u32,
+}
+
+impl Default for BurstOptions {
+    fn default() -> Self {
+        Self {
+            max_gap_seconds: 60,
+            max_distance: 6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BurstPhoto {
+    pub path: String,
+    pub size: u64,
+    pub last_modified: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhotoBurst {
+    pub width: u32,
+    pub height: u32,
+    /// Proposed to keep: the largest file of the burst
+    pub keep: BurstPhoto,
+    /// Proposed for removal, oldest first
+    pub duplicates: Vec<BurstPhoto>,
+    pub reclaimable: u64,
+}
+
+struct Candidate {
+    path: PathBuf,
+    size: u64,
+    modified: u64,
+}
+
+impl Candidate {
+    fn photo(&self) -> BurstPhoto {
+        BurstPhoto {
+            path: self.path.to_string_lossy().to_string(),
+            size: self.size,
+            last_modified: self.modified,
+        }
+    }
+}
+
+/// 64-bit difference hash: brightness gradients of a 9x8 grayscale thumbnail
+fn dhash(path: &Path) -> Option<u64> {
+    let image = image::open(path)
+        .map_err(|e| warn!("Cannot decode {}: {}", path.display(), e))
+        .ok()?;
+    let small = image.resize_exact(9, 8, image::imageops::FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    Some(hash)
+}
+
+/// Split a time-ordered run into bursts of visually similar neighbours
+fn split_similar(run: Vec<Candidate>, max_distance: u32) -> Vec<Vec<Candidate>> {
+    let hashes: Vec<Option<u64>> = run.par_iter().map(|c| dhash(&c.path)).collect();
+    let mut bursts: Vec<Vec<Candidate>> = Vec::new();
+    let mut previous: Option<u64> = None;
+    for (candidate, hash) in run.into_iter().zip(hashes) {
+        let similar = match (previous, hash) {
+            (Some(a), Some(b)) => (a ^ b).count_ones() <= max_distance,
+            _ => false,
+        };
+        match bursts.last_mut() {
+            Some(burst) if similar => burst.push(candidate),
+            _ => bursts.push(vec![candidate]),
+        }
+        previous = hash;
+    }
+    bursts
+}
+
+/// Bursts of near-identical images under `root`, most reclaimable first
+pub fn find_bursts(
+    root: &str,
+    options: &BurstOptions,
+    exclusions: &Exclusions,
+    cancel: &AtomicBool,
+) -> Result<Vec<PhotoBurst>, String> {
+    if !Path::new(root).is_dir() {
+        return Err("Directory does not exist".to_string());
+    }
+
+    // Dimensions come from the file header, so this pass stays cheap
+    let mut by_dimensions: HashMap<(u32, u32), Vec<Candidate>> = HashMap::new();
+    let walker = WalkDir::new(root).into_iter().filter_entry(|e| !exclusions.is_excluded(e.path()));
+    for entry in walker.filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".to_string());
+        }
+        let is_image = entry
+            .path()
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.as_str()));
+        if !is_image {
+            continue;
+        }
+        let (Ok(meta), Ok(dimensions)) = (entry.metadata(), image::image_dimensions(entry.path())) else {
+            continue;
+        };
+        let modified = meta
+            .modified()
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        by_dimensions.entry(dimensions).or_default().push(Candidate {
+            path: entry.into_path(),
+            size: meta.len(),
+            modified,
+        });
+    }
+
+    let mut bursts = Vec::new();
+    for ((width, height), mut candidates) in by_dimensions {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".to_string());
+        }
+        candidates.sort_by_key(|c| c.modified);
+        let mut runs: Vec<Vec<Candidate>> = Vec::new();
+        for candidate in candidates {
+            match runs.last_mut() {
+                Some(run) if candidate.modified - run.last().map_or(0, |c| c.modified) <= options.max_gap_seconds => {
+                    run.push(candidate)
+                }
+                _ => runs.push(vec![candidate]),
+            }
+        }
+        for run in runs.into_iter().filter(|r| r.len() > 1) {
+            for mut burst in split_similar(run, options.max_distance).into_iter().filter(|b| b.len() > 1) {
+                let keep_index = (0..burst.len()).max_by_key(|&i| burst[i].size).unwrap_or(0);
+                let keep = burst.remove(keep_index);
+                let duplicates: Vec<BurstPhoto> = burst.iter().map(Candidate::photo).collect();
+                bursts.push(PhotoBurst {
+                    width,
+                    height,
+                    keep: keep.photo(),
+                    reclaimable: duplicates.iter().map(|d| d.size).sum(),
+                    duplicates,
+                });
+            }
+        }
+    }
+    bursts.sort_by(|a, b| b.reclaimable.cmp(&a.reclaimable));
+    info!("Found {} photo bursts under {}", bursts.len(), root);
+    Ok(bursts)
+}
@@ -0,0 +1,160 @@
+// Profiles - Named workspace configurations
+//
+// A profile groups the scan roots, MCP allowed directories, scan exclusions
+// and default AI model that belong together ("Work laptop", "Media drive").
+// One profile is active at a time; the scanner, the MCP server and inference
+// read their defaults from it. With no active profile everything behaves as
+// before profiles existed.
+
+use crate::ai::ModelProvider;
+use crate::scanner::{Exclusions, SymlinkPolicy};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Event emitted when the active profile changes
+pub const PROFILE_EVENT: &str = "profile-changed";
+
+/// Model used when a request doesn't name one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DefaultModel {
+    pub provider: ModelProvider,
+    pub model_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    /// Folders offered as scan starting points
+    #[serde(default)]
+    pub scan_roots: Vec<String>,
+    /// Directories the MCP server may access while this profile is active
+    #[serde(default)]
+    pub mcp_allowed_directories: Vec<String>,
+    /// .gitignore-style patterns skipped by scans (see exclusions.rs)
+    #[serde(default)]
+    pub exclusions: Vec<String>,
+    /// Skip mounted network shares when scanning
+    #[serde(default)]
+    pub skip_network_mounts: bool,
+    /// Don't descend into other file systems (mounted drives and shares)
+    /// below a scanned folder
+    #[serde(default)]
+    pub same_device: bool,
+    /// What scans do with symbolic links
+    #[serde(default)]
+    pub symlink_policy: SymlinkPolicy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_model: Option<DefaultModel>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProfileData {
+    #[serde(default)]
+    profiles: Vec<Profile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    active_id: Option<String>,
+}
+
+/// Profiles persisted in the app data directory
+pub struct ProfileStore {
+    path: PathBuf,
+    data: Mutex<ProfileData>,
+}
+
+impl ProfileStore {
+    pub fn load(path: PathBuf) -> Self {
+        let data = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        Self {
+            path,
+            data: Mutex::new(data),
+        }
+    }
+
+    fn save(&self, data: &ProfileData) -> Result<(), String> {
+        storage::save_json(&self.path, data)
+    }
+
+    pub fn list(&self) -> Vec<Profile> {
+        self.data.lock().unwrap().profiles.clone()
+    }
+
+    pub fn active(&self) -> Option<Profile> {
+        let data = self.data.lock().unwrap();
+        let id = data.active_id.as_ref()?;
+        data.profiles.iter().find(|p| &p.id == id).cloned()
+    }
+
+    /// Scan exclusions of the active profile
+    pub fn exclusions(&self) -> Exclusions {
+        let Some(profile) = self.active() else { return Exclusions::default() };
+        let mut exclusions = Exclusions::new(profile.exclusions);
+        if profile.skip_network_mounts {
+            exclusions = exclusions.skipping_network_mounts();
+        }
+        if profile.same_device {
+            exclusions = exclusions.staying_on_device();
+        }
+        exclusions
+    }
+
+    /// Symlink policy of the active profile
+    pub fn symlink_policy(&self) -> SymlinkPolicy {
+        self.active().map(|profile| profile.symlink_policy).unwrap_or_default()
+    }
+
+    /// Create a profile, or replace the one with the same ID
+    pub fn save_profile(&self, mut profile: Profile) -> Result<Profile, String> {
+        if profile.name.trim().is_empty() {
+            return Err("Profile name cannot be empty".to_string());
+        }
+        if profile.id.is_empty() {
+            profile.id = uuid::Uuid::new_v4().to_string();
+        }
+        let mut data = self.data.lock().unwrap();
+        match data.profiles.iter_mut().find(|p| p.id == profile.id) {
+            Some(existing) => *existing = profile.clone(),
+            None => data.profiles.push(profile.clone()),
+        }
+        self.save(&data)?;
+        Ok(profile)
+    }
+
+    /// Delete a profile; deleting the active one deactivates it
+    pub fn delete(&self, id: &str) -> Result<bool, String> {
+        let mut data = self.data.lock().unwrap();
+        let before = data.profiles.len();
+        data.profiles.retain(|p| p.id != id);
+        if data.profiles.len() == before {
+            return Ok(false);
+        }
+        if data.active_id.as_deref() == Some(id) {
+            data.active_id = None;
+        }
+        self.save(&data)?;
+        Ok(true)
+    }
+
+    /// Make a profile active, or none with `None`
+    pub fn switch(&self, id: Option<&str>) -> Result<Option<Profile>, String> {
+        let mut data = self.data.lock().unwrap();
+        let profile = match id {
+            Some(id) => Some(
+                data.profiles
+                    .iter()
+                    .find(|p| p.id == id)
+                    .cloned()
+                    .ok_or_else(|| format!("Profile not found: {}", id))?,
+            ),
+            None => None,
+        };
+        data.active_id = profile.as_ref().map(|p| p.id.clone());
+        self.save(&data)?;
+        Ok(profile)
+    }
+}
@@ -0,0 +1,519 @@
+// Quarantine - Deletions that wait before they happen
+//
+// Bulk cleanups picked by the AI or by heuristics are where mistakes hide.
+// Instead of deleting such items, quarantine moves them into a managed
+// staging folder and keeps them for a number of days, after which they are
+// purged automatically; until then any of them can be restored to where it
+// came from. Unlike the OS trash the staging folder is Helium's own, so
+// retention is predictable and a restore doesn't depend on the platform's
+// trash implementation.
+//
+// Items are staged in the app's data folder, in a folder only the user can
+// open. Items on another volume would have to be copied there; on Unix they
+// go to the user's own `.helium-quarantine-<uid>` folder at that volume's
+// root instead, so quarantining stays a rename. Elsewhere, and whenever that
+// folder isn't private to the user, they are moved (copied, then removed)
+// into the data folder.
+// Quarantined items and the retention setting are tracked in a SQLite
+// database, so the index is updated row by row rather than rewritten whole
+// on every change, and expired items are found with a query.
+
+use crate::audit;
+use crate::i18n;
+use crate::paths;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// Event emitted when items enter or leave quarantine
+pub const QUARANTINE_EVENT: &str = "quarantine-changed";
+/// Prefix of the per-user staging folders at a volume root
+#[cfg(unix)]
+const STAGING_DIR: &str = ".helium-quarantine-";
+const PURGE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+const DEFAULT_RETENTION_DAYS: u32 = 14;
+
+/// Sources whose picks are guesses: the agent's, and heuristics like stale
+/// files. Their deletions are quarantined unless the user says otherwise.
+const LOW_CONFIDENCE_SOURCES: &[&str] = &["ai", "stale"];
+
+/// Whether deletions picked by `source` go to quarantine by default
+pub fn quarantines_by_default(source: &str) -> bool {
+    LOW_CONFIDENCE_SOURCES.contains(&source)
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS entries (
+        id TEXT PRIMARY KEY,
+        original_path TEXT NOT NULL,
+        quarantine_path TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        is_dir INTEGER NOT NULL,
+        source TEXT NOT NULL,
+        reason TEXT,
+        quarantined_at INTEGER NOT NULL,
+        expires_at INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS entries_expires_at ON entries (expires_at);
+    CREATE TABLE IF NOT EXISTS settings (
+        key TEXT PRIMARY KEY,
+        value INTEGER NOT NULL
+    );
+";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantineEntry {
+    pub id: String,
+    pub original_path: String,
+    /// Where the item is kept until it is restored or purged
+    pub quarantine_path: String,
+    pub size: u64,
+    pub is_dir: bool,
+    /// Where it was staged from, as in the basket: "treemap", "ai", ...
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Unix timestamps in seconds
+    pub quarantined_at: i64,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantineSummary {
+    pub entries: Vec<QuarantineEntry>,
+    pub total_size: u64,
+    pub retention_days: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantineFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreResult {
+    pub restored: Vec<String>,
+    pub failed: Vec<QuarantineFailure>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeResult {
+    pub purged: usize,
+    pub freed: u64,
+    pub failed: Vec<QuarantineFailure>,
+}
+
+fn entry_from_row(row: &Row) -> rusqlite::Result<QuarantineEntry> {
+    Ok(QuarantineEntry {
+        id: row.get(0)?,
+        original_path: row.get(1)?,
+        quarantine_path: row.get(2)?,
+        size: row.get::<_, i64>(3)? as u64,
+        is_dir: row.get(4)?,
+        source: row.get(5)?,
+        reason: row.get(6)?,
+        quarantined_at: row.get(7)?,
+        expires_at: row.get(8)?,
+    })
+}
+
+const ENTRY_COLUMNS: &str =
+    "id, original_path, quarantine_path, size, is_dir, source, reason, quarantined_at, expires_at";
+
+fn sql_error(e: rusqlite::Error) -> String {
+    format!("Quarantine database: {}", e)
+}
+
+/// Move `from` to `to`: a rename on the same volume, else copy and remove
+fn move_path(from: &Path, to: &Path) -> Result<(), String> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if from.is_dir() {
+        let options = fs_extra::dir::CopyOptions::new().copy_inside(true);
+        fs_extra::dir::move_dir(from, to, &options).map(|_| ()).map_err(|e| e.to_string())
+    } else {
+        let options = fs_extra::file::CopyOptions::new();
+        fs_extra::file::move_file(from, to, &options).map(|_| ()).map_err(|e| e.to_string())
+    }
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// `owner`'s staging folder on the volume holding `path`, when that isn't
+/// the volume of `home` (the data folder's staging folder, which it's
+/// better to use) and the folder can be made private to `owner`
+#[cfg(unix)]
+fn volume_staging_dir(path: &Path, home: &Path) -> Option<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    let home = std::fs::metadata(home).ok()?;
+    let item = std::fs::symlink_metadata(path).ok()?;
+    if item.dev() == home.dev() {
+        return None;
+    }
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    let mount = disks
+        .iter()
+        .filter(|d| paths::is_within(path, d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())?
+        .mount_point()
+        .to_path_buf();
+    let staging = mount.join(format!("{}{}", STAGING_DIR, home.uid()));
+    let _ = std::fs::DirBuilder::new().mode(0o700).create(&staging);
+    // Never stage into a folder someone else made, or others can open
+    let meta = std::fs::symlink_metadata(&staging).ok()?;
+    (meta.is_dir() && meta.uid() == home.uid() && meta.mode() & 0o077 == 0).then_some(staging)
+}
+
+#[cfg(not(unix))]
+fn volume_staging_dir(_path: &Path, _home: &Path) -> Option<PathBuf> {
+    None
+}
+
+/// Create `dir` (and missing parents) readable by the user only; an
+/// existing one is made private too
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    std::fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)
+}
+
+pub struct QuarantineStore {
+    /// The user's staging folder, in the app data folder
+    staging_dir: PathBuf,
+    db: Mutex<Connection>,
+}
+
+impl QuarantineStore {
+    /// Open (or create) the database at `path`. If it can't be opened the
+    /// store works in memory for this session, like a JSON store that
+    /// failed to load.
+    pub fn load(path: PathBuf, staging_dir: PathBuf) -> Self {
+        let db = Connection::open(&path)
+            .and_then(|db| db.execute_batch(SCHEMA).map(|_| db))
+            .unwrap_or_else(|e| {
+                warn!("Cannot open {}: {}; quarantine is kept in memory", path.display(), e);
+                let db = Connection::open_in_memory().expect("in-memory SQLite database");
+                db.execute_batch(SCHEMA).expect("quarantine schema");
+                db
+            });
+        Self {
+            staging_dir,
+            db: Mutex::new(db),
+        }
+    }
+
+    fn retention_days(db: &Connection) -> u32 {
+        db.query_row("SELECT value FROM settings WHERE key = 'retention_days'", [], |row| row.get(0))
+            .optional()
+            .ok()
+            .flatten()
+            .unwrap_or(DEFAULT_RETENTION_DAYS)
+    }
+
+    /// Entries matching `condition` (a WHERE clause over `params`), oldest first
+    fn entries(
+        db: &Connection,
+        condition: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<QuarantineEntry>, String> {
+        let sql = format!("SELECT {} FROM entries WHERE {} ORDER BY quarantined_at", ENTRY_COLUMNS, condition);
+        let mut statement = db.prepare(&sql).map_err(sql_error)?;
+        let rows = statement.query_map(params, entry_from_row).map_err(sql_error)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(sql_error)
+    }
+
+    fn remove_entries(db: &Connection, ids: &[String]) -> Result<(), String> {
+        for id in ids {
+            db.execute("DELETE FROM entries WHERE id = ?1", params![id]).map_err(sql_error)?;
+        }
+        Ok(())
+    }
+
+    pub fn summary(&self) -> QuarantineSummary {
+        let db = self.db.lock().unwrap();
+        let entries = Self::entries(&db, "1", params![]).unwrap_or_else(|e| {
+            warn!("{}", e);
+            Vec::new()
+        });
+        QuarantineSummary {
+            total_size: entries.iter().map(|e| e.size).sum(),
+            entries,
+            retention_days: Self::retention_days(&db),
+        }
+    }
+
+    pub fn set_retention(&self, days: u32) -> Result<QuarantineSummary, String> {
+        if days == 0 {
+            return Err(i18n::t("error.quarantine_retention"));
+        }
+        self.db
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO settings (key, value) VALUES ('retention_days', ?1)
+                 ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                params![days],
+            )
+            .map_err(sql_error)?;
+        Ok(self.summary())
+    }
+
+    /// Move one item into quarantine. `size` is passed in when the caller
+    /// already measured it.
+    pub fn quarantine(
+        &self,
+        original: &str,
+        size: Option<u64>,
+        source: &str,
+        reason: Option<String>,
+    ) -> Result<QuarantineEntry, String> {
+        self.admit(original, &paths::extended(Path::new(original)), size, source, reason)
+    }
+
+    /// Quarantine an item that was already moved away from `original` to
+    /// `current`, e.g. parked by a transaction; a restore puts it back at
+    /// `original`
+    pub fn quarantine_moved(
+        &self,
+        original: &str,
+        current: &Path,
+        source: &str,
+        reason: Option<String>,
+    ) -> Result<QuarantineEntry, String> {
+        self.admit(original, &paths::extended(current), None, source, reason)
+    }
+
+    fn admit(
+        &self,
+        original: &str,
+        from: &Path,
+        size: Option<u64>,
+        source: &str,
+        reason: Option<String>,
+    ) -> Result<QuarantineEntry, String> {
+        audit::ensure_writable()?;
+        let meta = std::fs::symlink_metadata(from).map_err(|e| format!("{}: {}", original, e))?;
+        let size = size.unwrap_or_else(|| {
+            if meta.is_dir() {
+                fs_extra::dir::get_size(from).unwrap_or(0)
+            } else {
+                meta.len()
+            }
+        });
+        let id = uuid::Uuid::new_v4().to_string();
+        let name = Path::new(original)
+            .file_name()
+            .map(|n| n.to_os_string())
+            .unwrap_or_else(|| "item".into());
+
+        create_private_dir(&self.staging_dir).map_err(|e| e.to_string())?;
+        let staged_in = |dir: &Path| dir.join(&id).join(&name);
+        let to = match volume_staging_dir(from, &self.staging_dir).map(|dir| staged_in(&dir)) {
+            Some(to) if move_path(from, &paths::extended(&to)).is_ok() => to,
+            _ => {
+                let to = staged_in(&self.staging_dir);
+                move_path(from, &paths::extended(&to))?;
+                to
+            }
+        };
+
+        let db = self.db.lock().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let entry = QuarantineEntry {
+            id,
+            original_path: original.to_string(),
+            quarantine_path: paths::display(&to),
+            size,
+            is_dir: meta.is_dir(),
+            source: source.to_string(),
+            reason,
+            quarantined_at: now,
+            expires_at: now + i64::from(Self::retention_days(&db)) * 86_400,
+        };
+        db.execute(
+            &format!("INSERT INTO entries ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)", ENTRY_COLUMNS),
+            params![
+                entry.id,
+                entry.original_path,
+                entry.quarantine_path,
+                entry.size as i64,
+                entry.is_dir,
+                entry.source,
+                entry.reason,
+                entry.quarantined_at,
+                entry.expires_at,
+            ],
+        )
+        .map_err(sql_error)?;
+        info!("Quarantined {} ({} bytes)", original, size);
+        Ok(entry)
+    }
+
+    /// Move items back to where they came from. An item whose original path
+    /// is taken again stays in quarantine.
+    pub fn restore(&self, ids: &[String]) -> Result<RestoreResult, String> {
+        audit::ensure_writable()?;
+        let db = self.db.lock().unwrap();
+        let mut result = RestoreResult::default();
+        let mut done = Vec::new();
+        let mut entries = Vec::new();
+        for id in ids {
+            entries.extend(Self::entries(&db, "id = ?1", params![id])?);
+        }
+        for entry in &entries {
+            let original = paths::extended(Path::new(&entry.original_path));
+            let outcome = if original.exists() {
+                Err(i18n::t("error.restore_target_exists"))
+            } else {
+                move_path(&paths::extended(Path::new(&entry.quarantine_path)), &original)
+            };
+            match outcome {
+                Ok(()) => {
+                    // The per-item folder is empty now
+                    if let Some(parent) = Path::new(&entry.quarantine_path).parent() {
+                        let _ = std::fs::remove_dir(paths::extended(parent));
+                    }
+                    result.restored.push(entry.original_path.clone());
+                    done.push(entry.id.clone());
+                }
+                Err(error) => result.failed.push(QuarantineFailure {
+                    path: entry.original_path.clone(),
+                    error,
+                }),
+            }
+        }
+        Self::remove_entries(&db, &done)?;
+        Ok(result)
+    }
+
+    /// Delete the staged copies of `entries` and forget them
+    fn purge_entries(db: &Connection, entries: Vec<QuarantineEntry>) -> Result<PurgeResult, String> {
+        let mut result = PurgeResult::default();
+        let mut done = Vec::new();
+        for entry in &entries {
+            let item_dir = Path::new(&entry.quarantine_path)
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| PathBuf::from(&entry.quarantine_path));
+            match remove_path(&paths::extended(&item_dir)) {
+                Ok(()) => {}
+                // Already gone: nothing left to purge
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    result.failed.push(QuarantineFailure {
+                        path: entry.original_path.clone(),
+                        error: e.to_string(),
+                    });
+                    continue;
+                }
+            }
+            result.purged += 1;
+            result.freed += entry.size;
+            done.push(entry.id.clone());
+        }
+        Self::remove_entries(db, &done)?;
+        Ok(result)
+    }
+
+    /// Delete quarantined items now
+    pub fn purge(&self, ids: &[String]) -> Result<PurgeResult, String> {
+        audit::ensure_writable()?;
+        let db = self.db.lock().unwrap();
+        let mut entries = Vec::new();
+        for id in ids {
+            entries.extend(Self::entries(&db, "id = ?1", params![id])?);
+        }
+        Self::purge_entries(&db, entries)
+    }
+
+    /// Delete items whose retention ended
+    pub fn purge_expired(&self) -> Result<PurgeResult, String> {
+        audit::ensure_writable()?;
+        let db = self.db.lock().unwrap();
+        let entries = Self::entries(&db, "expires_at <= ?1", params![chrono::Utc::now().timestamp()])?;
+        Self::purge_entries(&db, entries)
+    }
+
+    /// Purge expired items at startup and periodically while the app runs
+    pub fn spawn(app: AppHandle) {
+        tauri::async_runtime::spawn(async move {
+            loop {
+                // Audit mode keeps everything, expired or not
+                if !audit::is_enabled() {
+                    let handle = app.clone();
+                    let purged = tauri::async_runtime::spawn_blocking(move || {
+                        handle.state::<QuarantineStore>().purge_expired()
+                    })
+                    .await;
+                    match purged {
+                        Ok(Ok(result)) if result.purged > 0 => {
+                            info!("Purged {} expired quarantined items ({} bytes)", result.purged, result.freed);
+                            let _ = app.emit(QUARANTINE_EVENT, app.state::<QuarantineStore>().summary());
+                        }
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) => warn!("Quarantine purge failed: {}", e),
+                        Err(e) => warn!("Quarantine purge failed: {}", e),
+                    }
+                }
+                tokio::time::sleep(PURGE_INTERVAL).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moved_item_is_kept_privately_and_restored_to_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = QuarantineStore::load(dir.path().join("quarantine.db"), dir.path().join("staging"));
+        let original = dir.path().join("report.pdf");
+        let parked = dir.path().join("parked.pdf");
+        std::fs::write(&parked, "pdf").unwrap();
+
+        let entry = store
+            .quarantine_moved(&paths::display(&original), &parked, "ai", Some("duplicate".to_string()))
+            .unwrap();
+        assert!(!parked.exists());
+        assert!(Path::new(&entry.quarantine_path).starts_with(dir.path().join("staging")));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.path().join("staging")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        let restored = store.restore(&[entry.id]).unwrap();
+        assert_eq!(restored.restored.len(), 1);
+        assert_eq!(std::fs::read_to_string(&original).unwrap(), "pdf");
+    }
+}
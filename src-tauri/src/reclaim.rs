@@ -0,0 +1,175 @@
+// Reclaim - How much space a deletion would really free
+//
+// Summing the sizes of the selected items over-promises: a file with hard
+// links elsewhere keeps its data until the last link goes, cloud placeholders
+// (iCloud "dataless" files, OneDrive files on demand) show their full size but
+// occupy nothing locally, compressed and sparse files occupy less than their
+// length, and selecting a folder and something inside it counts the inner
+// item twice. Deleting to the trash frees nothing at all until the trash is
+// emptied, as the trash lives on the same volume. The forecast walks the
+// selection once and reports each of these effects and the free space each
+// volume would end up with.
+
+use crate::paths;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use sysinfo::Disks;
+use walkdir::WalkDir;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeForecast {
+    pub mount_point: String,
+    pub available_now: u64,
+    /// Free space after a permanent deletion (or after emptying the trash)
+    pub available_after: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReclaimForecast {
+    /// Naive sum of the items' sizes
+    pub requested_bytes: u64,
+    /// Disk space actually freed by a permanent deletion
+    pub reclaimable_bytes: u64,
+    /// Data kept alive by hard links outside the selection
+    pub hard_linked_bytes: u64,
+    /// Size of cloud placeholders, which occupy no local space
+    pub cloud_placeholder_bytes: u64,
+    /// Items selected twice (inside another selected folder), counted once
+    pub overlapping_bytes: u64,
+    /// Moving to the trash frees nothing until it is emptied
+    pub freed_by_trash: u64,
+    pub files: u64,
+    pub volumes: Vec<VolumeForecast>,
+}
+
+/// Identity of a file's data, where the platform exposes it
+#[cfg(unix)]
+fn inode(meta: &std::fs::Metadata) -> Option<((u64, u64), u64)> {
+    use std::os::unix::fs::MetadataExt;
+    (meta.nlink() > 1).then(|| ((meta.dev(), meta.ino()), meta.nlink()))
+}
+
+#[cfg(not(unix))]
+fn inode(_meta: &std::fs::Metadata) -> Option<((u64, u64), u64)> {
+    None
+}
+
+/// Whether the file's content lives in the cloud rather than on disk
+#[cfg(target_os = "macos")]
+fn is_placeholder(meta: &std::fs::Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    const SF_DATALESS: u32 = 0x4000_0000;
+    meta.st_flags() & SF_DATALESS != 0 || (meta.st_blocks() == 0 && meta.len() > 0)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn is_placeholder(meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    meta.blocks() == 0 && meta.len() > 0
+}
+
+#[cfg(windows)]
+fn is_placeholder(meta: &std::fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x40000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x400000;
+    meta.file_attributes() & (FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_OPEN | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS) != 0
+}
+
+/// Bytes the file occupies on disk
+#[cfg(unix)]
+fn allocated(meta: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn allocated(meta: &std::fs::Metadata) -> u64 {
+    meta.len()
+}
+
+/// Forecast the effect of deleting `targets`
+pub fn forecast(targets: &[String], cancel: &AtomicBool) -> Result<ReclaimForecast, String> {
+    let mut forecast = ReclaimForecast::default();
+    // Hard-linked inodes: allocated bytes, total links, links seen here, mount
+    let mut linked: HashMap<(u64, u64), (u64, u64, u64, String)> = HashMap::new();
+    let mut freed_per_volume: HashMap<String, u64> = HashMap::new();
+
+    let disks = Disks::new_with_refreshed_list();
+    let volume_of = |path: &Path| {
+        disks
+            .iter()
+            .filter(|d| paths::is_within(path, d.mount_point()))
+            .max_by_key(|d| d.mount_point().as_os_str().len())
+            .map(|d| d.mount_point().to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+
+    for (index, target) in targets.iter().enumerate() {
+        let path = Path::new(target);
+        let nested = targets
+            .iter()
+            .enumerate()
+            .any(|(other, outer)| other != index && paths::is_within(path, Path::new(outer)) && !paths::same(path, Path::new(outer)));
+        let duplicate = targets[..index].iter().any(|earlier| paths::same(path, Path::new(earlier)));
+
+        let volume = volume_of(path);
+        let walker = WalkDir::new(paths::extended(path));
+        for entry in walker.into_iter().filter_map(|e| e.ok()).filter(|e| !e.file_type().is_dir()) {
+            if cancel.load(Ordering::Relaxed) {
+                return Err("Cancelled".to_string());
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            let size = meta.len();
+            forecast.requested_bytes += size;
+            if nested || duplicate {
+                forecast.overlapping_bytes += size;
+                continue;
+            }
+            forecast.files += 1;
+            if is_placeholder(&meta) {
+                forecast.cloud_placeholder_bytes += size;
+                continue;
+            }
+            let bytes = allocated(&meta);
+            match inode(&meta) {
+                Some((id, links)) => {
+                    let seen = linked.entry(id).or_insert((bytes, links, 0, volume.clone()));
+                    seen.2 += 1;
+                }
+                None => *freed_per_volume.entry(volume.clone()).or_default() += bytes,
+            }
+        }
+    }
+
+    // Hard-linked data is only freed when every link is deleted
+    for (bytes, links, seen, volume) in linked.into_values() {
+        if seen >= links {
+            *freed_per_volume.entry(volume).or_default() += bytes;
+        } else {
+            forecast.hard_linked_bytes += bytes;
+        }
+    }
+
+    forecast.reclaimable_bytes = freed_per_volume.values().sum();
+    forecast.volumes = disks
+        .iter()
+        .filter_map(|disk| {
+            let mount = disk.mount_point().to_string_lossy().to_string();
+            let freed = *freed_per_volume.get(&mount)?;
+            Some(VolumeForecast {
+                available_now: disk.available_space(),
+                available_after: (disk.available_space() + freed).min(disk.total_space()),
+                mount_point: mount,
+            })
+        })
+        .collect();
+    Ok(forecast)
+}
@@ -0,0 +1,396 @@
+// Remote - Scanning NAS boxes and servers over SSH
+//
+// A remote host is scanned by running `find` on it through the system `ssh`
+// client and building the usual FileNode tree from the listing, so servers
+// appear next to local drives and drill down the same way. Remote paths are
+// written `ssh://<host id>/<absolute path>`. Authentication relies on the
+// user's SSH keys or agent (BatchMode, never a password prompt). Hosts whose
+// `find` lacks `-printf` (BusyBox) are scanned with `du -a` instead, which
+// reports sizes in kilobytes and no modification times.
+
+use crate::scanner::{FileNode, ScanStats};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+/// Scheme prefix of remote paths
+pub const SCHEME: &str = "ssh://";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteHost {
+    pub id: String,
+    /// Display name shown with the drives
+    pub name: String,
+    pub host: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// Private key to use instead of the agent / default keys
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<String>,
+    /// Directory shown as the host's root (defaults to "/")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+}
+
+impl RemoteHost {
+    /// Remote path of `path` on this host
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}{}", SCHEME, self.id, path)
+    }
+
+    fn ssh(&self) -> Command {
+        let mut cmd = Command::new("ssh");
+        cmd.args(["-o", "BatchMode=yes", "-o", "ConnectTimeout=10"]);
+        if let Some(port) = self.port {
+            cmd.args(["-p", &port.to_string()]);
+        }
+        if let Some(identity) = &self.identity_file {
+            cmd.arg("-i").arg(identity);
+        }
+        match &self.user {
+            Some(user) => cmd.arg(format!("{}@{}", user, self.host)),
+            None => cmd.arg(&self.host),
+        };
+        cmd
+    }
+
+    /// Run a shell command on the host, returning its output
+    pub fn run(&self, command: &str) -> Result<String, String> {
+        let output = self.ssh().arg(command).output().map_err(|e| format!("Failed to start ssh: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("{}: {}", self.host, String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Copy a local file to `remote` on the host, creating its folder. The
+    /// file is streamed through the ssh connection, so this works wherever
+    /// SFTP does without depending on the scp protocol variant.
+    pub fn upload(&self, local: &Path, remote: &str) -> Result<(), String> {
+        let dir = parent_of(remote).unwrap_or("/");
+        let mut file = std::fs::File::open(local).map_err(|e| e.to_string())?;
+        let mut child = self
+            .ssh()
+            .arg(format!("mkdir -p {} && cat > {}", shell_quote(dir), shell_quote(remote)))
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start ssh: {}", e))?;
+        let mut stdin = child.stdin.take().ok_or("ssh accepted no input")?;
+        let copied = std::io::copy(&mut file, &mut stdin);
+        drop(stdin);
+        let output = child.wait_with_output().map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(format!("{}: {}", self.host, String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        copied.map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    /// Copy `remote` on the host to a new local file
+    pub fn download(&self, remote: &str, local: &Path) -> Result<(), String> {
+        let file = std::fs::File::create(local).map_err(|e| e.to_string())?;
+        let output = self
+            .ssh()
+            .arg(format!("cat {}", shell_quote(remote)))
+            .stdout(Stdio::from(file))
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|e| format!("Failed to start ssh: {}", e))?;
+        if !output.status.success() {
+            let _ = std::fs::remove_file(local);
+            return Err(format!("{}: {}", self.host, String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(())
+    }
+}
+
+/// Quote an argument for the remote shell
+pub(crate) fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Flat listing entry
+struct RemoteEntry {
+    is_dir: bool,
+    size: u64,
+    modified: u64,
+}
+
+/// Run a listing command remotely, streaming lines to `parse`
+fn run_listing(
+    host: &RemoteHost,
+    command: &str,
+    cancel: Option<&Arc<AtomicBool>>,
+    mut parse: impl FnMut(&str),
+) -> Result<bool, String> {
+    let mut child = host
+        .ssh()
+        .arg(command)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start ssh: {}", e))?;
+    let stdout = child.stdout.take().ok_or("ssh produced no output")?;
+    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+        if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+            let _ = child.kill();
+            return Err("Cancelled".to_string());
+        }
+        parse(&line);
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    // find/du exit with 1 on unreadable entries but still list the rest
+    match output.status.code() {
+        Some(0) | Some(1) => Ok(true),
+        Some(255) => Err(format!(
+            "Cannot connect to {}: {}",
+            host.host,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        _ => Ok(false),
+    }
+}
+
+/// "/srv/data/" -> "/srv/data", keeping "/" itself
+fn trim_path(path: &str) -> &str {
+    match path.trim_end_matches('/') {
+        "" => "/",
+        trimmed => trimmed,
+    }
+}
+
+fn record(entries: &mut BTreeMap<String, RemoteEntry>, stats: Option<&Arc<ScanStats>>, path: &str, entry: RemoteEntry) {
+    if let (Some(stats), false) = (stats, entry.is_dir) {
+        stats.scanned_files.fetch_add(1, Ordering::Relaxed);
+        stats.total_size.fetch_add(entry.size, Ordering::Relaxed);
+    }
+    entries.insert(trim_path(path).to_string(), entry);
+}
+
+fn list(
+    host: &RemoteHost,
+    root: &str,
+    stats: Option<&Arc<ScanStats>>,
+    cancel: Option<&Arc<AtomicBool>>,
+) -> Result<BTreeMap<String, RemoteEntry>, String> {
+    let mut entries = BTreeMap::new();
+
+    // GNU find: type, size, mtime and path per line, staying on one filesystem
+    let find = format!("find {} -xdev -printf '%y\\t%s\\t%T@\\t%p\\n'", shell_quote(root));
+    let ok = run_listing(host, &find, cancel, |line| {
+        let mut fields = line.splitn(4, '\t');
+        let (Some(kind), Some(size), Some(mtime), Some(path)) = (fields.next(), fields.next(), fields.next(), fields.next()) else {
+            return;
+        };
+        record(&mut entries, stats, path, RemoteEntry {
+            is_dir: kind == "d",
+            size: size.parse().unwrap_or(0),
+            modified: mtime.split('.').next().and_then(|s| s.parse().ok()).unwrap_or(0),
+        });
+    })?;
+    if ok && !entries.is_empty() {
+        return Ok(entries);
+    }
+    entries.clear();
+
+    info!("find -printf unavailable on {}, falling back to du", host.host);
+    let mut sizes: Vec<(String, u64)> = Vec::new();
+    let du = format!("du -a -k -x {}", shell_quote(root));
+    run_listing(host, &du, cancel, |line| {
+        if let Some((kb, path)) = line.split_once('\t') {
+            if let Ok(kb) = kb.trim().parse::<u64>() {
+                sizes.push((trim_path(path).to_string(), kb * 1024));
+            }
+        }
+    })?;
+    // du lists a directory after its contents: anything that is a prefix of
+    // an earlier path is a directory
+    let mut dirs = std::collections::HashSet::new();
+    for (path, _) in &sizes {
+        if let Some((parent, _)) = path.rsplit_once('/') {
+            dirs.insert(if parent.is_empty() { "/".to_string() } else { parent.to_string() });
+        }
+    }
+    for (path, size) in sizes {
+        let is_dir = dirs.contains(&path);
+        record(&mut entries, stats, &path, RemoteEntry {
+            is_dir,
+            size: if is_dir { 0 } else { size },
+            modified: 0,
+        });
+    }
+    Ok(entries)
+}
+
+fn parent_of(path: &str) -> Option<&str> {
+    match path.rsplit_once('/') {
+        Some(("", _)) if path != "/" => Some("/"),
+        Some((parent, _)) if !parent.is_empty() => Some(parent),
+        _ => None,
+    }
+}
+
+/// Build a FileNode with `depth` levels of children from the flat listing
+fn build(host: &RemoteHost, entries: &BTreeMap<String, RemoteEntry>, totals: &BTreeMap<String, (u64, u64)>, path: &str, depth: usize) -> FileNode {
+    let entry = &entries[path];
+    let (size, file_count) = if entry.is_dir { totals.get(path).copied().unwrap_or((0, 0)) } else { (entry.size, 1) };
+    let children = (entry.is_dir && depth > 0).then(|| {
+        let prefix = if path == "/" { "/".to_string() } else { format!("{}/", path) };
+        let mut children: Vec<FileNode> = entries
+            .range(prefix.clone()..)
+            .take_while(|(p, _)| p.starts_with(&prefix))
+            .filter(|(p, _)| p.len() > prefix.len() && !p[prefix.len()..].contains('/'))
+            .map(|(p, _)| build(host, entries, totals, p, depth - 1))
+            .collect();
+        children.sort_by(|a, b| b.size.cmp(&a.size));
+        children
+    });
+    FileNode {
+        name: path.rsplit('/').next().filter(|n| !n.is_empty()).unwrap_or(&host.name).to_string(),
+        path: host.url(path),
+        size,
+        is_dir: entry.is_dir,
+        children,
+        last_modified: entry.modified,
+        file_count,
+        volume: None,
+        link: None,
+        skipped_links: None,
+        allocated_size: None,
+        extensions: None,
+        deferred: false,
+    }
+}
+
+/// Scan `path` on `host`, returning the root with two levels of children
+/// like a local scan
+pub fn scan(
+    host: &RemoteHost,
+    path: &str,
+    stats: Option<Arc<ScanStats>>,
+    cancel: Option<Arc<AtomicBool>>,
+) -> Result<FileNode, String> {
+    let root = trim_path(path);
+    let entries = list(host, root, stats.as_ref(), cancel.as_ref())?;
+    if !entries.contains_key(root) {
+        return Err(format!("{} does not exist on {}", root, host.name));
+    }
+
+    // Directory totals: every file adds to all of its ancestors
+    let mut totals: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    for (file, entry) in entries.iter().filter(|(_, e)| !e.is_dir) {
+        let mut current = parent_of(file);
+        while let Some(dir) = current {
+            let total = totals.entry(dir.to_string()).or_default();
+            total.0 += entry.size;
+            total.1 += 1;
+            if dir == root {
+                break;
+            }
+            current = parent_of(dir);
+        }
+    }
+    Ok(build(host, &entries, &totals, root, 2))
+}
+
+/// Configured remote hosts, persisted in the app data directory
+pub struct RemoteStore {
+    path: PathBuf,
+    hosts: Mutex<Vec<RemoteHost>>,
+}
+
+impl RemoteStore {
+    pub fn load(path: PathBuf) -> Self {
+        let hosts = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        Self {
+            path,
+            hosts: Mutex::new(hosts),
+        }
+    }
+
+    pub fn list(&self) -> Vec<RemoteHost> {
+        self.hosts.lock().unwrap().clone()
+    }
+
+    /// Add a host, or replace the one with the same ID
+    pub fn save_host(&self, mut host: RemoteHost) -> Result<RemoteHost, String> {
+        if host.host.trim().is_empty() {
+            return Err("Host name cannot be empty".to_string());
+        }
+        if host.id.is_empty() {
+            host.id = uuid::Uuid::new_v4().to_string();
+        }
+        if host.id.contains('/') {
+            return Err("Host ID cannot contain '/'".to_string());
+        }
+        let mut hosts = self.hosts.lock().unwrap();
+        match hosts.iter_mut().find(|h| h.id == host.id) {
+            Some(existing) => *existing = host.clone(),
+            None => hosts.push(host.clone()),
+        }
+        storage::save_json(&self.path, &*hosts)?;
+        Ok(host)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<bool, String> {
+        let mut hosts = self.hosts.lock().unwrap();
+        let before = hosts.len();
+        hosts.retain(|h| h.id != id);
+        if hosts.len() == before {
+            return Ok(false);
+        }
+        storage::save_json(&self.path, &*hosts)?;
+        Ok(true)
+    }
+
+    pub fn get(&self, id: &str) -> Option<RemoteHost> {
+        self.hosts.lock().unwrap().iter().find(|h| h.id == id).cloned()
+    }
+
+    /// Host and remote path of an `ssh://` path; None for local paths
+    pub fn resolve(&self, url: &str) -> Option<Result<(RemoteHost, String), String>> {
+        let rest = url.strip_prefix(SCHEME)?;
+        let (id, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], rest[i..].to_string()),
+            None => (rest, String::new()),
+        };
+        Some(match self.get(id) {
+            Some(host) => {
+                let path = if path.is_empty() { host.root.clone().unwrap_or_else(|| "/".to_string()) } else { path };
+                Ok((host, path))
+            }
+            None => Err(format!("Unknown remote host: {}", id)),
+        })
+    }
+
+    /// Hosts as drive entries (size unknown until scanned)
+    pub fn drives(&self) -> Vec<FileNode> {
+        self.list()
+            .into_iter()
+            .map(|host| FileNode {
+                path: host.url(host.root.as_deref().unwrap_or("/")),
+                name: host.name,
+                size: 0,
+                is_dir: true,
+                children: None,
+                last_modified: 0,
+                file_count: 0,
+                volume: None,
+                link: None,
+                skipped_links: None,
+                allocated_size: None,
+                extensions: None,
+                deferred: false,
+            })
+            .collect()
+    }
+}
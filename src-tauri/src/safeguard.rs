@@ -0,0 +1,135 @@
+// Safeguard - Recovery record taken before risky cleanups
+//
+// Some junk categories (system temp folders, prefetch data, system logs)
+// are safe to clear in general but occasionally hold something a user
+// needed. When the user opts in, cleaning any of them first creates a System
+// Restore point on Windows, and on every platform writes a manifest of the
+// files about to be deleted with their sizes and SHA-256 hashes, so what was
+// removed can be identified and recovered from a backup later.
+
+use crate::paths;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+/// Files above this size are listed without a hash
+const MAX_HASH_BYTES: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    created_at: i64,
+    entries: Vec<ManifestEntry>,
+}
+
+/// What was recorded before a cleanup
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreCleanRecord {
+    pub restore_point: bool,
+    /// Why no restore point was created, where one was attempted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restore_point_error: Option<String>,
+    pub manifest_path: String,
+    pub files: u64,
+}
+
+pub(crate) fn sha256_file(path: &Path) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buffer[..n]),
+            Err(_) => return None,
+        }
+    }
+    Some(hex::encode(hasher.finalize()))
+}
+
+/// Write a manifest of every file under `targets` into `dir`
+pub fn write_manifest(targets: &[String], dir: &Path) -> Result<(PathBuf, u64), String> {
+    let mut entries = Vec::new();
+    for target in targets {
+        let walker = WalkDir::new(paths::extended(Path::new(target)));
+        for entry in walker.into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            entries.push(ManifestEntry {
+                path: paths::display(entry.path()),
+                size,
+                sha256: (size <= MAX_HASH_BYTES).then(|| sha256_file(entry.path())).flatten(),
+            });
+        }
+    }
+
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let now = chrono::Utc::now();
+    let path = dir.join(format!("cleanup-{}.json", now.format("%Y%m%d-%H%M%S")));
+    let manifest = Manifest {
+        created_at: now.timestamp(),
+        entries,
+    };
+    let json = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write cleanup manifest: {}", e))?;
+    Ok((path, manifest.entries.len() as u64))
+}
+
+/// Create a System Restore point. Needs administrator rights, and Windows
+/// skips it silently if another was created within the last 24 hours.
+#[cfg(windows)]
+pub fn create_restore_point(description: &str) -> Result<(), String> {
+    let script = format!(
+        "Checkpoint-Computer -Description '{}' -RestorePointType MODIFY_SETTINGS -ErrorAction Stop",
+        description.replace('\'', "''")
+    );
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .output()
+        .map_err(|e| format!("Failed to run PowerShell: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn create_restore_point(_description: &str) -> Result<(), String> {
+    Err("System Restore is only available on Windows".to_string())
+}
+
+/// Recovery record for a cleanup of `targets`: a restore point where the
+/// platform has them, and always a manifest in `manifest_dir`
+pub fn before_clean(targets: &[String], manifest_dir: &Path) -> Result<PreCleanRecord, String> {
+    let (restore_point, restore_point_error) = if cfg!(windows) {
+        match create_restore_point("Helium cleanup") {
+            Ok(()) => (true, None),
+            Err(e) => {
+                warn!("Restore point not created: {}", e);
+                (false, Some(e))
+            }
+        }
+    } else {
+        (false, None)
+    };
+    let (manifest_path, files) = write_manifest(targets, manifest_dir)?;
+    info!("Cleanup manifest of {} files written to {}", files, manifest_path.display());
+    Ok(PreCleanRecord {
+        restore_point,
+        restore_point_error,
+        manifest_path: manifest_path.to_string_lossy().to_string(),
+        files,
+    })
+}
@@ -0,0 +1,151 @@
+// Scan Cache - Recently scanned trees, keyed by normalized path
+//
+// A scan returns its root with one level of children that already carry
+// their own children, so every child is cached too: drilling into a folder
+// right after scanning its parent is served from memory.
+//
+// A tree of a big drive takes hundreds of megabytes, so the cache has a
+// ceiling on its estimated size: past it, the least recently used entries
+// are dropped (see memory).
+
+use crate::scanner::FileNode;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime};
+
+/// Ceiling on the cache's estimated size unless configured otherwise
+pub const DEFAULT_MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+static MAX_BYTES: AtomicU64 = AtomicU64::new(DEFAULT_MAX_BYTES);
+
+struct CacheEntry {
+    node: FileNode,
+    timestamp: SystemTime,
+    /// Estimated memory held by `node`
+    bytes: u64,
+    last_used: Instant,
+}
+
+/// How much the cache holds, for the memory report
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheUsage {
+    pub entries: usize,
+    pub bytes: u64,
+    pub max_bytes: u64,
+}
+
+lazy_static! {
+    static ref SCAN_CACHE: Mutex<HashMap<String, CacheEntry>> = Mutex::new(HashMap::new());
+}
+
+const CACHE_TTL: u64 = 60 * 60; 
+
+/// Cached tree for `path`, if scanned within the TTL
+pub fn get(path: &str) -> Option<FileNode> {
+    let mut cache = SCAN_CACHE.lock().ok()?;
+    let entry = cache.get_mut(&crate::paths::key(path))?;
+    let elapsed = entry.timestamp.elapsed().ok()?;
+    if elapsed.as_secs() >= CACHE_TTL {
+        return None;
+    }
+    entry.last_used = Instant::now();
+    Some(entry.node.clone())
+}
+
+/// Whether `path` has a cached tree within the TTL, without cloning it
+pub fn is_cached(path: &str) -> bool {
+    let Ok(cache) = SCAN_CACHE.lock() else {
+        return false;
+    };
+    cache
+        .get(&crate::paths::key(path))
+        .and_then(|entry| entry.timestamp.elapsed().ok())
+        .is_some_and(|elapsed| elapsed.as_secs() < CACHE_TTL)
+}
+
+/// Cache a scanned tree and its immediate children
+pub fn store(path: &str, node: &FileNode) {
+    let Ok(mut cache) = SCAN_CACHE.lock() else {
+        return;
+    };
+    let now = SystemTime::now();
+    
+    cache.insert(crate::paths::key(path), CacheEntry {
+        node: node.clone(),
+        timestamp: now,
+        bytes: estimated_bytes(node),
+        last_used: Instant::now(),
+    });
+    
+    if let Some(children) = &node.children {
+        for child in children {
+            cache.insert(crate::paths::key(&child.path), CacheEntry {
+                node: child.clone(),
+                timestamp: now,
+                bytes: estimated_bytes(child),
+                last_used: Instant::now(),
+            });
+        }
+    }
+    evict(&mut cache, MAX_BYTES.load(Ordering::Relaxed));
+}
+
+/// Rough heap and inline size of a tree: the nodes and their strings
+fn estimated_bytes(node: &FileNode) -> u64 {
+    let own = std::mem::size_of::<FileNode>() + node.name.len() + node.path.len();
+    let children = node.children.iter().flatten().map(estimated_bytes).sum::<u64>();
+    own as u64 + children
+}
+
+/// Drop least recently used entries until the cache fits in `max_bytes`
+fn evict(cache: &mut HashMap<String, CacheEntry>, max_bytes: u64) {
+    let mut total: u64 = cache.values().map(|e| e.bytes).sum();
+    if total <= max_bytes {
+        return;
+    }
+    let mut by_use: Vec<(Instant, String, u64)> = cache
+        .iter()
+        .map(|(key, entry)| (entry.last_used, key.clone(), entry.bytes))
+        .collect();
+    by_use.sort_by_key(|(last_used, _, _)| *last_used);
+    let mut evicted = 0;
+    for (_, key, bytes) in by_use {
+        if total <= max_bytes {
+            break;
+        }
+        cache.remove(&key);
+        total -= bytes;
+        evicted += 1;
+    }
+    tracing::info!("Evicted {} scan cache entries to stay within {} bytes", evicted, max_bytes);
+}
+
+/// Change the ceiling, evicting entries if the cache is now over it
+pub fn set_max_bytes(max_bytes: u64) {
+    MAX_BYTES.store(max_bytes, Ordering::Relaxed);
+    if let Ok(mut cache) = SCAN_CACHE.lock() {
+        evict(&mut cache, max_bytes);
+    }
+}
+
+pub fn usage() -> CacheUsage {
+    let (entries, bytes) = SCAN_CACHE
+        .lock()
+        .map(|cache| (cache.len(), cache.values().map(|e| e.bytes).sum()))
+        .unwrap_or((0, 0));
+    CacheUsage {
+        entries,
+        bytes,
+        max_bytes: MAX_BYTES.load(Ordering::Relaxed),
+    }
+}
+
+pub fn clear() {
+    if let Ok(mut cache) = SCAN_CACHE.lock() {
+        cache.clear();
+    }
+}
@@ -0,0 +1,156 @@
+// Scan History - Folder sizes recorded by past scans
+//
+// Every fresh scan records its root and immediate children with their sizes.
+// Comparing the latest record of a folder against an older one shows what
+// grew in between, which the drive space alerts quote ("Downloads grew
+// 12 GB since last week").
+
+use crate::scanner::FileNode;
+use crate::paths;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Records kept per scanned root
+const MAX_RECORDS_PER_ROOT: usize = 30;
+
+/// Records older than this are dropped (90 days)
+const MAX_AGE_SECS: i64 = 90 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanRecord {
+    pub path: String,
+    /// Unix timestamp of the scan
+    pub timestamp: i64,
+    pub size: u64,
+    /// Size of each immediate child, keyed by path
+    #[serde(default)]
+    pub children: BTreeMap<String, u64>,
+}
+
+/// How much a folder grew between two scans
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderGrowth {
+    pub path: String,
+    pub previous_size: u64,
+    pub current_size: u64,
+    /// Growth in bytes (negative if the folder shrank)
+    pub growth: i64,
+    /// Timestamp of the scan compared against
+    pub since: i64,
+}
+
+/// Scan records persisted in the app data directory
+pub struct ScanHistory {
+    path: PathBuf,
+    records: Mutex<Vec<ScanRecord>>,
+}
+
+impl ScanHistory {
+    pub fn load(path: PathBuf) -> Self {
+        let records = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        Self {
+            path,
+            records: Mutex::new(records),
+        }
+    }
+
+    /// Record the sizes of a freshly scanned tree
+    pub fn record(&self, node: &FileNode) {
+        let now = chrono::Utc::now().timestamp();
+        let record = ScanRecord {
+            path: node.path.clone(),
+            timestamp: now,
+            size: node.size,
+            children: node
+                .children
+                .iter()
+                .flatten()
+                .filter(|c| c.is_dir)
+                .map(|c| (c.path.clone(), c.size))
+                .collect(),
+        };
+
+        let mut records = self.records.lock().unwrap();
+        records.retain(|r| now - r.timestamp < MAX_AGE_SECS);
+        records.push(record);
+        let root = paths::key(&node.path);
+        let same_root = records.iter().filter(|r| paths::key(&r.path) == root).count();
+        if same_root > MAX_RECORDS_PER_ROOT {
+            if let Some(oldest) = records.iter().position(|r| paths::key(&r.path) == root) {
+                records.remove(oldest);
+            }
+        }
+        if let Err(e) = storage::save_json(&self.path, &*records) {
+            warn!("Failed to save scan history: {}", e);
+        }
+    }
+
+    /// Records of one root, oldest first
+    pub fn records(&self, path: &str) -> Vec<ScanRecord> {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| paths::key(&r.path) == paths::key(path))
+            .cloned()
+            .collect()
+    }
+
+    /// The folder that grew the most since `since`, among the roots accepted
+    /// by `include`. Each root's latest scan is compared with its newest scan
+    /// from before `since`, or its oldest scan if all are more recent.
+    pub fn largest_growth(&self, include: impl Fn(&str) -> bool, since: i64) -> Option<FolderGrowth> {
+        let records = self.records.lock().unwrap();
+        let mut roots: Vec<&str> = records.iter().map(|r| r.path.as_str()).filter(|p| include(p)).collect();
+        roots.sort_by_key(|p| paths::key(p));
+        roots.dedup_by_key(|p| paths::key(p));
+
+        let mut best: Option<FolderGrowth> = None;
+        for root in roots {
+            let history: Vec<&ScanRecord> = records.iter().filter(|r| paths::key(&r.path) == paths::key(root)).collect();
+            let (Some(latest), Some(first)) = (history.last(), history.first()) else {
+                continue;
+            };
+            let baseline = history
+                .iter()
+                .rev()
+                .find(|r| r.timestamp <= since)
+                .unwrap_or(first);
+            if baseline.timestamp >= latest.timestamp {
+                continue;
+            }
+
+            // A root always grows by as much as its children together, so
+            // name the child unless the scan recorded none
+            let folders: Vec<(&String, u64, Option<u64>)> = if latest.children.is_empty() {
+                vec![(&latest.path, latest.size, Some(baseline.size))]
+            } else {
+                latest
+                    .children
+                    .iter()
+                    .map(|(p, size)| (p, *size, baseline.children.get(p).copied()))
+                    .collect()
+            };
+            for (path, current_size, previous_size) in folders {
+                let previous_size = previous_size.unwrap_or(0);
+                let growth = current_size as i64 - previous_size as i64;
+                if growth > 0 && !best.as_ref().is_some_and(|b| b.growth >= growth) {
+                    best = Some(FolderGrowth {
+                        path: path.clone(),
+                        previous_size,
+                        current_size,
+                        growth,
+                        since: baseline.timestamp,
+                    });
+                }
+            }
+        }
+        best
+    }
+}
@@ -0,0 +1,70 @@
+// Scan Sessions - Scans running side by side, each stopped on its own
+//
+// Every scan_dir call is a session with the ID of its operation. The
+// session is announced to the window that started it with a `scan-started`
+// event, its progress goes only to that window, and cancel_scan with the ID
+// stops that scan alone; without an ID it stops the scans of the calling
+// window. Two windows (or two tabs of one) can thus scan different drives
+// without one's cancel button stopping the other's scan.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Event sent to the starting window when a scan session begins
+pub const SCAN_STARTED_EVENT: &str = "scan-started";
+
+/// Payload of SCAN_STARTED_EVENT
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanStarted {
+    pub session_id: String,
+    pub path: String,
+}
+
+struct Session {
+    /// Label of the webview that started it
+    window: String,
+    cancel: Arc<AtomicBool>,
+}
+
+/// Running scan sessions, kept in managed state
+#[derive(Default)]
+pub struct ScanSessions {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl ScanSessions {
+    pub fn begin(&self, id: &str, window: &str, cancel: Arc<AtomicBool>) {
+        self.sessions.lock().unwrap().insert(
+            id.to_string(),
+            Session {
+                window: window.to_string(),
+                cancel,
+            },
+        );
+    }
+
+    pub fn end(&self, id: &str) {
+        self.sessions.lock().unwrap().remove(id);
+    }
+
+    /// Stop one session. Returns false if it is not running.
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.sessions.lock().unwrap().get(id) {
+            Some(session) => {
+                session.cancel.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop every session started from `window`, returning how many
+    pub fn cancel_window(&self, window: &str) -> usize {
+        let sessions = self.sessions.lock().unwrap();
+        let started = sessions.values().filter(|s| s.window == window);
+        started.map(|s| s.cancel.store(true, Ordering::Relaxed)).count()
+    }
+}
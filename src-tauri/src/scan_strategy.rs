@@ -0,0 +1,236 @@
+// Scan Strategy - Walker tuning per storage type
+//
+// One rayon configuration does not fit every volume. NVMe drives keep getting
+// faster with more outstanding requests, while a spinning disk thrashes its
+// heads when many threads read different folders at once, and a network share
+// is bound by round trips rather than by the disk. The scan root's volume is
+// classified (NVMe, SATA SSD, HDD, network, USB) and a strategy is picked for
+// it: walker threads, how many entries are handled between progress updates
+// and cancellation checks, and whether directory entries are stat'ed in inode
+// order, which keeps an HDD reading mostly sequentially. Settings can override
+// the detected type or any part of the strategy per mount point.
+
+use crate::paths;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use sysinfo::{DiskKind, Disks};
+
+/// File systems that live on another machine
+const NETWORK_FILE_SYSTEMS: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smbfs", "smb2", "smb3", "afpfs", "fuse.sshfs", "sshfs", "9p", "webdav", "davfs",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StorageKind {
+    Nvme,
+    SataSsd,
+    Hdd,
+    Network,
+    Usb,
+    Unknown,
+}
+
+/// Order in which the entries of a directory are stat'ed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StatOrder {
+    /// As the directory lists them
+    Listing,
+    /// By inode number, close to on-disk order on most Unix file systems
+    Inode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanStrategy {
+    /// Walker threads
+    pub threads: usize,
+    /// Entries handled between progress updates and cancellation checks
+    pub batch_size: usize,
+    pub stat_order: StatOrder,
+}
+
+impl ScanStrategy {
+    /// Defaults for a storage type on a machine with `cores` CPU cores
+    pub fn for_kind(kind: StorageKind, cores: usize) -> Self {
+        let (threads, batch_size, stat_order) = match kind {
+            StorageKind::Nvme => ((cores * 2).max(8), 256, StatOrder::Listing),
+            StorageKind::SataSsd | StorageKind::Unknown => (cores.max(2), 128, StatOrder::Listing),
+            // More threads only add seeks
+            StorageKind::Hdd => (2, 64, StatOrder::Inode),
+            StorageKind::Usb => (2, 64, StatOrder::Listing),
+            // Round trips dominate; overlap them, but don't flood the server
+            StorageKind::Network => (8, 512, StatOrder::Listing),
+        };
+        Self {
+            threads,
+            batch_size,
+            stat_order,
+        }
+    }
+}
+
+impl Default for ScanStrategy {
+    fn default() -> Self {
+        Self::for_kind(StorageKind::Unknown, cores())
+    }
+}
+
+/// Settings override for one mount point; unset fields keep the detected value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeOverride {
+    pub mount_point: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<StorageKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threads: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stat_order: Option<StatOrder>,
+}
+
+/// Strategy chosen for a scan root, and why
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeStrategy {
+    pub mount_point: String,
+    pub detected_kind: StorageKind,
+    pub strategy: ScanStrategy,
+    /// A settings override changed the detected strategy
+    pub overridden: bool,
+}
+
+fn cores() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
+}
+
+/// Classify a disk reported by the OS
+fn classify(name: &str, file_system: &str, kind: DiskKind, removable: bool) -> StorageKind {
+    if NETWORK_FILE_SYSTEMS.contains(&file_system.to_lowercase().as_str()) {
+        return StorageKind::Network;
+    }
+    if removable {
+        return StorageKind::Usb;
+    }
+    match kind {
+        DiskKind::SSD if name.contains("nvme") => StorageKind::Nvme,
+        DiskKind::SSD => StorageKind::SataSsd,
+        DiskKind::HDD => StorageKind::Hdd,
+        DiskKind::Unknown(_) => StorageKind::Unknown,
+    }
+}
+
+/// Mount point and storage type of the volume holding `path`
+pub fn detect(path: &Path) -> (String, StorageKind) {
+    // \\server\share is a network path whether or not it's mapped to a drive
+    let display = paths::display(path);
+    if display.starts_with(r"\\") && !display.starts_with(r"\\?\") {
+        return (display, StorageKind::Network);
+    }
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .iter()
+        .filter(|d| paths::is_within(path, d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())
+        .map(|d| {
+            let kind = classify(
+                &d.name().to_string_lossy().to_lowercase(),
+                &d.file_system().to_string_lossy(),
+                d.kind(),
+                d.is_removable(),
+            );
+            (d.mount_point().to_string_lossy().to_string(), kind)
+        })
+        .unwrap_or_else(|| (String::new(), StorageKind::Unknown))
+}
+
+/// Mount points of the network shares mounted right now
+pub fn network_mounts() -> Vec<PathBuf> {
+    Disks::new_with_refreshed_list()
+        .iter()
+        .filter(|d| NETWORK_FILE_SYSTEMS.contains(&d.file_system().to_string_lossy().to_lowercase().as_str()))
+        .map(|d| d.mount_point().to_path_buf())
+        .collect()
+}
+
+/// Per-volume overrides persisted in the app data directory
+pub struct ScanStrategyStore {
+    path: PathBuf,
+    overrides: Mutex<Vec<VolumeOverride>>,
+}
+
+impl ScanStrategyStore {
+    pub fn load(path: PathBuf) -> Self {
+        let overrides = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        Self {
+            path,
+            overrides: Mutex::new(overrides),
+        }
+    }
+
+    pub fn overrides(&self) -> Vec<VolumeOverride> {
+        self.overrides.lock().unwrap().clone()
+    }
+
+    /// Add an override, or replace the one for the same mount point
+    pub fn save_override(&self, entry: VolumeOverride) -> Result<(), String> {
+        if entry.mount_point.trim().is_empty() {
+            return Err("Mount point cannot be empty".to_string());
+        }
+        if entry.threads == Some(0) || entry.batch_size == Some(0) {
+            return Err("Threads and batch size must be at least 1".to_string());
+        }
+        let mut overrides = self.overrides.lock().unwrap();
+        match overrides.iter_mut().find(|o| o.mount_point == entry.mount_point) {
+            Some(existing) => *existing = entry,
+            None => overrides.push(entry),
+        }
+        storage::save_json(&self.path, &*overrides)
+    }
+
+    pub fn delete_override(&self, mount_point: &str) -> Result<bool, String> {
+        let mut overrides = self.overrides.lock().unwrap();
+        let before = overrides.len();
+        overrides.retain(|o| o.mount_point != mount_point);
+        if overrides.len() == before {
+            return Ok(false);
+        }
+        storage::save_json(&self.path, &*overrides)?;
+        Ok(true)
+    }
+
+    /// Strategy for scanning `path`: detected, then overridden by settings
+    pub fn resolve(&self, path: &Path) -> VolumeStrategy {
+        let (mount_point, detected_kind) = detect(path);
+        let entry = self
+            .overrides
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|o| o.mount_point == mount_point)
+            .cloned();
+        let Some(entry) = entry else {
+            return VolumeStrategy {
+                strategy: ScanStrategy::for_kind(detected_kind, cores()),
+                mount_point,
+                detected_kind,
+                overridden: false,
+            };
+        };
+        let mut strategy = ScanStrategy::for_kind(entry.kind.unwrap_or(detected_kind), cores());
+        strategy.threads = entry.threads.unwrap_or(strategy.threads);
+        strategy.batch_size = entry.batch_size.unwrap_or(strategy.batch_size);
+        strategy.stat_order = entry.stat_order.unwrap_or(strategy.stat_order);
+        VolumeStrategy {
+            mount_point,
+            detected_kind,
+            strategy,
+            overridden: true,
+        }
+    }
+}
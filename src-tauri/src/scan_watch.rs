@@ -0,0 +1,232 @@
+// Scan Watch - Incremental rescans of watched roots
+//
+// Refreshing a large drive used to re-read the entire tree even when only a
+// few folders changed. Once a path has been scanned it is watched
+// recursively, and every change reported by the watcher marks its folder and
+// that folder's ancestors dirty. The next scan of the path takes every
+// folder of the previous tree that isn't dirty as it was, and walks only the
+// dirty ones (plus the listing of the root, which is cheap), so a refresh
+// costs as much as what changed. The watcher can drop events (the OS queue
+// overflows, too many changes pile up); the root is then rescanned in full.
+// Changes seen here also count as churn for the cache warmer.
+//
+// At most MAX_ROOTS roots are watched, the least recently scanned one is
+// dropped first. A root inside another watched root shares its watch.
+
+use crate::paths;
+use crate::scanner::{FileNode, Reuse};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{debug, warn};
+
+/// Scanned roots watched for changes
+const MAX_ROOTS: usize = 8;
+/// Changed paths kept per root before it is rescanned in full instead
+const MAX_DIRTY: usize = 50_000;
+
+struct WatchedRoot {
+    path: PathBuf,
+    /// Whether the watch was added for this root, rather than covered by an
+    /// enclosing root's
+    watching: bool,
+    /// Paths changed since the last scan began
+    dirty: HashSet<PathBuf>,
+    /// Changes were lost; the next scan walks everything
+    overflowed: bool,
+    /// Result of the last scan
+    tree: Option<FileNode>,
+    last_scanned: Instant,
+}
+
+#[derive(Default)]
+struct Inner {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    /// By normalized path
+    roots: Mutex<HashMap<String, WatchedRoot>>,
+}
+
+impl Inner {
+    fn record(&self, event: notify::Event) {
+        let mut roots = self.roots.lock().unwrap();
+        if event.need_rescan() {
+            for root in roots.values_mut() {
+                root.overflowed = true;
+                root.dirty.clear();
+            }
+            return;
+        }
+        for path in &event.paths {
+            crate::cache_warmer::observe(path);
+            for root in roots.values_mut().filter(|r| paths::is_within(path, &r.path)) {
+                if root.overflowed {
+                    continue;
+                }
+                root.dirty.insert(path.clone());
+                if root.dirty.len() > MAX_DIRTY {
+                    debug!("Too many changes below {}; rescanning it in full", root.path.display());
+                    root.overflowed = true;
+                    root.dirty.clear();
+                }
+            }
+        }
+    }
+
+    fn watch(&self, path: &Path) -> bool {
+        let mut watcher = self.watcher.lock().unwrap();
+        let Some(watcher) = watcher.as_mut() else {
+            return false;
+        };
+        match watcher.watch(path, RecursiveMode::Recursive) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Cannot watch {} for incremental rescans: {}", path.display(), e);
+                false
+            }
+        }
+    }
+
+    fn unwatch(&self, path: &Path) {
+        if let Some(watcher) = self.watcher.lock().unwrap().as_mut() {
+            let _ = watcher.unwatch(path);
+        }
+    }
+}
+
+/// Folders of the previous tree that haven't changed since it was scanned
+pub struct Unchanged {
+    folders: HashMap<String, FileNode>,
+    /// Changed paths and all their ancestors
+    dirty: HashSet<String>,
+}
+
+impl Unchanged {
+    fn new(tree: &FileNode, dirty: &HashSet<PathBuf>, root: &Path) -> Self {
+        let mut folders = HashMap::new();
+        for child in tree.children.iter().flatten().filter(|c| c.is_dir) {
+            for grandchild in child.children.iter().flatten().filter(|c| c.is_dir) {
+                folders.insert(paths::key(&grandchild.path), grandchild.clone());
+            }
+            folders.insert(paths::key(&child.path), child.clone());
+        }
+        let mut dirty_keys = HashSet::new();
+        for path in dirty {
+            for ancestor in path.ancestors() {
+                if !dirty_keys.insert(paths::key(&paths::display(ancestor))) || ancestor == root {
+                    break;
+                }
+            }
+        }
+        Self {
+            folders,
+            dirty: dirty_keys,
+        }
+    }
+}
+
+impl Reuse for Unchanged {
+    fn reuse(&self, path: &Path) -> Option<FileNode> {
+        let key = paths::key(&paths::display(path));
+        if self.dirty.contains(&key) {
+            return None;
+        }
+        self.folders.get(&key).cloned()
+    }
+}
+
+/// Watched roots, held in managed state
+#[derive(Clone, Default)]
+pub struct ScanWatcher {
+    inner: Arc<Inner>,
+}
+
+impl ScanWatcher {
+    pub fn new() -> Self {
+        let watcher = Self::default();
+        let events = Arc::downgrade(&watcher.inner);
+        let created = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Some(inner) = events.upgrade() else {
+                return;
+            };
+            match event {
+                Ok(event) => inner.record(event),
+                Err(e) => warn!("Scan watcher error: {}", e),
+            }
+        });
+        match created {
+            Ok(created) => *watcher.inner.watcher.lock().unwrap() = Some(created),
+            Err(e) => warn!("Incremental rescans unavailable: {}", e),
+        }
+        watcher
+    }
+
+    /// Called as a scan of `path` starts. Returns the folders the scan can
+    /// reuse, if the path was scanned before and watched since; starts
+    /// watching it otherwise. Changes from here on count toward the next scan.
+    pub fn begin(&self, path: &str) -> Option<Unchanged> {
+        let key = paths::key(path);
+        let mut roots = self.inner.roots.lock().unwrap();
+        if let Some(root) = roots.get_mut(&key) {
+            let unchanged = match (&root.tree, root.overflowed) {
+                (Some(tree), false) => Some(Unchanged::new(tree, &root.dirty, &root.path)),
+                _ => None,
+            };
+            root.dirty.clear();
+            root.overflowed = false;
+            root.last_scanned = Instant::now();
+            if let Some(unchanged) = &unchanged {
+                debug!("Incremental rescan of {}: {} changed folders", path, unchanged.dirty.len());
+            }
+            return unchanged;
+        }
+
+        if roots.len() >= MAX_ROOTS {
+            let oldest = roots.iter().min_by_key(|(_, r)| r.last_scanned).map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest.and_then(|k| roots.remove(&k)) {
+                if oldest.watching {
+                    self.inner.unwatch(&oldest.path);
+                    // Roots inside it relied on its watch
+                    roots.retain(|_, r| r.watching || !paths::is_within(&r.path, &oldest.path));
+                }
+            }
+        }
+
+        let root_path = PathBuf::from(path);
+        let covered = roots.values().any(|r| r.watching && paths::is_within(&root_path, &r.path));
+        let watching = !covered && self.inner.watch(&root_path);
+        if !covered && !watching {
+            return None;
+        }
+        if watching {
+            // Roots inside this one share its watch from now on
+            for inner_root in roots.values_mut().filter(|r| r.watching && paths::is_within(&r.path, &root_path)) {
+                self.inner.unwatch(&inner_root.path);
+                inner_root.watching = false;
+            }
+        }
+        roots.insert(
+            key,
+            WatchedRoot {
+                path: root_path,
+                watching,
+                dirty: HashSet::new(),
+                overflowed: false,
+                tree: None,
+                last_scanned: Instant::now(),
+            },
+        );
+        None
+    }
+
+    /// Called when a scan of `path` ends, with its tree if it succeeded. A
+    /// failed or cancelled scan leaves nothing to build on, so the next one
+    /// walks everything.
+    pub fn finish(&self, path: &str, tree: Option<&FileNode>) {
+        let mut roots = self.inner.roots.lock().unwrap();
+        if let Some(root) = roots.get_mut(&paths::key(path)) {
+            root.tree = tree.cloned();
+        }
+    }
+}
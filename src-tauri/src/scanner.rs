@@ -2,8 +2,23 @@
 use serde::{Deserialize, Serialize};
 use std::time::SystemTime;
 use rayon::prelude::*;
+use crate::paths;
+pub use crate::exclusions::Exclusions;
+pub use crate::scan_strategy::ScanStrategy;
+use crate::scan_strategy::StatOrder;
+use crate::volume_space::VolumeSpace;
+use crate::breakdown::SizeGroup;
+use crate::content_type;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, atomic::{AtomicBool, AtomicU64, Ordering}};
 
+/// Extensions listed by a type breakdown; the rest only count in categories
+const TOP_TYPE_EXTENSIONS: usize = 50;
+
+/// Bytes and files by lowercase extension ("" for files without one)
+pub type ExtensionTotals = HashMap<String, (u64, u64)>;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FileNode {
     pub name: String,
@@ -13,20 +28,647 @@ pub struct FileNode {
     pub children: Option<Vec<FileNode>>,
     pub last_modified: u64,
     pub file_count: u64,
+    /// Space details of a drive, as listed by get_drives
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume: Option<VolumeSpace>,
+    /// Set when the entry is a symbolic link (or a junction on Windows)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<LinkInfo>,
+    /// Links below a folder that were not measured: left out under
+    /// SymlinkPolicy::Skip, or broken and cyclic links under Follow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped_links: Option<u64>,
+    /// Space taken on disk, as the OS's own disk usage views count it:
+    /// less than `size` for sparse and compressed files, more for many
+    /// small ones. None where unknown, e.g. remote and cloud listings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allocated_size: Option<u64>,
+    /// Bytes and files by extension below a folder, for get_type_breakdown.
+    /// Not sent with the tree; None where the scan couldn't tell (remote
+    /// listings, folders taken from a checkpoint).
+    #[serde(skip)]
+    pub extensions: Option<Arc<ExtensionTotals>>,
+    /// A folder scan_dir_shallow listed without measuring: `size`,
+    /// `file_count` and `allocated_size` only count what was listed, and
+    /// get_deep_stats tells the rest
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deferred: bool,
+}
+
+/// What a folder is made of, by file category and by extension
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeBreakdown {
+    pub path: String,
+    pub total_size: u64,
+    pub file_count: u64,
+    /// Video, images, archives, code, documents, ...; largest first
+    pub categories: Vec<SizeGroup>,
+    /// Largest first, top entries only
+    pub extensions: Vec<SizeGroup>,
+}
+
+impl TypeBreakdown {
+    pub(crate) fn new(path: String, extensions: &ExtensionTotals) -> Self {
+        let mut categories: HashMap<&str, (u64, u64)> = HashMap::new();
+        for (extension, (size, files)) in extensions {
+            let category = categories.entry(content_type::category_of_extension(extension)).or_default();
+            category.0 += size;
+            category.1 += files;
+        }
+        let sorted = |groups: Vec<SizeGroup>| {
+            let mut groups = groups;
+            groups.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+            groups
+        };
+        let mut by_extension = sorted(
+            extensions
+                .iter()
+                .map(|(name, &(size, files))| SizeGroup { name: name.clone(), size, files })
+                .collect(),
+        );
+        by_extension.truncate(TOP_TYPE_EXTENSIONS);
+        Self {
+            path,
+            total_size: extensions.values().map(|(size, _)| size).sum(),
+            file_count: extensions.values().map(|(_, files)| files).sum(),
+            categories: sorted(
+                categories
+                    .into_iter()
+                    .map(|(name, (size, files))| SizeGroup { name: name.to_string(), size, files })
+                    .collect(),
+            ),
+            extensions: by_extension,
+        }
+    }
+
+    /// Breakdown of a scanned folder, if the scan recorded one
+    pub fn of_node(node: &FileNode) -> Option<Self> {
+        node.extensions.as_ref().map(|extensions| Self::new(node.path.clone(), extensions))
+    }
+}
+
+/// What a scan does with symbolic links
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SymlinkPolicy {
+    /// Leave links out
+    Skip,
+    /// Count the link itself, not what it points to
+    #[default]
+    CountLinkSize,
+    /// Count what the link points to, unless following it would loop
+    Follow,
+}
+
+/// How a link in the tree was measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkHandling {
+    /// By the size of the link itself
+    Counted,
+    /// By what it points to
+    Followed,
+    /// Not at all: it points to a folder containing it
+    Cycle,
+    /// Not at all: what it points to doesn't exist
+    Broken,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LinkInfo {
+    /// Where the link points, as stored in it
+    pub target: String,
+    pub handling: LinkHandling,
+}
+
+/// The symlink policy of a scan, and the folders it entered through links on
+/// the way to the current one
+#[derive(Clone)]
+struct LinkWalk {
+    policy: SymlinkPolicy,
+    /// Canonical paths of the scan root and of every followed link's target
+    chain: Vec<PathBuf>,
+    /// Device of the scan root, when folders on other devices are skipped
+    device: Option<u64>,
+}
+
+/// A directory entry as the symlink policy has it measured
+struct Measured {
+    /// The entry's own metadata, or its target's for a followed link
+    meta: std::fs::Metadata,
+    link: Option<LinkInfo>,
+    /// Set when a link to a folder was followed, for walking below it
+    followed: Option<LinkWalk>,
+}
+
+impl LinkWalk {
+    fn new(policy: SymlinkPolicy, root: &Path, same_device: bool) -> Self {
+        let chain = match policy {
+            SymlinkPolicy::Follow => std::fs::canonicalize(root).into_iter().collect(),
+            _ => Vec::new(),
+        };
+        let device = same_device
+            .then(|| std::fs::metadata(root).ok().and_then(|meta| device_of(root, &meta)))
+            .flatten();
+        Self { policy, chain, device }
+    }
+
+    /// Whether the folder at `path`, measured as `meta`, is a mount point
+    /// (or a followed link) leading off the scan root's device
+    fn leaves_device(&self, path: &Path, meta: &std::fs::Metadata) -> bool {
+        meta.is_dir() && self.device.is_some_and(|root| device_of(path, meta).is_some_and(|dev| dev != root))
+    }
+
+    /// Measure the entry at `path` whose own (not followed) metadata is
+    /// `meta`. A link that isn't measured is an error, saying why unless the
+    /// policy skips links.
+    fn measure(&self, path: &Path, meta: std::fs::Metadata) -> Result<Measured, Option<LinkInfo>> {
+        if !meta.file_type().is_symlink() {
+            return Ok(Measured { meta, link: None, followed: None });
+        }
+        let target = std::fs::read_link(path).map(|t| paths::display(&t)).unwrap_or_default();
+        let info = |handling| Some(LinkInfo { target: target.clone(), handling });
+        match self.policy {
+            SymlinkPolicy::Skip => Err(None),
+            SymlinkPolicy::CountLinkSize => Ok(Measured { meta, link: info(LinkHandling::Counted), followed: None }),
+            SymlinkPolicy::Follow => {
+                let (Ok(real), Ok(target_meta)) = (std::fs::canonicalize(path), std::fs::metadata(path)) else {
+                    return Err(info(LinkHandling::Broken));
+                };
+                if !target_meta.is_dir() {
+                    return Ok(Measured { meta: target_meta, link: info(LinkHandling::Followed), followed: None });
+                }
+                // A folder holding the link, or one entered on the way here,
+                // would be walked forever
+                let parent = path.parent().and_then(|p| std::fs::canonicalize(p).ok());
+                if parent.iter().chain(&self.chain).any(|entered| paths::is_within(entered, &real)) {
+                    return Err(info(LinkHandling::Cycle));
+                }
+                let mut followed = self.clone();
+                followed.chain.push(real);
+                Ok(Measured { meta: target_meta, link: info(LinkHandling::Followed), followed: Some(followed) })
+            }
+        }
+    }
+}
+
+/// Device the entry at `path` is on: st_dev
+#[cfg(unix)]
+fn device_of(_path: &Path, meta: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.dev())
+}
+
+/// Device the entry at `path` is on: the serial number of its volume
+#[cfg(windows)]
+fn device_of(path: &Path, _meta: &std::fs::Metadata) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetVolumePathNameW(file_name: *const u16, volume_path: *mut u16, length: u32) -> i32;
+        fn GetVolumeInformationW(
+            root: *const u16,
+            name: *mut u16,
+            name_size: u32,
+            serial: *mut u32,
+            max_component_length: *mut u32,
+            flags: *mut u32,
+            file_system_name: *mut u16,
+            file_system_name_size: u32,
+        ) -> i32;
+    }
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut volume = vec![0u16; wide.len().max(261)];
+    let mut serial = 0u32;
+    // SAFETY: `wide` is NUL-terminated, and `volume` is at least as long as
+    // the volume path written to it, a prefix of `path`
+    let found = unsafe {
+        GetVolumePathNameW(wide.as_ptr(), volume.as_mut_ptr(), volume.len() as u32) != 0
+            && GetVolumeInformationW(
+                volume.as_ptr(),
+                std::ptr::null_mut(),
+                0,
+                &mut serial,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                0,
+            ) != 0
+    };
+    found.then_some(u64::from(serial))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn device_of(_path: &Path, _meta: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+fn modified_secs(meta: &std::fs::Metadata) -> u64 {
+    meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)
+        .duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+fn skipped(count: u64) -> Option<u64> {
+    (count > 0).then_some(count)
+}
+
+/// Space the file at `path` takes on disk, from its block count
+#[cfg(unix)]
+pub fn allocated_size(_path: &Path, meta: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    // st_blocks is in 512-byte units whatever the file system's block size
+    meta.blocks() * 512
+}
+
+/// Space the file at `path` takes on disk, after NTFS compression and
+/// without the unallocated ranges of sparse files
+#[cfg(windows)]
+pub fn allocated_size(path: &Path, meta: &std::fs::Metadata) -> u64 {
+    use std::os::windows::ffi::OsStrExt;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCompressedFileSizeW(file_name: *const u16, file_size_high: *mut u32) -> u32;
+    }
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut high = 0u32;
+    // SAFETY: `wide` is NUL-terminated and outlives the call
+    let low = unsafe { GetCompressedFileSizeW(wide.as_ptr(), &mut high) };
+    // INVALID_FILE_SIZE is also a valid low word; only an error code tells
+    if low == u32::MAX && std::io::Error::last_os_error().raw_os_error().unwrap_or(0) != 0 {
+        return meta.len();
+    }
+    (u64::from(high) << 32) | u64::from(low)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn allocated_size(_path: &Path, meta: &std::fs::Metadata) -> u64 {
+    meta.len()
+}
+
+/// A node's space on disk, for nodes measured before it was recorded
+fn allocated_of(node: &FileNode) -> u64 {
+    node.allocated_size.unwrap_or(node.size)
+}
+
+/// What a walk below a folder measured
+#[derive(Debug, Clone)]
+struct Totals {
+    size: u64,
+    allocated: u64,
+    count: u64,
+    skipped_links: u64,
+    /// None once a folder without them was counted in
+    extensions: Option<ExtensionTotals>,
+}
+
+impl Default for Totals {
+    fn default() -> Self {
+        Self { size: 0, allocated: 0, count: 0, skipped_links: 0, extensions: Some(HashMap::new()) }
+    }
+}
+
+impl Totals {
+    fn add_file(&mut self, path: &Path, meta: &std::fs::Metadata) {
+        self.size += meta.len();
+        self.allocated += allocated_size(path, meta);
+        self.count += 1;
+        if let Some(extensions) = &mut self.extensions {
+            let group = extensions.entry(content_type::lowercase_extension(path)).or_default();
+            group.0 += meta.len();
+            group.1 += 1;
+        }
+    }
+
+    fn add_extensions(&mut self, other: Option<&ExtensionTotals>) {
+        match (&mut self.extensions, other) {
+            (Some(extensions), Some(other)) => {
+                for (extension, (size, files)) in other {
+                    let group = extensions.entry(extension.clone()).or_default();
+                    group.0 += size;
+                    group.1 += files;
+                }
+            }
+            _ => self.extensions = None,
+        }
+    }
+
+    fn add_node(&mut self, node: &FileNode) {
+        self.size += node.size;
+        self.allocated += allocated_of(node);
+        self.count += node.file_count;
+        self.skipped_links += node.skipped_links.unwrap_or(0);
+        self.add_extensions(node.extensions.as_deref());
+    }
+
+    fn add(&mut self, other: Totals) {
+        self.size += other.size;
+        self.allocated += other.allocated;
+        self.count += other.count;
+        self.skipped_links += other.skipped_links;
+        self.add_extensions(other.extensions.as_ref());
+    }
+}
+
+/// Earlier work a scan may take folders from instead of walking them again:
+/// a checkpoint of an interrupted scan, or the previous tree of a watched root
+pub trait Reuse: Sync {
+    /// A folder measured earlier that is still accurate
+    fn reuse(&self, path: &Path) -> Option<FileNode>;
+    /// Called for every folder the scan measured itself
+    fn finished(&self, _node: &FileNode) {}
+}
+
+fn reused(reuse: &[&dyn Reuse], path: &Path) -> Option<FileNode> {
+    reuse.iter().find_map(|r| r.reuse(path))
+}
+
+fn finished(reuse: &[&dyn Reuse], node: &FileNode) {
+    for r in reuse {
+        r.finished(node);
+    }
 }
 
+/// Receives folders of a running scan (see ScanStats::on_child)
+pub type NodeCallback = Box<dyn Fn(&FileNode) + Send + Sync>;
+
 pub struct ScanStats {
     pub scanned_files: AtomicU64,
     pub total_size: AtomicU64,
     pub errors: AtomicU64,
+    /// Called with each folder directly below the root as soon as it is
+    /// measured, so a long scan can be shown as it goes
+    pub on_child: Option<NodeCallback>,
+}
+
+/// Put directory entries in the order they should be stat'ed
+fn order_entries(entries: &mut [std::fs::DirEntry], order: StatOrder) {
+    #[cfg(unix)]
+    if order == StatOrder::Inode {
+        use std::os::unix::fs::DirEntryExt;
+        entries.sort_by_key(|e| e.ino());
+    }
+    #[cfg(not(unix))]
+    let _ = (entries, order);
 }
 
+/// Scan `path` with the walker threads, batching and stat order of `strategy`,
+/// handling symbolic links as `symlinks` says. Folders any of `reuse` still
+/// holds are taken from it rather than walked again, and every folder
+/// measured is reported back to it. With `same_device` (by default, when
+/// `exclusions` stay on the device) folders on another file system than
+/// `path` (mounted drives and shares) are skipped. The root of an NTFS drive
+/// is read from its master file table instead where the process may (see
+/// ntfs_mft).
+#[allow(clippy::too_many_arguments)]
 pub fn scan_directory(
     path: &str,
     stats: Option<Arc<ScanStats>>,
-    cancel: Option<Arc<AtomicBool>>
+    cancel: Option<Arc<AtomicBool>>,
+    exclusions: &Exclusions,
+    strategy: &ScanStrategy,
+    symlinks: SymlinkPolicy,
+    same_device: Option<bool>,
+    reuse: &[&dyn Reuse],
+) -> Result<FileNode, String> {
+    #[cfg(windows)]
+    if let Some(result) = crate::ntfs_mft::scan(path, stats.as_ref(), cancel.as_deref(), exclusions, symlinks) {
+        return result;
+    }
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(strategy.threads)
+        .thread_name(|i| format!("scan-{}", i))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let same_device = same_device.unwrap_or(exclusions.same_device());
+    let links = LinkWalk::new(symlinks, &paths::extended(Path::new(path)), same_device);
+    pool.install(|| scan_root(path, stats, cancel, exclusions, strategy, &links, reuse))
+}
+
+/// A synthetic root named `name` holding separately scanned `roots`,
+/// largest first; its path is empty, like the drive list's
+pub fn merge_roots(name: String, roots: Vec<FileNode>) -> FileNode {
+    let mut totals = Totals { extensions: None, ..Totals::default() };
+    let mut children = roots;
+    for root in &mut children {
+        // A drive's root has no file name of its own
+        if root.name.is_empty() {
+            root.name = root.path.clone();
+        }
+        totals.add_node(root);
+    }
+    children.sort_by(|a, b| b.size.cmp(&a.size));
+    FileNode {
+        name,
+        path: String::new(),
+        size: totals.size,
+        is_dir: true,
+        last_modified: children.iter().map(|c| c.last_modified).max().unwrap_or(0),
+        file_count: totals.count,
+        volume: None,
+        link: None,
+        skipped_links: skipped(totals.skipped_links),
+        allocated_size: Some(totals.allocated),
+        extensions: None,
+        deferred: children.iter().any(|c| c.deferred),
+        children: Some(children),
+    }
+}
+
+/// Type breakdown of `path` by walking it, for folders the last scan
+/// didn't record one for
+pub fn type_breakdown(
+    path: &str,
+    cancel: Option<Arc<AtomicBool>>,
+    exclusions: &Exclusions,
+    symlinks: SymlinkPolicy,
+) -> Result<TypeBreakdown, String> {
+    let root = paths::extended(Path::new(path));
+    if !root.is_dir() {
+        return Err("Directory does not exist".to_string());
+    }
+    let links = LinkWalk::new(symlinks, &root, exclusions.same_device());
+    let totals = get_deep_stats(&root, None, cancel, exclusions, ScanStrategy::default().batch_size, &links)?;
+    Ok(TypeBreakdown::new(paths::display(&root), &totals.extensions.unwrap_or_default()))
+}
+
+/// Deepest listing scan_shallow gives
+pub const MAX_SHALLOW_DEPTH: usize = 8;
+
+/// List `path` `depth` levels deep (1 lists its own entries) without
+/// measuring the folders below that: those come back `deferred`, for
+/// deep_stats to measure once they are shown
+pub fn scan_shallow(
+    path: &str,
+    depth: usize,
+    cancel: &AtomicBool,
+    exclusions: &Exclusions,
+    symlinks: SymlinkPolicy,
 ) -> Result<FileNode, String> {
-    let root_path = std::path::Path::new(path);
+    let root = paths::extended(Path::new(path));
+    let meta = std::fs::metadata(&root).map_err(|_| "Directory does not exist".to_string())?;
+    // Unlike folders below it, an unreadable root is an error
+    std::fs::read_dir(&root).map_err(|e| e.to_string())?;
+    let links = LinkWalk::new(symlinks, &root, exclusions.same_device());
+    let (totals, children, deferred) = list_shallow(&root, depth.clamp(1, MAX_SHALLOW_DEPTH), cancel, exclusions, &links)?;
+    Ok(FileNode {
+        name: root.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        path: path.to_string(),
+        size: totals.size,
+        is_dir: true,
+        children: Some(children),
+        last_modified: modified_secs(&meta),
+        file_count: totals.count,
+        volume: None,
+        link: None,
+        skipped_links: skipped(totals.skipped_links),
+        allocated_size: Some(totals.allocated),
+        extensions: None,
+        deferred,
+    })
+}
+
+/// Entries of `dir` down to `depth` levels, largest first, their totals, and
+/// whether any folder among them was left unmeasured
+fn list_shallow(
+    dir: &Path,
+    depth: usize,
+    cancel: &AtomicBool,
+    exclusions: &Exclusions,
+    links: &LinkWalk,
+) -> Result<(Totals, Vec<FileNode>, bool), String> {
+    let mut totals = Totals { extensions: None, ..Totals::default() };
+    let mut children = Vec::new();
+    let mut deferred = false;
+    let Ok(read_dir) = std::fs::read_dir(dir) else { return Ok((totals, children, deferred)) };
+    for entry in read_dir.filter_map(|e| e.ok()).filter(|e| !exclusions.is_excluded(&e.path())) {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".to_string());
+        }
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else { continue };
+        let (meta, link, followed) = match links.measure(&path, meta) {
+            Ok(Measured { meta, .. }) if links.leaves_device(&path, &meta) => continue,
+            Ok(Measured { meta, link, followed }) => (meta, link, followed),
+            Err(link) => {
+                totals.skipped_links += 1;
+                children.extend(link.map(|link| unmeasured_node(&entry, link)));
+                continue;
+            }
+        };
+        let mut node = FileNode {
+            name: entry.file_name().to_string_lossy().to_string(),
+            path: paths::display(&path),
+            size: meta.len(),
+            is_dir: meta.is_dir(),
+            children: None,
+            last_modified: modified_secs(&meta),
+            file_count: 1,
+            volume: None,
+            link,
+            skipped_links: None,
+            allocated_size: Some(allocated_size(&path, &meta)),
+            extensions: None,
+            deferred: false,
+        };
+        if !node.is_dir {
+            totals.add_file(&path, &meta);
+            children.push(node);
+            continue;
+        }
+        if depth > 1 {
+            let links = followed.as_ref().unwrap_or(links);
+            let (below, listed, below_deferred) = list_shallow(&path, depth - 1, cancel, exclusions, links)?;
+            node.size = below.size;
+            node.file_count = below.count;
+            node.allocated_size = Some(below.allocated);
+            node.skipped_links = skipped(below.skipped_links);
+            node.children = Some(listed);
+            node.deferred = below_deferred;
+        } else {
+            node.size = 0;
+            node.file_count = 0;
+            node.allocated_size = Some(0);
+            node.deferred = true;
+        }
+        deferred |= node.deferred;
+        totals.add_node(&node);
+        children.push(node);
+    }
+    children.sort_by(|a, b| b.size.cmp(&a.size));
+    Ok((totals, children, deferred))
+}
+
+/// Size, space on disk and file count of everything below `path`, for a
+/// folder scan_shallow deferred. Children are not listed.
+pub fn deep_stats(
+    path: &str,
+    cancel: Option<Arc<AtomicBool>>,
+    exclusions: &Exclusions,
+    symlinks: SymlinkPolicy,
+) -> Result<FileNode, String> {
+    let root = paths::extended(Path::new(path));
+    let meta = std::fs::metadata(&root).map_err(|_| "Directory does not exist".to_string())?;
+    if !meta.is_dir() {
+        return Err("Directory does not exist".to_string());
+    }
+    let links = LinkWalk::new(symlinks, &root, exclusions.same_device());
+    let totals = get_deep_stats(&root, None, cancel, exclusions, ScanStrategy::default().batch_size, &links)?;
+    Ok(FileNode {
+        name: root.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        path: path.to_string(),
+        size: totals.size,
+        is_dir: true,
+        children: None,
+        last_modified: modified_secs(&meta),
+        file_count: totals.count,
+        volume: None,
+        link: None,
+        skipped_links: skipped(totals.skipped_links),
+        allocated_size: Some(totals.allocated),
+        extensions: totals.extensions.map(Arc::new),
+        deferred: false,
+    })
+}
+
+/// A link that wasn't measured, kept in the tree so it is still visible
+fn unmeasured_node(entry: &std::fs::DirEntry, link: LinkInfo) -> FileNode {
+    FileNode {
+        name: entry.file_name().to_string_lossy().to_string(),
+        path: paths::display(&entry.path()),
+        size: 0,
+        is_dir: false,
+        children: None,
+        last_modified: 0,
+        file_count: 0,
+        volume: None,
+        link: Some(link),
+        skipped_links: None,
+        allocated_size: Some(0),
+        extensions: None,
+        deferred: false,
+    }
+}
+
+/// Count a reused folder in the progress totals
+fn count_reused(stats: &Option<Arc<ScanStats>>, node: &FileNode) {
+    if let Some(s) = stats {
+        s.scanned_files.fetch_add(node.file_count, Ordering::Relaxed);
+        s.total_size.fetch_add(node.size, Ordering::Relaxed);
+    }
+}
+
+fn scan_root(
+    path: &str,
+    stats: Option<Arc<ScanStats>>,
+    cancel: Option<Arc<AtomicBool>>,
+    exclusions: &Exclusions,
+    strategy: &ScanStrategy,
+    links: &LinkWalk,
+    reuse: &[&dyn Reuse],
+) -> Result<FileNode, String> {
+    // Extended-length root, so entries beyond MAX_PATH on Windows are read too
+    let root_path = paths::extended(Path::new(path));
     if !root_path.exists() {
         return Err("Directory does not exist".to_string());
     }
@@ -38,12 +680,19 @@ pub fn scan_directory(
     }
 
     // 1. List immediate children of the requested path
-    let read_dir = std::fs::read_dir(path).map_err(|e| e.to_string())?;
-    let entries: Vec<_> = read_dir.filter_map(|e| e.ok()).collect();
+    let read_dir = std::fs::read_dir(&root_path).map_err(|e| e.to_string())?;
+    let mut entries: Vec<_> = read_dir
+        .filter_map(|e| e.ok())
+        .filter(|e| !exclusions.is_excluded(&e.path()))
+        .collect();
+    order_entries(&mut entries, strategy.stat_order);
     
     // Partition
     let mut files = Vec::new();
     let mut dirs = Vec::new();
+    // Links that weren't measured; shown if the policy didn't ask to skip them
+    let mut unmeasured = Vec::new();
+    let mut skipped_links = 0;
     
     for entry in entries {
         if let Some(c) = &cancel {
@@ -51,74 +700,92 @@ pub fn scan_directory(
         }
 
         if let Ok(metadata) = entry.metadata() {
-            if metadata.is_dir() {
-                dirs.push(entry);
-            } else {
-                files.push((entry, metadata));
+            match links.measure(&entry.path(), metadata) {
+                Ok(Measured { meta, .. }) if links.leaves_device(&entry.path(), &meta) => {}
+                Ok(Measured { meta, link, followed }) if meta.is_dir() => dirs.push((entry, meta, link, followed)),
+                Ok(Measured { meta, link, .. }) => files.push((entry, meta, link)),
+                Err(link) => {
+                    skipped_links += 1;
+                    if let Some(link) = link {
+                        unmeasured.push((entry, link));
+                    }
+                }
             }
         }
     }
     
-    let mut total_size = 0;
-    let mut file_count = 0;
+    let mut totals = Totals { skipped_links, ..Totals::default() };
     
     // Files in root
-    for (_entry, meta) in &files {
-        let size = meta.len();
-        total_size += size;
-        file_count += 1;
+    for (entry, meta, _link) in &files {
+        totals.add_file(&entry.path(), meta);
         
         if let Some(s) = &stats {
             s.scanned_files.fetch_add(1, Ordering::Relaxed);
-            s.total_size.fetch_add(size, Ordering::Relaxed);
+            s.total_size.fetch_add(meta.len(), Ordering::Relaxed);
         }
     }
     
     // 2. Process subdirectories in parallel (Lookahead scan)
     // We want to return a node for each directory that INCLUDES its own children list
     // This allows the caller to cache these nodes effectively.
-    let dir_results_res: Result<Vec<FileNode>, String> = dirs.par_iter().map(|entry| {
+    let dir_results_res: Result<Vec<FileNode>, String> = dirs.par_iter().map(|(entry, metadata, link, followed)| {
         if let Some(c) = &cancel {
              if c.load(Ordering::Relaxed) { return Err("Cancelled".to_string()); }
         }
 
         let path = entry.path();
-        let path_str = path.to_string_lossy().to_string();
+        let report = |node: &FileNode| {
+            if let Some(on_child) = stats.as_ref().and_then(|s| s.on_child.as_ref()) {
+                on_child(node);
+            }
+        };
+        if let Some(node) = reused(reuse, &path) {
+            count_reused(&stats, &node);
+            report(&node);
+            return Ok(node);
+        }
+        let path_str = paths::display(&path);
         let name = entry.file_name().to_string_lossy().to_string();
         
-        let metadata = entry.metadata().unwrap();
-        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH)
-            .duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs();
+        let modified = modified_secs(metadata);
 
         // LOOKAHEAD: Scan the children of this subdirectory 
         // to populate its `children` field and calculate exact size.
-        let (size, count, children) = scan_subdir_details(&path, stats.clone(), cancel.clone())?;
+        let links = followed.as_ref().unwrap_or(links);
+        let (totals, children) = scan_subdir_details(&path, stats.clone(), cancel.clone(), exclusions, strategy, links, reuse)?;
 
-        Ok(FileNode {
+        let node = FileNode {
             name,
             path: path_str,
-            size,
+            size: totals.size,
             is_dir: true,
             children: Some(children), // We now populate this!
             last_modified: modified,
-            file_count: count,
-        })
+            file_count: totals.count,
+            volume: None,
+            link: link.clone(),
+            skipped_links: skipped(totals.skipped_links),
+            allocated_size: Some(totals.allocated),
+            extensions: totals.extensions.map(Arc::new),
+            deferred: false,
+        };
+        finished(reuse, &node);
+        report(&node);
+        Ok(node)
     }).collect();
     
     let dir_results = dir_results_res?;
     
     // Aggregate totals
     for dir in &dir_results {
-        total_size += dir.size;
-        file_count += dir.file_count;
+        totals.add_node(dir);
     }
 
     // Convert files in root to FileNodes
-    let mut file_nodes: Vec<FileNode> = files.iter().map(|(entry, meta)| {
+    let mut file_nodes: Vec<FileNode> = files.into_iter().map(|(entry, meta, link)| {
         let name = entry.file_name().to_string_lossy().to_string();
-        let path_str = entry.path().to_string_lossy().to_string();
-        let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)
-            .duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs();
+        let path_str = paths::display(&entry.path());
 
         FileNode {
             name,
@@ -126,10 +793,19 @@ pub fn scan_directory(
             size: meta.len(),
             is_dir: false,
             children: None,
-            last_modified: modified,
+            last_modified: modified_secs(&meta),
             file_count: 1,
+            volume: None,
+            link,
+            skipped_links: None,
+            allocated_size: Some(allocated_size(&entry.path(), &meta)),
+            extensions: None,
+            deferred: false,
         }
     }).collect();
+
+    // Broken and cyclic links take no space, but should still be visible
+    file_nodes.extend(unmeasured.into_iter().map(|(entry, link)| unmeasured_node(&entry, link)));
     
     // Combine dirs and files
     let mut children_nodes = dir_results;
@@ -141,32 +817,44 @@ pub fn scan_directory(
     Ok(FileNode {
         name: root_path.file_name().unwrap_or_default().to_string_lossy().to_string(),
         path: path.to_string(), // Keep original path string for consistency
-        size: total_size,
+        size: totals.size,
         is_dir: true,
         children: Some(children_nodes),
         last_modified: 0,
-        file_count,
+        file_count: totals.count,
+        volume: None,
+        link: None,
+        skipped_links: skipped(totals.skipped_links),
+        allocated_size: Some(totals.allocated),
+        extensions: totals.extensions.map(Arc::new),
+        deferred: false,
     })
 }
 
-// Scans a subdirectory: Lists ITS children, and calculates their sizes (deep)
+// Scans a subdirectory: Lists ITS children, and calculates their sizes (deep).
+// Returns the totals below it, and its folders.
 fn scan_subdir_details(
-    path: &std::path::Path, 
+    path: &Path, 
     stats: Option<Arc<ScanStats>>, 
-    cancel: Option<Arc<AtomicBool>>
-) -> Result<(u64, u64, Vec<FileNode>), String> {
+    cancel: Option<Arc<AtomicBool>>,
+    exclusions: &Exclusions,
+    strategy: &ScanStrategy,
+    links: &LinkWalk,
+    reuse: &[&dyn Reuse],
+) -> Result<(Totals, Vec<FileNode>), String> {
     // List children of this subdirectory
     
-    let mut total_size = 0;
-    let mut total_count = 0;
+    let mut totals = Totals::default();
     let mut children_nodes = Vec::new();
 
     if let Ok(read_dir) = std::fs::read_dir(path) {
-        let entries: Vec<_> = read_dir.filter_map(|e| e.ok()).collect();
+        let mut entries: Vec<_> = read_dir
+            .filter_map(|e| e.ok())
+            .filter(|e| !exclusions.is_excluded(&e.path()))
+            .collect();
+        order_entries(&mut entries, strategy.stat_order);
         
         // Split into files/dirs
-        let mut sub_files_size = 0;
-        let mut sub_files_count = 0;
         let mut sub_dirs = Vec::new();
         
         for entry in entries {
@@ -175,93 +863,134 @@ fn scan_subdir_details(
             }
 
              if let Ok(meta) = entry.metadata() {
-                if meta.is_dir() {
-                    sub_dirs.push(entry);
-                } else {
-                    let s = meta.len();
-                    sub_files_size += s;
-                    sub_files_count += 1;
-                    
-                    if let Some(st) = &stats {
-                        st.scanned_files.fetch_add(1, Ordering::Relaxed);
-                        st.total_size.fetch_add(s, Ordering::Relaxed);
+                match links.measure(&entry.path(), meta) {
+                    Ok(Measured { meta, .. }) if links.leaves_device(&entry.path(), &meta) => {}
+                    Ok(Measured { meta, link, followed }) if meta.is_dir() => sub_dirs.push((entry, meta, link, followed)),
+                    Ok(Measured { meta, .. }) => {
+                        totals.add_file(&entry.path(), &meta);
+                        
+                        if let Some(st) = &stats {
+                            st.scanned_files.fetch_add(1, Ordering::Relaxed);
+                            st.total_size.fetch_add(meta.len(), Ordering::Relaxed);
+                        }
                     }
+                    Err(_) => totals.skipped_links += 1,
                 }
              }
         }
         
-        total_size += sub_files_size;
-        total_count += sub_files_count;
-        
         // Process these subdirectories (Deep scan for size)
-        let sub_dir_nodes_res: Result<Vec<FileNode>, String> = sub_dirs.par_iter().map(|entry| {
+        let sub_dir_nodes_res: Result<Vec<FileNode>, String> = sub_dirs.par_iter().map(|(entry, meta, link, followed)| {
              if let Some(c) = &cancel {
                  if c.load(Ordering::Relaxed) { return Err("Cancelled".to_string()); }
              }
              
              let p = entry.path();
+             if let Some(node) = reused(reuse, &p) {
+                 count_reused(&stats, &node);
+                 return Ok(node);
+             }
              let name = entry.file_name().to_string_lossy().to_string();
-             let p_str = p.to_string_lossy().to_string();
+             let p_str = paths::display(&p);
              
              // Get stats using walkdir (Deep scan)
-             let (s, c) = get_deep_stats(&p, stats.clone(), cancel.clone())?;
-             
-             let m = entry.metadata().ok().and_then(|m| m.modified().ok())
-                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
-                .map(|d| d.as_secs()).unwrap_or(0);
+             let links = followed.as_ref().unwrap_or(links);
+             let deep = get_deep_stats(&p, stats.clone(), cancel.clone(), exclusions, strategy.batch_size, links)?;
                 
-             Ok(FileNode {
+             let node = FileNode {
                  name,
                  path: p_str,
-                 size: s,
+                 size: deep.size,
                  is_dir: true,
                  children: None, // We stop lookahead at 1 level deep to avoid recursion explosion
-                 last_modified: m,
-                 file_count: c,
-             })
+                 last_modified: modified_secs(meta),
+                 file_count: deep.count,
+                 volume: None,
+                 link: link.clone(),
+                 skipped_links: skipped(deep.skipped_links),
+                 allocated_size: Some(deep.allocated),
+                 extensions: deep.extensions.map(Arc::new),
+                 deferred: false,
+             };
+             finished(reuse, &node);
+             Ok(node)
         }).collect();
 
         let sub_dir_nodes = sub_dir_nodes_res?;
         
         for node in &sub_dir_nodes {
-            total_size += node.size;
-            total_count += node.file_count;
+            totals.add_node(node);
         }
         
         children_nodes = sub_dir_nodes;
         children_nodes.sort_by(|a, b| b.size.cmp(&a.size));
     }
     
-    Ok((total_size, total_count, children_nodes))
+    Ok((totals, children_nodes))
 }
 
+// Size, space on disk, file count and unmeasured links of everything below `path`
 fn get_deep_stats(
-    path: &std::path::Path, 
+    path: &Path, 
     stats: Option<Arc<ScanStats>>, 
-    cancel: Option<Arc<AtomicBool>>
-) -> Result<(u64, u64), String> {
-    let mut size = 0;
-    let mut count = 0;
+    cancel: Option<Arc<AtomicBool>>,
+    exclusions: &Exclusions,
+    batch_size: usize,
+    links: &LinkWalk,
+) -> Result<Totals, String> {
+    let mut totals = Totals::default();
+    // Progress not yet added to the shared counters
+    let (mut pending_size, mut pending_count) = (0, 0);
+    let flush = |pending_size: &mut u64, pending_count: &mut u64| {
+        if let Some(st) = &stats {
+            st.scanned_files.fetch_add(*pending_count, Ordering::Relaxed);
+            st.total_size.fetch_add(*pending_size, Ordering::Relaxed);
+        }
+        (*pending_size, *pending_count) = (0, 0);
+    };
     
-    // Using simple walkdir; we should periodically check cancel
-    for (idx, entry) in walkdir::WalkDir::new(path).min_depth(1).into_iter().enumerate() {
-        if idx % 100 == 0 {
+    // Using simple walkdir; we should periodically check cancel. Links are
+    // never followed by walkdir itself, so the policy decides about each.
+    // `path` is on the root's device by now, so walkdir can keep to it.
+    let walker = walkdir::WalkDir::new(path)
+        .min_depth(1)
+        .same_file_system(links.device.is_some())
+        .into_iter()
+        .filter_entry(|e| !exclusions.is_excluded(e.path()));
+    for (idx, entry) in walker.enumerate() {
+        if idx % batch_size.max(1) == 0 {
+             flush(&mut pending_size, &mut pending_count);
              if let Some(c) = &cancel {
                  if c.load(Ordering::Relaxed) { return Err("Cancelled".to_string()); }
              }
         }
 
         match entry {
+            Ok(entry) if entry.file_type().is_symlink() => {
+                let Ok(meta) = entry.metadata() else { continue };
+                match links.measure(entry.path(), meta) {
+                    Ok(Measured { meta, .. }) if links.leaves_device(entry.path(), &meta) => {}
+                    Ok(Measured { followed: Some(followed), .. }) => {
+                        totals.add(get_deep_stats(entry.path(), stats.clone(), cancel.clone(), exclusions, batch_size, &followed)?);
+                    }
+                    Ok(Measured { meta, .. }) => {
+                        totals.add_file(entry.path(), &meta);
+                        pending_size += meta.len();
+                        pending_count += 1;
+                    }
+                    Err(_) => totals.skipped_links += 1,
+                }
+            }
             Ok(entry) => {
                 if entry.file_type().is_file() {
-                    let s = entry.metadata().map(|m| m.len()).unwrap_or(0);
-                    size += s;
-                    count += 1;
-
-                    if let Some(st) = &stats {
-                        st.scanned_files.fetch_add(1, Ordering::Relaxed);
-                        st.total_size.fetch_add(s, Ordering::Relaxed);
+                    match entry.metadata() {
+                        Ok(meta) => {
+                            totals.add_file(entry.path(), &meta);
+                            pending_size += meta.len();
+                        }
+                        Err(_) => totals.count += 1,
                     }
+                    pending_count += 1;
                 }
             }
             Err(_e) => {
@@ -272,6 +1001,58 @@ fn get_deep_stats(
             }
         }
     }
+    flush(&mut pending_size, &mut pending_count);
     
-    Ok((size, count))
+    Ok(totals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tree on one device with a followed link into a folder on another
+    /// (tmpfs), or None where no second writable file system is at hand
+    #[cfg(unix)]
+    fn tree_with_foreign_link() -> Option<(tempfile::TempDir, tempfile::TempDir)> {
+        use std::os::unix::fs::MetadataExt;
+        let tree = tempfile::tempdir().ok()?;
+        let foreign = tempfile::tempdir_in("/dev/shm").ok()?;
+        let dev = |p: &Path| std::fs::metadata(p).map(|m| m.dev()).ok();
+        if dev(tree.path())? == dev(foreign.path())? {
+            return None;
+        }
+        std::fs::write(tree.path().join("local.bin"), [0u8; 100]).ok()?;
+        std::fs::write(foreign.path().join("foreign.bin"), [0u8; 1000]).ok()?;
+        std::os::unix::fs::symlink(foreign.path(), tree.path().join("mount")).ok()?;
+        Some((tree, foreign))
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_same_device_stops_at_device_boundary() {
+        let Some((tree, _foreign)) = tree_with_foreign_link() else {
+            return;
+        };
+        let path = tree.path().to_string_lossy().to_string();
+        let scan = |same_device| {
+            let strategy = ScanStrategy::default();
+            scan_directory(&path, None, None, &Exclusions::default(), &strategy, SymlinkPolicy::Follow, same_device, &[])
+                .unwrap()
+        };
+
+        let across = scan(Some(false));
+        assert_eq!((across.file_count, across.size), (2, 1100));
+
+        let staying = scan(Some(true));
+        assert_eq!((staying.file_count, staying.size), (1, 100));
+        let names: Vec<_> = staying.children.unwrap().into_iter().map(|c| c.name).collect();
+        assert_eq!(names, ["local.bin"]);
+
+        // The profile's setting applies when the scan doesn't say
+        let profile = Exclusions::default().staying_on_device();
+        assert!(!profile.is_empty());
+        let node = scan_directory(&path, None, None, &profile, &ScanStrategy::default(), SymlinkPolicy::Follow, None, &[])
+            .unwrap();
+        assert_eq!(node.file_count, 1);
+    }
 }
@@ -0,0 +1,303 @@
+// Shred - Secure deletion with verified overwrites
+//
+// Shredding overwrites every byte of a file in place a number of times
+// (random data, then zeros on the last pass), flushing each pass to the
+// device, before the file is renamed to a random name and removed. After the
+// last pass the file is read back and compared with what was written, so the
+// report states whether the overwrite actually completed rather than that it
+// was attempted. Each shredded file gets a record (path, size, passes,
+// timestamps, SHA-256 of the final content) and the whole run is appended to
+// the audit log, whose hash chain makes it usable as evidence of sanitization.
+//
+// SSDs remap writes and copy-on-write filesystems (APFS, Btrfs, ZFS) write
+// elsewhere, so old blocks can survive an in-place overwrite; records on such
+// volumes say so, and full-disk encryption is the reliable answer there.
+
+use crate::audit;
+use crate::paths;
+use crate::scan_strategy::{self, StorageKind};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{info, warn};
+
+/// Passes used when the caller doesn't choose
+pub const DEFAULT_PASSES: u32 = 3;
+/// More passes than this add time, not safety
+pub const MAX_PASSES: u32 = 7;
+const CHUNK: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PassPattern {
+    Random,
+    Zeros,
+}
+
+/// Evidence for one shredded file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShredRecord {
+    pub path: String,
+    pub size: u64,
+    pub passes: Vec<PassPattern>,
+    /// Unix timestamps in milliseconds
+    pub started_at: i64,
+    pub finished_at: i64,
+    /// The last pass was read back and matched what was written
+    pub verified: bool,
+    /// SHA-256 of the content after the last pass
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_content_hash: Option<String>,
+    pub removed: bool,
+    /// Storage where in-place overwrites may leave old copies behind
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caveat: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of a shredding run, as appended to the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShredReport {
+    pub records: Vec<ShredRecord>,
+    pub files: usize,
+    pub verified_files: usize,
+    pub bytes: u64,
+    pub cancelled: bool,
+    /// Hash of the audit log entry holding this report
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit_hash: Option<String>,
+}
+
+/// Pseudo-random pass data: SHA-256 in counter mode over a random seed.
+/// The point is data unrelated to the old content, not secrecy.
+struct PassData {
+    seed: [u8; 16],
+    counter: u64,
+}
+
+impl PassData {
+    fn new() -> Self {
+        Self {
+            seed: *uuid::Uuid::new_v4().as_bytes(),
+            counter: 0,
+        }
+    }
+
+    fn fill(&mut self, buf: &mut [u8]) {
+        for block in buf.chunks_mut(32) {
+            let mut hasher = Sha256::new();
+            hasher.update(self.seed);
+            hasher.update(self.counter.to_le_bytes());
+            self.counter += 1;
+            let digest = hasher.finalize();
+            block.copy_from_slice(&digest[..block.len()]);
+        }
+    }
+}
+
+fn passes_for(count: u32) -> Vec<PassPattern> {
+    let count = count.clamp(1, MAX_PASSES);
+    // Random passes first; a final pass of zeros leaves nothing that looks
+    // like it was deliberately scrambled
+    let mut passes = vec![PassPattern::Random; count as usize - 1];
+    passes.push(PassPattern::Zeros);
+    passes
+}
+
+fn caveat_for(kind: StorageKind, file_system: &str) -> Option<String> {
+    let fs = file_system.to_lowercase();
+    if matches!(fs.as_str(), "apfs" | "btrfs" | "zfs" | "refs") {
+        return Some(format!(
+            "{} is copy-on-write; earlier copies of the data may remain until the space is reused",
+            file_system
+        ));
+    }
+    match kind {
+        StorageKind::Nvme | StorageKind::SataSsd | StorageKind::Usb => Some(
+            "Flash storage remaps writes; earlier copies may remain in spare blocks. Use full-disk encryption for guaranteed sanitization".to_string(),
+        ),
+        StorageKind::Network => Some("Network storage may keep snapshots or replicas the overwrite can't reach".to_string()),
+        StorageKind::Hdd | StorageKind::Unknown => None,
+    }
+}
+
+fn file_system_of(path: &Path) -> String {
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    disks
+        .iter()
+        .filter(|d| paths::is_within(path, d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())
+        .map(|d| d.file_system().to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Overwrite the file with every pass and verify the last one. Returns the
+/// hash of the final content and whether the read-back matched.
+fn overwrite(path: &Path, size: u64, passes: &[PassPattern], cancel: &AtomicBool) -> Result<(String, bool), String> {
+    let mut file = OpenOptions::new().write(true).read(true).open(path).map_err(|e| e.to_string())?;
+    let mut buf = vec![0u8; CHUNK];
+    let mut written_hash = String::new();
+
+    for pattern in passes {
+        let mut data = PassData::new();
+        let mut hasher = Sha256::new();
+        file.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+        let mut left = size;
+        while left > 0 {
+            if cancel.load(Ordering::Relaxed) {
+                return Err("Cancelled".to_string());
+            }
+            let len = left.min(CHUNK as u64) as usize;
+            match pattern {
+                PassPattern::Random => data.fill(&mut buf[..len]),
+                PassPattern::Zeros => buf[..len].fill(0),
+            }
+            file.write_all(&buf[..len]).map_err(|e| e.to_string())?;
+            hasher.update(&buf[..len]);
+            left -= len as u64;
+        }
+        // Each pass has to reach the device, or the next one just replaces it in the cache
+        file.sync_all().map_err(|e| e.to_string())?;
+        written_hash = hex::encode(hasher.finalize());
+    }
+
+    // Read back through a fresh handle and compare with what the last pass wrote
+    let mut reader = File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut read = 0u64;
+    loop {
+        let n = reader.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        read += n as u64;
+    }
+    let read_hash = hex::encode(hasher.finalize());
+    Ok((written_hash.clone(), read == size && read_hash == written_hash))
+}
+
+/// Rename to a random name in the same folder, so the original name doesn't
+/// survive in the directory entry, then remove
+fn remove_obscured(path: &Path) -> std::io::Result<()> {
+    let target = path
+        .parent()
+        .map(|parent| parent.join(uuid::Uuid::new_v4().simple().to_string()))
+        .unwrap_or_else(|| path.to_path_buf());
+    let path = match std::fs::rename(path, &target) {
+        Ok(()) => target,
+        Err(_) => path.to_path_buf(),
+    };
+    std::fs::remove_file(path)
+}
+
+fn shred_file(path: &Path, passes: &[PassPattern], caveat: Option<String>, cancel: &AtomicBool) -> ShredRecord {
+    let started_at = chrono::Utc::now().timestamp_millis();
+    let extended = paths::extended(path);
+    let size = std::fs::metadata(&extended).map(|m| m.len()).unwrap_or(0);
+    let mut record = ShredRecord {
+        path: paths::display(path),
+        size,
+        passes: passes.to_vec(),
+        started_at,
+        finished_at: started_at,
+        verified: false,
+        final_content_hash: None,
+        removed: false,
+        caveat,
+        error: None,
+    };
+
+    match overwrite(&extended, size, passes, cancel) {
+        Ok((hash, verified)) => {
+            record.final_content_hash = Some(hash);
+            record.verified = verified;
+            if verified {
+                match remove_obscured(&extended) {
+                    Ok(()) => record.removed = true,
+                    Err(e) => record.error = Some(e.to_string()),
+                }
+            } else {
+                // Leave the file in place so the user can see it wasn't sanitized
+                warn!("Overwrite of {} could not be verified", record.path);
+                record.error = Some("Read-back did not match the written data".to_string());
+            }
+        }
+        Err(e) => record.error = Some(e),
+    }
+    record.finished_at = chrono::Utc::now().timestamp_millis();
+    record
+}
+
+/// Files under `root` (or `root` itself), deepest first, and its folders
+fn collect(root: &Path) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    if !paths::extended(root).is_dir() {
+        return (vec![root.to_path_buf()], Vec::new());
+    }
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    for entry in walkdir::WalkDir::new(root).contents_first(true).into_iter().filter_map(|e| e.ok()) {
+        if entry.file_type().is_dir() {
+            dirs.push(entry.into_path());
+        } else if entry.file_type().is_file() {
+            files.push(entry.into_path());
+        }
+    }
+    (files, dirs)
+}
+
+/// Shred files and folders and append the report to the audit log.
+/// Symlinks are not followed; folders are removed once empty.
+pub fn shred(targets: &[String], passes: u32, cancel: &AtomicBool, progress: impl Fn(f32, &str)) -> Result<ShredReport, String> {
+    let passes = passes_for(passes);
+    let mut records = Vec::new();
+    let mut cancelled = false;
+
+    for (index, target) in targets.iter().enumerate() {
+        let root = Path::new(target);
+        if !paths::extended(root).exists() {
+            return Err(format!("{}: not found", target));
+        }
+        let (_, kind) = scan_strategy::detect(root);
+        let caveat = caveat_for(kind, &file_system_of(root));
+        let (files, dirs) = collect(root);
+        for file in &files {
+            if cancel.load(Ordering::Relaxed) {
+                cancelled = true;
+                break;
+            }
+            progress(index as f32 / targets.len() as f32, &paths::display(file));
+            records.push(shred_file(file, &passes, caveat.clone(), cancel));
+        }
+        if cancelled {
+            break;
+        }
+        // contents_first lists children before their parents
+        for dir in &dirs {
+            let _ = std::fs::remove_dir(paths::extended(dir));
+        }
+    }
+
+    let mut report = ShredReport {
+        files: records.len(),
+        verified_files: records.iter().filter(|r| r.verified).count(),
+        bytes: records.iter().filter(|r| r.verified).map(|r| r.size).sum(),
+        records,
+        cancelled,
+        audit_hash: None,
+    };
+    // The evidence matters even when some files failed, so log every run
+    let entry = audit::append_log("shred", &report)?;
+    report.audit_hash = Some(entry.hash);
+    info!(
+        "Shredded {} of {} files ({} bytes)",
+        report.verified_files, report.files, report.bytes
+    );
+    Ok(report)
+}
@@ -0,0 +1,495 @@
+// Size Index - Continuously maintained directory sizes for chosen roots
+//
+// An indexed root is walked once, after which a filesystem watcher keeps the
+// size and file count of every directory below it up to date. Changed
+// directories are reconciled in small batches (own files re-summed, new and
+// removed subdirectories added or dropped) and the difference is propagated
+// to their ancestors, so sizes stay current without rescanning. Only
+// directories are stored, which keeps the index small enough to persist: on
+// the next launch sizes are available immediately while a background rebuild
+// catches up with changes made while Helium was closed.
+
+use crate::scanner::{Exclusions, FileNode};
+use crate::storage;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// How often queued filesystem changes are applied
+const APPLY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often changed roots are written to disk
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Sizes of one directory
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DirSizes {
+    /// Files directly inside the directory
+    own_size: u64,
+    own_files: u64,
+    /// Everything below the directory
+    size: u64,
+    files: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RootIndex {
+    root: String,
+    built_at: i64,
+    dirs: HashMap<String, DirSizes>,
+}
+
+/// Indexed root as shown to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedRoot {
+    pub path: String,
+    pub size: u64,
+    pub file_count: u64,
+    pub directory_count: u64,
+    /// Unix timestamp of the last full walk (0 while the first one runs)
+    pub built_at: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    /// Root path -> index file name
+    roots: HashMap<String, String>,
+}
+
+fn key(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// Walk `dir` and return the sizes of every directory below it (inclusive)
+fn walk(dir: &Path, exclusions: &Exclusions) -> HashMap<String, DirSizes> {
+    let mut dirs: HashMap<String, DirSizes> = HashMap::new();
+    dirs.insert(key(dir), DirSizes::default());
+    let entries = walkdir::WalkDir::new(dir)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|e| !exclusions.is_excluded(e.path()))
+        .filter_map(|e| e.ok());
+    for entry in entries {
+        if entry.file_type().is_dir() {
+            dirs.entry(key(entry.path())).or_default();
+        } else if entry.file_type().is_file() {
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            if let Some(parent) = entry.path().parent() {
+                let sizes = dirs.entry(key(parent)).or_default();
+                sizes.own_size += size;
+                sizes.own_files += 1;
+            }
+        }
+    }
+
+    // Aggregate bottom-up: deepest directories first
+    let mut paths: Vec<String> = dirs.keys().cloned().collect();
+    paths.sort_by_key(|p| std::cmp::Reverse(Path::new(p).components().count()));
+    for sizes in dirs.values_mut() {
+        sizes.size = sizes.own_size;
+        sizes.files = sizes.own_files;
+    }
+    for path in paths {
+        if Path::new(&path) == dir {
+            continue;
+        }
+        let sizes = dirs[&path];
+        if let Some(parent) = Path::new(&path).parent().map(key) {
+            if let Some(parent) = dirs.get_mut(&parent) {
+                parent.size += sizes.size;
+                parent.files += sizes.files;
+            }
+        }
+    }
+    dirs
+}
+
+impl RootIndex {
+    /// Add a difference to `dir` and every ancestor up to the root
+    fn propagate(&mut self, dir: &Path, size: i64, files: i64) {
+        let root = PathBuf::from(&self.root);
+        let mut current = Some(dir);
+        while let Some(path) = current {
+            if let Some(sizes) = self.dirs.get_mut(&key(path)) {
+                sizes.size = (sizes.size as i64 + size).max(0) as u64;
+                sizes.files = (sizes.files as i64 + files).max(0) as u64;
+            }
+            if path == root {
+                break;
+            }
+            current = path.parent();
+        }
+    }
+
+    fn children(&self, dir: &Path) -> Vec<String> {
+        self.dirs
+            .keys()
+            .filter(|p| Path::new(p).parent() == Some(dir))
+            .cloned()
+            .collect()
+    }
+
+    fn remove_subtree(&mut self, dir: &Path) {
+        let Some(sizes) = self.dirs.get(&key(dir)).copied() else {
+            return;
+        };
+        self.dirs.retain(|p, _| !Path::new(p).starts_with(dir));
+        if let Some(parent) = dir.parent() {
+            self.propagate(parent, -(sizes.size as i64), -(sizes.files as i64));
+        }
+    }
+
+    fn add_subtree(&mut self, dir: &Path, exclusions: &Exclusions) {
+        let subtree = walk(dir, exclusions);
+        let sizes = subtree[&key(dir)];
+        self.dirs.extend(subtree);
+        if let Some(parent) = dir.parent() {
+            self.propagate(parent, sizes.size as i64, sizes.files as i64);
+        }
+    }
+
+    /// Bring one directory in line with the disk
+    fn reconcile(&mut self, dir: &Path, exclusions: &Exclusions) {
+        if !dir.starts_with(&self.root) || exclusions.is_excluded(dir) {
+            return;
+        }
+        if !dir.is_dir() {
+            self.remove_subtree(dir);
+            return;
+        }
+        if !self.dirs.contains_key(&key(dir)) {
+            match dir.parent() {
+                Some(parent) if self.dirs.contains_key(&key(parent)) => self.add_subtree(dir, exclusions),
+                Some(parent) => self.reconcile(parent, exclusions),
+                None => {}
+            }
+            return;
+        }
+
+        let Ok(read_dir) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let (mut own_size, mut own_files) = (0u64, 0u64);
+        let mut on_disk = HashSet::new();
+        for entry in read_dir.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if exclusions.is_excluded(&path) {
+                continue;
+            }
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => {
+                    on_disk.insert(key(&path));
+                }
+                Ok(t) if t.is_file() => {
+                    own_size += entry.metadata().map(|m| m.len()).unwrap_or(0);
+                    own_files += 1;
+                }
+                _ => {}
+            }
+        }
+
+        let old = self.dirs[&key(dir)];
+        if let Some(sizes) = self.dirs.get_mut(&key(dir)) {
+            sizes.own_size = own_size;
+            sizes.own_files = own_files;
+        }
+        self.propagate(dir, own_size as i64 - old.own_size as i64, own_files as i64 - old.own_files as i64);
+
+        let indexed: HashSet<String> = self.children(dir).into_iter().collect();
+        for removed in indexed.difference(&on_disk) {
+            self.remove_subtree(Path::new(removed));
+        }
+        for added in on_disk.difference(&indexed) {
+            self.add_subtree(Path::new(added), exclusions);
+        }
+    }
+
+    fn node(&self, path: &str, depth: usize) -> Option<FileNode> {
+        let sizes = self.dirs.get(path)?;
+        let children = (depth > 0).then(|| {
+            let mut children: Vec<FileNode> = self
+                .children(Path::new(path))
+                .iter()
+                .filter_map(|child| self.node(child, depth - 1))
+                .collect();
+            children.sort_by(|a, b| b.size.cmp(&a.size));
+            children
+        });
+        Some(FileNode {
+            name: Path::new(path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.to_string()),
+            path: path.to_string(),
+            size: sizes.size,
+            is_dir: true,
+            children,
+            last_modified: self.built_at.max(0) as u64,
+            file_count: sizes.files,
+            volume: None,
+            link: None,
+            skipped_links: None,
+            allocated_size: None,
+            extensions: None,
+            deferred: false,
+        })
+    }
+
+    fn summary(&self) -> IndexedRoot {
+        let sizes = self.dirs.get(&self.root).copied().unwrap_or_default();
+        IndexedRoot {
+            path: self.root.clone(),
+            size: sizes.size,
+            file_count: sizes.files,
+            directory_count: self.dirs.len() as u64,
+            built_at: self.built_at,
+        }
+    }
+}
+
+struct Inner {
+    dir: PathBuf,
+    manifest: Mutex<Manifest>,
+    roots: Mutex<HashMap<String, RootIndex>>,
+    /// Directories touched since the last apply
+    pending: Mutex<HashSet<PathBuf>>,
+    /// Roots changed since the last save
+    dirty: Mutex<HashSet<String>>,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    exclusions: Mutex<Exclusions>,
+}
+
+/// The index of all roots, held in managed state
+#[derive(Clone)]
+pub struct SizeIndex {
+    inner: Arc<Inner>,
+}
+
+impl SizeIndex {
+    /// Load persisted roots from `dir` (index files plus a manifest)
+    pub fn load(dir: PathBuf) -> Self {
+        let manifest: Manifest = storage::load_json(&dir.join("manifest.json")).ok().flatten().unwrap_or_default();
+        let roots = manifest
+            .roots
+            .iter()
+            .map(|(root, file)| {
+                let index = storage::load_json(&dir.join(file)).ok().flatten().unwrap_or_else(|| RootIndex {
+                    root: root.clone(),
+                    ..Default::default()
+                });
+                (root.clone(), index)
+            })
+            .collect();
+        Self {
+            inner: Arc::new(Inner {
+                dir,
+                manifest: Mutex::new(manifest),
+                roots: Mutex::new(roots),
+                pending: Mutex::new(HashSet::new()),
+                dirty: Mutex::new(HashSet::new()),
+                watcher: Mutex::new(None),
+                exclusions: Mutex::new(Exclusions::default()),
+            }),
+        }
+    }
+
+    /// Exclusions applied to walks and reconciles (from the active profile)
+    pub fn set_exclusions(&self, exclusions: Exclusions) {
+        *self.inner.exclusions.lock().unwrap() = exclusions;
+    }
+
+    fn exclusions(&self) -> Exclusions {
+        self.inner.exclusions.lock().unwrap().clone()
+    }
+
+    fn save_manifest(&self) -> Result<(), String> {
+        let manifest = self.inner.manifest.lock().unwrap();
+        storage::save_json(&self.inner.dir.join("manifest.json"), &*manifest)
+    }
+
+    fn save_root(&self, root: &str) {
+        let Some(file) = self.inner.manifest.lock().unwrap().roots.get(root).cloned() else {
+            return;
+        };
+        let roots = self.inner.roots.lock().unwrap();
+        if let Some(index) = roots.get(root) {
+            if let Err(e) = storage::save_json(&self.inner.dir.join(file), index) {
+                warn!("Failed to save size index for {}: {}", root, e);
+            }
+        }
+    }
+
+    /// Start the watcher, rebuild persisted roots and apply changes
+    /// periodically while the app runs
+    pub fn spawn(&self) {
+        let index = self.clone();
+        let pending = self.clone();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) => {
+                let mut queue = pending.inner.pending.lock().unwrap();
+                for path in event.paths {
+                    crate::cache_warmer::observe(&path);
+                    // A file change affects its directory's own size; a
+                    // removed directory is reconciled by its own path
+                    if !path.is_dir() {
+                        if let Some(parent) = path.parent() {
+                            queue.insert(parent.to_path_buf());
+                        }
+                    }
+                    queue.insert(path);
+                }
+            }
+            Err(e) => warn!("Size index watcher error: {}", e),
+        });
+        match watcher {
+            Ok(watcher) => *self.inner.watcher.lock().unwrap() = Some(watcher),
+            Err(e) => warn!("Size index cannot watch for changes: {}", e),
+        }
+
+        let roots: Vec<String> = self.inner.roots.lock().unwrap().keys().cloned().collect();
+        for root in roots {
+            self.watch(&root);
+            self.rebuild(root);
+        }
+
+        tauri::async_runtime::spawn(async move {
+            let mut since_save = Duration::ZERO;
+            loop {
+                tokio::time::sleep(APPLY_INTERVAL).await;
+                let worker = index.clone();
+                let _ = tauri::async_runtime::spawn_blocking(move || worker.apply_pending()).await;
+                since_save += APPLY_INTERVAL;
+                if since_save >= SAVE_INTERVAL {
+                    since_save = Duration::ZERO;
+                    let dirty: Vec<String> = index.inner.dirty.lock().unwrap().drain().collect();
+                    for root in dirty {
+                        index.save_root(&root);
+                    }
+                }
+            }
+        });
+    }
+
+    fn watch(&self, root: &str) {
+        if let Some(watcher) = self.inner.watcher.lock().unwrap().as_mut() {
+            if let Err(e) = watcher.watch(Path::new(root), RecursiveMode::Recursive) {
+                warn!("Cannot watch {}: {}", root, e);
+            }
+        }
+    }
+
+    /// Reconcile every directory touched since the last call
+    fn apply_pending(&self) {
+        let pending: Vec<PathBuf> = self.inner.pending.lock().unwrap().drain().collect();
+        if pending.is_empty() {
+            return;
+        }
+        let exclusions = self.exclusions();
+        let mut roots = self.inner.roots.lock().unwrap();
+        let mut dirty = self.inner.dirty.lock().unwrap();
+        for path in pending {
+            if let Some(index) = roots.values_mut().find(|i| path.starts_with(&i.root)) {
+                index.reconcile(&path, &exclusions);
+                dirty.insert(index.root.clone());
+            }
+        }
+    }
+
+    /// Walk a root in the background and replace its index
+    fn rebuild(&self, root: String) {
+        let index = self.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let started = std::time::Instant::now();
+            let dirs = walk(Path::new(&root), &index.exclusions());
+            let mut roots = index.inner.roots.lock().unwrap();
+            // The root may have been removed while walking
+            if let Some(existing) = roots.get_mut(&root) {
+                existing.dirs = dirs;
+                existing.built_at = chrono::Utc::now().timestamp();
+                info!("Indexed {} ({} directories) in {:?}", root, existing.dirs.len(), started.elapsed());
+                drop(roots);
+                index.save_root(&root);
+            }
+        });
+    }
+
+    pub fn roots(&self) -> Vec<IndexedRoot> {
+        let mut roots: Vec<IndexedRoot> = self.inner.roots.lock().unwrap().values().map(RootIndex::summary).collect();
+        roots.sort_by(|a, b| a.path.cmp(&b.path));
+        roots
+    }
+
+    /// Indexed directories in memory and their estimated size in bytes
+    pub fn memory_usage(&self) -> (usize, u64) {
+        let per_entry = std::mem::size_of::<(String, DirSizes)>();
+        let roots = self.inner.roots.lock().unwrap();
+        let dirs = roots.values().flat_map(|r| r.dirs.keys());
+        dirs.fold((0, 0), |(count, bytes), key| (count + 1, bytes + (per_entry + key.capacity()) as u64))
+    }
+
+    /// Start indexing a directory; sizes are available once the first walk ends
+    pub fn add_root(&self, path: &str) -> Result<IndexedRoot, String> {
+        let root = crate::paths::normalize(path);
+        if !Path::new(&root).is_dir() {
+            return Err(crate::i18n::t("error.path_not_found"));
+        }
+        {
+            let roots = self.inner.roots.lock().unwrap();
+            if roots.keys().any(|r| Path::new(&root).starts_with(r) || Path::new(r).starts_with(&root)) {
+                return Err(format!("{} overlaps an indexed root", root));
+            }
+        }
+        self.inner
+            .manifest
+            .lock()
+            .unwrap()
+            .roots
+            .insert(root.clone(), format!("{}.json", uuid::Uuid::new_v4()));
+        self.save_manifest()?;
+        let index = RootIndex {
+            root: root.clone(),
+            ..Default::default()
+        };
+        let summary = index.summary();
+        self.inner.roots.lock().unwrap().insert(root.clone(), index);
+        self.watch(&root);
+        self.rebuild(root);
+        Ok(summary)
+    }
+
+    pub fn remove_root(&self, path: &str) -> Result<bool, String> {
+        let root = crate::paths::normalize(path);
+        if self.inner.roots.lock().unwrap().remove(&root).is_none() {
+            return Ok(false);
+        }
+        if let Some(watcher) = self.inner.watcher.lock().unwrap().as_mut() {
+            let _ = watcher.unwatch(Path::new(&root));
+        }
+        let file = self.inner.manifest.lock().unwrap().roots.remove(&root);
+        self.save_manifest()?;
+        if let Some(file) = file {
+            let _ = std::fs::remove_file(self.inner.dir.join(file));
+        }
+        Ok(true)
+    }
+
+    /// Indexed tree of a directory with two levels of subdirectories (like a
+    /// scan result, but without individual files). None if not indexed.
+    pub fn tree(&self, path: &str) -> Option<FileNode> {
+        let path = crate::paths::normalize(path);
+        let roots = self.inner.roots.lock().unwrap();
+        let index = roots.values().find(|i| crate::paths::is_within(Path::new(&path), Path::new(&i.root)))?;
+        if index.built_at == 0 {
+            return None;
+        }
+        index.node(&path, 2)
+    }
+}
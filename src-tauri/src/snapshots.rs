@@ -0,0 +1,236 @@
+// Snapshots - Local backup snapshots that hold "purgeable" space
+//
+// Time Machine keeps local APFS snapshots on macOS, and Windows keeps System
+// Restore points (volume shadow copies) and File History versions. The OS
+// counts that space as purgeable or reserved, so Helium's numbers disagree
+// with Finder/Explorer unless it is shown separately. This module reports
+// those snapshots as special categories and offers only reclaim actions the
+// OS itself provides, which never touch the newest restore point or the
+// backups on an external drive.
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tracing::info;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotItem {
+    pub id: String,
+    pub name: String,
+    /// Creation time as reported by the OS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotCategory {
+    /// "time-machine", "system-restore" or "file-history"
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// Space held by the category, when the OS reports it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_size: Option<u64>,
+    pub items: Vec<SnapshotItem>,
+    /// Reclaim actions that apply to this category
+    pub actions: Vec<ReclaimAction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase", tag = "kind")]
+pub enum ReclaimAction {
+    /// Delete one Time Machine local snapshot
+    DeleteLocalSnapshot { id: String },
+    /// Ask Time Machine to thin local snapshots as far as it can
+    ThinLocalSnapshots,
+    /// Delete the oldest System Restore point of a volume
+    DeleteOldestRestorePoint { volume: String },
+    /// Delete File History versions older than this many days
+    CleanupFileHistory { older_than_days: u32 },
+}
+
+async fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let detail = if stderr.trim().is_empty() { stdout } else { stderr };
+        return Err(format!("{} failed: {}", program, detail.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Parse sizes like "1.23 GB" as printed by vssadmin and diskutil
+#[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+fn parse_size(text: &str) -> Option<u64> {
+    let mut parts = text.split_whitespace();
+    let value: f64 = parts.next()?.replace(',', "").parse().ok()?;
+    let multiplier = match parts.next()?.trim_end_matches(|c: char| !c.is_alphabetic()) {
+        "B" | "bytes" => 1u64,
+        "KB" => 1 << 10,
+        "MB" => 1 << 20,
+        "GB" => 1 << 30,
+        "TB" => 1 << 40,
+        _ => return None,
+    };
+    Some((value * multiplier as f64) as u64)
+}
+
+#[cfg(target_os = "macos")]
+async fn platform_report() -> Result<Vec<SnapshotCategory>, String> {
+    // com.apple.TimeMachine.2024-05-01-101500.local
+    let output = run("tmutil", &["listlocalsnapshots", "/"]).await?;
+    let items: Vec<SnapshotItem> = output
+        .lines()
+        .filter_map(|line| {
+            let date = line.trim().strip_prefix("com.apple.TimeMachine.")?.strip_suffix(".local")?;
+            Some(SnapshotItem {
+                id: date.to_string(),
+                name: line.trim().to_string(),
+                created: Some(date.to_string()),
+                size: None,
+            })
+        })
+        .collect();
+
+    // Older releases print a purgeable figure; newer ones only via Finder
+    let purgeable = run("diskutil", &["info", "/"]).await.ok().and_then(|info| {
+        info.lines()
+            .find(|l| l.contains("Purgeable"))
+            .and_then(|l| l.split_once(':'))
+            .and_then(|(_, value)| parse_size(value.trim()))
+    });
+
+    let mut actions: Vec<ReclaimAction> = items
+        .iter()
+        .map(|i| ReclaimAction::DeleteLocalSnapshot { id: i.id.clone() })
+        .collect();
+    if !items.is_empty() {
+        actions.push(ReclaimAction::ThinLocalSnapshots);
+    }
+    Ok(vec![SnapshotCategory {
+        id: "time-machine".to_string(),
+        name: "Time Machine local snapshots".to_string(),
+        description: "Hourly snapshots kept on this Mac while the backup disk is away. macOS counts them as purgeable and removes them when space runs low.".to_string(),
+        total_size: purgeable,
+        items,
+        actions,
+    }])
+}
+
+#[cfg(target_os = "windows")]
+async fn platform_report() -> Result<Vec<SnapshotCategory>, String> {
+    let mut categories = Vec::new();
+
+    // vssadmin needs administrator rights; without them the category is
+    // reported empty rather than failing the whole report
+    let storage = run("vssadmin", &["list", "shadowstorage"]).await.unwrap_or_default();
+    let mut volume = None;
+    let mut used = None;
+    for line in storage.lines().map(str::trim) {
+        if let Some(v) = line.strip_prefix("For volume:") {
+            volume = v.split_whitespace().next().map(|v| v.trim_start_matches('(').trim_end_matches(')').to_string());
+        }
+        if let Some(v) = line.strip_prefix("Used Shadow Copy Storage space:") {
+            used = parse_size(v.trim());
+        }
+    }
+    let shadows = run("vssadmin", &["list", "shadows"]).await.unwrap_or_default();
+    let mut items = Vec::new();
+    let mut created = None;
+    for line in shadows.lines().map(str::trim) {
+        // "Contained 1 shadow copies at creation time: 5/1/2024 10:15:00 AM"
+        if let Some((_, time)) = line.split_once("creation time:") {
+            created = Some(time.trim().to_string());
+        }
+        if let Some(id) = line.strip_prefix("Shadow Copy ID:") {
+            let id = id.trim().to_string();
+            items.push(SnapshotItem {
+                name: format!("Restore point {}", id),
+                id,
+                created: created.take(),
+                size: None,
+            });
+        }
+    }
+    let actions = match &volume {
+        // Keep the newest restore point
+        Some(volume) if items.len() > 1 => vec![ReclaimAction::DeleteOldestRestorePoint { volume: volume.clone() }],
+        _ => Vec::new(),
+    };
+    categories.push(SnapshotCategory {
+        id: "system-restore".to_string(),
+        name: "System Restore points".to_string(),
+        description: "Shadow copies Windows keeps to undo system changes. Their storage is reserved on the volume and hidden from Explorer.".to_string(),
+        total_size: used,
+        items,
+        actions,
+    });
+
+    // File History keeps versions on the backup drive; only its local
+    // configuration and staging cache live on this PC
+    if let Some(local) = dirs::data_local_dir().map(|d| d.join("Microsoft").join("Windows").join("FileHistory")) {
+        if local.exists() {
+            let size = walkdir::WalkDir::new(&local)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter_map(|e| e.metadata().ok())
+                .filter(|m| m.is_file())
+                .map(|m| m.len())
+                .sum();
+            categories.push(SnapshotCategory {
+                id: "file-history".to_string(),
+                name: "File History".to_string(),
+                description: "Versions of your files kept by File History. Cleaning up removes versions older than the chosen age.".to_string(),
+                total_size: Some(size),
+                items: Vec::new(),
+                actions: vec![ReclaimAction::CleanupFileHistory { older_than_days: 365 }],
+            });
+        }
+    }
+    Ok(categories)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+async fn platform_report() -> Result<Vec<SnapshotCategory>, String> {
+    Ok(Vec::new())
+}
+
+/// Local snapshot categories of this machine (empty where not applicable)
+pub async fn report() -> Result<Vec<SnapshotCategory>, String> {
+    platform_report().await
+}
+
+/// Perform a reclaim action offered by `report`
+pub async fn reclaim(action: &ReclaimAction) -> Result<(), String> {
+    info!("Reclaiming snapshot space: {:?}", action);
+    match action {
+        ReclaimAction::DeleteLocalSnapshot { id } if cfg!(target_os = "macos") => {
+            // The ID is the snapshot date; refuse anything else
+            if !id.chars().all(|c| c.is_ascii_digit() || c == '-') {
+                return Err(format!("Invalid snapshot ID: {}", id));
+            }
+            run("tmutil", &["deletelocalsnapshots", id]).await.map(|_| ())
+        }
+        // Ask for more than any disk holds so every thinnable snapshot goes
+        ReclaimAction::ThinLocalSnapshots if cfg!(target_os = "macos") => {
+            run("tmutil", &["thinlocalsnapshots", "/", "999999999999999", "4"]).await.map(|_| ())
+        }
+        ReclaimAction::DeleteOldestRestorePoint { volume } if cfg!(target_os = "windows") => {
+            let volume = format!("/for={}", volume);
+            run("vssadmin", &["delete", "shadows", &volume, "/oldest", "/quiet"]).await.map(|_| ())
+        }
+        ReclaimAction::CleanupFileHistory { older_than_days } if cfg!(target_os = "windows") => {
+            let days = older_than_days.to_string();
+            run("FhManagerw.exe", &["-cleanup", &days, "-quiet"]).await.map(|_| ())
+        }
+        _ => Err("This action is not available on this platform".to_string()),
+    }
+}
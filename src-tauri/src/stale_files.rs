@@ -0,0 +1,169 @@
+// Stale Files - Files nobody has changed or opened in a long time
+//
+// A download from three years ago or a VM image last booted for one project
+// can take more space than everything touched this year. This walks a folder
+// for files neither modified nor accessed for a number of days and groups
+// them by the folder they are in, largest group first, so a forgotten
+// Downloads or VMs folder stands out as one entry.
+//
+// A file's last use is the later of its modification and access times.
+// Access times are only as good as the file system keeps them: with
+// `noatime` mounts, or last-access updates turned off on NTFS, reads are not
+// recorded, and a file that was only read recently can still be listed.
+// The report is a list to review, not to delete from unseen.
+
+use crate::paths;
+use crate::scanner::Exclusions;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
+use tracing::info;
+use walkdir::WalkDir;
+
+/// Files listed per folder; the group's totals include the rest
+const FILES_PER_GROUP: usize = 50;
+/// Folders listed; the report's totals include the rest
+const MAX_GROUPS: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleOptions {
+    /// Files unused for at least this many days are stale
+    #[serde(default = "default_days")]
+    pub days: u64,
+    /// Smaller files are left out
+    #[serde(default)]
+    pub min_size: u64,
+    /// Count opening a file as using it, not only changing it
+    #[serde(default = "default_use_access_time")]
+    pub use_access_time: bool,
+}
+
+fn default_days() -> u64 {
+    365
+}
+
+fn default_use_access_time() -> bool {
+    true
+}
+
+impl Default for StaleOptions {
+    fn default() -> Self {
+        Self {
+            days: default_days(),
+            min_size: 0,
+            use_access_time: default_use_access_time(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleFile {
+    pub path: String,
+    pub size: u64,
+    /// Unix timestamps in seconds
+    pub last_modified: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_accessed: Option<u64>,
+}
+
+/// Stale files directly in one folder
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleGroup {
+    pub directory: String,
+    pub size: u64,
+    pub file_count: u64,
+    /// When any of its stale files was last used (Unix seconds)
+    pub last_used: u64,
+    /// Largest first, top entries only
+    pub files: Vec<StaleFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleReport {
+    pub path: String,
+    pub options: StaleOptions,
+    pub total_size: u64,
+    pub file_count: u64,
+    /// Largest first, top entries only
+    pub groups: Vec<StaleGroup>,
+}
+
+fn secs(time: std::io::Result<SystemTime>) -> Option<u64> {
+    time.ok()?.duration_since(SystemTime::UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+/// Files under `path` not used for `options.days`, grouped by folder
+pub fn find_stale(path: &str, options: StaleOptions, exclusions: &Exclusions, cancel: &AtomicBool) -> Result<StaleReport, String> {
+    let root = paths::extended(Path::new(path));
+    if !root.is_dir() {
+        return Err("Directory does not exist".to_string());
+    }
+    let now = secs(Ok(SystemTime::now())).unwrap_or(0);
+    let cutoff = now.saturating_sub(options.days.saturating_mul(86_400));
+
+    let mut groups: HashMap<String, StaleGroup> = HashMap::new();
+    let walker = WalkDir::new(&root).into_iter().filter_entry(|e| !exclusions.is_excluded(e.path()));
+    for entry in walker.filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".to_string());
+        }
+        let Ok(meta) = entry.metadata() else { continue };
+        if meta.len() < options.min_size {
+            continue;
+        }
+        let last_modified = secs(meta.modified()).unwrap_or(0);
+        let last_accessed = options.use_access_time.then(|| secs(meta.accessed())).flatten();
+        let last_used = last_modified.max(last_accessed.unwrap_or(0));
+        if last_used >= cutoff {
+            continue;
+        }
+
+        let directory = entry.path().parent().map(paths::display).unwrap_or_default();
+        let group = groups.entry(directory.clone()).or_insert_with(|| StaleGroup {
+            directory,
+            size: 0,
+            file_count: 0,
+            last_used: 0,
+            files: Vec::new(),
+        });
+        group.size += meta.len();
+        group.file_count += 1;
+        group.last_used = group.last_used.max(last_used);
+        group.files.push(StaleFile {
+            path: paths::display(entry.path()),
+            size: meta.len(),
+            last_modified,
+            last_accessed,
+        });
+        // Keep the per-folder list small
+        if group.files.len() > FILES_PER_GROUP * 2 {
+            group.files.sort_by(|a, b| b.size.cmp(&a.size));
+            group.files.truncate(FILES_PER_GROUP);
+        }
+    }
+
+    let mut groups: Vec<StaleGroup> = groups.into_values().collect();
+    for group in &mut groups {
+        group.files.sort_by(|a, b| b.size.cmp(&a.size));
+        group.files.truncate(FILES_PER_GROUP);
+    }
+    groups.sort_by(|a, b| b.size.cmp(&a.size));
+    let total_size = groups.iter().map(|g| g.size).sum();
+    let file_count = groups.iter().map(|g| g.file_count).sum();
+    groups.truncate(MAX_GROUPS);
+    info!("Found {} stale files in {} folders under {}", file_count, groups.len(), path);
+
+    Ok(StaleReport {
+        path: paths::display(&root),
+        options,
+        total_size,
+        file_count,
+        groups,
+    })
+}
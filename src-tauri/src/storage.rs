@@ -0,0 +1,193 @@
+// Storage - Crash-resilient JSON persistence for application state
+//
+// Small helpers shared by the stores that keep user data (prompt presets,
+// conversations, settings) under the app data directory. Documents are
+// written to a temporary file and renamed over the old one, so a crash
+// mid-write leaves either the old or the new version, never a torn file. The
+// previous version is kept as a backup, every document carries a schema
+// version, and a corrupt document is moved aside (falling back to the backup)
+// instead of preventing Helium from starting. A document written by a newer
+// version is an error and is left untouched, so a downgrade can't lose it.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+use tracing::warn;
+
+/// Schema version written into every document
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope<'a, T> {
+    schema_version: u32,
+    data: &'a T,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredEnvelope {
+    schema_version: u32,
+    data: Value,
+}
+
+/// Resolve the app data directory, creating it if needed
+pub fn app_data_dir<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+/// `<path>.<suffix>` next to the document
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Why a stored document could not be used
+enum ParseError {
+    /// Written by a newer version of Helium
+    Newer(String),
+    Corrupt(String),
+}
+
+/// Parse a document, unwrapping the versioned envelope. Documents written
+/// before versioning are bare values and are read as-is.
+fn parse<T: DeserializeOwned>(content: &str) -> Result<T, ParseError> {
+    let value: Value = serde_json::from_str(content).map_err(|e| ParseError::Corrupt(e.to_string()))?;
+    let data = match serde_json::from_value::<StoredEnvelope>(value.clone()) {
+        Ok(envelope) if envelope.schema_version > SCHEMA_VERSION => {
+            return Err(ParseError::Newer(format!(
+                "written by a newer version of Helium (schema {}, supported {})",
+                envelope.schema_version, SCHEMA_VERSION
+            )));
+        }
+        Ok(envelope) => envelope.data,
+        Err(_) => value,
+    };
+    serde_json::from_value(data).map_err(|e| ParseError::Corrupt(e.to_string()))
+}
+
+fn read<T: DeserializeOwned>(path: &Path) -> Option<Result<T, ParseError>> {
+    let content = std::fs::read_to_string(path).ok()?;
+    Some(parse(&content))
+}
+
+/// Load a JSON document, returning None if it is missing or unreadable.
+///
+/// A document that fails to parse is moved aside as `<name>.corrupt-<time>`
+/// and the backup of the previous version is used instead, if there is one.
+/// A document written by a newer version is an error; it stays in place and
+/// `save_json` won't overwrite it.
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, String> {
+    match read(path) {
+        None => Ok(None),
+        Some(Ok(value)) => Ok(Some(value)),
+        Some(Err(ParseError::Newer(e))) => {
+            warn!("Not loading {}: {}", path.display(), e);
+            Err(format!("{} was {}", path.display(), e))
+        }
+        Some(Err(ParseError::Corrupt(e))) => {
+            warn!("Failed to parse {}: {}", path.display(), e);
+            let quarantine = sibling(path, &format!("corrupt-{}", chrono::Utc::now().timestamp()));
+            if let Err(e) = std::fs::rename(path, &quarantine) {
+                warn!("Could not move {} aside: {}", path.display(), e);
+            }
+
+            match read(&sibling(path, "bak")) {
+                Some(Ok(value)) => {
+                    warn!("Recovered {} from its backup", path.display());
+                    Ok(Some(value))
+                }
+                _ => Ok(None),
+            }
+        }
+    }
+}
+
+/// Write a JSON document atomically, creating parent directories as needed.
+/// The previous version is kept as `<name>.bak`. A document written by a newer
+/// version is never overwritten.
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(Err(ParseError::Newer(e))) = read::<Value>(path) {
+        return Err(format!("Not overwriting {}: it was {}", path.display(), e));
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(&Envelope {
+        schema_version: SCHEMA_VERSION,
+        data: value,
+    })
+    .map_err(|e| e.to_string())?;
+
+    let tmp = sibling(path, "tmp");
+    let mut file = std::fs::File::create(&tmp).map_err(|e| e.to_string())?;
+    file.write_all(content.as_bytes()).map_err(|e| e.to_string())?;
+    file.sync_all().map_err(|e| e.to_string())?;
+    drop(file);
+
+    // Keep the last good version only if it still parses
+    if read::<Value>(path).is_some_and(|r| r.is_ok()) {
+        let _ = std::fs::copy(path, sibling(path, "bak"));
+    }
+    std::fs::rename(&tmp, path).map_err(|e| e.to_string())?;
+
+    // Make the rename itself durable
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = std::fs::File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+/// Remove a document together with its backup and temporary file
+pub fn remove_json(path: &Path) -> Result<(), String> {
+    for file in [sibling(path, "tmp"), sibling(path, "bak")] {
+        let _ = std::fs::remove_file(file);
+    }
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_newer_document_is_kept_and_not_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        save_json(&path, &json!({ "old": true })).unwrap();
+        let newer = json!({ "schemaVersion": SCHEMA_VERSION + 1, "data": { "new": true } });
+        std::fs::write(&path, newer.to_string()).unwrap();
+
+        let loaded = load_json::<Value>(&path);
+        assert!(loaded.unwrap_err().contains("newer version"));
+        // Neither moved aside nor replaced by the backup
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), newer.to_string());
+        assert!(save_json(&path, &json!({ "old": true })).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), newer.to_string());
+    }
+
+    #[test]
+    fn test_corrupt_document_falls_back_to_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        save_json(&path, &json!({ "version": 1 })).unwrap();
+        save_json(&path, &json!({ "version": 2 })).unwrap();
+        std::fs::write(&path, "{ not json").unwrap();
+
+        assert_eq!(load_json::<Value>(&path).unwrap(), Some(json!({ "version": 1 })));
+        assert!(!path.exists());
+    }
+}
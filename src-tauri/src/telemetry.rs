@@ -0,0 +1,259 @@
+// Telemetry - Opt-in anonymous usage and performance metrics
+//
+// Off by default. Once the user opts in, Helium counts which features are
+// used and records coarse performance samples (scan duration by file-count
+// bucket, generation speed by provider). Nothing identifying is kept: no
+// paths, file names, prompts or model IDs, and file counts are rounded to a
+// power of ten. Data is queued locally, can be inspected in full before it
+// leaves the machine, and is uploaded in batches. Opting out deletes the
+// queue.
+
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Collector endpoint, set for release builds
+const ENDPOINT: Option<&str> = option_env!("HELIUM_TELEMETRY_URL");
+
+/// Samples kept locally before the oldest are dropped
+const MAX_SAMPLES: usize = 1000;
+
+/// How often queued data is uploaded
+pub const UPLOAD_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase", tag = "kind")]
+pub enum PerformanceSample {
+    /// A directory scan; `file_bucket` is the file count rounded down to a power of ten
+    Scan { file_bucket: u64, duration_ms: u64 },
+    /// A generation's output speed
+    Generation { provider: String, tokens_per_second: f32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimedSample {
+    /// Unix timestamp truncated to the hour
+    pub hour: i64,
+    #[serde(flatten)]
+    pub sample: PerformanceSample,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TelemetryData {
+    #[serde(default)]
+    enabled: bool,
+    /// Random ID generated on opt-in, replaced on every opt-in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    install_id: Option<String>,
+    #[serde(default)]
+    feature_counts: BTreeMap<String, u64>,
+    #[serde(default)]
+    samples: Vec<TimedSample>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_upload: Option<i64>,
+}
+
+/// Everything Helium would send, for the local viewer
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TelemetryState {
+    pub enabled: bool,
+    /// Whether this build has somewhere to upload to
+    pub upload_configured: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_upload: Option<i64>,
+    pub pending: TelemetryBatch,
+}
+
+/// One upload
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TelemetryBatch {
+    pub install_id: String,
+    pub app_version: String,
+    pub os: String,
+    pub feature_counts: BTreeMap<String, u64>,
+    pub samples: Vec<TimedSample>,
+}
+
+/// Telemetry queue persisted in the app data directory
+#[derive(Clone)]
+pub struct Telemetry {
+    path: PathBuf,
+    data: Arc<Mutex<TelemetryData>>,
+}
+
+fn current_hour() -> i64 {
+    let now = chrono::Utc::now().timestamp();
+    now - now % 3600
+}
+
+/// Round a count down to a power of ten (0, 1, 10, 100, ...)
+fn bucket(count: u64) -> u64 {
+    if count == 0 {
+        0
+    } else {
+        10u64.pow(count.ilog10())
+    }
+}
+
+impl Telemetry {
+    pub fn load(path: PathBuf) -> Self {
+        let data = storage::load_json(&path).ok().flatten().unwrap_or_default();
+        Self {
+            path,
+            data: Arc::new(Mutex::new(data)),
+        }
+    }
+
+    fn save(&self, data: &TelemetryData) {
+        if let Err(e) = storage::save_json(&self.path, data) {
+            warn!("Failed to save telemetry: {}", e);
+        }
+    }
+
+    /// Change a recorded value if the user opted in
+    fn record(&self, change: impl FnOnce(&mut TelemetryData)) {
+        let mut data = self.data.lock().unwrap();
+        if !data.enabled {
+            return;
+        }
+        change(&mut data);
+        self.save(&data);
+    }
+
+    /// Count one use of a feature ("scan", "junk-clean", "inference:agent", ...)
+    pub fn feature_used(&self, feature: &str) {
+        self.record(|data| *data.feature_counts.entry(feature.to_string()).or_insert(0) += 1);
+    }
+
+    fn sample(&self, sample: PerformanceSample) {
+        self.record(|data| {
+            if data.samples.len() >= MAX_SAMPLES {
+                data.samples.remove(0);
+            }
+            data.samples.push(TimedSample {
+                hour: current_hour(),
+                sample,
+            });
+        });
+    }
+
+    pub fn scan_completed(&self, file_count: u64, duration: Duration) {
+        self.sample(PerformanceSample::Scan {
+            file_bucket: bucket(file_count),
+            duration_ms: duration.as_millis() as u64,
+        });
+    }
+
+    pub fn generation_completed(&self, provider: &str, completion_tokens: u32, duration: Duration) {
+        let secs = duration.as_secs_f32();
+        if completion_tokens == 0 || secs <= 0.0 {
+            return;
+        }
+        self.sample(PerformanceSample::Generation {
+            provider: provider.to_string(),
+            tokens_per_second: completion_tokens as f32 / secs,
+        });
+    }
+
+    /// Opt in or out. Opting out deletes everything queued.
+    pub fn set_enabled(&self, enabled: bool) {
+        let mut data = self.data.lock().unwrap();
+        if enabled && !data.enabled {
+            data.install_id = Some(uuid::Uuid::new_v4().to_string());
+        }
+        if !enabled {
+            *data = TelemetryData::default();
+        }
+        data.enabled = enabled;
+        self.save(&data);
+    }
+
+    fn batch(data: &TelemetryData) -> TelemetryBatch {
+        TelemetryBatch {
+            install_id: data.install_id.clone().unwrap_or_default(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            feature_counts: data.feature_counts.clone(),
+            samples: data.samples.clone(),
+        }
+    }
+
+    pub fn state(&self) -> TelemetryState {
+        let data = self.data.lock().unwrap();
+        TelemetryState {
+            enabled: data.enabled,
+            upload_configured: ENDPOINT.is_some(),
+            last_upload: data.last_upload,
+            pending: Self::batch(&data),
+        }
+    }
+
+    /// Upload the queue and clear what was sent. Returns false if there was
+    /// nothing to send.
+    pub async fn upload(&self) -> Result<bool, String> {
+        let endpoint = ENDPOINT.ok_or_else(|| "This build has no telemetry endpoint".to_string())?;
+        let (batch, sent_samples) = {
+            let data = self.data.lock().unwrap();
+            if !data.enabled {
+                return Err("Telemetry is disabled".to_string());
+            }
+            if data.feature_counts.is_empty() && data.samples.is_empty() {
+                return Ok(false);
+            }
+            (Self::batch(&data), data.samples.len())
+        };
+
+        let response = reqwest::Client::new()
+            .post(endpoint)
+            .json(&batch)
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Telemetry upload failed: {}", response.status()));
+        }
+
+        // Keep whatever was recorded while the upload was in flight
+        let mut data = self.data.lock().unwrap();
+        for (feature, count) in &batch.feature_counts {
+            if let Some(current) = data.feature_counts.get_mut(feature) {
+                *current = current.saturating_sub(*count);
+            }
+        }
+        data.feature_counts.retain(|_, count| *count > 0);
+        let sent = sent_samples.min(data.samples.len());
+        data.samples.drain(..sent);
+        data.last_upload = Some(chrono::Utc::now().timestamp());
+        self.save(&data);
+        info!("Uploaded telemetry ({} samples)", sent);
+        Ok(true)
+    }
+
+    /// Upload periodically while the app runs
+    pub fn spawn_uploader(&self) {
+        if ENDPOINT.is_none() {
+            return;
+        }
+        let telemetry = self.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(UPLOAD_INTERVAL).await;
+                if !telemetry.data.lock().unwrap().enabled {
+                    continue;
+                }
+                if let Err(e) = telemetry.upload().await {
+                    warn!("{}", e);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,429 @@
+// Transactions - All-or-nothing multi-step file operations
+//
+// A batch of moves, renames and deletions (e.g. an AI organization plan) that
+// stops halfway leaves a folder structure nobody asked for. A transaction is
+// validated up front, then run step by step against a journal on disk: the
+// undo of each step is recorded before the step runs, so when a step fails,
+// the user cancels or the app dies mid-way, the steps already applied are
+// undone in reverse order (on the next start, in the crash case).
+//
+// Deletions go to the trash, but only once every step has succeeded: until
+// then the item is parked in a hidden `.helium-txn-<id>` folder next to it,
+// which is a plain rename and just as easy to undo. Items to quarantine are
+// parked the same way and handed back to the caller on commit.
+
+use crate::paths;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{error, info, warn};
+
+/// One step of a transaction
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransactionStep {
+    /// Nothing to do if the folder already exists
+    CreateDirectory { path: String },
+    /// Missing parent folders of `to` are created
+    Move { from: String, to: String },
+    Rename { path: String, new_name: String },
+    Trash { path: String },
+    /// Like `Trash`, but the item is returned in `TransactionResult::held`
+    /// for the caller to quarantine
+    Quarantine { path: String },
+}
+
+/// A parked item the caller takes over once the transaction committed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeldItem {
+    pub original: String,
+    pub parked: String,
+}
+
+/// How to revert an applied step; undoing a step that never ran is a no-op
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Undo {
+    RemoveDirectory { path: String },
+    MoveBack { from: String, to: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum JournalState {
+    /// Steps are being applied; roll back if found on startup
+    Running,
+    /// Every step applied; parked items are being trashed
+    Committing,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Journal {
+    id: String,
+    created_at: i64,
+    state: JournalState,
+    steps: Vec<TransactionStep>,
+    /// Undo records in the order they were taken
+    undo: Vec<Undo>,
+    /// Items parked for the trash
+    parked: Vec<String>,
+    /// Items parked for the caller
+    #[serde(default)]
+    held: Vec<HeldItem>,
+}
+
+/// Outcome of a transaction
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionResult {
+    pub id: String,
+    pub committed: bool,
+    /// Steps applied before the transaction committed or was rolled back
+    pub completed: usize,
+    /// Index of the step that failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed_step: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Items that could not be moved to the trash after committing; they
+    /// stay in their `.helium-txn-*` folder
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trash_failures: Vec<String>,
+    /// Items of `Quarantine` steps, parked until the caller moves them on
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub held: Vec<HeldItem>,
+}
+
+fn is_protected(path: &Path) -> bool {
+    paths::is_root_or_home(path)
+}
+
+fn exists(path: &Path) -> bool {
+    std::fs::symlink_metadata(paths::extended(path)).is_ok()
+}
+
+/// Check every step against the file system as the earlier steps will have
+/// left it
+pub fn validate(steps: &[TransactionStep]) -> Result<(), String> {
+    let mut appeared: HashSet<String> = HashSet::new();
+    let mut gone: HashSet<String> = HashSet::new();
+    let present = |path: &str, appeared: &HashSet<String>, gone: &HashSet<String>| {
+        let key = paths::key(path);
+        appeared.contains(&key)
+            || (!gone.iter().any(|g| paths::is_within(Path::new(&key), Path::new(g))) && exists(Path::new(path)))
+    };
+
+    for (index, step) in steps.iter().enumerate() {
+        let fail = |message: String| Err(format!("Step {}: {}", index + 1, message));
+        let (source, target) = match step {
+            TransactionStep::CreateDirectory { path } if present(path, &appeared, &gone) => {
+                if !appeared.contains(&paths::key(path)) && !Path::new(path).is_dir() {
+                    return fail(format!("Not a folder: {}", path));
+                }
+                continue;
+            }
+            TransactionStep::CreateDirectory { path } => (None, path.clone()),
+            TransactionStep::Move { from, to } => (Some(from.clone()), to.clone()),
+            TransactionStep::Rename { path, new_name } => {
+                if new_name.is_empty() || new_name.contains(['/', '\\']) || new_name == "." || new_name == ".." {
+                    return fail(format!("Invalid name: {}", new_name));
+                }
+                let target = Path::new(path).with_file_name(new_name);
+                (Some(path.clone()), target.to_string_lossy().to_string())
+            }
+            TransactionStep::Trash { path } | TransactionStep::Quarantine { path } => {
+                (Some(path.clone()), String::new())
+            }
+        };
+
+        if let Some(source) = &source {
+            if !Path::new(source).is_absolute() {
+                return fail(format!("Not an absolute path: {}", source));
+            }
+            if is_protected(Path::new(source)) {
+                return fail(format!("Refusing to touch {}", source));
+            }
+            if !present(source, &appeared, &gone) {
+                return fail(format!("Does not exist: {}", source));
+            }
+        }
+        if !target.is_empty() {
+            if !Path::new(&target).is_absolute() {
+                return fail(format!("Not an absolute path: {}", target));
+            }
+            if present(&target, &appeared, &gone) {
+                return fail(format!("Already exists: {}", target));
+            }
+            if let Some(source) = &source {
+                if paths::is_within(Path::new(&target), Path::new(source)) {
+                    return fail(format!("Cannot move {} into itself", source));
+                }
+            }
+        }
+
+        if let Some(source) = source {
+            let key = paths::key(&source);
+            appeared.retain(|a| !paths::is_within(Path::new(a), Path::new(&key)));
+            gone.insert(key);
+        }
+        if !target.is_empty() {
+            let key = paths::key(&target);
+            gone.remove(&key);
+            appeared.insert(key);
+        }
+    }
+    Ok(())
+}
+
+struct Runner<'a> {
+    journal: Journal,
+    journal_path: PathBuf,
+    cancel: &'a AtomicBool,
+}
+
+impl Runner<'_> {
+    fn save(&self) -> Result<(), String> {
+        storage::save_json(&self.journal_path, &self.journal)
+    }
+
+    /// Record an undo before the change it reverts
+    fn record(&mut self, undo: Undo) -> Result<(), String> {
+        self.journal.undo.push(undo);
+        self.save()
+    }
+
+    fn create_dir(&mut self, path: &Path) -> Result<(), String> {
+        self.record(Undo::RemoveDirectory {
+            path: paths::display(path),
+        })?;
+        std::fs::create_dir(paths::extended(path)).map_err(|e| format!("Cannot create {}: {}", path.display(), e))
+    }
+
+    /// Create the missing ancestors of `path`, outermost first
+    fn create_parents(&mut self, path: &Path) -> Result<(), String> {
+        let missing: Vec<&Path> = path.ancestors().skip(1).take_while(|p| !exists(p)).collect();
+        for dir in missing.into_iter().rev() {
+            self.create_dir(dir)?;
+        }
+        Ok(())
+    }
+
+    fn move_path(&mut self, from: &Path, to: &Path) -> Result<(), String> {
+        self.record(Undo::MoveBack {
+            from: paths::display(to),
+            to: paths::display(from),
+        })?;
+        std::fs::rename(paths::extended(from), paths::extended(to))
+            .map_err(|e| format!("Cannot move {} to {}: {}", from.display(), to.display(), e))
+    }
+
+    fn apply(&mut self, step: &TransactionStep) -> Result<(), String> {
+        match step {
+            TransactionStep::CreateDirectory { path } => {
+                let path = Path::new(path);
+                if exists(path) {
+                    return Ok(());
+                }
+                self.create_parents(path)?;
+                self.create_dir(path)
+            }
+            TransactionStep::Move { from, to } => {
+                self.create_parents(Path::new(to))?;
+                self.move_path(Path::new(from), Path::new(to))
+            }
+            TransactionStep::Rename { path, new_name } => {
+                let path = Path::new(path);
+                self.move_path(path, &path.with_file_name(new_name))
+            }
+            TransactionStep::Trash { path } => {
+                let parked = self.park(Path::new(path))?;
+                self.journal.parked.push(parked);
+                self.save()
+            }
+            TransactionStep::Quarantine { path } => {
+                let parked = self.park(Path::new(path))?;
+                self.journal.held.push(HeldItem {
+                    original: path.clone(),
+                    parked,
+                });
+                self.save()
+            }
+        }
+    }
+
+    /// Move `path` into the holding folder next to it
+    fn park(&mut self, path: &Path) -> Result<String, String> {
+        let parent = path.parent().ok_or_else(|| format!("Refusing to delete {}", path.display()))?;
+        let holding = parent.join(format!(".helium-txn-{}", self.journal.id));
+        if !exists(&holding) {
+            self.create_dir(&holding)?;
+        }
+        let parked = holding.join(path.file_name().unwrap_or_default());
+        self.move_path(path, &parked)?;
+        Ok(paths::display(&parked))
+    }
+}
+
+/// Undo recorded changes in reverse order. Errors are logged and the rest
+/// is still undone.
+fn rollback(journal: &Journal) {
+    for undo in journal.undo.iter().rev() {
+        let outcome = match undo {
+            Undo::RemoveDirectory { path } => {
+                let path = paths::extended(Path::new(path));
+                if path.exists() { std::fs::remove_dir(&path) } else { Ok(()) }
+            }
+            Undo::MoveBack { from, to } => {
+                let (from, to) = (paths::extended(Path::new(from)), paths::extended(Path::new(to)));
+                if exists(&from) && !exists(&to) { std::fs::rename(&from, &to) } else { Ok(()) }
+            }
+        };
+        if let Err(e) = outcome {
+            error!("Rollback of transaction {} could not undo {:?}: {}", journal.id, undo, e);
+        }
+    }
+    info!("Transaction {} rolled back", journal.id);
+}
+
+/// Move parked items to the trash and remove the holding folders
+fn finish_commit(journal: &Journal) -> Vec<String> {
+    let mut failures = Vec::new();
+    for item in &journal.parked {
+        if let Err(e) = trash::delete(item) {
+            warn!("Could not move {} to the trash: {}", item, e);
+            failures.push(item.clone());
+        }
+    }
+    let holding_dirs: HashSet<PathBuf> = journal
+        .parked
+        .iter()
+        .filter_map(|p| Path::new(p).parent().map(Path::to_path_buf))
+        .collect();
+    for dir in holding_dirs {
+        // Only empty ones; a failed trash leaves the item inside
+        let _ = std::fs::remove_dir(paths::extended(&dir));
+    }
+    failures
+}
+
+/// Remove the holding folder of `item` once the caller moved it out
+pub fn release(item: &HeldItem) {
+    if let Some(holding) = Path::new(&item.parked).parent() {
+        let _ = std::fs::remove_dir(paths::extended(holding));
+    }
+}
+
+/// Validate and run `steps` as one transaction, journaled in `journal_dir`.
+/// Any failure, or `cancel`, rolls back what was applied.
+pub fn run(steps: Vec<TransactionStep>, journal_dir: &Path, cancel: &AtomicBool) -> Result<TransactionResult, String> {
+    validate(&steps)?;
+    std::fs::create_dir_all(journal_dir).map_err(|e| e.to_string())?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let mut runner = Runner {
+        journal_path: journal_dir.join(format!("{}.json", id)),
+        journal: Journal {
+            id: id.clone(),
+            created_at: chrono::Utc::now().timestamp(),
+            state: JournalState::Running,
+            steps: steps.clone(),
+            undo: Vec::new(),
+            parked: Vec::new(),
+            held: Vec::new(),
+        },
+        cancel,
+    };
+    runner.save()?;
+
+    let mut result = TransactionResult {
+        id,
+        committed: false,
+        completed: 0,
+        failed_step: None,
+        error: None,
+        trash_failures: Vec::new(),
+        held: Vec::new(),
+    };
+    for (index, step) in steps.iter().enumerate() {
+        let outcome = if runner.cancel.load(Ordering::Relaxed) {
+            Err("Cancelled".to_string())
+        } else {
+            runner.apply(step)
+        };
+        if let Err(e) = outcome {
+            warn!("Transaction {} failed at step {}: {}", result.id, index + 1, e);
+            rollback(&runner.journal);
+            let _ = std::fs::remove_file(&runner.journal_path);
+            result.failed_step = Some(index);
+            result.error = Some(e);
+            return Ok(result);
+        }
+        result.completed += 1;
+    }
+
+    runner.journal.state = JournalState::Committing;
+    runner.save()?;
+    result.trash_failures = finish_commit(&runner.journal);
+    result.held = runner.journal.held.clone();
+    result.committed = true;
+    let _ = std::fs::remove_file(&runner.journal_path);
+    info!("Transaction {} committed {} steps", result.id, result.completed);
+    Ok(result)
+}
+
+/// Finish transactions interrupted by a crash: roll back those still
+/// running, complete those that were committing
+pub fn recover(journal_dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(journal_dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !path.extension().is_some_and(|e| e == "json") {
+            continue;
+        }
+        let journal: Journal = match storage::load_json(&path) {
+            Ok(Some(journal)) => journal,
+            _ => continue,
+        };
+        warn!("Recovering interrupted transaction {}", journal.id);
+        match journal.state {
+            JournalState::Running => rollback(&journal),
+            JournalState::Committing => {
+                finish_commit(&journal);
+                // Nobody is left to quarantine these; they stay parked
+                for item in &journal.held {
+                    warn!("{} was left in {}", item.original, item.parked);
+                }
+            }
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quarantine_step_hands_parked_item_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let item = dir.path().join("old.log");
+        std::fs::write(&item, "log").unwrap();
+        let original = paths::display(&item);
+
+        let steps = vec![TransactionStep::Quarantine { path: original.clone() }];
+        let result = run(steps, &dir.path().join("journal"), &AtomicBool::new(false)).unwrap();
+
+        assert!(result.committed);
+        assert!(!item.exists());
+        assert_eq!(result.held.len(), 1);
+        assert_eq!(result.held[0].original, original);
+        // Not trashed: still parked for the caller
+        assert_eq!(std::fs::read_to_string(&result.held[0].parked).unwrap(), "log");
+    }
+}
@@ -0,0 +1,121 @@
+// Treemap - A scanned tree as flat rows ready to draw
+//
+// A full scan of a large drive is a tree of hundreds of thousands of nodes;
+// sending it whole and walking it recursively in the webview stalls the UI
+// for seconds, and most of those nodes would be rectangles smaller than a
+// pixel anyway. Instead the cached tree is flattened here into rows of
+// `{path, parent, size, depth}`, expanded largest folder first until a row
+// budget is spent, with nodes below a minimum size left out: a folder's
+// rectangle is sized by its own total, so what is left out simply shows as
+// the unsplit part of its parent. Rows come parents first, so the frontend
+// can build the layout in one pass.
+
+use crate::scanner::FileNode;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Rows returned unless asked otherwise, and at most
+pub const DEFAULT_ROWS: usize = 20_000;
+pub const MAX_ROWS: usize = 200_000;
+/// Default minimum size as a fraction of the root's
+const DEFAULT_MIN_FRACTION: u64 = 10_000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreemapRow {
+    pub path: String,
+    pub name: String,
+    /// None for the root row
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    pub size: u64,
+    /// 0 for the root
+    pub depth: u32,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Treemap {
+    /// The root first; every folder before its children, which follow each
+    /// other largest first
+    pub rows: Vec<TreemapRow>,
+    /// Whether the row budget ran out before every node above the minimum
+    /// size was listed
+    pub truncated: bool,
+    /// Smallest size listed; smaller nodes are part of their parent's area
+    pub min_size: u64,
+}
+
+/// Limits of a flattened tree
+#[derive(Debug, Clone, Copy)]
+pub struct TreemapLimits {
+    pub max_rows: usize,
+    /// Levels below the root to expand
+    pub max_depth: u32,
+    /// Leave out nodes smaller than this; None for 1/10,000 of the root
+    pub min_size: Option<u64>,
+}
+
+/// A folder waiting to be expanded, ordered by size
+struct Pending<'a>(&'a FileNode, u32);
+
+impl PartialEq for Pending<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.size == other.0.size
+    }
+}
+
+impl Eq for Pending<'_> {}
+
+impl PartialOrd for Pending<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.size.cmp(&other.0.size)
+    }
+}
+
+fn row(node: &FileNode, parent: Option<&FileNode>, depth: u32) -> TreemapRow {
+    TreemapRow {
+        path: node.path.clone(),
+        name: node.name.clone(),
+        parent: parent.map(|p| p.path.clone()),
+        size: node.size,
+        depth,
+        is_dir: node.is_dir,
+    }
+}
+
+/// Flatten `root` into rows, largest folders expanded first
+pub fn flatten(root: &FileNode, limits: TreemapLimits) -> Treemap {
+    let min_size = limits.min_size.unwrap_or(root.size / DEFAULT_MIN_FRACTION).max(1);
+    let max_rows = limits.max_rows.clamp(1, MAX_ROWS);
+    let mut rows = vec![row(root, None, 0)];
+    let mut truncated = false;
+    let mut pending = BinaryHeap::from([Pending(root, 0)]);
+    'expand: while let Some(Pending(node, depth)) = pending.pop() {
+        if depth >= limits.max_depth {
+            continue;
+        }
+        let Some(children) = &node.children else { continue };
+        let mut children: Vec<&FileNode> = children.iter().filter(|c| c.size >= min_size).collect();
+        children.sort_by_key(|c| Reverse(c.size));
+        for child in children {
+            if rows.len() >= max_rows {
+                truncated = true;
+                break 'expand;
+            }
+            rows.push(row(child, Some(node), depth + 1));
+            if child.children.is_some() {
+                pending.push(Pending(child, depth + 1));
+            }
+        }
+    }
+    Treemap { rows, truncated, min_size }
+}
@@ -0,0 +1,102 @@
+// UI Actions - Lets the assistant drive the analyzer UI
+//
+// Describing where the big folders are is less useful than showing them. The
+// agent emits structured actions through the `ui_action` MCP tool: open a
+// folder in the explorer, highlight items in it, or open the cleaner's
+// preview of a junk category. Nothing comes from the model unchecked: paths
+// must exist and lie in the allowed directories, highlighted items must share
+// one folder (the one the explorer shows), and categories must be known to
+// the cleaner. Valid actions are re-emitted as one typed `ui-action` event
+// the frontend follows; invalid ones are returned to the agent as errors.
+
+use crate::junk_plugins::JunkPluginServer;
+use crate::paths;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Event carrying each validated action
+pub const UI_ACTION_EVENT: &str = "ui-action";
+
+/// More would be noise rather than a highlight
+const MAX_HIGHLIGHTED: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UIAction {
+    /// Open a folder in the explorer
+    NavigateTo { path: String },
+    /// Open the folder holding `paths` and select them
+    HighlightItems {
+        paths: Vec<String>,
+        /// Their common folder; filled in by validation
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        folder: Option<String>,
+    },
+    /// Open the cleaner on a junk category
+    OpenCleanPreview { category: String },
+}
+
+impl UIAction {
+    /// Paths the action touches, to check against the allowed directories
+    pub fn paths(&self) -> Vec<&str> {
+        match self {
+            UIAction::NavigateTo { path } => vec![path.as_str()],
+            UIAction::HighlightItems { paths, .. } => paths.iter().map(String::as_str).collect(),
+            UIAction::OpenCleanPreview { .. } => Vec::new(),
+        }
+    }
+
+    /// Check the action against the file system and the known junk
+    /// categories, returning it in the form the frontend expects
+    pub fn validate(self, plugins: &[JunkPluginServer]) -> Result<UIAction, String> {
+        match self {
+            UIAction::NavigateTo { path } => {
+                if !paths::extended(Path::new(&path)).is_dir() {
+                    return Err(format!("Not a folder: {}", path));
+                }
+                Ok(UIAction::NavigateTo {
+                    path: paths::display(Path::new(&path)),
+                })
+            }
+            UIAction::HighlightItems { paths: items, .. } => {
+                if items.is_empty() {
+                    return Err("Nothing to highlight".to_string());
+                }
+                if items.len() > MAX_HIGHLIGHTED {
+                    return Err(format!("At most {} items can be highlighted at once", MAX_HIGHLIGHTED));
+                }
+                let mut folder: Option<&Path> = None;
+                for item in &items {
+                    let path = Path::new(item);
+                    if !paths::extended(path).exists() {
+                        return Err(format!("Not found: {}", item));
+                    }
+                    let parent = path.parent().ok_or_else(|| format!("Cannot highlight a root: {}", item))?;
+                    match folder {
+                        Some(folder) if !paths::same(folder, parent) => {
+                            return Err("Highlighted items must be in the same folder".to_string());
+                        }
+                        Some(_) => {}
+                        None => folder = Some(parent),
+                    }
+                }
+                let folder = folder.map(paths::display);
+                Ok(UIAction::HighlightItems {
+                    paths: items.iter().map(|p| paths::display(Path::new(p))).collect(),
+                    folder,
+                })
+            }
+            UIAction::OpenCleanPreview { category } => {
+                let known = crate::cleaner::category_ids().contains(&category.as_str())
+                    || category
+                        .strip_prefix("plugin:")
+                        .and_then(|rest| rest.split_once(':'))
+                        .is_some_and(|(server, _)| plugins.iter().any(|p| p.enabled && p.id == server));
+                if !known {
+                    return Err(format!("Unknown junk category: {}", category));
+                }
+                Ok(UIAction::OpenCleanPreview { category })
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+// Updater - Release checks and self-update
+//
+// Checks the release feed for a newer build, downloads it with progress
+// events, verifies its signature against the public key built into the app
+// and installs it so the next launch runs the new version. Builds without an
+// updater key (HELIUM_UPDATER_PUBKEY at compile time or `plugins.updater.pubkey`
+// in tauri.conf.json) don't register the updater plugin, and checking for
+// updates says so. Release builds that ship updates also pass
+// `bundle.createUpdaterArtifacts` (with TAURI_SIGNING_PRIVATE_KEY set) as a
+// config override, so plain builds don't need the signing key.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_updater::{Update, UpdaterExt};
+use tracing::info;
+
+use crate::event_batch::EventBatcher;
+use crate::operations::{OperationKind, OperationManager};
+
+/// Event carrying download progress of an update
+pub const UPDATE_PROGRESS_EVENT: &str = "update-download-progress";
+
+const NOT_CONFIGURED: &str = "Updates are not configured for this build";
+
+/// Release found by the last check, kept until it is installed
+pub struct UpdateState {
+    /// Whether the updater plugin was registered with a public key
+    configured: bool,
+    pending: Mutex<Option<Update>>,
+}
+
+impl UpdateState {
+    pub fn new(configured: bool) -> Self {
+        Self { configured, pending: Mutex::new(None) }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Release date (RFC 3339)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProgress {
+    pub downloaded: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+}
+
+/// Key update signatures are verified with: HELIUM_UPDATER_PUBKEY at compile
+/// time, else `plugins.updater.pubkey` of `config`. None if neither is set.
+pub fn public_key(config: &tauri::Config) -> Option<String> {
+    let configured = config
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|key| key.as_str());
+    option_env!("HELIUM_UPDATER_PUBKEY")
+        .into_iter()
+        .chain(configured)
+        .find(|key| !key.trim().is_empty())
+        .map(str::to_string)
+}
+
+/// Ask the release feed for a newer version. None if up to date.
+pub async fn check(app: &AppHandle, state: &UpdateState) -> Result<Option<UpdateInfo>, String> {
+    if !state.configured {
+        return Err(NOT_CONFIGURED.to_string());
+    }
+    let update = app
+        .updater()
+        .map_err(|e| e.to_string())?
+        .check()
+        .await
+        .map_err(|e| format!("Update check failed: {}", e))?;
+
+    let info = update.as_ref().map(|update| UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        notes: update.body.clone(),
+        date: update.date.map(|d| d.to_string()),
+    });
+    if let Some(info) = &info {
+        info!("Update available: {} -> {}", info.current_version, info.version);
+    }
+    *state.pending.lock().unwrap() = update;
+    Ok(info)
+}
+
+/// Download, verify and install the update found by `check`
+pub async fn install(app: &AppHandle, state: &UpdateState, operations: &OperationManager) -> Result<(), String> {
+    if !state.configured {
+        return Err(NOT_CONFIGURED.to_string());
+    }
+    let update = state
+        .pending
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| "No update available; check for updates first".to_string())?;
+
+    let operation = operations.start_uncancellable(app, OperationKind::Download, update.version.clone());
+    let mut downloaded = 0u64;
+    let events = EventBatcher::latest(app.clone(), UPDATE_PROGRESS_EVENT);
+    let result = update
+        .download_and_install(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                operation.progress(total.map(|t| downloaded as f32 / t as f32), None);
+                events.send(UpdateProgress { downloaded, total });
+            },
+            || info!("Update downloaded and verified"),
+        )
+        .await
+        .map_err(|e| format!("Update failed: {}", e));
+    events.flush();
+    operation.finish(&result);
+    result?;
+
+    info!("Installed {}; applies on restart", update.version);
+    Ok(())
+}
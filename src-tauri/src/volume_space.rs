@@ -0,0 +1,177 @@
+// Volume Space - Free space as the OS file manager reports it
+//
+// On APFS the free space statfs reports leaves out purgeable space (local
+// Time Machine snapshots, iCloud files that can be evicted, caches the system
+// may clear), while Finder's "available" includes it, so Helium's drive
+// totals used to disagree with Finder's. This reads the "available for
+// important usage" capacity macOS itself uses for Finder, reports the
+// difference as purgeable, and breaks the APFS container down by volume role
+// (System, Data, VM, Preboot, ...): every volume of a container shares its
+// free space, so a volume's used figure alone doesn't explain the total.
+// Elsewhere the figures are the plain free space and nothing is purgeable.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApfsVolume {
+    pub name: String,
+    /// "System", "Data", "VM", "Preboot", "Recovery", ...; empty for user volumes
+    pub roles: Vec<String>,
+    pub used: u64,
+    pub device: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApfsContainer {
+    /// e.g. "disk3"
+    pub reference: String,
+    pub capacity: u64,
+    pub free: u64,
+    pub volumes: Vec<ApfsVolume>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeSpace {
+    pub total: u64,
+    /// Free right now, as statfs reports it
+    pub free: u64,
+    /// Free plus purgeable: Finder's "available"
+    pub available: u64,
+    /// Space the OS frees on demand
+    pub purgeable: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<ApfsContainer>,
+}
+
+impl VolumeSpace {
+    /// Used space as the OS file manager shows it
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use super::{ApfsContainer, ApfsVolume};
+    use serde_json::Value;
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    use tracing::debug;
+
+    /// Output of `diskutil <args> -plist`, converted to JSON by plutil
+    fn diskutil_json(args: &[&str]) -> Option<Value> {
+        let output = Command::new("diskutil").args(args).arg("-plist").output().ok()?;
+        if !output.status.success() {
+            debug!("diskutil {:?} failed", args);
+            return None;
+        }
+        let mut plutil = Command::new("plutil")
+            .args(["-convert", "json", "-o", "-", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+        plutil.stdin.take()?.write_all(&output.stdout).ok()?;
+        let converted = plutil.wait_with_output().ok()?;
+        serde_json::from_slice(&converted.stdout).ok()
+    }
+
+    /// Capacity Finder shows as available: free plus purgeable
+    pub fn important_usage_capacity(mount_point: &str) -> Option<u64> {
+        let script = format!(
+            "ObjC.import('Foundation');\
+             var key = 'NSURLVolumeAvailableCapacityForImportantUsageKey';\
+             var values = $.NSURL.fileURLWithPath({}).resourceValuesForKeysError([key], null);\
+             values.objectForKey(key).longLongValue",
+            serde_json::to_string(mount_point).ok()?
+        );
+        let output = Command::new("osascript").args(["-l", "JavaScript", "-e", &script]).output().ok()?;
+        if !output.status.success() {
+            debug!("Important-usage capacity of {} unavailable", mount_point);
+            return None;
+        }
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    }
+
+    /// All APFS containers with their volumes
+    pub fn containers() -> Vec<ApfsContainer> {
+        let Some(list) = diskutil_json(&["apfs", "list"]) else {
+            return Vec::new();
+        };
+        let containers = list["Containers"].as_array().cloned().unwrap_or_default();
+        containers
+            .iter()
+            .map(|c| ApfsContainer {
+                reference: c["ContainerReference"].as_str().unwrap_or_default().to_string(),
+                capacity: c["CapacityCeiling"].as_u64().unwrap_or(0),
+                free: c["CapacityFree"].as_u64().unwrap_or(0),
+                volumes: c["Volumes"]
+                    .as_array()
+                    .map(|volumes| {
+                        volumes
+                            .iter()
+                            .map(|v| ApfsVolume {
+                                name: v["Name"].as_str().unwrap_or_default().to_string(),
+                                roles: v["Roles"]
+                                    .as_array()
+                                    .map(|r| r.iter().filter_map(|r| r.as_str().map(str::to_string)).collect())
+                                    .unwrap_or_default(),
+                                used: v["CapacityInUse"].as_u64().unwrap_or(0),
+                                device: v["DeviceIdentifier"].as_str().unwrap_or_default().to_string(),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// APFS container reference ("disk3") of the volume mounted at `mount_point`
+    pub fn container_of(mount_point: &str) -> Option<String> {
+        let info = diskutil_json(&["info", mount_point])?;
+        info["APFSContainerReference"].as_str().map(str::to_string)
+    }
+}
+
+/// Reads the space of the volumes of one drive listing. The container list
+/// is read once and shared by the volumes.
+pub struct SpaceReader {
+    #[cfg(target_os = "macos")]
+    containers: Vec<ApfsContainer>,
+}
+
+impl SpaceReader {
+    pub fn load() -> Self {
+        Self {
+            #[cfg(target_os = "macos")]
+            containers: macos::containers(),
+        }
+    }
+
+    /// Space of the volume at `mount_point`; `total` and `free` come from
+    /// statfs (sysinfo)
+    #[cfg_attr(not(target_os = "macos"), allow(unused_variables, unused_mut))]
+    pub fn read(&self, mount_point: &str, file_system: &str, total: u64, free: u64) -> VolumeSpace {
+        let mut space = VolumeSpace {
+            total,
+            free,
+            available: free,
+            purgeable: 0,
+            container: None,
+        };
+        #[cfg(target_os = "macos")]
+        if file_system.eq_ignore_ascii_case("apfs") {
+            if let Some(available) = macos::important_usage_capacity(mount_point) {
+                // Finder never reports less than what's actually free
+                space.available = available.max(free);
+                space.purgeable = space.available - free;
+            }
+            space.container = macos::container_of(mount_point)
+                .and_then(|reference| self.containers.iter().find(|c| c.reference == reference).cloned());
+        }
+        space
+    }
+}
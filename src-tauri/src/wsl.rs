@@ -0,0 +1,276 @@
+// WSL - Windows Subsystem for Linux distro storage (Windows)
+//
+// A WSL 2 distro keeps its whole filesystem in one ext4.vhdx file, which a
+// normal scan shows as a single opaque multi-gigabyte blob. This module finds
+// the installed distros and their virtual disks, breaks a distro's usage down
+// by top-level folder by running `du` inside it, and compacts the virtual
+// disk so space freed inside Linux is returned to Windows. The vhdx never
+// shrinks on its own. On other platforms no distros are reported.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WslDistro {
+    pub name: String,
+    /// "Running", "Stopped", "Installing", ...
+    pub state: String,
+    /// WSL version (1 or 2); only version 2 uses a virtual disk
+    pub version: u8,
+    pub is_default: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vhdx_path: Option<String>,
+    /// Size of the virtual disk on the Windows drive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vhdx_size: Option<u64>,
+}
+
+/// Size of a top-level folder inside a distro
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WslFolderUsage {
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WslUsage {
+    pub distro: String,
+    /// Space used inside the Linux filesystem
+    pub used_bytes: u64,
+    /// Size of the virtual disk; the difference to `used_bytes` is what
+    /// compaction can reclaim
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vhdx_size: Option<u64>,
+    /// Largest first
+    pub folders: Vec<WslFolderUsage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactResult {
+    pub distro: String,
+    pub size_before: u64,
+    pub size_after: u64,
+    pub reclaimed: u64,
+}
+
+/// Parse `wsl --list --verbose` (the "*" marks the default distro)
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn parse_list(output: &str) -> Vec<(String, String, u8, bool)> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let is_default = line.trim_start().starts_with('*');
+            let mut fields = line.trim_start_matches([' ', '*']).split_whitespace();
+            let name = fields.next()?.to_string();
+            let state = fields.next()?.to_string();
+            let version = fields.next()?.parse().ok()?;
+            Some((name, state, version, is_default))
+        })
+        .collect()
+}
+
+/// Parse `du -k -d 1` output into (path, bytes), dropping the total line for `root`
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn parse_du(output: &str, root: &str) -> (u64, Vec<WslFolderUsage>) {
+    let mut total = 0;
+    let mut folders = Vec::new();
+    for line in output.lines() {
+        let Some((size, path)) = line.split_once('\t') else {
+            continue;
+        };
+        let Ok(kb) = size.trim().parse::<u64>() else {
+            continue;
+        };
+        if path == root {
+            total = kb * 1024;
+        } else {
+            folders.push(WslFolderUsage {
+                path: path.to_string(),
+                size: kb * 1024,
+            });
+        }
+    }
+    folders.sort_by(|a, b| b.size.cmp(&a.size));
+    (total, folders)
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use tokio::process::Command;
+    use tracing::info;
+
+    const LXSS_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Lxss";
+
+    /// Run `wsl.exe`, whose own messages are UTF-16LE
+    async fn wsl(args: &[&str]) -> Result<String, String> {
+        let output = Command::new("wsl.exe")
+            .args(args)
+            .output()
+            .await
+            .map_err(|e| format!("WSL is not installed: {}", e))?;
+        let text = if output.stdout.len() >= 2 && output.stdout[1] == 0 {
+            let units: Vec<u16> = output
+                .stdout
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        } else {
+            String::from_utf8_lossy(&output.stdout).to_string()
+        };
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("wsl {} failed: {}", args.join(" "), stderr.trim()));
+        }
+        Ok(text.replace('\0', ""))
+    }
+
+    /// Distro name -> install folder, from the registry
+    async fn base_paths() -> HashMap<String, PathBuf> {
+        let Ok(output) = Command::new("reg").args(["query", LXSS_KEY, "/s"]).output().await else {
+            return HashMap::new();
+        };
+        let text = String::from_utf8_lossy(&output.stdout);
+        let mut paths = HashMap::new();
+        let (mut name, mut base) = (None, None);
+        for line in text.lines() {
+            if line.starts_with("HKEY_") {
+                name = None;
+                base = None;
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next()) {
+                (Some("DistributionName"), Some("REG_SZ")) => name = Some(fields.collect::<Vec<_>>().join(" ")),
+                (Some("BasePath"), Some("REG_SZ")) => {
+                    let value = fields.collect::<Vec<_>>().join(" ");
+                    base = Some(PathBuf::from(value.trim_start_matches(r"\\?\")));
+                }
+                _ => {}
+            }
+            if let (Some(n), Some(b)) = (&name, &base) {
+                paths.insert(n.clone(), b.clone());
+            }
+        }
+        paths
+    }
+
+    pub async fn list_distros() -> Result<Vec<WslDistro>, String> {
+        let output = wsl(&["--list", "--verbose"]).await?;
+        let base_paths = base_paths().await;
+        Ok(parse_list(&output)
+            .into_iter()
+            .map(|(name, state, version, is_default)| {
+                let vhdx = base_paths
+                    .get(&name)
+                    .map(|base| base.join("ext4.vhdx"))
+                    .filter(|p| version == 2 && p.exists());
+                WslDistro {
+                    vhdx_size: vhdx.as_ref().and_then(|p| std::fs::metadata(p).ok()).map(|m| m.len()),
+                    vhdx_path: vhdx.map(|p| p.to_string_lossy().to_string()),
+                    name,
+                    state,
+                    version,
+                    is_default,
+                }
+            })
+            .collect())
+    }
+
+    async fn find(name: &str) -> Result<WslDistro, String> {
+        list_distros()
+            .await?
+            .into_iter()
+            .find(|d| d.name == name)
+            .ok_or_else(|| format!("WSL distro not found: {}", name))
+    }
+
+    pub async fn usage(name: &str) -> Result<WslUsage, String> {
+        let distro = find(name).await?;
+        // -x stays on the distro's own filesystem (skips /mnt/c and friends)
+        let output = Command::new("wsl.exe")
+            .args(["-d", name, "-u", "root", "--", "du", "-x", "-k", "-d", "1", "/"])
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| e.to_string())?;
+        // du exits non-zero on unreadable entries but still prints sizes
+        let (used_bytes, folders) = parse_du(&String::from_utf8_lossy(&output.stdout), "/");
+        if used_bytes == 0 {
+            return Err(format!("Could not measure {}: {}", name, String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(WslUsage {
+            distro: distro.name,
+            used_bytes,
+            vhdx_size: distro.vhdx_size,
+            folders,
+        })
+    }
+
+    pub async fn compact(name: &str) -> Result<CompactResult, String> {
+        let distro = find(name).await?;
+        let vhdx = distro
+            .vhdx_path
+            .ok_or_else(|| format!("{} has no virtual disk to compact (WSL 1 distro?)", name))?;
+        let size_before = distro.vhdx_size.unwrap_or(0);
+
+        // The virtual disk is locked while the WSL VM runs
+        wsl(&["--shutdown"]).await?;
+
+        let script = std::env::temp_dir().join(format!("helium-compact-{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &script,
+            format!(
+                "select vdisk file=\"{}\"\r\nattach vdisk readonly\r\ncompact vdisk\r\ndetach vdisk\r\n",
+                vhdx
+            ),
+        )
+        .map_err(|e| e.to_string())?;
+        let output = Command::new("diskpart").arg("/s").arg(&script).output().await;
+        let _ = std::fs::remove_file(&script);
+        let output = output.map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(format!(
+                "Compaction failed (diskpart needs administrator rights): {}",
+                String::from_utf8_lossy(&output.stdout).trim()
+            ));
+        }
+
+        let size_after = std::fs::metadata(&vhdx).map(|m| m.len()).unwrap_or(size_before);
+        info!("Compacted {}: {} -> {} bytes", name, size_before, size_after);
+        Ok(CompactResult {
+            distro: distro.name,
+            size_before,
+            size_after,
+            reclaimed: size_before.saturating_sub(size_after),
+        })
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::*;
+
+    const UNSUPPORTED: &str = "WSL is only available on Windows";
+
+    pub async fn list_distros() -> Result<Vec<WslDistro>, String> {
+        Ok(Vec::new())
+    }
+
+    pub async fn usage(_name: &str) -> Result<WslUsage, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub async fn compact(_name: &str) -> Result<CompactResult, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+}
+
+pub use platform::{compact, list_distros, usage};
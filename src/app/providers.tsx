@@ -2,6 +2,7 @@
 
 import * as React from 'react';
 import { FluentProvider, webDarkTheme } from '@fluentui/react-components';
+import { invoke } from '@tauri-apps/api/core';
 
 // Custom theme or override can be done here. 
 // For now, standard webDarkTheme is professional enough.
@@ -11,6 +12,10 @@ export function Providers({ children }: { children: React.ReactNode }) {
 
     React.useEffect(() => {
         setMounted(true);
+        // Backend-generated text (category names, errors) follows the UI language
+        invoke('set_locale', { locale: navigator.language }).catch(() => {
+            // No catalog for this language; the backend stays in English
+        });
     }, []);
 
     // Fluent UI uses JS to style, so SSR/Hydration can be tricky without SSR provider.
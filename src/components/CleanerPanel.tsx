@@ -20,6 +20,7 @@ import {
     AccordionHeader,
     AccordionPanel,
     Badge,
+    SpinButton,
 } from '@fluentui/react-components';
 import {
     DeleteRegular,
@@ -28,9 +29,10 @@ import {
     StethoscopeRegular,
     InfoRegular,
     FolderOpenRegular,
+    ClockRegular,
 } from '@fluentui/react-icons';
 import { invoke } from '@tauri-apps/api/core';
-import { JunkCategory, JunkItem } from '../types/cleaner';
+import { CleanResult, InUseItem, JunkAgeSettings, JunkCategory, JunkItem } from '../types/cleaner';
 
 const useStyles = makeStyles({
     container: {
@@ -81,21 +83,36 @@ const useStyles = makeStyles({
     },
 });
 
-export const CleanerPanel = () => {
+interface CleanerPanelProps {
+    /** Category to open, e.g. when the assistant points at it */
+    focusCategory?: string;
+}
+
+export const CleanerPanel = ({ focusCategory }: CleanerPanelProps) => {
     const styles = useStyles();
     const [categories, setCategories] = useState<JunkCategory[]>([]);
+    const [openItems, setOpenItems] = useState<string[]>([]);
     const [loading, setLoading] = useState(false);
     const [scanning, setScanning] = useState(false); // Visual state for scanning
     const [selectedItems, setSelectedItems] = useState<Set<string>>(new Set());
     const [cleanDialogOpen, setCleanDialogOpen] = useState(false);
     const [errorDialogOpen, setErrorDialogOpen] = useState(false);
     const [cleaningErrors, setCleaningErrors] = useState<string[]>([]);
+    const [skippedInUse, setSkippedInUse] = useState<InUseItem[]>([]);
+    const [ageDialogOpen, setAgeDialogOpen] = useState(false);
+    const [ageSettings, setAgeSettings] = useState<JunkAgeSettings | null>(null);
 
     // Initial scan
     useEffect(() => {
         handleScan();
     }, []);
 
+    useEffect(() => {
+        if (focusCategory) {
+            setOpenItems(open => open.includes(focusCategory) ? open : [...open, focusCategory]);
+        }
+    }, [focusCategory]);
+
     const handleScan = async () => {
         setLoading(true);
         setScanning(true);
@@ -128,13 +145,15 @@ export const CleanerPanel = () => {
         }
     };
 
-    const handleClean = async () => {
+    const handleClean = async (paths: string[] = Array.from(selectedItems)) => {
         setCleanDialogOpen(false);
+        setSkippedInUse([]);
         setLoading(true);
         setCleaningErrors([]);
 
         try {
-            await invoke('clean_junk', { paths: Array.from(selectedItems) });
+            const result = await invoke<CleanResult>('clean_junk', { paths });
+            setSkippedInUse(result.skippedInUse);
         } catch (e) {
             // Parse the error string into an array of individual errors
             const errorMessage = String(e);
@@ -148,6 +167,32 @@ export const CleanerPanel = () => {
         }
     };
 
+    const openAgeSettings = async () => {
+        try {
+            setAgeSettings(await invoke<JunkAgeSettings>('get_junk_age_settings'));
+            setAgeDialogOpen(true);
+        } catch (e) {
+            console.error('Failed to load junk age settings:', e);
+        }
+    };
+
+    const saveAgeSettings = async () => {
+        if (!ageSettings) return;
+        setAgeDialogOpen(false);
+        try {
+            await invoke('set_junk_age_settings', { settings: ageSettings });
+            await handleScan();
+        } catch (e) {
+            console.error('Failed to save junk age settings:', e);
+        }
+    };
+
+    // Built-in categories found by the last scan, plus those with a minimum age
+    const ageCategories = Array.from(new Set([
+        ...Object.keys(ageSettings?.minAgeHours ?? {}),
+        ...categories.filter(c => !c.provider && c.id !== 'unplayed_games').map(c => c.id),
+    ])).sort();
+
     const formatSize = (bytes: number): string => {
         if (bytes === 0) return '0 B';
         const k = 1024;
@@ -188,9 +233,14 @@ export const CleanerPanel = () => {
                     <Subtitle2 style={{ fontSize: '20px' }}>System Cleaner</Subtitle2>
                     <Text block style={{ color: '#aaa' }}>Remove temporary files and free up space.</Text>
                 </div>
-                <Button appearance="subtle" icon={<ArrowClockwiseRegular />} onClick={handleScan} disabled={loading}>
-                    Rescan
-                </Button>
+                <span style={{ display: 'flex', gap: '4px' }}>
+                    <Button appearance="subtle" icon={<ClockRegular />} onClick={openAgeSettings} disabled={loading}>
+                        Minimum Ages
+                    </Button>
+                    <Button appearance="subtle" icon={<ArrowClockwiseRegular />} onClick={handleScan} disabled={loading}>
+                        Rescan
+                    </Button>
+                </span>
             </div>
 
             {/* Summary / Hero Section */}
@@ -233,7 +283,12 @@ export const CleanerPanel = () => {
             {/* Details List */}
             {categories.length > 0 && !scanning && (
                 <div className={styles.listContainer}>
-                    <Accordion multiple collapsible>
+                    <Accordion
+                        multiple
+                        collapsible
+                        openItems={openItems}
+                        onToggle={(e, data) => setOpenItems(data.openItems as string[])}
+                    >
                         {categories.map((cat) => {
                             // Check status for category checkbox
                             const catItems = cat.items.map(i => i.path);
@@ -251,7 +306,10 @@ export const CleanerPanel = () => {
                                             />
                                             <div style={{ display: 'flex', flexDirection: 'column' }}>
                                                 <Text weight="semibold">{cat.name}</Text>
-                                                <Text size={200} style={{ color: '#aaa' }}>{cat.description} • {formatSize(cat.total_size)}</Text>
+                                                <Text size={200} style={{ color: '#aaa' }}>
+                                                    {cat.description} • {formatSize(cat.total_size)}
+                                                    {cat.too_recent > 0 && ` • ${cat.too_recent} recently changed items kept`}
+                                                </Text>
                                             </div>
                                             <Badge appearance="filled" color="brand" style={{ marginLeft: 'auto' }}>
                                                 {cat.items.length}
@@ -319,7 +377,7 @@ export const CleanerPanel = () => {
                             <Button
                                 appearance="primary"
                                 style={{ backgroundColor: '#d13438', color: 'white' }}
-                                onClick={handleClean}
+                                onClick={() => handleClean()}
                             >
                                 Delete
                             </Button>
@@ -328,6 +386,82 @@ export const CleanerPanel = () => {
                 </DialogSurface>
             </Dialog>
 
+            {/* Minimum Age Dialog */}
+            <Dialog open={ageDialogOpen} onOpenChange={(event, data) => setAgeDialogOpen(data.open)}>
+                <DialogSurface>
+                    <DialogBody>
+                        <DialogTitle>Minimum Age of Junk</DialogTitle>
+                        <DialogContent>
+                            <Text block style={{ marginBottom: '12px' }}>
+                                Items that changed more recently than this are left out, as running apps may still use them. 0 lists everything.
+                            </Text>
+                            <div style={{ display: 'grid', gridTemplateColumns: '1fr 120px auto', gap: '8px', alignItems: 'center' }}>
+                                {ageCategories.map(id => (
+                                    <React.Fragment key={id}>
+                                        <Text>{categories.find(c => c.id === id)?.name ?? id}</Text>
+                                        <SpinButton
+                                            min={0}
+                                            value={ageSettings?.minAgeHours[id] ?? 0}
+                                            onChange={(_, data) => {
+                                                const hours = data.value ?? parseInt(data.displayValue ?? '', 10);
+                                                if (!ageSettings || hours == null || Number.isNaN(hours)) return;
+                                                setAgeSettings({ minAgeHours: { ...ageSettings.minAgeHours, [id]: Math.max(0, hours) } });
+                                            }}
+                                        />
+                                        <Text size={200}>hours</Text>
+                                    </React.Fragment>
+                                ))}
+                            </div>
+                        </DialogContent>
+                        <DialogActions>
+                            <Button appearance="secondary" onClick={() => setAgeDialogOpen(false)}>Cancel</Button>
+                            <Button appearance="primary" onClick={saveAgeSettings}>Save and Rescan</Button>
+                        </DialogActions>
+                    </DialogBody>
+                </DialogSurface>
+            </Dialog>
+
+            {/* In-Use Dialog */}
+            <Dialog open={skippedInUse.length > 0} onOpenChange={(event, data) => !data.open && setSkippedInUse([])}>
+                <DialogSurface>
+                    <DialogBody>
+                        <DialogTitle>Some Items Are in Use</DialogTitle>
+                        <DialogContent>
+                            <Text block style={{ marginBottom: '12px' }}>
+                                These items were skipped because a running program has them open. Close it and try again:
+                            </Text>
+                            <div style={{
+                                maxHeight: '300px',
+                                overflowY: 'auto',
+                                backgroundColor: '#1a1a1a',
+                                padding: '12px',
+                                borderRadius: '4px',
+                                border: '1px solid #333',
+                            }}>
+                                {skippedInUse.map(item => (
+                                    <div key={item.path} style={{ marginBottom: '8px' }}>
+                                        <Text block size={200} style={{ fontFamily: 'monospace' }}>
+                                            {item.path}
+                                        </Text>
+                                        <Text block size={200} style={{ color: '#aaa' }}>
+                                            {item.reason}
+                                        </Text>
+                                    </div>
+                                ))}
+                            </div>
+                        </DialogContent>
+                        <DialogActions>
+                            <Button appearance="secondary" onClick={() => setSkippedInUse([])}>
+                                Close
+                            </Button>
+                            <Button appearance="primary" onClick={() => handleClean(skippedInUse.map(item => item.path))}>
+                                Retry
+                            </Button>
+                        </DialogActions>
+                    </DialogBody>
+                </DialogSurface>
+            </Dialog>
+
             {/* Error Dialog */}
             <Dialog open={errorDialogOpen} onOpenChange={(event, data) => setErrorDialogOpen(data.open)}>
                 <DialogSurface>
@@ -4,8 +4,10 @@ import * as React from 'react';
 import {
     PieChart, Pie, Cell, Tooltip as RechartsTooltip, Legend, ResponsiveContainer
 } from 'recharts';
-import { makeStyles, shorthands, Text, tokens } from '@fluentui/react-components';
+import { makeStyles, shorthands, Spinner, Tab, TabList, Text, tokens } from '@fluentui/react-components';
+import { invoke } from '@tauri-apps/api/core';
 import { FileNode } from '@/types';
+import { TypeBreakdown } from '@/types/typeBreakdown';
 
 const useStyles = makeStyles({
     container: {
@@ -31,18 +33,43 @@ const useStyles = makeStyles({
 
 interface DiskUsageChartProps {
     items: FileNode[];
+    /** Folder the items are in; its type breakdown is shown on request */
+    path?: string;
 }
 
+type ChartMode = 'items' | 'types';
+
 const COLORS = [
     '#0088FE', '#00C49F', '#FFBB28', '#FF8042',
     '#8884d8', '#82ca9d', '#a4de6c', '#d0ed57',
     '#ffc658', '#8dd1e1'
 ];
 
-export const DiskUsageChart: React.FC<DiskUsageChartProps> = ({ items }) => {
+export const DiskUsageChart: React.FC<DiskUsageChartProps> = ({ items, path }) => {
     const styles = useStyles();
+    const [mode, setMode] = React.useState<ChartMode>('items');
+    const [breakdown, setBreakdown] = React.useState<TypeBreakdown | null>(null);
+    const [breakdownError, setBreakdownError] = React.useState<string | null>(null);
+
+    React.useEffect(() => {
+        setBreakdown(null);
+        setBreakdownError(null);
+        if (mode !== 'types' || !path) return;
+        let current = true;
+        invoke<TypeBreakdown>('get_type_breakdown', { path })
+            .then(result => { if (current) setBreakdown(result); })
+            .catch(err => { if (current) setBreakdownError(String(err)); });
+        return () => { current = false; };
+    }, [mode, path]);
 
     const data = React.useMemo(() => {
+        if (mode === 'types') {
+            return (breakdown?.categories ?? []).map(group => ({
+                name: group.name,
+                value: group.size,
+                formattedSize: formatSize(group.size),
+            }));
+        }
         if (!items || items.length === 0) return [];
 
         // Sort by size desc
@@ -69,11 +96,28 @@ export const DiskUsageChart: React.FC<DiskUsageChartProps> = ({ items }) => {
         }
 
         return chartData;
-    }, [items]);
+    }, [items, mode, breakdown]);
+
+    const modeTabs = path && (
+        <TabList size="small" selectedValue={mode} onTabSelect={(_, d) => setMode(d.value as ChartMode)}>
+            <Tab value="items">By item</Tab>
+            <Tab value="types">By type</Tab>
+        </TabList>
+    );
+
+    if (mode === 'types' && !breakdown) {
+        return (
+            <div className={styles.container}>
+                {modeTabs}
+                {breakdownError ? <Text>{breakdownError}</Text> : <Spinner size="small" label="Sorting files by type..." />}
+            </div>
+        );
+    }
 
     if (data.length === 0) {
         return (
             <div className={styles.container}>
+                {modeTabs}
                 <Text>No data to visualize</Text>
             </div>
         );
@@ -82,6 +126,7 @@ export const DiskUsageChart: React.FC<DiskUsageChartProps> = ({ items }) => {
     return (
         <div className={styles.container}>
             <Text className={styles.title} size={400}>Disk Usage Distribution</Text>
+            {modeTabs}
             <div className={styles.chartContainer}>
                 <ResponsiveContainer width="100%" height="100%">
                     <PieChart>
@@ -55,8 +55,11 @@ import { BreadcrumbPath } from './BreadcrumbPath';
 import { CleanerPanel } from './CleanerPanel';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
-import { FileNode } from '@/types';
+import { FileNode, ScanStarted } from '@/types';
 import { FileMetadata } from '@/types/ai-types';
+import { BackupCheck } from '@/types/backups';
+import { UIAction } from '@/types/uiActions';
+import { DISK_IMAGE_EXTENSIONS, ImageContents } from '@/types/diskImages';
 
 const useStyles = makeStyles({
     container: {
@@ -100,7 +103,15 @@ interface ExplorerState {
     error: string | null;
 }
 
+interface ScanNodeEvent {
+    session_id: string;
+    /** Root of the scan the folder is directly below */
+    parent: string;
+    node: FileNode;
+}
+
 interface ScanProgressPayload {
+    session_id: string;
     path: string;
     count: number;
     size: number;
@@ -295,6 +306,9 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
     const [selectedItems, setSelectedItems] = React.useState<Set<SelectionItemId>>(new Set());
     const [showChart, setShowChart] = React.useState(false);
     const [viewMode, setViewMode] = React.useState<'explorer' | 'cleaner'>('explorer');
+    const [cleanerFocus, setCleanerFocus] = React.useState<string | undefined>(undefined);
+    // Items the assistant asked to highlight once their folder has loaded
+    const pendingHighlightRef = useRef<{ folder: string; paths: string[] } | null>(null);
 
     // Scan Progress State
     const [scanProgress, setScanProgress] = useState<ScanProgressPayload | null>(null);
@@ -303,6 +317,7 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
     const lastProgressRef = useRef<{ count: number, time: number } | null>(null);
     const currentScanPathRef = useRef<string | null>(null); // Track which path is being scanned
     const scanCompletedRef = useRef<boolean>(false); // Flag to prevent race condition
+    const scanSessionRef = useRef<string | null>(null); // Session of this window's scan, for cancel_scan
 
     // Context synchronization
     React.useEffect(() => {
@@ -331,6 +346,10 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
     const [deleteDialogOpen, setDeleteDialogOpen] = React.useState(false);
     const [propertiesDialogOpen, setPropertiesDialogOpen] = React.useState(false);
     const [dialogItem, setDialogItem] = React.useState<FileNode | null>(null);
+    const [backupWarning, setBackupWarning] = React.useState<string | null>(null);
+    const [imageDialogOpen, setImageDialogOpen] = React.useState(false);
+    const [imageContents, setImageContents] = React.useState<ImageContents | null>(null);
+    const [imageError, setImageError] = React.useState<string | null>(null);
 
     // Compute the actually selected item object (only one supported for now)
     const selectedItem = React.useMemo(() => {
@@ -375,6 +394,12 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
             renderHeaderCell: () => 'Size',
             renderCell: (item) => formatSize(item.size),
         }),
+        createTableColumn({
+            columnId: 'allocated',
+            compare: (a, b) => (a.allocated_size ?? a.size) - (b.allocated_size ?? b.size),
+            renderHeaderCell: () => 'On Disk',
+            renderCell: (item) => item.allocated_size !== undefined ? formatSize(item.allocated_size) : '-',
+        }),
         createTableColumn({
             columnId: 'count',
             compare: (a, b) => a.file_count - b.file_count,
@@ -393,6 +418,7 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
         // Set the current scan path and reset completion flag
         currentScanPathRef.current = path;
         scanCompletedRef.current = false;
+        scanSessionRef.current = null;
 
         setState(prev => ({ ...prev, loading: true, error: null }));
         setIsScanning(true);
@@ -446,7 +472,10 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
 
             // Then update the UI with the new data
             setState(prev => ({ ...prev, loading: false, data, path }));
-            setSelectedItems(new Set()); // Clear selection on navigate
+            // Clear selection on navigate, unless the assistant highlights items here
+            const highlight = pendingHighlightRef.current;
+            pendingHighlightRef.current = null;
+            setSelectedItems(new Set(highlight?.folder === path ? highlight.paths : []));
         } catch (e: unknown) {
             // Mark as completed even on error
             scanCompletedRef.current = true;
@@ -472,6 +501,13 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
         }));
         fetchData(initialPath);
 
+        // Remember this window's scan session so cancelling stops only it
+        const unlistenStarted = listen<ScanStarted>('scan-started', (event) => {
+            if (!scanCompletedRef.current && currentScanPathRef.current === event.payload.path) {
+                scanSessionRef.current = event.payload.sessionId;
+            }
+        });
+
         const unlistenPromise = listen<ScanProgressPayload>('scan-progress', (event) => {
             // CRITICAL: Ignore progress events after scan is completed or for different paths
             if (scanCompletedRef.current) {
@@ -482,6 +518,11 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
                 return;
             }
 
+            // Another scan of the same path, e.g. from another window
+            if (scanSessionRef.current && scanSessionRef.current !== event.payload.session_id) {
+                return;
+            }
+
             const now = Date.now();
             const currentCount = event.payload.count;
 
@@ -504,7 +545,38 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
             setIsScanning(true);
         });
 
+        // Fill in the tree while a long scan runs; the final result replaces it
+        const unlistenNodes = listen<ScanNodeEvent>('scan-node', (event) => {
+            const { session_id, parent, node } = event.payload;
+            if (scanCompletedRef.current || currentScanPathRef.current !== parent) {
+                return;
+            }
+            if (scanSessionRef.current && scanSessionRef.current !== session_id) {
+                return;
+            }
+            setState(prev => {
+                const partial = prev.path === parent && prev.data?.path === parent ? prev.data.children ?? [] : [];
+                const children = [...partial.filter(child => child.path !== node.path), node]
+                    .sort((a, b) => b.size - a.size);
+                return {
+                    ...prev,
+                    path: parent,
+                    data: {
+                        name: parent,
+                        path: parent,
+                        size: children.reduce((total, child) => total + child.size, 0),
+                        is_dir: true,
+                        children,
+                        last_modified: 0,
+                        file_count: children.reduce((total, child) => total + child.file_count, 0),
+                    },
+                };
+            });
+        });
+
         return () => {
+            unlistenNodes.then(unlisten => unlisten());
+            unlistenStarted.then(unlisten => unlisten());
             unlistenPromise.then(unlisten => unlisten());
         };
     }, []);
@@ -524,6 +596,38 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
         fetchData(newPath);
     };
 
+    // Actions the assistant takes in the UI (ui_action tool)
+    const handleUIAction = (action: UIAction) => {
+        switch (action.type) {
+            case 'navigate_to':
+                setViewMode('explorer');
+                handleNavigate(action.path);
+                break;
+            case 'highlight_items':
+                setViewMode('explorer');
+                if (action.folder === state.path) {
+                    setSelectedItems(new Set(action.paths));
+                } else {
+                    pendingHighlightRef.current = { folder: action.folder, paths: action.paths };
+                    handleNavigate(action.folder);
+                }
+                break;
+            case 'open_clean_preview':
+                setCleanerFocus(action.category);
+                setViewMode('cleaner');
+                break;
+        }
+    };
+    const uiActionRef = useRef(handleUIAction);
+    uiActionRef.current = handleUIAction;
+
+    React.useEffect(() => {
+        const unlistenPromise = listen<UIAction>('ui-action', (event) => uiActionRef.current(event.payload));
+        return () => {
+            unlistenPromise.then(unlisten => unlisten());
+        };
+    }, []);
+
     const handleBack = () => {
         if (state.historyIndex > 0) {
             const newIndex = state.historyIndex - 1;
@@ -562,7 +666,12 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
 
     const handleDeleteClick = (item: FileNode) => {
         setDialogItem(item);
+        setBackupWarning(null);
         setDeleteDialogOpen(true);
+        // Warn when the originals have no recent backup
+        invoke<BackupCheck>('check_backups', { paths: [item.path] })
+            .then(check => setBackupWarning(check.warning ?? null))
+            .catch(e => console.error(`Backup check failed: ${e}`));
     };
 
     const confirmDelete = async () => {
@@ -604,12 +713,27 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
         setPropertiesDialogOpen(true);
     };
 
+    const isDiskImage = (item: FileNode | null) =>
+        !!item && !item.is_dir && DISK_IMAGE_EXTENSIONS.includes(item.name.split('.').pop()?.toLowerCase() ?? '');
+
+    // Show what a disk image holds before the user decides to delete it
+    const handleInspectImage = (item: FileNode) => {
+        setDialogItem(item);
+        setImageContents(null);
+        setImageError(null);
+        setImageDialogOpen(true);
+        invoke<ImageContents>('inspect_disk_image', { path: item.path })
+            .then(setImageContents)
+            .catch(e => setImageError(String(e)));
+    };
+
     const handleCancelScan = async () => {
         // Mark as completed to stop accepting progress events
         scanCompletedRef.current = true;
         currentScanPathRef.current = null;
 
-        await invoke('cancel_scan');
+        await invoke('cancel_scan', { sessionId: scanSessionRef.current ?? undefined });
+        scanSessionRef.current = null;
         setIsScanning(false);
         setScanProgress(null);
         setScanSpeed(0);
@@ -695,7 +819,7 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
             <div style={{ display: 'flex', flexGrow: 1, overflow: 'hidden', gap: '10px' }}>
                 {viewMode === 'cleaner' ? (
                     <div style={{ flexGrow: 1, height: '100%', overflow: 'hidden' }}>
-                        <CleanerPanel />
+                        <CleanerPanel focusCategory={cleanerFocus} />
                     </div>
                 ) : (
                     <>
@@ -774,6 +898,11 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
                                         <MenuItem icon={<InfoRegular />} onClick={() => contextMenuItem && handlePropertiesClick(contextMenuItem)} disabled={!contextMenuItem}>
                                             Properties
                                         </MenuItem>
+                                        {isDiskImage(contextMenuItem) && (
+                                            <MenuItem icon={<HardDriveRegular />} onClick={() => contextMenuItem && handleInspectImage(contextMenuItem)}>
+                                                Look Inside
+                                            </MenuItem>
+                                        )}
                                         <MenuItem icon={<DeleteRegular />} onClick={() => contextMenuItem && handleDeleteClick(contextMenuItem)}>
                                             Delete
                                         </MenuItem>
@@ -803,6 +932,13 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
                                                         <Text weight="medium">Size:</Text>
                                                         <Text>{formatSize(dialogItem.size)} ({dialogItem.size.toLocaleString()} bytes)</Text>
 
+                                                        {dialogItem.allocated_size !== undefined && (
+                                                            <>
+                                                                <Text weight="medium">On disk:</Text>
+                                                                <Text>{formatSize(dialogItem.allocated_size)} ({dialogItem.allocated_size.toLocaleString()} bytes)</Text>
+                                                            </>
+                                                        )}
+
                                                         <Text weight="medium">Modified:</Text>
                                                         <Text>{new Date(dialogItem.last_modified * 1000).toLocaleString()}</Text>
 
@@ -812,6 +948,24 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
                                                                 <Text>{dialogItem.file_count.toLocaleString()} Files</Text>
                                                             </>
                                                         )}
+                                                        {dialogItem.volume && (
+                                                            <>
+                                                                <Text weight="medium">Available:</Text>
+                                                                <Text>{formatSize(dialogItem.volume.available)} of {formatSize(dialogItem.volume.total)}</Text>
+                                                                {dialogItem.volume.purgeable > 0 && (
+                                                                    <>
+                                                                        <Text weight="medium">Purgeable:</Text>
+                                                                        <Text>{formatSize(dialogItem.volume.purgeable)}</Text>
+                                                                    </>
+                                                                )}
+                                                                {dialogItem.volume.container?.volumes.map(volume => (
+                                                                    <React.Fragment key={volume.device}>
+                                                                        <Text weight="medium">{volume.roles.join(', ') || 'Volume'}:</Text>
+                                                                        <Text>{volume.name} ({formatSize(volume.used)})</Text>
+                                                                    </React.Fragment>
+                                                                ))}
+                                                            </>
+                                                        )}
                                                     </div>
                                                 </div>
                                             )}
@@ -823,6 +977,41 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
                                 </DialogSurface>
                             </Dialog>
 
+                            {/* Disk Image Contents Dialog */}
+                            <Dialog open={imageDialogOpen} onOpenChange={(event, data) => setImageDialogOpen(data.open)}>
+                                <DialogSurface>
+                                    <DialogBody>
+                                        <DialogTitle>Inside {dialogItem?.name}</DialogTitle>
+                                        <DialogContent>
+                                            {imageError && <Text style={{ color: 'var(--colorPaletteRedForeground1)' }}>{imageError}</Text>}
+                                            {!imageError && !imageContents && <Spinner label="Attaching the image read-only..." />}
+                                            {imageContents && (
+                                                <div style={{ display: 'flex', flexDirection: 'column', gap: '10px' }}>
+                                                    <Text>
+                                                        {imageContents.fileCount.toLocaleString()} files in {imageContents.folderCount.toLocaleString()} folders,{' '}
+                                                        {formatSize(imageContents.contentSize)} of content in a {formatSize(imageContents.imageSize)} image
+                                                    </Text>
+                                                    <Caption1>Largest entries</Caption1>
+                                                    <div style={{ display: 'grid', gridTemplateColumns: '1fr auto', gap: '4px 12px', maxHeight: '300px', overflowY: 'auto' }}>
+                                                        {imageContents.topLevel.slice(0, 50).map(entry => (
+                                                            <React.Fragment key={entry.path}>
+                                                                <Text style={{ wordBreak: 'break-all' }}>
+                                                                    {entry.isDir ? <FolderRegular /> : <DocumentRegular />} {entry.path}
+                                                                </Text>
+                                                                <Text>{formatSize(entry.size)}</Text>
+                                                            </React.Fragment>
+                                                        ))}
+                                                    </div>
+                                                </div>
+                                            )}
+                                        </DialogContent>
+                                        <DialogActions>
+                                            <Button appearance="primary" onClick={() => setImageDialogOpen(false)}>Close</Button>
+                                        </DialogActions>
+                                    </DialogBody>
+                                </DialogSurface>
+                            </Dialog>
+
                             {/* Delete Confirmation Dialog */}
                             <Dialog open={deleteDialogOpen} onOpenChange={(event, data) => setDeleteDialogOpen(data.open)}>
                                 <DialogSurface>
@@ -837,6 +1026,11 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
                                                     Warning: This is a folder. All contents will be deleted.
                                                 </Text>
                                             )}
+                                            {backupWarning && (
+                                                <Text block style={{ marginTop: '10px', color: 'var(--colorPaletteMarigoldForeground1)' }}>
+                                                    {backupWarning}
+                                                </Text>
+                                            )}
                                         </DialogContent>
                                         <DialogActions>
                                             <Button appearance="secondary" onClick={() => setDeleteDialogOpen(false)}>Cancel</Button>
@@ -850,7 +1044,7 @@ export const FileExplorer = ({ onToggleAI, isAIPanelOpen, onContextChange }: Fil
                         {/* Chart Panel */}
                         {showChart && items.length > 0 && (
                             <div style={{ width: '40%', minWidth: '300px', display: 'flex', flexDirection: 'column' }}>
-                                <DiskUsageChart items={items} />
+                                <DiskUsageChart items={items} path={state.path || undefined} />
                             </div>
                         )}
                     </>
@@ -4,7 +4,9 @@
  * Wrapper around AI inference that handles tool calling loop.
  */
 
-import { InferenceRequest, InferenceResponse, ChatMessage, MessageRole, ToolExecutionData } from '@/types/ai-types';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { AgentRun, InferenceRequest, InferenceResponse, ChatMessage, MCPNotification, MessageRole, PendingToolCall, ToolExecutionData } from '@/types/ai-types';
 import { runInference } from './ai-service';
 import { mcpService } from './mcp-service';
 import { detectToolCall, extractToolCalls, formatToolResult, removeToolCallTags } from './tool-calling';
@@ -25,6 +27,61 @@ export interface InferenceWithToolsOptions {
     onProgress?: (progress: any) => void;
 }
 
+/**
+ * Checkpoint the loop so a run cut off by a restart can be resumed or
+ * abandoned. Only runs of saved conversations are checkpointed; failures
+ * never stop the run.
+ */
+async function checkpoint(
+    runId: string,
+    request: InferenceRequest,
+    step: number,
+    pendingToolCall?: PendingToolCall
+): Promise<void> {
+    if (!request.conversationId) return;
+    const run: AgentRun = {
+        id: runId,
+        conversationId: request.conversationId,
+        step,
+        messages: request.messages,
+        pendingToolCall,
+        updatedAt: Date.now(),
+        interrupted: false,
+    };
+    try {
+        await invoke('checkpoint_agent_run', { run });
+    } catch (error) {
+        console.warn('[InferenceWithTools] Checkpoint failed:', error);
+    }
+}
+
+/**
+ * System message telling the model what changed while it was working, from
+ * the MCP notifications received since its last inference. Repeats of the
+ * same notification are reported once.
+ */
+function notificationMessage(notifications: MCPNotification[]): ChatMessage {
+    const lines = new Set<string>();
+    for (const n of notifications) {
+        if (n.method === 'notifications/tools/list_changed') {
+            let detail = '';
+            if (n.params?.auditMode === true) detail = ' (audit mode is on: tools that modify files will be refused)';
+            if (n.params?.auditMode === false) detail = ' (audit mode is off)';
+            lines.add(`- ${n.server}: the available tools changed${detail}.`);
+        } else if (n.method === 'notifications/resources/updated' && typeof n.params?.uri === 'string') {
+            lines.add(`- ${n.server}: ${n.params.uri} changed; earlier results about it may be out of date.`);
+        } else {
+            lines.add(`- ${n.server}: ${n.method}${n.params ? ` ${JSON.stringify(n.params)}` : ''}`);
+        }
+    }
+    return {
+        id: `mcp-notifications-${Date.now()}`,
+        role: MessageRole.System,
+        content: `The environment changed while you were working:\n${[...lines].join('\n')}\nTake this into account before your next step.`,
+        timestamp: Date.now(),
+    };
+}
+
 /**
  * Run inference with automatic tool calling support
  *
@@ -44,179 +101,209 @@ export async function runInferenceWithTools(
     let iterations = 0;
     let finalResponse: InferenceResponse | null = null;
     const allToolExecutions: ToolExecutionData[] = []; // Track all tool executions
+    const runId = crypto.randomUUID();
+
+    // MCP notifications received during the run reach the model before its
+    // next inference
+    let notifications: MCPNotification[] = [];
+    const unlistenNotifications = await listen<MCPNotification>('mcp-notification', (event) => {
+        notifications.push(event.payload);
+    }).catch(() => undefined);
+
+    try {
+        while (iterations < MAX_TOOL_ITERATIONS) {
+            iterations++;
+            if (notifications.length > 0) {
+                currentRequest = {
+                    ...currentRequest,
+                    messages: [...currentRequest.messages, notificationMessage(notifications)],
+                };
+                notifications = [];
+            }
+            await checkpoint(runId, currentRequest, iterations);
+
+            console.log(`[InferenceWithTools] Iteration ${iterations}/${MAX_TOOL_ITERATIONS}`);
+
+            // Run inference
+            const response = await runInference(currentRequest, onChunk, onProgress);
+
+            console.log(`[InferenceWithTools] Response content preview:`, response.message.content.substring(0, 200));
+
+            // Check for native tool calls first (OpenAI format in response.message.toolCalls)
+            let toolCalls: any[] = [];
+
+            if (response.message.toolCalls && response.message.toolCalls.length > 0) {
+                // Native function calling - convert OpenAI format to our internal format
+                console.log(`[InferenceWithTools] Found ${response.message.toolCalls.length} native tool calls`);
+                toolCalls = response.message.toolCalls.map((tc: any) => ({
+                    id: tc.id,
+                    name: tc.function.name,
+                    arguments: JSON.parse(tc.function.arguments), // OpenAI returns arguments as JSON string
+                }));
+            } else {
+                // Fallback to prompt-based tool calling (XML/JSON in content)
+                const hasToolCalls = detectToolCall(response.message.content);
+                console.log(`[InferenceWithTools] Has prompt-based tool calls:`, hasToolCalls);
+
+                if (!hasToolCalls) {
+                    // No tool calls - we're done
+                    console.log(`[InferenceWithTools] No tool calls detected, finishing`);
+                    finalResponse = response;
+                    break;
+                }
 
-    while (iterations < MAX_TOOL_ITERATIONS) {
-        iterations++;
-
-        console.log(`[InferenceWithTools] Iteration ${iterations}/${MAX_TOOL_ITERATIONS}`);
-
-        // Run inference
-        const response = await runInference(currentRequest, onChunk, onProgress);
-
-        console.log(`[InferenceWithTools] Response content preview:`, response.message.content.substring(0, 200));
-
-        // Check for native tool calls first (OpenAI format in response.message.toolCalls)
-        let toolCalls: any[] = [];
+                // Extract tool calls from content
+                toolCalls = extractToolCalls(response.message.content);
+            }
 
-        if (response.message.toolCalls && response.message.toolCalls.length > 0) {
-            // Native function calling - convert OpenAI format to our internal format
-            console.log(`[InferenceWithTools] Found ${response.message.toolCalls.length} native tool calls`);
-            toolCalls = response.message.toolCalls.map((tc: any) => ({
-                id: tc.id,
-                name: tc.function.name,
-                arguments: JSON.parse(tc.function.arguments), // OpenAI returns arguments as JSON string
-            }));
-        } else {
-            // Fallback to prompt-based tool calling (XML/JSON in content)
-            const hasToolCalls = detectToolCall(response.message.content);
-            console.log(`[InferenceWithTools] Has prompt-based tool calls:`, hasToolCalls);
+            console.log(`[InferenceWithTools] Extracted ${toolCalls.length} tool calls`);
 
-            if (!hasToolCalls) {
-                // No tool calls - we're done
-                console.log(`[InferenceWithTools] No tool calls detected, finishing`);
+            if (toolCalls.length === 0) {
+                // False positive - no valid tool calls found
+                console.log(`[InferenceWithTools] Tool call tags found but extraction failed`);
                 finalResponse = response;
                 break;
             }
 
-            // Extract tool calls from content
-            toolCalls = extractToolCalls(response.message.content);
-        }
+            console.log(`[InferenceWithTools] 🔧 Found ${toolCalls.length} tool call(s):`, toolCalls);
 
-        console.log(`[InferenceWithTools] Extracted ${toolCalls.length} tool calls`);
+            // Execute each tool call
+            const toolResults: ChatMessage[] = [];
 
-        if (toolCalls.length === 0) {
-            // False positive - no valid tool calls found
-            console.log(`[InferenceWithTools] Tool call tags found but extraction failed`);
-            finalResponse = response;
-            break;
-        }
-
-        console.log(`[InferenceWithTools] 🔧 Found ${toolCalls.length} tool call(s):`, toolCalls);
-
-        // Execute each tool call
-        const toolResults: ChatMessage[] = [];
-
-        for (const toolCall of toolCalls) {
-            try {
-                const startTime = Date.now();
+            for (const toolCall of toolCalls) {
+                try {
+                    const startTime = Date.now();
 
-                console.log(`[InferenceWithTools] 🔧 Executing tool: ${toolCall.name}`);
-                console.log(`[InferenceWithTools]    Arguments:`, JSON.stringify(toolCall.arguments, null, 2));
+                    console.log(`[InferenceWithTools] 🔧 Executing tool: ${toolCall.name}`);
+                    console.log(`[InferenceWithTools]    Arguments:`, JSON.stringify(toolCall.arguments, null, 2));
 
-                // Create tool execution data (executing status)
-                const toolExecution: ToolExecutionData = {
-                    toolName: toolCall.name,
-                    arguments: toolCall.arguments,
-                    status: 'executing',
-                };
-
-                // Notify about tool execution
-                if (onToolExecution) {
-                    onToolExecution({
+                    // Create tool execution data (executing status)
+                    const toolExecution: ToolExecutionData = {
                         toolName: toolCall.name,
                         arguments: toolCall.arguments,
-                    });
-                }
-
-                // Execute the tool
-                const result = await mcpService.executeTool(toolCall);
-                const executionTimeMs = Date.now() - startTime;
-
-                console.log(`[InferenceWithTools] ✅ Tool ${toolCall.name} executed in ${executionTimeMs}ms`);
-                console.log(`[InferenceWithTools]    Result length: ${result.content.length} characters`);
-                console.log(`[InferenceWithTools]    Result preview: ${result.content.substring(0, 200)}${result.content.length > 200 ? '...' : ''}`);
-
-                // Log full result for debugging (useful when inspecting tool responses)
-                if (result.content.length < 1000) {
-                    console.log(`[InferenceWithTools]    Full result:`, result.content);
-                } else {
-                    console.log(`[InferenceWithTools]    Full result (first 1000 chars):`, result.content.substring(0, 1000) + '...');
-                }
-
-                // Update tool execution data (success status)
-                toolExecution.status = result.isError ? 'error' : 'success';
-                toolExecution.result = result.content;
-                toolExecution.executionTimeMs = executionTimeMs;
-                if (result.isError) {
-                    toolExecution.error = result.content;
-                }
-
-                // Add to all executions
-                allToolExecutions.push(toolExecution);
-
-                // Notify about result
-                if (onToolExecution) {
-                    onToolExecution({
-                        toolName: toolCall.name,
+                        status: 'executing',
+                    };
+
+                    // Notify about tool execution
+                    if (onToolExecution) {
+                        onToolExecution({
+                            toolName: toolCall.name,
+                            arguments: toolCall.arguments,
+                        });
+                    }
+
+                    await checkpoint(runId, currentRequest, iterations, {
+                        id: toolCall.id,
+                        name: toolCall.name,
                         arguments: toolCall.arguments,
-                        result: result.content,
-                        error: result.isError ? result.content : undefined,
-                        executionTimeMs,
+                        startedAt: startTime,
                     });
-                }
 
-                // Create tool result message
-                const toolResultMessage: ChatMessage = {
-                    id: `tool-result-${Date.now()}-${toolCall.id}`,
-                    role: MessageRole.User, // Tool results come back as user messages
-                    content: formatToolResult(toolCall.name, result.content, result.isError),
-                    timestamp: Date.now(),
-                };
-
-                toolResults.push(toolResultMessage);
-            } catch (error) {
-                console.error(`[InferenceWithTools] Tool execution error:`, error);
-
-                // Create tool execution data (error status)
-                const toolExecution: ToolExecutionData = {
-                    toolName: toolCall.name,
-                    arguments: toolCall.arguments,
-                    status: 'error',
-                    error: error instanceof Error ? error.message : String(error),
-                };
-                allToolExecutions.push(toolExecution);
-
-                const errorMessage: ChatMessage = {
-                    id: `tool-error-${Date.now()}-${toolCall.id}`,
-                    role: MessageRole.User,
-                    content: formatToolResult(
-                        toolCall.name,
-                        `Error: ${error instanceof Error ? error.message : String(error)}`,
-                        true
-                    ),
-                    timestamp: Date.now(),
-                };
-
-                toolResults.push(errorMessage);
-
-                if (onToolExecution) {
-                    onToolExecution({
+                    // Execute the tool
+                    const result = await mcpService.executeTool(toolCall);
+                    const executionTimeMs = Date.now() - startTime;
+
+                    console.log(`[InferenceWithTools] ✅ Tool ${toolCall.name} executed in ${executionTimeMs}ms`);
+                    console.log(`[InferenceWithTools]    Result length: ${result.content.length} characters`);
+                    console.log(`[InferenceWithTools]    Result preview: ${result.content.substring(0, 200)}${result.content.length > 200 ? '...' : ''}`);
+
+                    // Log full result for debugging (useful when inspecting tool responses)
+                    if (result.content.length < 1000) {
+                        console.log(`[InferenceWithTools]    Full result:`, result.content);
+                    } else {
+                        console.log(`[InferenceWithTools]    Full result (first 1000 chars):`, result.content.substring(0, 1000) + '...');
+                    }
+
+                    // Update tool execution data (success status)
+                    toolExecution.status = result.isError ? 'error' : 'success';
+                    toolExecution.result = result.content;
+                    toolExecution.executionTimeMs = executionTimeMs;
+                    if (result.isError) {
+                        toolExecution.error = result.content;
+                    }
+
+                    // Add to all executions
+                    allToolExecutions.push(toolExecution);
+
+                    // Notify about result
+                    if (onToolExecution) {
+                        onToolExecution({
+                            toolName: toolCall.name,
+                            arguments: toolCall.arguments,
+                            result: result.content,
+                            error: result.isError ? result.content : undefined,
+                            executionTimeMs,
+                        });
+                    }
+
+                    // Create tool result message
+                    const toolResultMessage: ChatMessage = {
+                        id: `tool-result-${Date.now()}-${toolCall.id}`,
+                        role: MessageRole.User, // Tool results come back as user messages
+                        content: formatToolResult(toolCall.name, result.content, result.isError),
+                        timestamp: Date.now(),
+                    };
+
+                    toolResults.push(toolResultMessage);
+                } catch (error) {
+                    console.error(`[InferenceWithTools] Tool execution error:`, error);
+
+                    // Create tool execution data (error status)
+                    const toolExecution: ToolExecutionData = {
                         toolName: toolCall.name,
                         arguments: toolCall.arguments,
+                        status: 'error',
                         error: error instanceof Error ? error.message : String(error),
-                    });
+                    };
+                    allToolExecutions.push(toolExecution);
+
+                    const errorMessage: ChatMessage = {
+                        id: `tool-error-${Date.now()}-${toolCall.id}`,
+                        role: MessageRole.User,
+                        content: formatToolResult(
+                            toolCall.name,
+                            `Error: ${error instanceof Error ? error.message : String(error)}`,
+                            true
+                        ),
+                        timestamp: Date.now(),
+                    };
+
+                    toolResults.push(errorMessage);
+
+                    if (onToolExecution) {
+                        onToolExecution({
+                            toolName: toolCall.name,
+                            arguments: toolCall.arguments,
+                            error: error instanceof Error ? error.message : String(error),
+                        });
+                    }
                 }
             }
-        }
 
-        // Add assistant's tool call message to history
-        const assistantMessage: ChatMessage = {
-            ...response.message,
-            // Remove tool call tags from display content
-            content: removeToolCallTags(response.message.content) || '(Using tools...)',
-        };
-
-        // Prepare next iteration with updated conversation history
-        currentRequest = {
-            ...currentRequest,
-            messages: [
-                ...currentRequest.messages,
-                assistantMessage,
-                ...toolResults,
-            ],
-        };
-
-        // Continue the loop to get LLM's next response
+            // Add assistant's tool call message to history
+            const assistantMessage: ChatMessage = {
+                ...response.message,
+                // Remove tool call tags from display content
+                content: removeToolCallTags(response.message.content) || '(Using tools...)',
+            };
+
+            // Prepare next iteration with updated conversation history
+            currentRequest = {
+                ...currentRequest,
+                messages: [
+                    ...currentRequest.messages,
+                    assistantMessage,
+                    ...toolResults,
+                ],
+            };
+
+            // Continue the loop to get LLM's next response
+        }
+    } finally {
+        unlistenNotifications?.();
+        if (currentRequest.conversationId) {
+            await invoke('finish_agent_run', { id: runId }).catch(() => undefined);
+        }
     }
 
     if (!finalResponse) {
@@ -230,3 +317,17 @@ export async function runInferenceWithTools(
 
     return finalResponse;
 }
+
+/**
+ * Continue an interrupted agent run (see list_interrupted_agent_runs) from
+ * its last checkpoint. `request` supplies the model and mode; its messages
+ * are replaced by the run's.
+ */
+export async function resumeAgentRun(
+    runId: string,
+    request: InferenceRequest,
+    options: InferenceWithToolsOptions = {}
+): Promise<InferenceResponse> {
+    const messages = await invoke<ChatMessage[]>('resume_agent_run', { id: runId });
+    return runInferenceWithTools({ ...request, messages }, options);
+}
@@ -19,6 +19,22 @@ export interface InitializeMCPResponse {
 export interface ExecuteToolRequest {
     tool_name: string;
     arguments: Record<string, unknown>;
+    /** Set once the user approved an action the guardrails escalated */
+    approved?: boolean;
+}
+
+export interface GuardrailPolicy {
+    maxFilesPerAction: number;
+    approvalSizeThreshold: number;
+    approveAllDeletes: boolean;
+    protectedPaths: string[];
+}
+
+export interface GuardrailDecision {
+    verdict: 'allow' | 'require_approval' | 'block';
+    reasons: string[];
+    fileCount: number;
+    totalBytes: number;
 }
 
 export interface ExecuteToolResponse {
@@ -32,6 +48,7 @@ export interface ExecuteToolResponse {
     is_error: boolean;
     execution_time_ms?: number;
     error?: string;
+    guardrail?: GuardrailDecision;
 }
 
 /**
@@ -53,6 +70,8 @@ class MCPService {
                 allowedDirectories: config.allowedDirectories,
                 confirmDestructive: config.confirmDestructive,
                 maxFileSize: config.maxFileSize,
+                resultCache: config.resultCache,
+                resourceLimits: config.resourceLimits,
             });
 
             if (response.success) {
@@ -0,0 +1,25 @@
+export type AgeGranularity = 'year' | 'quarter';
+
+export interface AgeBucket {
+    /** "2021" or "2021-Q3" */
+    period: string;
+    size: number;
+    files: number;
+}
+
+export interface DirectoryAge {
+    path: string;
+    size: number;
+    /** Oldest period first */
+    buckets: AgeBucket[];
+    /** Size-weighted mean last-modified time (Unix seconds), for shading */
+    meanModified: number;
+}
+
+export interface AgeHeatmap {
+    granularity: AgeGranularity;
+    /** The requested folder, including its own files */
+    root: DirectoryAge;
+    /** Immediate subfolders, largest first */
+    children: DirectoryAge[];
+}
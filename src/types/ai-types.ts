@@ -123,6 +123,30 @@ export interface OpenAIToolCallFunction {
     arguments: string; // JSON string of arguments
 }
 
+/**
+ * Partial tool call streamed as `ai-tool-call-delta` while the model generates it
+ */
+export interface ToolCallDelta {
+    /** Position of the call among this response's tool calls */
+    index: number;
+    id?: string;
+    name?: string;
+    /** Newly received fragment of the JSON arguments */
+    argumentsDelta: string;
+    /** Arguments received so far (possibly incomplete JSON) */
+    arguments: string;
+}
+
+/**
+ * File or folder attached to a conversation (attach_context); its listing or
+ * content is sent with every request of the conversation
+ */
+export interface ContextAttachment {
+    path: string;
+    isDir: boolean;
+    addedAt: number;
+}
+
 /**
  * Chat message
  */
@@ -135,7 +159,7 @@ export interface ChatMessage {
     content: string;
     /** Timestamp */
     timestamp: number;
-    /** Associated file/folder paths (for context) */
+    /** File/folder paths whose listings or contents are sent with this request */
     contextPaths?: string[];
     /** Whether this message is currently streaming */
     isStreaming?: boolean;
@@ -145,6 +169,49 @@ export interface ChatMessage {
     toolExecutions?: ToolExecutionData[];
     /** Tool calls in OpenAI format (for native function calling) */
     toolCalls?: OpenAIToolCall[];
+    /** Generation stopped (cancelled or app quit) before completing */
+    interrupted?: boolean;
+}
+
+/**
+ * Tool call in flight when an agent run was checkpointed
+ */
+export interface PendingToolCall {
+    id: string;
+    name: string;
+    arguments: Record<string, unknown>;
+    startedAt: number;
+}
+
+/**
+ * Checkpoint of the tool-calling loop (checkpoint_agent_run), used to
+ * recover runs cut off by an app restart
+ */
+export interface AgentRun {
+    id: string;
+    conversationId: string;
+    /** Loop iteration, starting at 1 */
+    step: number;
+    /** Messages sent with the next inference */
+    messages: ChatMessage[];
+    pendingToolCall?: PendingToolCall;
+    updatedAt: number;
+    /** Left over from an earlier app session: resume or abandon it */
+    interrupted: boolean;
+}
+
+/**
+ * Notification an MCP server sent on its own (the `mcp-notification` event),
+ * injected into a running agent loop
+ */
+export interface MCPNotification {
+    /** JSON-RPC method, e.g. "notifications/tools/list_changed" */
+    method: string;
+    params?: Record<string, unknown>;
+    /** Server that sent it */
+    server: string;
+    /** Unix timestamp in milliseconds */
+    receivedAt: number;
 }
 
 /**
@@ -229,6 +296,10 @@ export interface InferenceRequest {
     mode: AIMode;
     /** Optional tools for native function calling (OpenAI format) */
     tools?: Tool[];
+    /** Prompt preset applied by the backend */
+    presetId?: string;
+    /** Saved conversation whose pinned settings override the defaults */
+    conversationId?: string;
 }
 
 /**
@@ -281,6 +352,75 @@ export interface ProviderStatus {
     error?: string;
 }
 
+/**
+ * A GPU found by get_accelerators
+ */
+export interface GpuInfo {
+    name: string;
+    vendor: 'nvidia' | 'amd' | 'intel' | 'apple' | 'other';
+    /** Bytes */
+    vramTotal?: number;
+    vramUsed?: number;
+    /** Current load in percent */
+    utilization?: number;
+    /** Shares system memory (Apple Silicon, most integrated GPUs) */
+    unifiedMemory: boolean;
+}
+
+/**
+ * Compute backend availability
+ */
+export interface ComputeBackend {
+    name: 'cuda' | 'metal' | 'directml';
+    /** This build can run models on it */
+    supported: boolean;
+    /** The machine has the hardware and drivers for it */
+    present: boolean;
+}
+
+/**
+ * Result of get_accelerators
+ */
+export interface AcceleratorReport {
+    gpus: GpuInfo[];
+    backends: ComputeBackend[];
+    /** Device embedded models run on */
+    inferenceDevice: 'cuda' | 'metal' | 'cpu';
+    /** Embedded models larger than this do not fit the inference device */
+    modelBudgetBytes: number;
+    /** Why the budget is what it is, for display */
+    budgetReason: string;
+}
+
+/**
+ * An embedded model checked against this machine, from get_recommended_models
+ */
+export interface ModelRecommendation {
+    modelId: string;
+    /** Approximate size of the weights in bytes */
+    sizeBytes: number;
+    /** Memory the model needs once loaded */
+    requiredBytes: number;
+    /** Fits the inference device's memory budget */
+    usable: boolean;
+    recommended: boolean;
+    /** Why the model is recommended, usable or not */
+    reason: string;
+}
+
+/**
+ * Result of get_recommended_models
+ */
+export interface RecommendedModels {
+    /** Best embedded model for this machine; absent when none fits */
+    defaultModelId?: string;
+    models: ModelRecommendation[];
+    inferenceDevice: 'cuda' | 'metal' | 'cpu';
+    budgetBytes: number;
+    /** Summary of the decision, for display */
+    rationale: string;
+}
+
 /**
  * AI settings/preferences
  */
@@ -381,6 +521,33 @@ export interface MCPServerConfig {
     confirmDestructive: boolean;
     /** Maximum file size for read operations (in bytes) */
     maxFileSize?: number;
+    /** Opt-in caching of read-only tool results */
+    resultCache?: ToolCacheSettings;
+    /** Concurrency and read-rate limits of the filesystem tools */
+    resourceLimits?: MCPResourceLimits;
+}
+
+/**
+ * Result cache of read-only MCP tools (get/set_tool_cache_settings).
+ * Entries also expire when the queried path's modification time changes.
+ */
+export interface ToolCacheSettings {
+    enabled: boolean;
+    /** Seconds a result stays valid */
+    ttlSecs: number;
+}
+
+/**
+ * Limits that keep agent tool calls from starving the scanner
+ * (get/set_mcp_resource_limits)
+ */
+export interface MCPResourceLimits {
+    /** Recursive walks (sizes, trees, searches, analyses) running at once */
+    maxConcurrentWalks: number;
+    /** Files read or edited at once */
+    maxOpenFiles: number;
+    /** Global cap on bytes read per second; absent for no cap */
+    maxReadBytesPerSec?: number;
 }
 
 /**
@@ -394,6 +561,12 @@ export enum AIErrorType {
     NetworkError = 'network_error',
     InvalidConfiguration = 'invalid_configuration',
     ContextTooLarge = 'context_too_large',
+    BudgetExceeded = 'budget_exceeded',
+    DnsFailure = 'dns_failure',
+    Timeout = 'timeout',
+    RateLimited = 'rate_limited',
+    ServerError = 'server_error',
+    AuthenticationFailed = 'authentication_failed',
 }
 
 /**
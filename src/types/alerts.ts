@@ -0,0 +1,36 @@
+export interface AlertSettings {
+    enabled: boolean;
+    /** Alert when free space drops below this percentage of the drive */
+    thresholdPercent: number;
+    /** Mount points to watch; empty watches every drive */
+    trackedDrives: string[];
+    intervalMinutes: number;
+}
+
+/** How much a folder grew between two scans */
+export interface FolderGrowth {
+    path: string;
+    previousSize: number;
+    currentSize: number;
+    growth: number;
+    /** Timestamp of the scan compared against */
+    since: number;
+}
+
+/** Payload of the drive-space-alert event */
+export interface DriveAlert {
+    mountPoint: string;
+    name: string;
+    totalBytes: number;
+    availableBytes: number;
+    freePercent: number;
+    growth?: FolderGrowth;
+    message: string;
+}
+
+export interface ScanRecord {
+    path: string;
+    timestamp: number;
+    size: number;
+    children: Record<string, number>;
+}
@@ -0,0 +1,9 @@
+/** What import_config changed */
+export interface ImportSummary {
+    profiles: number;
+    promptPresets: number;
+    /** Settings groups that were applied */
+    settings: string[];
+    /** Parts that could not be applied, with the reason */
+    skipped: string[];
+}
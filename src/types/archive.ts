@@ -0,0 +1,57 @@
+/** Where archived files are copied to */
+export type ArchiveTarget =
+    /** A local folder, usually on another drive */
+    | { kind: 'folder'; path: string }
+    /** A folder on a host from the remote hosts list */
+    | { kind: 'sftp'; hostId: string; path: string }
+    /** An rclone remote and path, e.g. "nas:archive" */
+    | { kind: 'rclone'; remote: string };
+
+export interface ArchiveDestination {
+    /** Empty when saving a new destination */
+    id: string;
+    name: string;
+    target: ArchiveTarget;
+}
+
+/** A file moved to a destination, with a stub left at its original path */
+export interface ArchivedFile {
+    id: string;
+    originalPath: string;
+    stubPath: string;
+    destinationName: string;
+    target: ArchiveTarget;
+    /** Path of the copy at the destination */
+    location: string;
+    size: number;
+    sha256: string;
+    /** Unix timestamp in seconds */
+    archivedAt: number;
+}
+
+/** Payload of get_archive and the archive-changed event */
+export interface ArchiveSummary {
+    destinations: ArchiveDestination[];
+    entries: ArchivedFile[];
+    totalSize: number;
+}
+
+export interface ArchiveFailure {
+    path: string;
+    error: string;
+}
+
+/** Result of archive_files */
+export interface ArchiveResult {
+    archived: ArchivedFile[];
+    failed: ArchiveFailure[];
+}
+
+/** Result of restore_archived */
+export interface ArchiveRestoreResult {
+    restored: string[];
+    failed: ArchiveFailure[];
+}
+
+/** Event emitted with an ArchiveSummary whenever files are archived or restored */
+export const ARCHIVE_EVENT = 'archive-changed';
@@ -0,0 +1,58 @@
+/** English text of the error for modifications refused in audit mode (the backend localizes it) */
+export const AUDIT_MODE_ERROR = 'Audit mode is on: Helium is read-only';
+
+/** Payload of get_audit_mode, set_audit_mode and the audit-mode-changed event */
+export interface AuditSettings {
+    enabled: boolean;
+}
+
+/** An evidence record of the audit log, from get_audit_log */
+export interface AuditLogEntry {
+    /** Unix timestamp in milliseconds */
+    timestamp: number;
+    /** What the record is evidence of, e.g. "shred" */
+    kind: string;
+    record: unknown;
+    previousHash: string;
+    /** SHA-256 over the previous hash and this entry's content */
+    hash: string;
+}
+
+/** Result of verify_audit_log */
+export interface AuditLogCheck {
+    entries: number;
+    intact: boolean;
+    /** Index of the first entry that doesn't chain */
+    brokenAt?: number;
+}
+
+export type PassPattern = 'random' | 'zeros';
+
+/** Evidence for one shredded file */
+export interface ShredRecord {
+    path: string;
+    size: number;
+    passes: PassPattern[];
+    /** Unix timestamps in milliseconds */
+    startedAt: number;
+    finishedAt: number;
+    /** The last pass was read back and matched what was written */
+    verified: boolean;
+    /** SHA-256 of the content after the last pass */
+    finalContentHash?: string;
+    removed: boolean;
+    /** Storage where in-place overwrites may leave old copies behind */
+    caveat?: string;
+    error?: string;
+}
+
+/** Result of shred_items; the kind "shred" record of the audit log */
+export interface ShredReport {
+    records: ShredRecord[];
+    files: number;
+    verifiedFiles: number;
+    bytes: number;
+    cancelled: boolean;
+    /** Hash of the audit log entry holding this report */
+    auditHash?: string;
+}
@@ -0,0 +1,22 @@
+export type BackupKind = 'time-machine' | 'file-history' | 'borg' | 'restic' | 'rsnapshot';
+
+/** A backup found by detect_backups */
+export interface BackupTarget {
+    path: string;
+    kind: BackupKind;
+    /** Unix timestamp in seconds of the newest backup */
+    lastUpdated?: number;
+    ageDays?: number;
+    /** Older than 30 days, or of unknown age */
+    stale: boolean;
+}
+
+/** Result of check_backups, asked before a deletion */
+export interface BackupCheck {
+    targets: BackupTarget[];
+    /** Most recently updated target */
+    newest?: BackupTarget;
+    /** No backup at all, or even the newest one is stale */
+    stale: boolean;
+    warning?: string;
+}
@@ -0,0 +1,49 @@
+export interface BasketItem {
+    path: string;
+    /** Size when staged */
+    size: number;
+    isDir: boolean;
+    /** Where it was staged from: "treemap", "junk", "duplicates", "ai", ... */
+    source: string;
+    reason?: string;
+    addedAt: number;
+    modified: number;
+}
+
+/**
+ * missing: gone, skipped on commit; nested: inside a staged folder;
+ * changed: modified since staged (blocks commit); protected: root or home (blocks commit)
+ */
+export type ConflictKind = 'missing' | 'nested' | 'changed' | 'protected';
+
+export interface BasketConflict {
+    path: string;
+    kind: ConflictKind;
+    /** The staged folder containing a nested item */
+    otherPath?: string;
+}
+
+export interface BasketSummary {
+    /** Pass to commit_basket; changes on every edit */
+    revision: number;
+    items: BasketItem[];
+    /** Bytes a commit would free, counting nested items once */
+    totalSize: number;
+    conflicts: BasketConflict[];
+}
+
+export interface BasketFailure {
+    path: string;
+    error: string;
+}
+
+export interface BasketCommitResult {
+    deleted: string[];
+    /** Moved into quarantine instead of deleted */
+    quarantined: string[];
+    failed: BasketFailure[];
+    freed: number;
+}
+
+/** Event emitted with a BasketSummary whenever the basket changes */
+export const BASKET_EVENT = 'basket-changed';
@@ -0,0 +1,39 @@
+export type StorageKind =
+    | 'indexed-db'
+    | 'service-worker'
+    | 'local-storage'
+    | 'file-system'
+    | 'site-data'
+    | 'extension-data'
+    | 'extension-code'
+    | 'other';
+
+export interface StorageEntry {
+    kind: StorageKind;
+    path: string;
+    size: number;
+    /** Can be deleted on its own (with the browser closed); it is recreated empty */
+    clearable: boolean;
+}
+
+/** A site or extension and what it stores */
+export interface StorageOwner {
+    /** Origin of a site, or an extension's name */
+    name: string;
+    extensionId?: string;
+    size: number;
+    entries: StorageEntry[];
+}
+
+export interface BrowserProfile {
+    browser: string;
+    profile: string;
+    path: string;
+    /** Nothing can be cleared while the browser runs */
+    running: boolean;
+    size: number;
+    sites: StorageOwner[];
+    extensions: StorageOwner[];
+    /** Storage shared by sites or not attributable to one */
+    other: StorageEntry[];
+}
@@ -12,4 +12,39 @@ export interface JunkCategory {
     items: JunkItem[];
     total_size: number;
     icon: string;
+    /** Cleaning it can be preceded by a recovery record (clean_junk `safeguard`) */
+    risky: boolean;
+    /** Plugin server that contributed the category; absent for built-in ones */
+    provider?: string;
+    /** Items left out for having changed more recently than the category's minimum age */
+    too_recent: number;
+}
+
+export interface JunkAgeSettings {
+    /** Minimum age in hours by category ID; categories not listed have none */
+    minAgeHours: Record<string, number>;
+}
+
+/** Recorded by clean_junk when `safeguard` is set */
+export interface PreCleanRecord {
+    /** A System Restore point was created (Windows) */
+    restorePoint: boolean;
+    restorePointError?: string;
+    /** JSON manifest of the deleted files with sizes and SHA-256 hashes */
+    manifestPath: string;
+    files: number;
+}
+
+/** An item clean_junk left alone because a running program had it open */
+export interface InUseItem {
+    path: string;
+    /** Programs holding it, as "name (pid)"; empty where only the lock is known (Windows) */
+    processes: string[];
+    reason: string;
+}
+
+export interface CleanResult {
+    record?: PreCleanRecord;
+    /** Cleaning these again after closing the programs can succeed */
+    skippedInUse: InUseItem[];
 }
@@ -0,0 +1,23 @@
+export type ContentKind = 'text' | 'image' | 'video' | 'audio' | 'document' | 'archive' | 'executable' | 'binary';
+
+/** What a file actually is, from its first bytes or its extension */
+export interface ContentType {
+    mime: string;
+    kind: ContentKind;
+    /** Usual extension of the detected format */
+    extension?: string;
+    detectedBy: 'magic' | 'text' | 'extension';
+}
+
+/** Result of preview_file */
+export interface FilePreview {
+    path: string;
+    size: number;
+    contentType: ContentType;
+    /** Start of a text file */
+    text?: string;
+    /** The text stops before the end of the file */
+    truncated: boolean;
+    /** Data URL of an image small enough to inline */
+    imageData?: string;
+}
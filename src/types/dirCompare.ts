@@ -0,0 +1,37 @@
+/** What compare_directories checks besides presence */
+export type CompareMode = 'name' | 'size' | 'hash';
+
+export interface ComparedEntry {
+    /** Relative to the compared directories, with `/` separators */
+    path: string;
+    isDir: boolean;
+    /** Total size of a folder's files */
+    size: number;
+}
+
+export interface EntryDifference {
+    path: string;
+    /** A file on one side and a folder on the other is a "type" difference */
+    reason: 'size' | 'content' | 'type';
+    sizeA: number;
+    sizeB: number;
+}
+
+/** Result of compare_directories */
+export interface DirectoryComparison {
+    a: string;
+    b: string;
+    mode: CompareMode;
+    maxDepth?: number;
+    onlyInA: ComparedEntry[];
+    onlyInB: ComparedEntry[];
+    different: EntryDifference[];
+    /** Files present and equal on both sides */
+    identicalFiles: number;
+    onlyInABytes: number;
+    onlyInBBytes: number;
+    /** Some list was cut at its limit; the byte totals still cover everything */
+    truncated: boolean;
+    /** B holds everything A has, unchanged */
+    bContainsA: boolean;
+}
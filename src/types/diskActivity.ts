@@ -0,0 +1,13 @@
+export interface ProcessDiskActivity {
+    pid: number;
+    name: string;
+    exe?: string;
+    /** Bytes written during the sampling window */
+    writtenBytes: number;
+    /** Bytes read during the sampling window */
+    readBytes: number;
+    /** Bytes per second over the window */
+    writeRate: number;
+    /** Bytes written since the process started */
+    totalWrittenBytes: number;
+}
@@ -0,0 +1,27 @@
+/** Extensions inspect_disk_image accepts */
+export const DISK_IMAGE_EXTENSIONS = ['dmg', 'sparseimage', 'iso', 'img', 'wim', 'esd', 'vhd', 'vhdx'];
+
+export interface ImageEntry {
+    /** Path inside the image, separated by `/` */
+    path: string;
+    size: number;
+    isDir: boolean;
+}
+
+/** Returned by inspect_disk_image */
+export interface ImageContents {
+    path: string;
+    /** Lowercase extension, e.g. "iso" */
+    format: string;
+    imageSize: number;
+    /** mounted: attached read-only and walked; listed: listed by 7-Zip */
+    method: 'mounted' | 'listed';
+    fileCount: number;
+    folderCount: number;
+    /** Bytes of the files inside, before any compression of the image */
+    contentSize: number;
+    /** Top-level entries, folders with the size of everything below them */
+    topLevel: ImageEntry[];
+    /** Largest files, biggest first */
+    largestFiles: ImageEntry[];
+}
@@ -0,0 +1,16 @@
+export type CheckStatus = 'ok' | 'warning' | 'error';
+
+export interface EnvironmentCheck {
+    /** "node", "npx", "huggingface", "acceleration", "disk" or "scan:<path>" */
+    id: string;
+    status: CheckStatus;
+    summary: string;
+    suggestion?: string;
+}
+
+export interface EnvironmentReport {
+    os: string;
+    arch: string;
+    appVersion: string;
+    checks: EnvironmentCheck[];
+}
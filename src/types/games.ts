@@ -0,0 +1,14 @@
+export type GameStore = 'steam' | 'epic';
+
+export interface Game {
+    store: GameStore;
+    /** Steam app id or Epic app name */
+    id: string;
+    name: string;
+    installDir: string;
+    size: number;
+    /** Unix timestamp in seconds; not recorded by Epic */
+    lastPlayed?: number;
+    /** Launcher file describing the install */
+    manifest: string;
+}
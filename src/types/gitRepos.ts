@@ -0,0 +1,27 @@
+export interface RepoEntry {
+    path: string;
+    size: number;
+}
+
+export interface GitRepo {
+    path: string;
+    name: string;
+    /** 0 for worktrees and submodules, whose objects live elsewhere */
+    gitDirSize: number;
+    /** Everything outside .git, including untracked and ignored files */
+    workingTreeSize: number;
+    untrackedSize: number;
+    ignoredSize: number;
+    /** Largest first */
+    largestUntracked: RepoEntry[];
+    /** Largest first; typically build outputs and dependency folders */
+    largestIgnored: RepoEntry[];
+    /** .git is large and outweighs the tracked files; `git gc` may help */
+    gcSuggested: boolean;
+}
+
+export interface GcResult {
+    path: string;
+    sizeBefore: number;
+    sizeAfter: number;
+}
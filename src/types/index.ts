@@ -6,4 +6,71 @@ export interface FileNode {
     children?: FileNode[];
     last_modified: number;
     file_count: number;
+    /** Space details of a drive, as listed by get_drives */
+    volume?: VolumeSpace;
+    /** Set when the entry is a symbolic link (or a junction on Windows) */
+    link?: LinkInfo;
+    /** Links below a folder that were not measured (skipped, broken or cyclic) */
+    skipped_links?: number;
+    /**
+     * Space taken on disk: less than size for sparse and compressed files,
+     * more for many small ones. Unknown for remote and cloud listings.
+     */
+    allocated_size?: number;
+    /**
+     * A folder scan_dir_shallow listed without measuring: size and file_count
+     * only count what was listed; get_deep_stats returns the full totals
+     */
+    deferred?: boolean;
+}
+
+/** Sent to the window that started a scan; its sessionId cancels only that scan */
+export interface ScanStarted {
+    sessionId: string;
+    path: string;
+}
+
+/** What a scan does with symbolic links */
+export type SymlinkPolicy = 'skip' | 'count-link-size' | 'follow';
+
+/**
+ * counted: by the link's own size; followed: by its target;
+ * cycle, broken: not measured
+ */
+export type LinkHandling = 'counted' | 'followed' | 'cycle' | 'broken';
+
+export interface LinkInfo {
+    /** Where the link points, as stored in it */
+    target: string;
+    handling: LinkHandling;
+}
+
+/** A volume of an APFS container */
+export interface ApfsVolume {
+    name: string;
+    /** "System", "Data", "VM", "Preboot", ...; empty for user volumes */
+    roles: string[];
+    used: number;
+    device: string;
+}
+
+/** APFS container whose free space its volumes share */
+export interface ApfsContainer {
+    /** e.g. "disk3" */
+    reference: string;
+    capacity: number;
+    free: number;
+    volumes: ApfsVolume[];
+}
+
+/** Free space of a drive as the OS file manager reports it */
+export interface VolumeSpace {
+    total: number;
+    /** Free right now */
+    free: number;
+    /** Free plus purgeable: Finder's "available" */
+    available: number;
+    /** Space the OS frees on demand */
+    purgeable: number;
+    container?: ApfsContainer;
 }
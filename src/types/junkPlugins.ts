@@ -0,0 +1,23 @@
+/** Tool a junk plugin server exposes; it returns a JSON list of JunkCandidate */
+export const JUNK_DISCOVERY_TOOL = 'helium_junk_candidates';
+
+/** An external MCP server that contributes junk categories */
+export interface JunkPluginServer {
+    /** Empty when adding a new server */
+    id: string;
+    /** Shown as the provider of its categories */
+    name: string;
+    /** Program that starts the server over stdio */
+    command: string;
+    args: string[];
+    enabled: boolean;
+}
+
+/** One entry of the discovery tool's reply */
+export interface JunkCandidate {
+    path: string;
+    size: number;
+    reason: string;
+    /** Groups candidates; defaults to the server name */
+    category?: string;
+}
@@ -0,0 +1,16 @@
+export type LogLevel = 'error' | 'warn' | 'info' | 'debug' | 'trace';
+
+export interface LogFilter {
+    level?: LogLevel;
+    target?: string;
+    contains?: string;
+    limit?: number;
+}
+
+export interface LogEntry {
+    timestamp: string;
+    level: string;
+    target: string;
+    message: string;
+    fields?: Record<string, unknown>;
+}
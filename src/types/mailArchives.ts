@@ -0,0 +1,15 @@
+/** pst: Outlook personal folders; ost: Outlook offline cache; mbox: one mailbox per file, or an Apple Mail .mbox folder */
+export type ArchiveKind = 'pst' | 'ost' | 'mbox';
+
+export interface MailArchive {
+    path: string;
+    name: string;
+    kind: ArchiveKind;
+    size: number;
+    /** Unix timestamp in seconds */
+    lastModified: number;
+    account?: string;
+    /** A cache no configured account uses; safe to delete */
+    orphaned: boolean;
+    orphanReason?: string;
+}
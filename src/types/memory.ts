@@ -0,0 +1,35 @@
+/** Returned by get_memory_settings; set with set_memory_settings */
+export interface MemorySettings {
+    /** Ceiling on the scan cache's estimated size; least recently used trees are dropped past it */
+    scanCacheMaxBytes: number;
+    /** RAM budget of resident models; absent keeps the default of half the physical memory */
+    modelBudgetBytes?: number;
+}
+
+export interface CacheUsage {
+    entries: number;
+    bytes: number;
+    maxBytes: number;
+}
+
+export interface ModelUsage {
+    loaded: number;
+    bytes: number;
+    budgetBytes: number;
+    /** Parsed tokenizers kept alongside */
+    tokenizers: number;
+}
+
+export interface IndexUsage {
+    directories: number;
+    bytes: number;
+}
+
+/** Returned by get_memory_report; sizes other than processBytes are estimates */
+export interface MemoryReport {
+    /** Resident memory of the whole process */
+    processBytes?: number;
+    scanCache: CacheUsage;
+    models: ModelUsage;
+    sizeIndex: IndexUsage;
+}
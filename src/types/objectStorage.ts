@@ -0,0 +1,30 @@
+import type { FileNode } from './index';
+
+export interface ObjectStorageAccount {
+    /** Empty when adding a new account */
+    id: string;
+    name: string;
+    /** S3-compatible endpoint, e.g. "https://<account>.r2.cloudflarestorage.com"; AWS when unset */
+    endpoint?: string;
+    /** Signing region ("auto" for R2) */
+    region: string;
+    accessKeyId: string;
+    defaultBucket?: string;
+}
+
+export interface StorageClassUsage {
+    storageClass: string;
+    size: number;
+    objects: number;
+}
+
+export interface BucketReport {
+    bucket: string;
+    prefix: string;
+    /** Paths are written s3://<bucket>/<key> */
+    tree: FileNode;
+    totalSize: number;
+    objectCount: number;
+    /** Largest class first */
+    storageClasses: StorageClassUsage[];
+}
@@ -0,0 +1,41 @@
+export type OperationKind =
+    | 'scan'
+    | 'junk-scan'
+    | 'cleanup'
+    | 'deletion'
+    | 'download'
+    | 'inference'
+    | 'summarize'
+    | 'transaction'
+    | 'archive';
+
+export type OperationStatus = 'running' | 'completed' | 'failed' | 'cancelled';
+
+/** Payload of the `operation-updated` event and of `list_operations` */
+export interface OperationInfo {
+    id: string;
+    kind: OperationKind;
+    label: string;
+    status: OperationStatus;
+    /** 0 to 1; absent while the total is unknown */
+    progress?: number;
+    message?: string;
+    cancellable: boolean;
+    /** Unix timestamp in milliseconds */
+    startedAt: number;
+}
+
+/** An operation interrupted by a restart, from `list_resumable_operations` */
+export interface ResumableOperation {
+    id: string;
+    kind: OperationKind;
+    /** What the operation works on: the scanned path */
+    target: string;
+    /** Unix timestamps in milliseconds */
+    startedAt: number;
+    updatedAt: number;
+    /** Folders that won't be walked again */
+    completedFolders: number;
+    completedFiles: number;
+    completedBytes: number;
+}
@@ -0,0 +1,20 @@
+export type AccessState = 'granted' | 'denied' | 'unknown' | 'notApplicable';
+
+export type SettingsPane = 'fullDiskAccess' | 'filesAndFolders';
+
+export interface ProtectedLocation {
+    path: string;
+    accessible: boolean;
+}
+
+export interface PermissionReport {
+    fullDiskAccess: AccessState;
+    locations: ProtectedLocation[];
+}
+
+/** Payload of the `permission-required` event */
+export interface PermissionIssue {
+    path: string;
+    blocked: string;
+    pane: SettingsPane;
+}
@@ -0,0 +1,22 @@
+export interface BurstOptions {
+    /** Largest gap between consecutive captures of one burst, in seconds (default 60) */
+    maxGapSeconds?: number;
+    /** Largest perceptual-hash distance (of 64 bits) between neighbours (default 6) */
+    maxDistance?: number;
+}
+
+export interface BurstPhoto {
+    path: string;
+    size: number;
+    lastModified: number;
+}
+
+export interface PhotoBurst {
+    width: number;
+    height: number;
+    /** Proposed to keep: the largest file of the burst */
+    keep: BurstPhoto;
+    /** Proposed for removal, oldest first */
+    duplicates: BurstPhoto[];
+    reclaimable: number;
+}
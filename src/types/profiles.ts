@@ -0,0 +1,28 @@
+import { ModelProvider } from './ai-types';
+import { SymlinkPolicy } from './index';
+
+export interface DefaultModel {
+    provider: ModelProvider;
+    modelId: string;
+}
+
+export interface Profile {
+    /** Empty when creating a profile */
+    id: string;
+    name: string;
+    scanRoots: string[];
+    mcpAllowedDirectories: string[];
+    /**
+     * .gitignore-style patterns: names ("node_modules"), globs ("*.iso",
+     * "**\/cache/*.bin"), folders only ("build/"), absolute paths
+     * ("/mnt/backup") and negations ("!keep.iso")
+     */
+    exclusions: string[];
+    /** Skip mounted network shares when scanning */
+    skipNetworkMounts?: boolean;
+    /** Don't descend into other mounted drives and shares below a scanned folder */
+    sameDevice?: boolean;
+    /** What scans do with symbolic links; count-link-size when absent */
+    symlinkPolicy?: SymlinkPolicy;
+    defaultModel?: DefaultModel;
+}
@@ -0,0 +1,43 @@
+/** An item kept in quarantine until it is restored or purged */
+export interface QuarantineEntry {
+    id: string;
+    originalPath: string;
+    /** Where the item is kept meanwhile */
+    quarantinePath: string;
+    size: number;
+    isDir: boolean;
+    /** Where it was staged from, as in the basket: "treemap", "ai", ... */
+    source: string;
+    reason?: string;
+    /** Unix timestamps in seconds */
+    quarantinedAt: number;
+    expiresAt: number;
+}
+
+/** Payload of get_quarantine and the quarantine-changed event */
+export interface QuarantineSummary {
+    entries: QuarantineEntry[];
+    totalSize: number;
+    retentionDays: number;
+}
+
+export interface QuarantineFailure {
+    path: string;
+    error: string;
+}
+
+/** Result of restore_quarantined */
+export interface RestoreResult {
+    restored: string[];
+    failed: QuarantineFailure[];
+}
+
+/** Result of purge_quarantined */
+export interface PurgeResult {
+    purged: number;
+    freed: number;
+    failed: QuarantineFailure[];
+}
+
+/** Event emitted with a QuarantineSummary whenever items enter or leave quarantine */
+export const QUARANTINE_EVENT = 'quarantine-changed';
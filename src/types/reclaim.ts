@@ -0,0 +1,25 @@
+/** Free space a volume would end up with after the deletion */
+export interface VolumeForecast {
+    mountPoint: string;
+    availableNow: number;
+    /** After a permanent deletion, or once the trash is emptied */
+    availableAfter: number;
+}
+
+/** Result of forecast_reclaim */
+export interface ReclaimForecast {
+    /** Naive sum of the items' sizes */
+    requestedBytes: number;
+    /** Disk space actually freed by a permanent deletion */
+    reclaimableBytes: number;
+    /** Data kept alive by hard links outside the selection */
+    hardLinkedBytes: number;
+    /** Size of cloud placeholders, which occupy no local space */
+    cloudPlaceholderBytes: number;
+    /** Items inside another selected folder, counted once */
+    overlappingBytes: number;
+    /** Always 0: the trash keeps the data on the same volume until emptied */
+    freedByTrash: number;
+    files: number;
+    volumes: VolumeForecast[];
+}
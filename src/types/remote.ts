@@ -0,0 +1,15 @@
+/** Paths on remote hosts are written ssh://<host id>/<absolute path> */
+export const REMOTE_SCHEME = 'ssh://';
+
+export interface RemoteHost {
+    /** Empty when adding a new host */
+    id: string;
+    name: string;
+    host: string;
+    user?: string;
+    port?: number;
+    /** Private key to use instead of the agent / default keys */
+    identityFile?: string;
+    /** Directory shown as the host's root (defaults to "/") */
+    root?: string;
+}
@@ -0,0 +1,29 @@
+export type StorageKind = 'nvme' | 'sata-ssd' | 'hdd' | 'network' | 'usb' | 'unknown';
+
+/** Order in which directory entries are stat'ed; 'inode' keeps HDDs reading sequentially */
+export type StatOrder = 'listing' | 'inode';
+
+export interface ScanStrategy {
+    /** Walker threads */
+    threads: number;
+    /** Entries handled between progress updates and cancellation checks */
+    batchSize: number;
+    statOrder: StatOrder;
+}
+
+/** Settings override for a mount point; unset fields keep the detected value */
+export interface VolumeOverride {
+    mountPoint: string;
+    kind?: StorageKind;
+    threads?: number;
+    batchSize?: number;
+    statOrder?: StatOrder;
+}
+
+/** Result of get_scan_strategy */
+export interface VolumeStrategy {
+    mountPoint: string;
+    detectedKind: StorageKind;
+    strategy: ScanStrategy;
+    overridden: boolean;
+}
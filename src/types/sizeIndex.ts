@@ -0,0 +1,8 @@
+export interface IndexedRoot {
+    path: string;
+    size: number;
+    fileCount: number;
+    directoryCount: number;
+    /** Unix timestamp of the last full walk (0 while the first one runs) */
+    builtAt: number;
+}
@@ -0,0 +1,23 @@
+export interface SnapshotItem {
+    id: string;
+    name: string;
+    /** Creation time as reported by the OS */
+    created?: string;
+    size?: number;
+}
+
+export type ReclaimAction =
+    | { kind: 'deleteLocalSnapshot'; id: string }
+    | { kind: 'thinLocalSnapshots' }
+    | { kind: 'deleteOldestRestorePoint'; volume: string }
+    | { kind: 'cleanupFileHistory'; olderThanDays: number };
+
+export interface SnapshotCategory {
+    id: 'time-machine' | 'system-restore' | 'file-history';
+    name: string;
+    description: string;
+    /** Space held by the category, when the OS reports it */
+    totalSize?: number;
+    items: SnapshotItem[];
+    actions: ReclaimAction[];
+}
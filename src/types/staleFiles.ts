@@ -0,0 +1,36 @@
+export interface StaleOptions {
+    /** Files unused for at least this many days are stale (default 365) */
+    days?: number;
+    /** Smaller files are left out */
+    minSize?: number;
+    /** Count opening a file as using it, not only changing it (default true) */
+    useAccessTime?: boolean;
+}
+
+export interface StaleFile {
+    path: string;
+    size: number;
+    /** Unix timestamps in seconds */
+    lastModified: number;
+    lastAccessed?: number;
+}
+
+/** Stale files directly in one folder */
+export interface StaleGroup {
+    directory: string;
+    size: number;
+    fileCount: number;
+    /** When any of its stale files was last used (Unix seconds) */
+    lastUsed: number;
+    /** Largest first, top entries only */
+    files: StaleFile[];
+}
+
+export interface StaleReport {
+    path: string;
+    options: Required<StaleOptions>;
+    totalSize: number;
+    fileCount: number;
+    /** Largest first, top entries only */
+    groups: StaleGroup[];
+}
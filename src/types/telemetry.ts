@@ -0,0 +1,21 @@
+export type PerformanceSample =
+    | { kind: 'scan'; fileBucket: number; durationMs: number }
+    | { kind: 'generation'; provider: string; tokensPerSecond: number };
+
+/** A sample with its Unix timestamp truncated to the hour */
+export type TimedSample = PerformanceSample & { hour: number };
+
+export interface TelemetryBatch {
+    installId: string;
+    appVersion: string;
+    os: string;
+    featureCounts: Record<string, number>;
+    samples: TimedSample[];
+}
+
+export interface TelemetryState {
+    enabled: boolean;
+    uploadConfigured: boolean;
+    lastUpload?: number;
+    pending: TelemetryBatch;
+}
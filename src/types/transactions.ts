@@ -0,0 +1,20 @@
+/** One step of a file transaction (run_file_transaction) */
+export type TransactionStep =
+    | { type: 'create_directory'; path: string }
+    /** Missing parent folders of `to` are created */
+    | { type: 'move'; from: string; to: string }
+    | { type: 'rename'; path: string; new_name: string }
+    | { type: 'trash'; path: string };
+
+export interface TransactionResult {
+    id: string;
+    /** False when a step failed or the batch was cancelled; nothing was changed then */
+    committed: boolean;
+    /** Steps applied before committing or rolling back */
+    completed: number;
+    /** Index of the failed step */
+    failedStep?: number;
+    error?: string;
+    /** Items left in a `.helium-txn-*` folder because the trash refused them */
+    trashFailures?: string[];
+}
@@ -0,0 +1,21 @@
+/** A node of a scanned tree, as returned flat by get_treemap */
+export interface TreemapRow {
+    path: string;
+    name: string;
+    /** Absent for the root row */
+    parent?: string;
+    size: number;
+    /** 0 for the root */
+    depth: number;
+    isDir: boolean;
+}
+
+/** Result of get_treemap */
+export interface Treemap {
+    /** The root first; every folder before its children, which follow each other largest first */
+    rows: TreemapRow[];
+    /** Whether the row budget ran out before every node above minSize was listed */
+    truncated: boolean;
+    /** Smallest size listed; smaller nodes are part of their parent's area */
+    minSize: number;
+}
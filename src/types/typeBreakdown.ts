@@ -0,0 +1,24 @@
+export interface SizeGroup {
+    /** Category name or extension ("" for files without one) */
+    name: string;
+    size: number;
+    files: number;
+}
+
+export interface TypeBreakdown {
+    path: string;
+    totalSize: number;
+    fileCount: number;
+    /** video, images, archives, code, documents, ...; largest first */
+    categories: SizeGroup[];
+    /** Largest first, top entries only */
+    extensions: SizeGroup[];
+}
+
+/** A file listed by get_largest_files */
+export interface LargeFile {
+    path: string;
+    size: number;
+    /** Unix timestamp in seconds */
+    lastModified: number;
+}
@@ -0,0 +1,11 @@
+/**
+ * Action the assistant takes in the UI through the ui_action tool, validated
+ * by the backend and emitted as the `ui-action` event
+ */
+export type UIAction =
+    /** Open a folder in the explorer */
+    | { type: 'navigate_to'; path: string }
+    /** Open `folder` and select `paths` in it */
+    | { type: 'highlight_items'; paths: string[]; folder: string }
+    /** Open the cleaner on a junk category */
+    | { type: 'open_clean_preview'; category: string };
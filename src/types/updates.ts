@@ -0,0 +1,13 @@
+export interface UpdateInfo {
+    version: string;
+    currentVersion: string;
+    notes?: string;
+    /** RFC 3339 release date */
+    date?: string;
+}
+
+/** Payload of the `update-download-progress` event */
+export interface UpdateProgress {
+    downloaded: number;
+    total?: number;
+}
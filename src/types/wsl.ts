@@ -0,0 +1,32 @@
+export interface WslDistro {
+    name: string;
+    /** "Running", "Stopped", "Installing", ... */
+    state: string;
+    /** WSL version; only version 2 uses a virtual disk */
+    version: number;
+    isDefault: boolean;
+    vhdxPath?: string;
+    vhdxSize?: number;
+}
+
+export interface WslFolderUsage {
+    path: string;
+    size: number;
+}
+
+export interface WslUsage {
+    distro: string;
+    /** Space used inside the Linux filesystem */
+    usedBytes: number;
+    /** Size of the virtual disk; the difference to usedBytes is reclaimable */
+    vhdxSize?: number;
+    /** Largest first */
+    folders: WslFolderUsage[];
+}
+
+export interface CompactResult {
+    distro: string;
+    sizeBefore: number;
+    sizeAfter: number;
+    reclaimed: number;
+}